@@ -1,22 +1,194 @@
+use crate::audio::tts::{Speaker, TtsConfig};
+use crate::audio::{MusicConfig, MusicPlayer};
 use crate::core::IApp;
+use crate::core::app_loop::{CatchUp, LoopRates};
+use crate::core::display::DisplayMode;
+use crate::core::frame_stats;
 use crate::core::gl_canvas::Canvas;
 use crate::core::gl_renderer::Renderer;
-use crate::core::input::Input;
-use crate::error::Result;
+use crate::core::input::{Event, Input, Key, Keymap, TimedEvent};
+use crate::error::{Result, ResultExt};
+use crate::geo::geocode::Geocoder;
+use crate::gfx::animation::Easing;
+use crate::gfx::sharpen::UnsharpMask;
+use crate::gfx::transition::TransitionKind;
 use crate::gl::opengl::OpenGlFunctions;
-use crate::scene::{layouter::Layouter, manager::SceneManager};
-use std::path::PathBuf;
+use crate::headless::HeadlessConfig;
+use crate::ipc::{Bus, Command, EventSink};
+use crate::net::fetch::Fetcher;
+use crate::net::mdns::{self, Advertiser, ServiceInfo};
+use crate::net::presence::{PresenceConfig, PresenceDetector};
+use crate::net::{events::EventHub, server::HttpServer, settings::SettingsService};
+use crate::scene::{
+    FrameEvent, SceneEvent, SceneKind, SystemEvent, UserEvent, decoder::PhotoDecoder,
+    font::DEFAULT_FONT, layouter::Layouter, manager::SceneManager, overlay::ClockOverlayStyle,
+    photo::Letterbox, registry::SceneRegistry, rotation::Rotation, schedule::Schedule,
+    slideshow::PhotoFilter,
+};
+use crate::util::ambient_light::{AmbientLightConfig, AutoBrightness};
+use crate::util::crash;
+use crate::util::datetime::Time;
+use crate::util::locale::{LocaleId, TimePattern, Units};
+use crate::util::logger::{self, LogConfig};
+use crate::util::tasks::{self, TaskSender};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+// Input that waited longer, e.g. during a stall, would fire all at once
+const STALE_INPUT: Duration = Duration::from_secs(1);
 
 #[derive(Clone, Debug)]
 pub struct AppConfig {
-    pub photo_dir: PathBuf,
+    pub config_file: Option<PathBuf>, // changed settings are saved here
+    pub profile: Option<String>,      // section of the config file used
+    pub name: String,                 // of this frame, e.g. "Living Room"
+    pub photo_dirs: Vec<PathBuf>,
+    pub start_scene: SceneKind,
+    pub scene_config: BTreeMap<String, toml::Table>, // [scenes.<name>] of plugins
+    pub slide_duration: f32,                         // seconds a photo is shown
+    pub transition_duration: f32,                    // seconds of the cross fade
+    pub transition_easing: Easing,                   // of the cross fade
+    pub transition_effect: Option<TransitionKind>,   // a random one if None
+    pub slide_jitter: f32,    // fraction the slide duration randomly varies by
+    pub ken_burns: bool,      // photos slowly pan and zoom while shown
+    pub letterbox: Letterbox, // fills the screen around photos
+    pub photo_filter: PhotoFilter, // of the photos of the built-in slideshows
+    pub locale: LocaleId,
+    pub time_format: Option<TimePattern>,
+    pub units: Units,
+    pub night: Option<NightHours>,
+    pub schedule: Schedule, // scenes shown at certain times instead of start_scene
+    pub rotation: Rotation, // order of the scene keys, empty for all scenes
+    pub weather: WeatherConfig,
+    pub geocoding: GeocodingConfig,
+    pub ambient_light: AmbientLightConfig, // dims the frame in dark rooms
+    pub music: MusicConfig,
+    pub tts: TtsConfig,           // speaks the `say` messages of the schedule
+    pub presence: PresenceConfig, // sleeps while nobody is home
+    pub keymap: Keymap,
+    pub rates: LoopRates,           // of updates and frames, active and idle
+    pub catch_up: CatchUp,          // of a loop running late
+    pub display: DisplayMode,       // fullscreen, monitor and window size
+    pub listen: Option<SocketAddr>, // address of the HTTP server, off if None
+    pub api_token: Option<String>,  // for changes over HTTP, local only if None
+    pub advertise: bool,            // announce the HTTP server via mDNS
+    pub dbus: Option<Bus>,          // register org.home.Frame, Linux only
+    pub control_socket: Option<PathBuf>, // for `home-rs ctl`, a named pipe on Windows
+    pub log: LogConfig,
+    pub crash_dir: PathBuf,
+    pub debug_overlay: bool,
+    pub clock_overlay: Option<ClockOverlayStyle>, // time and date on top of all scenes
+    pub sharpen: Option<UnsharpMask>,
+    pub background_decoding: bool, // photos are decoded on a worker thread
+    pub font: PathBuf,             // MSDF atlas or TrueType font
+    pub text_px_range: Option<f32>, // overrides the distance range of the font atlas
+    pub headless: Option<HeadlessConfig>, // render to PNG files instead of a window
+    pub bench: bool,               // time the photo pipeline and exit
+    pub record_input: Option<PathBuf>, // input events are written here
+    pub replay_input: Option<PathBuf>, // recorded input fed to the updates
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
-            photo_dir: PathBuf::from("assets/photos/"),
+            config_file: None,
+            profile: None,
+            name: String::from("Home Frame"),
+            photo_dirs: vec![PathBuf::from("assets/photos/")],
+            start_scene: SceneKind::default(),
+            scene_config: BTreeMap::new(),
+            slide_duration: 1.5,
+            transition_duration: 0.4,
+            transition_easing: Easing::SineInOut,
+            transition_effect: Some(TransitionKind::Crossfade),
+            slide_jitter: 0.0,
+            ken_burns: false,
+            letterbox: Letterbox::default(),
+            photo_filter: PhotoFilter::default(),
+            locale: LocaleId::default(),
+            time_format: None,
+            units: Units::metric(),
+            night: None,
+            schedule: Schedule::default(),
+            rotation: Rotation::default(),
+            weather: WeatherConfig::default(),
+            geocoding: GeocodingConfig::default(),
+            ambient_light: AmbientLightConfig::default(),
+            music: MusicConfig::default(),
+            tts: TtsConfig::default(),
+            presence: PresenceConfig::default(),
+            keymap: Keymap::default(),
+            rates: LoopRates::default(),
+            catch_up: CatchUp::default(),
+            display: DisplayMode::default(),
+            listen: None,
+            api_token: None,
+            advertise: true,
+            dbus: None,
+            control_socket: default_control_socket(),
+            log: LogConfig::default(),
+            crash_dir: PathBuf::from("crash"),
+            debug_overlay: false,
+            clock_overlay: None,
+            sharpen: None,
+            background_decoding: true,
+            font: PathBuf::from(DEFAULT_FONT),
+            text_px_range: None,
+            headless: None,
+            bench: false,
+            record_input: None,
+            replay_input: None,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Local time range in which the display is dimmed, may wrap past midnight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NightHours {
+    pub start: Time,
+    pub end: Time,
+}
+
+impl NightHours {
+    pub fn contains(&self, time: Time) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherConfig {
+    pub api_key: Option<String>,
+    pub location: Option<String>,
+}
+
+// ----------------------------------------------------------------------------
+// Places of photos are resolved with the cache only unless `url` names a
+// Nominatim compatible service, e.g. "https://nominatim.openstreetmap.org"
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeocodingConfig {
+    pub url: Option<String>,
+    pub cache: PathBuf,
+}
+
+impl Default for GeocodingConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            cache: PathBuf::from("geocache.json"),
         }
     }
 }
@@ -25,44 +197,434 @@ pub struct App {
     config: AppConfig,
     renderer: Renderer,
     scenes: SceneManager,
+    server: Option<HttpServer>,
+    advertiser: Option<Advertiser>,
+    sinks: Vec<Arc<dyn EventSink>>,
+    commands: Receiver<Command>,
+    fetcher: Fetcher,
+    tasks: TaskSender,
+    task_events: Receiver<SystemEvent>, // results of worker threads
+    geocoder: Geocoder,
+    brightness: Option<AutoBrightness>,
+    music: Option<MusicPlayer>,
+    speaker: Option<Speaker>, // started with the first announcement
+    presence: Option<PresenceDetector>,
+    asleep: Option<bool>, // whether the slideshow was paused before
+    size: (i32, i32),     // of the window
+    redraw: bool,         // the last frame is outdated, e.g. after a resize
+    input_pending: bool,  // input or commands arrived with the last update
+    exit_requested: bool,
+    frame_count: u32,
+    t_fps: Option<Instant>,
 }
 
 impl App {
     pub fn new(config: AppConfig, gl: OpenGlFunctions, cx: i32, cy: i32) -> Result<Self> {
+        Self::with_scenes(config, SceneRegistry::new(), gl, cx, cy)
+    }
+
+    // Like `new`, with scene plugins registered by the caller
+    pub fn with_scenes(
+        config: AppConfig,
+        scenes: SceneRegistry,
+        gl: OpenGlFunctions,
+        cx: i32,
+        cy: i32,
+    ) -> Result<Self> {
         let gl = Rc::new(gl);
         let aspect_ratio = cx as f32 / cy as f32;
         let canvas = Canvas::new(Rc::clone(&gl), aspect_ratio)?;
-        let layouter = Layouter::new(canvas)?;
-        let scenes = SceneManager::new(layouter, &config.photo_dir)?;
+        let (tasks, task_events) = tasks::channel();
+        let mut layouter = Layouter::new(canvas, &config.font)?;
+        layouter.set_sharpen(config.sharpen);
+        layouter.set_letterbox(config.letterbox);
+        layouter.set_px_range(config.text_px_range);
+        if config.background_decoding {
+            let decoder = PhotoDecoder::start(tasks.clone(), config.sharpen, config.letterbox)?;
+            layouter.set_decoder(decoder);
+        }
+        let admin_url = config.listen.and_then(settings_url);
+        let mut scenes = SceneManager::new(layouter, &config, scenes, admin_url)?;
+
+        let (command_sender, commands) = mpsc::channel();
+        let fetcher = Fetcher::start(tasks.clone())?;
+        let mut geocoder = Geocoder::new(&config.geocoding, config.locale, fetcher.clone());
+        geocoder.resolve(scenes.photos_mut());
+        let mut sinks: Vec<Arc<dyn EventSink>> = Vec::new();
+
+        let server = match config.listen {
+            Some(addr) => {
+                let settings = SettingsService::new(&config, command_sender.clone());
+                let events = Arc::new(EventHub::new());
+                let token = config.api_token.clone();
+                Some(HttpServer::start(addr, token, events, Arc::new(settings))?)
+            }
+            None => None,
+        };
+        if let Some(server) = &server {
+            sinks.push(server.events().clone());
+        }
+        let advertiser = server
+            .as_ref()
+            .filter(|_| config.advertise)
+            .and_then(|server| start_advertiser(&config.name, server.addr()));
+
+        if let Some(bus) = config.dbus {
+            sinks.push(start_dbus(bus, command_sender.clone())?);
+        }
+        if let Some(path) = &config.control_socket {
+            sinks.extend(start_control_socket(path, command_sender.clone()));
+        }
+
+        // logged errors are forwarded to the control services as well
+        if !sinks.is_empty() {
+            let error_sinks = sinks.clone();
+            logger::set_error_hook(move |message| {
+                let event = FrameEvent::Error {
+                    message: message.to_string(),
+                };
+                for sink in &error_sinks {
+                    sink.publish(&event);
+                }
+            });
+        }
+
+        let mut renderer = Renderer::new(gl, cx as usize, cy as usize)?;
+        let brightness = AutoBrightness::new(&config.ambient_light);
+        if let Some(brightness) = &brightness {
+            renderer.set_brightness(brightness.brightness());
+        }
+
+        // the slideshow works without music, so failures are not fatal
+        let music = config.music.path.as_ref().and_then(|_| {
+            MusicPlayer::start(&config.music)
+                .inspect_err(|e| log::warn!("Music failed: {e:?}"))
+                .ok()
+        });
+        let presence = config.presence.is_enabled().then(|| {
+            PresenceDetector::start(&config.presence, tasks.clone())
+                .inspect_err(|e| log::warn!("Presence detection failed: {e:?}"))
+                .ok()
+        });
 
         Ok(Self {
             config,
-            renderer: Renderer::new(gl, cx as usize, cy as usize)?,
+            renderer,
             scenes,
+            server,
+            advertiser,
+            sinks,
+            commands,
+            fetcher,
+            tasks,
+            task_events,
+            geocoder,
+            brightness,
+            music,
+            speaker: None,
+            presence: presence.flatten(),
+            asleep: None,
+            size: (cx, cy),
+            redraw: true,
+            input_pending: false,
+            exit_requested: false,
+            frame_count: 0,
+            t_fps: None,
         })
     }
 
     pub fn resize(&mut self, cx: i32, cy: i32) {
+        // minimized windows have no area, moved ones report their size again
+        if cx <= 0 || cy <= 0 || (cx, cy) == self.size {
+            return;
+        }
+        self.size = (cx, cy);
         let aspect_ratio = cx as f32 / cy as f32;
         self.renderer.resize(cx, cy);
         self.scenes.resize(aspect_ratio);
+        self.redraw = true;
+    }
+
+    // The window was uncovered and shows garbage until the next frame
+    pub fn request_redraw(&mut self) {
+        self.redraw = true;
+    }
+
+    fn say(&mut self, text: &str) {
+        if self.speaker.is_none() {
+            self.speaker = Speaker::start(&self.config.tts)
+                .inspect_err(|e| log::warn!("Text to speech failed: {e:?}"))
+                .ok();
+        }
+        if let Some(speaker) = &self.speaker {
+            speaker.say(text);
+        }
+    }
+
+    // The slideshow and music stand still while the frame sleeps and continue
+    // as they were on waking up
+    fn set_asleep(&mut self, asleep: bool) {
+        let paused = match (asleep, self.asleep) {
+            (true, None) => {
+                self.asleep = Some(self.scenes.is_paused());
+                true
+            }
+            (false, Some(paused)) => {
+                self.asleep = None;
+                paused
+            }
+            _ => return,
+        };
+        self.scenes.set_paused(paused);
+        if let Some(music) = &mut self.music {
+            music.set_paused(paused);
+        }
+        self.redraw = true;
+    }
+
+    // Results of worker threads, the scenes see all of them
+    fn handle_task_event(&mut self, event: SystemEvent) {
+        match &event {
+            SystemEvent::Presence { present } => self.set_asleep(!present),
+            event if self.geocoder.handle(event) => {
+                self.geocoder.resolve(self.scenes.photos_mut());
+            }
+            _ => {}
+        }
+        self.scenes.update(&SceneEvent::System(event));
+    }
+
+    // The exit key was pressed, the platform layer closes the window
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    // ------------------------------------------------------------------------
+    // Keys become user events, see `SceneManager::handle_user_event`
+    fn handle_input(&mut self, TimedEvent { t, event }: TimedEvent) {
+        if t.elapsed() > STALE_INPUT {
+            log::debug!("Dropped stale input {event}");
+            return;
+        }
+        let Event::KeyDown { key } = event else {
+            return;
+        };
+        let user_event = match key {
+            Key::ToggleOverlay => return self.scenes.toggle_overlay(),
+            Key::Home => UserEvent::Home,
+            Key::NextScene => UserEvent::Next,
+            Key::PrevScene => UserEvent::Previous,
+            Key::Exit => {
+                self.exit_requested = true;
+                UserEvent::Exit
+            }
+        };
+        self.scenes.handle_user_event(user_event);
+    }
+
+    // Top-down RGB24 pixels of the last rendered frame of size cx x cy
+    pub fn capture(&self, cx: usize, cy: usize) -> Vec<u8> {
+        self.renderer.read_pixels(cx, cy)
+    }
+
+    // Shared HTTP client, results are passed to the scenes as system events
+    pub fn fetcher(&self) -> &Fetcher {
+        &self.fetcher
+    }
+
+    // For results of worker threads, passed to the scenes as system events
+    pub fn tasks(&self) -> &TaskSender {
+        &self.tasks
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The input of the main loop, recording or replaying a session if configured
+pub fn create_input(config: &AppConfig) -> Result<Input> {
+    let mut input = Input::new();
+    if let Some(path) = &config.replay_input {
+        input.replay(&std::fs::read_to_string(path).with_path("reading", path)?)?;
+        log::info!("Replaying input of {path:?}");
+    }
+    if let Some(path) = &config.record_input {
+        input.record(std::fs::File::create(path).with_path("creating", path)?);
+        log::info!("Recording input to {path:?}");
     }
+    Ok(input)
+}
+
+// ----------------------------------------------------------------------------
+#[cfg(target_os = "linux")]
+fn start_dbus(bus: Bus, commands: Sender<Command>) -> Result<Arc<dyn EventSink>> {
+    Ok(crate::ipc::dbus::DBusService::start(bus, commands)?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn start_dbus(_bus: Bus, _commands: Sender<Command>) -> Result<Arc<dyn EventSink>> {
+    Err(crate::error::Error::DBus {
+        msg: String::from("D-Bus is only supported on Linux"),
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Scripts can still use D-Bus or HTTP, so failures are not fatal
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn start_control_socket(path: &Path, commands: Sender<Command>) -> Option<Arc<dyn EventSink>> {
+    let socket = crate::ipc::socket::ControlSocket::start(path, commands)
+        .inspect_err(|e| log::warn!("Control socket failed: {e:?}"))
+        .ok()?;
+    Some(socket)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn start_control_socket(_path: &Path, _commands: Sender<Command>) -> Option<Arc<dyn EventSink>> {
+    log::warn!("The control socket is only supported on Linux and Windows");
+    None
+}
+
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn default_control_socket() -> Option<PathBuf> {
+    Some(crate::ipc::socket::default_path())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn default_control_socket() -> Option<PathBuf> {
+    None
+}
+
+// ----------------------------------------------------------------------------
+// The address others reach the server at, the local one of a wildcard
+fn reachable_ipv4(addr: SocketAddr) -> Option<Ipv4Addr> {
+    match addr.ip() {
+        IpAddr::V4(ip) if !ip.is_unspecified() => Some(ip),
+        _ => mdns::local_ipv4(),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Shown on the error scene, for fixing the settings from a phone
+fn settings_url(addr: SocketAddr) -> Option<String> {
+    let ip = reachable_ipv4(addr)?;
+    Some(format!("http://{ip}:{}/", addr.port()))
+}
+
+// ----------------------------------------------------------------------------
+// A frame without mDNS is still reachable by IP, so failures are not fatal
+fn start_advertiser(name: &str, addr: SocketAddr) -> Option<Advertiser> {
+    let ip = reachable_ipv4(addr)?;
+    Advertiser::start(ServiceInfo::new(name, ip, addr.port()))
+        .inspect_err(|e| log::warn!("mDNS advertisement failed: {e:?}"))
+        .ok()
 }
 
 impl IApp for App {
     fn update(
         &mut self,
         _t: std::time::Instant,
-        _dt: std::time::Duration,
-        _input: &mut Input,
+        dt: std::time::Duration,
+        input: &mut Input,
     ) -> Result<()> {
-        self.scenes.update(&crate::scene::SceneEvent::TimeTick);
+        while let Ok(event) = self.task_events.try_recv() {
+            self.handle_task_event(event);
+        }
+        let events = input.take_events();
+        self.input_pending = !events.is_empty();
+        for event in events {
+            self.handle_input(event);
+        }
+        while let Ok(command) = self.commands.try_recv() {
+            log::info!("Remote command: {command:?}");
+            self.input_pending = true;
+            match command {
+                Command::Next => self.scenes.update(&SceneEvent::User(UserEvent::Next)),
+                Command::Previous => self.scenes.update(&SceneEvent::User(UserEvent::Previous)),
+                Command::Pause(paused) => {
+                    self.scenes.set_paused(paused);
+                    if let Some(music) = &mut self.music {
+                        music.set_paused(paused);
+                    }
+                }
+                Command::SetVolume(volume) => match &mut self.music {
+                    Some(music) => music.set_volume(volume),
+                    None => log::warn!("No music playing"),
+                },
+                Command::Mute(muted) => match &mut self.music {
+                    Some(music) => music.set_muted(muted),
+                    None => log::warn!("No music playing"),
+                },
+                Command::SetScene(kind) => self.scenes.switch_scene(kind),
+                Command::ApplySettings(settings) => match settings.apply(&mut self.config) {
+                    Ok(()) => {
+                        self.scenes.reload(&self.config);
+                        self.geocoder.resolve(self.scenes.photos_mut());
+                    }
+                    Err(e) => log::error!("Failed to apply settings: {e:?}"),
+                },
+                Command::UpdatePhoto(update) => {
+                    if let Err(e) = self.scenes.update_photo_meta(&update) {
+                        log::error!("Failed to update the photo: {e}");
+                    }
+                }
+            }
+        }
+        self.scenes.update(&SceneEvent::TimeTick(dt.as_secs_f32()));
+        if let Some(brightness) = self.brightness.as_mut().and_then(|b| b.update(dt)) {
+            self.renderer.set_brightness(brightness);
+            self.redraw = true;
+        }
+        if let Some(music) = &mut self.music {
+            let night = self.config.night.as_ref();
+            music.update(dt, night.filter(|_| self.config.music.mute_at_night));
+        }
+
+        for text in self.scenes.take_announcements() {
+            self.say(&text);
+        }
+        for event in self.scenes.take_events() {
+            crash::record_event(&event);
+            for sink in &self.sinks {
+                sink.publish(&event);
+            }
+        }
         Ok(())
     }
 
-    fn render(&mut self, _t: &std::time::Instant) -> Result<()> {
+    fn render(&mut self, t: &std::time::Instant, alpha: f32) -> Result<()> {
+        self.redraw = false;
+        if self.asleep.is_some() {
+            self.renderer.clear();
+            return Ok(());
+        }
         //let camera = camera::Camera::new([0.0, 0.0, 0.0, 1.0].into(), 1.0);
-        self.renderer.render(self.scenes.canvas())?;
+        self.scenes.prepare_render(alpha);
+        if let Err(e) = self.renderer.render(self.scenes.canvas()) {
+            // the error scene can't be shown either, e.g. with a lost context
+            if self.scenes.is_showing_error() {
+                return Err(e);
+            }
+            log::error!("Render failed: {e:?}");
+            self.scenes.show_error(&e);
+        }
+
+        // Frame rate is averaged over roughly one second
+        self.frame_count += 1;
+        let t_fps = *self.t_fps.get_or_insert(*t);
+        let dt = t.duration_since(t_fps);
+        if dt >= Duration::from_secs(1) {
+            let fps = self.frame_count as f32 / dt.as_secs_f32();
+            self.scenes.update_overlay(fps, frame_stats::latest());
+            self.frame_count = 0;
+            self.t_fps = Some(*t);
+        }
         Ok(())
     }
+
+    fn is_idle(&self) -> bool {
+        self.asleep.is_some() || (!self.scenes.is_moving() && !self.input_pending)
+    }
+
+    fn needs_redraw(&self) -> bool {
+        // the scenes don't show while asleep
+        self.redraw || (self.asleep.is_none() && self.scenes.needs_redraw())
+    }
 }
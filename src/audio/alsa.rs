@@ -0,0 +1,150 @@
+use crate::error::{Error, Result};
+use std::ffi::{CStr, c_char, c_int, c_long, c_uint, c_ulong, c_void};
+
+// libasound is loaded at runtime, frames without sound hardware or without
+// music configured don't need it installed
+unsafe extern "C" {
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlclose(handle: *mut c_void) -> c_int;
+}
+const RTLD_NOW: c_int = 2;
+
+type SndPcm = *mut c_void;
+
+const SND_PCM_STREAM_PLAYBACK: c_int = 0;
+const SND_PCM_FORMAT_S16_LE: c_int = 2;
+const SND_PCM_ACCESS_RW_INTERLEAVED: c_int = 3;
+const LATENCY_US: c_uint = 200_000;
+
+type FnOpen = unsafe extern "C" fn(*mut SndPcm, *const c_char, c_int, c_int) -> c_int;
+type FnSetParams =
+    unsafe extern "C" fn(SndPcm, c_int, c_int, c_uint, c_uint, c_int, c_uint) -> c_int;
+type FnWritei = unsafe extern "C" fn(SndPcm, *const c_void, c_ulong) -> c_long;
+type FnRecover = unsafe extern "C" fn(SndPcm, c_int, c_int) -> c_int;
+type FnDrop = unsafe extern "C" fn(SndPcm) -> c_int;
+type FnClose = unsafe extern "C" fn(SndPcm) -> c_int;
+type FnStrError = unsafe extern "C" fn(c_int) -> *const c_char;
+
+// ----------------------------------------------------------------------------
+// Playback stream on the "default" ALSA device, 16 bit interleaved samples
+pub struct AlsaPcm {
+    lib: *mut c_void,
+    pcm: SndPcm,
+    channels: u16,
+    writei: FnWritei,
+    recover: FnRecover,
+    drop: FnDrop,
+    close: FnClose,
+    strerror: FnStrError,
+}
+
+// The stream is only used by the music thread that opened it
+unsafe impl Send for AlsaPcm {}
+
+impl AlsaPcm {
+    // ------------------------------------------------------------------------
+    pub fn open(channels: u16, sample_rate: u32) -> Result<Self> {
+        let lib = unsafe { dlopen(c"libasound.so.2".as_ptr(), RTLD_NOW) };
+        if lib.is_null() {
+            return Err(Error::Audio {
+                msg: String::from("libasound.so.2 not found"),
+            });
+        }
+        // the library is closed again by Drop
+        let mut stream = Self {
+            lib,
+            pcm: std::ptr::null_mut(),
+            channels,
+            writei: unsafe { symbol(lib, c"snd_pcm_writei")? },
+            recover: unsafe { symbol(lib, c"snd_pcm_recover")? },
+            drop: unsafe { symbol(lib, c"snd_pcm_drop")? },
+            close: unsafe { symbol(lib, c"snd_pcm_close")? },
+            strerror: unsafe { symbol(lib, c"snd_strerror")? },
+        };
+
+        unsafe {
+            let open: FnOpen = symbol(lib, c"snd_pcm_open")?;
+            let err = open(
+                &mut stream.pcm,
+                c"default".as_ptr(),
+                SND_PCM_STREAM_PLAYBACK,
+                0,
+            );
+            stream.check("snd_pcm_open", err)?;
+
+            let set_params: FnSetParams = symbol(lib, c"snd_pcm_set_params")?;
+            let err = set_params(
+                stream.pcm,
+                SND_PCM_FORMAT_S16_LE,
+                SND_PCM_ACCESS_RW_INTERLEAVED,
+                channels as c_uint,
+                sample_rate,
+                1, // let ALSA resample if the device needs another rate
+                LATENCY_US,
+            );
+            stream.check("snd_pcm_set_params", err)?;
+        }
+        Ok(stream)
+    }
+
+    // ------------------------------------------------------------------------
+    // Blocks until the samples are queued, recovers from buffer underruns,
+    // e.g. after the music was paused
+    pub fn write(&mut self, samples: &[i16]) -> Result<()> {
+        let mut samples = samples;
+        while !samples.is_empty() {
+            let frames = samples.len() / self.channels as usize;
+            let ptr = samples.as_ptr() as *const c_void;
+            let written = unsafe { (self.writei)(self.pcm, ptr, frames as c_ulong) };
+            if written < 0 {
+                let err = unsafe { (self.recover)(self.pcm, written as c_int, 1) };
+                self.check("snd_pcm_writei", err)?;
+                continue;
+            }
+            samples = &samples[written as usize * self.channels as usize..];
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Stops at once, queued samples are discarded
+    pub fn stop(&mut self) {
+        unsafe { (self.drop)(self.pcm) };
+    }
+
+    // ------------------------------------------------------------------------
+    fn check(&self, call: &str, err: c_int) -> Result<()> {
+        if err >= 0 {
+            return Ok(());
+        }
+        let msg = unsafe { CStr::from_ptr((self.strerror)(err)) };
+        Err(Error::Audio {
+            msg: format!("{call} failed: {}", msg.to_string_lossy()),
+        })
+    }
+}
+
+impl Drop for AlsaPcm {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.pcm.is_null() {
+                (self.close)(self.pcm);
+            }
+            dlclose(self.lib);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+/// # Safety
+/// `T` must be the function pointer type of the symbol
+unsafe fn symbol<T: Copy>(lib: *mut c_void, name: &CStr) -> Result<T> {
+    let ptr = unsafe { dlsym(lib, name.as_ptr()) };
+    if ptr.is_null() {
+        return Err(Error::Audio {
+            msg: format!("{} not found in libasound", name.to_string_lossy()),
+        });
+    }
+    Ok(unsafe { std::mem::transmute_copy::<*mut c_void, T>(&ptr) })
+}
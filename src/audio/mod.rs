@@ -0,0 +1,337 @@
+use crate::app::NightHours;
+use crate::error::{Error, Result, ResultExt};
+use crate::library;
+use crate::util::datetime::DateTime;
+use crate::util::random::{self, XorShift};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+pub mod alsa;
+pub mod tts;
+pub mod wav;
+
+// Controls are applied between chunks of this length
+const CHUNK_MS: usize = 50;
+
+// The quiet hours are checked this often
+const QUIET_CHECK: Duration = Duration::from_secs(1);
+
+// ----------------------------------------------------------------------------
+// Background music of the slideshow. `path` is a folder of WAV files or an
+// .m3u playlist, the tracks repeat until the frame quits.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MusicConfig {
+    pub path: Option<PathBuf>,
+    pub volume: f32, // 0.0 to 1.0, changed remotely with `volume <percent>`
+    pub shuffle: bool,
+    pub mute_at_night: bool, // silent during the night hours
+}
+
+impl Default for MusicConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            volume: 0.5,
+            shuffle: false,
+            mute_at_night: true,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub fn is_wav_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+}
+
+// ----------------------------------------------------------------------------
+// The tracks of a folder, sorted by name, or of a playlist file
+pub fn playlist(path: &Path) -> Result<Vec<PathBuf>> {
+    let tracks = if path.is_dir() {
+        let files = library::list_files(path)?;
+        files.into_iter().filter(|p| is_wav_file(p)).collect()
+    } else {
+        let text = std::fs::read_to_string(path).with_path("reading", path)?;
+        parse_m3u(&text, path.parent().unwrap_or(Path::new("")))
+    };
+    if tracks.is_empty() {
+        return Err(Error::Audio {
+            msg: format!("No music found in {}", path.display()),
+        });
+    }
+    Ok(tracks)
+}
+
+// ----------------------------------------------------------------------------
+// One path per line, relative ones start at `base`, #EXTINF and other
+// comments are skipped
+pub fn parse_m3u(text: &str, base: &Path) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .collect()
+}
+
+// ----------------------------------------------------------------------------
+// Fisher-Yates
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = XorShift::new(seed);
+    for i in (1..items.len()).rev() {
+        items.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Scales the samples by a gain going linearly from `from` to `to`, so that
+// volume changes don't click
+pub fn apply_gain(samples: &mut [i16], channels: usize, from: f32, to: f32) {
+    let frames = (samples.len() / channels).max(1);
+    for (i, frame) in samples.chunks_mut(channels).enumerate() {
+        let gain = from + (to - from) * (i + 1) as f32 / frames as f32;
+        for s in frame {
+            *s = (*s as f32 * gain) as i16;
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Control {
+    Gain(f32),
+    Pause(bool),
+    Quit,
+}
+
+// ----------------------------------------------------------------------------
+// Plays the tracks on a background thread, the app forwards volume changes,
+// pauses and the quiet hours
+pub struct MusicPlayer {
+    controls: Sender<Control>,
+    thread: Option<JoinHandle<()>>,
+    volume: f32,
+    muted: bool,
+    quiet: bool, // muted during the night hours
+    t_quiet: Duration,
+}
+
+impl MusicPlayer {
+    pub fn start(config: &MusicConfig) -> Result<Self> {
+        let path = config.path.as_ref().ok_or(Error::InvalidArgument {
+            arg: String::from("music path"),
+        })?;
+        let mut tracks = playlist(path)?;
+        let seed = match config.shuffle {
+            true => random::time_seed(),
+            false => 0,
+        };
+        if seed != 0 {
+            shuffle(&mut tracks, seed);
+        }
+        log::info!("Playing {} tracks from {}", tracks.len(), path.display());
+
+        let volume = config.volume.clamp(0.0, 1.0);
+        let (controls, receiver) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name(String::from("music"))
+            .spawn(move || {
+                if let Err(e) = play(tracks, seed, gain(volume), receiver) {
+                    log::error!("Music stopped: {e:?}");
+                }
+            })?;
+        Ok(Self {
+            controls,
+            thread: Some(thread),
+            volume,
+            muted: false,
+            quiet: false,
+            t_quiet: Duration::ZERO,
+        })
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.send_gain();
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.send_gain();
+    }
+
+    // Follows the pause of the slideshow
+    pub fn set_paused(&mut self, paused: bool) {
+        let _ = self.controls.send(Control::Pause(paused));
+    }
+
+    // ------------------------------------------------------------------------
+    // Mutes the music while `night` contains the local time
+    pub fn update(&mut self, dt: Duration, night: Option<&NightHours>) {
+        self.t_quiet = self.t_quiet.saturating_sub(dt);
+        if !self.t_quiet.is_zero() {
+            return;
+        }
+        self.t_quiet = QUIET_CHECK;
+        let now = DateTime::local_now();
+        let quiet = night.is_some_and(|night| night.contains(now.time));
+        if quiet != self.quiet {
+            log::info!(
+                "Music {} for the night hours",
+                if quiet { "muted" } else { "unmuted" }
+            );
+            self.quiet = quiet;
+            self.send_gain();
+        }
+    }
+
+    fn send_gain(&self) {
+        let gain = match self.muted || self.quiet {
+            true => 0.0,
+            false => gain(self.volume),
+        };
+        let _ = self.controls.send(Control::Gain(gain));
+    }
+}
+
+impl Drop for MusicPlayer {
+    fn drop(&mut self) {
+        let _ = self.controls.send(Control::Quit);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Loudness is perceived logarithmically, a squared volume is closer to it
+// than a linear one
+fn gain(volume: f32) -> f32 {
+    volume * volume
+}
+
+// ----------------------------------------------------------------------------
+// Returns when quit, or with an error if the output fails or no track of the
+// playlist can be decoded
+#[cfg(target_os = "linux")]
+fn play(mut tracks: Vec<PathBuf>, seed: u64, gain: f32, controls: Receiver<Control>) -> Result<()> {
+    use alsa::AlsaPcm;
+
+    let mut output: Option<(AlsaPcm, (u16, u32))> = None;
+    let mut gain = (gain, gain); // of the last chunk and the target
+    let mut paused = false;
+    for round in 1u64.. {
+        let mut played = 0;
+        for path in &tracks {
+            let track = std::fs::read(path)
+                .map_err(Error::from)
+                .and_then(|data| wav::decode(&data));
+            let track = match track {
+                Ok(track) => track,
+                Err(e) => {
+                    log::warn!("Skipping {}: {e:?}", path.display());
+                    continue;
+                }
+            };
+            played += 1;
+            log::debug!("Playing {}", path.display());
+
+            // the device is reopened if the sample format changes
+            let format = (track.channels, track.sample_rate);
+            if output.as_ref().is_some_and(|(_, f)| *f != format) {
+                output = None;
+            }
+            let pcm = match &mut output {
+                Some((pcm, _)) => pcm,
+                None => {
+                    &mut output
+                        .insert((AlsaPcm::open(format.0, format.1)?, format))
+                        .0
+                }
+            };
+            let channels = track.channels as usize;
+            let chunk_len = channels * track.sample_rate as usize * CHUNK_MS / 1000;
+            let mut chunk = Vec::with_capacity(chunk_len);
+            for samples in track.samples.chunks(chunk_len) {
+                loop {
+                    let control = match paused {
+                        true => controls.recv().unwrap_or(Control::Quit),
+                        false => match controls.try_recv() {
+                            Ok(control) => control,
+                            Err(mpsc::TryRecvError::Empty) => break,
+                            Err(mpsc::TryRecvError::Disconnected) => Control::Quit,
+                        },
+                    };
+                    match control {
+                        Control::Gain(target) => gain.1 = target,
+                        Control::Pause(pause) => paused = pause,
+                        Control::Quit => {
+                            pcm.stop();
+                            return Ok(());
+                        }
+                    }
+                }
+
+                chunk.clear();
+                chunk.extend_from_slice(samples);
+                apply_gain(&mut chunk, channels, gain.0, gain.1);
+                gain.0 = gain.1;
+                pcm.write(&chunk)?;
+            }
+        }
+        if played == 0 {
+            return Err(Error::Audio {
+                msg: String::from("No track of the playlist could be played"),
+            });
+        }
+        if seed != 0 {
+            shuffle(&mut tracks, seed.wrapping_add(round));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn play(_tracks: Vec<PathBuf>, _seed: u64, _gain: f32, _controls: Receiver<Control>) -> Result<()> {
+    Err(Error::Audio {
+        msg: String::from("Music is only supported on Linux"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playlist() {
+        let text = "#EXTM3U\n#EXTINF:123,Artist - Title\nsong.wav\n\n  /music/b.wav \n";
+        assert_eq!(
+            parse_m3u(text, Path::new("/home/frame")),
+            [
+                PathBuf::from("/home/frame/song.wav"),
+                PathBuf::from("/music/b.wav")
+            ]
+        );
+
+        let mut items: Vec<_> = (0..20).collect();
+        shuffle(&mut items, 42);
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+
+        let mut samples = [1000, -1000, 1000, -1000];
+        apply_gain(&mut samples, 2, 1.0, 0.5);
+        assert_eq!(samples, [750, -750, 500, -500]);
+        assert_eq!(gain(0.5), 0.25);
+        assert!(is_wav_file(Path::new("a/b.WAV")));
+        assert!(!is_wav_file(Path::new("a/b.mp3")));
+    }
+}
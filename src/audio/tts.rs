@@ -0,0 +1,126 @@
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+
+// ----------------------------------------------------------------------------
+// Text to speech by an external program. An argument "{text}" is replaced
+// by the message, without one the message is written to its stdin. The
+// default is speech-dispatcher on Linux and System.Speech on Windows, other
+// engines are used like ["espeak-ng", "-v", "de", "{text}"].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TtsConfig {
+    pub command: Vec<String>,
+}
+
+impl Default for TtsConfig {
+    fn default() -> Self {
+        Self {
+            command: default_command().iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn default_command() -> &'static [&'static str] {
+    &[
+        "powershell",
+        "-NoProfile",
+        "-Command",
+        "Add-Type -AssemblyName System.Speech; \
+         (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak([Console]::In.ReadToEnd())",
+    ]
+}
+
+#[cfg(not(target_os = "windows"))]
+fn default_command() -> &'static [&'static str] {
+    &["spd-say", "--wait", "{text}"]
+}
+
+// ----------------------------------------------------------------------------
+// Speaks messages one after the other on a background thread
+pub struct Speaker {
+    messages: Sender<String>,
+}
+
+impl Speaker {
+    pub fn start(config: &TtsConfig) -> Result<Self> {
+        if config.command.is_empty() {
+            return Err(Error::InvalidArgument {
+                arg: String::from("tts command"),
+            });
+        }
+        let command = config.command.clone();
+        let (messages, receiver) = mpsc::channel::<String>();
+        std::thread::Builder::new()
+            .name(String::from("tts"))
+            .spawn(move || {
+                for text in receiver {
+                    if let Err(e) = speak(&command, &text) {
+                        log::warn!("Failed to speak '{text}': {e:?}");
+                    }
+                }
+            })?;
+        Ok(Self { messages })
+    }
+
+    pub fn say(&self, text: &str) {
+        log::info!("Saying '{text}'");
+        let _ = self.messages.send(text.to_string());
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The program and its arguments with the message filled in, None if the
+// message goes to stdin
+pub fn command_line(command: &[String], text: &str) -> (Vec<String>, Option<String>) {
+    let args = command
+        .iter()
+        .map(|arg| arg.replace("{text}", text))
+        .collect();
+    let stdin = (!command.iter().any(|arg| arg.contains("{text}"))).then(|| text.to_string());
+    (args, stdin)
+}
+
+// ----------------------------------------------------------------------------
+fn speak(command: &[String], text: &str) -> Result<()> {
+    let (args, stdin) = command_line(command, text);
+    let mut child = Command::new(&args[0])
+        .args(&args[1..])
+        .stdin(match stdin {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .stdout(Stdio::null())
+        .spawn()?;
+    if let (Some(text), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(Error::Audio {
+            msg: format!("{} exited with {status}", args[0]),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let command = ["espeak-ng", "-v", "de", "{text}"].map(String::from);
+        let (args, stdin) = command_line(&command, "Guten Morgen");
+        assert_eq!(args, ["espeak-ng", "-v", "de", "Guten Morgen"]);
+        assert_eq!(stdin, None);
+
+        let command = ["say-stdin"].map(String::from);
+        let (args, stdin) = command_line(&command, "Hello");
+        assert_eq!(args, ["say-stdin"]);
+        assert_eq!(stdin.as_deref(), Some("Hello"));
+    }
+}
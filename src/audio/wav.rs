@@ -0,0 +1,155 @@
+use crate::error::{Error, Result};
+
+// ----------------------------------------------------------------------------
+// Decoded RIFF WAVE file, interleaved 16 bit samples
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wav {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub samples: Vec<i16>,
+}
+
+impl Wav {
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+}
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+// ----------------------------------------------------------------------------
+// Integer PCM with 8 to 32 bits and 32 bit float are supported, other
+// encodings are rejected
+pub fn decode(data: &[u8]) -> Result<Wav> {
+    let invalid = |msg: &str| Error::Audio {
+        msg: format!("Invalid WAV file: {msg}"),
+    };
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(invalid("no RIFF WAVE header"));
+    }
+
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let id = &data[pos..pos + 4];
+        let len = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let body = &data[pos + 8..(pos + 8).saturating_add(len).min(data.len())];
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let u16_at = |i: usize| u16::from_le_bytes([body[i], body[i + 1]]);
+                let mut tag = u16_at(0);
+                if tag == FORMAT_EXTENSIBLE && body.len() >= 26 {
+                    tag = u16_at(24); // first two bytes of the sub format GUID
+                }
+                let channels = u16_at(2);
+                let sample_rate = u32::from_le_bytes(body[4..8].try_into().unwrap());
+                format = Some((tag, channels, sample_rate, u16_at(14)));
+            }
+            b"data" => {
+                let (tag, channels, sample_rate, bits) =
+                    format.ok_or_else(|| invalid("data before fmt"))?;
+                if channels == 0 || sample_rate == 0 {
+                    return Err(invalid("no channels"));
+                }
+                let samples = match (tag, bits) {
+                    (FORMAT_PCM, 8) => body.iter().map(|&s| (s as i16 - 128) << 8).collect(),
+                    (FORMAT_PCM, 16) => body
+                        .chunks_exact(2)
+                        .map(|s| i16::from_le_bytes([s[0], s[1]]))
+                        .collect(),
+                    (FORMAT_PCM, 24) => body
+                        .chunks_exact(3)
+                        .map(|s| i16::from_le_bytes([s[1], s[2]]))
+                        .collect(),
+                    (FORMAT_PCM, 32) => body
+                        .chunks_exact(4)
+                        .map(|s| i16::from_le_bytes([s[2], s[3]]))
+                        .collect(),
+                    (FORMAT_FLOAT, 32) => body
+                        .chunks_exact(4)
+                        .map(|s| {
+                            let v = f32::from_le_bytes(s.try_into().unwrap());
+                            (v.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+                        })
+                        .collect(),
+                    _ => return Err(invalid(&format!("format {tag} with {bits} bits"))),
+                };
+                return Ok(Wav {
+                    channels,
+                    sample_rate,
+                    samples,
+                });
+            }
+            _ => {}
+        }
+        // chunks are padded to an even size, a size past the end (or past
+        // usize on 32 bit targets) ends the search
+        let next = len
+            .checked_add(len & 1)
+            .and_then(|size| size.checked_add(8))
+            .and_then(|size| pos.checked_add(size));
+        match next {
+            Some(next) if next <= data.len() => pos = next,
+            _ => break,
+        }
+    }
+    Err(invalid("no data chunk"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(tag: u16, channels: u16, bits: u16, data: &[u8]) -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&tag.to_le_bytes());
+        fmt.extend_from_slice(&channels.to_le_bytes());
+        fmt.extend_from_slice(&44100u32.to_le_bytes());
+        fmt.extend_from_slice(&(44100 * channels as u32 * bits as u32 / 8).to_le_bytes());
+        fmt.extend_from_slice(&(channels * bits / 8).to_le_bytes());
+        fmt.extend_from_slice(&bits.to_le_bytes());
+
+        let mut file = b"RIFF\0\0\0\0WAVE".to_vec();
+        file.extend_from_slice(b"LIST\x03\0\0\0abc\0"); // odd sized, padded
+        file.extend_from_slice(b"fmt \x10\0\0\0");
+        file.extend_from_slice(&fmt);
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(data);
+        file
+    }
+
+    #[test]
+    fn test_decode() {
+        let data = [0x00, 0x80, 0xff, 0x7f, 0x34, 0x12, 0x00, 0x00];
+        let decoded = decode(&wav(FORMAT_PCM, 2, 16, &data)).unwrap();
+        assert_eq!(decoded.channels, 2);
+        assert_eq!(decoded.sample_rate, 44100);
+        assert_eq!(decoded.samples, [i16::MIN, i16::MAX, 0x1234, 0]);
+        assert_eq!(decoded.frames(), 2);
+
+        let decoded = decode(&wav(FORMAT_PCM, 1, 8, &[0, 128, 255])).unwrap();
+        assert_eq!(decoded.samples, [-32768, 0, 32512]);
+
+        let data = [0xff, 0x34, 0x12];
+        assert_eq!(
+            decode(&wav(FORMAT_PCM, 1, 24, &data)).unwrap().samples,
+            [0x1234]
+        );
+
+        let data = [-1.0f32, 0.5].map(f32::to_le_bytes).concat();
+        let decoded = decode(&wav(FORMAT_FLOAT, 1, 32, &data)).unwrap();
+        assert_eq!(decoded.samples, [-32767, 16383]);
+
+        assert!(decode(&wav(2, 1, 4, &[0, 0])).is_err()); // ADPCM
+
+        // a chunk claiming 4 GB hides the data
+        let mut huge = wav(FORMAT_PCM, 1, 8, &[0, 128, 255]);
+        huge.splice(12..12, *b"junk\xff\xff\xff\xff");
+        assert!(decode(&huge).is_err());
+        assert!(decode(b"RIFF\0\0\0\0WAVE").is_err());
+        assert!(decode(b"not a wav file").is_err());
+    }
+}
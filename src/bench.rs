@@ -0,0 +1,230 @@
+use crate::app::AppConfig;
+use crate::core::gl_canvas::Canvas;
+use crate::core::gl_renderer::Renderer;
+use crate::error::{Error, Result};
+use crate::gl::opengl::OpenGlFunctions;
+use crate::library;
+use crate::scene::layouter::Layouter;
+use crate::scene::photo;
+use crate::scene::{Element, Layout, LayoutId, LayoutItem, Picture};
+use crate::v2d::rect::Rect;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Size of the off-screen frame, a typical TV
+const BENCH_SIZE: (usize, usize) = (1920, 1080);
+
+// Frames rendered per photo, the render stage is much shorter than the others
+const RENDER_FRAMES: usize = 10;
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Decode,  // WebP file to YCbCr 4:2:0 planes
+    Convert, // to interleaved YCbCr, downscaled to the maximum texture size
+    Upload,  // texture creation, until the GPU finished
+    Render,  // one frame showing the photo
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Decode, Stage::Convert, Stage::Upload, Stage::Render];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Decode => "decode",
+            Stage::Convert => "convert",
+            Stage::Upload => "upload",
+            Stage::Render => "render",
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Timings of every stage, printed as a table with percentiles in ms
+#[derive(Clone, Debug, Default)]
+pub struct BenchReport {
+    pub photos: usize,
+    pub failed: usize,
+    samples: [Vec<Duration>; 4],
+}
+
+impl BenchReport {
+    pub fn add(&mut self, stage: Stage, t: Duration) {
+        self.samples[stage as usize].push(t);
+    }
+
+    pub fn samples(&self, stage: Stage) -> &[Duration] {
+        &self.samples[stage as usize]
+    }
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{} photos, {} failed", self.photos, self.failed)?;
+        writeln!(
+            f,
+            "{:<8} {:>6} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            "stage", "count", "mean", "p50", "p90", "p99", "max"
+        )?;
+        for stage in Stage::ALL {
+            let mut samples = self.samples(stage).to_vec();
+            samples.sort();
+            let ms = |t: Duration| t.as_secs_f64() * 1000.0;
+            let total: Duration = samples.iter().sum();
+            let mean = total.checked_div(samples.len() as u32).unwrap_or_default();
+            writeln!(
+                f,
+                "{:<8} {:>6} {:>8.2} {:>8.2} {:>8.2} {:>8.2} {:>8.2}",
+                stage.name(),
+                samples.len(),
+                ms(mean),
+                ms(percentile(&samples, 50.0)),
+                ms(percentile(&samples, 90.0)),
+                ms(percentile(&samples, 99.0)),
+                ms(samples.last().copied().unwrap_or_default()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Nearest-rank percentile of sorted samples, zero if there are none
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// ----------------------------------------------------------------------------
+// Runs every photo of the configured directories through the pipeline in an
+// off-screen context
+#[cfg(target_os = "linux")]
+pub fn run(config: &AppConfig) -> Result<BenchReport> {
+    use crate::gl::linux::egl::EglContext;
+
+    let (cx, cy) = BENCH_SIZE;
+    let context = EglContext::headless(cx, cy)?;
+    bench(config, context.load()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn run(_config: &AppConfig) -> Result<BenchReport> {
+    Err(Error::InvalidArgument {
+        arg: String::from("--bench is only supported on Linux"),
+    })
+}
+
+// ----------------------------------------------------------------------------
+fn bench(config: &AppConfig, gl: OpenGlFunctions) -> Result<BenchReport> {
+    let (cx, cy) = BENCH_SIZE;
+    let gl = Rc::new(gl);
+    let canvas = Canvas::new(Rc::clone(&gl), cx as f32 / cy as f32)?;
+    let mut layouter = Layouter::new(canvas, &config.font)?;
+    layouter.set_sharpen(config.sharpen);
+    layouter.set_letterbox(config.letterbox);
+    layouter.set_px_range(config.text_px_range);
+    let renderer = Renderer::new(Rc::clone(&gl), cx, cy)?;
+    renderer.resize(cx as i32, cy as i32);
+
+    let mut report = BenchReport::default();
+    for dir in &config.photo_dirs {
+        for path in library::list_files(dir)?
+            .iter()
+            .filter(|p| photo::is_webp_file(p))
+        {
+            report.photos += 1;
+            if let Err(e) = bench_photo(&gl, &mut layouter, &renderer, path, &mut report) {
+                log::warn!("Bench: {path:?} failed: {e:?}");
+                report.failed += 1;
+            }
+        }
+    }
+    if report.photos == 0 {
+        return Err(Error::EmptyPhotos);
+    }
+    Ok(report)
+}
+
+// ----------------------------------------------------------------------------
+fn bench_photo(
+    gl: &OpenGlFunctions,
+    layouter: &mut Layouter,
+    renderer: &Renderer,
+    path: &Path,
+    report: &mut BenchReport,
+) -> Result<()> {
+    let t = Instant::now();
+    let frame = photo::decode_webp(path)?;
+    report.add(Stage::Decode, t.elapsed());
+
+    let t = Instant::now();
+    let (cx, cy, yuv24) = photo::frame_to_ycbcr24(&frame);
+    let (cx, cy, yuv24) = layouter.prepare_photo(cx, cy, yuv24);
+    report.add(Stage::Convert, t.elapsed());
+
+    // the driver may copy the texture lazily, waiting for the GPU includes it
+    let t = Instant::now();
+    let handle = layouter.upload_photo(cx, cy, &yuv24)?;
+    unsafe { gl.Finish() };
+    report.add(Stage::Upload, t.elapsed());
+
+    let picture = Picture {
+        dst: Rect::unit().fit(handle.aspect_ratio / layouter.aspect_ratio()),
+        src: Rect::unit(),
+        opacity: 1.0,
+        handle,
+    };
+    layouter.update_layout(&Layout {
+        items: vec![LayoutItem {
+            id: LayoutId(0),
+            element: Element::Picture(picture),
+            animation_time: None,
+        }],
+    });
+    let result = (0..RENDER_FRAMES).try_for_each(|_| {
+        let t = Instant::now();
+        renderer.render(layouter.canvas())?;
+        unsafe { gl.Finish() };
+        report.add(Stage::Render, t.elapsed());
+        Ok(())
+    });
+
+    layouter.update_layout(&Layout::empty());
+    layouter.free_handle(handle);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let ms = Duration::from_millis;
+        let sorted: Vec<_> = (1..=10).map(ms).collect();
+        assert_eq!(percentile(&sorted, 50.0), ms(5));
+        assert_eq!(percentile(&sorted, 90.0), ms(9));
+        assert_eq!(percentile(&sorted, 99.0), ms(10));
+        assert_eq!(percentile(&sorted, 0.0), ms(1));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+
+        let mut report = BenchReport {
+            photos: 2,
+            ..Default::default()
+        };
+        report.add(Stage::Decode, ms(30));
+        report.add(Stage::Decode, ms(10));
+        let text = report.to_string();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], "2 photos, 0 failed");
+        assert_eq!(
+            lines[2],
+            "decode        2    20.00    10.00    30.00    30.00    30.00"
+        );
+        assert!(lines[3].starts_with("convert       0     0.00"));
+    }
+}
@@ -0,0 +1,859 @@
+use crate::app::{AppConfig, GeocodingConfig, NightHours, WeatherConfig};
+use crate::audio::MusicConfig;
+use crate::audio::tts::TtsConfig;
+use crate::core::app_loop::{CatchUp, LoopRate};
+use crate::error::{Error, Result, ResultExt};
+use crate::gfx::animation::Easing;
+use crate::gfx::transition::TransitionKind;
+use crate::headless;
+use crate::ipc::Bus;
+use crate::net::presence::PresenceConfig;
+use crate::scene::SceneKind;
+use crate::scene::overlay::{ClockOverlayStyle, Corner};
+use crate::scene::photo::{Letterbox, MAX_RATING};
+use crate::scene::rotation::{Rotation, RotationEntry};
+use crate::scene::schedule::{Schedule, ScheduleRule};
+use crate::scene::slideshow::PhotoFilter;
+use crate::util::ambient_light::AmbientLightConfig;
+use crate::util::datetime::Time;
+use crate::util::locale::{LocaleId, TimePattern, Units};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// ----------------------------------------------------------------------------
+// Settings read from a TOML or JSON file given with `--config`. Every value
+// is optional, missing ones keep the defaults, e.g.:
+//
+//   photo_dirs = ["/media/photos", "/media/scans"]
+//   slide_duration = 8.0
+//   locale = "de-DE"
+//
+//   [night]
+//   start = "22:30"
+//   end = "06:30"
+//
+//   [keymap]
+//   Q = "exit"
+//
+//   [[schedule]]
+//   days = "mon-fri"
+//   start = "06:30"
+//   end = "08:00"
+//   scene = "weather"
+//   say = "Good morning"   # spoken when the entry starts, see [tts]
+//
+//   [[rotation]]
+//   scene = "all"
+//   dwell = 600            # seconds until the next entry, stays if missing
+//
+// Frames sharing one file pick a `[profiles.<name>]` section with
+// `--profile <name>`, its values override the ones above:
+//
+//   [profiles.kitchen]
+//   photo_dirs = ["/media/photos/food"]
+//   start_scene = "today"
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub name: Option<String>,
+    pub photo_dirs: Vec<PathBuf>,
+    pub start_scene: Option<String>,
+    pub slide_duration: Option<f32>,
+    pub transition_duration: Option<f32>,
+    pub transition_easing: Option<String>, // e.g. "linear" or "cubic-in-out"
+    pub transition_effect: Option<String>, // e.g. "wipe-left" or "random"
+    pub slide_jitter: Option<f32>,         // e.g. 0.2 to show photos ±20% as long
+    pub ken_burns: Option<bool>,           // slow pan and zoom over each photo
+    pub letterbox: Option<String>,         // "plain", "blur" or "color"
+    pub photo_filter: Option<PhotoFilter>, // e.g. min_rating = 3
+    pub locale: Option<String>,
+    pub time_format: Option<String>,
+    pub units: Option<String>,
+    pub night: Option<NightConfig>,
+    pub schedule: Vec<ScheduleConfig>,
+    pub rotation: Vec<RotationConfig>,
+    pub weather: Option<WeatherConfig>,
+    pub geocoding: Option<GeocodingConfig>,
+    pub ambient_light: Option<AmbientLightConfig>,
+    pub music: Option<MusicConfig>,
+    pub tts: Option<TtsConfig>,
+    pub presence: Option<PresenceConfig>,
+    pub keymap: BTreeMap<String, String>, // key name => action
+    pub update_ms: Option<u64>,
+    pub max_fps: Option<f32>,
+    pub idle: Option<IdleConfig>, // rates while nothing moves
+    pub catch_up: Option<CatchUpConfig>,
+    pub fullscreen: Option<bool>,
+    pub monitor: Option<usize>, // 0 is the primary monitor
+    pub size: Option<String>,   // of the window, e.g. "1280x800"
+    pub listen: Option<SocketAddr>,
+    pub api_token: Option<String>, // needed to change settings over HTTP
+    pub advertise: Option<bool>,
+    pub dbus: Option<String>, // "session" or "system"
+    pub control_socket: Option<PathBuf>,
+    pub debug_overlay: Option<bool>,
+    pub clock_overlay: Option<ClockOverlayConfig>,
+    pub crash_dir: Option<PathBuf>,
+    pub font: Option<PathBuf>,                 // .png/.json atlas or .ttf
+    pub scenes: BTreeMap<String, toml::Table>, // plugin name => its settings
+    pub profiles: BTreeMap<String, ConfigFile>,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NightConfig {
+    pub start: String,
+    pub end: String,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleConfig {
+    #[serde(default)]
+    pub days: String, // e.g. "mon-fri" or "sat,sun", every day if empty
+    pub start: String,
+    pub end: String,
+    pub scene: String,
+    pub say: Option<String>,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RotationConfig {
+    pub scene: String,
+    pub dwell: Option<f32>, // seconds
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClockOverlayConfig {
+    pub position: Option<String>, // e.g. "top-left", "bottom-right" by default
+    pub opacity: Option<f32>,
+    pub time_format: Option<String>, // "12h" or "24h", the one of the frame if None
+    pub date: Option<bool>,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdleConfig {
+    pub update_ms: Option<u64>,
+    pub max_fps: Option<f32>,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CatchUpConfig {
+    pub max_updates: Option<u32>,
+    pub drop_lag_after_ms: Option<u64>, // 0 never drops the lag
+}
+
+// ----------------------------------------------------------------------------
+// Files ending in .json are read as JSON, everything else as TOML
+pub fn load(path: &Path) -> Result<ConfigFile> {
+    log::info!("Reading config: {path:?}");
+    let text = std::fs::read_to_string(path).with_path("reading", path)?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        Ok(serde_json::from_str(&text)?)
+    } else {
+        toml::from_str(&text).map_err(|err| toml_error(&text, err))
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn toml_error(text: &str, err: toml::de::Error) -> Error {
+    let (line, column) = err.span().map_or((0, 0), |span| {
+        let before = &text[..span.start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            span.start - line_start + 1,
+        )
+    });
+    Error::Serde {
+        line,
+        column,
+        msg: err.message().to_string(),
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl ConfigFile {
+    // Applies the top level values, then the ones of the profile
+    pub fn apply_profile(mut self, profile: Option<&str>, config: &mut AppConfig) -> Result<()> {
+        let overlay = match profile {
+            Some(name) => Some(self.profiles.remove(name).ok_or(Error::InvalidArgument {
+                arg: format!("profile {name}"),
+            })?),
+            None => None,
+        };
+        self.apply(config)?;
+        if let Some(overlay) = overlay {
+            if !overlay.profiles.is_empty() {
+                return Err(Error::InvalidArgument {
+                    arg: String::from("profiles can't be nested"),
+                });
+            }
+            overlay.apply(config)?;
+        }
+        Ok(())
+    }
+
+    // Profiles are ignored
+    pub fn apply(self, config: &mut AppConfig) -> Result<()> {
+        if let Some(name) = self.name {
+            config.name = name;
+        }
+        if !self.photo_dirs.is_empty() {
+            config.photo_dirs = self.photo_dirs;
+        }
+        if let Some(name) = self.start_scene {
+            config.start_scene =
+                SceneKind::from_name(&name).ok_or(Error::InvalidArgument { arg: name })?;
+        }
+        if let Some(seconds) = self.slide_duration {
+            config.slide_duration = positive(seconds, "slide_duration")?;
+        }
+        if let Some(seconds) = self.transition_duration {
+            config.transition_duration = positive(seconds, "transition_duration")?;
+        }
+        if let Some(name) = self.transition_easing {
+            config.transition_easing =
+                Easing::from_name(&name).ok_or(Error::InvalidArgument { arg: name })?;
+        }
+        if let Some(name) = self.transition_effect {
+            config.transition_effect = match name.as_str() {
+                "random" => None,
+                _ => Some(
+                    TransitionKind::from_name(&name).ok_or(Error::InvalidArgument { arg: name })?,
+                ),
+            };
+        }
+        if let Some(jitter) = self.slide_jitter {
+            config.slide_jitter = fraction(jitter, "slide_jitter")?;
+        }
+        if let Some(ken_burns) = self.ken_burns {
+            config.ken_burns = ken_burns;
+        }
+        if let Some(name) = self.letterbox {
+            config.letterbox =
+                Letterbox::from_name(&name).ok_or(Error::InvalidArgument { arg: name })?;
+        }
+        if let Some(filter) = self.photo_filter {
+            if filter.min_rating.is_some_and(|rating| rating > MAX_RATING) {
+                return Err(Error::InvalidArgument {
+                    arg: String::from("photo_filter.min_rating"),
+                });
+            }
+            config.photo_filter = filter;
+        }
+        if let Some(name) = self.locale {
+            config.locale =
+                LocaleId::from_name(&name).ok_or(Error::InvalidArgument { arg: name })?;
+        }
+        if let Some(name) = self.time_format {
+            config.time_format =
+                Some(TimePattern::from_name(&name).ok_or(Error::InvalidArgument { arg: name })?);
+        }
+        if let Some(name) = self.units {
+            config.units = Units::from_name(&name).ok_or(Error::InvalidArgument { arg: name })?;
+        }
+        if let Some(night) = self.night {
+            config.night = Some(NightHours {
+                start: Time::from_hms_str(&night.start)?,
+                end: Time::from_hms_str(&night.end)?,
+            });
+        }
+        if !self.schedule.is_empty() {
+            let rules = self.schedule.iter().map(|rule| {
+                let parsed = ScheduleRule::parse(&rule.days, &rule.start, &rule.end, &rule.scene);
+                parsed.map(|parsed| ScheduleRule {
+                    say: rule.say.clone(),
+                    ..parsed
+                })
+            });
+            config.schedule = Schedule {
+                rules: rules.collect::<Result<_>>()?,
+            };
+        }
+        if !self.rotation.is_empty() {
+            let entries = self.rotation.iter().map(|entry| {
+                let dwell = entry.dwell.map(|seconds| positive(seconds, "dwell"));
+                RotationEntry::parse(&entry.scene, dwell.transpose()?)
+            });
+            config.rotation = Rotation {
+                entries: entries.collect::<Result<_>>()?,
+            };
+        }
+        if let Some(weather) = self.weather {
+            config.weather = weather;
+        }
+        if let Some(geocoding) = self.geocoding {
+            config.geocoding = geocoding;
+        }
+        if let Some(ambient_light) = self.ambient_light {
+            config.ambient_light = ambient_light;
+        }
+        if let Some(music) = self.music {
+            config.music = music;
+        }
+        if let Some(tts) = self.tts {
+            config.tts = tts;
+        }
+        if let Some(presence) = self.presence {
+            config.presence = presence;
+        }
+        for (name, action) in self.keymap {
+            // "none" removes a default binding
+            if action == "none" {
+                config.keymap.unbind(&name);
+            } else {
+                config.keymap.bind(&name, action.parse()?);
+            }
+        }
+        apply_rate(&mut config.rates.active, self.update_ms, self.max_fps, "")?;
+        if let Some(idle) = self.idle {
+            apply_rate(
+                &mut config.rates.idle,
+                idle.update_ms,
+                idle.max_fps,
+                "idle.",
+            )?;
+        }
+        if let Some(catch_up) = self.catch_up {
+            apply_catch_up(&mut config.catch_up, &catch_up)?;
+        }
+        if let Some(fullscreen) = self.fullscreen {
+            config.display.fullscreen = fullscreen;
+        }
+        if let Some(monitor) = self.monitor {
+            config.display.monitor = Some(monitor);
+        }
+        if let Some(size) = self.size {
+            config.display.size = parse_window_size(&size).ok_or(Error::InvalidArgument {
+                arg: format!("size: {size}"),
+            })?;
+        }
+        if let Some(addr) = self.listen {
+            config.listen = Some(addr);
+        }
+        if let Some(token) = self.api_token {
+            config.api_token = Some(token);
+        }
+        if let Some(advertise) = self.advertise {
+            config.advertise = advertise;
+        }
+        if let Some(name) = self.dbus {
+            config.dbus = Some(Bus::from_name(&name).ok_or(Error::InvalidArgument { arg: name })?);
+        }
+        if let Some(path) = self.control_socket {
+            config.control_socket = Some(path);
+        }
+        if let Some(debug_overlay) = self.debug_overlay {
+            config.debug_overlay = debug_overlay;
+        }
+        if let Some(clock_overlay) = self.clock_overlay {
+            config.clock_overlay = Some(apply_clock_overlay(&clock_overlay)?);
+        }
+        if let Some(dir) = self.crash_dir {
+            config.crash_dir = dir;
+        }
+        if let Some(font) = self.font {
+            config.font = font;
+        }
+        config.scene_config.extend(self.scenes);
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The part of the config that can be changed on the settings page of the
+// HTTP server
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub photo_dirs: Vec<PathBuf>,
+    pub slide_duration: f32,
+    pub transition_duration: f32,
+    #[serde(default)]
+    pub slide_jitter: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub night: Option<NightConfig>, // None turns night hours off
+}
+
+impl Settings {
+    // ------------------------------------------------------------------------
+    pub fn from_config(config: &AppConfig) -> Self {
+        let hm = |time: Time| {
+            let (hour, minute, _) = time.to_hms();
+            format!("{hour:02}:{minute:02}")
+        };
+        Self {
+            photo_dirs: config.photo_dirs.clone(),
+            slide_duration: config.slide_duration,
+            transition_duration: config.transition_duration,
+            slide_jitter: config.slide_jitter,
+            night: config.night.map(|night| NightConfig {
+                start: hm(night.start),
+                end: hm(night.end),
+            }),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Photo directories must exist, the other values are checked like the
+    // ones of the config file
+    pub fn apply(&self, config: &mut AppConfig) -> Result<()> {
+        if self.photo_dirs.is_empty() {
+            return Err(Error::InvalidArgument {
+                arg: String::from("photo_dirs"),
+            });
+        }
+        if let Some(dir) = self.photo_dirs.iter().find(|dir| !dir.is_dir()) {
+            return Err(Error::FileNotFound { path: dir.clone() });
+        }
+
+        let file = ConfigFile {
+            photo_dirs: self.photo_dirs.clone(),
+            slide_duration: Some(self.slide_duration),
+            transition_duration: Some(self.transition_duration),
+            slide_jitter: Some(self.slide_jitter),
+            night: self.night.clone(),
+            ..ConfigFile::default()
+        };
+        file.apply(config)?;
+        if self.night.is_none() {
+            config.night = None;
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Updates these values in the config file at `path`, or in its profile
+    // section, and keeps all others. Comments of a TOML file are lost.
+    pub fn save(&self, path: &Path, profile: Option<&str>) -> Result<()> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(Error::from(e).context(format!("reading {}", path.display()))),
+        };
+
+        let text = if path.extension().is_some_and(|ext| ext == "json") {
+            let mut root: serde_json::Map<String, serde_json::Value> = if text.trim().is_empty() {
+                serde_json::Map::new()
+            } else {
+                serde_json::from_str(&text)?
+            };
+            let serde_json::Value::Object(values) = serde_json::to_value(self)? else {
+                return Err(invalid_settings());
+            };
+            let table = match profile {
+                Some(name) => json_profile(&mut root, name)?,
+                None => &mut root,
+            };
+            table.remove("night");
+            table.extend(values);
+            serde_json::to_string_pretty(&root)? + "\n"
+        } else {
+            let mut root: toml::Table = toml::from_str(&text).map_err(|e| toml_error(&text, e))?;
+            let values = toml::Table::try_from(self).map_err(toml_ser_error)?;
+            let table = match profile {
+                Some(name) => toml_profile(&mut root, name)?,
+                None => &mut root,
+            };
+            table.remove("night");
+            table.extend(values);
+            toml::to_string(&root).map_err(toml_ser_error)?
+        };
+
+        // written next to the file first so a failed write keeps the old one
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, text).with_path("writing", &tmp)?;
+        std::fs::rename(&tmp, path).with_path("replacing", path)?;
+        log::info!("Saved settings to {path:?}");
+        Ok(())
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The `profiles.<name>` table, created if missing
+fn toml_profile<'a>(root: &'a mut toml::Table, name: &str) -> Result<&'a mut toml::Table> {
+    let new_table = || toml::Value::Table(toml::Table::new());
+    let profiles = root.entry("profiles").or_insert_with(new_table);
+    let profiles = profiles.as_table_mut().ok_or_else(invalid_settings)?;
+    let profile = profiles.entry(name).or_insert_with(new_table);
+    profile.as_table_mut().ok_or_else(invalid_settings)
+}
+
+fn json_profile<'a>(
+    root: &'a mut serde_json::Map<String, serde_json::Value>,
+    name: &str,
+) -> Result<&'a mut serde_json::Map<String, serde_json::Value>> {
+    let new_object = || serde_json::Value::Object(serde_json::Map::new());
+    let profiles = root.entry("profiles").or_insert_with(new_object);
+    let profiles = profiles.as_object_mut().ok_or_else(invalid_settings)?;
+    let profile = profiles.entry(name).or_insert_with(new_object);
+    profile.as_object_mut().ok_or_else(invalid_settings)
+}
+
+fn invalid_settings() -> Error {
+    Error::InvalidArgument {
+        arg: String::from("settings"),
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn toml_ser_error(err: toml::ser::Error) -> Error {
+    Error::Serde {
+        line: 0,
+        column: 0,
+        msg: err.to_string(),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// `prefix` names the table in errors, e.g. "idle."
+fn apply_rate(
+    rate: &mut LoopRate,
+    update_ms: Option<u64>,
+    max_fps: Option<f32>,
+    prefix: &str,
+) -> Result<()> {
+    if let Some(ms) = update_ms {
+        if ms == 0 {
+            return Err(Error::InvalidArgument {
+                arg: format!("{prefix}update_ms"),
+            });
+        }
+        rate.update_interval = Duration::from_millis(ms);
+    }
+    if let Some(fps) = max_fps {
+        rate.max_fps = Some(positive(fps, &format!("{prefix}max_fps"))?);
+    }
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+fn apply_catch_up(catch_up: &mut CatchUp, file: &CatchUpConfig) -> Result<()> {
+    if let Some(max_updates) = file.max_updates {
+        if max_updates == 0 {
+            return Err(Error::InvalidArgument {
+                arg: String::from("catch_up.max_updates"),
+            });
+        }
+        catch_up.max_updates = max_updates;
+    }
+    if let Some(ms) = file.drop_lag_after_ms {
+        catch_up.drop_lag_after = (ms > 0).then(|| Duration::from_millis(ms));
+    }
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+fn apply_clock_overlay(file: &ClockOverlayConfig) -> Result<ClockOverlayStyle> {
+    let mut style = ClockOverlayStyle::default();
+    if let Some(name) = &file.position {
+        style.position = Corner::from_name(name).ok_or(Error::InvalidArgument {
+            arg: format!("clock_overlay.position: {name}"),
+        })?;
+    }
+    if let Some(opacity) = file.opacity {
+        if !(0.0..=1.0).contains(&opacity) {
+            return Err(Error::InvalidArgument {
+                arg: String::from("clock_overlay.opacity"),
+            });
+        }
+        style.opacity = opacity;
+    }
+    if let Some(name) = &file.time_format {
+        style.time_format = Some(TimePattern::from_name(name).ok_or(Error::InvalidArgument {
+            arg: format!("clock_overlay.time_format: {name}"),
+        })?);
+    }
+    if let Some(date) = file.date {
+        style.date = date;
+    }
+    Ok(style)
+}
+
+// ----------------------------------------------------------------------------
+// Below 1 so that durations varied by it stay positive
+fn fraction(value: f32, name: &str) -> Result<f32> {
+    match (0.0..1.0).contains(&value) {
+        true => Ok(value),
+        false => Err(Error::InvalidArgument {
+            arg: name.to_string(),
+        }),
+    }
+}
+
+fn positive(value: f32, name: &str) -> Result<f32> {
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(Error::InvalidArgument {
+            arg: name.to_string(),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// "WxH" of `size` and `--size`, written like the `--headless` size
+pub fn parse_window_size(size: &str) -> Option<(u32, u32)> {
+    let (cx, cy) = headless::parse_size(size)?;
+    Some((cx.try_into().ok()?, cy.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::Key;
+
+    #[test]
+    fn test_toml_config() {
+        let text = r#"
+            photo_dirs = ["/media/photos", "/media/scans"]
+            slide_duration = 8.0
+            slide_jitter = 0.2
+            ken_burns = true
+            letterbox = "blur"
+            transition_easing = "cubic-in-out"
+            transition_effect = "random"
+            locale = "de-DE"
+            start_scene = "today"
+            fullscreen = true
+            monitor = 1
+            size = "1280x800"
+            listen = "0.0.0.0:8080"
+            api_token = "secret"
+            max_fps = 30
+
+            [idle]
+            update_ms = 250
+
+            [catch_up]
+            drop_lag_after_ms = 0
+
+            [photo_filter]
+            min_rating = 3
+            tags = ["family"]
+
+            [clock_overlay]
+            position = "top-left"
+            time_format = "12h"
+
+            [night]
+            start = "22:30"
+            end = "06:30"
+
+            [weather]
+            api_key = "secret"
+
+            [keymap]
+            Q = "exit"
+            Escape = "none"
+
+            [[schedule]]
+            days = "mon-fri"
+            start = "06:30"
+            end = "08:00"
+            scene = "today"
+            say = "Good morning"
+
+            [[rotation]]
+            scene = "all"
+            dwell = 600
+
+            [[rotation]]
+            scene = "clock"
+        "#;
+        let file: ConfigFile = toml::from_str(text).unwrap();
+        let mut config = AppConfig::default();
+        file.apply(&mut config).unwrap();
+
+        assert_eq!(config.photo_dirs.len(), 2);
+        assert_eq!(config.slide_duration, 8.0);
+        assert_eq!(config.transition_duration, 0.4);
+        assert_eq!(config.slide_jitter, 0.2);
+        assert!(config.ken_burns);
+        assert_eq!(config.letterbox, Letterbox::Blur);
+        assert_eq!(config.photo_filter.min_rating, Some(3));
+        assert_eq!(config.photo_filter.tags, ["family"]);
+        assert!(config.photo_filter.exclude_tags.is_empty());
+        assert_eq!(config.transition_easing, Easing::CubicInOut);
+        assert_eq!(config.transition_effect, None);
+        assert_eq!(config.locale, LocaleId::German);
+        assert_eq!(config.start_scene, SceneKind::Today);
+        assert!(config.display.fullscreen);
+        assert_eq!(config.display.monitor, Some(1));
+        assert_eq!(config.display.size, (1280, 800));
+        assert_eq!(config.listen, Some("0.0.0.0:8080".parse().unwrap()));
+        assert_eq!(config.api_token.as_deref(), Some("secret"));
+        assert_eq!(config.rates.active.max_fps, Some(30.0));
+        assert_eq!(
+            config.rates.idle.update_interval,
+            Duration::from_millis(250)
+        );
+        assert_eq!(config.rates.idle.max_fps, None);
+        assert_eq!(config.catch_up.max_updates, 4);
+        assert_eq!(config.catch_up.drop_lag_after, None);
+        let clock_overlay = config.clock_overlay.unwrap();
+        assert_eq!(clock_overlay.position, Corner::TopLeft);
+        assert_eq!(clock_overlay.time_format, Some(TimePattern::HmsColon12));
+        assert_eq!(clock_overlay.opacity, 0.8);
+        assert!(clock_overlay.date);
+        assert_eq!(config.weather.api_key.as_deref(), Some("secret"));
+        assert_eq!(config.keymap.key("q"), Some(Key::Exit));
+        assert_eq!(config.keymap.key("Escape"), None);
+        assert_eq!(config.schedule.rules.len(), 1);
+        assert_eq!(config.schedule.rules[0].scene, SceneKind::Today);
+        assert_eq!(
+            config.schedule.rules[0].say.as_deref(),
+            Some("Good morning")
+        );
+        assert_eq!(config.rotation.entries.len(), 2);
+        assert_eq!(config.rotation.entries[0].dwell, Some(600.0));
+        assert_eq!(config.rotation.entries[1].scene, SceneKind::Clock);
+        assert_eq!(config.rotation.entries[1].dwell, None);
+
+        let night = config.night.unwrap();
+        assert!(night.contains(Time::from_hms(23, 0, 0).unwrap()));
+        assert!(night.contains(Time::from_hms(6, 0, 0).unwrap()));
+        assert!(!night.contains(Time::from_hms(12, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_json_config() {
+        let file: ConfigFile =
+            serde_json::from_str(r#"{ "units": "imperial", "time_format": "24h" }"#).unwrap();
+        let mut config = AppConfig::default();
+        file.apply(&mut config).unwrap();
+        assert_eq!(config.units, Units::imperial());
+        assert_eq!(config.time_format, Some(TimePattern::HmsColon24));
+    }
+
+    #[test]
+    fn test_save_settings() {
+        let dir = std::env::temp_dir().join(format!("home-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("frame.json");
+        std::fs::write(
+            &path,
+            r#"{ "locale": "de-DE", "night": { "start": "22:00", "end": "06:00" } }"#,
+        )
+        .unwrap();
+
+        let mut config = AppConfig {
+            photo_dirs: vec![dir.clone()],
+            ..AppConfig::default()
+        };
+        load(&path).unwrap().apply(&mut config).unwrap();
+        let mut settings = Settings::from_config(&config);
+        assert_eq!(settings.night.as_ref().unwrap().end, "06:00");
+
+        // night hours are removed when turned off
+        settings.night = None;
+        settings.save(&path, None).unwrap();
+        let mut config = AppConfig::default();
+        load(&path).unwrap().apply(&mut config).unwrap();
+        assert_eq!(config.locale, LocaleId::German);
+        assert_eq!(config.photo_dirs, vec![dir.clone()]);
+        assert_eq!(config.night, None);
+
+        settings.photo_dirs = vec![dir.join("missing")];
+        assert!(settings.apply(&mut config).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let text = r#"
+            photo_dirs = ["/media/photos"]
+            slide_duration = 8.0
+            locale = "de-DE"
+
+            [keymap]
+            Q = "exit"
+
+            [profiles.kitchen]
+            photo_dirs = ["/media/food"]
+            start_scene = "today"
+
+            [profiles.kitchen.keymap]
+            Q = "none"
+        "#;
+        let mut config = AppConfig::default();
+        let file: ConfigFile = toml::from_str(text).unwrap();
+        file.apply_profile(Some("kitchen"), &mut config).unwrap();
+        assert_eq!(config.photo_dirs, vec![PathBuf::from("/media/food")]);
+        assert_eq!(config.start_scene, SceneKind::Today);
+        assert_eq!(config.slide_duration, 8.0);
+        assert_eq!(config.locale, LocaleId::German);
+        assert_eq!(config.keymap.key("q"), None);
+
+        let mut config = AppConfig::default();
+        let file: ConfigFile = toml::from_str(text).unwrap();
+        file.apply_profile(None, &mut config).unwrap();
+        assert_eq!(config.photo_dirs, vec![PathBuf::from("/media/photos")]);
+        assert_eq!(config.keymap.key("q"), Some(Key::Exit));
+
+        let file: ConfigFile = toml::from_str(text).unwrap();
+        assert!(file.apply_profile(Some("garage"), &mut config).is_err());
+
+        // settings of a profile are saved into its section
+        let dir = std::env::temp_dir().join(format!("home-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("frames.toml");
+        std::fs::write(&path, text).unwrap();
+        let mut settings = Settings::from_config(&config);
+        settings.slide_duration = 3.0;
+        settings.save(&path, Some("kitchen")).unwrap();
+        let file = load(&path).unwrap();
+        assert_eq!(file.slide_duration, Some(8.0));
+        assert_eq!(file.profiles["kitchen"].slide_duration, Some(3.0));
+        assert_eq!(
+            file.profiles["kitchen"].start_scene.as_deref(),
+            Some("today")
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let err = toml::from_str::<ConfigFile>("slide_duration = 1\nfoo = 2")
+            .map_err(|err| toml_error("slide_duration = 1\nfoo = 2", err));
+        assert!(matches!(err, Err(Error::Serde { line: 2, .. })));
+
+        let file: ConfigFile = toml::from_str("slide_jitter = 1.5").unwrap();
+        assert!(file.apply(&mut AppConfig::default()).is_err());
+
+        let file: ConfigFile = toml::from_str("transition_easing = \"wobble\"").unwrap();
+        assert!(file.apply(&mut AppConfig::default()).is_err());
+
+        let file: ConfigFile = toml::from_str("transition_effect = \"spin\"").unwrap();
+        assert!(file.apply(&mut AppConfig::default()).is_err());
+
+        let file: ConfigFile = toml::from_str("[clock_overlay]\nposition = \"middle\"").unwrap();
+        assert!(file.apply(&mut AppConfig::default()).is_err());
+
+        let file: ConfigFile = toml::from_str("[photo_filter]\nmin_rating = 6").unwrap();
+        assert!(file.apply(&mut AppConfig::default()).is_err());
+
+        let file: ConfigFile = toml::from_str("size = \"1280\"").unwrap();
+        assert!(file.apply(&mut AppConfig::default()).is_err());
+
+        let file: ConfigFile = toml::from_str("locale = \"xx\"").unwrap();
+        assert!(file.apply(&mut AppConfig::default()).is_err());
+
+        let file: ConfigFile = toml::from_str("[keymap]\nQ = \"jump\"").unwrap();
+        assert!(file.apply(&mut AppConfig::default()).is_err());
+    }
+}
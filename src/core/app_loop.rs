@@ -1,50 +1,373 @@
+use crate::core::frame_stats::{self, FrameHistory, FrameTimes};
 use crate::core::{IApp, IClock, input};
 use crate::error::Result;
+use std::time::{Duration, Instant};
+
+// --------------------------------------------------------------------------------
+// Update period and frame rate cap of one state of the app
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoopRate {
+    pub update_interval: Duration,
+    pub max_fps: Option<f32>, // frames rendered per second, every update if None
+}
+
+// --------------------------------------------------------------------------------
+// The app runs at the idle rates while nothing moves, e.g. a still photo or
+// asleep, and presents frames only when they changed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoopRates {
+    pub active: LoopRate,
+    pub idle: LoopRate,
+}
+
+impl Default for LoopRates {
+    fn default() -> Self {
+        Self {
+            active: LoopRate {
+                update_interval: Duration::from_millis(10),
+                max_fps: None,
+            },
+            idle: LoopRate {
+                update_interval: Duration::from_millis(100),
+                max_fps: None,
+            },
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------
+// How a late loop catches up. A step runs at most `max_updates` updates, the
+// rest of the lag is skipped. A lag above `drop_lag_after`, e.g. after a system
+// suspend, is dropped right away so animations don't fast-forward.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CatchUp {
+    pub max_updates: u32,
+    pub drop_lag_after: Option<Duration>,
+}
+
+impl Default for CatchUp {
+    fn default() -> Self {
+        Self {
+            max_updates: 4,
+            drop_lag_after: Some(Duration::from_secs(1)),
+        }
+    }
+}
+
+// Called with the lag when the loop starts falling behind
+pub type SlowFrameHook = Box<dyn FnMut(Duration)>;
 
 // --------------------------------------------------------------------------------
 pub struct AppLoop {
-    dt_update: std::time::Duration,
-    t_lag: std::time::Duration,
+    rates: LoopRates,
+    catch_up: CatchUp,
+    on_slow_frame: SlowFrameHook,
+    idle: bool,
+    behind: bool, // the last step skipped updates
+    t_lag: Duration,
+    t_next_frame: Option<Instant>, // earliest time of the next frame when capped
+    history: FrameHistory,
 }
 
 impl AppLoop {
     // ----------------------------------------------------------------------------
-    pub fn new(dt_update: std::time::Duration) -> Self {
+    pub fn new(rates: LoopRates) -> Self {
         Self {
-            dt_update,
-            t_lag: std::time::Duration::ZERO,
+            rates,
+            catch_up: CatchUp::default(),
+            on_slow_frame: Box::new(|lag| log::warn!("Main loop is {lag:?} behind")),
+            idle: false,
+            behind: false,
+            t_lag: Duration::ZERO,
+            t_next_frame: None,
+            history: FrameHistory::new(),
         }
     }
 
     // ----------------------------------------------------------------------------
+    pub fn with_catch_up(mut self, catch_up: CatchUp) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    // ----------------------------------------------------------------------------
+    // Replaces the default warning in the log
+    pub fn on_slow_frame(mut self, hook: impl FnMut(Duration) + 'static) -> Self {
+        self.on_slow_frame = Box::new(hook);
+        self
+    }
+
+    // ----------------------------------------------------------------------------
+    fn rate(&self) -> &LoopRate {
+        match self.idle {
+            true => &self.rates.idle,
+            false => &self.rates.active,
+        }
+    }
+
+    // ----------------------------------------------------------------------------
+    // `present` swaps the buffers of a rendered frame, returns true if there
+    // was one
     pub fn step<App: IApp, Clock: IClock>(
         &mut self,
         app: &mut App,
         clock: &Clock,
         input: &mut input::Input,
-    ) -> Result<()> {
+        present: impl FnOnce(),
+    ) -> Result<bool> {
         // generic app loop: https://gameprogrammingpatterns.com/game-loop.html
         // Goal: consume dt_update time in this step, sleep if ahead, catch up if behind
         let t0 = clock.t_now();
+        let dt_update = self.rate().update_interval;
+
+        // Stalls: the time passed while the app was frozen is not made up
+        if let Some(drop_lag_after) = self.catch_up.drop_lag_after
+            && self.t_lag > drop_lag_after
+        {
+            log::info!("Dropped a lag of {:?}", self.t_lag);
+            self.t_lag = Duration::ZERO;
+        }
 
         // Slow machines: Clamp number of updates to avoid spiral of death
         // (otherwise the next loop will be late again)
-        let updates_needed = (self.t_lag.as_nanos() / self.dt_update.as_nanos()) as u32 + 1;
-        for _ in 0..updates_needed.min(4) {
-            app.update(t0, self.dt_update, input)?;
+        let max_updates = self.catch_up.max_updates.max(1);
+        let updates_needed = (self.t_lag.as_nanos() / dt_update.as_nanos()) as u32 + 1;
+        for _ in 0..updates_needed.min(max_updates) {
+            app.update(t0, dt_update, input)?;
         }
+        let behind = updates_needed > max_updates;
+        if behind && !self.behind {
+            (self.on_slow_frame)(self.t_lag);
+        }
+        self.behind = behind;
 
-        app.render(&t0)?;
+        // The updates run ahead of the clock by the rest of the period, the
+        // frame shows the state in between the last two. Clamped updates
+        // are behind, so the last state is shown.
+        let alpha = match behind {
+            true => 1.0,
+            false => {
+                let rest = self.t_lag.as_nanos() % dt_update.as_nanos();
+                rest as f32 / dt_update.as_nanos() as f32
+            }
+        };
+        let render = (!self.idle || app.needs_redraw()) && self.frame_due(t0);
+        if render {
+            let t_update = clock.t_now();
+            app.render(&t0, alpha)?;
+            let t_render = clock.t_now();
+            present();
+            let times = FrameTimes {
+                update: t_update.duration_since(t0),
+                render: t_render.duration_since(t_update),
+                swap: clock.dt_since(t_render),
+            };
+            if let Some(stats) = self.history.add(t0, times) {
+                frame_stats::publish(stats);
+            }
+        }
 
         self.t_lag += clock.dt_since(t0);
 
-        if let Some(t_sleep) = self.dt_update.checked_sub(self.t_lag) {
+        if let Some(t_sleep) = dt_update.checked_sub(self.t_lag) {
             // Fast machines: sleep to maintain a consistent update rate
             clock.sleep(t_sleep);
         }
 
         // Pretend that all updates have been processed
-        self.t_lag = self.t_lag.saturating_sub(self.dt_update * updates_needed);
-        Ok(())
+        self.t_lag = self.t_lag.saturating_sub(dt_update * updates_needed);
+
+        // The lag counts in periods of the old rate, so it starts over
+        let idle = app.is_idle();
+        if idle != self.idle {
+            self.idle = idle;
+            self.t_lag = Duration::ZERO;
+            self.t_next_frame = None;
+        }
+        Ok(render)
+    }
+
+    // ----------------------------------------------------------------------------
+    // Frames are spaced by the cap on average, a frame late by less than half
+    // a period doesn't push the following ones back
+    fn frame_due(&mut self, t: Instant) -> bool {
+        let Some(max_fps) = self.rate().max_fps.filter(|&fps| fps > 0.0) else {
+            return true;
+        };
+        let t_next = *self.t_next_frame.get_or_insert(t);
+        if t < t_next {
+            return false;
+        }
+        let period = Duration::from_secs_f32(1.0 / max_fps);
+        self.t_next_frame = Some((t_next + period).max(t + period / 2));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::clock::FakeClock;
+    use crate::core::input::{Event, Input};
+
+    // Counts the calls and takes `frame_time` per render
+    struct TestApp<'a> {
+        clock: &'a FakeClock,
+        frame_time: Duration,
+        updates: u32,
+        renders: u32,
+        events: Vec<(u32, Event)>,
+        idle: bool,
+        redraw: bool,
+    }
+
+    impl<'a> TestApp<'a> {
+        fn new(clock: &'a FakeClock, frame_time: Duration) -> Self {
+            Self {
+                clock,
+                frame_time,
+                updates: 0,
+                renders: 0,
+                events: Vec::new(),
+                idle: false,
+                redraw: false,
+            }
+        }
+    }
+
+    impl IApp for TestApp<'_> {
+        fn update(&mut self, _t: Instant, _dt: Duration, input: &mut Input) -> Result<()> {
+            let update = self.updates;
+            self.events
+                .extend(input.take_events().into_iter().map(|e| (update, e.event)));
+            self.updates += 1;
+            Ok(())
+        }
+
+        fn render(&mut self, _t: &Instant, _alpha: f32) -> Result<()> {
+            self.clock.advance(self.frame_time);
+            self.renders += 1;
+            self.redraw = false;
+            Ok(())
+        }
+
+        fn is_idle(&self) -> bool {
+            self.idle
+        }
+
+        fn needs_redraw(&self) -> bool {
+            self.redraw
+        }
+    }
+
+    fn steps(app_loop: &mut AppLoop, app: &mut TestApp, input: &mut Input, n: usize) -> u32 {
+        let clock = app.clock;
+        let presented = (0..n).map(|_| app_loop.step(app, clock, input, || {}).unwrap());
+        presented.filter(|&p| p).count() as u32
+    }
+
+    #[test]
+    fn test_fast_machine() {
+        let clock = FakeClock::new();
+        let t0 = clock.t_now();
+        let mut app = TestApp::new(&clock, Duration::from_millis(2));
+        let mut app_loop = AppLoop::new(LoopRates::default());
+        let presented = steps(&mut app_loop, &mut app, &mut Input::new(), 10);
+        assert_eq!((app.updates, app.renders, presented), (10, 10, 10));
+        assert_eq!(clock.dt_since(t0), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_slow_machine_catches_up() {
+        let clock = FakeClock::new();
+        let t0 = clock.t_now();
+        let mut app = TestApp::new(&clock, Duration::from_millis(35));
+        let mut app_loop = AppLoop::new(LoopRates::default());
+        let mut input = Input::new();
+        steps(&mut app_loop, &mut app, &mut input, 3);
+        assert_eq!(app.updates, 1 + 3 + 4);
+        // 3.5 updates per frame keep up with the clock
+        steps(&mut app_loop, &mut app, &mut input, 97);
+        assert_eq!(clock.dt_since(t0), Duration::from_millis(3500));
+        assert_eq!(app.updates, 347);
+    }
+
+    #[test]
+    fn test_catch_up() {
+        let clock = FakeClock::new();
+        let mut app = TestApp::new(&clock, Duration::from_millis(35));
+        let slow = std::rc::Rc::new(std::cell::Cell::new(0));
+        let hook_slow = std::rc::Rc::clone(&slow);
+        let catch_up = CatchUp {
+            max_updates: 2,
+            drop_lag_after: None,
+        };
+        let mut app_loop = AppLoop::new(LoopRates::default())
+            .with_catch_up(catch_up)
+            .on_slow_frame(move |_| hook_slow.set(hook_slow.get() + 1));
+        let mut input = Input::new();
+        steps(&mut app_loop, &mut app, &mut input, 10);
+        assert_eq!(app.updates, 1 + 9 * 2);
+        assert_eq!(slow.get(), 1);
+
+        // a stall of a minute is dropped instead of being caught up
+        let mut app = TestApp::new(&clock, Duration::from_millis(2));
+        let mut app_loop = AppLoop::new(LoopRates::default());
+        steps(&mut app_loop, &mut app, &mut input, 1);
+        app.frame_time = Duration::from_secs(60);
+        steps(&mut app_loop, &mut app, &mut input, 1);
+        app.frame_time = Duration::from_millis(2);
+        let t0 = clock.t_now();
+        steps(&mut app_loop, &mut app, &mut input, 2);
+        assert_eq!(app.updates, 1 + 1 + 2);
+        assert_eq!(clock.dt_since(t0), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_idle_rates() {
+        let clock = FakeClock::new();
+        let t0 = clock.t_now();
+        let mut app = TestApp::new(&clock, Duration::ZERO);
+        app.idle = true;
+        let mut rates = LoopRates::default();
+        rates.idle.max_fps = Some(2.0);
+        let mut app_loop = AppLoop::new(rates);
+        let mut input = Input::new();
+        // the first step still runs at the active rate, every frame changes
+        let presented: u32 = (0..21)
+            .map(|_| {
+                app.redraw = true;
+                steps(&mut app_loop, &mut app, &mut input, 1)
+            })
+            .sum();
+        assert_eq!(clock.dt_since(t0), Duration::from_millis(10 + 20 * 100));
+        assert_eq!(app.updates, 21);
+        assert_eq!(presented, 1 + 4);
+    }
+
+    #[test]
+    fn test_idle_skips_unchanged_frames() {
+        let clock = FakeClock::new();
+        let mut app = TestApp::new(&clock, Duration::from_millis(2));
+        app.idle = true;
+        let mut app_loop = AppLoop::new(LoopRates::default());
+        let mut input = Input::new();
+        assert_eq!(steps(&mut app_loop, &mut app, &mut input, 1), 1);
+        assert_eq!(steps(&mut app_loop, &mut app, &mut input, 10), 0);
+        app.redraw = true;
+        assert_eq!(steps(&mut app_loop, &mut app, &mut input, 10), 1);
+        assert_eq!((app.updates, app.renders), (21, 2));
+    }
+
+    #[test]
+    fn test_replayed_input() {
+        let clock = FakeClock::new();
+        let mut app = TestApp::new(&clock, Duration::from_millis(25));
+        let mut app_loop = AppLoop::new(LoopRates::default());
+        let mut input = Input::new();
+        input.replay("3 key-down next-scene\n7 wheel 120").unwrap();
+        steps(&mut app_loop, &mut app, &mut input, 5);
+        let updates: Vec<_> = app.events.iter().map(|(update, _)| *update).collect();
+        assert_eq!(updates, [3, 7]);
     }
 }
@@ -34,3 +34,46 @@ impl Clock {
         Clock {}
     }
 }
+
+// ----------------------------------------------------------------------------
+// Time only passes when advanced or slept, e.g. by a test app that pretends
+// to be slow, so loops can be stepped deterministically
+pub struct FakeClock {
+    t: std::cell::Cell<std::time::Instant>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self {
+            t: std::cell::Cell::new(std::time::Instant::now()),
+        }
+    }
+
+    pub fn advance(&self, dt: std::time::Duration) {
+        self.t.set(self.t.get() + dt);
+    }
+}
+
+impl IClock for FakeClock {
+    // ------------------------------------------------------------------------
+    fn t_now(&self) -> std::time::Instant {
+        self.t.get()
+    }
+
+    // ------------------------------------------------------------------------
+    fn dt_since(&self, t: std::time::Instant) -> std::time::Duration {
+        self.t_now().duration_since(t)
+    }
+
+    // ------------------------------------------------------------------------
+    fn sleep(&self, dt: std::time::Duration) -> std::time::Instant {
+        self.advance(dt);
+        self.t_now()
+    }
+}
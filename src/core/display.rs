@@ -0,0 +1,126 @@
+// ----------------------------------------------------------------------------
+// Where the window goes, shared by the Win32 and X11 front ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayMode {
+    pub fullscreen: bool,       // borderless, covering the whole monitor
+    pub monitor: Option<usize>, // index of the monitor, the primary one if None
+    pub size: (u32, u32),       // of the window unless fullscreen
+}
+
+impl Default for DisplayMode {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            monitor: None,
+            size: (800, 600),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Area of a monitor on the virtual desktop, in pixels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Monitor {
+    pub x: i32,
+    pub y: i32,
+    pub cx: u32,
+    pub cy: u32,
+}
+
+impl DisplayMode {
+    // ------------------------------------------------------------------------
+    // The monitor to show the window on, `monitors` lists the primary one
+    // first. Unknown indices fall back to the primary monitor.
+    pub fn monitor<'a>(&self, monitors: &'a [Monitor]) -> Option<&'a Monitor> {
+        let index = self.monitor.unwrap_or(0);
+        monitors.get(index).or_else(|| {
+            log::warn!("No monitor {index}, {} found", monitors.len());
+            monitors.first()
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    // Area of the window: the whole monitor if fullscreen, otherwise the
+    // window size centered on it
+    pub fn placement(&self, monitors: &[Monitor]) -> Monitor {
+        let (cx, cy) = self.size;
+        let Some(monitor) = self.monitor(monitors) else {
+            return Monitor { x: 0, y: 0, cx, cy };
+        };
+        if self.fullscreen {
+            return *monitor;
+        }
+        let center = |pos: i32, extent: u32, size: u32| {
+            pos + (i64::from(extent) - i64::from(size)).max(0) as i32 / 2
+        };
+        Monitor {
+            x: center(monitor.x, monitor.cx, cx),
+            y: center(monitor.y, monitor.cy, cy),
+            cx,
+            cy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placement() {
+        let monitors = [
+            Monitor {
+                x: 0,
+                y: 0,
+                cx: 1920,
+                cy: 1080,
+            },
+            Monitor {
+                x: 1920,
+                y: -200,
+                cx: 1280,
+                cy: 1024,
+            },
+        ];
+
+        let window = DisplayMode::default();
+        let placed = window.placement(&monitors);
+        assert_eq!(
+            (placed.x, placed.y, placed.cx, placed.cy),
+            (560, 240, 800, 600)
+        );
+
+        let fullscreen = DisplayMode {
+            fullscreen: true,
+            monitor: Some(1),
+            ..window
+        };
+        assert_eq!(fullscreen.placement(&monitors), monitors[1]);
+
+        // falls back to the primary monitor
+        let missing = DisplayMode {
+            monitor: Some(2),
+            ..fullscreen
+        };
+        assert_eq!(missing.placement(&monitors), monitors[0]);
+
+        // larger than the monitor, starts at its corner
+        let large = DisplayMode {
+            monitor: Some(1),
+            size: (1600, 1200),
+            ..window
+        };
+        let placed = large.placement(&monitors);
+        assert_eq!((placed.x, placed.y), (1920, -200));
+
+        assert_eq!(
+            window.placement(&[]),
+            Monitor {
+                x: 0,
+                y: 0,
+                cx: 800,
+                cy: 600
+            }
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Statistics cover the most recent frames, published once per second
+const WINDOW: usize = 240;
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Latest statistics of the main loop, e.g. for the debug overlay and the
+// HTTP server
+static LATEST: Mutex<Option<FrameStats>> = Mutex::new(None);
+
+// ----------------------------------------------------------------------------
+// Time spent in one frame on the updates, rendering and presenting it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTimes {
+    pub update: Duration,
+    pub render: Duration,
+    pub swap: Duration,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Timing {
+    pub min_ms: f32,
+    pub avg_ms: f32,
+    pub p99_ms: f32,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct FrameStats {
+    pub fps: f32,
+    pub update: Timing,
+    pub render: Timing,
+    pub swap: Timing,
+}
+
+// ----------------------------------------------------------------------------
+// Collects the frame times of the main loop
+#[derive(Debug, Default)]
+pub struct FrameHistory {
+    frames: VecDeque<FrameTimes>,
+    presented: u32, // frames since the last report
+    t_report: Option<Instant>,
+}
+
+impl FrameHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // ------------------------------------------------------------------------
+    // Returns new statistics about once per second
+    pub fn add(&mut self, t: Instant, times: FrameTimes) -> Option<FrameStats> {
+        if self.frames.len() == WINDOW {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(times);
+        self.presented += 1;
+
+        let t_report = *self.t_report.get_or_insert(t);
+        let dt = t.duration_since(t_report);
+        if dt < REPORT_INTERVAL {
+            return None;
+        }
+        let fps = self.presented as f32 / dt.as_secs_f32();
+        self.presented = 0;
+        self.t_report = Some(t);
+        Some(self.stats(fps))
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn stats(&self, fps: f32) -> FrameStats {
+        FrameStats {
+            fps,
+            update: timing(self.frames.iter().map(|f| f.update)),
+            render: timing(self.frames.iter().map(|f| f.render)),
+            swap: timing(self.frames.iter().map(|f| f.swap)),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn timing(times: impl Iterator<Item = Duration>) -> Timing {
+    let mut ms: Vec<f32> = times.map(|t| t.as_secs_f32() * 1000.0).collect();
+    if ms.is_empty() {
+        return Timing::default();
+    }
+    ms.sort_by(f32::total_cmp);
+    let p99 = ((ms.len() * 99).div_ceil(100)).max(1) - 1;
+    Timing {
+        min_ms: ms[0],
+        avg_ms: ms.iter().sum::<f32>() / ms.len() as f32,
+        p99_ms: ms[p99],
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub fn publish(stats: FrameStats) {
+    if let Ok(mut latest) = LATEST.lock() {
+        *latest = Some(stats);
+    }
+}
+
+pub fn latest() -> Option<FrameStats> {
+    LATEST.lock().ok().and_then(|latest| *latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_history() {
+        let mut history = FrameHistory::new();
+        let t0 = Instant::now();
+        for i in 0..100u32 {
+            let times = FrameTimes {
+                update: Duration::from_micros(500),
+                render: Duration::from_millis(if i == 50 { 40 } else { 2 }),
+                swap: Duration::from_millis(u64::from(i % 10)),
+            };
+            let t = t0 + Duration::from_millis(u64::from(i) * 10);
+            assert_eq!(history.add(t, times), None);
+        }
+        let stats = history
+            .add(t0 + Duration::from_secs(1), FrameTimes::default())
+            .unwrap();
+        assert_eq!(stats.fps, 101.0);
+        assert_eq!(stats.update.min_ms, 0.0);
+        assert_eq!(stats.render.p99_ms, 2.0);
+        assert_eq!(stats.swap.p99_ms, 9.0);
+        assert!((stats.render.avg_ms - 238.0 / 101.0).abs() < 1e-3);
+    }
+}
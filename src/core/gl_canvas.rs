@@ -1,8 +1,10 @@
 use crate::core::camera::Camera;
 use crate::core::gl_graphics;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::gfx::animation::Easing;
 use crate::gl::opengl::{self as gl};
-use crate::v2d::{m4x4::M4x4, v2::V2};
+use crate::v2d::{m4x4::M4x4, rect::Rect, v2::V2};
+use std::collections::HashMap;
 use std::rc::Rc;
 
 // ----------------------------------------------------------------------------
@@ -12,6 +14,8 @@ pub struct GlObject {
     pub pipeline_id: usize,
     pub material_id: usize,
     pub transform: M4x4,
+    pub opacity: f32,
+    pub src: Rect, // part of the texture shown, ignored by text
 }
 
 // ----------------------------------------------------------------------------
@@ -21,11 +25,16 @@ pub struct GlTransition {
     pub pipeline_id: usize,
     pub from_id: usize,
     pub to_id: usize,
-    pub progress: f32,
+    pub progress: f32, // linear, `easing` shapes it for the pipeline
+    pub easing: Easing,
     pub from_pos: V2,
     pub from_size: V2,
     pub to_pos: V2,
     pub to_size: V2,
+    pub from_src: Rect, // part of the textures shown
+    pub to_src: Rect,
+    pub from_rgba: bool, // the texture holds RGBA instead of YCbCr
+    pub to_rgba: bool,
 }
 
 // ----------------------------------------------------------------------------
@@ -33,7 +42,9 @@ pub struct GlTransition {
 pub enum GlMaterial {
     Color([f32; 4]),
     Texture(gl::GLuint),
+    MSDFTexture(gl::GLuint, f32), // atlas and its distance range in texels
     YUVTexture(gl::GLuint, gl::GLuint, gl::GLuint),
+    NV12Texture(gl::GLuint, gl::GLuint),
 }
 
 // ----------------------------------------------------------------------------
@@ -95,10 +106,15 @@ pub struct Canvas {
     camera: Camera,
     objects: Vec<GlObject>,
     transitions: Vec<GlTransition>,
-    materials: Vec<GlMaterial>,
-    meshes: Vec<GlMesh>,
+    materials: Vec<Option<GlMaterial>>, // kept across updates, see `set_material`
+    meshes: Vec<Option<GlMesh>>,
+    texture_sizes: HashMap<gl::GLuint, usize>,
 }
 
+// ----------------------------------------------------------------------------
+// Bytes per pixel of the texture formats accepted by gl_graphics::create_texture
+const TEXTURE_BPP: [usize; 4] = [4, 3, 1, 2];
+
 // ----------------------------------------------------------------------------
 impl Canvas {
     pub fn new(gl: Rc<gl::OpenGlFunctions>, aspect_ratio: f32) -> Result<Self> {
@@ -110,9 +126,28 @@ impl Canvas {
             transitions: Vec::new(),
             materials: Vec::new(),
             meshes: Vec::new(),
+            texture_sizes: HashMap::new(),
         })
     }
 
+    // ------------------------------------------------------------------------
+    fn track_texture(&mut self, id: gl::GLuint, width: usize, height: usize, format: usize) {
+        let bpp = TEXTURE_BPP.get(format).copied().unwrap_or(0);
+        self.texture_sizes.insert(id, width * height * bpp);
+    }
+
+    // ------------------------------------------------------------------------
+    fn delete_texture(&mut self, id: gl::GLuint) {
+        gl_graphics::delete_texture(&self.gl, id);
+        self.texture_sizes.remove(&id);
+    }
+
+    // ------------------------------------------------------------------------
+    // Approximate GPU memory used by all live textures
+    pub fn texture_bytes(&self) -> usize {
+        self.texture_sizes.values().sum()
+    }
+
     // ------------------------------------------------------------------------
     pub fn create_texture(
         &mut self,
@@ -130,9 +165,28 @@ impl Canvas {
             gl::LINEAR,
             gl::CLAMP_TO_EDGE,
         )?;
+        self.track_texture(id, width, height, format);
         Ok(GlMaterial::Texture(id))
     }
 
+    // ------------------------------------------------------------------------
+    // Partial update path, the size and format must match `create_texture`
+    pub fn update_texture(
+        &mut self,
+        material: &GlMaterial,
+        width: usize,
+        height: usize,
+        format: usize,
+        data: &[u8],
+    ) -> Result<()> {
+        match material {
+            GlMaterial::Texture(id) => {
+                gl_graphics::update_texture(&self.gl, *id, width, height, format, data)
+            }
+            _ => Err(Error::InvalidTextureFormat),
+        }
+    }
+
     // ------------------------------------------------------------------------
     pub fn create_yuv_texture(
         &mut self,
@@ -152,19 +206,64 @@ impl Canvas {
         let id_cr =
             gl_graphics::create_texture(&self.gl, width / 2, height / 2, format, cr, filter, wrap)?;
 
+        self.track_texture(id_luma, width, height, format);
+        self.track_texture(id_cb, width / 2, height / 2, format);
+        self.track_texture(id_cr, width / 2, height / 2, format);
         Ok(GlMaterial::YUVTexture(id_luma, id_cb, id_cr))
     }
 
+    // ------------------------------------------------------------------------
+    // Full size luma plane plus one half size plane with interleaved Cb/Cr
+    pub fn create_nv12_texture(
+        &mut self,
+        width: usize,
+        height: usize,
+        luma: &[u8],
+        cbcr: &[u8],
+    ) -> Result<GlMaterial> {
+        const LUMA_FORMAT: usize = 2;
+        const CHROMA_FORMAT: usize = 3;
+        let filter = gl::LINEAR;
+        let wrap = gl::CLAMP_TO_EDGE;
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+        let id_luma =
+            gl_graphics::create_texture(&self.gl, width, height, LUMA_FORMAT, luma, filter, wrap)?;
+        let id_cbcr = gl_graphics::create_texture(
+            &self.gl,
+            chroma_width,
+            chroma_height,
+            CHROMA_FORMAT,
+            cbcr,
+            filter,
+            wrap,
+        );
+        let id_cbcr = match id_cbcr {
+            Ok(id) => id,
+            Err(e) => {
+                gl_graphics::delete_texture(&self.gl, id_luma);
+                return Err(e);
+            }
+        };
+
+        self.track_texture(id_luma, width, height, LUMA_FORMAT);
+        self.track_texture(id_cbcr, chroma_width, chroma_height, CHROMA_FORMAT);
+        Ok(GlMaterial::NV12Texture(id_luma, id_cbcr))
+    }
+
     // ------------------------------------------------------------------------
     pub fn delete_material(&mut self, material: &GlMaterial) {
         match material {
-            GlMaterial::Texture(id) => {
-                gl_graphics::delete_texture(&self.gl, *id);
+            GlMaterial::Texture(id) | GlMaterial::MSDFTexture(id, _) => {
+                self.delete_texture(*id);
             }
             GlMaterial::YUVTexture(id_luma, id_cb, id_cr) => {
-                gl_graphics::delete_texture(&self.gl, *id_luma);
-                gl_graphics::delete_texture(&self.gl, *id_cb);
-                gl_graphics::delete_texture(&self.gl, *id_cr);
+                self.delete_texture(*id_luma);
+                self.delete_texture(*id_cb);
+                self.delete_texture(*id_cr);
+            }
+            GlMaterial::NV12Texture(id_luma, id_cbcr) => {
+                self.delete_texture(*id_luma);
+                self.delete_texture(*id_cbcr);
             }
             _ => {}
         }
@@ -180,17 +279,25 @@ impl Canvas {
         delete_mesh(&self.gl, mesh);
     }
 
-    pub fn update(
-        &mut self,
-        objects: Vec<GlObject>,
-        transitions: Vec<GlTransition>,
-        materials: Vec<GlMaterial>,
-        meshes: Vec<GlMesh>,
-    ) {
+    // ------------------------------------------------------------------------
+    // Material the objects refer to as `id`, None once it is deleted
+    pub fn set_material(&mut self, id: usize, material: Option<GlMaterial>) {
+        if self.materials.len() <= id {
+            self.materials.resize(id + 1, None);
+        }
+        self.materials[id] = material;
+    }
+
+    pub fn set_mesh(&mut self, id: usize, mesh: Option<GlMesh>) {
+        if self.meshes.len() <= id {
+            self.meshes.resize(id + 1, None);
+        }
+        self.meshes[id] = mesh;
+    }
+
+    pub fn update(&mut self, objects: Vec<GlObject>, transitions: Vec<GlTransition>) {
         self.objects = objects;
         self.transitions = transitions;
-        self.materials = materials;
-        self.meshes = meshes;
     }
 
     pub fn resize(&mut self, aspect_ratio: f32) {
@@ -213,11 +320,11 @@ impl Canvas {
         &self.transitions
     }
 
-    pub fn materials(&self) -> &[GlMaterial] {
-        &self.materials
+    pub fn material(&self, material_id: usize) -> Option<&GlMaterial> {
+        self.materials.get(material_id).and_then(|m| m.as_ref())
     }
 
     pub fn mesh(&self, mesh_id: usize) -> Option<&GlMesh> {
-        self.meshes.get(mesh_id)
+        self.meshes.get(mesh_id).and_then(|m| m.as_ref())
     }
 }
@@ -13,11 +13,16 @@ pub fn print_opengl_info(gl: &gl::OpenGlFunctions) {
         let mut max_units = 0;
         gl.GetIntegerv(gl::MAX_TEXTURE_UNITS, &mut max_units);
 
-        println!("OpenGL Version:  {}", version.unwrap_or("<error>"));
-        println!("OpenGL Vendor:   {}", vendor.unwrap_or("<error>"));
-        println!("OpenGL Renderer: {}", renderer.unwrap_or("<error>"));
-        println!("OpenGL Max Texture Size:  {max_size}");
-        println!("OpenGL Max Texture Units: {max_units}");
+        crate::util::crash::set_gl_info(
+            vendor.unwrap_or("<error>"),
+            renderer.unwrap_or("<error>"),
+            version.unwrap_or("<error>"),
+        );
+        log::info!("OpenGL Version:  {}", version.unwrap_or("<error>"));
+        log::info!("OpenGL Vendor:   {}", vendor.unwrap_or("<error>"));
+        log::info!("OpenGL Renderer: {}", renderer.unwrap_or("<error>"));
+        log::info!("OpenGL Max Texture Size:  {max_size}");
+        log::info!("OpenGL Max Texture Units: {max_units}");
     }
 }
 
@@ -221,10 +226,11 @@ pub fn create_texture(
     let width = check_texture_size(width, max_size)?;
     let height = check_texture_size(height, max_size)?;
 
-    const INTERNAL_FMT: [(gl::GLint, gl::GLenum); 3] = [
+    const INTERNAL_FMT: [(gl::GLint, gl::GLenum); 4] = [
         (gl::RGBA8, gl::RGBA),
         (gl::RGB8, gl::RGB),
         (gl::R8, gl::RED),
+        (gl::RG8, gl::RG), // interleaved chroma
     ];
     let Some((internal, format)) = INTERNAL_FMT.get(format) else {
         return Err(Error::InvalidTextureFormat);
@@ -260,6 +266,39 @@ pub fn create_texture(
     Ok(texture)
 }
 
+// --------------------------------------------------------------------------------
+// Replaces the contents of a texture created with the same size and format,
+// without reallocating it, e.g. for the frames of a video
+pub fn update_texture(
+    gl: &gl::OpenGlFunctions,
+    texture: GLuint,
+    width: usize,
+    height: usize,
+    format: usize,
+    data: &[u8],
+) -> Result<()> {
+    const FORMATS: [gl::GLenum; 4] = [gl::RGBA, gl::RGB, gl::RED, gl::RG];
+    let Some(format) = FORMATS.get(format) else {
+        return Err(Error::InvalidTextureFormat);
+    };
+
+    unsafe {
+        gl.BindTexture(gl::TEXTURE_2D, texture);
+        gl.TexSubImage2D(
+            gl::TEXTURE_2D,
+            0,
+            0,
+            0,
+            width as gl::GLsizei,
+            height as gl::GLsizei,
+            *format,
+            gl::UNSIGNED_BYTE,
+            data.as_ptr() as *const _,
+        );
+    }
+    check_gl_error(gl)
+}
+
 // --------------------------------------------------------------------------------
 pub fn create_framebuffer(
     gl: &gl::OpenGlFunctions,
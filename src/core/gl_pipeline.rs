@@ -2,7 +2,7 @@ use crate::core::gl_canvas::{GlMaterial, GlMesh};
 use crate::core::gl_graphics;
 use crate::error::Result;
 use crate::gl::opengl as gl;
-use crate::v2d::{m4x4::M4x4, v2::V2};
+use crate::v2d::{m4x4::M4x4, rect::Rect, v2::V2};
 use std::rc::Rc;
 
 // ----------------------------------------------------------------------------
@@ -32,11 +32,17 @@ pub struct GlUniforms {
     pub model: M4x4,
     pub camera: M4x4,
     pub mat_id: gl::GLint,
+    pub opacity: f32,
+    pub src: Rect, // part of the texture shown
     pub progress: f32,
     pub from_pos: V2,
     pub from_size: V2,
     pub to_pos: V2,
     pub to_size: V2,
+    pub from_src: Rect,
+    pub to_src: Rect,
+    pub from_rgba: bool,
+    pub to_rgba: bool,
 }
 
 // --------------------------------------------------------------------------------
@@ -55,6 +61,13 @@ pub trait GlTransition {
     ) -> Result<()>;
 }
 
+// --------------------------------------------------------------------------------
+// Sets a vec4 uniform to (x, y, width, height) of `rect`
+unsafe fn uniform_rect(gl: &gl::OpenGlFunctions, location: gl::GLint, rect: &Rect) {
+    let (pos, size) = (rect.pos, rect.size);
+    unsafe { gl.Uniform4f(location, pos.x0(), pos.x1(), size.x0(), size.x1()) };
+}
+
 pub mod v_pos_tex {
     use crate::core::gl_canvas::GlMaterial;
 
@@ -67,6 +80,8 @@ pub mod v_pos_tex {
         pub uid_model: gl::GLint,
         pub uid_camera: gl::GLint,
         pub uid_mat_id: gl::GLint,
+        pub uid_opacity: gl::GLint,
+        pub uid_src: gl::GLint,
     }
 
     // ----------------------------------------------------------------------------
@@ -74,19 +89,24 @@ pub mod v_pos_tex {
         pub fn new(gl: Rc<gl::OpenGlFunctions>) -> Result<Self> {
             let shader = gl_graphics::create_program(&gl, "pos_tex", VS_TEXTURE, FS_TEXTURE);
             if let Err(e) = shader {
-                println!("Error creating shader: {e:?}");
+                log::error!("Error creating shader: {e:?}");
                 return Err(e);
             };
             let shader = shader.unwrap();
             let uid_model = gl_graphics::get_uniform_location(&gl, shader, "model").unwrap_or(-1);
             let uid_camera = gl_graphics::get_uniform_location(&gl, shader, "camera").unwrap_or(-1);
             let uid_mat_id = gl_graphics::get_uniform_location(&gl, shader, "mat_id").unwrap_or(-1);
+            let uid_opacity =
+                gl_graphics::get_uniform_location(&gl, shader, "opacity").unwrap_or(-1);
+            let uid_src = gl_graphics::get_uniform_location(&gl, shader, "src").unwrap_or(-1);
             Ok(Pipeline {
                 gl,
                 shader,
                 uid_model,
                 uid_camera,
                 uid_mat_id,
+                uid_opacity,
+                uid_src,
             })
         }
     }
@@ -111,6 +131,10 @@ pub mod v_pos_tex {
                 gl.UniformMatrix4fv(self.uid_model, 1, gl::FALSE, unis.model.as_ptr());
                 gl.UniformMatrix4fv(self.uid_camera, 1, gl::FALSE, unis.camera.as_ptr());
                 gl.Uniform1i(self.uid_mat_id, unis.mat_id);
+                gl.Uniform1f(self.uid_opacity, unis.opacity);
+                uniform_rect(gl, self.uid_src, &unis.src);
+                gl.Enable(gl::BLEND);
+                gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
                 gl.ActiveTexture(gl::TEXTURE0);
                 gl.BindTexture(gl::TEXTURE_2D, texture);
                 gl.DrawArrays(gl::TRIANGLES, 0, bindings.count as gl::GLint);
@@ -133,6 +157,7 @@ pub mod v_pos_tex {
     #version 300 es
     uniform mat4 model;
     uniform mat4 camera;
+    uniform vec4 src;
 
     layout (location = 0) in vec2 a_pos;
     layout (location = 1) in vec2 a_tex;
@@ -141,19 +166,21 @@ pub mod v_pos_tex {
 
     void main() {
         gl_Position = camera * model * vec4(a_pos, 0.0, 1.0);
-        v_tex = a_tex;
+        v_tex = src.xy + a_tex * src.zw;
     }"#;
 
     // ----------------------------------------------------------------------------
     const FS_TEXTURE: &str = r#"
     #version 300 es
     uniform sampler2D txtre;
+    uniform mediump float opacity;
 
     in mediump vec2 v_tex;
     out mediump vec4 FragColor;
 
     void main() {
         FragColor = texture(txtre, v_tex.st);
+        FragColor.a *= opacity;
     }"#;
 }
 
@@ -169,26 +196,37 @@ pub mod msdf_tex {
         pub uid_model: gl::GLint,
         pub uid_camera: gl::GLint,
         pub uid_mat_id: gl::GLint,
+        pub uid_opacity: gl::GLint,
+        pub uid_px_range: gl::GLint,
     }
 
+    // Distance range of atlases that don't tell theirs
+    pub const DEFAULT_PX_RANGE: f32 = 4.0;
+
     // ----------------------------------------------------------------------------
     impl Pipeline {
         pub fn new(gl: Rc<gl::OpenGlFunctions>) -> Result<Self> {
             let shader = gl_graphics::create_program(&gl, "msdf_tex", VS_TEXTURE, FS_TEXTURE);
             if let Err(e) = shader {
-                println!("Error creating shader: {e:?}");
+                log::error!("Error creating shader: {e:?}");
                 return Err(e);
             };
             let shader = shader.unwrap();
             let uid_model = gl_graphics::get_uniform_location(&gl, shader, "model").unwrap_or(-1);
             let uid_camera = gl_graphics::get_uniform_location(&gl, shader, "camera").unwrap_or(-1);
             let uid_mat_id = gl_graphics::get_uniform_location(&gl, shader, "mat_id").unwrap_or(-1);
+            let uid_opacity =
+                gl_graphics::get_uniform_location(&gl, shader, "opacity").unwrap_or(-1);
+            let uid_px_range =
+                gl_graphics::get_uniform_location(&gl, shader, "px_range").unwrap_or(-1);
             Ok(Pipeline {
                 gl,
                 shader,
                 uid_model,
                 uid_camera,
                 uid_mat_id,
+                uid_opacity,
+                uid_px_range,
             })
         }
     }
@@ -202,10 +240,10 @@ pub mod msdf_tex {
             unis: &GlUniforms,
         ) -> Result<()> {
             let gl = &self.gl;
-            let texture = if let GlMaterial::Texture(id) = material {
-                *id
-            } else {
-                1
+            let (texture, px_range) = match material {
+                GlMaterial::MSDFTexture(id, px_range) => (*id, *px_range),
+                GlMaterial::Texture(id) => (*id, DEFAULT_PX_RANGE),
+                _ => (1, DEFAULT_PX_RANGE),
             };
             unsafe {
                 gl.UseProgram(self.shader);
@@ -213,6 +251,8 @@ pub mod msdf_tex {
                 gl.UniformMatrix4fv(self.uid_model, 1, gl::FALSE, unis.model.as_ptr());
                 gl.UniformMatrix4fv(self.uid_camera, 1, gl::FALSE, unis.camera.as_ptr());
                 gl.Uniform1i(self.uid_mat_id, unis.mat_id);
+                gl.Uniform1f(self.uid_opacity, unis.opacity);
+                gl.Uniform1f(self.uid_px_range, px_range);
                 gl.Enable(gl::BLEND);
                 gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
                 gl.ActiveTexture(gl::TEXTURE0);
@@ -249,17 +289,33 @@ pub mod msdf_tex {
     }"#;
 
     // ----------------------------------------------------------------------------
+    // The distance is the median of the three channels, which keeps the corners
+    // sharp. `px_range` is the distance range of the atlas in texels, scaled to
+    // screen pixels it gives an edge of about one pixel at any text size.
     const FS_TEXTURE: &str = r#"
     #version 300 es
     uniform sampler2D txtre;
+    uniform mediump float opacity;
+    uniform mediump float px_range;
 
     in mediump vec2 v_tex;
     out mediump vec4 FragColor;
 
+    mediump float median(mediump float r, mediump float g, mediump float b) {
+        return max(min(r, g), min(max(r, g), b));
+    }
+
+    mediump float screen_px_range() {
+        mediump vec2 unit_range = vec2(px_range) / vec2(textureSize(txtre, 0));
+        mediump vec2 screen_tex_size = vec2(1.0) / fwidth(v_tex);
+        return max(0.5 * dot(unit_range, screen_tex_size), 1.0);
+    }
+
     void main() {
-        mediump vec4 color = texture(txtre, v_tex.st);
-        mediump float sig_dist = color.a * 2.0 - 1.0;
-        mediump float alpha = smoothstep(-0.1, 0.1, sig_dist);
+        mediump vec3 msd = texture(txtre, v_tex.st).rgb;
+        mediump float sig_dist = median(msd.r, msd.g, msd.b) - 0.5;
+        mediump float px_dist = screen_px_range() * sig_dist;
+        mediump float alpha = clamp(px_dist + 0.5, 0.0, 1.0) * opacity;
         FragColor = vec4(alpha, alpha, alpha, alpha);
     }"#;
 }
@@ -276,6 +332,8 @@ pub mod v_yuv_tex {
         pub uid_model: gl::GLint,
         pub uid_camera: gl::GLint,
         pub uid_mat_id: gl::GLint,
+        pub uid_opacity: gl::GLint,
+        pub uid_src: gl::GLint,
         pub uid_yuv: gl::GLint,
     }
 
@@ -284,13 +342,16 @@ pub mod v_yuv_tex {
         pub fn new(gl: Rc<gl::OpenGlFunctions>) -> Result<Self> {
             let shader = gl_graphics::create_program(&gl, "yuv_tex", VS_TEXTURE, FS_TEXTURE);
             if let Err(e) = shader {
-                println!("Error creating shader: {e:?}");
+                log::error!("Error creating shader: {e:?}");
                 return Err(e);
             };
             let shader = shader.unwrap();
             let uid_model = gl_graphics::get_uniform_location(&gl, shader, "model").unwrap_or(-1);
             let uid_camera = gl_graphics::get_uniform_location(&gl, shader, "camera").unwrap_or(-1);
             let uid_mat_id = gl_graphics::get_uniform_location(&gl, shader, "mat_id").unwrap_or(-1);
+            let uid_opacity =
+                gl_graphics::get_uniform_location(&gl, shader, "opacity").unwrap_or(-1);
+            let uid_src = gl_graphics::get_uniform_location(&gl, shader, "src").unwrap_or(-1);
             let uid_yuv = gl_graphics::get_uniform_location(&gl, shader, "yuv_tex").unwrap_or(-1);
 
             Ok(Pipeline {
@@ -299,6 +360,8 @@ pub mod v_yuv_tex {
                 uid_model,
                 uid_camera,
                 uid_mat_id,
+                uid_opacity,
+                uid_src,
                 uid_yuv,
             })
         }
@@ -324,7 +387,11 @@ pub mod v_yuv_tex {
                 gl.UniformMatrix4fv(self.uid_model, 1, gl::FALSE, unis.model.as_ptr());
                 gl.UniformMatrix4fv(self.uid_camera, 1, gl::FALSE, unis.camera.as_ptr());
                 gl.Uniform1i(self.uid_mat_id, unis.mat_id);
+                gl.Uniform1f(self.uid_opacity, unis.opacity);
+                uniform_rect(gl, self.uid_src, &unis.src);
                 gl.Uniform1i(self.uid_yuv, 0);
+                gl.Enable(gl::BLEND);
+                gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
                 gl.ActiveTexture(gl::TEXTURE0);
                 gl.BindTexture(gl::TEXTURE_2D, tex);
                 gl.DrawArrays(gl::TRIANGLE_STRIP, 0, bindings.count as gl::GLint);
@@ -347,6 +414,7 @@ pub mod v_yuv_tex {
     #version 300 es
     uniform mat4 model;
     uniform mat4 camera;
+    uniform vec4 src;
 
     layout (location = 0) in vec2 a_pos;
     layout (location = 1) in vec2 a_tex;
@@ -355,13 +423,14 @@ pub mod v_yuv_tex {
 
     void main() {
         gl_Position = camera * model * vec4(a_pos, 0.0, 1.0);
-        v_tex = a_tex;
+        v_tex = src.xy + a_tex * src.zw;
     }"#;
 
     // ----------------------------------------------------------------------------
     const FS_TEXTURE: &str = r#"
     #version 300 es
     uniform sampler2D yuv_tex;
+    uniform mediump float opacity;
 
     in mediump vec2 v_tex;
     out mediump vec4 FragColor;
@@ -375,13 +444,14 @@ pub mod v_yuv_tex {
         rgb.r = yuv.x + 1.402 * yuv.z;
         rgb.g = yuv.x - 0.344 * yuv.y - 0.714 * yuv.z;
         rgb.b = yuv.x + 1.772 * yuv.y;
-        FragColor = vec4(rgb, 1.0);
+        FragColor = vec4(rgb, opacity);
     }"#;
 }
 
 pub mod yuv_dual {
     use super::*;
     use crate::core::gl_canvas::GlMaterial;
+    use crate::gfx::transition::TransitionKind;
 
     // ----------------------------------------------------------------------------
     pub struct Transition {
@@ -396,14 +466,20 @@ pub mod yuv_dual {
         pub uid_from_size: gl::GLint,
         pub uid_to_pos: gl::GLint,
         pub uid_to_size: gl::GLint,
+        pub uid_from_src: gl::GLint,
+        pub uid_to_src: gl::GLint,
+        pub uid_from_rgba: gl::GLint,
+        pub uid_to_rgba: gl::GLint,
     }
 
     // ----------------------------------------------------------------------------
     impl Transition {
-        pub fn new(gl: Rc<gl::OpenGlFunctions>) -> Result<Self> {
-            let shader = gl_graphics::create_program(&gl, "yuv_dual", VS_TEXTURE, FS_TEXTURE);
+        pub fn new(gl: Rc<gl::OpenGlFunctions>, kind: TransitionKind) -> Result<Self> {
+            let name = format!("yuv_dual {}", kind.name());
+            let fs = [FS_COMMON, effect(kind), FS_MAIN].concat();
+            let shader = gl_graphics::create_program(&gl, &name, VS_TEXTURE, &fs);
             if let Err(e) = shader {
-                println!("Error creating shader: {e:?}");
+                log::error!("Error creating shader: {e:?}");
                 return Err(e);
             };
             let shader = shader.unwrap();
@@ -418,6 +494,10 @@ pub mod yuv_dual {
             let uid_from_size = get_uniform_location(&gl, shader, "from_size").unwrap_or(-1);
             let uid_to_pos = get_uniform_location(&gl, shader, "to_pos").unwrap_or(-1);
             let uid_to_size = get_uniform_location(&gl, shader, "to_size").unwrap_or(-1);
+            let uid_from_src = get_uniform_location(&gl, shader, "from_src").unwrap_or(-1);
+            let uid_to_src = get_uniform_location(&gl, shader, "to_src").unwrap_or(-1);
+            let uid_from_rgba = get_uniform_location(&gl, shader, "from_rgba").unwrap_or(-1);
+            let uid_to_rgba = get_uniform_location(&gl, shader, "to_rgba").unwrap_or(-1);
 
             Ok(Transition {
                 gl,
@@ -431,6 +511,10 @@ pub mod yuv_dual {
                 uid_from_size,
                 uid_to_pos,
                 uid_to_size,
+                uid_from_src,
+                uid_to_src,
+                uid_from_rgba,
+                uid_to_rgba,
             })
         }
     }
@@ -467,6 +551,13 @@ pub mod yuv_dual {
                 gl.Uniform2f(self.uid_from_size, unis.from_size.x0(), unis.from_size.x1());
                 gl.Uniform2f(self.uid_to_pos, unis.to_pos.x0(), unis.to_pos.x1());
                 gl.Uniform2f(self.uid_to_size, unis.to_size.x0(), unis.to_size.x1());
+                uniform_rect(gl, self.uid_from_src, &unis.from_src);
+                uniform_rect(gl, self.uid_to_src, &unis.to_src);
+                gl.Uniform1i(self.uid_from_rgba, unis.from_rgba.into());
+                gl.Uniform1i(self.uid_to_rgba, unis.to_rgba.into());
+                // premultiplied, see FS_COMMON
+                gl.Enable(gl::BLEND);
+                gl.BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
                 gl.ActiveTexture(gl::TEXTURE0);
                 gl.BindTexture(gl::TEXTURE_2D, from_tex);
                 gl.ActiveTexture(gl::TEXTURE1);
@@ -491,57 +582,134 @@ pub mod yuv_dual {
     #version 300 es
     uniform mat4 model;
     uniform mat4 camera;
-    uniform vec2 from_pos;
-    uniform vec2 from_size;
-    uniform vec2 to_pos;
-    uniform vec2 to_size;
 
     layout (location = 0) in vec2 a_pos;
     layout (location = 1) in vec2 a_tex;
 
-    out vec2 v_tex0;
-    out vec2 v_tex1;
+    out vec2 v_pos;
 
     void main() {
         gl_Position = camera * model * vec4(a_pos, 0.0, 1.0);
-        v_tex0 = (a_tex - from_pos) / from_size;
-        v_tex1 = (a_tex - to_pos) / to_size;
+        v_pos = a_tex;
     }"#;
 
     // ----------------------------------------------------------------------------
-    const FS_TEXTURE: &str = r#"
+    // `from_at` and `to_at` give the photos at a position of the screen, from
+    // (0, 0) to (1, 1), in YCbCr premultiplied with their alpha. Outside the
+    // photos they are transparent and show what is below, e.g. the letterbox.
+    // The effects blend them in `blend`.
+    const FS_COMMON: &str = r#"
     #version 300 es
     uniform sampler2D from_tex;
     uniform sampler2D to_tex;
+    uniform mediump vec2 from_pos;
+    uniform mediump vec2 from_size;
+    uniform mediump vec2 to_pos;
+    uniform mediump vec2 to_size;
+    uniform mediump vec4 from_src;
+    uniform mediump vec4 to_src;
     uniform mediump float progress;
+    uniform bool from_rgba;
+    uniform bool to_rgba;
 
-    in mediump vec2 v_tex0;
-    in mediump vec2 v_tex1;
+    in mediump vec2 v_pos;
     out mediump vec4 FragColor;
 
-    void main() {
-        mediump vec3 from_yuv;
-        if (v_tex0.x >= 0.0 && v_tex0.x <= 1.0 &&
-            v_tex0.y >= 0.0 && v_tex0.y <= 1.0) {
-            from_yuv = texture(from_tex, v_tex0.st).rgb - vec3(0.0, 0.5, 0.5);
-        } else {
-            from_yuv = vec3(0.1, 0.0, 0.0);
+    // RGBA textures (PNG photos) are converted and keep their transparency
+    mediump vec4 to_ycbcr(mediump vec4 texel, bool rgba) {
+        if (!rgba) {
+            return vec4(texel.rgb - vec3(0.0, 0.5, 0.5), 1.0);
         }
+        mediump vec3 yuv;
+        yuv.x = dot(texel.rgb, vec3(0.299, 0.587, 0.114));
+        yuv.y = dot(texel.rgb, vec3(-0.169, -0.331, 0.5));
+        yuv.z = dot(texel.rgb, vec3(0.5, -0.419, -0.081));
+        return vec4(yuv * texel.a, texel.a);
+    }
 
-        mediump vec3 to_yuv;
-        if (v_tex1.x >= 0.0 && v_tex1.x <= 1.0 &&
-            v_tex1.y >= 0.0 && v_tex1.y <= 1.0) {
-            to_yuv = texture(to_tex, v_tex1.st).rgb - vec3(0.0, 0.5, 0.5);
-        } else {
-            to_yuv = vec3(0.1, 0.0, 0.0);
+    mediump vec4 photo_at(sampler2D tex, bool rgba, mediump vec2 p,
+                          mediump vec2 pos, mediump vec2 size, mediump vec4 src) {
+        mediump vec2 t = (p - pos) / size;
+        if (t.x < 0.0 || t.x > 1.0 || t.y < 0.0 || t.y > 1.0) {
+            return vec4(0.0);
         }
+        return to_ycbcr(texture(tex, src.xy + t * src.zw), rgba);
+    }
+
+    mediump vec4 from_at(mediump vec2 p) {
+        return photo_at(from_tex, from_rgba, p, from_pos, from_size, from_src);
+    }
 
-        mediump vec3 yuv = mix(from_yuv, to_yuv, progress);
+    mediump vec4 to_at(mediump vec2 p) {
+        return photo_at(to_tex, to_rgba, p, to_pos, to_size, to_src);
+    }
+    "#;
 
+    // ----------------------------------------------------------------------------
+    const FS_MAIN: &str = r#"
+    void main() {
+        mediump vec4 yuva = blend(v_pos);
+        mediump vec3 yuv = yuva.xyz;
         mediump vec3 rgb;
         rgb.r = yuv.x + 1.402 * yuv.z;
         rgb.g = yuv.x - 0.344 * yuv.y - 0.714 * yuv.z;
         rgb.b = yuv.x + 1.772 * yuv.y;
-        FragColor = vec4(rgb, 1.0);
+        FragColor = vec4(rgb, yuva.a);
     }"#;
+
+    // ----------------------------------------------------------------------------
+    // The `blend` of each effect, wipes and the circle have soft edges
+    fn effect(kind: TransitionKind) -> &'static str {
+        match kind {
+            TransitionKind::Crossfade => {
+                r#"
+    mediump vec4 blend(mediump vec2 p) {
+        return mix(from_at(p), to_at(p), progress);
+    }"#
+            }
+            TransitionKind::WipeLeft => {
+                r#"
+    mediump vec4 blend(mediump vec2 p) {
+        mediump float edge = 1.0 - progress * 1.05;
+        return mix(from_at(p), to_at(p), smoothstep(edge, edge + 0.05, p.x));
+    }"#
+            }
+            TransitionKind::WipeRight => {
+                r#"
+    mediump vec4 blend(mediump vec2 p) {
+        mediump float edge = progress * 1.05;
+        return mix(from_at(p), to_at(p), 1.0 - smoothstep(edge - 0.05, edge, p.x));
+    }"#
+            }
+            TransitionKind::Circle => {
+                r#"
+    mediump vec4 blend(mediump vec2 p) {
+        // width over height of the screen, the circle stays round
+        mediump float aspect = abs(dFdy(p.y) / dFdx(p.x));
+        mediump vec2 d = (p - 0.5) * vec2(aspect, 1.0);
+        mediump float radius = progress * (0.5 * length(vec2(aspect, 1.0)) + 0.05);
+        mediump float inside = 1.0 - smoothstep(radius - 0.05, radius, length(d));
+        return mix(from_at(p), to_at(p), inside);
+    }"#
+            }
+            TransitionKind::Push => {
+                r#"
+    mediump vec4 blend(mediump vec2 p) {
+        mediump vec2 shift = vec2(progress, 0.0);
+        if (p.x < 1.0 - progress) {
+            return from_at(p + shift);
+        }
+        return to_at(p + shift - vec2(1.0, 0.0));
+    }"#
+            }
+            TransitionKind::Zoom => {
+                r#"
+    mediump vec4 blend(mediump vec2 p) {
+        mediump vec4 from_yuv = from_at(0.5 + (p - 0.5) / (1.0 + progress));
+        mediump vec4 to_yuv = to_at(0.5 + (p - 0.5) * (1.5 - 0.5 * progress));
+        return mix(from_yuv, to_yuv, progress);
+    }"#
+            }
+        }
+    }
 }
@@ -1,11 +1,14 @@
 use crate::core::gl_canvas::Canvas;
 use crate::core::gl_graphics::{
-    create_framebuffer, create_program, create_texture_vao, print_opengl_info,
+    create_framebuffer, create_program, create_texture_vao, get_uniform_location, print_opengl_info,
 };
-use crate::core::gl_pipeline::{self, GlUniforms, msdf_tex, v_pos_tex, v_yuv_tex, yuv_dual};
-use crate::error::Result;
+use crate::core::gl_pipeline::{
+    self, GlTransition, GlUniforms, msdf_tex, v_pos_tex, v_yuv_tex, yuv_dual,
+};
+use crate::error::{Result, ResultExt};
+use crate::gfx::transition::TransitionKind;
 use crate::gl::opengl as gl;
-use crate::v2d::{affine4x4, m4x4::M4x4, v2::V2};
+use crate::v2d::{affine4x4, m4x4::M4x4, rect::Rect, v2::V2};
 use std::rc::Rc;
 
 // --------------------------------------------------------------------------------
@@ -25,21 +28,37 @@ const FS_TEXTURE: &str = r#"
 in mediump vec2 TexCoord;
 out mediump vec4 FragColor;
 uniform mediump sampler2D screen;
+uniform mediump float brightness;
 
 void main() {
-    FragColor = texture(screen, TexCoord.st);
+    FragColor = vec4(texture(screen, TexCoord.st).rgb * brightness, 1.0);
 }"#;
 
+// --------------------------------------------------------------------------------
+// Depth convention of the projection matrices used with the renderer.
+// Standard: affine4x4::perspective, depth [-1, 1], near < far.
+// ReverseZ: affine4x4::perspective_reverse_z and the infinite variant, depth
+// [0, 1] with near = 1, for better precision of distant geometry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthMode {
+    #[default]
+    Standard,
+    ReverseZ,
+}
+
 // --------------------------------------------------------------------------------
 pub struct Renderer {
     gl: Rc<gl::OpenGlFunctions>,
     pipelines: Vec<Box<dyn gl_pipeline::GlPipeline>>,
-    transition_pipelines: Vec<Box<dyn gl_pipeline::GlTransition>>,
+    transition_pipelines: Vec<Box<dyn gl_pipeline::GlTransition>>, // by TransitionKind
     texture_vao: gl::GLuint,
     texture_program: gl::GLuint,
+    uid_brightness: gl::GLint,
+    brightness: f32, // of the final frame, dimmed in dark rooms
     fbo: gl::GLuint,
     color_tex: gl::GLuint,
     depth_tex: gl::GLuint,
+    depth_mode: DepthMode,
 }
 
 impl Renderer {
@@ -48,26 +67,76 @@ impl Renderer {
         print_opengl_info(&gl);
 
         let texture_vao = create_texture_vao(&gl);
-        let texture_program = create_program(&gl, "texture", VS_TEXTURE, FS_TEXTURE)?;
-        let (fbo, color_tex, depth_tex) = create_framebuffer(&gl, width, height)?;
+        let texture_program = create_program(&gl, "texture", VS_TEXTURE, FS_TEXTURE)
+            .context("creating the 2nd render pass")?;
+        let uid_brightness = get_uniform_location(&gl, texture_program, "brightness").unwrap_or(-1);
+        let (fbo, color_tex, depth_tex) = create_framebuffer(&gl, width, height)
+            .with_context(|| format!("creating a {width}x{height} framebuffer"))?;
 
-        let rgb_pipe = Box::new(v_pos_tex::Pipeline::new(Rc::clone(&gl))?);
-        let yuv_pipe = Box::new(v_yuv_tex::Pipeline::new(Rc::clone(&gl))?);
-        let msdf_pipe = Box::new(msdf_tex::Pipeline::new(Rc::clone(&gl))?);
-        let dual_pipe = Box::new(yuv_dual::Transition::new(Rc::clone(&gl))?);
+        let pipeline = |name: &str| format!("creating the {name} pipeline");
+        let rgb_pipe = v_pos_tex::Pipeline::new(Rc::clone(&gl)).context(&pipeline("RGB"))?;
+        let yuv_pipe = v_yuv_tex::Pipeline::new(Rc::clone(&gl)).context(&pipeline("YUV"))?;
+        let msdf_pipe = msdf_tex::Pipeline::new(Rc::clone(&gl)).context(&pipeline("MSDF"))?;
+        let transition_pipe = |kind: TransitionKind| -> Result<Box<dyn GlTransition>> {
+            let pipe =
+                yuv_dual::Transition::new(Rc::clone(&gl), kind).context(&pipeline(kind.name()))?;
+            Ok(Box::new(pipe))
+        };
+        let transition_pipes = TransitionKind::ALL.into_iter().map(transition_pipe);
+        let transition_pipelines = transition_pipes.collect::<Result<_>>()?;
 
         Ok(Self {
             gl,
-            pipelines: vec![rgb_pipe, yuv_pipe, msdf_pipe],
-            transition_pipelines: vec![dual_pipe],
+            pipelines: vec![Box::new(rgb_pipe), Box::new(yuv_pipe), Box::new(msdf_pipe)],
+            transition_pipelines,
             texture_vao,
             texture_program,
+            uid_brightness,
+            brightness: 1.0,
             fbo,
             color_tex,
             depth_tex,
+            depth_mode: DepthMode::Standard,
         })
     }
 
+    // ----------------------------------------------------------------------------
+    // Falls back to standard depth if the driver lacks clip control or
+    // glClearDepthf.
+    pub fn set_depth_mode(&mut self, mode: DepthMode) {
+        let gl = &self.gl;
+        self.depth_mode = match mode {
+            DepthMode::Standard => unsafe {
+                // the defaults, missing functions leave them as they are
+                gl.ClipControl(gl::LOWER_LEFT, gl::NEGATIVE_ONE_TO_ONE);
+                gl.DepthFunc(gl::LESS);
+                gl.ClearDepthf(1.0);
+                DepthMode::Standard
+            },
+            DepthMode::ReverseZ => unsafe {
+                if gl.ClearDepthf(0.0) && gl.ClipControl(gl::LOWER_LEFT, gl::ZERO_TO_ONE) {
+                    gl.DepthFunc(gl::GREATER);
+                    DepthMode::ReverseZ
+                } else {
+                    log::warn!("Reverse-Z depth not supported by the driver");
+                    gl.ClearDepthf(1.0);
+                    DepthMode::Standard
+                }
+            },
+        };
+    }
+
+    // ----------------------------------------------------------------------------
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
+    }
+
+    // ----------------------------------------------------------------------------
+    // Scales the colors of the final frame, 1.0 is full brightness
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.brightness = brightness.clamp(0.0, 1.0);
+    }
+
     // ----------------------------------------------------------------------------
     fn render_1st_pass(&self, canvas: &Canvas) -> Result<()> {
         let gl = &self.gl;
@@ -89,11 +158,17 @@ impl Renderer {
             model: M4x4::identity(),
             camera,
             mat_id: 0,
+            opacity: 1.0,
+            src: Rect::unit(),
             progress: 0.0,
             from_pos: V2::zero(),
             from_size: V2::zero(),
             to_pos: V2::zero(),
             to_size: V2::zero(),
+            from_src: Rect::unit(),
+            to_src: Rect::unit(),
+            from_rgba: false,
+            to_rgba: false,
         };
 
         uniforms.model = M4x4::identity();
@@ -103,11 +178,15 @@ impl Renderer {
             uniforms.from_size = transition.from_size;
             uniforms.to_pos = transition.to_pos;
             uniforms.to_size = transition.to_size;
-            uniforms.progress = transition.progress;
+            uniforms.from_src = transition.from_src;
+            uniforms.to_src = transition.to_src;
+            uniforms.from_rgba = transition.from_rgba;
+            uniforms.to_rgba = transition.to_rgba;
+            uniforms.progress = transition.easing.apply(transition.progress);
             let mesh = canvas.mesh(transition.mesh_id);
             let pipe = self.transition_pipelines.get(transition.pipeline_id);
-            let from = canvas.materials().get(transition.from_id);
-            let to = canvas.materials().get(transition.to_id);
+            let from = canvas.material(transition.from_id);
+            let to = canvas.material(transition.to_id);
             match (mesh, pipe, from, to) {
                 (Some(mesh), Some(pipe), Some(from), Some(to)) => {
                     pipe.render(mesh, from, to, &uniforms)?;
@@ -121,11 +200,13 @@ impl Renderer {
         for obj in canvas.objects() {
             let mesh = canvas.mesh(obj.mesh_id);
             let pipe = self.pipelines.get(obj.pipeline_id);
-            let material = canvas.materials().get(obj.material_id);
+            let material = canvas.material(obj.material_id);
             match (mesh, pipe, material) {
                 (Some(mesh), Some(pipe), Some(material)) => {
                     uniforms.model = obj.transform;
                     uniforms.mat_id = obj.material_id as gl::GLint;
+                    uniforms.opacity = obj.opacity;
+                    uniforms.src = obj.src;
                     pipe.render(mesh, material, &uniforms)?;
                 }
                 _ => {
@@ -145,6 +226,7 @@ impl Renderer {
             gl.Disable(gl::DEPTH_TEST);
 
             gl.UseProgram(self.texture_program);
+            gl.Uniform1f(self.uid_brightness, self.brightness);
             gl.BindVertexArray(self.texture_vao);
             gl.ActiveTexture(gl::TEXTURE0);
             gl.BindTexture(gl::TEXTURE_2D, self.color_tex);
@@ -160,9 +242,48 @@ impl Renderer {
         Ok(())
     }
 
+    // ----------------------------------------------------------------------------
+    // A black frame instead of the canvas, e.g. while the display sleeps
+    pub fn clear(&self) {
+        let gl = &self.gl;
+        unsafe {
+            gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl.ClearColor(0.0, 0.0, 0.0, 1.0);
+            gl.Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    // ----------------------------------------------------------------------------
+    // Top-down RGB24 pixels of the rendered frame in the default framebuffer
+    pub fn read_pixels(&self, cx: usize, cy: usize) -> Vec<u8> {
+        let gl = &self.gl;
+        let mut rgba = vec![0u8; cx * cy * 4];
+        unsafe {
+            gl.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            gl.ReadPixels(
+                0,
+                0,
+                cx as gl::GLsizei,
+                cy as gl::GLsizei,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                rgba.as_mut_ptr() as *mut _,
+            );
+        }
+
+        // OpenGL rows start at the bottom
+        let mut rgb = Vec::with_capacity(cx * cy * 3);
+        for row in rgba.chunks_exact(cx * 4).rev() {
+            for px in row.chunks_exact(4) {
+                rgb.extend_from_slice(&px[..3]);
+            }
+        }
+        rgb
+    }
+
     // ----------------------------------------------------------------------------
     pub fn resize(&self, cx: i32, cy: i32) {
-        println!("Resize to {cx} x {cy}");
+        log::info!("Resize to {cx} x {cy}");
         unsafe { self.gl.Viewport(0, 0, cx, cy) };
     }
 }
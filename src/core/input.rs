@@ -1,12 +1,105 @@
+use crate::error::{Error, Result};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Instant;
+
 // ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Key {
     Home,
     Exit,
     NextScene,
     PrevScene,
+    ToggleOverlay,
+}
+
+impl FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "home" => Ok(Key::Home),
+            "exit" => Ok(Key::Exit),
+            "next-scene" => Ok(Key::NextScene),
+            "prev-scene" => Ok(Key::PrevScene),
+            "toggle-overlay" => Ok(Key::ToggleOverlay),
+            _ => Err(Error::InvalidArgument { arg: s.to_string() }),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Key::Home => "home",
+            Key::Exit => "exit",
+            Key::NextScene => "next-scene",
+            Key::PrevScene => "prev-scene",
+            Key::ToggleOverlay => "toggle-overlay",
+        };
+        f.write_str(name)
+    }
 }
 
 // ----------------------------------------------------------------------------
+// Platform independent names of the physical keys that can be bound. The
+// platform layers translate their key codes into these names.
+pub const LETTER_KEYS: [&str; 26] = [
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S",
+    "T", "U", "V", "W", "X", "Y", "Z",
+];
+pub const DIGIT_KEYS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+pub const FUNCTION_KEYS: [&str; 12] = [
+    "F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8", "F9", "F10", "F11", "F12",
+];
+
+// ----------------------------------------------------------------------------
+// Maps key names to actions
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(String, Key)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Keymap {
+            bindings: Vec::new(),
+        };
+        keymap.bind("Escape", Key::Exit);
+        keymap.bind("Home", Key::Home);
+        keymap.bind("Left", Key::PrevScene);
+        keymap.bind("Right", Key::NextScene);
+        keymap.bind("F1", Key::ToggleOverlay);
+        keymap
+    }
+}
+
+impl Keymap {
+    // ------------------------------------------------------------------------
+    // Replaces an existing binding of the same key name
+    pub fn bind(&mut self, name: &str, key: Key) {
+        self.unbind(name);
+        self.bindings.push((name.to_string(), key));
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn unbind(&mut self, name: &str) {
+        self.bindings.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn key(&self, name: &str) -> Option<Key> {
+        self.bindings
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, key)| *key)
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Event {
     MouseMove { x: i32, y: i32 },
     ButtonDown { button: u32 },
@@ -16,9 +109,71 @@ pub enum Event {
     KeyUp { key: Key },
 }
 
+// Recorded as e.g. "key-down next-scene" or "mouse-move 120 80"
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::MouseMove { x, y } => write!(f, "mouse-move {x} {y}"),
+            Event::ButtonDown { button } => write!(f, "button-down {button}"),
+            Event::ButtonUp { button } => write!(f, "button-up {button}"),
+            Event::Wheel { delta } => write!(f, "wheel {delta}"),
+            Event::KeyDown { key } => write!(f, "key-down {key}"),
+            Event::KeyUp { key } => write!(f, "key-up {key}"),
+        }
+    }
+}
+
+impl FromStr for Event {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidArgument { arg: s.to_string() };
+        let mut words = s.split_whitespace();
+        let mut arg = || words.next().ok_or_else(invalid);
+        let event = match arg()? {
+            "mouse-move" => Event::MouseMove {
+                x: arg()?.parse().map_err(|_| invalid())?,
+                y: arg()?.parse().map_err(|_| invalid())?,
+            },
+            "button-down" => Event::ButtonDown {
+                button: arg()?.parse().map_err(|_| invalid())?,
+            },
+            "button-up" => Event::ButtonUp {
+                button: arg()?.parse().map_err(|_| invalid())?,
+            },
+            "wheel" => Event::Wheel {
+                delta: arg()?.parse().map_err(|_| invalid())?,
+            },
+            "key-down" => Event::KeyDown {
+                key: arg()?.parse()?,
+            },
+            "key-up" => Event::KeyUp {
+                key: arg()?.parse()?,
+            },
+            _ => return Err(invalid()),
+        };
+        Ok(event)
+    }
+}
+
 // ----------------------------------------------------------------------------
+// An event with the time it was received, replayed events are received when
+// they are taken
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedEvent {
+    pub t: Instant,
+    pub event: Event,
+}
+
+// ----------------------------------------------------------------------------
+// Events are taken once per update. A recording lists them by the number of
+// the update that took them, one "<update> <event>" line each, so a replay
+// with the same update interval feeds them to the same updates again.
 pub struct Input {
-    events: Vec<Event>,
+    events: Vec<TimedEvent>,
+    update: u64, // updates so far
+    recording: Option<Box<dyn Write>>,
+    replay: VecDeque<(u64, Event)>,
 }
 
 // ----------------------------------------------------------------------------
@@ -31,14 +186,137 @@ impl Default for Input {
 // ----------------------------------------------------------------------------
 impl Input {
     pub fn new() -> Input {
-        Input { events: Vec::new() }
+        Input {
+            events: Vec::new(),
+            update: 0,
+            recording: None,
+            replay: VecDeque::new(),
+        }
     }
 
     pub fn add_event(&mut self, event: Event) {
-        self.events.push(event);
+        self.add_event_at(Instant::now(), event);
+    }
+
+    pub fn add_event_at(&mut self, t: Instant, event: Event) {
+        self.events.push(TimedEvent { t, event });
+    }
+
+    pub fn take_events(&mut self) -> Vec<TimedEvent> {
+        while let Some(&(update, event)) = self.replay.front()
+            && update <= self.update
+        {
+            self.replay.pop_front();
+            self.add_event(event);
+        }
+        let events = std::mem::take(&mut self.events);
+        if let Some(recording) = &mut self.recording {
+            for TimedEvent { event, .. } in &events {
+                // lines are written right away to survive a crash
+                if let Err(e) = writeln!(recording, "{} {event}", self.update) {
+                    log::warn!("Failed to record input: {e:?}");
+                    self.recording = None;
+                    break;
+                }
+            }
+        }
+        self.update += 1;
+        events
+    }
+
+    // ------------------------------------------------------------------------
+    // Writes the taken events to `writer` from now on
+    pub fn record(&mut self, writer: impl Write + 'static) {
+        self.recording = Some(Box::new(writer));
+    }
+
+    // ------------------------------------------------------------------------
+    // Feeds the events of a recording to the updates, empty lines and lines
+    // starting with '#' are skipped
+    pub fn replay(&mut self, recording: &str) -> Result<()> {
+        for line in recording.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || Error::InvalidArgument {
+                arg: line.to_string(),
+            };
+            let (update, event) = line.split_once(' ').ok_or_else(invalid)?;
+            let update: u64 = update.parse().map_err(|_| invalid())?;
+            self.replay.push_back((update, event.parse()?));
+        }
+        self.replay
+            .make_contiguous()
+            .sort_by_key(|(update, _)| *update);
+        Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keymap() {
+        let mut keymap = Keymap::default();
+        assert_eq!(keymap.key("F1"), Some(Key::ToggleOverlay));
+        assert_eq!(keymap.key("escape"), Some(Key::Exit));
+        assert_eq!(keymap.key("Q"), None);
+
+        keymap.bind("q", "exit".parse().unwrap());
+        keymap.bind("f1", Key::Home);
+        assert_eq!(keymap.key("Q"), Some(Key::Exit));
+        assert_eq!(keymap.key("F1"), Some(Key::Home));
+
+        keymap.unbind("Escape");
+        assert_eq!(keymap.key("Escape"), None);
+        assert!("jump".parse::<Key>().is_err());
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let buf = SharedBuf::default();
+        let mut input = Input::new();
+        input.record(buf.clone());
+        input.take_events();
+        input.add_event(Event::KeyDown {
+            key: Key::NextScene,
+        });
+        input.add_event(Event::MouseMove { x: 120, y: -8 });
+        assert_eq!(input.take_events().len(), 2);
+        input.take_events();
+        input.add_event(Event::Wheel { delta: -120 });
+        input.take_events();
+
+        let recording = String::from_utf8(buf.0.borrow().clone()).unwrap();
+        assert_eq!(
+            recording,
+            "1 key-down next-scene\n1 mouse-move 120 -8\n3 wheel -120\n"
+        );
+
+        let mut replay = Input::new();
+        replay.replay(&format!("# session\n{recording}")).unwrap();
+        let updates: Vec<Vec<_>> = (0..4)
+            .map(|_| replay.take_events().iter().map(|e| e.event).collect())
+            .collect();
+        assert!(updates[0].is_empty() && updates[2].is_empty());
+        assert_eq!(updates[1][1], Event::MouseMove { x: 120, y: -8 });
+        assert_eq!(updates[3], [Event::Wheel { delta: -120 }]);
 
-    pub fn take_events(&mut self) -> Vec<Event> {
-        std::mem::take(&mut self.events)
+        assert!(replay.replay("1 key-down jump").is_err());
+        assert!(replay.replay("key-down exit").is_err());
     }
 }
@@ -3,6 +3,8 @@ use crate::error::Result;
 pub mod app_loop;
 pub mod camera;
 pub mod clock;
+pub mod display;
+pub mod frame_stats;
 pub mod gl_canvas;
 pub mod gl_graphics;
 pub mod gl_pipeline;
@@ -24,5 +26,11 @@ pub trait IApp {
         dt: std::time::Duration,
         input: &mut input::Input,
     ) -> Result<()>;
-    fn render(&mut self, t: &std::time::Instant) -> Result<()>;
+    // `alpha` is the fraction of the update period between the last two
+    // updates the frame shows, 1.0 is the state of the last update
+    fn render(&mut self, t: &std::time::Instant, alpha: f32) -> Result<()>;
+    // Nothing moves, the loop runs at its idle rates
+    fn is_idle(&self) -> bool;
+    // While idle, frames are only rendered if they changed
+    fn needs_redraw(&self) -> bool;
 }
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // ----------------------------------------------------------------------------
 #[derive(Debug)]
@@ -13,6 +13,9 @@ pub enum Error {
     InvalidPhotoId,
     InvalidCString,
     InvalidLocation,
+    InvalidRequest,
+    InvalidResponse,
+    InvalidUrl,
     InvalidColorFormat,
     InvalidTextureSize,
     InvalidTextureFormat,
@@ -55,22 +58,115 @@ pub enum Error {
     Png {
         err: miniz::png_read::Error,
     },
+    Jpeg {
+        msg: String,
+    },
+    DBus {
+        msg: String,
+    },
+    Ipc {
+        msg: String,
+    },
+    Http {
+        status: u16,
+    },
+    Tls {
+        msg: String,
+    },
+    Egl {
+        msg: String,
+    },
+    Audio {
+        msg: String,
+    },
+    Video {
+        msg: String,
+    },
+    Decode {
+        msg: String,
+    },
+    Font {
+        msg: String,
+    },
+    Script {
+        msg: String,
+    },
     Serde {
         line: usize,
         column: usize,
         msg: String,
     },
+    // `source` occurred while doing `context`, e.g. "reading photo.json"
+    Context {
+        context: String,
+        source: Box<Error>,
+    },
 }
 
 // ----------------------------------------------------------------------------
+impl Error {
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Error::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // The innermost error without its contexts
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root_cause(),
+            e => e,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Contexts from the outside in, e.g.
+// "loading config kitchen.toml: reading kitchen.toml: FileIo { .. }"
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let err = format!("{:?}", self);
-        f.write_str(&err)
+        match self {
+            Error::Context { context, source } => write!(f, "{context}: {source}"),
+            e => write!(f, "{e:?}"),
+        }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Context { source, .. } => Some(source.as_ref()),
+            Error::FileIo { err } => Some(err),
+            Error::ParseInt { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Adds what was being done to errors on their way up, e.g.
+// `std::fs::read(path).with_path("reading", path)?`
+pub trait ResultExt<T> {
+    fn context(self, context: &str) -> Result<T>;
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T>;
+    fn with_path(self, operation: &str, path: &Path) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for std::result::Result<T, E> {
+    fn context(self, context: &str) -> Result<T> {
+        self.map_err(|e| e.into().context(context))
+    }
+
+    fn with_context(self, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|e| e.into().context(context()))
+    }
+
+    fn with_path(self, operation: &str, path: &Path) -> Result<T> {
+        self.map_err(|e| e.into().context(format!("{operation} {}", path.display())))
+    }
+}
 
 // ----------------------------------------------------------------------------
 impl From<std::io::Error> for Error {
@@ -121,3 +217,29 @@ impl From<miniz::png_read::Error> for Error {
 
 // ----------------------------------------------------------------------------
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_chain() {
+        let read = std::fs::read_to_string("/nonexistent/photo.json");
+        let e = read
+            .with_path("reading", Path::new("/nonexistent/photo.json"))
+            .context("loading photos")
+            .unwrap_err();
+        let text = e.to_string();
+        assert!(text.starts_with("loading photos: reading /nonexistent/photo.json: FileIo"));
+        assert!(matches!(e.root_cause(), Error::FileIo { .. }));
+
+        let io = std::error::Error::source(&e)
+            .and_then(|e| e.source())
+            .unwrap();
+        assert!(io.source().unwrap().is::<std::io::Error>());
+        assert!(matches!(
+            Error::EmptyPhotos.root_cause(),
+            Error::EmptyPhotos
+        ));
+    }
+}
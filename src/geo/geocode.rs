@@ -0,0 +1,408 @@
+use crate::app::GeocodingConfig;
+use crate::error::{Error, Result, ResultExt};
+use crate::geo::Coord;
+use crate::net::fetch::{self, Fetcher};
+use crate::scene::SystemEvent;
+use crate::scene::photo::Photo;
+use crate::util::locale::LocaleId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+// ----------------------------------------------------------------------------
+// Results of earlier lookups, null where the service found nothing. The file
+// can be edited to add places without any network access, e.g.
+//   { "places": { "stahlbrode, germany, baltic sea": [54.234, 13.287] },
+//     "coords": { "38.71,-9.14": ["Lisbon", "Portugal"] } }
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct GeoCache {
+    places: BTreeMap<String, Option<Coord>>, // place query => position
+    coords: BTreeMap<String, Option<Vec<String>>>, // `coord_key` => place
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Lookup {
+    Place(String), // `place_query` of a photo without coordinates
+    Coord(String), // `coord_key` of a photo without place
+}
+
+impl Lookup {
+    // ------------------------------------------------------------------------
+    fn fetch_key(&self) -> String {
+        match self {
+            Lookup::Place(query) => format!("geocode:{query}"),
+            Lookup::Coord(key) => format!("reverse:{key}"),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // zoom 10 asks for the city rather than the street
+    fn request(&self, url: &str, language: &str) -> String {
+        match self {
+            Lookup::Place(query) => format!(
+                "{url}/search?format=jsonv2&limit=1&accept-language={language}&q={}",
+                fetch::encode_component(query)
+            ),
+            Lookup::Coord(key) => {
+                let (lat, lon) = key.split_once(',').unwrap_or_default();
+                format!(
+                    "{url}/reverse?format=jsonv2&zoom=10&accept-language={language}&lat={lat}&lon={lon}"
+                )
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Resolves the free text `place` of photos without "coord" to coordinates
+// and the coordinates of photos without `place` to a readable place, e.g.
+// ["Lisbon", "Portugal"]. Known results come from the cache file, unknown
+// ones are looked up with a Nominatim compatible API if configured, one
+// request at a time as public services ask for. Lookups that fail for network
+// reasons are retried once the frame is online again.
+pub struct Geocoder {
+    url: Option<String>,
+    language: &'static str,
+    cache_path: PathBuf,
+    cache: GeoCache,
+    fetcher: Fetcher,
+    queue: VecDeque<Lookup>,
+    pending: Option<Lookup>,
+    failed: BTreeSet<Lookup>,
+}
+
+impl Geocoder {
+    // ------------------------------------------------------------------------
+    pub fn new(config: &GeocodingConfig, locale: LocaleId, fetcher: Fetcher) -> Self {
+        let cache = match load_cache(&config.cache) {
+            Ok(cache) => cache,
+            Err(e) => {
+                log::warn!("Failed to read geocoding cache {:?}: {e:?}", config.cache);
+                GeoCache::default()
+            }
+        };
+        Self {
+            url: config
+                .url
+                .as_ref()
+                .map(|url| url.trim_end_matches('/').to_string()),
+            language: locale.language(),
+            cache_path: config.cache.clone(),
+            cache,
+            fetcher,
+            queue: VecDeque::new(),
+            pending: None,
+            failed: BTreeSet::new(),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Fills in cached results and queues the unknown ones, call again after
+    // `handle` returned true
+    pub fn resolve(&mut self, photos: &mut [Photo]) {
+        for photo in photos.iter_mut() {
+            let meta = &mut photo.meta;
+            let lookup = match (meta.coord, meta.place.as_deref()) {
+                (None, Some(place)) => {
+                    let Some(query) = place_query(place) else {
+                        continue;
+                    };
+                    match self.cache.places.get(&query) {
+                        Some(coord) => {
+                            meta.coord = *coord;
+                            continue;
+                        }
+                        None => Lookup::Place(query),
+                    }
+                }
+                (Some(coord), None) if coord.is_valid() => {
+                    let key = coord_key(coord);
+                    match self.cache.coords.get(&key) {
+                        Some(place) => {
+                            meta.place = place.clone();
+                            continue;
+                        }
+                        None => Lookup::Coord(key),
+                    }
+                }
+                _ => continue,
+            };
+            if self.url.is_some()
+                && self.pending.as_ref() != Some(&lookup)
+                && !self.queue.contains(&lookup)
+                && !self.failed.contains(&lookup)
+            {
+                self.queue.push_back(lookup);
+            }
+        }
+        self.dispatch();
+    }
+
+    // ------------------------------------------------------------------------
+    // Takes the fetch results, returns true if a photo can be updated
+    pub fn handle(&mut self, event: &SystemEvent) -> bool {
+        let resolved = match event {
+            SystemEvent::Fetched { key, body } => match self.take_pending(key) {
+                Some(lookup) => self.store(lookup, body),
+                None => false,
+            },
+            // the fetcher would retry, but other lookups shouldn't wait
+            SystemEvent::FetchFailed { key, .. } => {
+                if let Some(lookup) = self.take_pending(key) {
+                    self.fetcher.cancel(key);
+                    self.failed.insert(lookup);
+                }
+                false
+            }
+            SystemEvent::Online => {
+                self.queue.extend(std::mem::take(&mut self.failed));
+                false
+            }
+            _ => false,
+        };
+        self.dispatch();
+        resolved
+    }
+
+    // ------------------------------------------------------------------------
+    fn store(&mut self, lookup: Lookup, body: &[u8]) -> bool {
+        let found = match &lookup {
+            Lookup::Place(query) => parse_search(body).map(|coord| {
+                log::info!("Geocoded {query:?}: {coord:?}");
+                self.cache.places.insert(query.clone(), coord);
+                coord.is_some()
+            }),
+            Lookup::Coord(key) => parse_reverse(body).map(|place| {
+                log::info!("Reverse geocoded {key}: {place:?}");
+                let found = place.is_some();
+                self.cache.coords.insert(key.clone(), place);
+                found
+            }),
+        };
+        match found {
+            Ok(found) => {
+                if let Err(e) = save_cache(&self.cache_path, &self.cache) {
+                    log::warn!("Failed to write geocoding cache: {e:?}");
+                }
+                found
+            }
+            Err(e) => {
+                log::warn!("Invalid geocoding result for {lookup:?}: {e:?}");
+                false
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn take_pending(&mut self, key: &str) -> Option<Lookup> {
+        self.pending.take_if(|pending| pending.fetch_key() == key)
+    }
+
+    // ------------------------------------------------------------------------
+    fn dispatch(&mut self) {
+        if self.pending.is_some() {
+            return;
+        }
+        let (Some(url), Some(lookup)) = (&self.url, self.queue.pop_front()) else {
+            return;
+        };
+        let request = lookup.request(url, self.language);
+        match self.fetcher.fetch(&lookup.fetch_key(), &request) {
+            Ok(()) => self.pending = Some(lookup),
+            Err(e) => {
+                log::error!("Geocoding disabled, invalid URL {url:?}: {e:?}");
+                self.url = None;
+                self.queue.clear();
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Photos within about a kilometer share a cache entry
+fn coord_key(coord: Coord) -> String {
+    format!("{:.2},{:.2}", coord.lat, coord.lon)
+}
+
+// ----------------------------------------------------------------------------
+// Cache key and search text of a place, e.g. "stahlbrode, germany"
+fn place_query(place: &[String]) -> Option<String> {
+    let parts: Vec<_> = place
+        .iter()
+        .map(|p| p.trim().to_lowercase())
+        .filter(|p| !p.is_empty())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+// ----------------------------------------------------------------------------
+// Nominatim returns a list of matches with the coordinates as strings
+fn parse_search(body: &[u8]) -> Result<Option<Coord>> {
+    #[derive(Deserialize)]
+    struct Match {
+        lat: String,
+        lon: String,
+    }
+
+    let matches: Vec<Match> = serde_json::from_slice(body)?;
+    let Some(first) = matches.first() else {
+        return Ok(None);
+    };
+    let coord = Coord {
+        lat: first.lat.parse().map_err(|_| Error::InvalidLocation)?,
+        lon: first.lon.parse().map_err(|_| Error::InvalidLocation)?,
+    };
+    match coord.is_valid() {
+        true => Ok(Some(coord)),
+        false => Err(Error::InvalidLocation),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The most specific locality plus the country, None for positions without an
+// address such as the open sea
+fn parse_reverse(body: &[u8]) -> Result<Option<Vec<String>>> {
+    const LOCALITIES: [&str; 7] = [
+        "city",
+        "town",
+        "village",
+        "hamlet",
+        "municipality",
+        "county",
+        "state",
+    ];
+
+    #[derive(Deserialize)]
+    struct Reverse {
+        address: Option<BTreeMap<String, serde_json::Value>>,
+    }
+
+    let reverse: Reverse = serde_json::from_slice(body)?;
+    let Some(address) = reverse.address else {
+        return Ok(None);
+    };
+    let name = |key: &str| address.get(key).and_then(|v| v.as_str()).map(String::from);
+    let place: Vec<_> = LOCALITIES
+        .iter()
+        .find_map(|key| name(key))
+        .into_iter()
+        .chain(name("country"))
+        .collect();
+    Ok((!place.is_empty()).then_some(place))
+}
+
+// ----------------------------------------------------------------------------
+fn load_cache(path: &Path) -> Result<GeoCache> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).with_path("parsing", path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(GeoCache::default()),
+        Err(e) => Err(e).with_path("reading", path),
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn save_cache(path: &Path, cache: &GeoCache) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(cache)? + "\n").with_path("writing", &tmp)?;
+    std::fs::rename(&tmp, path).with_path("replacing", path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::photo::PhotoMeta;
+    use crate::util::tasks;
+    use std::sync::Arc;
+
+    fn photo(meta: &str) -> Photo {
+        Photo {
+            path: PathBuf::from("photo.webp"),
+            meta: serde_json::from_str::<PhotoMeta>(meta).unwrap(),
+            last_shown: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_search() {
+        let body = br#"[{"lat": "38.7077", "lon": "-9.1365", "name": "Lisboa"}]"#;
+        let coord = parse_search(body).unwrap();
+        assert_eq!(
+            coord,
+            Some(Coord {
+                lat: 38.7077,
+                lon: -9.1365
+            })
+        );
+        assert_eq!(parse_search(b"[]").unwrap(), None);
+        assert!(parse_search(br#"[{"lat": "x", "lon": "1"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_reverse() {
+        let body = br#"{"address": {"town": "Sintra", "state": "Lisboa", "country": "Portugal"}}"#;
+        let place = parse_reverse(body).unwrap().unwrap();
+        assert_eq!(place, ["Sintra", "Portugal"]);
+        assert_eq!(
+            parse_reverse(br#"{"error": "Unable to geocode"}"#).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = std::env::temp_dir().join(format!("home-rs-geo-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = GeocodingConfig {
+            // nothing is fetched, the test feeds the results
+            url: Some(String::from("http://127.0.0.1:9")),
+            cache: dir.join("geocache.json"),
+        };
+        std::fs::write(&config.cache, r#"{ "places": { "atlantis": null } }"#).unwrap();
+
+        let (sender, _events) = tasks::channel();
+        let fetcher = Fetcher::start(sender).unwrap();
+        let mut geocoder = Geocoder::new(&config, LocaleId::Us, fetcher);
+        let mut photos = vec![
+            photo(r#"{ "place": ["Lisbon ", "Portugal"] }"#),
+            photo(r#"{ "place": ["Atlantis"] }"#),
+            photo(r#"{ "coord": [38.7077, -9.1365] }"#),
+        ];
+        geocoder.resolve(&mut photos);
+        let lisbon = Lookup::Place(String::from("lisbon, portugal"));
+        assert_eq!(geocoder.pending, Some(lisbon));
+        assert_eq!(geocoder.queue, [Lookup::Coord(String::from("38.71,-9.14"))]);
+
+        let event = SystemEvent::Fetched {
+            key: String::from("geocode:lisbon, portugal"),
+            body: Arc::from(&br#"[{"lat": "38.7", "lon": "-9.1"}]"#[..]),
+        };
+        assert!(geocoder.handle(&event));
+        geocoder.resolve(&mut photos);
+        assert_eq!(
+            photos[0].meta.coord,
+            Some(Coord {
+                lat: 38.7,
+                lon: -9.1
+            })
+        );
+        assert_eq!(photos[1].meta.coord, None);
+
+        // the reverse lookup is sent once the first one is done
+        let event = SystemEvent::Fetched {
+            key: String::from("reverse:38.71,-9.14"),
+            body: Arc::from(&br#"{"address": {"city": "Lisbon", "country": "Portugal"}}"#[..]),
+        };
+        assert!(geocoder.handle(&event));
+        geocoder.resolve(&mut photos);
+        let place = photos[2].meta.place.as_deref().unwrap();
+        assert_eq!(place, ["Lisbon", "Portugal"]);
+
+        // the results are cached for the next start
+        let cache = load_cache(&config.cache).unwrap();
+        assert_eq!((cache.places.len(), cache.coords.len()), (2, 1));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+pub mod geocode;
+
+use crate::scene::photo::Photo;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ----------------------------------------------------------------------------
+// WGS84 position in degrees, stored as `[lat, lon]` like the "coord" of the
+// photo metadata
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "[f64; 2]", into = "[f64; 2]")]
+pub struct Coord {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl From<[f64; 2]> for Coord {
+    fn from([lat, lon]: [f64; 2]) -> Self {
+        Self { lat, lon }
+    }
+}
+
+impl From<Coord> for [f64; 2] {
+    fn from(coord: Coord) -> Self {
+        [coord.lat, coord.lon]
+    }
+}
+
+// Grid cell of `Coord::region`, in multiples of the cell size
+pub type Region = (i32, i32);
+
+impl Coord {
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.lat) && (-180.0..=180.0).contains(&self.lon)
+    }
+
+    // Cell of a grid with `cell` degrees, e.g. 1.0 for areas of about 100 km
+    pub fn region(&self, cell: f64) -> Region {
+        (
+            (self.lat / cell).floor() as i32,
+            (self.lon / cell).floor() as i32,
+        )
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Indices of the photos with coordinates, grouped by region
+pub fn group_by_region(photos: &[Photo], cell: f64) -> BTreeMap<Region, Vec<usize>> {
+    let mut groups: BTreeMap<Region, Vec<usize>> = BTreeMap::new();
+    for (i, photo) in photos.iter().enumerate() {
+        if let Some(coord) = photo.meta.coord {
+            groups.entry(coord.region(cell)).or_default().push(i);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region() {
+        let coord: Coord = serde_json::from_str("[50.919, 14.0575]").unwrap();
+        assert_eq!(
+            coord,
+            Coord {
+                lat: 50.919,
+                lon: 14.0575
+            }
+        );
+        assert_eq!(coord.region(1.0), (50, 14));
+        assert_eq!(coord.region(10.0), (5, 1));
+        assert_eq!(
+            Coord {
+                lat: -0.5,
+                lon: -70.2
+            }
+            .region(1.0),
+            (-1, -71)
+        );
+        assert!(
+            !Coord {
+                lat: 91.0,
+                lon: 0.0
+            }
+            .is_valid()
+        );
+    }
+}
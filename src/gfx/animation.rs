@@ -1,8 +1,116 @@
+use std::f32::consts::PI;
+
+// ----------------------------------------------------------------------------
+// Maps the linear progress t in [0, 1] to an eased progress. All curves start
+// at 0 and end at 1, Spring and Bounce overshoot or rebound in between.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    Spring,
+    Bounce,
+}
+
+impl Easing {
+    // ------------------------------------------------------------------------
+    // Name in config files, e.g. "sine-in-out"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Easing::Linear),
+            "quad-in" => Some(Easing::QuadIn),
+            "quad-out" => Some(Easing::QuadOut),
+            "quad-in-out" => Some(Easing::QuadInOut),
+            "cubic-in" => Some(Easing::CubicIn),
+            "cubic-out" => Some(Easing::CubicOut),
+            "cubic-in-out" => Some(Easing::CubicInOut),
+            "sine-in" => Some(Easing::SineIn),
+            "sine-out" => Some(Easing::SineOut),
+            "sine-in-out" => Some(Easing::SineInOut),
+            "spring" => Some(Easing::Spring),
+            "bounce" => Some(Easing::Bounce),
+            _ => None,
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - 4.0 * (1.0 - t).powi(3)
+                }
+            }
+            Easing::SineIn => 1.0 - (0.5 * PI * t).cos(),
+            Easing::SineOut => (0.5 * PI * t).sin(),
+            Easing::SineInOut => 0.5 - 0.5 * (PI * t).cos(),
+            Easing::Spring => spring(t),
+            Easing::Bounce => bounce(t),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Damped oscillation around the target value
+fn spring(t: f32) -> f32 {
+    if t >= 1.0 {
+        return 1.0;
+    }
+    const DAMPING: f32 = 6.0;
+    const FREQUENCY: f32 = 4.5;
+    1.0 - (-DAMPING * t).exp() * (2.0 * PI * FREQUENCY * t).cos()
+}
+
+// ----------------------------------------------------------------------------
+// Ball dropped on the target value, rebounding three times
+fn bounce(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug)]
 pub struct Animation<T> {
-    t0: f32, // Start time of the animation
-    t1: f32, // End time of the animation
-    x0: T,   // Start value of the animation
-    x1: T,   // End value of the animation
+    t0: f32,        // Start time of the animation
+    t1: f32,        // End time of the animation
+    x0: T,          // Start value of the animation
+    x1: T,          // End value of the animation
+    easing: Easing, // Progress curve between t0 and t1
 }
 
 impl<T> Animation<T>
@@ -14,7 +122,52 @@ where
 {
     // Create a new animation
     pub fn new(t0: f32, t1: f32, x0: T, x1: T) -> Self {
-        Animation { t0, t1, x0, x1 }
+        Animation {
+            t0,
+            t1,
+            x0,
+            x1,
+            easing: Easing::Linear,
+        }
+    }
+
+    // Use the given easing curve instead of linear blending
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    // Shift the animation by dt
+    pub fn delayed(mut self, dt: f32) -> Self {
+        self.t0 += dt;
+        self.t1 += dt;
+        self
+    }
+
+    // Same time span, running from x1 back to x0
+    pub fn reversed(self) -> Self {
+        Animation {
+            x0: self.x1,
+            x1: self.x0,
+            ..self
+        }
+    }
+
+    // Follow-up animation starting where this one ends
+    pub fn then(&self, duration: f32, x2: T) -> Self {
+        Animation::new(self.t1, self.t1 + duration, self.x1, x2).with_easing(self.easing)
+    }
+
+    pub fn start_time(&self) -> f32 {
+        self.t0
+    }
+
+    pub fn end_time(&self) -> f32 {
+        self.t1
+    }
+
+    pub fn is_finished(&self, t: f32) -> bool {
+        t >= self.t1
     }
 
     // Evaluate the animation at time t
@@ -24,8 +177,245 @@ where
         } else if t >= self.t1 {
             self.x1
         } else {
-            let s = (t - self.t0) / (self.t1 - self.t0);
+            let s = self.easing.apply((t - self.t0) / (self.t1 - self.t0));
             self.x0 + (self.x1 - self.x0) * s
         }
     }
 }
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    pub easing: Easing, // Curve of the segment from the previous keyframe to this
+}
+
+// ----------------------------------------------------------------------------
+// What a track does after its last keyframe.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Repeat {
+    #[default]
+    Once, // hold the last value
+    Loop,     // jump back to the first keyframe
+    PingPong, // run backwards to the first keyframe, then forward again
+}
+
+// ----------------------------------------------------------------------------
+// Sequence of keyframes sorted by time, sampled piecewise with the easing of
+// each segment's end keyframe. Times before the first keyframe hold the first value.
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+    repeat: Repeat,
+}
+
+impl<T> Track<T>
+where
+    T: Copy
+        + std::ops::Sub<Output = T>
+        + std::ops::Add<T, Output = T>
+        + std::ops::Mul<f32, Output = T>,
+{
+    // Create a track holding `value` from `time` on
+    pub fn new(time: f32, value: T) -> Self {
+        Track {
+            keys: vec![Keyframe {
+                time,
+                value,
+                easing: Easing::Linear,
+            }],
+            repeat: Repeat::Once,
+        }
+    }
+
+    // Add a keyframe, `easing` shapes the segment towards it
+    pub fn key(mut self, time: f32, value: T, easing: Easing) -> Self {
+        let pos = self.keys.partition_point(|k| k.time <= time);
+        self.keys.insert(
+            pos,
+            Keyframe {
+                time,
+                value,
+                easing,
+            },
+        );
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    pub fn start_time(&self) -> f32 {
+        self.keys[0].time
+    }
+
+    pub fn end_time(&self) -> f32 {
+        self.keys[self.keys.len() - 1].time
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.end_time() - self.start_time()
+    }
+
+    // Repeating tracks never finish
+    pub fn is_finished(&self, t: f32) -> bool {
+        self.repeat == Repeat::Once && t >= self.end_time()
+    }
+
+    // Evaluate the track at time t
+    pub fn blend(&self, t: f32) -> T {
+        let t = self.local_time(t);
+        let next = self.keys.partition_point(|k| k.time <= t);
+        if next == 0 {
+            return self.keys[0].value;
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1].value;
+        }
+
+        let k0 = &self.keys[next - 1];
+        let k1 = &self.keys[next];
+        let s = k1.easing.apply((t - k0.time) / (k1.time - k0.time));
+        k0.value + (k1.value - k0.value) * s
+    }
+
+    // Map t into the keyframe range according to the repeat mode
+    fn local_time(&self, t: f32) -> f32 {
+        let t0 = self.start_time();
+        let d = self.duration();
+        if t <= t0 || d <= 0.0 {
+            return t;
+        }
+        match self.repeat {
+            Repeat::Once => t,
+            Repeat::Loop => t0 + (t - t0) % d,
+            Repeat::PingPong => {
+                let u = (t - t0) % (2.0 * d);
+                if u <= d { t0 + u } else { t0 + 2.0 * d - u }
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl<T> From<Animation<T>> for Track<T>
+where
+    T: Copy
+        + std::ops::Sub<Output = T>
+        + std::ops::Add<T, Output = T>
+        + std::ops::Mul<f32, Output = T>,
+{
+    fn from(a: Animation<T>) -> Self {
+        Track::new(a.t0, a.x0).key(a.t1, a.x1, a.easing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_float_eq;
+    use crate::v2d::float_eq::float_eq_rel;
+
+    const ALL: [Easing; 12] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::Spring,
+        Easing::Bounce,
+    ];
+
+    #[test]
+    fn test_easing_end_points() {
+        for easing in ALL {
+            assert_float_eq!(easing.apply(0.0), 0.0);
+            assert_float_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_float_eq!(Easing::QuadInOut.apply(0.5), 0.5);
+        assert_float_eq!(Easing::CubicInOut.apply(0.5), 0.5);
+        assert_float_eq!(Easing::SineInOut.apply(0.5), 0.5);
+        assert!(Easing::QuadIn.apply(0.25) < 0.25);
+        assert!(Easing::QuadOut.apply(0.25) > 0.25);
+    }
+
+    #[test]
+    fn test_easing_names() {
+        assert_eq!(Easing::from_name("sine-in-out"), Some(Easing::SineInOut));
+        assert_eq!(Easing::from_name("bounce"), Some(Easing::Bounce));
+        assert_eq!(Easing::from_name("SineInOut"), None);
+    }
+
+    #[test]
+    fn test_animation_chain() {
+        let fade_in = Animation::new(1.0, 2.0, 0.0, 1.0).with_easing(Easing::QuadIn);
+        let fade_out = fade_in.then(2.0, 0.0);
+
+        assert_float_eq!(fade_in.blend(0.0), 0.0);
+        assert_float_eq!(fade_in.blend(1.5), 0.25);
+        assert_float_eq!(fade_in.blend(3.0), 1.0);
+        assert_float_eq!(fade_out.blend(2.0), 1.0);
+        assert_float_eq!(fade_out.blend(3.0), 0.75);
+        assert_float_eq!(fade_out.end_time(), 4.0);
+
+        let back = fade_in.reversed().delayed(1.0);
+        assert_float_eq!(back.blend(2.0), 1.0);
+        assert_float_eq!(back.blend(3.0), 0.0);
+        assert!(back.is_finished(3.0));
+    }
+
+    #[test]
+    fn test_track_segments() {
+        let track = Track::new(0.0, 0.0)
+            .key(1.0, 10.0, Easing::Linear)
+            .key(3.0, 0.0, Easing::QuadIn)
+            .key(4.0, 5.0, Easing::Linear);
+
+        assert_float_eq!(track.blend(-1.0), 0.0);
+        assert_float_eq!(track.blend(0.5), 5.0);
+        assert_float_eq!(track.blend(1.0), 10.0);
+        assert_float_eq!(track.blend(2.0), 7.5);
+        assert_float_eq!(track.blend(3.5), 2.5);
+        assert_float_eq!(track.blend(9.0), 5.0);
+        assert!(track.is_finished(4.0));
+
+        // keys are kept sorted regardless of insertion order
+        let sorted =
+            Track::new(0.0, 0.0)
+                .key(2.0, 2.0, Easing::Linear)
+                .key(1.0, 4.0, Easing::Linear);
+        assert_float_eq!(sorted.blend(1.0), 4.0);
+        assert_float_eq!(sorted.blend(1.5), 3.0);
+    }
+
+    #[test]
+    fn test_track_repeat() {
+        let track = Track::from(Animation::new(1.0, 3.0, 0.0, 2.0));
+
+        let once = track.clone();
+        assert_float_eq!(once.blend(4.0), 2.0);
+
+        let looped = track.clone().with_repeat(Repeat::Loop);
+        assert_float_eq!(looped.blend(4.0), 1.0);
+        assert_float_eq!(looped.blend(6.5), 1.5);
+        assert!(!looped.is_finished(100.0));
+
+        let ping_pong = track.with_repeat(Repeat::PingPong);
+        assert_float_eq!(ping_pong.blend(2.0), 1.0);
+        assert_float_eq!(ping_pong.blend(4.0), 1.0);
+        assert_float_eq!(ping_pong.blend(4.5), 0.5);
+        assert_float_eq!(ping_pong.blend(6.0), 1.0);
+    }
+}
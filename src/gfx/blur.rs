@@ -0,0 +1,159 @@
+// ----------------------------------------------------------------------------
+// Separable blurs on 8 bit images with `channels` interleaved components and
+// tightly packed rows, e.g. Y8 (1) or RGBA (4). Pixels outside the image
+// repeat the edge.
+
+// ----------------------------------------------------------------------------
+// Average over a (2 * radius + 1)^2 window, cost independent of the radius
+pub fn box_blur(data: &mut [u8], cx: usize, cy: usize, channels: usize, radius: usize) {
+    if radius == 0 || cx == 0 || cy == 0 {
+        return;
+    }
+
+    let mut line = Vec::new();
+    for y in 0..cy {
+        let row = &mut data[y * cx * channels..(y + 1) * cx * channels];
+        line.clear();
+        line.extend_from_slice(row);
+        box_line(&line, row, channels, radius);
+    }
+
+    let mut col = vec![0; cy * channels];
+    let mut out = vec![0; cy * channels];
+    for x in 0..cx {
+        for y in 0..cy {
+            let p = (y * cx + x) * channels;
+            col[y * channels..(y + 1) * channels].copy_from_slice(&data[p..p + channels]);
+        }
+        box_line(&col, &mut out, channels, radius);
+        for y in 0..cy {
+            let p = (y * cx + x) * channels;
+            data[p..p + channels].copy_from_slice(&out[y * channels..(y + 1) * channels]);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Running sum along one line of pixels
+fn box_line(src: &[u8], dst: &mut [u8], channels: usize, radius: usize) {
+    let len = src.len() / channels;
+    let last = len as isize - 1;
+    let window = (2 * radius + 1) as u32;
+    let at = |i: isize, ch: usize| src[i.clamp(0, last) as usize * channels + ch] as u32;
+
+    for ch in 0..channels {
+        let r = radius as isize;
+        let mut sum: u32 = (-r..=r).map(|i| at(i, ch)).sum();
+        for i in 0..len as isize {
+            dst[i as usize * channels + ch] = ((sum + window / 2) / window) as u8;
+            sum = sum + at(i + r + 1, ch) - at(i - r, ch);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub fn gaussian_blur(data: &mut [u8], cx: usize, cy: usize, channels: usize, sigma: f32) {
+    if sigma <= 0.0 || cx == 0 || cy == 0 {
+        return;
+    }
+
+    let src: Vec<f32> = data[..cx * cy * channels]
+        .iter()
+        .map(|&v| v as f32)
+        .collect();
+    let blurred = convolve(&src, cx, cy, channels, &gaussian_kernel(sigma));
+    for (d, b) in data.iter_mut().zip(blurred) {
+        *d = b.round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Normalized kernel covering +-3 sigma
+pub fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    let r = (3.0 * sigma).ceil() as isize;
+    let kernel: Vec<f32> = (-r..=r)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    kernel.into_iter().map(|k| k / sum).collect()
+}
+
+// ----------------------------------------------------------------------------
+// Apply a symmetric 1D kernel horizontally and then vertically
+pub fn convolve(src: &[f32], cx: usize, cy: usize, channels: usize, kernel: &[f32]) -> Vec<f32> {
+    let r = (kernel.len() / 2) as isize;
+    let clamp = |i: isize, len: usize| i.clamp(0, len as isize - 1) as usize;
+
+    let mut tmp = vec![0.0; cx * cy * channels];
+    for y in 0..cy {
+        for x in 0..cx {
+            for ch in 0..channels {
+                tmp[(y * cx + x) * channels + ch] = kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(k, w)| {
+                        let sx = clamp(x as isize + k as isize - r, cx);
+                        w * src[(y * cx + sx) * channels + ch]
+                    })
+                    .sum();
+            }
+        }
+    }
+
+    let mut dst = vec![0.0; cx * cy * channels];
+    for y in 0..cy {
+        for x in 0..cx {
+            for ch in 0..channels {
+                dst[(y * cx + x) * channels + ch] = kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(k, w)| {
+                        let sy = clamp(y as isize + k as isize - r, cy);
+                        w * tmp[(sy * cx + x) * channels + ch]
+                    })
+                    .sum();
+            }
+        }
+    }
+    dst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_blur() {
+        let mut y8 = vec![0, 0, 90, 0, 0];
+        box_blur(&mut y8, 5, 1, 1, 1);
+        assert_eq!(y8, vec![0, 30, 30, 30, 0]);
+
+        // channels are blurred independently, edges repeat
+        let mut rgba = vec![
+            10, 200, 0, 255, //
+            40, 200, 0, 255, //
+            10, 200, 0, 255, //
+        ];
+        box_blur(&mut rgba, 1, 3, 4, 1);
+        assert_eq!(rgba[..4], [20, 200, 0, 255]);
+        assert_eq!(rgba[4..8], [20, 200, 0, 255]);
+    }
+
+    #[test]
+    fn test_gaussian_blur() {
+        let kernel = gaussian_kernel(1.0);
+        assert_eq!(kernel.len(), 7);
+        assert!((kernel.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+        let mut flat = vec![123; 6 * 5 * 4];
+        gaussian_blur(&mut flat, 6, 5, 4, 2.0);
+        assert_eq!(flat, vec![123; 6 * 5 * 4]);
+
+        let mut dot = vec![0; 9 * 9];
+        dot[4 * 9 + 4] = 255;
+        gaussian_blur(&mut dot, 9, 9, 1, 1.0);
+        assert!(dot[4 * 9 + 4] < 255 && dot[4 * 9 + 4] > dot[4 * 9 + 5]);
+        assert_eq!(dot[4 * 9 + 3], dot[4 * 9 + 5]);
+        assert_eq!(dot[3 * 9 + 4], dot[4 * 9 + 5]);
+    }
+}
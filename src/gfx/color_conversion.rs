@@ -1,12 +1,23 @@
+use crate::error::{Error, Result};
 use crate::gfx::color_format::ColorFormat;
+use crate::gfx::simd;
 
 // ----------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ImageGeometry {
     pub cx: usize,
     pub cy: usize,
     pub cf: ColorFormat,
 }
 
+// ----------------------------------------------------------------------------
+// Pixels of any format with at least one byte per pixel, rows `stride` bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageBuffer {
+    pub data: Vec<u8>,
+    pub stride: usize,
+}
+
 // ----------------------------------------------------------------------------
 pub struct ImagePal {
     pub data: Vec<u8>,
@@ -18,6 +29,7 @@ pub struct ImagePal {
 pub struct ImageRgb32 {
     pub data: Vec<u32>,
     pub stride: usize,
+    pub premultiplied: bool, // color channels already scaled by alpha
 }
 
 // ----------------------------------------------------------------------------
@@ -30,13 +42,15 @@ pub fn pal1_to_rgb32(pal1: ImagePal, geo: &ImageGeometry) -> ImageRgb32 {
     let mut rgb32 = ImageRgb32 {
         data: vec![0; geo.cx * geo.cy],
         stride: geo.cx,
+        premultiplied: false,
     };
 
     for y in 0..geo.cy {
         let src = &pal1.data[y * pal1.stride..(y + 1) * pal1.stride];
         let dst = &mut rgb32.data[y * rgb32.stride..(y + 1) * rgb32.stride];
 
-        for x in 0..geo.cx {
+        let done = simd::pal1_row(dst, &src[..geo.cx / 8], &pal1.palette);
+        for x in done..geo.cx {
             let idx = (src[x / 8] >> (7 - (x & 7))) & 1;
             dst[x] = pal1.palette[idx as usize];
         }
@@ -50,13 +64,15 @@ pub fn pal8_to_rgb32(pal8: ImagePal, geo: &ImageGeometry) -> ImageRgb32 {
     let mut rgb32 = ImageRgb32 {
         data: vec![0; geo.cx * geo.cy],
         stride: geo.cx,
+        premultiplied: false,
     };
 
     for y in 0..geo.cy {
         let src = &pal8.data[y * pal8.stride..(y + 1) * pal8.stride];
         let dst = &mut rgb32.data[y * rgb32.stride..(y + 1) * rgb32.stride];
 
-        for x in 0..geo.cx {
+        let done = simd::pal8_row(dst, &src[..geo.cx], &pal8.palette);
+        for x in done..geo.cx {
             let idx = src[x];
             dst[x] = pal8.palette[idx as usize];
         }
@@ -65,6 +81,157 @@ pub fn pal8_to_rgb32(pal8: ImagePal, geo: &ImageGeometry) -> ImageRgb32 {
     rgb32
 }
 
+// ----------------------------------------------------------------------------
+pub fn pal2_to_rgb32(pal2: ImagePal, geo: &ImageGeometry) -> ImageRgb32 {
+    packed_pal_to_rgb32(&pal2, geo, 2)
+}
+
+// ----------------------------------------------------------------------------
+pub fn pal4_to_rgb32(pal4: ImagePal, geo: &ImageGeometry) -> ImageRgb32 {
+    packed_pal_to_rgb32(&pal4, geo, 4)
+}
+
+// ----------------------------------------------------------------------------
+fn packed_pal_to_rgb32(pal: &ImagePal, geo: &ImageGeometry, bits: usize) -> ImageRgb32 {
+    let mut rgb32 = ImageRgb32 {
+        data: vec![0; geo.cx * geo.cy],
+        stride: geo.cx,
+        premultiplied: false,
+    };
+
+    for y in 0..geo.cy {
+        let src = &pal.data[y * pal.stride..(y + 1) * pal.stride];
+        let dst = &mut rgb32.data[y * rgb32.stride..(y + 1) * rgb32.stride];
+
+        for (x, d) in dst.iter_mut().enumerate() {
+            *d = pal.palette[packed_index(src, x, bits) as usize];
+        }
+    }
+
+    rgb32
+}
+
+// ----------------------------------------------------------------------------
+// Index of pixel x in a row of `bits` wide indices, leftmost pixel in the
+// most significant bits
+fn packed_index(row: &[u8], x: usize, bits: usize) -> u8 {
+    let per_byte = 8 / bits;
+    let shift = 8 - bits * (x % per_byte + 1);
+    (row[x / per_byte] >> shift) & ((1 << bits) - 1) as u8
+}
+
+// ----------------------------------------------------------------------------
+// Expand 1, 2 or 4 bit grayscale (`geo.cf`) to 8 bit, rows are `stride` bytes
+pub fn gray_to_y8(src: &[u8], stride: usize, geo: &ImageGeometry) -> Result<Vec<u8>> {
+    let bits = match geo.cf {
+        ColorFormat::Y1 => 1,
+        ColorFormat::Y2 => 2,
+        ColorFormat::Y4 => 4,
+        ColorFormat::Y8 => 8,
+        _ => return Err(Error::InvalidColorFormat),
+    };
+    let scale = 255 / ((1 << bits) - 1) as u8;
+
+    let mut y8 = vec![0; geo.cx * geo.cy];
+    for y in 0..geo.cy {
+        let src = &src[y * stride..(y + 1) * stride];
+        let dst = &mut y8[y * geo.cx..(y + 1) * geo.cx];
+
+        for (x, d) in dst.iter_mut().enumerate() {
+            *d = packed_index(src, x, bits) * scale;
+        }
+    }
+    Ok(y8)
+}
+
+// ----------------------------------------------------------------------------
+pub fn y8_to_rgb32(y8: &[u8], geo: &ImageGeometry) -> ImageRgb32 {
+    let data = y8[..geo.cx * geo.cy]
+        .iter()
+        .map(|&v| 0xff000000 | (v as u32 * 0x010101))
+        .collect();
+    ImageRgb32 {
+        data,
+        stride: geo.cx,
+        premultiplied: false,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Scale the color channels by alpha, as expected by blending with
+// ONE, ONE_MINUS_SRC_ALPHA. Filtering premultiplied pixels avoids dark fringes
+// around transparent edges.
+pub fn premultiply(rgb32: &mut ImageRgb32) {
+    if !rgb32.premultiplied {
+        rgb32
+            .data
+            .iter_mut()
+            .for_each(|p| *p = premultiply_argb(*p));
+        rgb32.premultiplied = true;
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub fn unpremultiply(rgb32: &mut ImageRgb32) {
+    if rgb32.premultiplied {
+        rgb32
+            .data
+            .iter_mut()
+            .for_each(|p| *p = unpremultiply_argb(*p));
+        rgb32.premultiplied = false;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Same for interleaved 8 bit RGBA as delivered by the PNG decoder
+pub fn premultiply_rgba(rgba: &mut [u8]) {
+    for p in rgba.chunks_exact_mut(4) {
+        let a = p[3] as u32;
+        p[..3]
+            .iter_mut()
+            .for_each(|c| *c = mul_alpha(*c as u32, a) as u8);
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub fn unpremultiply_rgba(rgba: &mut [u8]) {
+    for p in rgba.chunks_exact_mut(4) {
+        let a = p[3] as u32;
+        p[..3]
+            .iter_mut()
+            .for_each(|c| *c = div_alpha(*c as u32, a) as u8);
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub fn premultiply_argb(argb: u32) -> u32 {
+    let a = argb >> 24;
+    let r = mul_alpha((argb >> 16) & 0xff, a);
+    let g = mul_alpha((argb >> 8) & 0xff, a);
+    let b = mul_alpha(argb & 0xff, a);
+    (a << 24) | (r << 16) | (g << 8) | b
+}
+
+// ----------------------------------------------------------------------------
+pub fn unpremultiply_argb(argb: u32) -> u32 {
+    let a = argb >> 24;
+    let r = div_alpha((argb >> 16) & 0xff, a);
+    let g = div_alpha((argb >> 8) & 0xff, a);
+    let b = div_alpha(argb & 0xff, a);
+    (a << 24) | (r << 16) | (g << 8) | b
+}
+
+// ----------------------------------------------------------------------------
+fn mul_alpha(c: u32, a: u32) -> u32 {
+    (c * a + 127) / 255
+}
+
+// ----------------------------------------------------------------------------
+// Color is lost for fully transparent pixels
+fn div_alpha(c: u32, a: u32) -> u32 {
+    (c * 255 + a / 2).checked_div(a).map_or(0, |c| c.min(255))
+}
+
 pub fn ycbcr420_to_ycbcr24(luma: &[u8], cb: &[u8], cr: &[u8], geo: &ImageGeometry) -> Vec<u8> {
     let mut yuv24 = vec![0; geo.cx * geo.cy * 3];
     let chroma_width = geo.cx.div_ceil(2);
@@ -77,7 +244,8 @@ pub fn ycbcr420_to_ycbcr24(luma: &[u8], cb: &[u8], cr: &[u8], geo: &ImageGeometr
         let src_cr = &cr[chroma_y * chroma_width..(chroma_y + 1) * chroma_width];
         let dst = &mut yuv24[y * geo.cx * 3..(y + 1) * geo.cx * 3];
 
-        for x in 0..geo.cx {
+        let done = simd::ycbcr420_row(dst, src_luma, src_cb, src_cr);
+        for x in done..geo.cx {
             let chroma_x = (x / 2).min(chroma_width - 1);
             let y_val = src_luma[x];
             let cb_val = src_cb[chroma_x];
@@ -91,6 +259,236 @@ pub fn ycbcr420_to_ycbcr24(luma: &[u8], cb: &[u8], cr: &[u8], geo: &ImageGeometr
     yuv24
 }
 
+// ----------------------------------------------------------------------------
+// Chroma is subsampled 2x2 and stored interleaved as Cb, Cr pairs
+pub fn nv12_to_ycbcr24(luma: &[u8], cbcr: &[u8], geo: &ImageGeometry) -> Vec<u8> {
+    let mut yuv24 = vec![0; geo.cx * geo.cy * 3];
+    let chroma_width = geo.cx.div_ceil(2);
+
+    for y in 0..geo.cy {
+        let src_luma = &luma[y * geo.cx..(y + 1) * geo.cx];
+        let chroma_y = y / 2;
+        let src_cbcr = &cbcr[chroma_y * chroma_width * 2..(chroma_y + 1) * chroma_width * 2];
+        let dst = &mut yuv24[y * geo.cx * 3..(y + 1) * geo.cx * 3];
+
+        for x in 0..geo.cx {
+            let chroma_x = x / 2;
+            dst[x * 3] = src_luma[x];
+            dst[x * 3 + 1] = src_cbcr[chroma_x * 2];
+            dst[x * 3 + 2] = src_cbcr[chroma_x * 2 + 1];
+        }
+    }
+    yuv24
+}
+
+// ----------------------------------------------------------------------------
+// Chroma planes are subsampled horizontally only
+pub fn ycbcr422_to_ycbcr24(luma: &[u8], cb: &[u8], cr: &[u8], geo: &ImageGeometry) -> Vec<u8> {
+    let mut yuv24 = vec![0; geo.cx * geo.cy * 3];
+    let chroma_width = geo.cx.div_ceil(2);
+
+    for y in 0..geo.cy {
+        let src_luma = &luma[y * geo.cx..(y + 1) * geo.cx];
+        let src_cb = &cb[y * chroma_width..(y + 1) * chroma_width];
+        let src_cr = &cr[y * chroma_width..(y + 1) * chroma_width];
+        let dst = &mut yuv24[y * geo.cx * 3..(y + 1) * geo.cx * 3];
+
+        for x in 0..geo.cx {
+            let chroma_x = x / 2;
+            dst[x * 3] = src_luma[x];
+            dst[x * 3 + 1] = src_cb[chroma_x];
+            dst[x * 3 + 2] = src_cr[chroma_x];
+        }
+    }
+    yuv24
+}
+
+// ----------------------------------------------------------------------------
+// Packed 4:2:2, every 4 bytes Y0 Cb Y1 Cr cover two pixels
+pub fn yuyv_to_ycbcr24(yuyv: &[u8], geo: &ImageGeometry) -> Vec<u8> {
+    let mut yuv24 = vec![0; geo.cx * geo.cy * 3];
+    let stride = geo.cx.div_ceil(2) * 4;
+
+    for y in 0..geo.cy {
+        let src = &yuyv[y * stride..(y + 1) * stride];
+        let dst = &mut yuv24[y * geo.cx * 3..(y + 1) * geo.cx * 3];
+
+        for x in 0..geo.cx {
+            let pair = &src[(x / 2) * 4..(x / 2) * 4 + 4];
+            dst[x * 3] = pair[(x & 1) * 2];
+            dst[x * 3 + 1] = pair[1];
+            dst[x * 3 + 2] = pair[3];
+        }
+    }
+    yuv24
+}
+
+// ----------------------------------------------------------------------------
+// Luma weights of the RGB <-> YCbCr matrices, full range as used by JPEG and
+// the YUV shaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YCbCrMatrix {
+    Bt601, // SD video, JPEG, WebP
+    Bt709, // HD video
+}
+
+impl YCbCrMatrix {
+    // ------------------------------------------------------------------------
+    fn weights(&self) -> (f32, f32) {
+        match self {
+            YCbCrMatrix::Bt601 => (0.299, 0.114),
+            YCbCrMatrix::Bt709 => (0.2126, 0.0722),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub fn rgb_to_ycbcr(rgb: [u8; 3], matrix: YCbCrMatrix) -> [u8; 3] {
+    let (kr, kb) = matrix.weights();
+    let kg = 1.0 - kr - kb;
+    let [r, g, b] = rgb.map(f32::from);
+
+    let y = kr * r + kg * g + kb * b;
+    let cb = 128.0 + (b - y) / (2.0 * (1.0 - kb));
+    let cr = 128.0 + (r - y) / (2.0 * (1.0 - kr));
+    [y, cb, cr].map(clamp_u8)
+}
+
+// ----------------------------------------------------------------------------
+pub fn ycbcr_to_rgb(ycbcr: [u8; 3], matrix: YCbCrMatrix) -> [u8; 3] {
+    let (kr, kb) = matrix.weights();
+    let kg = 1.0 - kr - kb;
+    let [y, cb, cr] = ycbcr.map(f32::from);
+
+    let r = y + 2.0 * (1.0 - kr) * (cr - 128.0);
+    let b = y + 2.0 * (1.0 - kb) * (cb - 128.0);
+    let g = (y - kr * r - kb * b) / kg;
+    [r, g, b].map(clamp_u8)
+}
+
+// ----------------------------------------------------------------------------
+fn clamp_u8(x: f32) -> u8 {
+    x.round().clamp(0.0, 255.0) as u8
+}
+
+// ----------------------------------------------------------------------------
+// Interleaved 8 bit RGB to the interleaved YCbCr layout of the YUV textures
+pub fn rgb24_to_ycbcr24(rgb: &[u8], geo: &ImageGeometry, matrix: YCbCrMatrix) -> Vec<u8> {
+    convert_pixels::<3, 3>(rgb, geo, |p| rgb_to_ycbcr(p, matrix))
+}
+
+// ----------------------------------------------------------------------------
+// Interleaved 8 bit RGBA to YCbCr, alpha is dropped
+pub fn rgba32_to_ycbcr24(rgba: &[u8], geo: &ImageGeometry, matrix: YCbCrMatrix) -> Vec<u8> {
+    convert_pixels::<4, 3>(rgba, geo, |[r, g, b, _]| rgb_to_ycbcr([r, g, b], matrix))
+}
+
+// ----------------------------------------------------------------------------
+pub fn ycbcr24_to_rgb24(ycbcr: &[u8], geo: &ImageGeometry, matrix: YCbCrMatrix) -> Vec<u8> {
+    convert_pixels::<3, 3>(ycbcr, geo, |p| ycbcr_to_rgb(p, matrix))
+}
+
+// ----------------------------------------------------------------------------
+fn convert_pixels<const N: usize, const M: usize>(
+    src: &[u8],
+    geo: &ImageGeometry,
+    f: impl Fn([u8; N]) -> [u8; M],
+) -> Vec<u8> {
+    let count = geo.cx * geo.cy;
+    let mut dst = vec![0; count * M];
+    let src = src[..count * N].chunks_exact(N);
+    for (d, s) in dst.chunks_exact_mut(M).zip(src) {
+        d.copy_from_slice(&f(s.try_into().unwrap()));
+    }
+    dst
+}
+
+// ----------------------------------------------------------------------------
+// Bit counts of alpha, red, green and blue of the 16 bit RGB formats, packed
+// from the most significant bit down. 32 bit colors are 0xAARRGGBB.
+fn rgb16_bits(cf: ColorFormat) -> Result<[u32; 4]> {
+    match cf {
+        ColorFormat::RGB0565 => Ok([0, 5, 6, 5]),
+        ColorFormat::RGB0555 => Ok([0, 5, 5, 5]),
+        ColorFormat::RGB1555 => Ok([1, 5, 5, 5]),
+        ColorFormat::RGB4444 => Ok([4, 4, 4, 4]),
+        _ => Err(Error::InvalidColorFormat),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// 4x4 ordered dither thresholds
+const BAYER4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// ----------------------------------------------------------------------------
+// Pack to the 16 bit format given by `geo.cf`. Dithering trades the banding of
+// smooth gradients for a fine pattern, alpha is never dithered.
+pub fn rgb32_to_rgb16(rgb32: &ImageRgb32, geo: &ImageGeometry, dither: bool) -> Result<Vec<u16>> {
+    let bits = rgb16_bits(geo.cf)?;
+    let mut rgb16 = vec![0; geo.cx * geo.cy];
+
+    for y in 0..geo.cy {
+        let src = &rgb32.data[y * rgb32.stride..y * rgb32.stride + geo.cx];
+        let dst = &mut rgb16[y * geo.cx..(y + 1) * geo.cx];
+
+        for x in 0..geo.cx {
+            let d = if dither {
+                (BAYER4[y & 3][x & 3] as f32 + 0.5) / 16.0
+            } else {
+                0.5
+            };
+
+            let mut packed = 0;
+            for (i, &n) in bits.iter().enumerate() {
+                let c = (src[x] >> (24 - 8 * i)) & 0xff;
+                let d = if i == 0 { 0.5 } else { d };
+                packed = (packed << n) | quantize(c, n, d);
+            }
+            dst[x] = packed as u16;
+        }
+    }
+    Ok(rgb16)
+}
+
+// ----------------------------------------------------------------------------
+// Unpack a 16 bit format, channels without alpha bits become opaque
+pub fn rgb16_to_rgb32(rgb16: &[u16], geo: &ImageGeometry) -> Result<ImageRgb32> {
+    let bits = rgb16_bits(geo.cf)?;
+    let data = rgb16[..geo.cx * geo.cy]
+        .iter()
+        .map(|&p| {
+            let mut shift: u32 = bits.iter().sum();
+            bits.iter().fold(0, |argb, &n| {
+                shift -= n;
+                let c = if n == 0 {
+                    0xff
+                } else {
+                    expand((p as u32 >> shift) & ((1 << n) - 1), n)
+                };
+                (argb << 8) | c
+            })
+        })
+        .collect();
+    Ok(ImageRgb32 {
+        data,
+        stride: geo.cx,
+        premultiplied: false,
+    })
+}
+
+// ----------------------------------------------------------------------------
+// 8 bit value to n bits, `d` in [0, 1) is the rounding threshold
+fn quantize(c: u32, n: u32, d: f32) -> u32 {
+    let max = (1 << n) - 1;
+    ((c * max) as f32 / 255.0 + d).floor().min(max as f32) as u32
+}
+
+// ----------------------------------------------------------------------------
+fn expand(v: u32, n: u32) -> u32 {
+    let max = (1 << n) - 1;
+    (v * 255 + max / 2) / max
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,4 +522,351 @@ mod tests {
         ];
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_ycbcr420_to_ycbcr24_wide() {
+        // wide enough for the vector kernels plus a scalar tail
+        let (cx, cy): (usize, usize) = (37, 3);
+        let (chroma_width, chroma_height) = (cx.div_ceil(2), cy.div_ceil(2));
+        let luma: Vec<u8> = (0..cx * cy).map(|i| (i * 7 % 251) as u8).collect();
+        let cb: Vec<u8> = (0..chroma_width * chroma_height).map(|i| i as u8).collect();
+        let cr: Vec<u8> = cb.iter().map(|c| 200 - c).collect();
+
+        let geo = ImageGeometry {
+            cx,
+            cy,
+            cf: ColorFormat::YCbCr420,
+        };
+
+        let result = ycbcr420_to_ycbcr24(&luma, &cb, &cr, &geo);
+
+        for y in 0..cy {
+            for x in 0..cx {
+                let c = (y / 2) * chroma_width + x / 2;
+                let p = (y * cx + x) * 3;
+                assert_eq!(result[p..p + 3], [luma[y * cx + x], cb[c], cr[c]]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pal_to_rgb32() {
+        let palette: Vec<u32> = (0..256).map(|i| i * 0x010203).collect();
+        let geo = ImageGeometry {
+            cx: 21,
+            cy: 2,
+            cf: ColorFormat::Pal1,
+        };
+
+        let data = vec![0xa5, 0x0f, 0x80, 0xff, 0x00, 0x7f];
+        let pal1 = ImagePal {
+            data: data.clone(),
+            stride: 3,
+            palette: palette[..2].to_vec(),
+        };
+        let rgb32 = pal1_to_rgb32(pal1, &geo);
+        for y in 0..geo.cy {
+            for x in 0..geo.cx {
+                let bit = (data[y * 3 + x / 8] >> (7 - x % 8)) & 1;
+                assert_eq!(rgb32.data[y * 21 + x], palette[bit as usize]);
+            }
+        }
+
+        let data: Vec<u8> = (0..42).map(|i| (i * 37) as u8).collect();
+        let pal8 = ImagePal {
+            data: data.clone(),
+            stride: 21,
+            palette: palette.clone(),
+        };
+        let geo = ImageGeometry {
+            cf: ColorFormat::Pal8,
+            ..geo
+        };
+        let rgb32 = pal8_to_rgb32(pal8, &geo);
+        let expected: Vec<u32> = data.iter().map(|&i| palette[i as usize]).collect();
+        assert_eq!(rgb32.data, expected);
+    }
+
+    #[test]
+    fn test_premultiply() {
+        let mut rgb32 = ImageRgb32 {
+            data: vec![0xffc08040, 0x80ff8000, 0x00ffffff],
+            stride: 3,
+            premultiplied: false,
+        };
+        premultiply(&mut rgb32);
+        assert!(rgb32.premultiplied);
+        assert_eq!(rgb32.data, vec![0xffc08040, 0x80804000, 0x00000000]);
+
+        // premultiplying twice is a no-op
+        premultiply(&mut rgb32);
+        assert_eq!(rgb32.data[1], 0x80804000);
+
+        unpremultiply(&mut rgb32);
+        assert!(!rgb32.premultiplied);
+        assert_eq!(rgb32.data, vec![0xffc08040, 0x80ff8000, 0x00000000]);
+
+        let mut rgba = vec![255, 128, 0, 128, 10, 20, 30, 255];
+        premultiply_rgba(&mut rgba);
+        assert_eq!(rgba, vec![128, 64, 0, 128, 10, 20, 30, 255]);
+        unpremultiply_rgba(&mut rgba);
+        assert_eq!(rgba, vec![255, 128, 0, 128, 10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_low_bit_gray() {
+        let geo = ImageGeometry {
+            cx: 5,
+            cy: 2,
+            cf: ColorFormat::Y1,
+        };
+        let y1 = vec![0b1010_1000, 0b0111_1000];
+        let y8 = gray_to_y8(&y1, 1, &geo).unwrap();
+        assert_eq!(y8, vec![255, 0, 255, 0, 255, 0, 255, 255, 255, 255]);
+
+        let geo = ImageGeometry {
+            cf: ColorFormat::Y2,
+            ..geo
+        };
+        let y2 = vec![0b00_01_10_11, 0b01_000000, 0b11_10_01_00, 0b10_000000];
+        let y8 = gray_to_y8(&y2, 2, &geo).unwrap();
+        assert_eq!(y8, vec![0, 85, 170, 255, 85, 255, 170, 85, 0, 170]);
+
+        let geo = ImageGeometry {
+            cf: ColorFormat::Y4,
+            ..geo
+        };
+        let y4 = vec![0x0f, 0x37, 0x10, 0xf0, 0x81, 0x20];
+        let y8 = gray_to_y8(&y4, 3, &geo).unwrap();
+        assert_eq!(y8, vec![0, 255, 51, 119, 17, 255, 0, 136, 17, 34]);
+
+        let rgb32 = y8_to_rgb32(
+            &y8[..2],
+            &ImageGeometry {
+                cx: 2,
+                cy: 1,
+                ..geo
+            },
+        );
+        assert_eq!(rgb32.data, vec![0xff000000, 0xffffffff]);
+
+        let geo = ImageGeometry {
+            cf: ColorFormat::Pal4,
+            ..geo
+        };
+        assert!(gray_to_y8(&y4, 3, &geo).is_err());
+    }
+
+    #[test]
+    fn test_pal2_pal4_to_rgb32() {
+        let palette: Vec<u32> = (0..16).map(|i| 0xff000000 | i).collect();
+        let geo = ImageGeometry {
+            cx: 3,
+            cy: 2,
+            cf: ColorFormat::Pal2,
+        };
+        let pal2 = ImagePal {
+            data: vec![0b11_10_01_00, 0b00_11_00_00],
+            stride: 1,
+            palette: palette[..4].to_vec(),
+        };
+        let rgb32 = pal2_to_rgb32(pal2, &geo);
+        let indices: Vec<u32> = rgb32.data.iter().map(|p| p & 0xff).collect();
+        assert_eq!(indices, vec![3, 2, 1, 0, 3, 0]);
+
+        let pal4 = ImagePal {
+            data: vec![0xa5, 0xf0, 0x0c, 0x3b],
+            stride: 2,
+            palette,
+        };
+        let geo = ImageGeometry {
+            cf: ColorFormat::Pal4,
+            ..geo
+        };
+        let rgb32 = pal4_to_rgb32(pal4, &geo);
+        let indices: Vec<u32> = rgb32.data.iter().map(|p| p & 0xff).collect();
+        assert_eq!(indices, vec![10, 5, 15, 0, 12, 3]);
+    }
+
+    #[test]
+    fn test_nv12_to_ycbcr24() {
+        #[rustfmt::skip]
+        let luma = vec![
+             10,  20,  30,  40,
+             50,  60,  70,  80,
+             90, 100, 110, 120,
+            130, 140, 150, 160,
+        ];
+        let cbcr = vec![1, 5, 2, 6, 3, 7, 4, 8];
+
+        let geo = ImageGeometry {
+            cx: 4,
+            cy: 4,
+            cf: ColorFormat::NV12,
+        };
+
+        let result = nv12_to_ycbcr24(&luma, &cbcr, &geo);
+
+        #[rustfmt::skip]
+        let expected = vec![
+             10, 1, 5,   20, 1, 5,   30, 2, 6,   40, 2, 6,
+             50, 1, 5,   60, 1, 5,   70, 2, 6,   80, 2, 6,
+             90, 3, 7,  100, 3, 7,  110, 4, 8,  120, 4, 8,
+            130, 3, 7,  140, 3, 7,  150, 4, 8,  160, 4, 8,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_ycbcr422_to_ycbcr24() {
+        let luma = vec![10, 20, 30, 40, 50, 60, 70, 80];
+        let cb = vec![1, 2, 3, 4];
+        let cr = vec![5, 6, 7, 8];
+
+        let geo = ImageGeometry {
+            cx: 4,
+            cy: 2,
+            cf: ColorFormat::YCbCr422,
+        };
+
+        #[rustfmt::skip]
+        let expected = vec![
+            10, 1, 5,   20, 1, 5,   30, 2, 6,   40, 2, 6,
+            50, 3, 7,   60, 3, 7,   70, 4, 8,   80, 4, 8,
+        ];
+        assert_eq!(ycbcr422_to_ycbcr24(&luma, &cb, &cr, &geo), expected);
+
+        #[rustfmt::skip]
+        let yuyv = vec![
+            10, 1, 20, 5,   30, 2, 40, 6,
+            50, 3, 60, 7,   70, 4, 80, 8,
+        ];
+        let geo = ImageGeometry {
+            cf: ColorFormat::YUYV,
+            ..geo
+        };
+        assert_eq!(yuyv_to_ycbcr24(&yuyv, &geo), expected);
+    }
+
+    #[test]
+    fn test_rgb16_pack_unpack() {
+        let geo = ImageGeometry {
+            cx: 4,
+            cy: 1,
+            cf: ColorFormat::RGB0565,
+        };
+        let rgb32 = ImageRgb32 {
+            data: vec![0xffffffff, 0xffff0000, 0xff00ff00, 0x800000ff],
+            stride: 4,
+            premultiplied: false,
+        };
+
+        let rgb565 = rgb32_to_rgb16(&rgb32, &geo, false).unwrap();
+        assert_eq!(rgb565, vec![0xffff, 0xf800, 0x07e0, 0x001f]);
+        let back = rgb16_to_rgb32(&rgb565, &geo).unwrap();
+        assert_eq!(
+            back.data,
+            vec![0xffffffff, 0xffff0000, 0xff00ff00, 0xff0000ff]
+        );
+
+        let geo = ImageGeometry {
+            cf: ColorFormat::RGB1555,
+            ..geo
+        };
+        let rgb1555 = rgb32_to_rgb16(&rgb32, &geo, false).unwrap();
+        assert_eq!(rgb1555, vec![0xffff, 0xfc00, 0x83e0, 0x801f]);
+
+        let geo = ImageGeometry {
+            cf: ColorFormat::RGB0555,
+            ..geo
+        };
+        let all: Vec<u16> = (0..0x8000).collect();
+        let geo_all = ImageGeometry {
+            cx: all.len(),
+            ..geo
+        };
+        let unpacked = rgb16_to_rgb32(&all, &geo_all).unwrap();
+        assert_eq!(rgb32_to_rgb16(&unpacked, &geo_all, false).unwrap(), all);
+
+        let geo = ImageGeometry {
+            cf: ColorFormat::Y8,
+            ..geo
+        };
+        assert!(rgb32_to_rgb16(&rgb32, &geo, false).is_err());
+    }
+
+    #[test]
+    fn test_rgb16_dither() {
+        // a dark gray between two 5 bit levels averages out with dithering
+        let geo = ImageGeometry {
+            cx: 8,
+            cy: 8,
+            cf: ColorFormat::RGB0555,
+        };
+        let gray = ImageRgb32 {
+            data: vec![0xff0c0c0c; 64],
+            stride: 8,
+            premultiplied: false,
+        };
+
+        let mean = |dither| {
+            let rgb16 = rgb32_to_rgb16(&gray, &geo, dither).unwrap();
+            let rgb32 = rgb16_to_rgb32(&rgb16, &geo).unwrap();
+            rgb32.data.iter().map(|p| (p & 0xff) as f32).sum::<f32>() / 64.0
+        };
+        assert!((mean(false) - 12.0).abs() > 3.0);
+        assert!((mean(true) - 12.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_rgb24_to_ycbcr24() {
+        #[rustfmt::skip]
+        let rgb = vec![
+              0,   0,   0,   255, 255, 255,
+            255,   0,   0,     0,   0, 255,
+        ];
+
+        let geo = ImageGeometry {
+            cx: 2,
+            cy: 2,
+            cf: ColorFormat::RGB0888,
+        };
+
+        let result = rgb24_to_ycbcr24(&rgb, &geo, YCbCrMatrix::Bt601);
+
+        #[rustfmt::skip]
+        let expected = vec![
+              0, 128, 128,   255, 128, 128,
+             76,  85, 255,    29, 255, 107,
+        ];
+        assert_eq!(result, expected);
+
+        let result = rgb24_to_ycbcr24(&rgb, &geo, YCbCrMatrix::Bt709);
+        assert_eq!(&result[6..], &[54, 99, 255, 18, 255, 116]);
+    }
+
+    #[test]
+    fn test_ycbcr_round_trip() {
+        for matrix in [YCbCrMatrix::Bt601, YCbCrMatrix::Bt709] {
+            for rgb in [[12, 200, 99], [128, 128, 128], [250, 17, 3], [40, 40, 230]] {
+                let back = ycbcr_to_rgb(rgb_to_ycbcr(rgb, matrix), matrix);
+                for (a, b) in rgb.iter().zip(back) {
+                    assert!(a.abs_diff(b) <= 1, "{rgb:?} != {back:?}");
+                }
+            }
+        }
+
+        let rgba = [255, 0, 0, 7];
+        let geo = ImageGeometry {
+            cx: 1,
+            cy: 1,
+            cf: ColorFormat::RGB8888,
+        };
+        let ycbcr = rgba32_to_ycbcr24(&rgba, &geo, YCbCrMatrix::Bt601);
+        assert_eq!(ycbcr, vec![76, 85, 255]);
+        assert_eq!(
+            ycbcr24_to_rgb24(&ycbcr, &geo, YCbCrMatrix::Bt601),
+            vec![254, 0, 0]
+        );
+    }
 }
@@ -26,7 +26,10 @@ pub enum ColorFormat {
     RGB0ggg, // 48 bit RGB with 16 bit for red, green and blue
     RGBgggg, // 64 bit RGBA with 16 bit for red, green and blue
 
-    YCbCr420, // 12 bit YCbCr 4:2:0
+    YCbCr420, // 12 bit YCbCr 4:2:0, three planes
+    NV12,     // 12 bit YCbCr 4:2:0, luma plane and interleaved Cb/Cr plane
+    YCbCr422, // 16 bit YCbCr 4:2:2, three planes
+    YUYV,     // 16 bit YCbCr 4:2:2, packed Y0 Cb Y1 Cr
 }
 
 // ----------------------------------------------------------------------------
@@ -46,13 +49,15 @@ impl ColorFormat {
             ColorFormat::Y2 | ColorFormat::Pal2 => 2,
             ColorFormat::Y4 | ColorFormat::Pal4 => 4,
             ColorFormat::Y8 | ColorFormat::Pal8 => 8,
-            ColorFormat::YCbCr420 => 12,
+            ColorFormat::YCbCr420 | ColorFormat::NV12 => 12,
             ColorFormat::RGB4444
             | ColorFormat::RGB0555
             | ColorFormat::RGB0565
             | ColorFormat::RGB1555
             | ColorFormat::YA8
-            | ColorFormat::Y16 => 16,
+            | ColorFormat::Y16
+            | ColorFormat::YCbCr422
+            | ColorFormat::YUYV => 16,
             ColorFormat::BGR0888 | ColorFormat::RGB0888 => 24,
             ColorFormat::BGR8888 | ColorFormat::RGB8888 | ColorFormat::YA16 => 32,
             ColorFormat::RGB0ggg => 48,
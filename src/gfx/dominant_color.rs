@@ -0,0 +1,111 @@
+// ----------------------------------------------------------------------------
+// Average and most frequent colors of an image, e.g. to tint letterbox bars,
+// panels and text shadows so they match the photo shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorStats {
+    pub average: [u8; 3],
+    pub dominant: Vec<DominantColor>, // most frequent first
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DominantColor {
+    pub color: [u8; 3],
+    pub share: f32, // fraction of the sampled pixels in [0, 1]
+}
+
+// Colors are grouped into 16 levels per channel, 4096 bins total
+const BITS: u32 = 4;
+const BINS: usize = 1 << (3 * BITS);
+
+// Sample about this many pixels, larger images are subsampled
+const MAX_SAMPLES: usize = 64 * 1024;
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Default)]
+struct Bin {
+    count: u32,
+    sum: [u32; 3],
+}
+
+impl Bin {
+    // ------------------------------------------------------------------------
+    fn add(&mut self, c: &[u8]) {
+        self.count += 1;
+        for (s, &v) in self.sum.iter_mut().zip(c) {
+            *s += v as u32;
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn mean(&self) -> [u8; 3] {
+        self.sum
+            .map(|s| ((s + self.count / 2) / self.count.max(1)) as u8)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// `pixels` holds `channels` interleaved components per pixel of which the
+// first three are used, RGB(A) or YCbCr alike.
+pub fn color_stats(pixels: &[u8], channels: usize, max_colors: usize) -> ColorStats {
+    let count = pixels.len() / channels;
+    let step = count.div_ceil(MAX_SAMPLES).max(1);
+
+    let mut bins = vec![Bin::default(); BINS];
+    let mut total = Bin::default();
+    for p in pixels.chunks_exact(channels).step_by(step) {
+        let c = &p[..3];
+        let idx = c
+            .iter()
+            .fold(0, |idx, &v| (idx << BITS) | (v >> (8 - BITS)) as usize);
+        bins[idx].add(c);
+        total.add(c);
+    }
+
+    let mut used: Vec<&Bin> = bins.iter().filter(|b| b.count > 0).collect();
+    used.sort_by_key(|b| std::cmp::Reverse(b.count));
+
+    let samples = total.count.max(1) as f32;
+    let dominant = used
+        .iter()
+        .take(max_colors)
+        .map(|b| DominantColor {
+            color: b.mean(),
+            share: b.count as f32 / samples,
+        })
+        .collect();
+
+    ColorStats {
+        average: total.mean(),
+        dominant,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_stats() {
+        // 3/4 blue-ish, 1/4 red, RGBA
+        let mut rgba = Vec::new();
+        for i in 0..100 {
+            if i % 4 == 0 {
+                rgba.extend_from_slice(&[250, 10, 10, 255]);
+            } else {
+                rgba.extend_from_slice(&[20, 40, 200 + (i % 2) as u8, 255]);
+            }
+        }
+
+        let stats = color_stats(&rgba, 4, 3);
+        assert_eq!(stats.dominant.len(), 2);
+        assert_eq!(stats.dominant[0].color, [20, 40, 201]);
+        assert_eq!(stats.dominant[0].share, 0.75);
+        assert_eq!(stats.dominant[1].color, [250, 10, 10]);
+        assert_eq!(stats.average, [78, 33, 153]);
+
+        let empty = color_stats(&[], 3, 3);
+        assert!(empty.dominant.is_empty());
+        assert_eq!(empty.average, [0, 0, 0]);
+    }
+}
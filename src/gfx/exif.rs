@@ -0,0 +1,337 @@
+use crate::geo::Coord;
+use crate::gfx::transform::Rotation;
+use crate::util::datetime::DateTime;
+
+// Tags of the image directory
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_DATETIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+
+// Tags of the EXIF directory
+const TAG_DATETIME_ORIGINAL: u16 = 0x9003;
+
+// Tags of the GPS directory
+const TAG_GPS_LATITUDE_REF: u16 = 1;
+const TAG_GPS_LATITUDE: u16 = 2;
+const TAG_GPS_LONGITUDE_REF: u16 = 3;
+const TAG_GPS_LONGITUDE: u16 = 4;
+
+// ----------------------------------------------------------------------------
+// What the camera tells about a photo
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Exif {
+    pub orientation: Orientation,
+    pub datetime: Option<DateTime>, // when it was taken, camera local time
+    pub coord: Option<Coord>,
+}
+
+// ----------------------------------------------------------------------------
+// Turns the stored pixels upright: mirrored horizontally first if `mirror`,
+// then rotated clockwise
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Orientation {
+    pub mirror: bool,
+    pub rotation: Rotation,
+}
+
+impl Orientation {
+    // ------------------------------------------------------------------------
+    // From the value of the orientation tag, 1 to 8, others are upright
+    pub fn from_tag(value: u16) -> Self {
+        let (mirror, rotation) = match value {
+            2 => (true, Rotation::R0),
+            3 => (false, Rotation::R180),
+            4 => (true, Rotation::R180),
+            5 => (true, Rotation::R270),
+            6 => (false, Rotation::R90),
+            7 => (true, Rotation::R90),
+            8 => (false, Rotation::R270),
+            _ => (false, Rotation::R0),
+        };
+        Self { mirror, rotation }
+    }
+
+    pub fn is_upright(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The EXIF data of a JPEG file, its APP1 segment comes before the image data.
+// None if there is none or it can't be read.
+pub fn from_jpeg(data: &[u8]) -> Option<Exif> {
+    if data.get(..2) != Some(&[0xff, 0xd8]) {
+        return None;
+    }
+    let mut pos = 2;
+    loop {
+        let (Some(0xff), Some(&marker)) = (data.get(pos), data.get(pos + 1)) else {
+            return None;
+        };
+        if marker == 0xff {
+            pos += 1; // fill byte
+            continue;
+        }
+        if matches!(marker, 0xd9 | 0xda) {
+            return None;
+        }
+        let len = usize::from(u16::from_be_bytes([
+            *data.get(pos + 2)?,
+            *data.get(pos + 3)?,
+        ]));
+        let segment = data.get(pos + 4..pos + 2 + len)?;
+        if marker == 0xe1
+            && let Some(tiff) = segment.strip_prefix(b"Exif\0\0")
+        {
+            return parse(tiff);
+        }
+        pos += 2 + len;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The TIFF structure of EXIF data, starting with its byte order
+pub fn parse(data: &[u8]) -> Option<Exif> {
+    let tiff = Tiff {
+        data,
+        little_endian: match data.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        },
+    };
+    if tiff.u16(2)? != 42 {
+        return None;
+    }
+    let ifd0 = tiff.entries(tiff.u32(4)?)?;
+    let find = |entries: &[Entry], tag: u16| entries.iter().find(|e| e.tag == tag).copied();
+
+    let orientation = find(&ifd0, TAG_ORIENTATION)
+        .and_then(|e| tiff.uint(e))
+        .map_or_else(Orientation::default, |v| Orientation::from_tag(v as u16));
+
+    // the original capture time, not the one of the last edit
+    let exif_ifd = find(&ifd0, TAG_EXIF_IFD)
+        .and_then(|e| tiff.uint(e))
+        .and_then(|offset| tiff.entries(offset));
+    let datetime = exif_ifd
+        .and_then(|entries| find(&entries, TAG_DATETIME_ORIGINAL))
+        .or_else(|| find(&ifd0, TAG_DATETIME))
+        .and_then(|e| tiff.ascii(e))
+        .and_then(parse_datetime);
+
+    let gps_ifd = find(&ifd0, TAG_GPS_IFD)
+        .and_then(|e| tiff.uint(e))
+        .and_then(|offset| tiff.entries(offset));
+    let coord = gps_ifd.and_then(|gps| {
+        let degrees = |value_tag: u16, ref_tag: u16, negative: &str| {
+            let [d, m, s] = tiff.rationals(find(&gps, value_tag)?)?;
+            let sign = match tiff.ascii(find(&gps, ref_tag)?)? == negative {
+                true => -1.0,
+                false => 1.0,
+            };
+            Some(sign * (d + m / 60.0 + s / 3600.0))
+        };
+        let lat = degrees(TAG_GPS_LATITUDE, TAG_GPS_LATITUDE_REF, "S")?;
+        let lon = degrees(TAG_GPS_LONGITUDE, TAG_GPS_LONGITUDE_REF, "W")?;
+        Some(Coord { lat, lon })
+    });
+
+    Some(Exif {
+        orientation,
+        datetime,
+        coord,
+    })
+}
+
+// ----------------------------------------------------------------------------
+// "YYYY:MM:DD HH:MM:SS", the fields are where ISO 8601 has them
+fn parse_datetime(s: &str) -> Option<DateTime> {
+    DateTime::from_iso8601(s).ok()
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    tag: u16,
+    kind: u16, // 2 ASCII, 3 SHORT, 4 LONG, 5 RATIONAL, ...
+    count: usize,
+    pos: usize, // of the value, in the entry if it fits in 4 bytes
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn u16(&self, pos: usize) -> Option<u16> {
+        let bytes = [*self.data.get(pos)?, *self.data.get(pos + 1)?];
+        Some(match self.little_endian {
+            true => u16::from_le_bytes(bytes),
+            false => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, pos: usize) -> Option<usize> {
+        let bytes: [u8; 4] = self.data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(match self.little_endian {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes),
+        } as usize)
+    }
+
+    // ------------------------------------------------------------------------
+    // The entries of the directory at `offset`
+    fn entries(&self, offset: usize) -> Option<Vec<Entry>> {
+        let count = usize::from(self.u16(offset)?);
+        (0..count)
+            .map(|i| {
+                let pos = offset + 2 + i * 12;
+                let kind = self.u16(pos + 2)?;
+                let count = self.u32(pos + 4)?;
+                let size = match kind {
+                    3 => 2,
+                    4 | 9 => 4,
+                    5 | 10 => 8,
+                    _ => 1,
+                };
+                Some(Entry {
+                    tag: self.u16(pos)?,
+                    kind,
+                    count,
+                    pos: match size * count <= 4 {
+                        true => pos + 8,
+                        false => self.u32(pos + 8)?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------
+    fn uint(&self, entry: Entry) -> Option<usize> {
+        match entry.kind {
+            3 => self.u16(entry.pos).map(usize::from),
+            4 => self.u32(entry.pos),
+            _ => None,
+        }
+    }
+
+    fn ascii(&self, entry: Entry) -> Option<&str> {
+        let bytes = self.data.get(entry.pos..entry.pos + entry.count)?;
+        let text = std::str::from_utf8(bytes).ok()?;
+        Some(text.trim_end_matches('\0'))
+    }
+
+    fn rationals<const N: usize>(&self, entry: Entry) -> Option<[f64; N]> {
+        if entry.kind != 5 || entry.count < N {
+            return None;
+        }
+        let mut values = [0.0; N];
+        for (i, value) in values.iter_mut().enumerate() {
+            let num = self.u32(entry.pos + i * 8)?;
+            let den = self.u32(entry.pos + i * 8 + 4)?;
+            *value = match den {
+                0 => 0.0,
+                den => num as f64 / den as f64,
+            };
+        }
+        Some(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Big endian TIFF with an orientation, a capture time and a position
+    fn tiff() -> Vec<u8> {
+        let mut data = b"MM\0\x2a\0\0\0\x08".to_vec();
+        let entry = |data: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32| {
+            data.extend(tag.to_be_bytes());
+            data.extend(kind.to_be_bytes());
+            data.extend(count.to_be_bytes());
+            data.extend(value.to_be_bytes());
+        };
+        // image directory at 8 with 3 entries, ends at 8 + 2 + 36 + 4 = 50
+        data.extend(3u16.to_be_bytes());
+        entry(&mut data, TAG_ORIENTATION, 3, 1, 6 << 16);
+        entry(&mut data, TAG_EXIF_IFD, 4, 1, 50);
+        entry(&mut data, TAG_GPS_IFD, 4, 1, 68);
+        data.extend(0u32.to_be_bytes());
+        // EXIF directory at 50, its date at 68 + 2 + 48 + 4 = 122
+        data.extend(1u16.to_be_bytes());
+        entry(&mut data, TAG_DATETIME_ORIGINAL, 2, 20, 122);
+        data.extend(0u32.to_be_bytes());
+        // GPS directory at 68, 48° 8' 24" N, 11° 34' 30" W at 122 + 20
+        data.extend(4u16.to_be_bytes());
+        entry(
+            &mut data,
+            TAG_GPS_LATITUDE_REF,
+            2,
+            2,
+            u32::from_be_bytes(*b"N\0\0\0"),
+        );
+        entry(&mut data, TAG_GPS_LATITUDE, 5, 3, 142);
+        entry(
+            &mut data,
+            TAG_GPS_LONGITUDE_REF,
+            2,
+            2,
+            u32::from_be_bytes(*b"W\0\0\0"),
+        );
+        entry(&mut data, TAG_GPS_LONGITUDE, 5, 3, 166);
+        data.extend(0u32.to_be_bytes());
+        data.extend(b"2019:07:14 10:22:05\0");
+        for value in [48u32, 8, 24, 11, 34, 30] {
+            data.extend(value.to_be_bytes());
+            data.extend(1u32.to_be_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse() {
+        let exif = parse(&tiff()).unwrap();
+        assert_eq!(exif.orientation, Orientation::from_tag(6));
+        assert_eq!(exif.orientation.rotation, Rotation::R90);
+        let datetime = exif.datetime.unwrap();
+        assert_eq!(datetime.as_iso8601(), "2019-07-14T10:22:05Z");
+        let coord = exif.coord.unwrap();
+        assert!((coord.lat - 48.14).abs() < 1e-9);
+        assert!((coord.lon + 11.575).abs() < 1e-9);
+
+        assert!(parse(b"II\x2b\0\x08\0\0\0").is_none());
+        assert!(parse(&tiff()[..40]).is_none());
+    }
+
+    #[test]
+    fn test_from_jpeg() {
+        let tiff = tiff();
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0, 4, 0, 0, 0xff, 0xe1];
+        jpeg.extend((tiff.len() as u16 + 8).to_be_bytes());
+        jpeg.extend(b"Exif\0\0");
+        jpeg.extend(&tiff);
+        jpeg.extend([0xff, 0xda, 0, 2]);
+        let exif = from_jpeg(&jpeg).unwrap();
+        assert_eq!(exif.orientation.rotation, Rotation::R90);
+
+        assert!(from_jpeg(&[0xff, 0xd8, 0xff, 0xda, 0, 2]).is_none());
+        assert!(from_jpeg(&jpeg[..20]).is_none());
+    }
+
+    #[test]
+    fn test_orientation() {
+        assert!(Orientation::from_tag(1).is_upright());
+        assert!(Orientation::from_tag(0).is_upright());
+        assert_eq!(
+            Orientation::from_tag(7),
+            Orientation {
+                mirror: true,
+                rotation: Rotation::R90
+            }
+        );
+    }
+}
@@ -0,0 +1,629 @@
+use crate::error::{Error, Result};
+
+// Coefficient order of the entropy coded blocks
+#[rustfmt::skip]
+const ZIGZAG: [usize; 64] = [
+     0,  1,  8, 16,  9,  2,  3, 10,
+    17, 24, 32, 25, 18, 11,  4,  5,
+    12, 19, 26, 33, 40, 48, 41, 34,
+    27, 20, 13,  6,  7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36,
+    29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46,
+    53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+// Huffman codes up to this length are looked up at once
+const FAST_BITS: u32 = 9;
+
+// Larger frames are rejected before their planes are allocated, cameras
+// stay well below
+const MAX_PIXELS: usize = 100_000_000;
+
+// ----------------------------------------------------------------------------
+// Baseline JPEG (sequential DCT, Huffman coded, 8 bit samples) as written by
+// cameras and phones, decoded to the interleaved YCbCr of the YUV textures.
+// JPEG uses full range BT.601 like the textures, so the samples are kept as
+// they are. Grayscale photos get neutral chroma, chroma is upsampled by
+// repeating it. Progressive, lossless and arithmetic coded files and CMYK
+// are not supported.
+//
+// The width is rounded up to a multiple of 4 so that rows of the texture
+// need no padding, the extra columns repeat the edge of the image.
+pub fn decode(data: &[u8]) -> Result<(usize, usize, Vec<u8>)> {
+    let mut decoder = Decoder::new();
+    if data.get(..2) != Some(&[0xff, 0xd8]) {
+        return Err(jpeg_error("not a JPEG file"));
+    }
+    let mut pos = 2;
+    loop {
+        // markers may be preceded by fill bytes
+        while data.get(pos) == Some(&0xff) && data.get(pos + 1) == Some(&0xff) {
+            pos += 1;
+        }
+        let (Some(0xff), Some(&marker)) = (data.get(pos), data.get(pos + 1)) else {
+            return Err(jpeg_error("missing marker"));
+        };
+        pos += 2;
+        match marker {
+            0xd9 => break,
+            0xd0..=0xd7 | 0x01 => continue,
+            _ => {}
+        }
+        let len = usize::from(read_u16(data, pos)?);
+        let segment = data
+            .get(pos + 2..pos + len)
+            .filter(|_| len >= 2)
+            .ok_or(jpeg_error("truncated segment"))?;
+        pos += len;
+        match marker {
+            0xc0 | 0xc1 => decoder.read_frame(segment)?,
+            0xc2 => return Err(jpeg_error("progressive JPEG is not supported")),
+            0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf => {
+                return Err(jpeg_error("unsupported JPEG coding"));
+            }
+            0xc4 => decoder.read_huffman_tables(segment)?,
+            0xdb => decoder.read_quant_tables(segment)?,
+            0xdd => decoder.restart_interval = usize::from(read_u16(segment, 0)?),
+            0xda => pos = decoder.read_scan(segment, data, pos)?,
+            _ => {} // APPn, comments
+        }
+    }
+    decoder.to_ycbcr24()
+}
+
+// ----------------------------------------------------------------------------
+fn jpeg_error(msg: &str) -> Error {
+    Error::Jpeg {
+        msg: msg.to_string(),
+    }
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16> {
+    match data.get(pos..pos + 2) {
+        Some(&[hi, lo]) => Ok(u16::from_be_bytes([hi, lo])),
+        _ => Err(jpeg_error("truncated segment")),
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Debug, Default)]
+struct Component {
+    id: u8,
+    h: usize, // sampling factors
+    v: usize,
+    quant: usize,
+    dc_table: usize,
+    ac_table: usize,
+    pred: i32, // DC of the previous block
+    stride: usize,
+    plane: Vec<u8>,
+}
+
+struct Decoder {
+    cx: usize,
+    cy: usize,
+    h_max: usize,
+    v_max: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+    components: Vec<Component>,
+    quant: [[u16; 64]; 4], // in zigzag order
+    dc_tables: [Option<Huffman>; 4],
+    ac_tables: [Option<Huffman>; 4],
+    restart_interval: usize,
+    idct: Idct,
+}
+
+impl Decoder {
+    // ------------------------------------------------------------------------
+    fn new() -> Self {
+        Self {
+            cx: 0,
+            cy: 0,
+            h_max: 1,
+            v_max: 1,
+            mcus_x: 0,
+            mcus_y: 0,
+            components: Vec::new(),
+            quant: [[1; 64]; 4],
+            dc_tables: Default::default(),
+            ac_tables: Default::default(),
+            restart_interval: 0,
+            idct: Idct::default(),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn read_frame(&mut self, segment: &[u8]) -> Result<()> {
+        if !self.components.is_empty() {
+            return Err(jpeg_error("more than one frame"));
+        }
+        let (&precision, rest) = segment.split_first().ok_or(jpeg_error("empty frame"))?;
+        if precision != 8 {
+            return Err(jpeg_error("only 8 bit samples are supported"));
+        }
+        self.cy = usize::from(read_u16(rest, 0)?);
+        self.cx = usize::from(read_u16(rest, 2)?);
+        let count = usize::from(*rest.get(4).ok_or(jpeg_error("truncated frame"))?);
+        if self.cx == 0 || self.cy == 0 || !(count == 1 || count == 3) {
+            return Err(jpeg_error("unsupported frame"));
+        }
+        if self.cx * self.cy > MAX_PIXELS {
+            return Err(jpeg_error("frame too large"));
+        }
+        let specs = rest
+            .get(5..5 + 3 * count)
+            .ok_or(jpeg_error("truncated frame"))?;
+        for spec in specs.chunks_exact(3) {
+            let (h, v) = (usize::from(spec[1] >> 4), usize::from(spec[1] & 15));
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) || spec[2] > 3 {
+                return Err(jpeg_error("invalid component"));
+            }
+            self.components.push(Component {
+                id: spec[0],
+                h,
+                v,
+                quant: usize::from(spec[2]),
+                ..Component::default()
+            });
+        }
+        self.h_max = self.components.iter().map(|c| c.h).max().unwrap_or(1);
+        self.v_max = self.components.iter().map(|c| c.v).max().unwrap_or(1);
+        self.mcus_x = self.cx.div_ceil(8 * self.h_max);
+        self.mcus_y = self.cy.div_ceil(8 * self.v_max);
+        for c in &mut self.components {
+            c.stride = self.mcus_x * c.h * 8;
+            c.plane = vec![0; c.stride * self.mcus_y * c.v * 8];
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    fn read_quant_tables(&mut self, mut segment: &[u8]) -> Result<()> {
+        while let Some((&pq_tq, rest)) = segment.split_first() {
+            let (wide, id) = (pq_tq >> 4 != 0, usize::from(pq_tq & 3));
+            let size = if wide { 128 } else { 64 };
+            let values = rest.get(..size).ok_or(jpeg_error("truncated table"))?;
+            for (k, q) in self.quant[id].iter_mut().enumerate() {
+                *q = match wide {
+                    true => u16::from_be_bytes([values[2 * k], values[2 * k + 1]]),
+                    false => u16::from(values[k]),
+                };
+            }
+            segment = &rest[size..];
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    fn read_huffman_tables(&mut self, mut segment: &[u8]) -> Result<()> {
+        while let Some((&tc_th, rest)) = segment.split_first() {
+            let counts: [u8; 16] = rest
+                .get(..16)
+                .and_then(|c| c.try_into().ok())
+                .ok_or(jpeg_error("truncated table"))?;
+            let total: usize = counts.iter().map(|&n| usize::from(n)).sum();
+            let symbols = rest
+                .get(16..16 + total)
+                .ok_or(jpeg_error("truncated table"))?;
+            let table = Some(Huffman::new(&counts, symbols)?);
+            match tc_th >> 4 {
+                0 => self.dc_tables[usize::from(tc_th & 3)] = table,
+                _ => self.ac_tables[usize::from(tc_th & 3)] = table,
+            }
+            segment = &rest[16 + total..];
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    // Decodes the entropy coded data following the scan header at `pos` of
+    // `data`, returns where it ends
+    fn read_scan(&mut self, segment: &[u8], data: &[u8], pos: usize) -> Result<usize> {
+        if self.components.is_empty() {
+            return Err(jpeg_error("scan before the frame"));
+        }
+        let count = usize::from(*segment.first().ok_or(jpeg_error("empty scan"))?);
+        let specs = segment
+            .get(1..1 + 2 * count)
+            .ok_or(jpeg_error("truncated scan"))?;
+        let mut scan = Vec::with_capacity(count);
+        for spec in specs.chunks_exact(2) {
+            let index = self
+                .components
+                .iter()
+                .position(|c| c.id == spec[0])
+                .ok_or(jpeg_error("unknown component in scan"))?;
+            let c = &mut self.components[index];
+            c.dc_table = usize::from(spec[1] >> 4 & 3);
+            c.ac_table = usize::from(spec[1] & 3);
+            c.pred = 0;
+            scan.push(index);
+        }
+        if scan.is_empty() {
+            return Err(jpeg_error("empty scan"));
+        }
+
+        let mut bits = Bits::new(data, pos);
+        // a single component is coded block by block, without the MCU padding
+        let (units_x, units_y) = match scan[..] {
+            [index] => {
+                let c = &self.components[index];
+                let cx = (self.cx * c.h).div_ceil(self.h_max);
+                let cy = (self.cy * c.v).div_ceil(self.v_max);
+                (cx.div_ceil(8), cy.div_ceil(8))
+            }
+            _ => (self.mcus_x, self.mcus_y),
+        };
+        let mut coefs = [0i32; 64];
+        for unit in 0..units_x * units_y {
+            if self.restart_interval > 0 && unit > 0 && unit % self.restart_interval == 0 {
+                bits.restart();
+                for &index in &scan {
+                    self.components[index].pred = 0;
+                }
+            }
+            let (ux, uy) = (unit % units_x, unit / units_x);
+            for &index in &scan {
+                let (blocks_x, blocks_y) = match scan.len() {
+                    1 => (1, 1),
+                    _ => (self.components[index].h, self.components[index].v),
+                };
+                for by in 0..blocks_y {
+                    for bx in 0..blocks_x {
+                        self.decode_block(index, &mut bits, &mut coefs)?;
+                        let c = &mut self.components[index];
+                        let x = (ux * blocks_x + bx) * 8;
+                        let y = (uy * blocks_y + by) * 8;
+                        let out = &mut c.plane[y * c.stride + x..];
+                        self.idct.apply(&coefs, out, c.stride);
+                    }
+                }
+            }
+        }
+        Ok(bits.end())
+    }
+
+    // ------------------------------------------------------------------------
+    fn decode_block(&mut self, index: usize, bits: &mut Bits, coefs: &mut [i32; 64]) -> Result<()> {
+        let c = &mut self.components[index];
+        let dc = self.dc_tables[c.dc_table].as_ref();
+        let ac = self.ac_tables[c.ac_table].as_ref();
+        let (Some(dc), Some(ac)) = (dc, ac) else {
+            return Err(jpeg_error("missing Huffman table"));
+        };
+        let quant = &self.quant[c.quant];
+
+        coefs.fill(0);
+        let size = dc.decode(bits)?;
+        // corrupt or 16 bit quantized data may exceed i32, decoded as noise
+        c.pred = c.pred.wrapping_add(bits.receive_extend(size));
+        coefs[0] = c.pred.saturating_mul(i32::from(quant[0]));
+        let mut k = 1;
+        while k < 64 {
+            let rs = ac.decode(bits)?;
+            let (run, size) = (usize::from(rs >> 4), rs & 15);
+            if size == 0 {
+                if run != 15 {
+                    break; // end of block
+                }
+                k += 16;
+                continue;
+            }
+            k += run;
+            if k > 63 {
+                return Err(jpeg_error("corrupt block"));
+            }
+            coefs[ZIGZAG[k]] = bits
+                .receive_extend(size)
+                .saturating_mul(i32::from(quant[k]));
+            k += 1;
+        }
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    fn to_ycbcr24(&self) -> Result<(usize, usize, Vec<u8>)> {
+        if self.components.is_empty() {
+            return Err(jpeg_error("no frame"));
+        }
+        let cx = self.cx.next_multiple_of(4);
+        let cy = self.cy;
+        let mut ycbcr = vec![128; cx * cy * 3];
+        for (i, c) in self.components.iter().enumerate() {
+            // nearest sample, the columns past the image repeat its edge
+            let columns: Vec<usize> = (0..cx)
+                .map(|x| x.min(self.cx - 1) * c.h / self.h_max)
+                .collect();
+            for y in 0..cy {
+                let row = &c.plane[y * c.v / self.v_max * c.stride..];
+                let dst = &mut ycbcr[y * cx * 3..(y + 1) * cx * 3];
+                for (px, &x) in dst.chunks_exact_mut(3).zip(&columns) {
+                    px[i] = row[x];
+                }
+            }
+        }
+        Ok((cx, cy, ycbcr))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Canonical Huffman code of a DHT segment
+struct Huffman {
+    fast: Vec<u16>,      // by the next FAST_BITS bits, length << 8 | symbol
+    max_code: [i32; 17], // by length, -1 if there are no codes of it
+    offset: [i32; 17],   // code + offset = index of the symbol
+    symbols: Vec<u8>,
+}
+
+impl Huffman {
+    fn new(counts: &[u8; 16], symbols: &[u8]) -> Result<Self> {
+        let mut table = Huffman {
+            fast: vec![0; 1 << FAST_BITS],
+            max_code: [-1; 17],
+            offset: [0; 17],
+            symbols: symbols.to_vec(),
+        };
+        let mut code = 0i32;
+        let mut k = 0i32;
+        for len in 1..=16 {
+            let count = i32::from(counts[len - 1]);
+            table.offset[len] = k - code;
+            for _ in 0..count {
+                // more codes than the length has room for
+                if code >= 1 << len {
+                    return Err(jpeg_error("invalid Huffman table"));
+                }
+                if len as u32 <= FAST_BITS {
+                    let shift = FAST_BITS - len as u32;
+                    let first = (code as usize) << shift;
+                    let entry = (len as u16) << 8 | u16::from(symbols[k as usize]);
+                    table.fast[first..first + (1 << shift)].fill(entry);
+                }
+                code += 1;
+                k += 1;
+            }
+            if count > 0 {
+                table.max_code[len] = code - 1;
+            }
+            code <<= 1;
+        }
+        Ok(table)
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u8> {
+        let entry = self.fast[bits.peek(FAST_BITS) as usize];
+        if entry != 0 {
+            bits.skip(u32::from(entry >> 8));
+            return Ok(entry as u8);
+        }
+        for len in FAST_BITS as usize + 1..=16 {
+            let code = bits.peek(len as u32) as i32;
+            if code <= self.max_code[len] {
+                bits.skip(len as u32);
+                return Ok(self.symbols[(code + self.offset[len]) as usize]);
+            }
+        }
+        Err(jpeg_error("invalid Huffman code"))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Bits of entropy coded data, stuffed zero bytes removed. Reading stops at
+// the next marker, missing bits are zero.
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    acc: u32, // msb first
+    count: u32,
+}
+
+impl<'a> Bits<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self {
+            data,
+            pos,
+            acc: 0,
+            count: 0,
+        }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 24 {
+            let byte = match (self.data.get(self.pos), self.data.get(self.pos + 1)) {
+                (Some(0xff), Some(0)) => {
+                    self.pos += 2;
+                    0xff
+                }
+                (Some(0xff), _) | (None, _) => 0,
+                (Some(&byte), _) => {
+                    self.pos += 1;
+                    byte
+                }
+            };
+            self.acc |= u32::from(byte) << (24 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn peek(&mut self, n: u32) -> u32 {
+        self.fill();
+        self.acc >> (32 - n)
+    }
+
+    fn skip(&mut self, n: u32) {
+        self.acc <<= n;
+        self.count -= n;
+    }
+
+    // The value of a coefficient coded with `size` bits
+    fn receive_extend(&mut self, size: u8) -> i32 {
+        if size == 0 {
+            return 0;
+        }
+        let size = u32::from(size.min(16));
+        let value = self.peek(size) as i32;
+        self.skip(size);
+        if value < 1 << (size - 1) {
+            value - (1 << size) + 1
+        } else {
+            value
+        }
+    }
+
+    // Skips to the data after the next restart marker
+    fn restart(&mut self) {
+        self.acc = 0;
+        self.count = 0;
+        while self.data.get(self.pos) == Some(&0xff) {
+            self.pos += 1;
+        }
+        if self
+            .data
+            .get(self.pos)
+            .is_some_and(|m| (0xd0..=0xd7).contains(m))
+        {
+            self.pos += 1;
+        }
+    }
+
+    // Position of the marker ending the data, past bytes left undecoded
+    fn end(&self) -> usize {
+        let mut pos = self.pos;
+        while let Some(&byte) = self.data.get(pos) {
+            let next = self.data.get(pos + 1).copied();
+            if byte == 0xff && next.is_some_and(|m| m != 0 && !(0xd0..=0xd7).contains(&m)) {
+                break;
+            }
+            pos += 1;
+        }
+        pos
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Separable inverse DCT of 8x8 blocks, level shifted and clamped to bytes
+struct Idct {
+    cos: [[f32; 8]; 8], // by sample, frequency
+}
+
+impl Default for Idct {
+    fn default() -> Self {
+        let mut cos = [[0.0; 8]; 8];
+        for (x, row) in cos.iter_mut().enumerate() {
+            for (u, c) in row.iter_mut().enumerate() {
+                let scale = if u == 0 { 0.5 / 2f32.sqrt() } else { 0.5 };
+                let angle = (2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0;
+                *c = scale * angle.cos();
+            }
+        }
+        Self { cos }
+    }
+}
+
+impl Idct {
+    fn apply(&self, coefs: &[i32; 64], out: &mut [u8], stride: usize) {
+        let mut rows = [0.0f32; 64];
+        for v in 0..8 {
+            let coefs = &coefs[v * 8..v * 8 + 8];
+            for x in 0..8 {
+                let cos = &self.cos[x];
+                rows[v * 8 + x] = (0..8).map(|u| cos[u] * coefs[u] as f32).sum();
+            }
+        }
+        for y in 0..8 {
+            let cos = &self.cos[y];
+            for x in 0..8 {
+                let sample: f32 = (0..8).map(|v| cos[v] * rows[v * 8 + x]).sum();
+                out[y * stride + x] = (sample + 128.0).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 16x8 grayscale, quantizer 1 and one code per table: DC size 7, end of
+    // block
+    fn gray_jpeg(scan: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xff, 0xd8];
+        data.extend([0xff, 0xdb, 0, 67, 0]);
+        data.extend([1; 64]);
+        data.extend([0xff, 0xc0, 0, 11, 8, 0, 8, 0, 16, 1, 1, 0x11, 0]);
+        for (class, symbol) in [(0x00, 7), (0x10, 0)] {
+            data.extend([0xff, 0xc4, 0, 20, class, 1]);
+            data.extend([0; 15]);
+            data.push(symbol);
+        }
+        data.extend([0xff, 0xda, 0, 8, 1, 1, 0x00, 0, 63, 0]);
+        data.extend(scan);
+        data.extend([0xff, 0xd9]);
+        data
+    }
+
+    #[test]
+    fn test_decode() {
+        // DC +80 then -80: flat blocks of 128 + 80 / 8 and 128
+        let data = gray_jpeg(&[0x50, 0x17, 0xbf]);
+        let (cx, cy, ycbcr) = decode(&data).unwrap();
+        assert_eq!((cx, cy), (16, 8));
+        assert_eq!(ycbcr.len(), 16 * 8 * 3);
+        assert_eq!(&ycbcr[..3], [138, 128, 128]);
+        assert_eq!(&ycbcr[7 * 3..8 * 3], [138, 128, 128]);
+        assert_eq!(&ycbcr[8 * 3..9 * 3], [128, 128, 128]);
+        let last = ycbcr.len() - 3;
+        assert_eq!(&ycbcr[last..], [128, 128, 128]);
+    }
+
+    #[test]
+    fn test_wide_quant_table() {
+        // DC size 15 and 16 bit quantizers of 65535: two DC steps of +32767
+        // exceed i32 and saturate
+        let mut data = gray_jpeg(&[0x7f, 0xff, 0, 0x3f, 0xff, 0, 0xbf]);
+        let dc_symbol = 105;
+        assert_eq!(data[dc_symbol], 7);
+        data[dc_symbol] = 15;
+        let dqt = [0, 131, 0x10].into_iter().chain([0xff; 128]);
+        data.splice(4..71, dqt);
+        let (_, _, ycbcr) = decode(&data).unwrap();
+        assert_eq!(&ycbcr[..3], [255, 128, 128]);
+        assert_eq!(&ycbcr[8 * 3..9 * 3], [255, 128, 128]);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(decode(b"RIFF....WEBP").is_err());
+        let mut progressive = gray_jpeg(&[0x50, 0x17, 0xbf]);
+        progressive[72] = 0xc2;
+        assert_eq!(&progressive[71..73], [0xff, 0xc2]);
+        assert!(decode(&progressive).is_err());
+        assert!(decode(&[0xff, 0xd8, 0xff, 0xd9]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_tables() {
+        // 3 codes of length 1, 2 at most fit
+        let mut counts = [0; 16];
+        counts[0] = 3;
+        assert!(Huffman::new(&counts, &[0, 1, 2]).is_err());
+        counts[0] = 2;
+        assert!(Huffman::new(&counts, &[0, 1]).is_ok());
+
+        // the DC table of the test image with 3 codes of length 1
+        let mut data = gray_jpeg(&[0x50, 0x17, 0xbf]);
+        let dht = 84;
+        assert_eq!(&data[dht..dht + 2], [0xff, 0xc4]);
+        data[dht + 3] = 22;
+        data[dht + 5] = 3;
+        data.splice(dht + 21..dht + 21, [1, 2]);
+        assert!(decode(&data).is_err());
+
+        // 65535 x 65535 pixels
+        let mut decoder = Decoder::new();
+        let frame = [8, 0xff, 0xff, 0xff, 0xff, 1, 1, 0x11, 0];
+        assert!(decoder.read_frame(&frame).is_err());
+    }
+}
@@ -1,3 +1,16 @@
 pub mod animation;
+pub mod blur;
 pub mod color_conversion;
 pub mod color_format;
+pub mod dominant_color;
+pub mod exif;
+pub mod jpeg;
+pub mod png_write;
+pub mod resize;
+pub mod sdf;
+pub mod sharpen;
+pub mod simd;
+pub mod timeline;
+pub mod transform;
+pub mod transition;
+pub mod ttf;
@@ -0,0 +1,238 @@
+use crate::gfx::color_conversion::ImageGeometry;
+use std::f32::consts::PI;
+
+// ----------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Bilinear, // triangle, fast and soft
+    Lanczos3, // windowed sinc, sharper, slight ringing at hard edges
+}
+
+impl Filter {
+    // ------------------------------------------------------------------------
+    fn support(&self) -> f32 {
+        match self {
+            Filter::Bilinear => 1.0,
+            Filter::Lanczos3 => 3.0,
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Filter::Bilinear => (1.0 - x).max(0.0),
+            Filter::Lanczos3 => {
+                if x < 3.0 {
+                    sinc(x) * sinc(x / 3.0)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn sinc(x: f32) -> f32 {
+    if x < 1e-6 {
+        1.0
+    } else {
+        let x = PI * x;
+        x.sin() / x
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Source pixels and their normalized weights contributing to one target pixel
+struct Contribution {
+    start: usize,
+    weights: Vec<f32>,
+}
+
+// ----------------------------------------------------------------------------
+// When downscaling the filter is stretched by the scale factor so that every
+// source pixel contributes.
+fn contributions(src_len: usize, dst_len: usize, filter: Filter) -> Vec<Contribution> {
+    let scale = src_len as f32 / dst_len as f32;
+    let filter_scale = scale.max(1.0);
+    let support = filter.support() * filter_scale;
+
+    (0..dst_len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            let start = (center - support).floor().max(0.0) as usize;
+            let end = ((center + support).ceil() as usize).min(src_len);
+
+            let mut weights: Vec<f32> = (start..end)
+                .map(|j| filter.weight((j as f32 + 0.5 - center) / filter_scale))
+                .collect();
+            let sum: f32 = weights.iter().sum();
+            if sum > 0.0 {
+                weights.iter_mut().for_each(|w| *w /= sum);
+                Contribution { start, weights }
+            } else {
+                let nearest = (center as usize).min(src_len - 1);
+                Contribution {
+                    start: nearest,
+                    weights: vec![1.0],
+                }
+            }
+        })
+        .collect()
+}
+
+// ----------------------------------------------------------------------------
+// Resize an 8 bit image with `channels` interleaved components per pixel and
+// tightly packed rows. A single plane of a planar format has one channel.
+pub fn resize(
+    src: &[u8],
+    cx: usize,
+    cy: usize,
+    channels: usize,
+    dst_cx: usize,
+    dst_cy: usize,
+    filter: Filter,
+) -> Vec<u8> {
+    if cx == dst_cx && cy == dst_cy {
+        return src[..cx * cy * channels].to_vec();
+    }
+    if cx == 0 || cy == 0 || dst_cx == 0 || dst_cy == 0 {
+        return vec![0; dst_cx * dst_cy * channels];
+    }
+
+    // horizontal pass into a float buffer of dst_cx x cy
+    let h = contributions(cx, dst_cx, filter);
+    let mut tmp = vec![0.0f32; dst_cx * cy * channels];
+    for y in 0..cy {
+        let src_row = &src[y * cx * channels..(y + 1) * cx * channels];
+        let tmp_row = &mut tmp[y * dst_cx * channels..(y + 1) * dst_cx * channels];
+        for (x, c) in h.iter().enumerate() {
+            for (k, w) in c.weights.iter().enumerate() {
+                let s = (c.start + k) * channels;
+                for ch in 0..channels {
+                    tmp_row[x * channels + ch] += w * src_row[s + ch] as f32;
+                }
+            }
+        }
+    }
+
+    // vertical pass
+    let v = contributions(cy, dst_cy, filter);
+    let row_len = dst_cx * channels;
+    let mut dst = vec![0; dst_cy * row_len];
+    let mut acc = vec![0.0f32; row_len];
+    for (y, c) in v.iter().enumerate() {
+        acc.fill(0.0);
+        for (k, w) in c.weights.iter().enumerate() {
+            let tmp_row = &tmp[(c.start + k) * row_len..(c.start + k + 1) * row_len];
+            for (a, t) in acc.iter_mut().zip(tmp_row) {
+                *a += w * t;
+            }
+        }
+        let dst_row = &mut dst[y * row_len..(y + 1) * row_len];
+        for (d, a) in dst_row.iter_mut().zip(&acc) {
+            *d = a.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    dst
+}
+
+// ----------------------------------------------------------------------------
+// Resize the three planes of a YCbCr 4:2:0 image, chroma at half resolution
+pub fn resize_ycbcr420(
+    luma: &[u8],
+    cb: &[u8],
+    cr: &[u8],
+    geo: &ImageGeometry,
+    dst_cx: usize,
+    dst_cy: usize,
+    filter: Filter,
+) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let (chroma_cx, chroma_cy) = (geo.cx.div_ceil(2), geo.cy.div_ceil(2));
+    let (dst_chroma_cx, dst_chroma_cy) = (dst_cx.div_ceil(2), dst_cy.div_ceil(2));
+    let resize_chroma = |plane: &[u8]| {
+        resize(
+            plane,
+            chroma_cx,
+            chroma_cy,
+            1,
+            dst_chroma_cx,
+            dst_chroma_cy,
+            filter,
+        )
+    };
+    (
+        resize(luma, geo.cx, geo.cy, 1, dst_cx, dst_cy, filter),
+        resize_chroma(cb),
+        resize_chroma(cr),
+    )
+}
+
+// ----------------------------------------------------------------------------
+// Largest size with the aspect ratio of cx x cy fitting into max_cx x max_cy,
+// never upscaling
+pub fn fit_size(cx: usize, cy: usize, max_cx: usize, max_cy: usize) -> (usize, usize) {
+    if cx <= max_cx && cy <= max_cy {
+        return (cx, cy);
+    }
+    let scale = f64::min(max_cx as f64 / cx as f64, max_cy as f64 / cy as f64);
+    let fit = |n: usize| ((n as f64 * scale).round() as usize).max(1);
+    (fit(cx), fit(cy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::color_format::ColorFormat;
+
+    #[test]
+    fn test_resize_bilinear() {
+        let src = vec![0, 100, 200, 100];
+        assert_eq!(resize(&src, 4, 1, 1, 2, 1, Filter::Bilinear), vec![71, 143]);
+        assert_eq!(resize(&src, 4, 1, 1, 4, 1, Filter::Bilinear), src);
+        assert_eq!(resize(&src, 2, 2, 1, 1, 1, Filter::Bilinear), vec![100]);
+
+        // interleaved channels are filtered independently
+        let rgb = vec![10, 20, 30, 30, 40, 50];
+        assert_eq!(
+            resize(&rgb, 2, 1, 3, 1, 1, Filter::Bilinear),
+            vec![20, 30, 40]
+        );
+    }
+
+    #[test]
+    fn test_resize_lanczos() {
+        let flat = vec![77; 13 * 7 * 2];
+        let out = resize(&flat, 13, 7, 2, 5, 3, Filter::Lanczos3);
+        assert_eq!(out, vec![77; 5 * 3 * 2]);
+
+        let ramp: Vec<u8> = (0..64).map(|i| i * 4).collect();
+        let up = resize(&ramp, 64, 1, 1, 100, 1, Filter::Lanczos3);
+        assert!(up[10..90].windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_resize_ycbcr420() {
+        let geo = ImageGeometry {
+            cx: 8,
+            cy: 6,
+            cf: ColorFormat::YCbCr420,
+        };
+        let (y, cb, cr) = resize_ycbcr420(
+            &[16; 48],
+            &[128; 12],
+            &[64; 12],
+            &geo,
+            3,
+            3,
+            Filter::Bilinear,
+        );
+        assert_eq!(y, vec![16; 9]);
+        assert_eq!(cb, vec![128; 4]);
+        assert_eq!(cr, vec![64; 4]);
+
+        assert_eq!(fit_size(4000, 3000, 1920, 1080), (1440, 1080));
+        assert_eq!(fit_size(800, 600, 1920, 1080), (800, 600));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::gfx::ttf::{Contour, Segment};
+use crate::v2d::v2::V2;
+
+// Quadratic curves are measured as this many lines
+const CURVE_STEPS: usize = 8;
+
+// ----------------------------------------------------------------------------
+// Signed distance field of glyph outlines, one byte per pixel. About 128 is
+// the edge, brighter is inside, and `px_range` pixels of distance span the whole
+// byte. `origin` is the center of the top left pixel in font units, `scale`
+// the pixels per font unit.
+pub fn distance_field(
+    contours: &[Contour],
+    width: usize,
+    height: usize,
+    origin: V2,
+    scale: f32,
+    px_range: f32,
+) -> Vec<u8> {
+    let edges = flatten(contours);
+    let mut field = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let p = origin + V2::new([x as f32, -(y as f32)]) / scale;
+            let distance = edges
+                .iter()
+                .map(|(a, b)| segment_distance(p, *a, *b))
+                .fold(f32::MAX, f32::min);
+            let sign = if winding(&edges, p) != 0 { 1.0 } else { -1.0 };
+            let value = 0.5 + sign * distance * scale / px_range;
+            field.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+    field
+}
+
+// ----------------------------------------------------------------------------
+fn flatten(contours: &[Contour]) -> Vec<(V2, V2)> {
+    let mut edges = Vec::new();
+    for segment in contours.iter().flatten() {
+        match *segment {
+            Segment::Line(a, b) => edges.push((a, b)),
+            Segment::Quad(a, c, b) => {
+                let mut from = a;
+                for i in 1..=CURVE_STEPS {
+                    let t = i as f32 / CURVE_STEPS as f32;
+                    let to = V2::lerp(&V2::lerp(&a, &c, t), &V2::lerp(&c, &b, t), t);
+                    edges.push((from, to));
+                    from = to;
+                }
+            }
+        }
+    }
+    edges
+}
+
+fn segment_distance(p: V2, a: V2, b: V2) -> f32 {
+    let ab = b - a;
+    let len2 = ab.x0() * ab.x0() + ab.x1() * ab.x1();
+    let t = match len2 > 0.0 {
+        true => (((p - a).x0() * ab.x0() + (p - a).x1() * ab.x1()) / len2).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+    V2::distance(&p, &(a + ab * t))
+}
+
+// ----------------------------------------------------------------------------
+// Non-zero winding number as TrueType fills its outlines
+fn winding(edges: &[(V2, V2)], p: V2) -> i32 {
+    let mut winding = 0;
+    for (a, b) in edges {
+        if (a.x1() <= p.x1()) == (b.x1() <= p.x1()) {
+            continue;
+        }
+        let t = (p.x1() - a.x1()) / (b.x1() - a.x1());
+        if a.x0() + t * (b.x0() - a.x0()) > p.x0() {
+            winding += if b.x1() > a.x1() { 1 } else { -1 };
+        }
+    }
+    winding
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_field() {
+        // 10x10 units square, counter-clockwise
+        let p = |x: f32, y: f32| V2::new([x, y]);
+        let square = vec![
+            Segment::Line(p(0.0, 0.0), p(10.0, 0.0)),
+            Segment::Line(p(10.0, 0.0), p(10.0, 10.0)),
+            Segment::Line(p(10.0, 10.0), p(0.0, 10.0)),
+            Segment::Line(p(0.0, 10.0), p(0.0, 0.0)),
+        ];
+        // one pixel per unit, pixels centered on -2.5..16.5
+        let field = distance_field(&[square], 20, 20, p(-2.5, 16.5), 1.0, 4.0);
+        let at = |x: usize, y: usize| field[y * 20 + x];
+        assert_eq!(at(0, 0), 0); // far outside
+        assert_eq!(at(7, 10), 255); // center
+        assert_eq!(at(3, 10), 159); // 0.5 inside the left edge
+        assert_eq!(at(2, 10), 96); // 0.5 outside
+    }
+}
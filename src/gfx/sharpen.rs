@@ -0,0 +1,77 @@
+use crate::gfx::blur;
+
+// ----------------------------------------------------------------------------
+// Unsharp mask: adds the difference between the image and a Gaussian blurred
+// copy back to the image, scaled by `amount`. Differences up to `threshold`
+// are left alone so that flat areas and noise are not amplified.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnsharpMask {
+    pub amount: f32, // 0.5 is subtle, 1.5 is strong
+    pub radius: f32, // sigma of the blur in pixels
+    pub threshold: u8,
+}
+
+impl Default for UnsharpMask {
+    fn default() -> Self {
+        Self {
+            amount: 0.6,
+            radius: 1.0,
+            threshold: 2,
+        }
+    }
+}
+
+impl UnsharpMask {
+    // ------------------------------------------------------------------------
+    // Sharpen one channel of an image with `channels` interleaved components,
+    // e.g. only the luma of YCbCr to avoid color fringes.
+    pub fn apply(&self, data: &mut [u8], cx: usize, cy: usize, channels: usize, channel: usize) {
+        if self.amount <= 0.0 || self.radius <= 0.0 || cx == 0 || cy == 0 {
+            return;
+        }
+
+        let plane: Vec<f32> = (0..cx * cy)
+            .map(|i| data[i * channels + channel] as f32)
+            .collect();
+        let blurred = blur::convolve(&plane, cx, cy, 1, &blur::gaussian_kernel(self.radius));
+
+        for (i, (orig, b)) in plane.iter().zip(&blurred).enumerate() {
+            let diff = orig - b;
+            if diff.abs() > self.threshold as f32 {
+                let v = orig + self.amount * diff;
+                data[i * channels + channel] = v.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unsharp_mask() {
+        let usm = UnsharpMask {
+            amount: 1.0,
+            radius: 1.0,
+            threshold: 0,
+        };
+
+        // flat areas stay untouched
+        let mut flat = vec![90; 6 * 4];
+        usm.apply(&mut flat, 6, 4, 1, 0);
+        assert_eq!(flat, vec![90; 6 * 4]);
+
+        // an edge gets more contrast on both sides
+        let mut edge = vec![100, 100, 100, 150, 150, 150];
+        usm.apply(&mut edge, 6, 1, 1, 0);
+        assert!(edge[2] < 100 && edge[3] > 150);
+        assert_eq!((edge[0], edge[5]), (100, 150));
+
+        // only the selected channel changes
+        let mut yuv = vec![100, 1, 2, 100, 1, 2, 150, 1, 2, 150, 1, 2];
+        usm.apply(&mut yuv, 4, 1, 3, 0);
+        assert!(yuv[3] < 100 && yuv[6] > 150);
+        assert!(yuv.chunks(3).all(|p| p[1..] == [1, 2]));
+    }
+}
@@ -0,0 +1,211 @@
+// ----------------------------------------------------------------------------
+// Vectorized row kernels for the color conversions. Each kernel converts a
+// prefix of the row and returns the number of pixels done, the caller's scalar
+// loop takes care of the rest. Without a usable instruction set they return 0.
+
+// ----------------------------------------------------------------------------
+// Y, Cb, Cr planes with horizontally subsampled chroma to interleaved YCbCr
+pub fn ycbcr420_row(dst: &mut [u8], luma: &[u8], cb: &[u8], cr: &[u8]) -> usize {
+    let cx = luma
+        .len()
+        .min(dst.len() / 3)
+        .min(cb.len() * 2)
+        .min(cr.len() * 2);
+
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("ssse3") {
+        return unsafe { x86::ycbcr420_row(dst, luma, cb, cr, cx) };
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        return unsafe { neon::ycbcr420_row(dst, luma, cb, cr, cx) };
+    }
+
+    let _ = (dst, cx);
+    0
+}
+
+// ----------------------------------------------------------------------------
+// 1 bit indices, most significant bit first, to 32 bit colors
+pub fn pal1_row(dst: &mut [u32], src: &[u8], palette: &[u32]) -> usize {
+    if palette.len() < 2 {
+        return 0;
+    }
+    let bytes = src.len().min(dst.len() / 8);
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SSE2 is part of the x86_64 baseline
+        unsafe { x86::pal1_row(dst, src, palette[0], palette[1], bytes) }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        return unsafe { neon::pal1_row(dst, src, palette[0], palette[1], bytes) };
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let _ = (dst, src, bytes);
+        0
+    }
+}
+
+// ----------------------------------------------------------------------------
+// 8 bit indices to 32 bit colors, requires a full 256 entry palette
+pub fn pal8_row(dst: &mut [u32], src: &[u8], palette: &[u32]) -> usize {
+    if palette.len() < 256 {
+        return 0;
+    }
+    let cx = src.len().min(dst.len());
+
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        return unsafe { x86::pal8_row(dst, src, palette, cx) };
+    }
+
+    let _ = (dst, cx);
+    0
+}
+
+// ----------------------------------------------------------------------------
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    // pshufb masks picking the Y, Cb and Cr byte of each output position for
+    // the three 16 byte blocks of 16 interleaved pixels. 0x80 yields zero.
+    const fn ycbcr_masks(component: usize, chroma: bool) -> [[u8; 16]; 3] {
+        let mut masks = [[0x80u8; 16]; 3];
+        let mut i = 0;
+        while i < 48 {
+            if i % 3 == component {
+                let pixel = i / 3;
+                masks[i / 16][i % 16] = if chroma { pixel / 2 } else { pixel } as u8;
+            }
+            i += 1;
+        }
+        masks
+    }
+
+    const Y_MASKS: [[u8; 16]; 3] = ycbcr_masks(0, false);
+    const CB_MASKS: [[u8; 16]; 3] = ycbcr_masks(1, true);
+    const CR_MASKS: [[u8; 16]; 3] = ycbcr_masks(2, true);
+
+    // ------------------------------------------------------------------------
+    #[target_feature(enable = "ssse3")]
+    pub unsafe fn ycbcr420_row(
+        dst: &mut [u8],
+        luma: &[u8],
+        cb: &[u8],
+        cr: &[u8],
+        cx: usize,
+    ) -> usize {
+        let blocks = cx / 16;
+        for i in 0..blocks {
+            unsafe {
+                let y = _mm_loadu_si128(luma.as_ptr().add(i * 16) as *const __m128i);
+                let u = _mm_loadl_epi64(cb.as_ptr().add(i * 8) as *const __m128i);
+                let v = _mm_loadl_epi64(cr.as_ptr().add(i * 8) as *const __m128i);
+                for k in 0..3 {
+                    let my = _mm_loadu_si128(Y_MASKS[k].as_ptr() as *const __m128i);
+                    let mu = _mm_loadu_si128(CB_MASKS[k].as_ptr() as *const __m128i);
+                    let mv = _mm_loadu_si128(CR_MASKS[k].as_ptr() as *const __m128i);
+                    let out = _mm_or_si128(
+                        _mm_shuffle_epi8(y, my),
+                        _mm_or_si128(_mm_shuffle_epi8(u, mu), _mm_shuffle_epi8(v, mv)),
+                    );
+                    let p = dst.as_mut_ptr().add(i * 48 + k * 16);
+                    _mm_storeu_si128(p as *mut __m128i, out);
+                }
+            }
+        }
+        blocks * 16
+    }
+
+    // ------------------------------------------------------------------------
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn pal1_row(dst: &mut [u32], src: &[u8], c0: u32, c1: u32, bytes: usize) -> usize {
+        let c0 = _mm_set1_epi32(c0 as i32);
+        let c1 = _mm_set1_epi32(c1 as i32);
+        let bits_lo = _mm_setr_epi32(0x80, 0x40, 0x20, 0x10);
+        let bits_hi = _mm_setr_epi32(0x08, 0x04, 0x02, 0x01);
+        for (i, &byte) in src[..bytes].iter().enumerate() {
+            let b = _mm_set1_epi32(byte as i32);
+            for (k, bits) in [bits_lo, bits_hi].into_iter().enumerate() {
+                let mask = _mm_cmpeq_epi32(_mm_and_si128(b, bits), bits);
+                let out = _mm_or_si128(_mm_and_si128(mask, c1), _mm_andnot_si128(mask, c0));
+                unsafe {
+                    let p = dst.as_mut_ptr().add(i * 8 + k * 4);
+                    _mm_storeu_si128(p as *mut __m128i, out);
+                }
+            }
+        }
+        bytes * 8
+    }
+
+    // ------------------------------------------------------------------------
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn pal8_row(dst: &mut [u32], src: &[u8], palette: &[u32], cx: usize) -> usize {
+        let blocks = cx / 8;
+        for i in 0..blocks {
+            unsafe {
+                let idx = _mm_loadl_epi64(src.as_ptr().add(i * 8) as *const __m128i);
+                let idx = _mm256_cvtepu8_epi32(idx);
+                let out = _mm256_i32gather_epi32::<4>(palette.as_ptr() as *const i32, idx);
+                _mm256_storeu_si256(dst.as_mut_ptr().add(i * 8) as *mut __m256i, out);
+            }
+        }
+        blocks * 8
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    // ------------------------------------------------------------------------
+    #[target_feature(enable = "neon")]
+    pub unsafe fn ycbcr420_row(
+        dst: &mut [u8],
+        luma: &[u8],
+        cb: &[u8],
+        cr: &[u8],
+        cx: usize,
+    ) -> usize {
+        let blocks = cx / 16;
+        for i in 0..blocks {
+            unsafe {
+                let y = vld1q_u8(luma.as_ptr().add(i * 16));
+                let u = vld1_u8(cb.as_ptr().add(i * 8));
+                let v = vld1_u8(cr.as_ptr().add(i * 8));
+                let u = vcombine_u8(vzip1_u8(u, u), vzip2_u8(u, u));
+                let v = vcombine_u8(vzip1_u8(v, v), vzip2_u8(v, v));
+                vst3q_u8(dst.as_mut_ptr().add(i * 48), uint8x16x3_t(y, u, v));
+            }
+        }
+        blocks * 16
+    }
+
+    // ------------------------------------------------------------------------
+    #[target_feature(enable = "neon")]
+    pub unsafe fn pal1_row(dst: &mut [u32], src: &[u8], c0: u32, c1: u32, bytes: usize) -> usize {
+        let c0 = vdupq_n_u32(c0);
+        let c1 = vdupq_n_u32(c1);
+        let bits_lo: [u32; 4] = [0x80, 0x40, 0x20, 0x10];
+        let bits_hi: [u32; 4] = [0x08, 0x04, 0x02, 0x01];
+        let bits_lo = unsafe { vld1q_u32(bits_lo.as_ptr()) };
+        let bits_hi = unsafe { vld1q_u32(bits_hi.as_ptr()) };
+        for (i, &byte) in src[..bytes].iter().enumerate() {
+            let b = vdupq_n_u32(byte as u32);
+            for (k, bits) in [bits_lo, bits_hi].into_iter().enumerate() {
+                let mask = vtstq_u32(b, bits);
+                let out = vbslq_u32(mask, c1, c0);
+                unsafe { vst1q_u32(dst.as_mut_ptr().add(i * 8 + k * 4), out) };
+            }
+        }
+        bytes * 8
+    }
+}
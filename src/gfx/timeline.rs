@@ -0,0 +1,151 @@
+use crate::gfx::animation::Track;
+
+// ----------------------------------------------------------------------------
+// Owns a set of scalar tracks identified by a scene defined key. Track times
+// are relative to the moment the track is added. Advancing the timeline
+// reports every track that ran to completion, finished tracks are dropped.
+#[derive(Clone, Debug)]
+pub struct Timeline<K> {
+    time: f32,
+    entries: Vec<Entry<K>>,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+struct Entry<K> {
+    key: K,
+    start: f32,
+    track: Track<f32>,
+}
+
+impl<K: Copy + PartialEq> Timeline<K> {
+    // ------------------------------------------------------------------------
+    pub fn new() -> Self {
+        Timeline {
+            time: 0.0,
+            entries: Vec::new(),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Starts `track` now, replacing a running track with the same key
+    pub fn add(&mut self, key: K, track: Track<f32>) {
+        self.remove(key);
+        self.entries.push(Entry {
+            key,
+            start: self.time,
+            track,
+        });
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn remove(&mut self, key: K) {
+        self.entries.retain(|e| e.key != key);
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn contains(&self, key: K) -> bool {
+        self.entries.iter().any(|e| e.key == key)
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // ------------------------------------------------------------------------
+    // Current value of the track, None if it is not running (anymore)
+    pub fn value(&self, key: K) -> Option<f32> {
+        self.entries
+            .iter()
+            .find(|e| e.key == key)
+            .map(|e| e.track.blend(self.time - e.start))
+    }
+
+    // ------------------------------------------------------------------------
+    // Moves time forward by dt seconds and returns the keys of all tracks
+    // that completed, in the order they were added.
+    pub fn advance(&mut self, dt: f32) -> Vec<K> {
+        self.time += dt;
+        let time = self.time;
+
+        let mut finished = Vec::new();
+        self.entries.retain(|e| {
+            let done = e.track.is_finished(time - e.start);
+            if done {
+                finished.push(e.key);
+            }
+            !done
+        });
+        finished
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl<K: Copy + PartialEq> Default for Timeline<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_float_eq;
+    use crate::gfx::animation::{Easing, Repeat};
+    use crate::v2d::float_eq::float_eq_rel;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Key {
+        Fade,
+        Hold,
+        Pulse,
+    }
+
+    #[test]
+    fn test_timeline() {
+        let mut timeline = Timeline::new();
+        timeline.add(
+            Key::Fade,
+            Track::new(0.0, 0.0).key(1.0, 1.0, Easing::Linear),
+        );
+        timeline.advance(0.5);
+        timeline.add(
+            Key::Hold,
+            Track::new(0.0, 1.0).key(1.0, 1.0, Easing::Linear),
+        );
+        timeline.add(
+            Key::Pulse,
+            Track::new(0.0, 0.0)
+                .key(0.1, 1.0, Easing::Linear)
+                .with_repeat(Repeat::PingPong),
+        );
+
+        assert_float_eq!(timeline.value(Key::Fade).unwrap(), 0.5);
+        assert!(timeline.advance(0.25).is_empty());
+        assert_eq!(timeline.advance(0.25), vec![Key::Fade]);
+        assert_eq!(timeline.value(Key::Fade), None);
+        assert_eq!(timeline.advance(0.5), vec![Key::Hold]);
+
+        // repeating tracks run until removed
+        assert!(timeline.contains(Key::Pulse));
+        timeline.remove(Key::Pulse);
+        assert!(timeline.is_empty());
+    }
+
+    #[test]
+    fn test_timeline_restart() {
+        let mut timeline = Timeline::new();
+        let track = Track::new(0.0, 0.0).key(1.0, 1.0, Easing::Linear);
+        timeline.add(Key::Fade, track.clone());
+        timeline.advance(0.75);
+        timeline.add(Key::Fade, track);
+        assert_float_eq!(timeline.value(Key::Fade).unwrap(), 0.0);
+        assert!(timeline.advance(0.75).is_empty());
+    }
+}
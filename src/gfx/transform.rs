@@ -0,0 +1,184 @@
+use crate::error::{Error, Result};
+use crate::gfx::color_conversion::{ImageBuffer, ImageGeometry};
+use crate::gfx::color_format::ColorFormat;
+
+// ----------------------------------------------------------------------------
+// Clockwise rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    R0,
+    R90,
+    R180,
+    R270,
+}
+
+// ----------------------------------------------------------------------------
+// Bytes per pixel, sub-byte and planar formats are not supported
+fn pixel_size(geo: &ImageGeometry) -> Result<usize> {
+    match geo.cf {
+        ColorFormat::YCbCr420 | ColorFormat::NV12 | ColorFormat::YCbCr422 | ColorFormat::YUYV => {
+            Err(Error::InvalidColorFormat)
+        }
+        cf if !cf.bpp().is_multiple_of(8) => Err(Error::InvalidColorFormat),
+        cf => Ok(cf.bpp() / 8),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Copy the cx x cy rect at (x, y) into a new buffer with rows padded to
+// `alignment` bytes
+pub fn crop(
+    src: &ImageBuffer,
+    geo: &ImageGeometry,
+    (x, y, cx, cy): (usize, usize, usize, usize),
+    alignment: usize,
+) -> Result<(ImageBuffer, ImageGeometry)> {
+    let n = pixel_size(geo)?;
+    if x + cx > geo.cx || y + cy > geo.cy {
+        return Err(Error::InvalidTextureSize);
+    }
+
+    let dst_geo = ImageGeometry { cx, cy, ..*geo };
+    let stride = geo.cf.stride(cx, alignment);
+    let mut data = vec![0; stride * cy];
+    for row in 0..cy {
+        let s = (y + row) * src.stride + x * n;
+        data[row * stride..row * stride + cx * n].copy_from_slice(&src.data[s..s + cx * n]);
+    }
+    Ok((ImageBuffer { data, stride }, dst_geo))
+}
+
+// ----------------------------------------------------------------------------
+pub fn rotate(
+    src: &ImageBuffer,
+    geo: &ImageGeometry,
+    rotation: Rotation,
+    alignment: usize,
+) -> Result<(ImageBuffer, ImageGeometry)> {
+    let n = pixel_size(geo)?;
+    let (cx, cy) = match rotation {
+        Rotation::R0 | Rotation::R180 => (geo.cx, geo.cy),
+        Rotation::R90 | Rotation::R270 => (geo.cy, geo.cx),
+    };
+
+    let dst_geo = ImageGeometry { cx, cy, ..*geo };
+    let stride = geo.cf.stride(cx, alignment);
+    let mut data = vec![0; stride * cy];
+    for y in 0..cy {
+        for x in 0..cx {
+            // source pixel ending up at (x, y)
+            let (sx, sy) = match rotation {
+                Rotation::R0 => (x, y),
+                Rotation::R90 => (y, geo.cy - 1 - x),
+                Rotation::R180 => (geo.cx - 1 - x, geo.cy - 1 - y),
+                Rotation::R270 => (geo.cx - 1 - y, x),
+            };
+            let s = sy * src.stride + sx * n;
+            let d = y * stride + x * n;
+            data[d..d + n].copy_from_slice(&src.data[s..s + n]);
+        }
+    }
+    Ok((ImageBuffer { data, stride }, dst_geo))
+}
+
+// ----------------------------------------------------------------------------
+// Mirrors the image horizontally, the left column becomes the right one
+pub fn mirror(
+    src: &ImageBuffer,
+    geo: &ImageGeometry,
+    alignment: usize,
+) -> Result<(ImageBuffer, ImageGeometry)> {
+    let n = pixel_size(geo)?;
+    let stride = geo.cf.stride(geo.cx, alignment);
+    let mut data = vec![0; stride * geo.cy];
+    for y in 0..geo.cy {
+        for x in 0..geo.cx {
+            let s = y * src.stride + (geo.cx - 1 - x) * n;
+            let d = y * stride + x * n;
+            data[d..d + n].copy_from_slice(&src.data[s..s + n]);
+        }
+    }
+    Ok((ImageBuffer { data, stride }, *geo))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 3x2 Y8 image with 4 byte aligned rows
+    fn image() -> (ImageBuffer, ImageGeometry) {
+        #[rustfmt::skip]
+        let data = vec![
+            1, 2, 3, 0,
+            4, 5, 6, 0,
+        ];
+        let geo = ImageGeometry {
+            cx: 3,
+            cy: 2,
+            cf: ColorFormat::Y8,
+        };
+        (ImageBuffer { data, stride: 4 }, geo)
+    }
+
+    #[test]
+    fn test_rotate() {
+        let (img, geo) = image();
+
+        let (r90, g90) = rotate(&img, &geo, Rotation::R90, 1).unwrap();
+        assert_eq!((g90.cx, g90.cy), (2, 3));
+        assert_eq!(r90.data, vec![4, 1, 5, 2, 6, 3]);
+
+        let (r180, _) = rotate(&img, &geo, Rotation::R180, 4).unwrap();
+        assert_eq!(r180.data, vec![6, 5, 4, 0, 3, 2, 1, 0]);
+
+        let (r270, _) = rotate(&img, &geo, Rotation::R270, 1).unwrap();
+        assert_eq!(r270.data, vec![3, 6, 2, 5, 1, 4]);
+
+        let (back, _) = rotate(&r90, &g90, Rotation::R270, 4).unwrap();
+        assert_eq!(back, img);
+
+        let geo = ImageGeometry {
+            cf: ColorFormat::Y4,
+            ..geo
+        };
+        assert!(rotate(&img, &geo, Rotation::R90, 1).is_err());
+    }
+
+    #[test]
+    fn test_mirror() {
+        let (img, geo) = image();
+        let (m, mg) = mirror(&img, &geo, 4).unwrap();
+        assert_eq!(mg, geo);
+        assert_eq!(m.data, vec![3, 2, 1, 0, 6, 5, 4, 0]);
+        assert_eq!(mirror(&m, &mg, 4).unwrap().0, img);
+    }
+
+    #[test]
+    fn test_crop() {
+        let (img, geo) = image();
+        let (c, cg) = crop(&img, &geo, (1, 0, 2, 2), 1).unwrap();
+        assert_eq!((cg.cx, cg.cy), (2, 2));
+        assert_eq!(c.data, vec![2, 3, 5, 6]);
+        assert!(crop(&img, &geo, (2, 1, 2, 1), 1).is_err());
+
+        // multi byte pixels
+        let rgb = ImageBuffer {
+            data: (0..12).collect(),
+            stride: 6,
+        };
+        let geo = ImageGeometry {
+            cx: 2,
+            cy: 2,
+            cf: ColorFormat::RGB0888,
+        };
+        let (c, _) = crop(&rgb, &geo, (1, 1, 1, 1), 4).unwrap();
+        assert_eq!(
+            c,
+            ImageBuffer {
+                data: vec![9, 10, 11, 0],
+                stride: 4
+            }
+        );
+    }
+}
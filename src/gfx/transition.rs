@@ -0,0 +1,78 @@
+use crate::util::random::XorShift;
+
+// ----------------------------------------------------------------------------
+// How one photo replaces another, each effect has its own transition
+// pipeline in the renderer at the index of the effect
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransitionKind {
+    #[default]
+    Crossfade = 0,
+    WipeLeft = 1,  // the new photo comes in from the right edge
+    WipeRight = 2, // the new photo comes in from the left edge
+    Circle = 3,    // the new photo grows from the center
+    Push = 4,      // the new photo pushes the old one out to the left
+    Zoom = 5,      // the old photo zooms towards the viewer, the new one follows
+}
+
+impl TransitionKind {
+    pub const ALL: [TransitionKind; 6] = [
+        TransitionKind::Crossfade,
+        TransitionKind::WipeLeft,
+        TransitionKind::WipeRight,
+        TransitionKind::Circle,
+        TransitionKind::Push,
+        TransitionKind::Zoom,
+    ];
+
+    // ------------------------------------------------------------------------
+    // Name in config files, e.g. "wipe-left"
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TransitionKind::Crossfade => "crossfade",
+            TransitionKind::WipeLeft => "wipe-left",
+            TransitionKind::WipeRight => "wipe-right",
+            TransitionKind::Circle => "circle",
+            TransitionKind::Push => "push",
+            TransitionKind::Zoom => "zoom",
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn random(rng: &mut XorShift) -> Self {
+        let index = (rng.next_f32() * Self::ALL.len() as f32) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl From<TransitionKind> for usize {
+    fn from(kind: TransitionKind) -> Self {
+        kind as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition_names() {
+        for kind in TransitionKind::ALL {
+            assert_eq!(TransitionKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(TransitionKind::from_name("WipeLeft"), None);
+
+        // the pipelines are created in this order
+        let indices: Vec<usize> = TransitionKind::ALL.into_iter().map(usize::from).collect();
+        assert_eq!(indices, (0..TransitionKind::ALL.len()).collect::<Vec<_>>());
+
+        let mut rng = XorShift::new(3);
+        let picked: Vec<TransitionKind> =
+            (0..100).map(|_| TransitionKind::random(&mut rng)).collect();
+        assert!(TransitionKind::ALL.iter().all(|kind| picked.contains(kind)));
+    }
+}
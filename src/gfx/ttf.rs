@@ -0,0 +1,634 @@
+use crate::error::{Error, Result};
+use crate::v2d::v2::V2;
+
+// Components of composite glyphs may nest, e.g. accents on ligatures
+const MAX_COMPOSITE_DEPTH: usize = 8;
+
+// ----------------------------------------------------------------------------
+// Outline segment in font units, y pointing up
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segment {
+    Line(V2, V2),
+    Quad(V2, V2, V2), // start, control, end
+}
+
+pub type Contour = Vec<Segment>;
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Metrics {
+    pub units_per_em: f32,
+    pub ascender: f32,
+    pub descender: f32,
+    pub line_gap: f32,
+}
+
+// ----------------------------------------------------------------------------
+// Just enough of TrueType to draw glyphs: character map, advances and the
+// quadratic outlines of the `glyf` table. Fonts with CFF outlines (most .otf
+// files) are not supported.
+pub struct TrueType<'a> {
+    metrics: Metrics,
+    num_glyphs: u16,
+    num_h_metrics: u16,
+    long_loca: bool,
+    cmap: &'a [u8], // the selected subtable
+    loca: &'a [u8],
+    glyf: &'a [u8],
+    hmtx: &'a [u8],
+    kern: &'a [u8], // pairs of the horizontal format 0 subtable, sorted
+}
+
+impl<'a> TrueType<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        match read_u32(data, 0)? {
+            0x0001_0000 | 0x7472_7565 => {} // 'true'
+            0x4f54_544f => return Err(font_error("CFF outlines are not supported")),
+            _ => return Err(font_error("not a TrueType font")),
+        }
+        let head = table(data, b"head")?;
+        let hhea = table(data, b"hhea")?;
+        let maxp = table(data, b"maxp")?;
+        let metrics = Metrics {
+            units_per_em: f32::from(read_u16(head, 18)?),
+            ascender: f32::from(read_i16(hhea, 4)?),
+            descender: f32::from(read_i16(hhea, 6)?),
+            line_gap: f32::from(read_i16(hhea, 8)?),
+        };
+        if metrics.units_per_em == 0.0 {
+            return Err(font_error("units per em is 0"));
+        }
+        Ok(Self {
+            metrics,
+            num_glyphs: read_u16(maxp, 4)?,
+            num_h_metrics: read_u16(hhea, 34)?,
+            long_loca: read_i16(head, 50)? != 0,
+            cmap: unicode_cmap(table(data, b"cmap")?)?,
+            loca: table(data, b"loca")?,
+            glyf: table(data, b"glyf")?,
+            hmtx: table(data, b"hmtx")?,
+            kern: table(data, b"kern").map_or(&[], kern_pairs),
+        })
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    // ------------------------------------------------------------------------
+    // Glyph of a character, None for characters the font lacks
+    pub fn glyph_index(&self, c: u32) -> Option<u16> {
+        let id = match read_u16(self.cmap, 0).ok()? {
+            4 => cmap_format4(self.cmap, c),
+            12 => cmap_format12(self.cmap, c),
+            _ => None,
+        }?;
+        (id != 0 && id < self.num_glyphs).then_some(id)
+    }
+
+    // ------------------------------------------------------------------------
+    // In font units
+    pub fn advance(&self, glyph: u16) -> f32 {
+        let i = glyph.min(self.num_h_metrics.saturating_sub(1));
+        read_u16(self.hmtx, 4 * usize::from(i)).map_or(0.0, f32::from)
+    }
+
+    // ------------------------------------------------------------------------
+    // From the legacy `kern` table, in font units. Kerning in the `GPOS`
+    // table of newer fonts is not read.
+    pub fn kerning(&self, left: u16, right: u16) -> f32 {
+        let key = (u32::from(left) << 16) | u32::from(right);
+        let pair = |i: usize| read_u32(self.kern, 6 * i).unwrap_or_default();
+        let (mut lo, mut hi) = (0, self.kern.len() / 6);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match pair(mid).cmp(&key) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => {
+                    return read_i16(self.kern, 6 * mid + 4).map_or(0.0, f32::from);
+                }
+            }
+        }
+        0.0
+    }
+
+    pub fn has_kerning(&self) -> bool {
+        !self.kern.is_empty()
+    }
+
+    // ------------------------------------------------------------------------
+    // Empty for glyphs without outline, e.g. the space
+    pub fn outline(&self, glyph: u16) -> Result<Vec<Contour>> {
+        let mut contours = Vec::new();
+        self.add_outline(glyph, [1.0, 0.0, 0.0, 1.0], V2::default(), 0, &mut contours)?;
+        Ok(contours)
+    }
+
+    fn glyph_data(&self, glyph: u16) -> Result<&'a [u8]> {
+        let i = usize::from(glyph);
+        let (start, end) = match self.long_loca {
+            true => (read_u32(self.loca, 4 * i)?, read_u32(self.loca, 4 * i + 4)?),
+            false => (
+                2 * u32::from(read_u16(self.loca, 2 * i)?),
+                2 * u32::from(read_u16(self.loca, 2 * i + 2)?),
+            ),
+        };
+        let (start, end) = (start as usize, end as usize);
+        match start <= end && end <= self.glyf.len() {
+            true => Ok(&self.glyf[start..end]),
+            false => Err(font_error("glyph offset out of range")),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // `m` is the 2x2 matrix [xx, xy, yx, yy] of composite components
+    fn add_outline(
+        &self,
+        glyph: u16,
+        m: [f32; 4],
+        offset: V2,
+        depth: usize,
+        contours: &mut Vec<Contour>,
+    ) -> Result<()> {
+        let data = self.glyph_data(glyph)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        let transform = |p: V2| {
+            let (x, y) = (p.x0(), p.x1());
+            V2::new([m[0] * x + m[2] * y, m[1] * x + m[3] * y]) + offset
+        };
+        let num_contours = read_i16(data, 0)?;
+        if num_contours >= 0 {
+            for points in simple_glyph(data, num_contours as usize)? {
+                let points: Vec<_> = points
+                    .into_iter()
+                    .map(|(p, on)| (transform(p), on))
+                    .collect();
+                contours.push(contour(&points));
+            }
+            return Ok(());
+        }
+        if depth >= MAX_COMPOSITE_DEPTH {
+            return Err(font_error("composite glyphs nested too deep"));
+        }
+        self.add_composite(data, m, offset, depth, contours)
+    }
+
+    fn add_composite(
+        &self,
+        data: &[u8],
+        m: [f32; 4],
+        offset: V2,
+        depth: usize,
+        contours: &mut Vec<Contour>,
+    ) -> Result<()> {
+        const ARGS_ARE_WORDS: u16 = 0x0001;
+        const ARGS_ARE_XY: u16 = 0x0002;
+        const HAS_SCALE: u16 = 0x0008;
+        const MORE_COMPONENTS: u16 = 0x0020;
+        const HAS_XY_SCALE: u16 = 0x0040;
+        const HAS_2X2: u16 = 0x0080;
+
+        let mut pos = 10;
+        loop {
+            let flags = read_u16(data, pos)?;
+            let component = read_u16(data, pos + 2)?;
+            pos += 4;
+            let (dx, dy) = match flags & ARGS_ARE_WORDS != 0 {
+                true => (read_i16(data, pos)?, read_i16(data, pos + 2)?),
+                false => (
+                    i16::from(read_u8(data, pos)? as i8),
+                    i16::from(read_u8(data, pos + 1)? as i8),
+                ),
+            };
+            pos += if flags & ARGS_ARE_WORDS != 0 { 4 } else { 2 };
+
+            let mut c = [1.0, 0.0, 0.0, 1.0];
+            if flags & HAS_SCALE != 0 {
+                let s = read_f2dot14(data, pos)?;
+                c = [s, 0.0, 0.0, s];
+                pos += 2;
+            } else if flags & HAS_XY_SCALE != 0 {
+                c = [
+                    read_f2dot14(data, pos)?,
+                    0.0,
+                    0.0,
+                    read_f2dot14(data, pos + 2)?,
+                ];
+                pos += 4;
+            } else if flags & HAS_2X2 != 0 {
+                for (i, c) in c.iter_mut().enumerate() {
+                    *c = read_f2dot14(data, pos + 2 * i)?;
+                }
+                pos += 8;
+            }
+
+            // components aligned by matching points are placed without offset
+            let d = match flags & ARGS_ARE_XY != 0 {
+                true => V2::new([f32::from(dx), f32::from(dy)]),
+                false => V2::default(),
+            };
+            let (x, y) = (d.x0(), d.x1());
+            let offset = V2::new([m[0] * x + m[2] * y, m[1] * x + m[3] * y]) + offset;
+            let m = [
+                c[0] * m[0] + c[1] * m[2],
+                c[0] * m[1] + c[1] * m[3],
+                c[2] * m[0] + c[3] * m[2],
+                c[2] * m[1] + c[3] * m[3],
+            ];
+            self.add_outline(component, m, offset, depth + 1, contours)?;
+
+            if flags & MORE_COMPONENTS == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Points of the contours, flagged whether they are on the curve
+fn simple_glyph(data: &[u8], num_contours: usize) -> Result<Vec<Vec<(V2, bool)>>> {
+    const ON_CURVE: u8 = 0x01;
+    const X_SHORT: u8 = 0x02;
+    const Y_SHORT: u8 = 0x04;
+    const REPEAT: u8 = 0x08;
+    const X_SAME_OR_POSITIVE: u8 = 0x10;
+    const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+    let mut ends = Vec::with_capacity(num_contours);
+    for i in 0..num_contours {
+        ends.push(usize::from(read_u16(data, 10 + 2 * i)?));
+    }
+    let num_points = ends.last().map_or(0, |&end| end + 1);
+    let instructions = usize::from(read_u16(data, 10 + 2 * num_contours)?);
+    let mut pos = 12 + 2 * num_contours + instructions;
+
+    let mut flags = Vec::with_capacity(num_points);
+    while flags.len() < num_points {
+        let flag = read_u8(data, pos)?;
+        pos += 1;
+        let mut count = 1;
+        if flag & REPEAT != 0 {
+            count += usize::from(read_u8(data, pos)?);
+            pos += 1;
+        }
+        flags.extend(std::iter::repeat_n(flag, count));
+    }
+    flags.truncate(num_points);
+
+    let mut coords = |short: u8, same_or_positive: u8| -> Result<Vec<f32>> {
+        let mut value = 0i32;
+        let mut values = Vec::with_capacity(num_points);
+        for &flag in &flags {
+            if flag & short != 0 {
+                let delta = i32::from(read_u8(data, pos)?);
+                pos += 1;
+                value += if flag & same_or_positive != 0 {
+                    delta
+                } else {
+                    -delta
+                };
+            } else if flag & same_or_positive == 0 {
+                value += i32::from(read_i16(data, pos)?);
+                pos += 2;
+            }
+            values.push(value as f32);
+        }
+        Ok(values)
+    };
+    let xs = coords(X_SHORT, X_SAME_OR_POSITIVE)?;
+    let ys = coords(Y_SHORT, Y_SAME_OR_POSITIVE)?;
+
+    let mut contours = Vec::with_capacity(num_contours);
+    let mut start = 0;
+    for end in ends {
+        if end < start || end >= num_points {
+            return Err(font_error("invalid contour end"));
+        }
+        let points = (start..=end).map(|i| (V2::new([xs[i], ys[i]]), flags[i] & ON_CURVE != 0));
+        contours.push(points.collect());
+        start = end + 1;
+    }
+    Ok(contours)
+}
+
+// ----------------------------------------------------------------------------
+// Segments of a closed contour, two control points in a row imply an on
+// curve point in their middle
+fn contour(points: &[(V2, bool)]) -> Contour {
+    let n = points.len();
+    let Some(first_on) = points.iter().position(|&(_, on)| on) else {
+        // only control points, start between the first two
+        if n < 2 {
+            return Vec::new();
+        }
+        let mut rotated = vec![(V2::lerp(&points[0].0, &points[1].0, 0.5), true)];
+        rotated.extend_from_slice(&points[1..]);
+        rotated.push(points[0]);
+        return contour(&rotated);
+    };
+
+    let mut segments = Vec::new();
+    let start = points[first_on].0;
+    let mut from = start;
+    let mut control: Option<V2> = None;
+    for i in 1..=n {
+        let (p, on) = points[(first_on + i) % n];
+        match (on, control) {
+            (true, None) => segments.push(Segment::Line(from, p)),
+            (true, Some(c)) => segments.push(Segment::Quad(from, c, p)),
+            (false, None) => {
+                control = Some(p);
+                continue;
+            }
+            (false, Some(c)) => {
+                let mid = V2::lerp(&c, &p, 0.5);
+                segments.push(Segment::Quad(from, c, mid));
+                from = mid;
+                control = Some(p);
+                continue;
+            }
+        }
+        from = p;
+        control = None;
+    }
+    segments.retain(|s| !matches!(s, Segment::Line(a, b) if a == b));
+    segments
+}
+
+// ----------------------------------------------------------------------------
+fn table<'a>(data: &'a [u8], tag: &[u8; 4]) -> Result<&'a [u8]> {
+    let num_tables = usize::from(read_u16(data, 4)?);
+    for i in 0..num_tables {
+        let record = 12 + 16 * i;
+        if data.get(record..record + 4) == Some(tag.as_slice()) {
+            let offset = read_u32(data, record + 8)? as usize;
+            let length = read_u32(data, record + 12)? as usize;
+            return data
+                .get(offset..offset.saturating_add(length))
+                .ok_or_else(|| font_error("table out of range"));
+        }
+    }
+    Err(font_error(&format!(
+        "no {} table",
+        String::from_utf8_lossy(tag)
+    )))
+}
+
+// ----------------------------------------------------------------------------
+// Pairs of the first horizontal kerning subtable in format 0 (Windows
+// version), 6 bytes each: left and right glyph, value
+fn kern_pairs(kern: &[u8]) -> &[u8] {
+    let pairs = || -> Result<&[u8]> {
+        if read_u16(kern, 0)? != 0 {
+            return Ok(&[]);
+        }
+        let mut pos = 4;
+        for _ in 0..read_u16(kern, 2)? {
+            let length = usize::from(read_u16(kern, pos + 2)?);
+            let coverage = read_u16(kern, pos + 4)?;
+            // format 0, horizontal, not cross-stream or minimum values
+            if coverage & 0xff07 == 0x0001 {
+                let num_pairs = usize::from(read_u16(kern, pos + 6)?);
+                let start = pos + 14;
+                return kern
+                    .get(start..start + 6 * num_pairs)
+                    .ok_or_else(|| font_error("kerning out of range"));
+            }
+            pos += length.max(6);
+        }
+        Ok(&[])
+    };
+    pairs().unwrap_or(&[])
+}
+
+// ----------------------------------------------------------------------------
+// Prefers the full Unicode map (format 12) over the BMP one (format 4)
+fn unicode_cmap(cmap: &[u8]) -> Result<&[u8]> {
+    let num_tables = usize::from(read_u16(cmap, 2)?);
+    let mut best: Option<(u8, &[u8])> = None;
+    for i in 0..num_tables {
+        let record = 4 + 8 * i;
+        let platform = read_u16(cmap, record)?;
+        let encoding = read_u16(cmap, record + 2)?;
+        let offset = read_u32(cmap, record + 4)? as usize;
+        let Some(subtable) = cmap.get(offset..) else {
+            continue;
+        };
+        let rank = match (platform, encoding, read_u16(subtable, 0)?) {
+            (0, _, 12) | (3, 10, 12) => 2,
+            (0, _, 4) | (3, 1, 4) => 1,
+            _ => continue,
+        };
+        if best.is_none_or(|(best, _)| rank > best) {
+            best = Some((rank, subtable));
+        }
+    }
+    best.map(|(_, subtable)| subtable)
+        .ok_or_else(|| font_error("no Unicode character map"))
+}
+
+fn cmap_format4(cmap: &[u8], c: u32) -> Option<u16> {
+    let c = u16::try_from(c).ok()?;
+    let seg_count = usize::from(read_u16(cmap, 6).ok()? / 2);
+    let ends = 14;
+    let starts = ends + 2 * seg_count + 2;
+    let deltas = starts + 2 * seg_count;
+    let range_offsets = deltas + 2 * seg_count;
+    for i in 0..seg_count {
+        if read_u16(cmap, ends + 2 * i).ok()? < c {
+            continue;
+        }
+        let start = read_u16(cmap, starts + 2 * i).ok()?;
+        if start > c {
+            return None;
+        }
+        let delta = read_u16(cmap, deltas + 2 * i).ok()?;
+        let range_offset = read_u16(cmap, range_offsets + 2 * i).ok()?;
+        if range_offset == 0 {
+            return Some(c.wrapping_add(delta));
+        }
+        let pos = range_offsets + 2 * i + usize::from(range_offset) + 2 * usize::from(c - start);
+        let id = read_u16(cmap, pos).ok()?;
+        return (id != 0).then(|| id.wrapping_add(delta));
+    }
+    None
+}
+
+fn cmap_format12(cmap: &[u8], c: u32) -> Option<u16> {
+    let num_groups = read_u32(cmap, 12).ok()? as usize;
+    for i in 0..num_groups {
+        let group = 16 + 12 * i;
+        let start = read_u32(cmap, group).ok()?;
+        let end = read_u32(cmap, group + 4).ok()?;
+        if (start..=end).contains(&c) {
+            let id = read_u32(cmap, group + 8).ok()?.checked_add(c - start)?;
+            return u16::try_from(id).ok();
+        }
+    }
+    None
+}
+
+// ----------------------------------------------------------------------------
+fn font_error(msg: &str) -> Error {
+    Error::Font {
+        msg: msg.to_string(),
+    }
+}
+
+fn read_u8(data: &[u8], pos: usize) -> Result<u8> {
+    data.get(pos)
+        .copied()
+        .ok_or_else(|| font_error("truncated"))
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16> {
+    match data.get(pos..pos + 2) {
+        Some(b) => Ok(u16::from_be_bytes([b[0], b[1]])),
+        None => Err(font_error("truncated")),
+    }
+}
+
+fn read_i16(data: &[u8], pos: usize) -> Result<i16> {
+    read_u16(data, pos).map(|v| v as i16)
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32> {
+    match data.get(pos..pos + 4) {
+        Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(font_error("truncated")),
+    }
+}
+
+fn read_f2dot14(data: &[u8], pos: usize) -> Result<f32> {
+    read_i16(data, pos).map(|v| f32::from(v) / 16384.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font_file(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![0, 1, 0, 0];
+        data.extend((tables.len() as u16).to_be_bytes());
+        data.extend([0; 6]);
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, table) in tables {
+            data.extend(*tag);
+            data.extend([0; 4]);
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+        }
+        for (_, table) in tables {
+            data.extend(table);
+        }
+        data
+    }
+
+    fn words(values: &[i32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|&v| (v as u16).to_be_bytes())
+            .collect()
+    }
+
+    // 'A' is a quarter circle-ish shape, 'B' the same shape moved by a
+    // composite glyph
+    fn test_font() -> Vec<u8> {
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        let mut hhea = vec![0; 36];
+        hhea[4..10].copy_from_slice(&words(&[800, -200, 90]));
+        hhea[34..36].copy_from_slice(&2u16.to_be_bytes());
+        let maxp = words(&[0, 0x5000, 3]);
+        let hmtx = words(&[500, 0, 600, 0]);
+        let mut cmap = words(&[0, 1, 3, 1, 0, 12]);
+        cmap.extend(words(&[4, 32, 0, 4, 4, 1, 0]));
+        cmap.extend(words(&[0x42, 0xffff, 0, 0x41, 0xffff, 1 - 0x41, 1, 0, 0]));
+        let mut glyf = words(&[1, 0, 0, 100, 100, 3, 0]);
+        glyf.extend([1, 1, 0, 1]);
+        glyf.extend(words(&[0, 100, 0, -100, 0, 0, 100, 0]));
+        glyf.extend(words(&[-1, 0, 0, 100, 100, 0x0003, 1, 10, 20]));
+        let loca = words(&[0, 0, 17, 26]);
+        let mut kern = words(&[0, 1, 0, 26, 0x0001, 2, 12, 1, 0]);
+        kern.extend(words(&[1, 2, -40, 2, 1, -15]));
+        font_file(&[
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"kern", kern),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ])
+    }
+
+    #[test]
+    fn test_truetype() {
+        let data = test_font();
+        let font = TrueType::parse(&data).unwrap();
+        let metrics = font.metrics();
+        assert_eq!(metrics.units_per_em, 1000.0);
+        assert_eq!(metrics.descender, -200.0);
+        assert_eq!(metrics.line_gap, 90.0);
+
+        assert_eq!(font.glyph_index(u32::from('A')), Some(1));
+        assert_eq!(font.glyph_index(u32::from('B')), Some(2));
+        assert_eq!(font.glyph_index(u32::from('C')), None);
+        assert_eq!(font.advance(0), 500.0);
+        assert_eq!(font.advance(2), 600.0);
+        assert_eq!(font.kerning(1, 2), -40.0);
+        assert_eq!(font.kerning(2, 1), -15.0);
+        assert_eq!(font.kerning(1, 1), 0.0);
+
+        let p = |x: f32, y: f32| V2::new([x, y]);
+        assert!(font.outline(0).unwrap().is_empty());
+        let outline = font.outline(1).unwrap();
+        assert_eq!(
+            outline,
+            [vec![
+                Segment::Line(p(0.0, 0.0), p(100.0, 0.0)),
+                Segment::Quad(p(100.0, 0.0), p(100.0, 100.0), p(0.0, 100.0)),
+                Segment::Line(p(0.0, 100.0), p(0.0, 0.0)),
+            ]]
+        );
+        let moved = font.outline(2).unwrap();
+        assert_eq!(moved[0][0], Segment::Line(p(10.0, 20.0), p(110.0, 20.0)));
+
+        assert!(TrueType::parse(b"OTTO\0\0\0\0\0\0\0\0").is_err());
+    }
+
+    #[test]
+    fn test_cmap_format12() {
+        // groups 'A'..='C' from glyph 10, and 'x'..='z' from u32::MAX - 1
+        let mut cmap = vec![0, 12, 0, 0, 0, 0, 0, 40, 0, 0, 0, 0, 0, 0, 0, 2];
+        for [start, end, glyph] in [[0x41, 0x43, 10], [0x78, 0x7a, u32::MAX - 1]] {
+            cmap.extend([start, end, glyph].map(u32::to_be_bytes).concat());
+        }
+        assert_eq!(cmap_format12(&cmap, 'B' as u32), Some(11));
+        assert_eq!(cmap_format12(&cmap, 'D' as u32), None);
+        assert_eq!(cmap_format12(&cmap, 'x' as u32), None);
+        assert_eq!(cmap_format12(&cmap, 'z' as u32), None);
+    }
+
+    #[test]
+    fn test_implied_points() {
+        let p = |x: f32, y: f32| V2::new([x, y]);
+        let segments = contour(&[
+            (p(0.0, 0.0), false),
+            (p(10.0, 0.0), false),
+            (p(10.0, 10.0), false),
+            (p(0.0, 10.0), false),
+        ]);
+        assert_eq!(segments.len(), 4);
+        assert_eq!(
+            segments[0],
+            Segment::Quad(p(5.0, 0.0), p(10.0, 0.0), p(10.0, 5.0))
+        );
+        assert!(matches!(segments[3], Segment::Quad(_, _, end) if end == p(5.0, 0.0)));
+    }
+}
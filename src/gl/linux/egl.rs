@@ -0,0 +1,237 @@
+use super::super::opengl::*;
+use crate::error::{Error, Result};
+use std::ffi::{CStr, c_char, c_int, c_void};
+
+// libEGL is only loaded for headless rendering, so the frame itself still
+// runs on systems without it
+unsafe extern "C" {
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlclose(handle: *mut c_void) -> c_int;
+}
+const RTLD_NOW: c_int = 2;
+
+type EGLDisplay = *mut c_void;
+type EGLConfig = *mut c_void;
+type EGLSurface = *mut c_void;
+type EGLContext = *mut c_void;
+type EGLint = i32;
+type EGLenum = u32;
+type EGLBoolean = u32;
+
+const EGL_NONE: EGLint = 0x3038;
+const EGL_ALPHA_SIZE: EGLint = 0x3021;
+const EGL_BLUE_SIZE: EGLint = 0x3022;
+const EGL_GREEN_SIZE: EGLint = 0x3023;
+const EGL_RED_SIZE: EGLint = 0x3024;
+const EGL_DEPTH_SIZE: EGLint = 0x3025;
+const EGL_SURFACE_TYPE: EGLint = 0x3033;
+const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+const EGL_HEIGHT: EGLint = 0x3056;
+const EGL_WIDTH: EGLint = 0x3057;
+const EGL_PBUFFER_BIT: EGLint = 0x0001;
+const EGL_OPENGL_BIT: EGLint = 0x0008;
+const EGL_OPENGL_API: EGLenum = 0x30a2;
+const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31dd;
+
+type FnGetProcAddress = unsafe extern "C" fn(*const c_char) -> *const c_void;
+type FnGetPlatformDisplay = unsafe extern "C" fn(EGLenum, *mut c_void, *const isize) -> EGLDisplay;
+type FnGetDisplay = unsafe extern "C" fn(*mut c_void) -> EGLDisplay;
+type FnInitialize = unsafe extern "C" fn(EGLDisplay, *mut EGLint, *mut EGLint) -> EGLBoolean;
+type FnBindApi = unsafe extern "C" fn(EGLenum) -> EGLBoolean;
+type FnChooseConfig = unsafe extern "C" fn(
+    EGLDisplay,
+    *const EGLint,
+    *mut EGLConfig,
+    EGLint,
+    *mut EGLint,
+) -> EGLBoolean;
+type FnCreatePbufferSurface =
+    unsafe extern "C" fn(EGLDisplay, EGLConfig, *const EGLint) -> EGLSurface;
+type FnCreateContext =
+    unsafe extern "C" fn(EGLDisplay, EGLConfig, EGLContext, *const EGLint) -> EGLContext;
+type FnMakeCurrent =
+    unsafe extern "C" fn(EGLDisplay, EGLSurface, EGLSurface, EGLContext) -> EGLBoolean;
+type FnDestroySurface = unsafe extern "C" fn(EGLDisplay, EGLSurface) -> EGLBoolean;
+type FnDestroyContext = unsafe extern "C" fn(EGLDisplay, EGLContext) -> EGLBoolean;
+type FnTerminate = unsafe extern "C" fn(EGLDisplay) -> EGLBoolean;
+type FnGetError = unsafe extern "C" fn() -> EGLint;
+
+// ----------------------------------------------------------------------------
+// Off-screen OpenGL context with a pbuffer surface, e.g. for rendering in CI.
+// Mesa's surfaceless platform needs neither X11 nor a GPU, it falls back to
+// the llvmpipe software rasterizer (forced with LIBGL_ALWAYS_SOFTWARE=1).
+pub struct EglContext {
+    lib: *mut c_void,
+    display: EGLDisplay,
+    surface: EGLSurface,
+    context: EGLContext,
+    get_proc_address: FnGetProcAddress,
+    make_current: FnMakeCurrent,
+    destroy_surface: FnDestroySurface,
+    destroy_context: FnDestroyContext,
+    terminate: FnTerminate,
+}
+
+impl EglContext {
+    // ------------------------------------------------------------------------
+    pub fn headless(cx: usize, cy: usize) -> Result<Self> {
+        let lib = unsafe { dlopen(c"libEGL.so.1".as_ptr(), RTLD_NOW) };
+        if lib.is_null() {
+            return Err(Error::OpenGlLoad {
+                name: String::from("libEGL.so.1"),
+            });
+        }
+        // the library is closed again by Drop
+        let mut context = Self {
+            lib,
+            display: std::ptr::null_mut(),
+            surface: std::ptr::null_mut(),
+            context: std::ptr::null_mut(),
+            get_proc_address: unsafe { symbol(lib, c"eglGetProcAddress")? },
+            make_current: unsafe { symbol(lib, c"eglMakeCurrent")? },
+            destroy_surface: unsafe { symbol(lib, c"eglDestroySurface")? },
+            destroy_context: unsafe { symbol(lib, c"eglDestroyContext")? },
+            terminate: unsafe { symbol(lib, c"eglTerminate")? },
+        };
+        unsafe { context.init(cx, cy)? };
+        Ok(context)
+    }
+
+    // ------------------------------------------------------------------------
+    unsafe fn init(&mut self, cx: usize, cy: usize) -> Result<()> {
+        unsafe {
+            let lib = self.lib;
+            let get_error: FnGetError = symbol(lib, c"eglGetError")?;
+            let failed = |call: &str| Error::Egl {
+                msg: format!("{call} failed with 0x{:x}", get_error()),
+            };
+
+            // the surfaceless platform works without a display server
+            let get_platform_display: Option<FnGetPlatformDisplay> =
+                symbol(lib, c"eglGetPlatformDisplay").ok();
+            self.display = match get_platform_display {
+                Some(get_platform_display) => get_platform_display(
+                    EGL_PLATFORM_SURFACELESS_MESA,
+                    std::ptr::null_mut(),
+                    std::ptr::null(),
+                ),
+                None => std::ptr::null_mut(),
+            };
+            if self.display.is_null() {
+                let get_display: FnGetDisplay = symbol(lib, c"eglGetDisplay")?;
+                self.display = get_display(std::ptr::null_mut());
+            }
+            if self.display.is_null() {
+                return Err(failed("eglGetDisplay"));
+            }
+
+            let initialize: FnInitialize = symbol(lib, c"eglInitialize")?;
+            let (mut major, mut minor) = (0, 0);
+            if initialize(self.display, &mut major, &mut minor) == 0 {
+                return Err(failed("eglInitialize"));
+            }
+            log::info!("EGL {major}.{minor} initialized");
+
+            let bind_api: FnBindApi = symbol(lib, c"eglBindAPI")?;
+            if bind_api(EGL_OPENGL_API) == 0 {
+                return Err(failed("eglBindAPI"));
+            }
+
+            let config_attribs = [
+                EGL_SURFACE_TYPE,
+                EGL_PBUFFER_BIT,
+                EGL_RENDERABLE_TYPE,
+                EGL_OPENGL_BIT,
+                EGL_RED_SIZE,
+                8,
+                EGL_GREEN_SIZE,
+                8,
+                EGL_BLUE_SIZE,
+                8,
+                EGL_ALPHA_SIZE,
+                8,
+                EGL_DEPTH_SIZE,
+                24,
+                EGL_NONE,
+            ];
+            let choose_config: FnChooseConfig = symbol(lib, c"eglChooseConfig")?;
+            let mut config = std::ptr::null_mut();
+            let mut count = 0;
+            let ok = choose_config(
+                self.display,
+                config_attribs.as_ptr(),
+                &mut config,
+                1,
+                &mut count,
+            );
+            if ok == 0 || count < 1 {
+                return Err(failed("eglChooseConfig"));
+            }
+
+            let surface_attribs = [EGL_WIDTH, cx as EGLint, EGL_HEIGHT, cy as EGLint, EGL_NONE];
+            let create_surface: FnCreatePbufferSurface = symbol(lib, c"eglCreatePbufferSurface")?;
+            self.surface = create_surface(self.display, config, surface_attribs.as_ptr());
+            if self.surface.is_null() {
+                return Err(failed("eglCreatePbufferSurface"));
+            }
+
+            let create_context: FnCreateContext = symbol(lib, c"eglCreateContext")?;
+            let context_attribs = [EGL_NONE];
+            self.context = create_context(
+                self.display,
+                config,
+                std::ptr::null_mut(),
+                context_attribs.as_ptr(),
+            );
+            if self.context.is_null() {
+                return Err(failed("eglCreateContext"));
+            }
+
+            if (self.make_current)(self.display, self.surface, self.surface, self.context) == 0 {
+                return Err(failed("eglMakeCurrent"));
+            }
+            Ok(())
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn load(&self) -> Result<OpenGlFunctions> {
+        OpenGlFunctions::load(|fn_name| {
+            let fn_ptr = unsafe { (self.get_proc_address)(fn_name.as_ptr() as *const _) };
+            (!fn_ptr.is_null()).then_some(fn_ptr as FnOpenGl)
+        })
+    }
+}
+
+impl Drop for EglContext {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.display.is_null() {
+                let none = std::ptr::null_mut();
+                (self.make_current)(self.display, none, none, none);
+                if !self.context.is_null() {
+                    (self.destroy_context)(self.display, self.context);
+                }
+                if !self.surface.is_null() {
+                    (self.destroy_surface)(self.display, self.surface);
+                }
+                (self.terminate)(self.display);
+            }
+            dlclose(self.lib);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+/// # Safety
+/// `T` must be the function pointer type of the symbol
+unsafe fn symbol<T: Copy>(lib: *mut c_void, name: &CStr) -> Result<T> {
+    let ptr = unsafe { dlsym(lib, name.as_ptr()) };
+    if ptr.is_null() {
+        return Err(Error::OpenGlLoad {
+            name: name.to_string_lossy().into_owned(),
+        });
+    }
+    Ok(unsafe { std::mem::transmute_copy::<*mut c_void, T>(&ptr) })
+}
@@ -1,3 +1,5 @@
+pub mod egl;
+
 use super::opengl::*;
 use crate::error::Result;
 use x11::xlib::*;
@@ -9,7 +11,10 @@ pub struct LinuxGLContext {
 }
 
 impl LinuxGLContext {
-    pub fn from_window(
+    /// # Safety
+    /// `display` must be an open X display and `window` a window on `screen`
+    /// of it, both outliving the context.
+    pub unsafe fn from_window(
         display: *mut Display,
         screen: std::os::raw::c_int,
         window: Window,
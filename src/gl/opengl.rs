@@ -60,6 +60,7 @@ pub const DEPTH_COMPONENT: GLenum = 0x1902;
 pub const RED: GLenum = 0x1903;
 pub const RGB: GLenum = 0x1907;
 pub const RGBA: GLenum = 0x1908;
+pub const RG: GLenum = 0x8227;
 
 pub const RGB5: GLint = 0x8050;
 pub const RGB8: GLint = 0x8051;
@@ -76,6 +77,7 @@ pub const RGBA16: GLint = 0x805B;
 
 pub const R8: GLint = 0x8229;
 pub const R16: GLint = 0x822A;
+pub const RG8: GLint = 0x822B;
 pub const R16F: GLint = 0x822D;
 pub const R32F: GLint = 0x822E;
 pub const RG16F: GLenum = 0x822F;
@@ -97,6 +99,20 @@ pub const BLEND: GLenum = 0x0BE2;
 pub const CULL_FACE: GLenum = 0x0B44;
 pub const DEPTH_TEST: GLenum = 0x0B71;
 pub const DEPTH_FUNC: GLenum = 0x0B74;
+
+pub const NEVER: GLenum = 0x0200;
+pub const LESS: GLenum = 0x0201;
+pub const EQUAL: GLenum = 0x0202;
+pub const LEQUAL: GLenum = 0x0203;
+pub const GREATER: GLenum = 0x0204;
+pub const NOTEQUAL: GLenum = 0x0205;
+pub const GEQUAL: GLenum = 0x0206;
+pub const ALWAYS: GLenum = 0x0207;
+
+pub const LOWER_LEFT: GLenum = 0x8CA1;
+pub const UPPER_LEFT: GLenum = 0x8CA2;
+pub const NEGATIVE_ONE_TO_ONE: GLenum = 0x935E;
+pub const ZERO_TO_ONE: GLenum = 0x935F;
 pub const LINE_SMOOTH: GLenum = 0x0B20;
 pub const PROGRAM_POINT_SIZE: GLenum = 0x8642;
 
@@ -190,18 +206,24 @@ pub type FnGetStringi = unsafe extern "system" fn(GLenum, GLint) -> *const GLuby
 pub type FnViewport = unsafe fn(GLint, GLint, GLsizei, GLsizei);
 pub type FnClearColor = unsafe fn(GLfloat, GLfloat, GLfloat, GLfloat);
 pub type FnClear = unsafe fn(GLbitfield);
+pub type FnFinish = unsafe fn();
 pub type FnEnable = unsafe fn(GLenum);
 pub type FnDisable = unsafe fn(GLenum);
 pub type FnAlphaFunc = unsafe fn(GLenum, GLclampf);
 pub type FnBlendFunc = unsafe fn(GLenum, GLenum);
 pub type FnPointSize = unsafe fn(GLfloat);
 pub type FnLineWidth = unsafe fn(GLfloat);
+pub type FnDepthFunc = unsafe extern "system" fn(GLenum);
+pub type FnClearDepthf = unsafe extern "system" fn(GLfloat);
+pub type FnClipControl = unsafe extern "system" fn(GLenum, GLenum);
 
 pub type FnGenTextures = unsafe fn(GLsizei, *mut GLuint);
 pub type FnBindTexture = unsafe fn(GLenum, GLuint);
 pub type FnDeleteTextures = unsafe fn(GLsizei, *const GLuint);
 pub type FnTexImage1D = unsafe fn(GLenum, GLint, GLint, GLsizei, GLint, GLenum, GLenum, *const GLvoid);
 pub type FnTexImage2D = unsafe fn(GLenum, GLint, GLint, GLsizei, GLsizei, GLint, GLenum, GLenum, *const GLvoid);
+pub type FnTexSubImage2D = unsafe fn(GLenum, GLint, GLint, GLint, GLsizei, GLsizei, GLenum, GLenum, *const GLvoid);
+pub type FnReadPixels = unsafe fn(GLint, GLint, GLsizei, GLsizei, GLenum, GLenum, *mut GLvoid);
 pub type FnTexParameterf = unsafe fn(GLenum, GLenum, GLfloat);
 pub type FnTexParameterfv = unsafe fn(GLenum, GLenum, *const GLfloat);
 pub type FnTexParameteri = unsafe fn(GLenum, GLenum, GLint);
@@ -287,18 +309,25 @@ pub struct OpenGlFunctions {
     fnViewport: FnViewport,
     fnClearColor: FnClearColor,
     fnClear: FnClear,
+    fnFinish: FnFinish,
     fnEnable: FnEnable,
     fnDisable: FnDisable,
     fnAlphaFunc: FnAlphaFunc,
     fnBlendFunc: FnBlendFunc,
     fnPointSize: FnPointSize,
     fnLineWidth: FnLineWidth,
+    fnDepthFunc: FnDepthFunc,
+    fnClearDepthf: Option<FnClearDepthf>,
+    // GL 4.5 / GL_EXT_clip_control, not available everywhere
+    fnClipControl: Option<FnClipControl>,
 
     fnGenTextures: FnGenTextures,
     fnBindTexture: FnBindTexture,
     fnDeleteTextures: FnDeleteTextures,
     fnTexImage1D: FnTexImage1D,
     fnTexImage2D: FnTexImage2D,
+    fnTexSubImage2D: FnTexSubImage2D,
+    fnReadPixels: FnReadPixels,
     fnTexParameterf: FnTexParameterf,
     fnTexParameterfv: FnTexParameterfv,
     fnTexParameteri: FnTexParameteri,
@@ -420,18 +449,26 @@ impl OpenGlFunctions {
             fnViewport: load_gl_fn!(load_fn, "glViewport\0" => FnViewport)?,
             fnClearColor: load_gl_fn!(load_fn, "glClearColor\0" => FnClearColor)?,
             fnClear: load_gl_fn!(load_fn, "glClear\0" => FnClear)?,
+            fnFinish: load_gl_fn!(load_fn, "glFinish\0" => FnFinish)?,
             fnEnable: load_gl_fn!(load_fn, "glEnable\0" => FnEnable)?,
             fnDisable: load_gl_fn!(load_fn, "glDisable\0" => FnDisable)?,
             fnAlphaFunc: load_gl_fn!(load_fn, "glAlphaFunc\0" => FnAlphaFunc)?,
             fnBlendFunc: load_gl_fn!(load_fn, "glBlendFunc\0" => FnBlendFunc)?,
             fnPointSize: load_gl_fn!(load_fn, "glPointSize\0" => FnPointSize)?,
             fnLineWidth: load_gl_fn!(load_fn, "glLineWidth\0" => FnLineWidth)?,
+            fnDepthFunc: load_gl_fn!(load_fn, "glDepthFunc\0" => FnDepthFunc)?,
+            fnClearDepthf: load_gl_fn!(load_fn, "glClearDepthf\0" => FnClearDepthf).ok(),
+            fnClipControl: load_gl_fn!(load_fn, "glClipControl\0" => FnClipControl)
+                .or_else(|_| load_gl_fn!(load_fn, "glClipControlEXT\0" => FnClipControl))
+                .ok(),
             
             fnGenTextures: load_gl_fn!(load_fn, "glGenTextures\0" => FnGenTextures)?,
             fnBindTexture: load_gl_fn!(load_fn, "glBindTexture\0" => FnBindTexture)?,
             fnDeleteTextures: load_gl_fn!(load_fn, "glDeleteTextures\0" => FnDeleteTextures)?,
             fnTexImage1D: load_gl_fn!(load_fn, "glTexImage1D\0" => FnTexImage1D)?,
             fnTexImage2D: load_gl_fn!(load_fn, "glTexImage2D\0" => FnTexImage2D)?,
+            fnTexSubImage2D: load_gl_fn!(load_fn, "glTexSubImage2D\0" => FnTexSubImage2D)?,
+            fnReadPixels: load_gl_fn!(load_fn, "glReadPixels\0" => FnReadPixels)?,
             fnTexParameterf: load_gl_fn!(load_fn, "glTexParameterf\0" => FnTexParameterf)?,
             fnTexParameterfv: load_gl_fn!(load_fn, "glTexParameterfv\0" => FnTexParameterfv)?,
             fnTexParameteri: load_gl_fn!(load_fn, "glTexParameteri\0" => FnTexParameteri)?,
@@ -518,18 +555,46 @@ impl OpenGlFunctions {
     impl_gl_fn!(fnViewport, Viewport(x: GLint, y: GLint, width: GLsizei, height: GLsizei));
     impl_gl_fn!(fnClearColor, ClearColor(red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat));
     impl_gl_fn!(fnClear, Clear(mask: GLbitfield));
+    impl_gl_fn!(fnFinish, Finish());
     impl_gl_fn!(fnEnable, Enable(cap: GLenum));
     impl_gl_fn!(fnDisable, Disable(cap: GLenum));
     impl_gl_fn!(fnAlphaFunc, AlphaFunc(func: GLenum, ref_value: GLclampf));
     impl_gl_fn!(fnBlendFunc, BlendFunc(src: GLenum, dst: GLenum));
     impl_gl_fn!(fnPointSize, PointSize(size: GLfloat));
     impl_gl_fn!(fnLineWidth, LineWidth(width: GLfloat));
+    impl_gl_fn!(fnDepthFunc, DepthFunc(func: GLenum));
+
+    // Returns false without glClearDepthf, e.g. GL < 4.1 lacking ES2 compatibility.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn ClearDepthf(&self, depth: GLfloat) -> bool {
+        match self.fnClearDepthf {
+            Some(f) => {
+                unsafe { f(depth) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Returns false if clip control is not supported by the driver.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn ClipControl(&self, origin: GLenum, depth: GLenum) -> bool {
+        match self.fnClipControl {
+            Some(f) => {
+                unsafe { f(origin, depth) };
+                true
+            }
+            None => false,
+        }
+    }
 
     impl_gl_fn!(fnGenTextures, GenTextures(n: GLsizei, textures: *mut GLuint));
     impl_gl_fn!(fnBindTexture, BindTexture(target: GLenum, texture: GLuint));
     impl_gl_fn!(fnDeleteTextures, DeleteTextures(n: GLsizei, textures: *const GLuint));
     impl_gl_fn!(fnTexImage1D, TexImage1D(target: GLenum, level: GLint, internal: GLint, width: GLsizei, border: GLint, format: GLenum, r#type: GLenum, pixels: *const GLvoid));
     impl_gl_fn!(fnTexImage2D, TexImage2D(target: GLenum, level: GLint, internal: GLint, width: GLsizei, height: GLsizei, border: GLint, format: GLenum, r#type: GLenum, pixels: *const GLvoid));
+    impl_gl_fn!(fnTexSubImage2D, TexSubImage2D(target: GLenum, level: GLint, x: GLint, y: GLint, width: GLsizei, height: GLsizei, format: GLenum, r#type: GLenum, pixels: *const GLvoid));
+    impl_gl_fn!(fnReadPixels, ReadPixels(x: GLint, y: GLint, width: GLsizei, height: GLsizei, format: GLenum, r#type: GLenum, pixels: *mut GLvoid));
     impl_gl_fn!(fnTexParameterf, TexParameterf(target: GLenum, pname: GLenum, param: GLfloat));
     impl_gl_fn!(fnTexParameterfv, TexParameterfv(target: GLenum, pname: GLenum, params: *const GLfloat));
     impl_gl_fn!(fnTexParameteri, TexParameteri(target: GLenum, pname: GLenum, param: GLint));
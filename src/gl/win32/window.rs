@@ -45,6 +45,8 @@ impl<T: IWindow> WindowProc<T> {
         title: &str,
         class_name: &str,
         style: WINDOW_STYLE,
+        pos: POINT,
+        size: SIZE,
         params: T::Params,
     ) -> Result<HWND> {
         let title = title.encode_utf16().chain(Some(0)).collect::<Vec<_>>();
@@ -73,10 +75,10 @@ impl<T: IWindow> WindowProc<T> {
                 PCWSTR(class_name.as_ptr()),
                 PCWSTR(title.as_ptr()),
                 style,
-                CW_USEDEFAULT,
-                CW_USEDEFAULT,
-                800,
-                600,
+                pos.x,
+                pos.y,
+                size.cx,
+                size.cy,
                 None,
                 None,
                 Some(h_instance.into()),
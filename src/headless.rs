@@ -0,0 +1,133 @@
+use crate::app::{App, AppConfig};
+use crate::core::IApp;
+use crate::core::input::Input;
+use crate::error::{Error, Result, ResultExt};
+use crate::gfx::png_write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// ----------------------------------------------------------------------------
+// Renders a fixed number of frames off-screen and writes them as PNG files,
+// e.g. to compare layouts and shaders against golden images
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeadlessConfig {
+    pub width: usize,
+    pub height: usize,
+    pub frames: usize,
+    pub frame_time: Duration, // simulated time between two frames
+    pub output: PathBuf,      // directory of frame-0000.png, ...
+}
+
+impl HeadlessConfig {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            frames: 1,
+            frame_time: Duration::from_millis(40),
+            output: PathBuf::from("headless"),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Parses a frame size like "1280x720"
+pub fn parse_size(size: &str) -> Option<(usize, usize)> {
+    let (cx, cy) = size.split_once(['x', 'X'])?;
+    let cx = cx.parse().ok().filter(|&cx| cx > 0)?;
+    let cy = cy.parse().ok().filter(|&cy| cy > 0)?;
+    Some((cx, cy))
+}
+
+// ----------------------------------------------------------------------------
+// Creates an EGL pbuffer context instead of a window and renders the frames
+#[cfg(target_os = "linux")]
+pub fn run(mut config: AppConfig) -> Result<Vec<PathBuf>> {
+    use crate::gl::linux::egl::EglContext;
+
+    let headless = config.headless.take().ok_or(Error::InvalidArgument {
+        arg: String::from("--headless"),
+    })?;
+    // a headless run must not take over the services of a running frame nor
+    // play music or switch the display
+    config.listen = None;
+    config.dbus = None;
+    config.control_socket = None;
+    config.music.path = None;
+    config.presence.phones.clear();
+    config.presence.bluetooth.clear();
+    // photos decoded in the background would show up in different frames
+    config.background_decoding = false;
+
+    let context = EglContext::headless(headless.width, headless.height)?;
+    let gl = context.load()?;
+    let cx = headless.width as i32;
+    let cy = headless.height as i32;
+    let mut input = crate::app::create_input(&config)?;
+    let mut app = App::new(config, gl, cx, cy)?;
+    app.resize(cx, cy);
+    render_frames(&mut app, &mut input, &headless)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn run(_config: AppConfig) -> Result<Vec<PathBuf>> {
+    Err(Error::InvalidArgument {
+        arg: String::from("--headless is only supported on Linux"),
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Steps the app with a fixed frame time so that runs are reproducible and
+// returns the written files
+pub fn render_frames(
+    app: &mut App,
+    input: &mut Input,
+    headless: &HeadlessConfig,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(&headless.output).with_path("creating", &headless.output)?;
+    let mut t = Instant::now();
+    let mut files = Vec::with_capacity(headless.frames);
+    for frame in 0..headless.frames {
+        if frame > 0 {
+            t += headless.frame_time;
+            app.update(t, headless.frame_time, input)?;
+        } else {
+            app.update(t, Duration::ZERO, input)?;
+        }
+        if app.exit_requested() {
+            break;
+        }
+        app.render(&t, 1.0)?;
+
+        let rgb = app.capture(headless.width, headless.height);
+        let path = frame_path(&headless.output, frame);
+        let png = png_write::encode_rgb24(headless.width, headless.height, &rgb);
+        std::fs::write(&path, png).with_path("writing", &path)?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+// ----------------------------------------------------------------------------
+fn frame_path(dir: &Path, frame: usize) -> PathBuf {
+    dir.join(format!("frame-{frame:04}.png"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1280x720"), Some((1280, 720)));
+        assert_eq!(parse_size("640X480"), Some((640, 480)));
+        assert_eq!(parse_size("0x480"), None);
+        assert_eq!(parse_size("640"), None);
+        assert_eq!(parse_size("640x-1"), None);
+        assert_eq!(parse_size("wide x tall"), None);
+        assert_eq!(
+            frame_path(Path::new("out"), 7),
+            Path::new("out/frame-0007.png")
+        );
+    }
+}
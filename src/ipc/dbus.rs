@@ -0,0 +1,609 @@
+use crate::error::{Error, Result};
+use crate::ipc::{Bus, Command, EventSink};
+use crate::scene::{FrameEvent, SceneKind};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixStream;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::Sender;
+
+// ----------------------------------------------------------------------------
+// D-Bus service `org.home.Frame` on the session or system bus, speaking the
+// wire protocol directly over the bus socket. Only the few types used by the
+// interface below are supported.
+pub const SERVICE: &str = "org.home.Frame";
+const PATH: &str = "/org/home/Frame";
+const INTERFACE: &str = "org.home.Frame";
+
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.home.Frame">
+    <method name="Next"/>
+    <method name="Previous"/>
+    <method name="Pause"><arg name="paused" type="b" direction="in"/></method>
+    <method name="SetScene"><arg name="scene" type="s" direction="in"/></method>
+    <method name="SetVolume"><arg name="percent" type="u" direction="in"/></method>
+    <method name="Mute"><arg name="muted" type="b" direction="in"/></method>
+    <signal name="PhotoChanged"><arg name="path" type="s"/><arg name="title" type="s"/></signal>
+    <signal name="SceneChanged"><arg name="name" type="s"/></signal>
+    <signal name="Error"><arg name="message" type="s"/></signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect"><arg name="xml" type="s" direction="out"/></method>
+  </interface>
+  <interface name="org.freedesktop.DBus.Peer">
+    <method name="Ping"/>
+  </interface>
+</node>
+"#;
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MessageType {
+    MethodCall = 1,
+    MethodReturn = 2,
+    Error = 3,
+    Signal = 4,
+}
+
+const NO_REPLY_EXPECTED: u8 = 0x1;
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+enum Arg {
+    Str(String),
+    U32(u32),
+    Bool(bool),
+}
+
+impl Arg {
+    fn signature(&self) -> char {
+        match self {
+            Arg::Str(_) => 's',
+            Arg::U32(_) => 'u',
+            Arg::Bool(_) => 'b',
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Message {
+    msg_type: Option<MessageType>,
+    flags: u8,
+    serial: u32,
+    path: Option<String>,
+    interface: Option<String>,
+    member: Option<String>,
+    error_name: Option<String>,
+    reply_serial: Option<u32>,
+    destination: Option<String>,
+    sender: Option<String>,
+    args: Vec<Arg>,
+}
+
+// Header field codes
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SENDER: u8 = 7;
+const FIELD_SIGNATURE: u8 = 8;
+
+// ----------------------------------------------------------------------------
+// Little endian marshalling, alignment is relative to the message start
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn align(&mut self, n: usize) {
+        self.buf.resize(self.buf.len().next_multiple_of(n), 0);
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.buf.extend_from_slice(s.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn sig(&mut self, s: &str) {
+        self.u8(s.len() as u8);
+        self.buf.extend_from_slice(s.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn arg(&mut self, arg: &Arg) {
+        match arg {
+            Arg::Str(s) => self.str(s),
+            Arg::U32(v) => self.u32(*v),
+            Arg::Bool(b) => self.u32(*b as u32),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn align(&mut self, n: usize) {
+        self.pos = self.pos.next_multiple_of(n);
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&[u8]> {
+        let b = self
+            .buf
+            .get(self.pos..self.pos + n)
+            .ok_or(Error::InvalidRequest)?;
+        self.pos += n;
+        Ok(b)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.align(4);
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn str(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let s = self.bytes(len + 1)?;
+        String::from_utf8(s[..len].to_vec()).map_err(|_| Error::InvalidRequest)
+    }
+
+    fn sig(&mut self) -> Result<String> {
+        let len = self.u8()? as usize;
+        let s = self.bytes(len + 1)?;
+        String::from_utf8(s[..len].to_vec()).map_err(|_| Error::InvalidRequest)
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Message {
+    // ------------------------------------------------------------------------
+    fn method_call(destination: &str, path: &str, interface: &str, member: &str) -> Self {
+        Self {
+            msg_type: Some(MessageType::MethodCall),
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            destination: Some(destination.to_string()),
+            ..Default::default()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn signal(member: &str, args: Vec<Arg>) -> Self {
+        Self {
+            msg_type: Some(MessageType::Signal),
+            path: Some(PATH.to_string()),
+            interface: Some(INTERFACE.to_string()),
+            member: Some(member.to_string()),
+            args,
+            ..Default::default()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn reply(&self, args: Vec<Arg>) -> Self {
+        Self {
+            msg_type: Some(MessageType::MethodReturn),
+            flags: NO_REPLY_EXPECTED,
+            reply_serial: Some(self.serial),
+            destination: self.sender.clone(),
+            args,
+            ..Default::default()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn error(&self, name: &str, text: &str) -> Self {
+        Self {
+            msg_type: Some(MessageType::Error),
+            error_name: Some(name.to_string()),
+            ..self.reply(vec![Arg::Str(text.to_string())])
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn encode(&self, serial: u32) -> Vec<u8> {
+        let mut body = Writer::default();
+        for arg in &self.args {
+            body.arg(arg);
+        }
+        let signature: String = self.args.iter().map(Arg::signature).collect();
+
+        let mut w = Writer::default();
+        w.u8(b'l');
+        w.u8(self.msg_type.map_or(0, |t| t as u8));
+        w.u8(self.flags);
+        w.u8(1);
+        w.u32(body.buf.len() as u32);
+        w.u32(serial);
+
+        // array of (code, variant), the length is patched in afterwards
+        w.u32(0);
+        let start = w.buf.len();
+        let field = |w: &mut Writer, code: u8, sig: &str| {
+            w.align(8);
+            w.u8(code);
+            w.sig(sig);
+        };
+        let strings = [
+            (FIELD_PATH, "o", &self.path),
+            (FIELD_INTERFACE, "s", &self.interface),
+            (FIELD_MEMBER, "s", &self.member),
+            (FIELD_ERROR_NAME, "s", &self.error_name),
+            (FIELD_DESTINATION, "s", &self.destination),
+            (FIELD_SENDER, "s", &self.sender),
+        ];
+        for (code, sig, value) in strings {
+            if let Some(value) = value {
+                field(&mut w, code, sig);
+                w.str(value);
+            }
+        }
+        if let Some(serial) = self.reply_serial {
+            field(&mut w, FIELD_REPLY_SERIAL, "u");
+            w.u32(serial);
+        }
+        if !signature.is_empty() {
+            field(&mut w, FIELD_SIGNATURE, "g");
+            w.sig(&signature);
+        }
+        let len = (w.buf.len() - start) as u32;
+        w.buf[start - 4..start].copy_from_slice(&len.to_le_bytes());
+
+        w.align(8);
+        w.buf.extend_from_slice(&body.buf);
+        w.buf
+    }
+
+    // ------------------------------------------------------------------------
+    fn decode(buf: &[u8]) -> Result<Self> {
+        let mut r = Reader { buf, pos: 0 };
+        if r.u8()? != b'l' {
+            // big endian peers are not supported
+            return Err(Error::InvalidRequest);
+        }
+        let msg_type = match r.u8()? {
+            1 => Some(MessageType::MethodCall),
+            2 => Some(MessageType::MethodReturn),
+            3 => Some(MessageType::Error),
+            4 => Some(MessageType::Signal),
+            _ => None,
+        };
+        let flags = r.u8()?;
+        r.u8()?;
+        let body_len = r.u32()? as usize;
+        let serial = r.u32()?;
+
+        let mut msg = Message {
+            msg_type,
+            flags,
+            serial,
+            ..Default::default()
+        };
+        let mut signature = String::new();
+        let fields_end = r.u32()? as usize + r.pos;
+        while r.pos < fields_end {
+            r.align(8);
+            let code = r.u8()?;
+            let sig = r.sig()?;
+            match (code, sig.as_str()) {
+                (FIELD_PATH, "o") => msg.path = Some(r.str()?),
+                (FIELD_INTERFACE, "s") => msg.interface = Some(r.str()?),
+                (FIELD_MEMBER, "s") => msg.member = Some(r.str()?),
+                (FIELD_ERROR_NAME, "s") => msg.error_name = Some(r.str()?),
+                (FIELD_REPLY_SERIAL, "u") => msg.reply_serial = Some(r.u32()?),
+                (FIELD_DESTINATION, "s") => msg.destination = Some(r.str()?),
+                (FIELD_SENDER, "s") => msg.sender = Some(r.str()?),
+                (FIELD_SIGNATURE, "g") => signature = r.sig()?,
+                // unknown fields must be skipped, this covers the basic types
+                (_, "s" | "o") => {
+                    r.str()?;
+                }
+                (_, "g") => {
+                    r.sig()?;
+                }
+                (_, "u" | "i" | "b" | "h") => {
+                    r.u32()?;
+                }
+                (_, "y") => {
+                    r.u8()?;
+                }
+                _ => return Err(Error::InvalidRequest),
+            }
+        }
+
+        r.align(8);
+        let body_end = r.pos + body_len;
+        for c in signature.chars() {
+            let arg = match c {
+                's' | 'o' => Arg::Str(r.str()?),
+                'u' => Arg::U32(r.u32()?),
+                'b' => Arg::Bool(r.u32()? != 0),
+                // arguments we don't understand end the parsing
+                _ => break,
+            };
+            msg.args.push(arg);
+        }
+        if r.pos > body_end {
+            return Err(Error::InvalidRequest);
+        }
+        Ok(msg)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Reads one complete message from the stream
+fn read_message(stream: &mut impl Read) -> Result<Vec<u8>> {
+    let mut buf = vec![0; 16];
+    stream.read_exact(&mut buf)?;
+    let body_len = u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]) as usize;
+    let fields_len = u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]) as usize;
+    let total = (16 + fields_len).next_multiple_of(8) + body_len;
+    if buf[0] != b'l' || total > 1 << 20 {
+        return Err(Error::InvalidRequest);
+    }
+    buf.resize(total, 0);
+    stream.read_exact(&mut buf[16..])?;
+    Ok(buf)
+}
+
+// ----------------------------------------------------------------------------
+pub struct DBusService {
+    stream: Mutex<UnixStream>,
+    serial: AtomicU32,
+}
+
+impl DBusService {
+    // ------------------------------------------------------------------------
+    // Connects, claims the service name and answers calls on a background
+    // thread, forwarding them to `commands`
+    pub fn start(bus: Bus, commands: Sender<Command>) -> Result<std::sync::Arc<Self>> {
+        let mut stream = connect(bus)?;
+        authenticate(&mut stream)?;
+
+        let service = std::sync::Arc::new(Self {
+            stream: Mutex::new(stream.try_clone()?),
+            serial: AtomicU32::new(1),
+        });
+
+        let mut reader = BufReader::new(stream);
+        let bus_name = "org.freedesktop.DBus";
+        let bus_path = "/org/freedesktop/DBus";
+        service.send(&Message::method_call(bus_name, bus_path, bus_name, "Hello"))?;
+
+        let mut request = Message::method_call(bus_name, bus_path, bus_name, "RequestName");
+        request.args = vec![Arg::Str(SERVICE.to_string()), Arg::U32(4)]; // DO_NOT_QUEUE
+        let serial = service.send(&request)?;
+
+        // 1 = primary owner, everything else means another frame owns the name
+        loop {
+            let msg = Message::decode(&read_message(&mut reader)?)?;
+            if msg.reply_serial == Some(serial) {
+                if msg.args.first() != Some(&Arg::U32(1)) {
+                    return Err(Error::DBus {
+                        msg: format!("name {SERVICE} is taken: {:?}", msg.args),
+                    });
+                }
+                break;
+            }
+        }
+        log::info!("D-Bus: serving {SERVICE} on the {bus:?} bus");
+
+        let thread_service = std::sync::Arc::clone(&service);
+        std::thread::Builder::new()
+            .name(String::from("dbus"))
+            .spawn(move || {
+                while let Ok(buf) = read_message(&mut reader) {
+                    match Message::decode(&buf) {
+                        Ok(msg) => thread_service.handle(&msg, &commands),
+                        Err(e) => log::debug!("D-Bus: invalid message: {e:?}"),
+                    }
+                }
+                log::warn!("D-Bus: connection closed");
+            })?;
+
+        Ok(service)
+    }
+
+    // ------------------------------------------------------------------------
+    fn send(&self, msg: &Message) -> Result<u32> {
+        let serial = self.serial.fetch_add(1, Ordering::Relaxed);
+        let mut stream = self.stream.lock().map_err(|_| Error::DBus {
+            msg: String::from("connection lock poisoned"),
+        })?;
+        stream.write_all(&msg.encode(serial))?;
+        Ok(serial)
+    }
+
+    // ------------------------------------------------------------------------
+    fn handle(&self, msg: &Message, commands: &Sender<Command>) {
+        if msg.msg_type != Some(MessageType::MethodCall) {
+            return;
+        }
+
+        let reply = match (msg.interface.as_deref(), msg.member.as_deref()) {
+            (Some("org.freedesktop.DBus.Introspectable") | None, Some("Introspect")) => {
+                msg.reply(vec![Arg::Str(INTROSPECTION.to_string())])
+            }
+            (Some("org.freedesktop.DBus.Peer") | None, Some("Ping")) => msg.reply(vec![]),
+            (Some(INTERFACE) | None, Some(member)) => match parse_command(member, &msg.args) {
+                Some(command) => {
+                    let _ = commands.send(command);
+                    msg.reply(vec![])
+                }
+                None => msg.error(
+                    "org.freedesktop.DBus.Error.InvalidArgs",
+                    &format!("Invalid arguments for {member}"),
+                ),
+            },
+            _ => msg.error(
+                "org.freedesktop.DBus.Error.UnknownMethod",
+                "Unknown interface or method",
+            ),
+        };
+
+        if msg.flags & NO_REPLY_EXPECTED == 0 {
+            if let Err(e) = self.send(&reply) {
+                log::debug!("D-Bus: failed to reply: {e:?}");
+            }
+        }
+    }
+}
+
+impl EventSink for DBusService {
+    fn publish(&self, event: &FrameEvent) {
+        let signal = match event {
+            FrameEvent::PhotoChanged { path, title } => Message::signal(
+                "PhotoChanged",
+                vec![
+                    Arg::Str(path.to_string_lossy().into_owned()),
+                    Arg::Str(title.clone().unwrap_or_default()),
+                ],
+            ),
+            FrameEvent::SceneChanged { name } => {
+                Message::signal("SceneChanged", vec![Arg::Str(name.clone())])
+            }
+            FrameEvent::Error { message } => {
+                Message::signal("Error", vec![Arg::Str(message.clone())])
+            }
+        };
+        let _ = self.send(&signal);
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn parse_command(member: &str, args: &[Arg]) -> Option<Command> {
+    match (member, args) {
+        ("Next", []) => Some(Command::Next),
+        ("Previous", []) => Some(Command::Previous),
+        ("Pause", [Arg::Bool(paused)]) => Some(Command::Pause(*paused)),
+        ("SetScene", [Arg::Str(name)]) => SceneKind::from_name(name).map(Command::SetScene),
+        ("SetVolume", [Arg::U32(percent)]) if *percent <= 100 => {
+            Some(Command::SetVolume(*percent as f32 / 100.0))
+        }
+        ("Mute", [Arg::Bool(muted)]) => Some(Command::Mute(*muted)),
+        _ => None,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Only unix socket addresses are supported, e.g. "unix:path=/run/user/1000/bus"
+fn connect(bus: Bus) -> Result<UnixStream> {
+    let address = match bus {
+        Bus::Session => std::env::var("DBUS_SESSION_BUS_ADDRESS").map_err(|_| Error::DBus {
+            msg: String::from("DBUS_SESSION_BUS_ADDRESS is not set"),
+        })?,
+        Bus::System => std::env::var("DBUS_SYSTEM_BUS_ADDRESS")
+            .unwrap_or_else(|_| String::from("unix:path=/var/run/dbus/system_bus_socket")),
+    };
+
+    for entry in address.split(';') {
+        let Some(params) = entry.strip_prefix("unix:") else {
+            continue;
+        };
+        for param in params.split(',') {
+            if let Some(path) = param.strip_prefix("path=") {
+                return Ok(UnixStream::connect(path)?);
+            }
+            if let Some(name) = param.strip_prefix("abstract=") {
+                use std::os::linux::net::SocketAddrExt;
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                return Ok(UnixStream::connect_addr(&addr)?);
+            }
+        }
+    }
+    Err(Error::DBus {
+        msg: format!("unsupported bus address {address:?}"),
+    })
+}
+
+// ----------------------------------------------------------------------------
+// SASL EXTERNAL, the bus checks our uid through the socket credentials
+fn authenticate(stream: &mut UnixStream) -> Result<()> {
+    let uid = std::fs::metadata("/proc/self")?.uid();
+    let hex: String = uid
+        .to_string()
+        .bytes()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    stream.write_all(format!("\0AUTH EXTERNAL {hex}\r\n").as_bytes())?;
+
+    let mut line = String::new();
+    BufReader::new(&mut *stream).read_line(&mut line)?;
+    if !line.starts_with("OK ") {
+        return Err(Error::DBus {
+            msg: format!("authentication failed: {}", line.trim_end()),
+        });
+    }
+    stream.write_all(b"BEGIN\r\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_roundtrip() {
+        let mut call = Message::method_call(SERVICE, PATH, INTERFACE, "SetScene");
+        call.sender = Some(String::from(":1.42"));
+        call.args = vec![Arg::Str(String::from("today"))];
+        let buf = call.encode(7);
+        assert_eq!(read_message(&mut &buf[..]).unwrap(), buf);
+
+        let decoded = Message::decode(&buf).unwrap();
+        assert_eq!(decoded, Message { serial: 7, ..call });
+
+        let reply = decoded.error("org.example.Error", "nope");
+        let decoded_reply = Message::decode(&reply.encode(8)).unwrap();
+        assert_eq!(decoded_reply.msg_type, Some(MessageType::Error));
+        assert_eq!(decoded_reply.reply_serial, Some(7));
+        assert_eq!(decoded_reply.destination.as_deref(), Some(":1.42"));
+        assert_eq!(decoded_reply.args, vec![Arg::Str(String::from("nope"))]);
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("Next", &[]), Some(Command::Next));
+        assert_eq!(
+            parse_command("Pause", &[Arg::Bool(true)]),
+            Some(Command::Pause(true))
+        );
+        assert_eq!(
+            parse_command("SetScene", &[Arg::Str(String::from("today"))]),
+            Some(Command::SetScene(SceneKind::Today))
+        );
+        assert_eq!(parse_command("SetScene", &[]), None);
+        assert_eq!(
+            parse_command("SetVolume", &[Arg::U32(25)]),
+            Some(Command::SetVolume(0.25))
+        );
+        assert_eq!(parse_command("SetVolume", &[Arg::U32(150)]), None);
+        assert_eq!(parse_command("Jump", &[]), None);
+    }
+}
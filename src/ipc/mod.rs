@@ -0,0 +1,53 @@
+use crate::config::Settings;
+use crate::net::events::EventHub;
+use crate::scene::photo::MetaUpdate;
+use crate::scene::{FrameEvent, SceneKind};
+
+#[cfg(target_os = "linux")]
+pub mod dbus;
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+pub mod socket;
+
+// ----------------------------------------------------------------------------
+// Remote control requests, sent from service threads to the app's main loop
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Next,
+    Previous,
+    Pause(bool),
+    SetScene(SceneKind),
+    SetVolume(f32), // of the music, 0.0 to 1.0
+    Mute(bool),
+    ApplySettings(Settings), // already saved to the config file
+    UpdatePhoto(MetaUpdate), // of the photo on screen
+}
+
+// ----------------------------------------------------------------------------
+// D-Bus message bus to register the control service on (Linux only)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bus {
+    Session,
+    System,
+}
+
+impl Bus {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "session" => Some(Bus::Session),
+            "system" => Some(Bus::System),
+            _ => None,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Receives the frame events, implemented by the control services
+pub trait EventSink: Send + Sync {
+    fn publish(&self, event: &FrameEvent);
+}
+
+impl EventSink for EventHub {
+    fn publish(&self, event: &FrameEvent) {
+        EventHub::publish(self, event);
+    }
+}
@@ -0,0 +1,426 @@
+use crate::error::{Error, Result};
+use crate::ipc::{Command, EventSink};
+use crate::scene::photo::{MAX_RATING, MetaUpdate};
+use crate::scene::{FrameEvent, SceneKind};
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+#[cfg(target_os = "linux")]
+pub use unix::default_path;
+#[cfg(target_os = "linux")]
+use unix::{Listener, connect};
+#[cfg(target_os = "windows")]
+pub use win32::default_path;
+#[cfg(target_os = "windows")]
+use win32::{Listener, connect};
+
+// ----------------------------------------------------------------------------
+// Control channel for scripts on the frame itself, see `home-rs ctl`: a Unix
+// domain socket on Linux, a named pipe on Windows. Every connection sends one
+// request line and gets one reply line: `ok`, `error: <reason>` or the JSON
+// status.
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    Command(Command),
+    Status,
+}
+
+impl Request {
+    // ------------------------------------------------------------------------
+    // e.g. "next", "prev", "pause", "resume", "scene today", "volume 40",
+    // "mute", "unmute", "rate 4", "tag family", "untag family", "status"
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let request = match (words.next()?, words.next()) {
+            ("next", None) => Request::Command(Command::Next),
+            ("prev" | "previous", None) => Request::Command(Command::Previous),
+            ("pause", None) => Request::Command(Command::Pause(true)),
+            ("resume", None) => Request::Command(Command::Pause(false)),
+            ("scene", Some(name)) => {
+                Request::Command(Command::SetScene(SceneKind::from_name(name)?))
+            }
+            ("volume", Some(percent)) => {
+                let percent: u8 = percent.parse().ok().filter(|&p| p <= 100)?;
+                Request::Command(Command::SetVolume(percent as f32 / 100.0))
+            }
+            ("mute", None) => Request::Command(Command::Mute(true)),
+            ("unmute", None) => Request::Command(Command::Mute(false)),
+            ("rate", Some(rating)) => {
+                let rating: u8 = rating.parse().ok().filter(|&r| r <= MAX_RATING)?;
+                Request::Command(Command::UpdatePhoto(MetaUpdate {
+                    rating: Some(rating),
+                    ..MetaUpdate::default()
+                }))
+            }
+            ("tag", Some(tag)) => Request::Command(Command::UpdatePhoto(MetaUpdate {
+                add_tags: vec![tag.to_string()],
+                ..MetaUpdate::default()
+            })),
+            ("untag", Some(tag)) => Request::Command(Command::UpdatePhoto(MetaUpdate {
+                remove_tags: vec![tag.to_string()],
+                ..MetaUpdate::default()
+            })),
+            ("status", None) => Request::Status,
+            _ => return None,
+        };
+        words.next().is_none().then_some(request)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Latest state reported by the scenes, answered to `status`
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Status {
+    pub scene: Option<String>,
+    pub photo: Option<PathBuf>,
+    pub title: Option<String>,
+}
+
+// ----------------------------------------------------------------------------
+pub struct ControlSocket {
+    status: Arc<Mutex<Status>>,
+}
+
+impl ControlSocket {
+    // ------------------------------------------------------------------------
+    // Listens on `path` and answers requests on a background thread,
+    // forwarding commands to `commands`
+    pub fn start(path: &Path, commands: Sender<Command>) -> Result<Arc<Self>> {
+        let mut listener = Listener::bind(path)?;
+        log::info!("Control socket: listening on {path:?}");
+
+        let status = Arc::new(Mutex::new(Status::default()));
+        let thread_status = Arc::clone(&status);
+        std::thread::Builder::new()
+            .name(String::from("control"))
+            .spawn(move || {
+                loop {
+                    let served = listener
+                        .accept()
+                        .and_then(|stream| serve(stream, &thread_status, &commands));
+                    if let Err(e) = served {
+                        log::debug!("Control socket: {e:?}");
+                    }
+                }
+            })?;
+
+        Ok(Arc::new(Self { status }))
+    }
+}
+
+impl EventSink for ControlSocket {
+    fn publish(&self, event: &FrameEvent) {
+        let Ok(mut status) = self.status.lock() else {
+            return;
+        };
+        match event {
+            FrameEvent::SceneChanged { name } => status.scene = Some(name.clone()),
+            FrameEvent::PhotoChanged { path, title } => {
+                status.photo = Some(path.clone());
+                status.title = title.clone();
+            }
+            FrameEvent::Error { .. } => {}
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn serve(
+    stream: impl Read + Write,
+    status: &Mutex<Status>,
+    commands: &Sender<Command>,
+) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    stream.read_line(&mut line)?;
+
+    let reply = match Request::parse(&line) {
+        Some(Request::Command(command)) => match commands.send(command) {
+            Ok(()) => String::from("ok"),
+            Err(_) => String::from("error: frame is shutting down"),
+        },
+        Some(Request::Status) => {
+            let status = status.lock().map(|s| s.clone()).unwrap_or_default();
+            serde_json::to_string(&status)?
+        }
+        None => format!("error: unknown request {:?}", line.trim()),
+    };
+    let stream = stream.get_mut();
+    stream.write_all(format!("{reply}\n").as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// Client side: sends `request` to the frame listening on `path`, returns the
+// reply or the reason of an `error:` reply as `Error::Ipc`
+pub fn send(path: &Path, request: &str) -> Result<String> {
+    let stream = connect(path).map_err(|e| Error::Ipc {
+        msg: format!("no frame listening on {path:?}: {e}"),
+    })?;
+    (&stream).write_all(format!("{request}\n").as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+    let reply = reply.trim_end();
+    match reply.strip_prefix("error: ") {
+        Some(msg) => Err(Error::Ipc {
+            msg: msg.to_string(),
+        }),
+        None => Ok(reply.to_string()),
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[cfg(target_os = "linux")]
+mod unix {
+    use crate::error::{Error, Result};
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    // ------------------------------------------------------------------------
+    // $XDG_RUNTIME_DIR/home-frame.sock, or a per user path in /tmp
+    pub fn default_path() -> PathBuf {
+        match std::env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) => Path::new(&dir).join("home-frame.sock"),
+            None => {
+                let uid = std::fs::metadata("/proc/self").map_or(0, |m| m.uid());
+                PathBuf::from(format!("/tmp/home-frame-{uid}.sock"))
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub struct Listener(UnixListener);
+
+    impl Listener {
+        // Only the user of the frame may connect
+        pub fn bind(path: &Path) -> Result<Self> {
+            // a socket left behind by a crashed frame refuses connections
+            if path.exists() {
+                if UnixStream::connect(path).is_ok() {
+                    return Err(Error::Ipc {
+                        msg: format!("{path:?} is in use by another frame"),
+                    });
+                }
+                std::fs::remove_file(path)?;
+            }
+            let listener = UnixListener::bind(path)?;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+            Ok(Self(listener))
+        }
+
+        pub fn accept(&mut self) -> Result<UnixStream> {
+            let (stream, _) = self.0.accept()?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            Ok(stream)
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn connect(path: &Path) -> std::io::Result<UnixStream> {
+        let stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        Ok(stream)
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[cfg(target_os = "windows")]
+mod win32 {
+    use crate::error::{Error, Result};
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::windows::io::{AsRawHandle, FromRawHandle};
+    use std::path::{Path, PathBuf};
+    use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_PIPE_CONNECTED, HANDLE};
+    use windows::Win32::Storage::FileSystem::{
+        FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_FLAGS_AND_ATTRIBUTES, PIPE_ACCESS_DUPLEX,
+    };
+    use windows::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+    use windows::core::HSTRING;
+
+    // Bytes buffered per direction, requests and replies are single lines
+    const BUFFER_SIZE: u32 = 4096;
+
+    // ------------------------------------------------------------------------
+    // \\.\pipe\home-frame-<user>, one per user like the Unix socket
+    pub fn default_path() -> PathBuf {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        PathBuf::from(format!(r"\\.\pipe\home-frame-{user}"))
+    }
+
+    // ------------------------------------------------------------------------
+    // Instances of the named pipe, one waits for the next client while the
+    // last one is served. The default security of pipes only lets the user
+    // of the frame and administrators write to them.
+    pub struct Listener {
+        name: HSTRING,
+        next: File,
+    }
+
+    impl Listener {
+        pub fn bind(path: &Path) -> Result<Self> {
+            let name = HSTRING::from(path);
+            let next = create_pipe(&name, FILE_FLAG_FIRST_PIPE_INSTANCE).map_err(|e| {
+                match e.raw_os_error() == Some(ERROR_ACCESS_DENIED.0 as i32) {
+                    true => Error::Ipc {
+                        msg: format!("{path:?} is in use by another frame"),
+                    },
+                    false => e.into(),
+                }
+            })?;
+            Ok(Self { name, next })
+        }
+
+        // Pipes have no timeouts, a client that doesn't send its request
+        // holds up the others
+        pub fn accept(&mut self) -> Result<Pipe> {
+            let handle = HANDLE(self.next.as_raw_handle());
+            let connected = unsafe { ConnectNamedPipe(handle, None) };
+            let next = create_pipe(&self.name, FILE_FLAGS_AND_ATTRIBUTES(0))?;
+            let pipe = Pipe(std::mem::replace(&mut self.next, next));
+            match connected {
+                Err(e) if e.code() != ERROR_PIPE_CONNECTED.to_hresult() => Err(e.into()),
+                _ => Ok(pipe),
+            }
+        }
+    }
+
+    fn create_pipe(name: &HSTRING, flags: FILE_FLAGS_AND_ATTRIBUTES) -> std::io::Result<File> {
+        let mode = PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS;
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name,
+                PIPE_ACCESS_DUPLEX | flags,
+                mode,
+                PIPE_UNLIMITED_INSTANCES,
+                BUFFER_SIZE,
+                BUFFER_SIZE,
+                0,
+                None,
+            )
+        };
+        if handle.is_invalid() {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(unsafe { File::from_raw_handle(handle.0) })
+    }
+
+    // ------------------------------------------------------------------------
+    // Server end of a connection. Closing a pipe drops what the client hasn't
+    // read yet, so `flush` waits for that.
+    pub struct Pipe(File);
+
+    impl Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.0.sync_all() // FlushFileBuffers
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn connect(path: &Path) -> std::io::Result<File> {
+        File::options().read(true).write(true).open(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(
+            Request::parse("next\n"),
+            Some(Request::Command(Command::Next))
+        );
+        assert_eq!(
+            Request::parse("prev"),
+            Some(Request::Command(Command::Previous))
+        );
+        assert_eq!(
+            Request::parse("resume"),
+            Some(Request::Command(Command::Pause(false)))
+        );
+        assert_eq!(
+            Request::parse("scene today"),
+            Some(Request::Command(Command::SetScene(SceneKind::Today)))
+        );
+        assert_eq!(Request::parse("status"), Some(Request::Status));
+        assert_eq!(Request::parse("scene"), None);
+        assert_eq!(
+            Request::parse("scene clock"),
+            Some(Request::Command(Command::SetScene(SceneKind::Clock)))
+        );
+        assert_eq!(
+            Request::parse("scene weather"),
+            Some(Request::Command(Command::SetScene(SceneKind::Plugin(
+                String::from("weather")
+            ))))
+        );
+        assert_eq!(Request::parse("scene Nope!"), None);
+        assert_eq!(
+            Request::parse("volume 40"),
+            Some(Request::Command(Command::SetVolume(0.4)))
+        );
+        assert_eq!(Request::parse("volume 101"), None);
+        assert_eq!(
+            Request::parse("unmute"),
+            Some(Request::Command(Command::Mute(false)))
+        );
+        assert_eq!(
+            Request::parse("tag family"),
+            Some(Request::Command(Command::UpdatePhoto(MetaUpdate {
+                add_tags: vec![String::from("family")],
+                ..MetaUpdate::default()
+            })))
+        );
+        assert_eq!(Request::parse("rate 6"), None);
+        assert_eq!(Request::parse("next please"), None);
+        assert_eq!(Request::parse(""), None);
+    }
+
+    #[test]
+    fn test_control_socket() {
+        let name = format!("home-frame-test-{}.sock", std::process::id());
+        let path = default_path().with_file_name(name);
+        let (sender, commands) = mpsc::channel();
+        let socket = ControlSocket::start(&path, sender.clone()).unwrap();
+        assert!(ControlSocket::start(&path, sender).is_err());
+
+        assert_eq!(send(&path, "next").unwrap(), "ok");
+        assert_eq!(commands.recv().unwrap(), Command::Next);
+        assert!(matches!(send(&path, "jump"), Err(Error::Ipc { .. })));
+
+        socket.publish(&FrameEvent::SceneChanged {
+            name: String::from("today"),
+        });
+        let status = send(&path, "status").unwrap();
+        assert_eq!(status, r#"{"scene":"today","photo":null,"title":null}"#);
+        let _ = std::fs::remove_file(&path);
+    }
+}
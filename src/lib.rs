@@ -0,0 +1,25 @@
+#![allow(dead_code)]
+// Home Assist engine: platform independent app, scenes, layout and rendering.
+// The binary in main.rs adds window creation, input and command line parsing.
+pub mod app;
+pub mod audio;
+pub mod bench;
+pub mod config;
+pub mod core;
+pub mod error;
+pub mod geo;
+pub mod gfx;
+pub mod gl;
+pub mod headless;
+pub mod ipc;
+pub mod library;
+pub mod net;
+pub mod scene;
+pub mod util;
+pub mod v2d;
+
+pub use app::{App, AppConfig};
+pub use core::gl_canvas::Canvas;
+pub use core::gl_renderer::Renderer;
+pub use scene::registry::{ScenePlugin, SceneRegistry};
+pub use scene::{Scene, layouter::Layouter, manager::SceneManager};
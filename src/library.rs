@@ -0,0 +1,463 @@
+use crate::error::{Error, Result, ResultExt};
+use crate::gfx::color_conversion::{ImageGeometry, YCbCrMatrix, ycbcr24_to_rgb24};
+use crate::gfx::color_format::ColorFormat;
+use crate::gfx::png_write;
+use crate::gfx::resize::{self, Filter};
+use crate::scene::photo::{self, META_VERSION, MetaUpdate, Photo, PhotoMeta};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+// Written into each photo directory by `home-rs index`
+pub const INDEX_FILE: &str = "index.json";
+pub const THUMB_DIR: &str = ".thumbs";
+
+const INDEX_VERSION: u32 = 1;
+const THUMB_SIZE: (usize, usize) = (320, 320);
+
+// ----------------------------------------------------------------------------
+// Problem with the sidecar JSON of a photo, line and column are 0 if the
+// problem isn't at a specific position
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetaProblem {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for MetaProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.line {
+            0 => write!(f, "{}: {}", self.path.display(), self.message),
+            line => write!(
+                f,
+                "{}:{line}:{}: {}",
+                self.path.display(),
+                self.column,
+                self.message
+            ),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Checks the sidecar files of all photos in `dir`, returns the number of
+// photos and the problems found
+pub fn validate_dir(dir: &Path) -> Result<(usize, Vec<MetaProblem>)> {
+    let files = list_files(dir)?;
+    let mut problems = Vec::new();
+    let mut photos = 0;
+    let problem = |path: &Path, message: &str| MetaProblem {
+        path: path.to_path_buf(),
+        line: 0,
+        column: 0,
+        message: message.to_string(),
+    };
+
+    for path in &files {
+        if photo::is_image_file(path) {
+            photos += 1;
+            let json_path = path.with_extension("json");
+            match std::fs::read_to_string(&json_path) {
+                Ok(text) => problems.extend(check_meta(&json_path, &text)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    problems.push(problem(path, "no sidecar JSON, the photo is skipped"));
+                }
+                Err(e) => problems.push(problem(&json_path, &e.to_string())),
+            }
+        } else if is_sidecar(path) && !photo::image_paths(path).any(|p| p.is_file()) {
+            problems.push(problem(path, "no photo for this sidecar"));
+        }
+    }
+    Ok((photos, problems))
+}
+
+// ----------------------------------------------------------------------------
+fn check_meta(path: &Path, text: &str) -> Option<MetaProblem> {
+    let (line, column, message) = match serde_json::from_str::<PhotoMeta>(text) {
+        Ok(meta) => match meta.coord {
+            Some(coord) if !coord.is_valid() => (0, 0, String::from("coord is out of range")),
+            _ => return None,
+        },
+        // the position is reported separately
+        Err(e) => {
+            let message = e.to_string();
+            let message = message
+                .rsplit_once(" at line ")
+                .map_or(&*message, |(m, _)| m);
+            (e.line(), e.column(), message.to_string())
+        }
+    };
+    Some(MetaProblem {
+        path: path.to_path_buf(),
+        line,
+        column,
+        message,
+    })
+}
+
+// ----------------------------------------------------------------------------
+fn is_sidecar(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+        && path.file_name().is_some_and(|name| name != INDEX_FILE)
+}
+
+// ----------------------------------------------------------------------------
+pub(crate) fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).with_path("listing", dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub file: String,
+    pub width: usize,
+    pub height: usize,
+    pub modified: u64,     // seconds since 1970 of the photo file
+    pub thumbnail: String, // PNG relative to the photo directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>, // copied from the sidecar
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tag: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_shown: Option<i64>, // seconds since 1970, by the slideshow
+}
+
+impl IndexEntry {
+    fn set_meta(&mut self, meta: Option<&PhotoMeta>) {
+        self.rating = meta.and_then(|m| m.rating);
+        self.tag = meta.and_then(|m| m.tag.clone()).unwrap_or_default();
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhotoIndex {
+    pub version: u32,
+    pub photos: Vec<IndexEntry>,
+}
+
+// ----------------------------------------------------------------------------
+// None if there is no index or it was written by another version
+pub fn load_index(dir: &Path) -> Option<PhotoIndex> {
+    let text = std::fs::read_to_string(dir.join(INDEX_FILE)).ok()?;
+    let index: PhotoIndex = serde_json::from_str(&text).ok()?;
+    (index.version == INDEX_VERSION).then_some(index)
+}
+
+// ----------------------------------------------------------------------------
+// Writes the index and a thumbnail per photo into `dir`. Photos that didn't
+// change since the last run are skipped, `report` gets every photo that was
+// decoded and its error if that failed. Returns the index and the number of
+// decoded photos.
+pub fn build_index(
+    dir: &Path,
+    mut report: impl FnMut(&Path, Option<&Error>),
+) -> Result<(PhotoIndex, usize)> {
+    let old = load_index(dir).unwrap_or_default();
+    let thumb_dir = dir.join(THUMB_DIR);
+    std::fs::create_dir_all(&thumb_dir).with_path("creating", &thumb_dir)?;
+
+    let mut index = PhotoIndex {
+        version: INDEX_VERSION,
+        photos: Vec::new(),
+    };
+    let mut decoded = 0;
+    for path in list_files(dir)?.iter().filter(|p| photo::is_image_file(p)) {
+        let Some(file) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let modified = std::fs::metadata(path)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        // the sidecar may change without the photo
+        let json_path = path.with_extension("json");
+        match migrate_sidecar(&json_path) {
+            Ok(true) => log::info!("Upgraded {json_path:?} to version {META_VERSION}"),
+            Ok(false) => {}
+            Err(e) => log::debug!("Not upgraded: {e}"),
+        }
+        let meta = Photo::from_path(path.clone()).ok().map(|p| p.meta);
+        let unchanged = old
+            .photos
+            .iter()
+            .find(|e| e.file == file && e.modified == modified && dir.join(&e.thumbnail).is_file());
+        if let Some(entry) = unchanged {
+            let mut entry = entry.clone();
+            entry.set_meta(meta.as_ref());
+            index.photos.push(entry);
+            continue;
+        }
+
+        decoded += 1;
+        let thumbnail = format!("{THUMB_DIR}/{}.png", file.trim_end_matches(".webp"));
+        match write_thumbnail(path, &dir.join(&thumbnail)) {
+            Ok((width, height)) => {
+                report(path, None);
+                let mut entry = IndexEntry {
+                    file: file.to_string(),
+                    width,
+                    height,
+                    modified,
+                    thumbnail,
+                    rating: None,
+                    tag: Vec::new(),
+                    last_shown: old
+                        .photos
+                        .iter()
+                        .find(|e| e.file == file)
+                        .and_then(|e| e.last_shown),
+                };
+                entry.set_meta(meta.as_ref());
+                index.photos.push(entry);
+            }
+            Err(e) => report(path, Some(&e)),
+        }
+    }
+
+    // thumbnails of removed photos
+    for path in list_files(&thumb_dir)? {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let thumbnail = format!("{THUMB_DIR}/{name}");
+        if !index.photos.iter().any(|e| e.thumbnail == thumbnail) {
+            std::fs::remove_file(&path)?;
+        }
+    }
+
+    save_index(dir, &index)?;
+    Ok((index, decoded))
+}
+
+// ----------------------------------------------------------------------------
+fn save_index(dir: &Path, index: &PhotoIndex) -> Result<()> {
+    write_atomic(
+        &dir.join(INDEX_FILE),
+        &(serde_json::to_string_pretty(index)? + "\n"),
+    )
+}
+
+// ----------------------------------------------------------------------------
+// Writes `update` into the sidecar JSON of `photo` and into the index of its
+// directory if there is one. Fields of the sidecar that aren't changed are
+// kept, unknown ones as well. Returns the new metadata.
+pub fn write_meta(photo: &Path, update: &MetaUpdate) -> Result<PhotoMeta> {
+    if !update.is_valid() {
+        return Err(Error::InvalidArgument {
+            arg: String::from("rating"),
+        });
+    }
+    let json_path = photo.with_extension("json");
+    let mut fields: serde_json::Map<String, serde_json::Value> =
+        match std::fs::read_to_string(&json_path) {
+            Ok(text) => serde_json::from_str(&text).with_path("parsing", &json_path)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::Map::new(),
+            Err(e) => return Err(e).with_path("reading", &json_path),
+        };
+    photo::migrate_meta(&mut fields);
+
+    match update.rating {
+        Some(0) => _ = fields.remove("rating"),
+        Some(rating) => _ = fields.insert(String::from("rating"), rating.into()),
+        None => {}
+    }
+    let mut tags: Vec<String> = match fields.remove("tag") {
+        Some(tags) => serde_json::from_value(tags).with_path("parsing", &json_path)?,
+        None => Vec::new(),
+    };
+    update.apply_tags(&mut tags);
+    if !tags.is_empty() {
+        fields.insert(String::from("tag"), tags.into());
+    }
+
+    // a sidecar the frame can't read any more is not written
+    let text = serde_json::to_string_pretty(&fields)? + "\n";
+    let meta: PhotoMeta = serde_json::from_str(&text).with_path("checking", &json_path)?;
+    write_atomic(&json_path, &text)?;
+
+    let file = photo.file_name().and_then(|n| n.to_str());
+    if let Some(dir) = photo.parent()
+        && let Some(mut index) = load_index(dir)
+        && let Some(entry) = index
+            .photos
+            .iter_mut()
+            .find(|e| Some(e.file.as_str()) == file)
+    {
+        entry.set_meta(Some(&meta));
+        save_index(dir, &index)?;
+    }
+    Ok(meta)
+}
+
+// ----------------------------------------------------------------------------
+// Notes in the index of its directory when `photo` was shown, photos without
+// an index are left alone
+pub fn record_shown(photo: &Path, time: i64) -> Result<()> {
+    let file = photo.file_name().and_then(|n| n.to_str());
+    if let Some(dir) = photo.parent()
+        && let Some(mut index) = load_index(dir)
+        && let Some(entry) = index
+            .photos
+            .iter_mut()
+            .find(|e| Some(e.file.as_str()) == file)
+    {
+        entry.last_shown = Some(time);
+        save_index(dir, &index)?;
+    }
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// Rewrites a sidecar of an older version in the current format, returns
+// false if it is up to date
+fn migrate_sidecar(json_path: &Path) -> Result<bool> {
+    let text = std::fs::read_to_string(json_path).with_path("reading", json_path)?;
+    let mut fields: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&text).with_path("parsing", json_path)?;
+    if !photo::migrate_meta(&mut fields) {
+        return Ok(false);
+    }
+    write_atomic(json_path, &(serde_json::to_string_pretty(&fields)? + "\n"))?;
+    Ok(true)
+}
+
+// ----------------------------------------------------------------------------
+// Written next to the file first so a failed write keeps the old one
+fn write_atomic(path: &Path, text: &str) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, text).with_path("writing", &tmp)?;
+    std::fs::rename(&tmp, path).with_path("replacing", path)
+}
+
+// ----------------------------------------------------------------------------
+// Returns the size of the photo
+fn write_thumbnail(src: &Path, dst: &Path) -> Result<(usize, usize)> {
+    let (cx, cy, yuv24) = photo::read_photo(src)?;
+    let (thumb_cx, thumb_cy) = resize::fit_size(cx, cy, THUMB_SIZE.0, THUMB_SIZE.1);
+    let yuv24 = resize::resize(&yuv24, cx, cy, 3, thumb_cx, thumb_cy, Filter::Bilinear);
+    let geo = ImageGeometry {
+        cx: thumb_cx,
+        cy: thumb_cy,
+        cf: ColorFormat::RGB0888,
+    };
+    let rgb = ycbcr24_to_rgb24(&yuv24, &geo, YCbCrMatrix::Bt601);
+    std::fs::write(dst, png_write::encode_rgb24(thumb_cx, thumb_cy, &rgb))
+        .with_path("writing", dst)?;
+    Ok((cx, cy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_dir() {
+        let dir = std::env::temp_dir().join(format!("home-rs-validate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, text: &str| std::fs::write(dir.join(name), text).unwrap();
+        write("good.webp", "");
+        write("good.json", r#"{ "place": ["Berlin"], "rating": 4 }"#);
+        write("broken.webp", "");
+        write(
+            "broken.json",
+            "{\n  \"place\": [\"Berlin\"],\n  \"rating\": \"high\"\n}",
+        );
+        write("far.webp", "");
+        write("far.json", r#"{ "coord": [120.0, 8.0] }"#);
+        write("lonely.webp", "");
+        write("orphan.json", "{}");
+        write(INDEX_FILE, "{}");
+
+        let (photos, problems) = validate_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(photos, 4);
+
+        let lines: Vec<_> = problems
+            .iter()
+            .map(|p| {
+                let name = p.path.file_name().unwrap().to_string_lossy();
+                format!("{name}:{}:{}: {}", p.line, p.column, p.message)
+            })
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("broken.json:3:18: invalid type: string \"high\""));
+        assert_eq!(lines[1], "far.json:0:0: coord is out of range");
+        assert_eq!(
+            lines[2],
+            "lonely.webp:0:0: no sidecar JSON, the photo is skipped"
+        );
+        assert_eq!(lines[3], "orphan.json:0:0: no photo for this sidecar");
+    }
+
+    #[test]
+    fn test_write_meta() {
+        let dir = std::env::temp_dir().join(format!("home-rs-meta-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let photo = dir.join("beach.webp");
+        std::fs::write(
+            dir.join("beach.json"),
+            r#"{ "place": ["Binz"], "tag": ["sea", "old"], "camera": "X100" }"#,
+        )
+        .unwrap();
+        let index = PhotoIndex {
+            version: INDEX_VERSION,
+            photos: vec![IndexEntry {
+                file: String::from("beach.webp"),
+                width: 640,
+                height: 480,
+                modified: 0,
+                thumbnail: String::from(".thumbs/beach.png"),
+                rating: None,
+                tag: Vec::new(),
+                last_shown: None,
+            }],
+        };
+        save_index(&dir, &index).unwrap();
+
+        let update = MetaUpdate {
+            rating: Some(5),
+            add_tags: vec![String::from("family"), String::from("sea")],
+            remove_tags: vec![String::from("old")],
+        };
+        let meta = write_meta(&photo, &update).unwrap();
+        assert_eq!(meta.rating, Some(5));
+        assert_eq!(meta.tag.unwrap(), ["sea", "family"]);
+
+        let text = std::fs::read_to_string(dir.join("beach.json")).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(json["version"], META_VERSION);
+        assert_eq!(json["camera"], "X100");
+        assert_eq!(json["place"][0], "Binz");
+        let index = load_index(&dir).unwrap();
+        assert_eq!(index.photos[0].rating, Some(5));
+        assert_eq!(index.photos[0].tag, ["sea", "family"]);
+
+        record_shown(&photo, 1_700_000_000).unwrap();
+        let index = load_index(&dir).unwrap();
+        assert_eq!(index.photos[0].last_shown, Some(1_700_000_000));
+        assert_eq!(index.photos[0].rating, Some(5));
+
+        let unrate = MetaUpdate {
+            rating: Some(0),
+            ..MetaUpdate::default()
+        };
+        assert_eq!(write_meta(&photo, &unrate).unwrap().rating, None);
+        let invalid = MetaUpdate {
+            rating: Some(6),
+            ..MetaUpdate::default()
+        };
+        assert!(write_meta(&photo, &invalid).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
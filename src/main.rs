@@ -1,39 +1,145 @@
-#![allow(dead_code)]
-mod app;
-mod core;
-mod error;
-mod gfx;
-mod gl;
-mod scene;
-mod util;
-mod v2d;
-
 // ----------------------------------------------------------------------------
 #[cfg(target_os = "windows")]
 pub fn main() {
+    if run_tool() {
+        return;
+    }
     if let Err(e) = win32::main() {
-        eprintln!("Error: {e:?}");
+        fatal("Error", &e);
     }
 }
 
 // ----------------------------------------------------------------------------
 #[cfg(target_os = "linux")]
 pub fn main() {
+    if run_tool() {
+        return;
+    }
     if let Err(e) = linux::main() {
-        eprintln!("Error: {e:?}");
+        fatal("Error", &e);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Subcommands that run without a window, returns false to start the frame.
+// There is no logger in these modes, scripts read stdout and stderr.
+fn run_tool() -> bool {
+    let mut args = env::args().skip(1);
+    let Some(command) = args.next() else {
+        return false;
+    };
+    let result = match command.as_str() {
+        #[cfg(any(target_os = "linux", target_os = "windows"))]
+        "ctl" => ctl(args),
+        "index" => index(args),
+        "validate-meta" => validate_meta(args),
+        _ => return false,
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+    true
+}
+
+// ----------------------------------------------------------------------------
+// `home-rs index <dir>...`, writes index.json and the thumbnails of the
+// photos in each directory
+fn index(args: impl Iterator<Item = String>) -> Result<()> {
+    let dirs = tool_args(args, "index")?;
+    for dir in &dirs {
+        let (index, decoded) = library::build_index(Path::new(dir), |path, e| match e {
+            Some(e) => eprintln!("{}: {e}", path.display()),
+            None => println!("{}", path.display()),
+        })?;
+        println!("{dir}: {} photos, {decoded} updated", index.photos.len());
+    }
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// `home-rs validate-meta <dir>...`, lists the problems of the sidecar files
+// as `path:line:column: message` and fails if there are any
+fn validate_meta(args: impl Iterator<Item = String>) -> Result<()> {
+    let dirs = tool_args(args, "validate-meta")?;
+    let mut count = 0;
+    for dir in &dirs {
+        let (photos, problems) = library::validate_dir(Path::new(dir))?;
+        for problem in &problems {
+            println!("{problem}");
+        }
+        eprintln!("{dir}: {photos} photos, {} problems", problems.len());
+        count += problems.len();
+    }
+    match count {
+        0 => Ok(()),
+        _ => Err(Error::InvalidGallery),
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn tool_args(args: impl Iterator<Item = String>, command: &str) -> Result<Vec<String>> {
+    let args: Vec<String> = args.collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print!("{USAGE}");
+        std::process::exit(0);
+    }
+    if args.is_empty() {
+        return Err(Error::InvalidArgument {
+            arg: format!("{command}: missing directory"),
+        });
     }
+    Ok(args)
+}
+
+// ----------------------------------------------------------------------------
+// `home-rs ctl [--control-socket <path>] <request>`, sends one request to the
+// running frame and prints the reply unless it's a plain "ok"
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn ctl(mut args: impl Iterator<Item = String>) -> Result<()> {
+    use home_rs::ipc::socket::{self, Request};
+
+    let mut path = socket::default_path();
+    let mut request = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--control-socket" => {
+                path = PathBuf::from(args.next().ok_or(Error::InvalidArgument { arg })?)
+            }
+            "--help" | "-h" => {
+                print!("{USAGE}");
+                return Ok(());
+            }
+            _ => request.push(arg),
+        }
+    }
+
+    let request = request.join(" ");
+    if Request::parse(&request).is_none() {
+        return Err(Error::InvalidArgument { arg: request });
+    }
+    let reply = socket::send(&path, &request)?;
+    if reply != "ok" {
+        println!("{reply}");
+    }
+    Ok(())
 }
 
 // ----------------------------------------------------------------------------
 #[cfg(target_os = "windows")]
 mod win32 {
-    use crate::app::App;
-    use crate::core::app_loop::AppLoop;
-    use crate::core::clock::Clock;
-    use crate::core::input::{self, Key};
-    use crate::error::{Error, Result};
-    use crate::gl::win32::Win32GlContext;
-    use crate::gl::win32::window::{IWindow, WindowProc};
+    use home_rs::App;
+    use home_rs::core::app_loop::AppLoop;
+    use home_rs::core::clock::Clock;
+    use home_rs::core::display::Monitor;
+    use home_rs::core::input::{self, Keymap};
+    use home_rs::error::{Error, Result};
+    use home_rs::gl::win32::Win32GlContext;
+    use home_rs::gl::win32::window::{IWindow, WindowProc};
+    use windows::Win32::Graphics::Gdi::{
+        EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITOR_DEFAULTTONEAREST, MONITORINFO,
+        MonitorFromWindow,
+    };
     use windows::Win32::UI::Input::{
         GetRawInputData, HRAWINPUT, KeyboardAndMouse, RAWINPUT, RAWINPUTHEADER, RID_INPUT,
         RIM_TYPEKEYBOARD, RIM_TYPEMOUSE,
@@ -43,24 +149,108 @@ mod win32 {
         UI::Input::{RAWINPUTDEVICE, RIDEV_INPUTSINK, RegisterRawInputDevices},
         UI::WindowsAndMessaging::*,
     };
+    use windows::core::BOOL;
 
     // ------------------------------------------------------------------------
     pub fn main() -> Result<()> {
         let cfg = super::init()?;
+        if cfg.bench {
+            return super::bench(&cfg);
+        }
+        if cfg.headless.is_some() {
+            return super::headless(cfg);
+        }
+        let display = cfg.display;
+        let area = display.placement(&monitors());
         let hwnd = WindowProc::<AppWindow>::create(
             "Home",
             "AppWindow",
             WS_POPUP | WS_VISIBLE,
+            POINT {
+                x: area.x,
+                y: area.y,
+            },
+            SIZE {
+                cx: area.cx as i32,
+                cy: area.cy as i32,
+            },
             AppWindowParams { cfg },
         );
 
         if let Ok(hwnd) = hwnd {
-            crate::gl::win32::window::run_message_loop(hwnd);
+            if display.fullscreen {
+                // on_size resizes the app
+                cover_monitor(hwnd)?;
+            }
+            home_rs::gl::win32::window::run_message_loop(hwnd);
         }
 
         Ok(())
     }
 
+    // ------------------------------------------------------------------------
+    // The monitors of the desktop, the primary one first
+    fn monitors() -> Vec<Monitor> {
+        unsafe extern "system" fn add(
+            monitor: HMONITOR,
+            _hdc: HDC,
+            _rect: *mut RECT,
+            data: LPARAM,
+        ) -> BOOL {
+            let infos = unsafe { &mut *(data.0 as *mut Vec<MONITORINFO>) };
+            if let Ok(info) = monitor_info(monitor) {
+                infos.push(info);
+            }
+            true.into()
+        }
+
+        let mut infos: Vec<MONITORINFO> = Vec::new();
+        let data = LPARAM(&mut infos as *mut Vec<MONITORINFO> as isize);
+        let _ = unsafe { EnumDisplayMonitors(None, None, Some(add), data) };
+        infos.sort_by_key(|info| info.dwFlags & MONITORINFOF_PRIMARY == 0);
+        infos
+            .iter()
+            .map(|info| to_monitor(info.rcMonitor))
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------
+    // Borderless fullscreen on the monitor the window was placed on
+    fn cover_monitor(hwnd: HWND) -> Result<()> {
+        let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+        let area = to_monitor(monitor_info(monitor)?.rcMonitor);
+        unsafe {
+            SetWindowPos(
+                hwnd,
+                Some(HWND_TOP),
+                area.x,
+                area.y,
+                area.cx as i32,
+                area.cy as i32,
+                SWP_SHOWWINDOW,
+            )?
+        };
+        Ok(())
+    }
+
+    fn monitor_info(monitor: HMONITOR) -> Result<MONITORINFO> {
+        let mut info = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        unsafe { GetMonitorInfoW(monitor, &mut info) }.ok()?;
+        Ok(info)
+    }
+
+    fn to_monitor(rect: RECT) -> Monitor {
+        Monitor {
+            x: rect.left,
+            y: rect.top,
+            cx: (rect.right - rect.left) as u32,
+            cy: (rect.bottom - rect.top) as u32,
+        }
+    }
+
     // ------------------------------------------------------------------------
     struct AppWindowParams {
         cfg: super::AppConfig,
@@ -71,6 +261,7 @@ mod win32 {
         clock: Clock,
         win32: Win32GlContext,
         input: input::Input,
+        keymap: Keymap,
         app_loop: AppLoop,
         app: App,
     }
@@ -99,16 +290,17 @@ mod win32 {
                 .map_err(Error::from)?
             };
 
-            let t_update = std::time::Duration::from_millis(10);
             let win32 = Win32GlContext::from_hwnd(hwnd)?;
-            let app_loop = AppLoop::new(t_update);
+            let app_loop = AppLoop::new(params.cfg.rates).with_catch_up(params.cfg.catch_up);
             let gl = win32.load()?;
+            let input = home_rs::app::create_input(&params.cfg)?;
             let app = App::new(params.cfg.clone(), gl, size.cx, size.cy)?;
 
             Ok(Self {
                 clock: Clock::new(),
                 win32,
-                input: input::Input::new(),
+                input,
+                keymap: params.cfg.keymap.clone(),
                 app_loop,
                 app,
             })
@@ -129,21 +321,23 @@ mod win32 {
         }
 
         fn on_loop(&mut self) -> LRESULT {
-            if let Err(e) = self
+            let win32 = &self.win32;
+            let step = self
                 .app_loop
-                .step(&mut self.app, &self.clock, &mut self.input)
-            {
-                eprintln!("Home loop exited with: {e:?}");
+                .step(&mut self.app, &self.clock, &mut self.input, || {
+                    win32.swap_buffers()
+                });
+            if let Err(e) = step {
+                super::fatal("Home loop exited with", &e);
+                unsafe { PostQuitMessage(0) };
+            } else if self.app.exit_requested() {
                 unsafe { PostQuitMessage(0) };
-                return LRESULT(0);
             }
-
-            self.win32.swap_buffers();
             LRESULT(0)
         }
 
         fn on_key_event(&mut self, msg: u32, vk: u32) -> LRESULT {
-            if let Some(key) = vk_to_key(vk) {
+            if let Some(key) = vk_to_name(vk).and_then(|name| self.keymap.key(name)) {
                 match msg {
                     WM_KEYDOWN => self.input.add_event(input::Event::KeyDown { key }),
                     WM_KEYUP => self.input.add_event(input::Event::KeyUp { key }),
@@ -212,7 +406,7 @@ mod win32 {
                     let kb = raw.data.keyboard;
                     let vk = kb.VKey as u32;
 
-                    if let Some(key) = vk_to_key(vk) {
+                    if let Some(key) = vk_to_name(vk).and_then(|name| self.keymap.key(name)) {
                         match kb.Message {
                             WM_KEYDOWN | WM_SYSKEYDOWN => {
                                 self.input.add_event(input::Event::KeyDown { key })
@@ -230,17 +424,32 @@ mod win32 {
     }
 
     // ------------------------------------------------------------------------
-    fn vk_to_key(vk: u32) -> Option<Key> {
+    fn vk_to_name(vk: u32) -> Option<&'static str> {
         const VK_ESCAPE: u32 = KeyboardAndMouse::VK_ESCAPE.0 as u32;
         const VK_LEFT: u32 = KeyboardAndMouse::VK_LEFT.0 as u32;
         const VK_RIGHT: u32 = KeyboardAndMouse::VK_RIGHT.0 as u32;
+        const VK_UP: u32 = KeyboardAndMouse::VK_UP.0 as u32;
+        const VK_DOWN: u32 = KeyboardAndMouse::VK_DOWN.0 as u32;
         const VK_HOME: u32 = KeyboardAndMouse::VK_HOME.0 as u32;
+        const VK_END: u32 = KeyboardAndMouse::VK_END.0 as u32;
+        const VK_SPACE: u32 = KeyboardAndMouse::VK_SPACE.0 as u32;
+        const VK_RETURN: u32 = KeyboardAndMouse::VK_RETURN.0 as u32;
+        const VK_F1: u32 = KeyboardAndMouse::VK_F1.0 as u32;
+        const VK_F12: u32 = KeyboardAndMouse::VK_F12.0 as u32;
 
         match vk {
-            VK_ESCAPE => Some(Key::Exit),
-            VK_LEFT => Some(Key::PrevScene),
-            VK_RIGHT => Some(Key::NextScene),
-            VK_HOME => Some(Key::Home),
+            VK_ESCAPE => Some("Escape"),
+            VK_LEFT => Some("Left"),
+            VK_RIGHT => Some("Right"),
+            VK_UP => Some("Up"),
+            VK_DOWN => Some("Down"),
+            VK_HOME => Some("Home"),
+            VK_END => Some("End"),
+            VK_SPACE => Some("Space"),
+            VK_RETURN => Some("Enter"),
+            VK_F1..=VK_F12 => Some(input::FUNCTION_KEYS[(vk - VK_F1) as usize]),
+            0x30..=0x39 => Some(input::DIGIT_KEYS[(vk - 0x30) as usize]),
+            0x41..=0x5a => Some(input::LETTER_KEYS[(vk - 0x41) as usize]),
             _ => None,
         }
     }
@@ -250,47 +459,61 @@ mod win32 {
 #[cfg(target_os = "linux")]
 #[allow(non_upper_case_globals)]
 mod linux {
-    use crate::app::App;
-    use crate::core::app_loop::AppLoop;
-    use crate::core::clock::Clock;
-    use crate::core::input::{self, Event, Key};
-    use crate::error::Result;
-    use crate::gl::linux::LinuxGLContext;
+    use home_rs::App;
+    use home_rs::core::app_loop::AppLoop;
+    use home_rs::core::clock::Clock;
+    use home_rs::core::display::Monitor;
+    use home_rs::core::input::{self, Event};
+    use home_rs::error::Result;
+    use home_rs::gl::linux::LinuxGLContext;
+    use x11::xinerama::{XineramaIsActive, XineramaQueryScreens};
     use x11::xlib::{
-        XCloseDisplay, XCreateSimpleWindow, XDefaultScreen, XDestroyWindow, XDisplayHeight,
-        XDisplayWidth, XEvent, XLookupKeysym, XMapWindow, XNextEvent, XOpenDisplay, XPending,
-        XRaiseWindow, XRootWindow, XSelectInput,
+        Atom, Display, False, PropModeReplace, USPosition, USSize, Window, XA_ATOM,
+        XAllocSizeHints, XChangeProperty, XCloseDisplay, XCreateSimpleWindow, XDefaultScreen,
+        XDestroyWindow, XDisplayHeight, XDisplayWidth, XEvent, XFree, XInternAtom, XLookupKeysym,
+        XMapWindow, XNextEvent, XOpenDisplay, XPending, XRaiseWindow, XRootWindow, XSelectInput,
+        XSetWMNormalHints,
     };
 
     pub fn main() -> Result<()> {
         let cfg = super::init()?;
+        if cfg.bench {
+            return super::bench(&cfg);
+        }
+        if cfg.headless.is_some() {
+            return super::headless(cfg);
+        }
+        let keymap = cfg.keymap.clone();
 
         let display = unsafe { XOpenDisplay(std::ptr::null()) };
         let screen = unsafe { XDefaultScreen(display) };
         let root = unsafe { XRootWindow(display, screen) };
 
-        let cx = unsafe { XDisplayWidth(display, screen) as u32 };
-        let cy = unsafe { XDisplayHeight(display, screen) as u32 };
-        let win = unsafe { XCreateSimpleWindow(display, root, 0, 0, cx, cy, 0, 0, 0) };
+        let area = cfg.display.placement(&unsafe { monitors(display, screen) });
+        let (cx, cy) = (area.cx, area.cy);
+        let win = unsafe { XCreateSimpleWindow(display, root, area.x, area.y, cx, cy, 0, 0, 0) };
 
         unsafe {
             XSelectInput(
                 display,
                 win,
-                x11::xlib::ExposureMask | x11::xlib::KeyPressMask,
+                x11::xlib::ExposureMask | x11::xlib::KeyPressMask | x11::xlib::StructureNotifyMask,
             );
+            set_placement(display, win, &area);
+            if cfg.display.fullscreen {
+                set_fullscreen(display, win);
+            }
             XMapWindow(display, win);
             XRaiseWindow(display, win);
         }
 
-        let context = LinuxGLContext::from_window(display, screen, win)?;
+        let context = unsafe { LinuxGLContext::from_window(display, screen, win)? };
         let gl = context.load()?;
         let clock = Clock::new();
 
-        let t_update = std::time::Duration::from_millis(10);
-        let mut app_loop = AppLoop::new(t_update);
+        let mut app_loop = AppLoop::new(cfg.rates).with_catch_up(cfg.catch_up);
+        let mut input = home_rs::app::create_input(&cfg)?;
         let mut app = App::new(cfg, gl, cx as i32, cy as i32)?;
-        let mut input = input::Input::new();
 
         loop {
             while unsafe { XPending(display) } > 0 {
@@ -298,10 +521,15 @@ mod linux {
                 unsafe { XNextEvent(display, &mut event) };
 
                 match unsafe { event.type_ } {
-                    x11::xlib::Expose => {}
+                    x11::xlib::Expose => app.request_redraw(),
+                    x11::xlib::ConfigureNotify => {
+                        let xconfigure = unsafe { event.configure };
+                        app.resize(xconfigure.width, xconfigure.height);
+                    }
                     x11::xlib::KeyPress => {
                         let keysym = unsafe { XLookupKeysym(&mut event.key as *mut _, 0) };
-                        if let Some(key) = xkey_to_key(keysym as u32) {
+                        let name = xkey_to_name(keysym as u32);
+                        if let Some(key) = name.and_then(|name| keymap.key(name)) {
                             input.add_event(Event::KeyDown { key });
                         }
                     }
@@ -309,50 +537,527 @@ mod linux {
                 }
             }
 
-            if let Err(e) = app_loop.step(&mut app, &clock, &mut input) {
-                eprintln!("Home loop exited with: {e:?}");
+            let step = app_loop.step(&mut app, &clock, &mut input, || context.swap_buffers());
+            if let Err(e) = &step {
+                super::fatal("Home loop exited with", e);
+            }
+            if step.is_err() || app.exit_requested() {
                 unsafe {
                     XDestroyWindow(display, win);
                     XCloseDisplay(display);
                 }
                 return Ok(());
             }
+        }
+    }
 
-            context.swap_buffers();
+    // ------------------------------------------------------------------------
+    // The monitors of the screen, Xinerama lists the primary one first.
+    // Without Xinerama the whole screen is one monitor.
+    unsafe fn monitors(display: *mut Display, screen: i32) -> Vec<Monitor> {
+        let mut monitors = Vec::new();
+        if unsafe { XineramaIsActive(display) } != 0 {
+            let mut count = 0;
+            let screens = unsafe { XineramaQueryScreens(display, &mut count) };
+            if !screens.is_null() {
+                let screens = unsafe { std::slice::from_raw_parts(screens, count as usize) };
+                monitors.extend(screens.iter().map(|info| Monitor {
+                    x: i32::from(info.x_org),
+                    y: i32::from(info.y_org),
+                    cx: info.width as u32,
+                    cy: info.height as u32,
+                }));
+                unsafe { XFree(screens.as_ptr() as *mut _) };
+            }
+        }
+        if monitors.is_empty() {
+            monitors.push(Monitor {
+                x: 0,
+                y: 0,
+                cx: unsafe { XDisplayWidth(display, screen) as u32 },
+                cy: unsafe { XDisplayHeight(display, screen) as u32 },
+            });
+        }
+        monitors
+    }
+
+    // ------------------------------------------------------------------------
+    // Window managers place new windows where they like unless the position
+    // was chosen by the user, fullscreen windows cover the monitor they are on
+    unsafe fn set_placement(display: *mut Display, win: Window, area: &Monitor) {
+        let hints = unsafe { XAllocSizeHints() };
+        if hints.is_null() {
+            return;
         }
+        unsafe {
+            (*hints).flags = USPosition | USSize;
+            (*hints).x = area.x;
+            (*hints).y = area.y;
+            (*hints).width = area.cx as i32;
+            (*hints).height = area.cy as i32;
+            XSetWMNormalHints(display, win, hints);
+            XFree(hints as *mut _);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Ask the window manager for a borderless fullscreen window (EWMH)
+    unsafe fn set_fullscreen(display: *mut Display, win: Window) {
+        let wm_state = unsafe { XInternAtom(display, c"_NET_WM_STATE".as_ptr(), False) };
+        let fullscreen =
+            unsafe { XInternAtom(display, c"_NET_WM_STATE_FULLSCREEN".as_ptr(), False) };
+        unsafe {
+            XChangeProperty(
+                display,
+                win,
+                wm_state,
+                XA_ATOM,
+                32,
+                PropModeReplace,
+                &fullscreen as *const Atom as *const u8,
+                1,
+            )
+        };
     }
 
-    fn xkey_to_key(keysym: u32) -> Option<Key> {
-        use x11::keysym::{XK_Escape, XK_Home, XK_Left, XK_Right};
+    fn xkey_to_name(keysym: u32) -> Option<&'static str> {
+        use x11::keysym::{
+            XK_0, XK_9, XK_Down, XK_End, XK_Escape, XK_F1, XK_F12, XK_Home, XK_Left, XK_Return,
+            XK_Right, XK_Up, XK_a, XK_space, XK_z,
+        };
         // X11 KeySym values fit in u32 despite XLookupKeysym returning u64
         match keysym {
-            XK_Escape => Some(Key::Exit),
-            XK_Home => Some(Key::Home),
-            XK_Left => Some(Key::PrevScene),
-            XK_Right => Some(Key::NextScene),
+            XK_Escape => Some("Escape"),
+            XK_Home => Some("Home"),
+            XK_End => Some("End"),
+            XK_Left => Some("Left"),
+            XK_Right => Some("Right"),
+            XK_Up => Some("Up"),
+            XK_Down => Some("Down"),
+            XK_space => Some("Space"),
+            XK_Return => Some("Enter"),
+            XK_F1..=XK_F12 => Some(input::FUNCTION_KEYS[(keysym - XK_F1) as usize]),
+            XK_0..=XK_9 => Some(input::DIGIT_KEYS[(keysym - XK_0) as usize]),
+            XK_a..=XK_z => Some(input::LETTER_KEYS[(keysym - XK_a) as usize]),
             _ => None,
         }
     }
 }
 
-use crate::app::AppConfig;
-use crate::error::{Error, Result};
-use crate::util::logger;
-use std::{env, path::PathBuf};
+use home_rs::AppConfig;
+use home_rs::bench;
+use home_rs::config;
+use home_rs::error::{Error, Result};
+use home_rs::gfx::sharpen::UnsharpMask;
+use home_rs::headless::{self, HeadlessConfig};
+use home_rs::ipc::Bus;
+use home_rs::library;
+use home_rs::scene::{
+    SceneKind, demo,
+    overlay::Corner,
+    photo::{Letterbox, MAX_RATING},
+};
+use home_rs::util::crash;
+use home_rs::util::locale::{LocaleId, TimePattern, Units};
+use home_rs::util::logger::{self, LogFilter};
+use std::path::{Path, PathBuf};
+use std::{env, str::FromStr, time::Duration};
 
 // ----------------------------------------------------------------------------
 fn init() -> Result<AppConfig> {
-    let _ = logger::init_logger(log::LevelFilter::Info);
+    let config = parse_args(env::args().skip(1));
+    let log_config = config.as_ref().map(|c| c.log.clone()).unwrap_or_default();
+    let _ = logger::init_logger(&log_config);
+    logger::install_panic_hook();
+    // reported here, `parse_args` runs before there is a logger
+    if let Ok(spec) = env::var("RUST_LOG")
+        && LogFilter::parse(&spec).is_err()
+    {
+        log::warn!("Ignoring the malformed RUST_LOG={spec:?}");
+    }
+    if let Ok(config) = &config {
+        crash::set_dir(&config.crash_dir);
+    }
+    config
+}
+
+// ----------------------------------------------------------------------------
+// `--headless WxH`, renders the frames to PNG files instead of a window
+fn headless(config: AppConfig) -> Result<()> {
+    for path in headless::run(config)? {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// `--bench`, prints the timings of the photo pipeline stages
+fn bench(config: &AppConfig) -> Result<()> {
+    print!("{}", bench::run(config)?);
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// Logs an error that ends the app and writes a crash report for it
+fn fatal(context: &str, e: &Error) {
+    log::error!("{context}: {e}");
+    if let Some(path) = crash::write_report(&format!("{context}: {e}")) {
+        log::error!("Crash report written to {path:?}");
+    }
+    log::logger().flush();
+}
 
+// ----------------------------------------------------------------------------
+const USAGE: &str = "\
+Usage: home-rs [OPTIONS]
+       home-rs ctl [--control-socket <path>] <REQUEST>
+       home-rs index <DIR>...
+       home-rs validate-meta <DIR>...
+
+Options:
+  --config <file>             Read settings from a TOML or .json file, flags
+                              given on the command line take precedence
+  --profile <name>            Use the [profiles.<name>] section of the config
+                              file on top of its other settings
+  --photo-dir <dir>           Photo directory, may be repeated
+                              [default: assets/photos/]
+  --demo                      Show generated sample photos instead of a library
+  --start-scene <scene>       Scene shown after start: all, today or the name of
+                              a scene plugin [default: all]
+  --min-rating <n>            Only show photos rated at least n of 5
+  --tag <tag>                 Only show photos with this tag, may be repeated
+  --locale <locale>           Date and number format: en-US, de-DE [default: en-US]
+  --time-format <12h|24h>     Override the clock format of the locale
+  --units <metric|imperial>   Weather units [default: metric]
+  --update-ms <ms>            Interval of the update loop [default: 10]
+  --max-fps <fps>             Cap of the rendered frames per second
+  --idle-update-ms <ms>       Interval of the update loop while nothing moves
+                              [default: 100]
+  --idle-max-fps <fps>        Frame cap while nothing moves [default: none]
+  --max-catch-up <n>          Updates per frame of a loop running late [default: 4]
+  --drop-lag-ms <ms>          Skip the time of longer stalls, 0 never skips
+                              [default: 1000]
+  --fullscreen                Cover the whole monitor instead of a window
+  --monitor <n>               Monitor to show the frame on, 0 is the primary one
+  --size <WxH>                Size of the window [default: 800x600]
+  --headless <WxH>            Render off-screen to PNG files and exit (Linux)
+  --frames <n>                Number of headless frames [default: 1]
+  --frame-ms <ms>             Time between headless frames [default: 40]
+  --output <dir>              Directory of the PNG files [default: headless]
+  --record-input <file>       Record the input events of the session
+  --replay-input <file>       Feed recorded input events to the updates
+  --bench                     Time decode, convert, upload and render of all
+                              photos and print percentiles (Linux)
+  --listen <addr:port>        Start the HTTP server, e.g. 0.0.0.0:8080
+  --api-token <token>         Token needed to change settings over HTTP, without
+                              one only the frame itself may change them
+  --name <name>               Name of this frame [default: Home Frame]
+  --no-advertise              Don't announce the HTTP server via mDNS
+  --dbus <session|system>     Register the org.home.Frame D-Bus service (Linux)
+  --control-socket <path>     Socket (named pipe on Windows) for `home-rs ctl`
+                              [default: $XDG_RUNTIME_DIR/home-frame.sock,
+                              \\\\.\\pipe\\home-frame-%USERNAME% on Windows]
+  --no-control-socket         Don't open the control socket
+  --debug-overlay             Show the debug overlay (toggle with F1)
+  --clock-overlay <corner>    Show the time and date on top of all scenes:
+                              top-left, top-right, bottom-left, bottom-right
+  --music <path>              Play a folder of WAV files or an .m3u playlist
+  --volume <percent>          Volume of the music [default: 50]
+  --light-sensor <path>       Dim the frame by an IIO light sensor (Linux), e.g.
+                              /sys/bus/iio/devices/iio:device0/in_illuminance_input
+  --presence <ip>             Turn the display off while the phone with this IP
+                              is away, repeatable
+  --letterbox <mode>          Fill the screen around photos: plain, blur or
+                              color [default: plain]
+  --sharpen <amount>          Sharpen downscaled photos, e.g. 0.6
+  --sharpen-radius <px>       Radius of the sharpening [default: 1.0]
+  --font <path>               MSDF atlas (.png/.json) or TrueType font (.ttf)
+  --text-px-range <texels>    Distance range of the font atlas [default: from the atlas]
+  --log-dir <dir>             Directory of the log files [default: log]
+  --crash-dir <dir>           Directory of the crash reports [default: crash]
+  --log-level <level>         Level of the log files [default: info]
+  --console-log-level <level> Level of the console output [default: warn]
+  --log-filter <spec>         Per module levels, e.g. warn,home_rs::scene=debug
+  --log-max-files <n>         Number of log files kept [default: 10]
+  --log-max-size <MB>         Rotate log files at this size [default: 4]
+  --log-max-age <hours>       Rotate log files at this age [default: 24]
+  -h, --help                  Print this help
+
+Levels: off, error, warn, info, debug, trace
+Requests: next, prev, pause, resume, scene <scene>, volume <percent>, mute,
+          unmute, rate <0-5>, tag <name>, untag <name>, status
+
+index writes index.json and thumbnails in .thumbs/ for the photos of each
+directory, validate-meta lists malformed or missing sidecar JSON files.
+";
+
+// ----------------------------------------------------------------------------
+fn parse_args(args: impl Iterator<Item = String>) -> Result<AppConfig> {
     let mut config = AppConfig::default();
-    let mut args = env::args().skip(1);
+    // a malformed RUST_LOG keeps the default filter, see `init`
+    if let Ok(spec) = env::var("RUST_LOG")
+        && let Ok(filter) = LogFilter::parse(&spec)
+    {
+        config.log.filter = filter;
+    }
+
+    // The config file is applied first so that command line flags override it
+    let args: Vec<String> = args.collect();
+    let value_of = |flag: &str| -> Result<Option<&String>> {
+        match args.iter().position(|arg| arg == flag) {
+            Some(i) => args.get(i + 1).map(Some).ok_or(Error::InvalidArgument {
+                arg: args[i].clone(),
+            }),
+            None => Ok(None),
+        }
+    };
+    let profile = value_of("--profile")?;
+    match value_of("--config")? {
+        Some(path) => {
+            let profile = profile.map(String::as_str);
+            config::load(Path::new(path))?.apply_profile(profile, &mut config)?;
+            config.config_file = Some(PathBuf::from(path));
+            config.profile = profile.map(String::from);
+        }
+        None if profile.is_some() => {
+            return Err(Error::InvalidArgument {
+                arg: String::from("--profile needs --config"),
+            });
+        }
+        None => {}
+    }
+
+    let mut photo_dirs = Vec::new();
+    let mut show_demo = false;
+    let mut headless_size = None;
+    let mut headless_frames = None;
+    let mut headless_frame_time = None;
+    let mut headless_output = None;
+    let mut args = args.into_iter();
     while let Some(arg) = args.next() {
-        #[allow(clippy::single_match)]
         match arg.as_str() {
+            "--config" | "--profile" => {
+                args.next();
+            }
             "--photo-dir" => {
                 if let Some(dir) = args.next() {
-                    config.photo_dir = PathBuf::from(dir);
+                    photo_dirs.push(PathBuf::from(dir));
+                }
+            }
+            "--start-scene" => {
+                let name = args.next().unwrap_or_default();
+                config.start_scene =
+                    SceneKind::from_name(&name).ok_or(Error::InvalidArgument { arg })?;
+            }
+            "--min-rating" => {
+                let rating: u8 = parse_value(&mut args, arg.clone())?;
+                if rating > MAX_RATING {
+                    return Err(Error::InvalidArgument { arg });
+                }
+                config.photo_filter.min_rating = Some(rating);
+            }
+            "--tag" => {
+                let tag = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.photo_filter.tags.push(tag);
+            }
+            "--locale" => {
+                let name = args.next().unwrap_or_default();
+                config.locale = LocaleId::from_name(&name).ok_or(Error::InvalidArgument { arg })?;
+            }
+            "--time-format" => {
+                let name = args.next().unwrap_or_default();
+                let time_format = TimePattern::from_name(&name);
+                config.time_format = Some(time_format.ok_or(Error::InvalidArgument { arg })?);
+            }
+            "--units" => {
+                let name = args.next().unwrap_or_default();
+                config.units = Units::from_name(&name).ok_or(Error::InvalidArgument { arg })?;
+            }
+            "--update-ms" => {
+                let ms: u64 = parse_value(&mut args, arg.clone())?;
+                if ms == 0 {
+                    return Err(Error::InvalidArgument { arg });
+                }
+                config.rates.active.update_interval = Duration::from_millis(ms);
+            }
+            "--max-fps" => {
+                config.rates.active.max_fps = Some(parse_fps(&mut args, arg)?);
+            }
+            "--idle-update-ms" => {
+                let ms: u64 = parse_value(&mut args, arg.clone())?;
+                if ms == 0 {
+                    return Err(Error::InvalidArgument { arg });
                 }
+                config.rates.idle.update_interval = Duration::from_millis(ms);
+            }
+            "--idle-max-fps" => {
+                config.rates.idle.max_fps = Some(parse_fps(&mut args, arg)?);
+            }
+            "--max-catch-up" => {
+                let max_updates: u32 = parse_value(&mut args, arg.clone())?;
+                if max_updates == 0 {
+                    return Err(Error::InvalidArgument { arg });
+                }
+                config.catch_up.max_updates = max_updates;
+            }
+            "--drop-lag-ms" => {
+                let ms: u64 = parse_value(&mut args, arg)?;
+                config.catch_up.drop_lag_after = (ms > 0).then(|| Duration::from_millis(ms));
+            }
+            "--fullscreen" => {
+                config.display.fullscreen = true;
+            }
+            "--monitor" => {
+                config.display.monitor = Some(parse_value(&mut args, arg)?);
+            }
+            "--size" => {
+                let size = args.next().unwrap_or_default();
+                config.display.size =
+                    config::parse_window_size(&size).ok_or(Error::InvalidArgument { arg })?;
+            }
+            "--demo" => {
+                show_demo = true;
+            }
+            "--record-input" => {
+                let path = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.record_input = Some(PathBuf::from(path));
+            }
+            "--replay-input" => {
+                let path = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.replay_input = Some(PathBuf::from(path));
+            }
+            "--bench" => {
+                config.bench = true;
+            }
+            "--headless" => {
+                let size = args.next().unwrap_or_default();
+                let (cx, cy) = headless::parse_size(&size).ok_or(Error::InvalidArgument { arg })?;
+                headless_size = Some((cx, cy));
+            }
+            "--frames" => {
+                let frames: usize = parse_value(&mut args, arg.clone())?;
+                if frames == 0 {
+                    return Err(Error::InvalidArgument { arg });
+                }
+                headless_frames = Some(frames);
+            }
+            "--frame-ms" => {
+                let ms = parse_value(&mut args, arg)?;
+                headless_frame_time = Some(Duration::from_millis(ms));
+            }
+            "--output" => {
+                let dir = args.next().ok_or(Error::InvalidArgument { arg })?;
+                headless_output = Some(PathBuf::from(dir));
+            }
+            "--listen" => {
+                config.listen = Some(parse_value(&mut args, arg)?);
+            }
+            "--api-token" => {
+                config.api_token = Some(args.next().ok_or(Error::InvalidArgument { arg })?);
+            }
+            "--name" => {
+                config.name = args.next().ok_or(Error::InvalidArgument { arg })?;
+            }
+            "--no-advertise" => {
+                config.advertise = false;
+            }
+            "--dbus" => {
+                let name = args.next().unwrap_or_default();
+                config.dbus = Some(Bus::from_name(&name).ok_or(Error::InvalidArgument { arg })?);
+            }
+            "--control-socket" => {
+                let path = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.control_socket = Some(PathBuf::from(path));
+            }
+            "--no-control-socket" => {
+                config.control_socket = None;
+            }
+            "--help" | "-h" => {
+                print!("{USAGE}");
+                std::process::exit(0);
+            }
+            "--debug-overlay" => {
+                config.debug_overlay = true;
+            }
+            "--clock-overlay" => {
+                let name = args.next().unwrap_or_default();
+                let position = Corner::from_name(&name).ok_or(Error::InvalidArgument { arg })?;
+                config.clock_overlay.get_or_insert_default().position = position;
+            }
+            "--music" => {
+                let path = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.music.path = Some(PathBuf::from(path));
+            }
+            "--volume" => {
+                let percent: u8 = parse_value(&mut args, arg.clone())?;
+                if percent > 100 {
+                    return Err(Error::InvalidArgument { arg });
+                }
+                config.music.volume = percent as f32 / 100.0;
+            }
+            "--light-sensor" => {
+                let path = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.ambient_light.sensor = Some(PathBuf::from(path));
+            }
+            "--presence" => {
+                let ip = parse_value(&mut args, arg)?;
+                config.presence.phones.push(ip);
+            }
+            "--letterbox" => {
+                let name = args.next().unwrap_or_default();
+                config.letterbox =
+                    Letterbox::from_name(&name).ok_or(Error::InvalidArgument { arg })?;
+            }
+            "--sharpen" => {
+                let amount = parse_value(&mut args, arg)?;
+                let usm = config.sharpen.get_or_insert_with(UnsharpMask::default);
+                usm.amount = amount;
+            }
+            "--sharpen-radius" => {
+                let radius = parse_value(&mut args, arg)?;
+                let usm = config.sharpen.get_or_insert_with(UnsharpMask::default);
+                usm.radius = radius;
+            }
+            "--font" => {
+                let path = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.font = PathBuf::from(path);
+            }
+            "--text-px-range" => {
+                let range: f32 = parse_value(&mut args, arg.clone())?;
+                if range <= 0.0 {
+                    return Err(Error::InvalidArgument { arg });
+                }
+                config.text_px_range = Some(range);
+            }
+            "--log-dir" => {
+                if let Some(dir) = args.next() {
+                    config.log.dir = PathBuf::from(dir);
+                }
+            }
+            "--crash-dir" => {
+                let dir = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.crash_dir = PathBuf::from(dir);
+            }
+            "--log-level" => {
+                config.log.file_level = parse_value(&mut args, arg)?;
+            }
+            "--console-log-level" => {
+                config.log.console_level = parse_value(&mut args, arg)?;
+            }
+            "--log-filter" => {
+                let spec = args.next().ok_or(Error::InvalidArgument { arg })?;
+                config.log.filter = LogFilter::parse(&spec)?;
+            }
+            "--log-max-files" => {
+                config.log.max_files = parse_value(&mut args, arg)?;
+            }
+            "--log-max-size" => {
+                let mb: u64 = parse_value(&mut args, arg)?;
+                config.log.max_file_size = mb * 1024 * 1024;
+            }
+            "--log-max-age" => {
+                let hours: u64 = parse_value(&mut args, arg)?;
+                config.log.max_file_age = Duration::from_secs(hours * 60 * 60);
             }
             _ => {
                 return Err(Error::InvalidArgument { arg });
@@ -360,5 +1065,42 @@ fn init() -> Result<AppConfig> {
         }
     }
 
+    if !photo_dirs.is_empty() {
+        config.photo_dirs = photo_dirs;
+    }
+    if show_demo {
+        config.photo_dirs = vec![PathBuf::from(demo::DEMO_DIR)];
+    }
+
+    // the headless flags may be given in any order
+    if let Some((cx, cy)) = headless_size {
+        let mut headless = HeadlessConfig::new(cx, cy);
+        headless.frames = headless_frames.unwrap_or(headless.frames);
+        headless.frame_time = headless_frame_time.unwrap_or(headless.frame_time);
+        headless.output = headless_output.unwrap_or(headless.output);
+        config.headless = Some(headless);
+    } else if headless_frames.is_some()
+        || headless_frame_time.is_some()
+        || headless_output.is_some()
+    {
+        return Err(Error::InvalidArgument {
+            arg: String::from("--frames, --frame-ms and --output need --headless"),
+        });
+    }
     Ok(config)
 }
+
+// ----------------------------------------------------------------------------
+fn parse_fps(args: &mut impl Iterator<Item = String>, arg: String) -> Result<f32> {
+    let fps: f32 = parse_value(args, arg.clone())?;
+    match fps > 0.0 {
+        true => Ok(fps),
+        false => Err(Error::InvalidArgument { arg }),
+    }
+}
+
+fn parse_value<T: FromStr>(args: &mut impl Iterator<Item = String>, arg: String) -> Result<T> {
+    args.next()
+        .and_then(|value| value.parse().ok())
+        .ok_or(Error::InvalidArgument { arg })
+}
@@ -0,0 +1,159 @@
+use crate::net::websocket;
+use crate::scene::FrameEvent;
+use std::io::Write;
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// Frames waiting for a client, clients falling further behind are dropped
+const QUEUE: usize = 16;
+
+// Clients that don't take a frame within this time are dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Frame = Arc<[u8]>;
+
+// ----------------------------------------------------------------------------
+// Fans frame events out to the connected WebSocket clients. The latest scene
+// and photo are replayed to new clients so they start with the current state.
+// Each client has its own writer thread, publishing never waits for them.
+#[derive(Default)]
+pub struct EventHub {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    clients: Vec<Client>,
+    scene: Option<Frame>,
+    photo: Option<Frame>,
+}
+
+struct Client {
+    stream: TcpStream,
+    queue: SyncSender<Frame>,
+    writer: JoinHandle<()>,
+}
+
+impl Client {
+    // ------------------------------------------------------------------------
+    // False if the client is gone or too slow, slow clients are disconnected
+    fn send(&self, frame: &Frame) -> bool {
+        match self.queue.try_send(Arc::clone(frame)) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                log::debug!("WebSocket client too slow, dropped");
+                let _ = self.stream.shutdown(Shutdown::Both);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+impl EventHub {
+    // ------------------------------------------------------------------------
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // ------------------------------------------------------------------------
+    // Serves the client on the calling thread until it closes the connection,
+    // answering its pings
+    pub fn add_client(&self, stream: TcpStream) {
+        let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+        let (Ok(mut reader), Ok(writer)) = (stream.try_clone(), stream.try_clone()) else {
+            return;
+        };
+        let (queue, frames) = mpsc::sync_channel(QUEUE);
+        let Ok(writer) = std::thread::Builder::new()
+            .name(String::from("ws-writer"))
+            .spawn(move || write_frames(writer, frames))
+        else {
+            return;
+        };
+        let client = Client {
+            stream,
+            queue,
+            writer,
+        };
+        let pongs = client.queue.clone();
+
+        {
+            let Ok(mut inner) = self.inner.lock() else {
+                return;
+            };
+            inner.clients.retain(|client| !client.writer.is_finished());
+            let state = [&inner.scene, &inner.photo];
+            for frame in state.into_iter().flatten() {
+                client.send(frame);
+            }
+            inner.clients.push(client);
+        }
+
+        loop {
+            match websocket::read_frame(&mut reader) {
+                Ok((websocket::OP_PING, payload)) => {
+                    let _ = pongs.try_send(websocket::pong_frame(&payload).into());
+                }
+                Ok((websocket::OP_CLOSE, _)) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+        // answer the close unless the writer is stuck
+        if pongs.try_send(websocket::close_frame().into()).is_err() {
+            let _ = reader.shutdown(Shutdown::Both);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn publish(&self, event: &FrameEvent) {
+        let Ok(json) = serde_json::to_string(event) else {
+            return;
+        };
+        let frame: Frame = websocket::text_frame(&json).into();
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        match event {
+            FrameEvent::SceneChanged { .. } => inner.scene = Some(Arc::clone(&frame)),
+            FrameEvent::PhotoChanged { .. } => inner.photo = Some(Arc::clone(&frame)),
+            FrameEvent::Error { .. } => {}
+        }
+        inner
+            .clients
+            .retain(|client| !client.writer.is_finished() && client.send(&frame));
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn client_count(&self) -> usize {
+        self.inner.lock().map_or(0, |mut inner| {
+            inner.clients.retain(|client| !client.writer.is_finished());
+            inner.clients.len()
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn close(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            let frame: Frame = websocket::close_frame().into();
+            for client in inner.clients.drain(..) {
+                client.send(&frame);
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Writes queued frames until the connection fails or is closed
+fn write_frames(mut stream: TcpStream, frames: Receiver<Frame>) {
+    for frame in frames {
+        if stream.write_all(&frame).is_err() || websocket::is_close(&frame) {
+            break;
+        }
+    }
+    // wakes up the reader of the connection
+    let _ = stream.shutdown(Shutdown::Both);
+}
@@ -0,0 +1,690 @@
+use crate::error::{Error, Result};
+use crate::net::http::Response;
+use crate::scene::SystemEvent;
+use crate::util::tasks::TaskSender;
+use std::collections::HashMap;
+use std::io::{BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const IO_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Pooled connections unused for longer are closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Retry delays double from MIN_BACKOFF up to MAX_BACKOFF
+const MIN_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
+// A single fetch gives up after this many failed attempts
+const MAX_ATTEMPTS: u32 = 5;
+
+// Consecutive network errors until the frame is considered offline
+const OFFLINE_AFTER: u32 = 3;
+
+const MAX_REDIRECTS: usize = 5;
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Url {
+    pub tls: bool,
+    pub host: String,
+    pub port: u16,
+    pub path: String, // with the query, always starts with '/'
+}
+
+impl Url {
+    // ------------------------------------------------------------------------
+    // http:// and https:// URLs without user info, the fragment is dropped
+    pub fn parse(url: &str) -> Result<Self> {
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else {
+            return Err(Error::InvalidUrl);
+        };
+
+        let (authority, path) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+        let path = path.split('#').next().unwrap_or_default();
+        let path = match path.starts_with('/') {
+            true => path.to_string(),
+            false => format!("/{path}"),
+        };
+        if authority.contains('@') || path.bytes().any(|b| b <= b' ' || b == 0x7f) {
+            return Err(Error::InvalidUrl);
+        }
+
+        let default_port = if tls { 443 } else { 80 };
+        let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+            // IPv6 literal, e.g. [::1]:8080
+            let (host, port) = rest.split_once(']').ok_or(Error::InvalidUrl)?;
+            (host, port.strip_prefix(':'))
+        } else {
+            match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| Error::InvalidUrl)?,
+            None => default_port,
+        };
+        if host.is_empty() || host.bytes().any(|b| b <= b' ' || b == b'/') {
+            return Err(Error::InvalidUrl);
+        }
+
+        Ok(Self {
+            tls,
+            host: host.to_ascii_lowercase(),
+            port,
+            path,
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    // Target of a redirect, absolute or relative to the host. Redirects from
+    // https:// to plain http:// are refused.
+    pub fn join(&self, location: &str) -> Result<Self> {
+        let url = if location.starts_with('/') && !location.starts_with("//") {
+            Url::parse(&format!("{}{location}", self.origin()))?
+        } else {
+            Url::parse(location)?
+        };
+        if self.tls && !url.tls {
+            log::warn!("Refusing the redirect from {self} to {url}");
+            return Err(Error::InvalidUrl);
+        }
+        Ok(url)
+    }
+
+    // ------------------------------------------------------------------------
+    fn origin(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{scheme}://{}", self.host_header())
+    }
+
+    // ------------------------------------------------------------------------
+    fn host_header(&self) -> String {
+        let host = match self.host.contains(':') {
+            true => format!("[{}]", self.host),
+            false => self.host.clone(),
+        };
+        match (self.tls, self.port) {
+            (false, 80) | (true, 443) => host,
+            (_, port) => format!("{host}:{port}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Url {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.origin(), self.path)
+    }
+}
+
+// ----------------------------------------------------------------------------
+enum Job {
+    Fetch {
+        key: String,
+        url: Url,
+        interval: Option<Duration>,
+    },
+    Cancel {
+        key: String,
+    },
+}
+
+// ----------------------------------------------------------------------------
+// Shared HTTP client for weather, feeds, calendars and remote photos. Requests
+// run on a background thread so the render loop never waits for the network,
+// results come back as `SystemEvent`s:
+//   Fetched      new or changed content, unchanged content is not repeated
+//   FetchFailed  after each failed attempt, retried with exponential backoff
+//   Offline      after several network errors in a row, Online once a server
+//                answers again and all failed requests are retried at once
+//
+// Each request is identified by a key chosen by the caller, e.g. "weather".
+// Connections are kept alive per host, ETag and Last-Modified are sent back
+// so that unchanged resources cost a 304 only.
+#[derive(Clone)]
+pub struct Fetcher {
+    jobs: Sender<Job>,
+}
+
+impl Fetcher {
+    // ------------------------------------------------------------------------
+    pub fn start(events: TaskSender) -> Result<Self> {
+        let (jobs, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(String::from("fetch"))
+            .spawn(move || Worker::new(events).run(&receiver))?;
+        Ok(Self { jobs })
+    }
+
+    // ------------------------------------------------------------------------
+    // Fetches `url` once, replaces a pending request with the same key
+    pub fn fetch(&self, key: &str, url: &str) -> Result<()> {
+        self.send(key, url, None)
+    }
+
+    // ------------------------------------------------------------------------
+    // Fetches `url` now and then every `interval`
+    pub fn poll(&self, key: &str, url: &str, interval: Duration) -> Result<()> {
+        self.send(key, url, Some(interval))
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn cancel(&self, key: &str) {
+        let key = key.to_string();
+        let _ = self.jobs.send(Job::Cancel { key });
+    }
+
+    // ------------------------------------------------------------------------
+    fn send(&self, key: &str, url: &str, interval: Option<Duration>) -> Result<()> {
+        let job = Job::Fetch {
+            key: key.to_string(),
+            url: Url::parse(url)?,
+            interval,
+        };
+        self.jobs.send(job).map_err(|_| Error::Ipc {
+            msg: String::from("fetch thread stopped"),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+struct Task {
+    key: String,
+    url: Url,
+    interval: Option<Duration>, // None for a single fetch
+    due: Instant,
+    failures: u32,
+    done: bool,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Option<Arc<[u8]>>, // last sent content
+}
+
+impl Task {
+    fn new(key: String, url: Url, interval: Option<Duration>) -> Self {
+        Self {
+            key,
+            url,
+            interval,
+            due: Instant::now(),
+            failures: 0,
+            done: false,
+            etag: None,
+            last_modified: None,
+            body: None,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn backoff(failures: u32) -> Duration {
+    let factor = 1 << failures.saturating_sub(1).min(16);
+    MIN_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+// ----------------------------------------------------------------------------
+struct Worker {
+    tasks: Vec<Task>,
+    client: Client,
+}
+
+impl Worker {
+    // ------------------------------------------------------------------------
+    fn new(events: TaskSender) -> Self {
+        Self {
+            tasks: Vec::new(),
+            client: Client::new(events),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Returns once all `Fetcher` handles are dropped
+    fn run(mut self, jobs: &Receiver<Job>) {
+        loop {
+            let due = self.tasks.iter().map(|t| t.due).min();
+            let job = match due {
+                Some(due) => jobs.recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => jobs.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match job {
+                Ok(job) => self.add(job),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            self.run_due(Instant::now());
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn add(&mut self, job: Job) {
+        match job {
+            Job::Fetch { key, url, interval } => {
+                self.tasks.retain(|t| t.key != key);
+                self.tasks.push(Task::new(key, url, interval));
+            }
+            Job::Cancel { key } => self.tasks.retain(|t| t.key != key),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn run_due(&mut self, now: Instant) {
+        self.client.close_idle(now);
+        for task in self.tasks.iter_mut().filter(|t| t.due <= now) {
+            self.client.run(task);
+        }
+        self.tasks.retain(|t| !t.done);
+
+        if std::mem::take(&mut self.client.reconnected) {
+            let now = Instant::now();
+            for task in self.tasks.iter_mut().filter(|t| t.failures > 0) {
+                task.due = now;
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+struct Connection {
+    reader: BufReader<Box<dyn Stream>>,
+    last_used: Instant,
+}
+
+type PoolKey = (String, u16, bool);
+
+// ----------------------------------------------------------------------------
+struct Client {
+    events: TaskSender,
+    pool: HashMap<PoolKey, Connection>,
+    network_errors: u32,
+    offline: bool,
+    reconnected: bool, // back online since the last `run_due`
+}
+
+impl Client {
+    // ------------------------------------------------------------------------
+    fn new(events: TaskSender) -> Self {
+        Self {
+            events,
+            pool: HashMap::new(),
+            network_errors: 0,
+            offline: false,
+            reconnected: false,
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn run(&mut self, task: &mut Task) {
+        let now = Instant::now();
+        let result = self.get(task).and_then(|response| match response.status {
+            200..=299 | 304 => Ok(response),
+            status => Err(Error::Http { status }),
+        });
+
+        match result {
+            Ok(response) => {
+                task.failures = 0;
+                if response.status != 304 {
+                    self.update(task, response);
+                }
+                match task.interval {
+                    Some(interval) => task.due = now + interval,
+                    None => task.done = true,
+                }
+            }
+            Err(e) => {
+                task.failures += 1;
+                log::warn!("Fetching {} failed ({}x): {e:?}", task.url, task.failures);
+                self.send(SystemEvent::FetchFailed {
+                    key: task.key.clone(),
+                    error: format!("{e:?}"),
+                });
+                if task.interval.is_none() && task.failures >= MAX_ATTEMPTS {
+                    task.done = true;
+                }
+                task.due = now + backoff(task.failures);
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn update(&mut self, task: &mut Task, response: Response) {
+        // the values are sent back in request headers
+        let header = |name| {
+            response
+                .header(name)
+                .filter(|v| !v.bytes().any(|b| b.is_ascii_control()))
+                .map(String::from)
+        };
+        task.etag = header("ETag");
+        task.last_modified = header("Last-Modified");
+
+        let body: Arc<[u8]> = response.body.into();
+        if task.body.as_ref() != Some(&body) {
+            task.body = Some(Arc::clone(&body));
+            self.send(SystemEvent::Fetched {
+                key: task.key.clone(),
+                body,
+            });
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn get(&mut self, task: &Task) -> Result<Response> {
+        let mut url = task.url.clone();
+        for _ in 0..MAX_REDIRECTS {
+            let response = self.request(&url, task)?;
+            match (response.status, response.header("Location")) {
+                (301 | 302 | 303 | 307 | 308, Some(location)) => url = url.join(location)?,
+                _ => return Ok(response),
+            }
+        }
+        Err(Error::Http { status: 310 })
+    }
+
+    // ------------------------------------------------------------------------
+    // A pooled connection may have been closed by the server in the meantime,
+    // then the request is repeated once on a new connection
+    fn request(&mut self, url: &Url, task: &Task) -> Result<Response> {
+        let mut request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: home-rs/{}\r\nAccept-Encoding: identity\r\n",
+            url.path,
+            url.host_header(),
+            env!("CARGO_PKG_VERSION")
+        );
+        if let Some(etag) = &task.etag {
+            request += &format!("If-None-Match: {etag}\r\n");
+        }
+        if let Some(last_modified) = &task.last_modified {
+            request += &format!("If-Modified-Since: {last_modified}\r\n");
+        }
+        request += "\r\n";
+
+        let key = (url.host.clone(), url.port, url.tls);
+        if let Some(connection) = self.pool.remove(&key) {
+            if let Ok(response) = self.exchange(key.clone(), connection, &request) {
+                return Ok(response);
+            }
+        }
+        let connection = match connect(url) {
+            Ok(stream) => Connection {
+                reader: BufReader::new(stream),
+                last_used: Instant::now(),
+            },
+            Err(e) => {
+                if matches!(e, Error::FileIo { .. }) {
+                    self.network_error();
+                }
+                return Err(e);
+            }
+        };
+        self.exchange(key, connection, &request)
+    }
+
+    // ------------------------------------------------------------------------
+    fn exchange(
+        &mut self,
+        key: PoolKey,
+        mut connection: Connection,
+        request: &str,
+    ) -> Result<Response> {
+        let stream = connection.reader.get_mut();
+        stream.write_all(request.as_bytes())?;
+        stream.flush()?;
+        let response = Response::read(&mut connection.reader, true)?;
+        self.online();
+        if response.keep_alive {
+            connection.last_used = Instant::now();
+            self.pool.insert(key, connection);
+        }
+        Ok(response)
+    }
+
+    // ------------------------------------------------------------------------
+    fn close_idle(&mut self, now: Instant) {
+        self.pool
+            .retain(|_, c| now.duration_since(c.last_used) < IDLE_TIMEOUT);
+    }
+
+    // ------------------------------------------------------------------------
+    fn network_error(&mut self) {
+        self.network_errors += 1;
+        if self.network_errors >= OFFLINE_AFTER && !self.offline {
+            log::warn!("Network is unreachable, frame is offline");
+            self.offline = true;
+            self.send(SystemEvent::Offline);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn online(&mut self) {
+        self.network_errors = 0;
+        if self.offline {
+            log::info!("Network is reachable again");
+            self.offline = false;
+            self.reconnected = true;
+            self.send(SystemEvent::Online);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn send(&self, event: SystemEvent) {
+        self.events.send(event);
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn connect(url: &Url) -> Result<Box<dyn Stream>> {
+    let mut last_error = None;
+    for addr in (url.host.as_str(), url.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(IO_TIMEOUT))?;
+                stream.set_write_timeout(Some(IO_TIMEOUT))?;
+                stream.set_nodelay(true)?;
+                return match url.tls {
+                    true => tls_connect(&url.host, stream),
+                    false => Ok(Box::new(stream)),
+                };
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| std::io::Error::from(std::io::ErrorKind::AddrNotAvailable))
+        .into())
+}
+
+// ----------------------------------------------------------------------------
+#[cfg(feature = "https")]
+fn tls_connect(host: &str, stream: TcpStream) -> Result<Box<dyn Stream>> {
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+    use std::sync::OnceLock;
+
+    static CONFIG: OnceLock<std::result::Result<Arc<ClientConfig>, rustls::Error>> =
+        OnceLock::new();
+    let tls_error = |e: rustls::Error| Error::Tls { msg: e.to_string() };
+
+    let config = CONFIG.get_or_init(|| {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+        };
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Ok(Arc::new(config))
+    });
+    let config = config.clone().map_err(tls_error)?;
+    let name = host.to_string().try_into().map_err(|_| Error::InvalidUrl)?;
+    let connection = ClientConnection::new(config, name).map_err(tls_error)?;
+    Ok(Box::new(StreamOwned::new(connection, stream)))
+}
+
+#[cfg(not(feature = "https"))]
+fn tls_connect(_host: &str, _stream: TcpStream) -> Result<Box<dyn Stream>> {
+    Err(Error::Tls {
+        msg: String::from("https:// needs the `https` feature"),
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Percent-encodes everything but unreserved characters, for query values
+pub fn encode_component(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for b in text.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded += &format!("%{b:02X}"),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::http::Request;
+    use crate::util::tasks;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_url() {
+        let url = Url::parse("https://API.example.com/v1/weather?q=Bern#now").unwrap();
+        assert!(url.tls);
+        assert_eq!(url.host, "api.example.com");
+        assert_eq!(url.port, 443);
+        assert_eq!(url.path, "/v1/weather?q=Bern");
+
+        let url = Url::parse("http://[::1]:8080?x=1").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("::1", 8080));
+        assert_eq!(url.to_string(), "http://[::1]:8080/?x=1");
+        assert_eq!(
+            url.join("/feed").unwrap().to_string(),
+            "http://[::1]:8080/feed"
+        );
+
+        let https = Url::parse("https://example.com/feed").unwrap();
+        assert!(https.join("/v2/feed").unwrap().tls);
+        assert!(https.join("https://cdn.example.com/feed").is_ok());
+        assert!(https.join("http://example.com/feed").is_err());
+
+        assert!(Url::parse("ftp://example.com/").is_err());
+        assert!(Url::parse("http://user@example.com/").is_err());
+        assert!(Url::parse("http://example.com/a b").is_err());
+        assert!(Url::parse("http://:80/").is_err());
+    }
+
+    #[test]
+    fn test_encode_component() {
+        assert_eq!(
+            encode_component("Königstein, DE"),
+            "K%C3%B6nigstein%2C%20DE"
+        );
+        assert_eq!(encode_component("a-b_c.d~"), "a-b_c.d~");
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), MIN_BACKOFF);
+        assert_eq!(backoff(3), MIN_BACKOFF * 4);
+        assert_eq!(backoff(100), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_etag_and_keep_alive() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            // all three requests arrive on the same connection
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut stream = stream;
+            let mut conditional = Vec::new();
+            for body in ["sunny", "sunny", "rain"] {
+                let request = Request::read(&mut reader).unwrap();
+                let etag = request.header("If-None-Match").map(String::from);
+                conditional.push(etag.clone());
+                if etag.as_deref() == Some(&format!("\"{body}\"")) {
+                    write!(stream, "HTTP/1.1 304 Not Modified\r\n\r\n").unwrap();
+                } else {
+                    write!(stream, "HTTP/1.1 200 OK\r\nETag: \"{body}\"\r\n").unwrap();
+                    write!(stream, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+                }
+            }
+            conditional
+        });
+
+        let (sender, events) = tasks::channel();
+        let mut worker = Worker::new(sender);
+        worker.add(Job::Fetch {
+            key: String::from("weather"),
+            url: Url::parse(&format!("http://127.0.0.1:{port}/weather")).unwrap(),
+            interval: Some(Duration::from_secs(60)),
+        });
+        for _ in 0..3 {
+            worker.tasks[0].due = Instant::now();
+            worker.run_due(Instant::now());
+        }
+        let conditional = server.join().unwrap();
+        let sunny = Some(String::from("\"sunny\""));
+        assert_eq!(conditional, [None, sunny.clone(), sunny]);
+
+        // the 304 sends no event
+        let bodies: Vec<_> = events
+            .try_iter()
+            .map(|e| match e {
+                SystemEvent::Fetched { key, body } => (key, body.to_vec()),
+                e => panic!("unexpected {e:?}"),
+            })
+            .collect();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[1], (String::from("weather"), b"rain".to_vec()));
+    }
+
+    #[test]
+    fn test_offline() {
+        // nothing listens on the port once the listener is dropped
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .unwrap()
+            .port();
+        let (sender, events) = tasks::channel();
+        let mut worker = Worker::new(sender);
+        worker.add(Job::Fetch {
+            key: String::from("feed"),
+            url: Url::parse(&format!("http://127.0.0.1:{port}/")).unwrap(),
+            interval: None,
+        });
+        for _ in 0..MAX_ATTEMPTS {
+            worker.tasks[0].due = Instant::now();
+            worker.run_due(Instant::now());
+        }
+        assert!(worker.tasks.is_empty());
+
+        let events: Vec<_> = events.try_iter().collect();
+        let failed = events
+            .iter()
+            .filter(|e| matches!(e, SystemEvent::FetchFailed { key, .. } if key == "feed"))
+            .count();
+        assert_eq!(failed, MAX_ATTEMPTS as usize);
+        assert!(matches!(
+            events[OFFLINE_AFTER as usize - 1],
+            SystemEvent::Offline
+        ));
+    }
+}
@@ -0,0 +1,267 @@
+use crate::error::{Error, Result};
+use std::io::{BufRead, Read, Write};
+
+// Limits for requests from the LAN, everything larger is rejected
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+const MAX_BODY: usize = 1024 * 1024;
+
+// Responses of remote servers may be larger, e.g. photos
+const MAX_RESPONSE_BODY: usize = 32 * 1024 * 1024;
+
+// ----------------------------------------------------------------------------
+// Minimal HTTP/1.1 request, enough for the control endpoints
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    // ------------------------------------------------------------------------
+    pub fn read(reader: &mut impl BufRead) -> Result<Self> {
+        let line = read_line(reader)?;
+        let mut parts = line.split(' ');
+        let (Some(method), Some(path), Some(version)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(Error::InvalidRequest);
+        };
+        if !version.starts_with("HTTP/1.") {
+            return Err(Error::InvalidRequest);
+        }
+
+        let headers = read_headers(reader)?;
+        let mut request = Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers,
+            body: Vec::new(),
+        };
+
+        if let Some(len) = request.header("Content-Length") {
+            let len: usize = len.parse().map_err(|_| Error::InvalidRequest)?;
+            if len > MAX_BODY {
+                return Err(Error::InvalidRequest);
+            }
+            request.body = vec![0; len];
+            reader.read_exact(&mut request.body)?;
+        }
+        Ok(request)
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        has_token(&self.headers, name, token)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Response of a remote server, read by the fetcher
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub keep_alive: bool, // the connection can be used for the next request
+}
+
+impl Response {
+    // ------------------------------------------------------------------------
+    // `has_body` is false for responses to HEAD requests
+    pub fn read(reader: &mut impl BufRead, has_body: bool) -> Result<Self> {
+        let line = read_line(reader).map_err(|_| Error::InvalidResponse)?;
+        let mut parts = line.splitn(3, ' ');
+        let (Some(version), Some(status)) = (parts.next(), parts.next()) else {
+            return Err(Error::InvalidResponse);
+        };
+        if !version.starts_with("HTTP/1.") {
+            return Err(Error::InvalidResponse);
+        }
+        let status: u16 = status.parse().map_err(|_| Error::InvalidResponse)?;
+        let headers = read_headers(reader).map_err(|_| Error::InvalidResponse)?;
+
+        let mut response = Response {
+            status,
+            keep_alive: version != "HTTP/1.0" && !has_token(&headers, "Connection", "close"),
+            headers,
+            body: Vec::new(),
+        };
+
+        // 1xx, 204 and 304 never have a body
+        if !has_body || status < 200 || status == 204 || status == 304 {
+            return Ok(response);
+        }
+        if response.has_token("Transfer-Encoding", "chunked") {
+            response.body = read_chunked(reader)?;
+        } else if let Some(len) = response.header("Content-Length") {
+            let len: usize = len.parse().map_err(|_| Error::InvalidResponse)?;
+            if len > MAX_RESPONSE_BODY {
+                return Err(Error::InvalidResponse);
+            }
+            response.body = vec![0; len];
+            reader.read_exact(&mut response.body)?;
+        } else {
+            // the body ends with the connection
+            Read::take(&mut *reader, MAX_RESPONSE_BODY as u64 + 1)
+                .read_to_end(&mut response.body)?;
+            if response.body.len() > MAX_RESPONSE_BODY {
+                return Err(Error::InvalidResponse);
+            }
+            response.keep_alive = false;
+        }
+        Ok(response)
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        has_token(&self.headers, name, token)
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn read_headers(reader: &mut impl BufRead) -> Result<Vec<(String, String)>> {
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            return Ok(headers);
+        }
+        if headers.len() >= MAX_HEADERS {
+            return Err(Error::InvalidRequest);
+        }
+        let (name, value) = line.split_once(':').ok_or(Error::InvalidRequest)?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Header names are case-insensitive
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+// ----------------------------------------------------------------------------
+// True if a comma separated header contains `token`, e.g.
+// "Connection: keep-alive, Upgrade"
+fn has_token(headers: &[(String, String)], name: &str, token: &str) -> bool {
+    find_header(headers, name)
+        .is_some_and(|v| v.split(',').any(|t| t.trim().eq_ignore_ascii_case(token)))
+}
+
+// ----------------------------------------------------------------------------
+// "Transfer-Encoding: chunked", chunk extensions and trailers are ignored
+fn read_chunked(reader: &mut impl BufRead) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader).map_err(|_| Error::InvalidResponse)?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| Error::InvalidResponse)?;
+        if size == 0 {
+            read_headers(reader).map_err(|_| Error::InvalidResponse)?;
+            return Ok(body);
+        }
+        if body.len() + size > MAX_RESPONSE_BODY {
+            return Err(Error::InvalidResponse);
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        if !read_line(reader).is_ok_and(|line| line.is_empty()) {
+            return Err(Error::InvalidResponse);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = Vec::new();
+    Read::take(&mut *reader, MAX_LINE as u64).read_until(b'\n', &mut line)?;
+    if line.last() != Some(&b'\n') {
+        return Err(Error::InvalidRequest);
+    }
+    let line = String::from_utf8(line).map_err(|_| Error::InvalidRequest)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+// ----------------------------------------------------------------------------
+// Writes a complete response and asks the client to close the connection
+pub fn write_response(
+    stream: &mut impl Write,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_request() {
+        let raw = b"POST /settings HTTP/1.1\r\nHost: frame\r\ncontent-length: 5\r\nConnection: keep-alive, Upgrade\r\n\r\nhello";
+        let req = Request::read(&mut &raw[..]).unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/settings");
+        assert_eq!(req.header("Host"), Some("frame"));
+        assert!(req.has_token("connection", "upgrade"));
+        assert_eq!(req.body, b"hello");
+
+        assert!(Request::read(&mut &b"GET /\r\n\r\n"[..]).is_err());
+        assert!(Request::read(&mut &b"GET / HTTP/1.1\r\nHost"[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_response() {
+        let raw = b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n1\r\n!\r\n0\r\n\r\n";
+        let res = Response::read(&mut &raw[..], true).unwrap();
+        assert_eq!(res.status, 200);
+        assert_eq!(res.header("etag"), Some("\"v1\""));
+        assert_eq!(res.body, b"hello!");
+        assert!(res.keep_alive);
+
+        let raw = b"HTTP/1.0 200 OK\r\n\r\nuntil closed";
+        let res = Response::read(&mut &raw[..], true).unwrap();
+        assert_eq!(res.body, b"until closed");
+        assert!(!res.keep_alive);
+
+        let raw = b"HTTP/1.1 304 Not Modified\r\nContent-Length: 10\r\n\r\n";
+        let res = Response::read(&mut &raw[..], true).unwrap();
+        assert!(res.body.is_empty());
+
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel";
+        assert!(Response::read(&mut &raw[..], true).is_err());
+    }
+
+    #[test]
+    fn test_write_response() {
+        let mut out = Vec::new();
+        write_response(&mut out, "404 Not Found", "text/plain", b"nope").unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(out.contains("Content-Length: 4\r\n"));
+        assert!(out.ends_with("\r\n\r\nnope"));
+    }
+}
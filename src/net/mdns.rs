@@ -0,0 +1,374 @@
+use crate::error::{Error, Result};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// ----------------------------------------------------------------------------
+// Minimal mDNS/DNS-SD responder (RFC 6762/6763) announcing the HTTP server as
+// `_home-frame._tcp.local` so companion apps find frames without typing IPs.
+// Names are not probed for conflicts, give every frame its own name.
+pub const SERVICE_TYPE: &str = "_home-frame._tcp.local";
+const SERVICES: &str = "_services._dns-sd._udp.local";
+
+const MDNS_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+const TTL: u32 = 120;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const TYPE_ANY: u16 = 255;
+
+const CLASS_IN: u16 = 1;
+const CACHE_FLUSH: u16 = 0x8000;
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServiceInfo {
+    pub instance: String, // e.g. "Living Room"
+    pub host: String,     // e.g. "living-room-0a2b.local"
+    pub addr: Ipv4Addr,
+    pub port: u16,
+    pub txt: Vec<String>, // "key=value" pairs
+}
+
+impl ServiceInfo {
+    // ------------------------------------------------------------------------
+    pub fn new(name: &str, addr: Ipv4Addr, port: u16) -> Self {
+        let slug: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let [.., a, b] = addr.octets();
+        Self {
+            // dots would split the instance label
+            instance: name.replace('.', " "),
+            host: format!("{}-{a:02x}{b:02x}.local", slug.trim_matches('-')),
+            addr,
+            port,
+            txt: vec![
+                format!("version={}", env!("CARGO_PKG_VERSION")),
+                String::from("events=/events"),
+            ],
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn instance_name(&self) -> String {
+        format!("{}.{SERVICE_TYPE}", self.instance)
+    }
+
+    // ------------------------------------------------------------------------
+    // True if any question of the query is about this service
+    fn matches(&self, questions: &[Question]) -> bool {
+        let instance = self.instance_name();
+        questions.iter().any(|q| {
+            let is = |name: &str, qtype: &[u16]| {
+                q.name.eq_ignore_ascii_case(name)
+                    && (q.qtype == TYPE_ANY || qtype.contains(&q.qtype))
+            };
+            is(SERVICE_TYPE, &[TYPE_PTR])
+                || is(SERVICES, &[TYPE_PTR])
+                || is(&instance, &[TYPE_SRV, TYPE_TXT])
+                || is(&self.host, &[TYPE_A])
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    // Full answer with all records, a `ttl` of 0 withdraws them (goodbye)
+    fn response(&self, id: u16, ttl: u32) -> Vec<u8> {
+        let instance = self.instance_name();
+
+        let mut srv = Vec::new();
+        srv.extend_from_slice(&[0, 0, 0, 0]); // priority, weight
+        srv.extend_from_slice(&self.port.to_be_bytes());
+        write_name(&mut srv, &self.host);
+
+        let mut txt = Vec::new();
+        for entry in &self.txt {
+            let entry = &entry.as_bytes()[..entry.len().min(255)];
+            txt.push(entry.len() as u8);
+            txt.extend_from_slice(entry);
+        }
+
+        let mut ptr = Vec::new();
+        write_name(&mut ptr, &instance);
+        let mut service = Vec::new();
+        write_name(&mut service, SERVICE_TYPE);
+
+        let records = [
+            (SERVICE_TYPE, TYPE_PTR, CLASS_IN, ptr),
+            (SERVICES, TYPE_PTR, CLASS_IN, service),
+            (&instance, TYPE_SRV, CLASS_IN | CACHE_FLUSH, srv),
+            (&instance, TYPE_TXT, CLASS_IN | CACHE_FLUSH, txt),
+            (
+                &self.host,
+                TYPE_A,
+                CLASS_IN | CACHE_FLUSH,
+                self.addr.octets().to_vec(),
+            ),
+        ];
+
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&id.to_be_bytes());
+        msg.extend_from_slice(&0x8400u16.to_be_bytes()); // response, authoritative
+        msg.extend_from_slice(&[0, 0]); // questions
+        msg.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        msg.extend_from_slice(&[0, 0, 0, 0]); // authority, additional
+        for (name, rtype, class, rdata) in records {
+            write_name(&mut msg, name);
+            msg.extend_from_slice(&rtype.to_be_bytes());
+            msg.extend_from_slice(&class.to_be_bytes());
+            msg.extend_from_slice(&ttl.to_be_bytes());
+            msg.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            msg.extend_from_slice(&rdata);
+        }
+        msg
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+struct Question {
+    name: String,
+    qtype: u16,
+}
+
+// ----------------------------------------------------------------------------
+// Questions of a query, responses from other hosts are ignored
+fn parse_query(msg: &[u8]) -> Result<(u16, Vec<Question>)> {
+    let u16_at = |pos: usize| -> Result<u16> {
+        let b = msg.get(pos..pos + 2).ok_or(Error::InvalidRequest)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    };
+
+    let id = u16_at(0)?;
+    let flags = u16_at(2)?;
+    if flags & 0x8000 != 0 {
+        return Ok((id, Vec::new()));
+    }
+
+    let count = u16_at(4)?;
+    let mut pos = 12;
+    let mut questions = Vec::new();
+    for _ in 0..count {
+        let (name, end) = read_name(msg, pos)?;
+        questions.push(Question {
+            name,
+            qtype: u16_at(end)?,
+        });
+        pos = end + 4;
+    }
+    Ok((id, questions))
+}
+
+// ----------------------------------------------------------------------------
+// Returns the dotted name and the position after it, follows compression
+// pointers
+fn read_name(msg: &[u8], mut pos: usize) -> Result<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    for _ in 0..64 {
+        let len = *msg.get(pos).ok_or(Error::InvalidRequest)? as usize;
+        match len {
+            0 => {
+                let name = labels.join(".");
+                return Ok((name, end.unwrap_or(pos + 1)));
+            }
+            0xc0.. => {
+                let low = *msg.get(pos + 1).ok_or(Error::InvalidRequest)? as usize;
+                end.get_or_insert(pos + 2);
+                pos = ((len & 0x3f) << 8) | low;
+            }
+            1..64 => {
+                let label = msg
+                    .get(pos + 1..pos + 1 + len)
+                    .ok_or(Error::InvalidRequest)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + len;
+            }
+            _ => return Err(Error::InvalidRequest),
+        }
+    }
+    Err(Error::InvalidRequest)
+}
+
+// ----------------------------------------------------------------------------
+// Uncompressed, labels are cut at 63 bytes
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    for label in name.split('.').filter(|l| !l.is_empty()) {
+        let label = &label.as_bytes()[..label.len().min(63)];
+        out.push(label.len() as u8);
+        out.extend_from_slice(label);
+    }
+    out.push(0);
+}
+
+// ----------------------------------------------------------------------------
+// Address of the interface used for multicast, nothing is sent
+pub fn local_ipv4() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((MDNS_ADDR, MDNS_PORT)).ok()?;
+    match socket.local_addr().ok()? {
+        SocketAddr::V4(addr) if !addr.ip().is_unspecified() => Some(*addr.ip()),
+        _ => None,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Answers queries on a background thread until dropped
+pub struct Advertiser {
+    stop: Arc<AtomicBool>,
+    socket: UdpSocket,
+    info: ServiceInfo,
+}
+
+impl Advertiser {
+    // ------------------------------------------------------------------------
+    pub fn start(info: ServiceInfo) -> Result<Self> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        // share the port with other responders, e.g. avahi
+        socket.set_reuse_address(true)?;
+        #[cfg(unix)]
+        socket.set_reuse_port(true)?;
+        let any = SocketAddr::from((Ipv4Addr::UNSPECIFIED, MDNS_PORT));
+        socket.bind(&any.into())?;
+        let socket: UdpSocket = socket.into();
+        socket.join_multicast_v4(&MDNS_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+        socket.set_multicast_ttl_v4(255)?;
+        socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+        log::info!(
+            "Advertising {:?} on {}:{} via mDNS",
+            info.instance,
+            info.host,
+            info.port
+        );
+        let group = SocketAddrV4::new(MDNS_ADDR, MDNS_PORT);
+        socket.send_to(&info.response(0, TTL), group)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread_socket = socket.try_clone()?;
+        let thread_info = info.clone();
+        std::thread::Builder::new()
+            .name(String::from("mdns"))
+            .spawn(move || respond(&thread_socket, &thread_info, &thread_stop))?;
+
+        Ok(Self { stop, socket, info })
+    }
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let group = SocketAddrV4::new(MDNS_ADDR, MDNS_PORT);
+        let _ = self.socket.send_to(&self.info.response(0, 0), group);
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn respond(socket: &UdpSocket, info: &ServiceInfo, stop: &AtomicBool) {
+    let mut buf = [0; 1500];
+    while !stop.load(Ordering::Relaxed) {
+        let Ok((len, from)) = socket.recv_from(&mut buf) else {
+            continue;
+        };
+        let Ok((id, questions)) = parse_query(&buf[..len]) else {
+            continue;
+        };
+        if !info.matches(&questions) {
+            continue;
+        }
+
+        // queries from other ports are one-shot (legacy unicast) and expect a
+        // direct answer with the query id
+        let result = if from.port() == MDNS_PORT {
+            socket.send_to(
+                &info.response(0, TTL),
+                SocketAddrV4::new(MDNS_ADDR, MDNS_PORT),
+            )
+        } else {
+            socket.send_to(&info.response(id, TTL), from)
+        };
+        if let Err(e) = result {
+            log::debug!("mDNS: failed to answer {from}: {e:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut msg = vec![0x12, 0x34, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+        write_name(&mut msg, name);
+        msg.extend_from_slice(&qtype.to_be_bytes());
+        msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+        msg
+    }
+
+    #[test]
+    fn test_service_info() {
+        let info = ServiceInfo::new("Living Room!", Ipv4Addr::new(192, 168, 10, 43), 8080);
+        assert_eq!(info.host, "living-room-0a2b.local");
+        assert_eq!(info.instance_name(), "Living Room!._home-frame._tcp.local");
+    }
+
+    #[test]
+    fn test_query() {
+        let info = ServiceInfo::new("Frame", Ipv4Addr::new(10, 0, 0, 2), 8080);
+
+        let (id, questions) = parse_query(&query(SERVICE_TYPE, TYPE_PTR)).unwrap();
+        assert_eq!(id, 0x1234);
+        assert_eq!(questions[0].name, SERVICE_TYPE);
+        assert!(info.matches(&questions));
+
+        let (_, questions) = parse_query(&query("_http._tcp.local", TYPE_PTR)).unwrap();
+        assert!(!info.matches(&questions));
+        let (_, questions) = parse_query(&query("frame-0002.local", TYPE_A)).unwrap();
+        assert!(info.matches(&questions));
+
+        // compressed second question pointing into the first one
+        let mut msg = query(SERVICE_TYPE, TYPE_PTR);
+        msg[5] = 2;
+        msg.extend_from_slice(&[0xc0, 12, 0, TYPE_SRV as u8, 0, 1]);
+        let (_, questions) = parse_query(&msg).unwrap();
+        assert_eq!(questions[1].name, SERVICE_TYPE);
+        assert_eq!(questions[1].qtype, TYPE_SRV);
+
+        // pointer loops and truncated messages are rejected
+        let mut msg = vec![0; 12];
+        msg[5] = 1;
+        msg.extend_from_slice(&[0xc0, 12]);
+        assert!(parse_query(&msg).is_err());
+        assert!(parse_query(&[0, 1]).is_err());
+    }
+
+    #[test]
+    fn test_response() {
+        let info = ServiceInfo::new("Frame", Ipv4Addr::new(10, 0, 0, 2), 8080);
+        let msg = info.response(7, TTL);
+        assert_eq!(msg[..4], [0, 7, 0x84, 0]);
+        assert_eq!(msg[6..8], [0, 5]);
+
+        // the first answer is the PTR to the instance
+        let (name, pos) = read_name(&msg, 12).unwrap();
+        assert_eq!(name, SERVICE_TYPE);
+        let (target, _) = read_name(&msg, pos + 10).unwrap();
+        assert_eq!(target, "Frame._home-frame._tcp.local");
+
+        // the A record comes last
+        assert_eq!(msg[msg.len() - 4..], [10, 0, 0, 2]);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod events;
+pub mod fetch;
+pub mod http;
+pub mod mdns;
+pub mod presence;
+pub mod server;
+pub mod settings;
+pub mod websocket;
@@ -0,0 +1,311 @@
+use crate::error::{Error, Result};
+use crate::scene::SystemEvent;
+use crate::util::tasks::TaskSender;
+use serde::Deserialize;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{IpAddr, SocketAddr, TcpStream, UdpSocket};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// Waiting time for the answer of a phone
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Without permission for ICMP sockets phones are probed by a TCP connect to
+// the lockdown port of iOS, other phones answer with a reset
+const PROBE_PORT: u16 = 62078;
+
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ICMP_ECHO_REPLY: u8 = 0;
+
+// ----------------------------------------------------------------------------
+// Phones of the household, pinged by IP or looked up by the MAC address of
+// their Bluetooth adapter. Phones in standby skip pings now and then, so
+// nobody is home only after `away_after` seconds without any answer. The
+// display commands run when the last phone leaves and the first returns.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PresenceConfig {
+    pub phones: Vec<IpAddr>,
+    pub bluetooth: Vec<String>, // e.g. "a4:c3:f0:85:ac:2d"
+    pub interval: u64,          // seconds between checks
+    pub away_after: u64,        // seconds
+    pub display_off: Vec<String>,
+    pub display_on: Vec<String>,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        let (off, on) = default_display_commands();
+        Self {
+            phones: Vec::new(),
+            bluetooth: Vec::new(),
+            interval: 30,
+            away_after: 900,
+            display_off: off.iter().map(|s| s.to_string()).collect(),
+            display_on: on.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl PresenceConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.phones.is_empty() || !self.bluetooth.is_empty()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn default_display_commands() -> (&'static [&'static str], &'static [&'static str]) {
+    (
+        &["xset", "dpms", "force", "off"],
+        &["xset", "dpms", "force", "on"],
+    )
+}
+
+// the frame shows black only, the monitor goes to standby by its own timeout
+#[cfg(not(target_os = "linux"))]
+fn default_display_commands() -> (&'static [&'static str], &'static [&'static str]) {
+    (&[], &[])
+}
+
+// ----------------------------------------------------------------------------
+// Somebody is present until nobody was seen for `away_after`, presence
+// returns with the first answer
+#[derive(Clone, Copy, Debug)]
+pub struct Presence {
+    away_after: Duration,
+    last_seen: Instant,
+    present: bool,
+}
+
+impl Presence {
+    pub fn new(away_after: Duration, now: Instant) -> Self {
+        Self {
+            away_after,
+            last_seen: now,
+            present: true,
+        }
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.present
+    }
+
+    // ------------------------------------------------------------------------
+    // Returns the new state when it changed
+    pub fn update(&mut self, seen: bool, now: Instant) -> Option<bool> {
+        if seen {
+            self.last_seen = now;
+        }
+        let present = seen || now.duration_since(self.last_seen) < self.away_after;
+        if present == self.present {
+            return None;
+        }
+        self.present = present;
+        Some(present)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Checks the phones on a background thread and switches the display, changes
+// are sent as `SystemEvent::Presence`
+pub struct PresenceDetector {
+    stop: Arc<AtomicBool>,
+}
+
+impl PresenceDetector {
+    pub fn start(config: &PresenceConfig, events: TaskSender) -> Result<Self> {
+        if !config.is_enabled() {
+            return Err(Error::InvalidArgument {
+                arg: String::from("presence phones"),
+            });
+        }
+        log::info!(
+            "Checking presence of {} phone(s) every {} s",
+            config.phones.len() + config.bluetooth.len(),
+            config.interval
+        );
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread_config = config.clone();
+        std::thread::Builder::new()
+            .name(String::from("presence"))
+            .spawn(move || detect(&thread_config, &events, &thread_stop))?;
+        Ok(Self { stop })
+    }
+}
+
+impl Drop for PresenceDetector {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn detect(config: &PresenceConfig, events: &TaskSender, stop: &AtomicBool) {
+    let mut presence = Presence::new(Duration::from_secs(config.away_after), Instant::now());
+    let mut seq = 0u16;
+    while !stop.load(Ordering::Relaxed) {
+        seq = seq.wrapping_add(1);
+        let seen = config.phones.iter().any(|&ip| probe(ip, seq))
+            || config.bluetooth.iter().any(|mac| bluetooth_nearby(mac));
+
+        if let Some(present) = presence.update(seen, Instant::now()) {
+            let (message, command) = match present {
+                true => ("Somebody came home", &config.display_on),
+                false => ("Nobody is home", &config.display_off),
+            };
+            log::info!("{message}");
+            if let Err(e) = run(command) {
+                log::warn!("Failed to switch the display: {e:?}");
+            }
+            if !events.send(SystemEvent::Presence { present }) {
+                break;
+            }
+        }
+
+        // seconds-wise to notice the stop flag
+        for _ in 0..config.interval.max(1) {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn probe(ip: IpAddr, seq: u16) -> bool {
+    match ping(ip, seq) {
+        Ok(answered) => answered,
+        Err(_) => connect(ip),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// ICMP echo via an unprivileged datagram socket, Linux allows them for the
+// groups in net.ipv4.ping_group_range
+fn ping(ip: IpAddr, seq: u16) -> Result<bool> {
+    let IpAddr::V4(_) = ip else {
+        return Err(Error::InvalidArgument {
+            arg: String::from("ping IPv6"),
+        });
+    };
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::ICMPV4))?;
+    let socket: UdpSocket = socket.into();
+    socket.set_read_timeout(Some(PROBE_TIMEOUT))?;
+    socket.send_to(&echo_request(seq), SocketAddr::new(ip, 0))?;
+
+    let deadline = Instant::now() + PROBE_TIMEOUT;
+    let mut buf = [0; 64];
+    while Instant::now() < deadline {
+        let Ok((len, from)) = socket.recv_from(&mut buf) else {
+            return Ok(false);
+        };
+        // the kernel sets the identifier, the sequence tells replies apart
+        if from.ip() == ip && is_echo_reply(&buf[..len], seq) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// ----------------------------------------------------------------------------
+// A refused connection is an answer as well
+fn connect(ip: IpAddr) -> bool {
+    match TcpStream::connect_timeout(&SocketAddr::new(ip, PROBE_PORT), PROBE_TIMEOUT) {
+        Ok(_) => true,
+        Err(e) => e.kind() == std::io::ErrorKind::ConnectionRefused,
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn echo_request(seq: u16) -> [u8; 8] {
+    let [s0, s1] = seq.to_be_bytes();
+    let mut packet = [ICMP_ECHO_REQUEST, 0, 0, 0, 0, 0, s0, s1];
+    let [c0, c1] = checksum(&packet).to_be_bytes();
+    packet[2] = c0;
+    packet[3] = c1;
+    packet
+}
+
+fn is_echo_reply(packet: &[u8], seq: u16) -> bool {
+    packet.len() >= 8 && packet[0] == ICMP_ECHO_REPLY && packet[6..8] == seq.to_be_bytes()
+}
+
+// Internet checksum (RFC 1071)
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|c| u32::from(u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)])))
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+// ----------------------------------------------------------------------------
+// Classic Bluetooth name request, answered by paired phones in range without
+// being discoverable
+#[cfg(target_os = "linux")]
+fn bluetooth_nearby(mac: &str) -> bool {
+    Command::new("hcitool")
+        .args(["name", mac])
+        .stdin(Stdio::null())
+        .output()
+        .is_ok_and(|output| !String::from_utf8_lossy(&output.stdout).trim().is_empty())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn bluetooth_nearby(_mac: &str) -> bool {
+    false
+}
+
+// ----------------------------------------------------------------------------
+fn run(command: &[String]) -> Result<()> {
+    let Some((program, args)) = command.split_first() else {
+        return Ok(());
+    };
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(Error::InvalidArgument {
+            arg: format!("{program} exited with {status}"),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presence() {
+        let t0 = Instant::now();
+        let secs = |s| t0 + Duration::from_secs(s);
+        let mut presence = Presence::new(Duration::from_secs(600), t0);
+        assert_eq!(presence.update(true, secs(30)), None);
+        assert_eq!(presence.update(false, secs(300)), None);
+        assert_eq!(presence.update(false, secs(629)), None);
+        assert_eq!(presence.update(false, secs(630)), Some(false));
+        assert!(!presence.is_present());
+        assert_eq!(presence.update(false, secs(900)), None);
+        assert_eq!(presence.update(true, secs(930)), Some(true));
+        assert!(presence.is_present());
+    }
+
+    #[test]
+    fn test_echo_request() {
+        let packet = echo_request(7);
+        assert_eq!(packet, [8, 0, 0xf7, 0xf8, 0, 0, 0, 7]);
+        assert_eq!(checksum(&packet), 0);
+        assert!(is_echo_reply(&[0, 0, 0, 0, 0x12, 0x34, 0, 7], 7));
+        assert!(!is_echo_reply(&[0, 0, 0, 0, 0x12, 0x34, 0, 8], 7));
+        assert!(!is_echo_reply(&packet, 7));
+    }
+}
@@ -0,0 +1,378 @@
+use crate::core::frame_stats;
+use crate::error::Result;
+use crate::net::events::EventHub;
+use crate::net::http::{self, Request};
+use crate::net::settings::{self, SettingsService};
+use crate::net::websocket;
+use std::io::BufReader;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+// Time a client gets to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Connections served at once, WebSocket clients included. Further ones are
+// turned away until one of them ends.
+const MAX_CONNECTIONS: usize = 32;
+
+// ----------------------------------------------------------------------------
+// HTTP server on a background thread, one thread per connection up to
+// `MAX_CONNECTIONS`.
+// The PUT requests need the API token, see `is_authorized`.
+//   GET /events            WebSocket stream of frame events as JSON
+//   GET /, /settings       Settings page
+//   GET|PUT /api/settings  Settings as JSON
+//   GET /api/stats         Frame times of the main loop as JSON, null before
+//                          the first second
+//   PUT /api/photo         Rating and tags of the photo on screen, e.g.
+//                          {"rating": 5} or {"add_tags": ["hidden"]}
+pub struct HttpServer {
+    addr: SocketAddr,
+    events: Arc<EventHub>,
+}
+
+impl HttpServer {
+    // ------------------------------------------------------------------------
+    pub fn start(
+        addr: SocketAddr,
+        token: Option<String>,
+        events: Arc<EventHub>,
+        settings: Arc<SettingsService>,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        log::info!("HTTP server listening on {addr}");
+
+        let hub = Arc::clone(&events);
+        let token: Option<Arc<str>> = token.map(Into::into);
+        let active = Arc::new(AtomicUsize::new(0));
+        std::thread::Builder::new()
+            .name(String::from("http"))
+            .spawn(move || {
+                for mut stream in listener.incoming().flatten() {
+                    if active.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                        log::debug!("HTTP: too many connections, refusing one");
+                        let status = "503 Service Unavailable";
+                        let _ = http::write_response(&mut stream, status, "text/plain", b"");
+                        continue;
+                    }
+                    let slot = ConnectionSlot::new(&active);
+                    let hub = Arc::clone(&hub);
+                    let settings = Arc::clone(&settings);
+                    let token = token.clone();
+                    let _ = std::thread::Builder::new()
+                        .name(String::from("http-conn"))
+                        .spawn(move || {
+                            handle_connection(stream, token.as_deref(), &hub, &settings);
+                            drop(slot);
+                        });
+                }
+            })?;
+
+        Ok(Self { addr, events })
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn events(&self) -> &Arc<EventHub> {
+        &self.events
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Counts a connection as active until its thread ends, or fails to start
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn new(active: &Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(active))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn handle_connection(
+    mut stream: TcpStream,
+    token: Option<&str>,
+    events: &EventHub,
+    settings: &SettingsService,
+) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let request = match stream
+        .try_clone()
+        .map_err(Into::into)
+        .and_then(|s| Request::read(&mut BufReader::new(s)))
+    {
+        Ok(request) => request,
+        Err(e) => {
+            log::debug!("HTTP: invalid request: {e:?}");
+            let _ = http::write_response(&mut stream, "400 Bad Request", "text/plain", b"");
+            return;
+        }
+    };
+
+    log::debug!("HTTP: {} {}", request.method, request.path);
+    let peer = stream.peer_addr().ok();
+    if request.method == "PUT" && !is_authorized(&request, peer, token) {
+        log::warn!(
+            "HTTP: unauthorized {} {} from {peer:?}",
+            request.method,
+            request.path
+        );
+        let msg = b"Changes need the API token, open the page with ?token=<token>";
+        let _ = http::write_response(&mut stream, "401 Unauthorized", "text/plain", msg);
+        return;
+    }
+    match request.path.as_str() {
+        "/events" if websocket::is_upgrade(&request) => {
+            if websocket::handshake(&mut stream, &request).is_ok() {
+                events.add_client(stream);
+            }
+        }
+        "/" | "/settings" if request.method == "GET" => {
+            let page = settings::PAGE.as_bytes();
+            let _ = http::write_response(&mut stream, "200 OK", "text/html; charset=utf-8", page);
+        }
+        "/api/settings" => {
+            let _ = settings.handle(&mut stream, &request);
+        }
+        "/api/photo" if request.method == "PUT" => {
+            let _ = settings.update_photo(&mut stream, &request);
+        }
+        "/api/stats" if request.method == "GET" => {
+            let stats = serde_json::to_vec(&frame_stats::latest()).unwrap_or_default();
+            let _ = http::write_response(&mut stream, "200 OK", "application/json", &stats);
+        }
+        _ => {
+            let _ = http::write_response(&mut stream, "404 Not Found", "text/plain", b"");
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// With a token the request has to carry it as `Authorization: Bearer <token>`,
+// without one only clients on the frame itself are trusted
+fn is_authorized(request: &Request, peer: Option<SocketAddr>, token: Option<&str>) -> bool {
+    match token {
+        Some(token) => request
+            .header("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes())),
+        None => peer.is_some_and(|peer| peer.ip().to_canonical().is_loopback()),
+    }
+}
+
+// Doesn't tell by its time how much of the token was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AppConfig;
+    use crate::ipc::Command;
+    use crate::scene::FrameEvent;
+    use std::io::{BufRead, Read, Write};
+    use std::sync::mpsc::{self, Receiver};
+
+    fn start(config: &AppConfig) -> (HttpServer, Receiver<Command>) {
+        let (sender, commands) = mpsc::channel();
+        let settings = Arc::new(SettingsService::new(config, sender));
+        let events = Arc::new(EventHub::new());
+        let addr = "127.0.0.1:0".parse().unwrap();
+        let token = config.api_token.clone();
+        (
+            HttpServer::start(addr, token, events, settings).unwrap(),
+            commands,
+        )
+    }
+
+    fn send(server: &HttpServer, request: &str) -> String {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_event_stream() {
+        let (server, _) = start(&AppConfig::default());
+        server.events().publish(&FrameEvent::SceneChanged {
+            name: String::from("All Photos"),
+        });
+
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream
+            .write_all(b"GET /events HTTP/1.1\r\nHost: frame\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n")
+            .unwrap();
+
+        let mut reader = BufReader::new(stream);
+        let mut status = String::new();
+        reader.read_line(&mut status).unwrap();
+        assert_eq!(status, "HTTP/1.1 101 Switching Protocols\r\n");
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+        }
+
+        // the current scene is replayed to new clients
+        let json = br#"{"type":"scene_changed","name":"All Photos"}"#;
+        let mut frame = vec![0; 2 + json.len()];
+        reader.read_exact(&mut frame).unwrap();
+        assert_eq!(frame[..2], [0x81, json.len() as u8]);
+        assert_eq!(&frame[2..], json);
+        assert_eq!(server.events().client_count(), 1);
+
+        // pings are answered, a close is echoed and ends the stream
+        reader
+            .get_mut()
+            .write_all(&[0x89, 0x82, 1, 2, 3, 4, b'H' ^ 1, b'i' ^ 2])
+            .unwrap();
+        let mut pong = [0; 4];
+        reader.read_exact(&mut pong).unwrap();
+        assert_eq!(pong, [0x8a, 2, b'H', b'i']);
+        reader
+            .get_mut()
+            .write_all(&[0x88, 0x80, 1, 2, 3, 4])
+            .unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [0x88, 0]);
+        while server.events().client_count() > 0 {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let response = send(&server, "GET /nope HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_settings() {
+        let dir = std::env::temp_dir().join(format!("home-settings-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("frame.toml");
+        std::fs::write(&path, "name = \"Kitchen\"\nslide_duration = 5.0\n").unwrap();
+
+        let mut config = AppConfig {
+            photo_dirs: vec![dir.clone()],
+            ..AppConfig::default()
+        };
+        let (server, _) = start(&config);
+        let response = send(&server, "GET /settings HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("<form"));
+
+        // read-only without a config file
+        let body = format!(
+            r#"{{"photo_dirs":[{:?}],"slide_duration":8.0,"transition_duration":1.0,"night":{{"start":"22:00","end":"07:00"}}}}"#,
+            dir
+        );
+        let put = format!(
+            "PUT /api/settings HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        assert!(send(&server, &put).starts_with("HTTP/1.1 409"));
+
+        config.config_file = Some(path.clone());
+        let (server, commands) = start(&config);
+        assert!(send(&server, &put).starts_with("HTTP/1.1 200"));
+        let Ok(Command::ApplySettings(settings)) = commands.try_recv() else {
+            panic!("settings not applied");
+        };
+        assert_eq!(settings.slide_duration, 8.0);
+
+        // the other values of the file are kept
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("name = \"Kitchen\""));
+        assert!(text.contains("slide_duration = 8.0"));
+        assert!(text.contains("[night]"));
+
+        let response = send(&server, "GET /api/settings HTTP/1.1\r\n\r\n");
+        assert!(response.contains(r#""slide_duration":8.0"#));
+
+        let bad = put.replace("8.0", "-1.0");
+        assert!(send(&server, &bad).starts_with("HTTP/1.1 400"));
+
+        // with a token even local clients need it
+        config.api_token = Some(String::from("secret"));
+        let (server, _) = start(&config);
+        assert!(send(&server, &put).starts_with("HTTP/1.1 401"));
+        let authorized = put.replacen("\r\n", "\r\nAuthorization: Bearer secret\r\n", 1);
+        assert!(send(&server, &authorized).starts_with("HTTP/1.1 200"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_authorization() {
+        let request = |headers: &str| {
+            let text = format!("PUT /api/photo HTTP/1.1\r\n{headers}\r\n");
+            Request::read(&mut text.as_bytes()).unwrap()
+        };
+        let local = "127.0.0.1:5000".parse().ok();
+        let mapped = "[::ffff:127.0.0.1]:5000".parse().ok();
+        let lan = "192.168.1.20:5000".parse().ok();
+        assert!(is_authorized(&request(""), local, None));
+        assert!(is_authorized(&request(""), mapped, None));
+        assert!(!is_authorized(&request(""), lan, None));
+        assert!(!is_authorized(&request(""), None, None));
+
+        let bearer = request("Authorization: Bearer secret\r\n");
+        assert!(is_authorized(&bearer, lan, Some("secret")));
+        assert!(!is_authorized(&bearer, lan, Some("secret2")));
+        assert!(!is_authorized(&request(""), local, Some("secret")));
+        let basic = request("Authorization: Basic secret\r\n");
+        assert!(!is_authorized(&basic, lan, Some("secret")));
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let (server, _) = start(&AppConfig::default());
+        let idle: Vec<TcpStream> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(server.addr()).unwrap())
+            .collect();
+        // refused before the request is read
+        let mut response = String::new();
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 503"));
+
+        // served again once a connection ends, refused ones may be reset
+        drop(idle);
+        loop {
+            let mut stream = TcpStream::connect(server.addr()).unwrap();
+            let _ = stream.write_all(b"GET /nope HTTP/1.1\r\n\r\n");
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            if response.starts_with("HTTP/1.1 404") {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let (server, _) = start(&AppConfig::default());
+        frame_stats::publish(frame_stats::FrameStats::default());
+        let response = send(&server, "GET /api/stats HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains(r#""render":{"min_ms":"#));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::app::AppConfig;
+use crate::config::Settings;
+use crate::ipc::Command;
+use crate::net::http::{self, Request};
+use crate::scene::photo::MetaUpdate;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::mpsc::Sender;
+
+// Static page, talks to /api/settings
+pub const PAGE: &str = include_str!("settings.html");
+
+const JSON: &str = "application/json";
+const TEXT: &str = "text/plain";
+
+// ----------------------------------------------------------------------------
+// Backs the settings page: hands out the current values and applies changed
+// ones by writing them to the config file and sending them to the app.
+pub struct SettingsService {
+    path: Option<PathBuf>, // config file, settings are read-only without one
+    profile: Option<String>,
+    current: Mutex<Settings>,
+    commands: Sender<Command>,
+}
+
+impl SettingsService {
+    // ------------------------------------------------------------------------
+    pub fn new(config: &AppConfig, commands: Sender<Command>) -> Self {
+        Self {
+            path: config.config_file.clone(),
+            profile: config.profile.clone(),
+            current: Mutex::new(Settings::from_config(config)),
+            commands,
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn handle(&self, stream: &mut impl Write, request: &Request) -> std::io::Result<()> {
+        match request.method.as_str() {
+            "GET" => {
+                let current = self.current.lock().map(|s| s.clone());
+                match current.map(|s| serde_json::to_vec(&s)) {
+                    Ok(Ok(json)) => http::write_response(stream, "200 OK", JSON, &json),
+                    _ => http::write_response(stream, "500 Internal Server Error", TEXT, b""),
+                }
+            }
+            "PUT" => match self.update(&request.body) {
+                Ok(json) => http::write_response(stream, "200 OK", JSON, &json),
+                Err((status, msg)) => http::write_response(stream, status, TEXT, msg.as_bytes()),
+            },
+            _ => http::write_response(stream, "405 Method Not Allowed", TEXT, b""),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Like and hide buttons, the app writes the change to the sidecar
+    pub fn update_photo(&self, stream: &mut impl Write, request: &Request) -> std::io::Result<()> {
+        let update = match serde_json::from_slice::<MetaUpdate>(&request.body) {
+            Ok(update) if update.is_valid() => update,
+            Ok(_) => return http::write_response(stream, "400 Bad Request", TEXT, b"rating"),
+            Err(e) => {
+                return http::write_response(
+                    stream,
+                    "400 Bad Request",
+                    TEXT,
+                    e.to_string().as_bytes(),
+                );
+            }
+        };
+        let _ = self.commands.send(Command::UpdatePhoto(update));
+        http::write_response(stream, "202 Accepted", TEXT, b"")
+    }
+
+    // ------------------------------------------------------------------------
+    // Returns the saved settings as JSON, or the status and reason of a
+    // failure
+    fn update(&self, body: &[u8]) -> Result<Vec<u8>, (&'static str, String)> {
+        let settings: Settings =
+            serde_json::from_slice(body).map_err(|e| ("400 Bad Request", e.to_string()))?;
+        settings
+            .apply(&mut AppConfig::default())
+            .map_err(|e| ("400 Bad Request", e.to_string()))?;
+
+        let Some(path) = &self.path else {
+            return Err((
+                "409 Conflict",
+                String::from("Start the frame with --config to change settings"),
+            ));
+        };
+        let mut current = self
+            .current
+            .lock()
+            .map_err(|_| ("500 Internal Server Error", String::new()))?;
+        settings
+            .save(path, self.profile.as_deref())
+            .map_err(|e| ("500 Internal Server Error", format!("{e:?}")))?;
+        *current = settings.clone();
+
+        let json = serde_json::to_vec(&settings).unwrap_or_default();
+        let _ = self.commands.send(Command::ApplySettings(settings));
+        Ok(json)
+    }
+}
@@ -0,0 +1,157 @@
+use crate::error::{Error, Result};
+use crate::net::http::Request;
+use crate::util::{base64, sha1::sha1};
+use std::io::{Read, Write};
+
+// Appended to the client key for the accept hash (RFC 6455, 1.3)
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+pub const OP_TEXT: u8 = 0x1;
+pub const OP_CLOSE: u8 = 0x8;
+pub const OP_PING: u8 = 0x9;
+pub const OP_PONG: u8 = 0xa;
+
+// Clients only send control frames, larger frames end the connection
+const MAX_PAYLOAD: u64 = 65536;
+
+// ----------------------------------------------------------------------------
+pub fn is_upgrade(req: &Request) -> bool {
+    req.method == "GET"
+        && req.has_token("Connection", "upgrade")
+        && req.has_token("Upgrade", "websocket")
+}
+
+// ----------------------------------------------------------------------------
+pub fn accept_key(key: &str) -> String {
+    base64::encode(&sha1(format!("{key}{GUID}").as_bytes()))
+}
+
+// ----------------------------------------------------------------------------
+// Answers an upgrade request, the connection carries frames afterwards
+pub fn handshake(stream: &mut impl Write, req: &Request) -> Result<()> {
+    let key = req
+        .header("Sec-WebSocket-Key")
+        .ok_or(Error::InvalidRequest)?;
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// Unmasked, unfragmented server to client frames
+pub fn text_frame(text: &str) -> Vec<u8> {
+    frame(OP_TEXT, text.as_bytes())
+}
+
+pub fn close_frame() -> Vec<u8> {
+    frame(OP_CLOSE, &[])
+}
+
+pub fn pong_frame(payload: &[u8]) -> Vec<u8> {
+    frame(OP_PONG, payload)
+}
+
+pub fn is_close(frame: &[u8]) -> bool {
+    frame.first() == Some(&(0x80 | OP_CLOSE))
+}
+
+fn frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..126 => out.push(len as u8),
+        len @ 126..65536 => {
+            out.push(126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            out.push(127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(payload);
+    out
+}
+
+// ----------------------------------------------------------------------------
+// Reads a client to server frame and returns its opcode and the unmasked
+// payload, fragments are returned as they come
+pub fn read_frame(stream: &mut impl Read) -> Result<(u8, Vec<u8>)> {
+    let mut head = [0; 2];
+    stream.read_exact(&mut head)?;
+    let len = match head[1] & 0x7f {
+        126 => {
+            let mut len = [0; 2];
+            stream.read_exact(&mut len)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0; 8];
+            stream.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    if len > MAX_PAYLOAD {
+        return Err(Error::InvalidRequest);
+    }
+    let mut mask = [0; 4];
+    if head[1] & 0x80 != 0 {
+        stream.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((head[0] & 0x0f, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_key() {
+        // example from RFC 6455
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        assert_eq!(text_frame("Hi"), vec![0x81, 2, b'H', b'i']);
+        assert_eq!(close_frame(), vec![0x88, 0]);
+
+        let long = "x".repeat(300);
+        let f = text_frame(&long);
+        assert_eq!(f[..4], [0x81, 126, 1, 44]);
+        assert_eq!(f.len(), 4 + 300);
+
+        assert_eq!(pong_frame(b"Hi"), vec![0x8a, 2, b'H', b'i']);
+        assert!(is_close(&close_frame()));
+        assert!(!is_close(&f));
+    }
+
+    #[test]
+    fn test_read_frame() {
+        // masked "Hello" from RFC 6455, 5.7
+        let data = [
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ];
+        let (opcode, payload) = read_frame(&mut &data[..]).unwrap();
+        assert_eq!((opcode, payload.as_slice()), (OP_TEXT, &b"Hello"[..]));
+
+        let ping = [0x89, 0x80, 1, 2, 3, 4];
+        assert_eq!(read_frame(&mut &ping[..]).unwrap(), (OP_PING, vec![]));
+
+        let huge = [0x82, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0];
+        assert!(read_frame(&mut &huge[..]).is_err());
+        assert!(read_frame(&mut &[0x88][..]).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use crate::gfx::animation::{Animation, Easing};
+use crate::scene::{Element, Layout, LayoutItem};
+use crate::v2d::{rect::Rect, scalar};
+
+// ----------------------------------------------------------------------------
+// Sits between the scenes and the layouter. Items that keep their id from one
+// layout to the next move from their currently shown rect and opacity to the
+// new ones over the item's `animation_time`, new items fade in. Frames shown
+// between two updates are sampled in between, see `layout_at`.
+#[derive(Clone, Debug, Default)]
+pub struct LayoutAnimator {
+    time: f32,
+    prev_time: f32, // before the last advance
+    items: Vec<AnimatedItem>,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+struct AnimatedItem {
+    target: LayoutItem,
+    from: Placement,
+    progress: Animation<f32>,
+    transition_from: Option<(f32, f32)>, // time and progress of the last transition step
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Placement {
+    dst: Rect,
+    opacity: f32,
+}
+
+impl LayoutAnimator {
+    // ------------------------------------------------------------------------
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // ------------------------------------------------------------------------
+    // Start animating towards the given layout
+    pub fn set_layout(&mut self, layout: &Layout) {
+        let items = layout.items.iter().map(|item| self.animate_item(item));
+        self.items = items.collect();
+    }
+
+    // ------------------------------------------------------------------------
+    // Advance by dt seconds, true if the shown layout changed
+    pub fn advance(&mut self, dt: f32) -> bool {
+        let animating = self.is_animating();
+        self.prev_time = self.time;
+        self.time += dt;
+        animating
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn is_animating(&self) -> bool {
+        let t = self.time;
+        self.items.iter().any(|item| !item.progress.is_finished(t))
+    }
+
+    // ------------------------------------------------------------------------
+    // The layout as it should be shown right now
+    pub fn layout(&self) -> Layout {
+        self.layout_at(1.0)
+    }
+
+    // ------------------------------------------------------------------------
+    // The layout `alpha` of the way from the previous update to the last one,
+    // transitions step from their previous progress
+    pub fn layout_at(&self, alpha: f32) -> Layout {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let t = scalar::lerp(self.prev_time, self.time, alpha);
+        let items = self.items.iter().map(|item| {
+            let mut shown = item.target.clone();
+            if let Some(placement) = item.shown(t) {
+                set_placement(&mut shown.element, placement);
+            }
+            if let (Element::Transition(transition), Some((since, from))) =
+                (&mut shown.element, item.transition_from)
+                && since == self.time
+            {
+                transition.progress = scalar::lerp(from, transition.progress, alpha);
+            }
+            shown
+        });
+        Layout {
+            items: items.collect(),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn animate_item(&self, item: &LayoutItem) -> AnimatedItem {
+        let t = self.time;
+        let prev = self.items.iter().find(|prev| prev.target.id == item.id);
+        let to = placement(&item.element);
+
+        if let Element::Transition(transition) = &item.element {
+            let from = match prev.map(|prev| &prev.target.element) {
+                Some(Element::Transition(prev)) => prev.progress,
+                _ => transition.progress,
+            };
+            return AnimatedItem {
+                target: item.clone(),
+                from: Placement::default(),
+                progress: Animation::new(t, t, 1.0, 1.0),
+                transition_from: Some((t, from)),
+            };
+        }
+
+        // Layouts are handed in on every change, keep running animations
+        // towards an unchanged target instead of restarting them.
+        if let Some(prev) = prev
+            && same_kind(&prev.target, item)
+            && placement(&prev.target.element) == to
+        {
+            return AnimatedItem {
+                target: item.clone(),
+                ..prev.clone()
+            };
+        }
+
+        let from = match prev {
+            Some(prev) if same_kind(&prev.target, item) => prev.shown(t),
+            Some(_) => None,
+            None => to.map(|to| Placement { opacity: 0.0, ..to }),
+        };
+
+        let duration = item.animation_time.unwrap_or(0.0);
+        match from {
+            Some(from) if duration > 0.0 => AnimatedItem {
+                target: item.clone(),
+                from,
+                progress: Animation::new(t, t + duration, 0.0, 1.0).with_easing(Easing::SineInOut),
+                transition_from: None,
+            },
+            _ => AnimatedItem {
+                target: item.clone(),
+                from: to.unwrap_or_default(),
+                progress: Animation::new(t, t, 1.0, 1.0),
+                transition_from: None,
+            },
+        }
+    }
+}
+
+impl AnimatedItem {
+    // ------------------------------------------------------------------------
+    // Interpolated placement at time t, None for elements without one
+    fn shown(&self, t: f32) -> Option<Placement> {
+        let to = placement(&self.target.element)?;
+        let s = self.progress.blend(t);
+        Some(Placement {
+            dst: Rect::lerp(&self.from.dst, &to.dst, s),
+            opacity: scalar::lerp(self.from.opacity, to.opacity, s),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            dst: Rect::unit(),
+            opacity: 1.0,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn same_kind(a: &LayoutItem, b: &LayoutItem) -> bool {
+    std::mem::discriminant(&a.element) == std::mem::discriminant(&b.element)
+}
+
+// ----------------------------------------------------------------------------
+fn placement(element: &Element) -> Option<Placement> {
+    let (dst, opacity) = match element {
+        Element::Picture(p) | Element::Thumbnail(p) => (p.dst, p.opacity),
+        Element::Icon(i) => (i.dst, i.opacity),
+        Element::Text(t) => (t.dst, t.opacity),
+        Element::Clock(c) => (c.dst, c.opacity),
+        Element::Transition(_) => return None,
+    };
+    Some(Placement { dst, opacity })
+}
+
+// ----------------------------------------------------------------------------
+fn set_placement(element: &mut Element, placement: Placement) {
+    let (dst, opacity) = match element {
+        Element::Picture(p) | Element::Thumbnail(p) => (&mut p.dst, &mut p.opacity),
+        Element::Icon(i) => (&mut i.dst, &mut i.opacity),
+        Element::Text(t) => (&mut t.dst, &mut t.opacity),
+        Element::Clock(c) => (&mut c.dst, &mut c.opacity),
+        Element::Transition(_) => return,
+    };
+    *dst = placement.dst;
+    *opacity = placement.opacity;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_float_eq;
+    use crate::scene::slots::SlotId;
+    use crate::scene::{Handle, LayoutId, Text};
+    use crate::v2d::float_eq::float_eq_rel;
+    use crate::v2d::{v2::V2, v4::V4};
+
+    fn text_layout(x: f32, animation_time: Option<f32>) -> Layout {
+        let text = Text {
+            dst: Rect::new(V2::new([x, 0.0]), V2::new([1.0, 1.0])),
+            opacity: 1.0,
+            color: V4::new([1.0, 1.0, 1.0, 1.0]),
+            handle: Handle {
+                material_id: None,
+                mesh_id: Some(SlotId {
+                    index: 0,
+                    generation: 0,
+                }),
+                aspect_ratio: 0.0,
+                pending: false,
+            },
+        };
+        Layout {
+            items: vec![LayoutItem {
+                id: LayoutId(7),
+                element: Element::Text(text),
+                animation_time,
+            }],
+        }
+    }
+
+    fn shown(animator: &LayoutAnimator) -> Placement {
+        placement(&animator.layout().items[0].element).unwrap()
+    }
+
+    #[test]
+    fn test_animator_fade_in_and_move() {
+        let mut animator = LayoutAnimator::new();
+        animator.set_layout(&text_layout(0.0, Some(1.0)));
+        assert_float_eq!(shown(&animator).opacity, 0.0);
+        assert!(animator.advance(0.5));
+        assert_float_eq!(shown(&animator).opacity, 0.5);
+        animator.advance(0.5);
+        assert!(!animator.is_animating());
+        assert_float_eq!(shown(&animator).opacity, 1.0);
+
+        animator.set_layout(&text_layout(2.0, Some(1.0)));
+        animator.advance(0.5);
+        assert_float_eq!(shown(&animator).dst.pos.x0(), 1.0);
+
+        // handing in the same target again keeps the running animation
+        animator.set_layout(&text_layout(2.0, Some(1.0)));
+        animator.advance(0.5);
+        assert_float_eq!(shown(&animator).dst.pos.x0(), 2.0);
+        assert!(!animator.is_animating());
+    }
+
+    #[test]
+    fn test_animator_layout_at() {
+        let mut animator = LayoutAnimator::new();
+        animator.set_layout(&text_layout(0.0, Some(1.0)));
+        animator.advance(0.5);
+        let opacity = |alpha| {
+            placement(&animator.layout_at(alpha).items[0].element)
+                .unwrap()
+                .opacity
+        };
+        assert_float_eq!(opacity(0.0), 0.0);
+        assert_float_eq!(opacity(0.5), Easing::SineInOut.apply(0.25));
+        assert_float_eq!(opacity(1.0), 0.5);
+    }
+
+    #[test]
+    fn test_animator_snap() {
+        let mut animator = LayoutAnimator::new();
+        animator.set_layout(&text_layout(0.0, None));
+        assert_float_eq!(shown(&animator).opacity, 1.0);
+        animator.set_layout(&text_layout(2.0, None));
+        assert_float_eq!(shown(&animator).dst.pos.x0(), 2.0);
+        assert!(!animator.is_animating());
+    }
+}
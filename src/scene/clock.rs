@@ -0,0 +1,155 @@
+use crate::scene::{
+    Context, Element, Handle, Layout, LayoutId, LayoutItem, Layouter, Scene, SceneEvent, Text,
+};
+use crate::util::datetime::Date;
+use crate::util::locale::{fmt_long, fmt_time};
+use crate::v2d::{rect::Rect, v2::V2, v4::V4};
+
+// ----------------------------------------------------------------------------
+const TIME_HEIGHT: f32 = 0.3;
+const DATE_HEIGHT: f32 = 0.08;
+const GAP: f32 = 0.02; // between the time and the date
+const MARGIN: f32 = 0.05;
+
+// ----------------------------------------------------------------------------
+// The local time large in the center of the screen, the long date below. The
+// texts are only created again when the shown minute changes.
+#[derive(Default)]
+pub struct ClockScene {
+    shown: Option<(Date, u32, u32)>, // date, hour and minute of the texts
+    time: Option<Handle>,
+    date: Option<Handle>,
+}
+
+impl ClockScene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // ------------------------------------------------------------------------
+    // None while the shown minute stays the same
+    fn refresh(&mut self, ctx: &Context, layouter: &mut Layouter) -> Option<Layout> {
+        let now = ctx.time.to_local();
+        let (hour, minute, _) = now.time.to_hms();
+        if self.shown == Some((now.date, hour, minute)) {
+            return None;
+        }
+        self.shown = Some((now.date, hour, minute));
+        self.free(layouter);
+
+        let time = fmt_time(&now.time, false, ctx.locale.as_ref());
+        let date = fmt_long(&now.date, ctx.locale.as_ref());
+        let aspect_ratio = layouter.aspect_ratio();
+        let max_width = |height: f32| (1.0 - 2.0 * MARGIN) * aspect_ratio / height;
+        let time_width = layouter.text_width(&time).min(max_width(TIME_HEIGHT));
+        let date_width = layouter.text_width(&date).min(max_width(DATE_HEIGHT));
+        self.time = create_text(layouter, &time, max_width(TIME_HEIGHT));
+        self.date = create_text(layouter, &date, max_width(DATE_HEIGHT));
+
+        // the time sits just above the center, the date below it
+        let mut items = Vec::new();
+        let y = 0.5 - TIME_HEIGHT / 3.0;
+        items.extend(self.time.map(|handle| {
+            centered_text(
+                LayoutId(0),
+                handle,
+                time_width,
+                y,
+                TIME_HEIGHT,
+                aspect_ratio,
+            )
+        }));
+        let y = y - GAP - DATE_HEIGHT;
+        items.extend(self.date.map(|handle| {
+            centered_text(
+                LayoutId(1),
+                handle,
+                date_width,
+                y,
+                DATE_HEIGHT,
+                aspect_ratio,
+            )
+        }));
+        Some(Layout { items })
+    }
+
+    fn free(&mut self, layouter: &mut Layouter) {
+        for handle in [self.time.take(), self.date.take()].into_iter().flatten() {
+            layouter.free_handle(handle);
+        }
+    }
+}
+
+impl Scene for ClockScene {
+    fn update(
+        &mut self,
+        event: &SceneEvent,
+        ctx: &Context,
+        layouter: &mut Layouter,
+    ) -> Option<Layout> {
+        match event {
+            SceneEvent::Enter => {
+                self.shown = None;
+                self.refresh(ctx, layouter)
+            }
+            SceneEvent::TimeTick(_) => self.refresh(ctx, layouter),
+            SceneEvent::Exit => {
+                self.free(layouter);
+                self.shown = None;
+                Some(Layout::empty())
+            }
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Clock"
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn create_text(layouter: &mut Layouter, text: &str, max_width: f32) -> Option<Handle> {
+    layouter
+        .create_fitted_text(text, max_width)
+        .inspect_err(|e| log::warn!("Failed to create clock text '{text}': {e:?}"))
+        .ok()
+}
+
+// `width` in em, the others in screen heights
+fn centered_text(
+    id: LayoutId,
+    handle: Handle,
+    width: f32,
+    y: f32,
+    height: f32,
+    aspect_ratio: f32,
+) -> LayoutItem {
+    let em = height / aspect_ratio;
+    LayoutItem {
+        id,
+        element: Element::Text(Text {
+            dst: Rect::new(V2::new([0.5 - width * em / 2.0, y]), V2::new([em, height])),
+            opacity: 1.0,
+            color: V4::new([1.0, 1.0, 1.0, 1.0]),
+            handle,
+        }),
+        animation_time: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_centered_text() {
+        let handle = Handle::PENDING;
+        let item = centered_text(LayoutId(0), handle, 2.0, 0.4, 0.2, 2.0);
+        let Element::Text(text) = &item.element else {
+            panic!("{item:?}");
+        };
+        // 2 em of 0.1 screen widths each
+        assert_eq!(text.dst.pos, V2::new([0.4, 0.4]));
+        assert_eq!(text.dst.size, V2::new([0.1, 0.2]));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::error::{Error, Result};
+use crate::gfx::sharpen::UnsharpMask;
+use crate::scene::SystemEvent;
+use crate::scene::photo::{DecodedPhoto, Letterbox};
+use crate::util::tasks::TaskSender;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+// ----------------------------------------------------------------------------
+// Decodes and downscales photos on a thread of its own, so that the render
+// thread only uploads them. Every request is answered with
+//
+//   PhotoDecoded  the image, ready for the upload to a texture
+//   PhotoFailed   the photo can't be read
+//
+// in the order of the requests. The thread ends with the last decoder.
+#[derive(Clone)]
+pub struct PhotoDecoder {
+    jobs: Sender<PathBuf>,
+}
+
+impl PhotoDecoder {
+    // ------------------------------------------------------------------------
+    pub fn start(
+        events: TaskSender,
+        sharpen: Option<UnsharpMask>,
+        letterbox: Letterbox,
+    ) -> Result<Self> {
+        let (jobs, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name(String::from("decode"))
+            .spawn(move || run(&receiver, &events, sharpen, letterbox))?;
+        Ok(Self { jobs })
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn request(&self, path: &Path) -> Result<()> {
+        self.jobs.send(path.to_path_buf()).map_err(|_| Error::Ipc {
+            msg: String::from("decode thread stopped"),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn run(
+    jobs: &Receiver<PathBuf>,
+    events: &TaskSender,
+    sharpen: Option<UnsharpMask>,
+    letterbox: Letterbox,
+) {
+    while let Ok(path) = jobs.recv() {
+        let event = match DecodedPhoto::read(&path, sharpen.as_ref(), letterbox) {
+            Ok(photo) => SystemEvent::PhotoDecoded {
+                path,
+                photo: Arc::new(photo),
+            },
+            Err(e) => SystemEvent::PhotoFailed {
+                path,
+                error: e.to_string(),
+            },
+        };
+        if !events.send(event) {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tasks;
+    use std::time::Duration;
+
+    #[test]
+    fn test_decoder() {
+        let (sender, events) = tasks::channel();
+        let decoder = PhotoDecoder::start(sender, None, Letterbox::Plain).unwrap();
+        decoder
+            .request(Path::new("/nonexistent/photo.webp"))
+            .unwrap();
+        let event = events.recv_timeout(Duration::from_secs(5));
+        let Ok(SystemEvent::PhotoFailed { path, error }) = event else {
+            panic!("{event:?}");
+        };
+        assert_eq!(path, Path::new("/nonexistent/photo.webp"));
+        assert!(error.contains("/nonexistent/photo.webp"), "{error}");
+    }
+}
@@ -0,0 +1,211 @@
+use crate::error::{Error, Result};
+use crate::geo::Coord;
+use crate::gfx::color_conversion::{ImageGeometry, YCbCrMatrix, rgb24_to_ycbcr24};
+use crate::gfx::color_format::ColorFormat;
+use crate::scene::photo::{DisplayHints, META_VERSION, Photo, PhotoMeta};
+use crate::util::datetime::{Date, DateTime, Time};
+use std::path::{Path, PathBuf};
+
+// Photo directory of `--demo`, its photos are generated instead of read
+pub const DEMO_DIR: &str = "<demo>";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pattern {
+    Sunset,
+    Ocean,
+    ColorBars,
+    Rings,
+    Checker,
+    Forest,
+}
+
+struct Sample {
+    name: &'static str,
+    size: (usize, usize),
+    pattern: Pattern,
+    place: [&'static str; 2],
+    title: &'static str,
+    coord: (f64, f64),
+    days_ago: i32,
+}
+
+const fn sample(
+    name: &'static str,
+    size: (usize, usize),
+    pattern: Pattern,
+    place: [&'static str; 2],
+    title: &'static str,
+    coord: (f64, f64),
+    days_ago: i32,
+) -> Sample {
+    Sample {
+        name,
+        size,
+        pattern,
+        place,
+        title,
+        coord,
+        days_ago,
+    }
+}
+
+#[rustfmt::skip]
+const SAMPLES: [Sample; 6] = [
+    sample("sunset", (1280, 720), Pattern::Sunset, ["Lisbon", "Portugal"], "Sunset at the river", (38.71, -9.14), 0),
+    sample("ocean", (1280, 720), Pattern::Ocean, ["Sydney", "Australia"], "Blue hour", (-33.87, 151.21), 0),
+    sample("bars", (1280, 720), Pattern::ColorBars, ["Berlin", "Germany"], "Test pattern", (52.52, 13.40), 365),
+    sample("rings", (720, 960), Pattern::Rings, ["Zurich", "Switzerland"], "Ripples", (47.37, 8.54), 30),
+    sample("checker", (960, 960), Pattern::Checker, ["New York", "USA"], "Floor tiles", (40.71, -74.01), 120),
+    sample("forest", (720, 960), Pattern::Forest, ["Vancouver", "Canada"], "Morning fog", (49.28, -123.12), 0),
+];
+
+// ----------------------------------------------------------------------------
+// The sample photos with made up metadata, some of them are from today so
+// that the "today" scene has something to show
+pub fn photos() -> Vec<Photo> {
+    let today = Date::today();
+    SAMPLES
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            let date = Date::new(today.days() - sample.days_ago);
+            let time = Time::from_hms(8 + 2 * i as u32, 15, 0).ok();
+            let (lat, lon) = sample.coord;
+            Photo {
+                path: Path::new(DEMO_DIR).join(format!("{}.webp", sample.name)),
+                meta: PhotoMeta {
+                    version: META_VERSION,
+                    datetime: time.map(|time| DateTime { date, time }),
+                    coord: Some(Coord { lat, lon }),
+                    place: Some(sample.place.map(String::from).to_vec()),
+                    title: Some(vec![String::from(sample.title)]),
+                    tag: Some(vec![String::from("demo")]),
+                    weather: None,
+                    rating: Some(3 + (i % 3) as u8),
+                    display: DisplayHints::default(),
+                },
+                last_shown: None,
+            }
+        })
+        .collect()
+}
+
+pub fn is_demo_photo(path: &Path) -> bool {
+    path.starts_with(DEMO_DIR)
+}
+
+// ----------------------------------------------------------------------------
+// Generates the interleaved YCbCr image of a sample photo
+pub fn generate(path: &Path) -> Result<(usize, usize, Vec<u8>)> {
+    let name = path.file_stem().and_then(|s| s.to_str());
+    let sample = SAMPLES.iter().find(|s| Some(s.name) == name);
+    let sample = sample.ok_or(Error::FileNotFound {
+        path: PathBuf::from(path),
+    })?;
+    let (cx, cy) = sample.size;
+
+    let mut rgb = Vec::with_capacity(cx * cy * 3);
+    for y in 0..cy {
+        for x in 0..cx {
+            let u = x as f32 / cx as f32;
+            let v = y as f32 / cy as f32;
+            rgb.extend_from_slice(&pixel(sample.pattern, u, v, x, y));
+        }
+    }
+    let geo = ImageGeometry {
+        cx,
+        cy,
+        cf: ColorFormat::RGB0888,
+    };
+    Ok((cx, cy, rgb24_to_ycbcr24(&rgb, &geo, YCbCrMatrix::Bt601)))
+}
+
+// ----------------------------------------------------------------------------
+// u and v are the position in the image from 0 to 1
+fn pixel(pattern: Pattern, u: f32, v: f32, x: usize, y: usize) -> [u8; 3] {
+    let mix = |a: [f32; 3], b: [f32; 3], t: f32| {
+        let t = t.clamp(0.0, 1.0);
+        [0, 1, 2].map(|i| (a[i] + (b[i] - a[i]) * t) as u8)
+    };
+    match pattern {
+        Pattern::Sunset => {
+            // sky fading from orange into purple above a dark river
+            if v < 0.65 {
+                mix([70.0, 40.0, 110.0], [250.0, 150.0, 60.0], v / 0.65)
+            } else {
+                let glow = 1.0 - ((u - 0.5).abs() * 4.0).min(1.0);
+                mix(
+                    [20.0, 20.0, 50.0],
+                    [200.0, 110.0, 50.0],
+                    glow * (1.0 - v) * 2.0,
+                )
+            }
+        }
+        Pattern::Ocean => {
+            let wave = ((u * 24.0 + v * 6.0).sin() * 0.5 + 0.5) * 0.15;
+            mix([120.0, 180.0, 230.0], [10.0, 40.0, 90.0], v + wave)
+        }
+        Pattern::ColorBars => {
+            const BARS: [[u8; 3]; 7] = [
+                [192, 192, 192],
+                [192, 192, 0],
+                [0, 192, 192],
+                [0, 192, 0],
+                [192, 0, 192],
+                [192, 0, 0],
+                [0, 0, 192],
+            ];
+            if v < 0.75 {
+                BARS[((u * 7.0) as usize).min(6)]
+            } else {
+                let gray = (u * 255.0) as u8;
+                [gray, gray, gray]
+            }
+        }
+        Pattern::Rings => {
+            let d = ((u - 0.5).powi(2) + (v - 0.5).powi(2) * 0.56).sqrt();
+            let ring = (d * 60.0).cos() * 0.5 + 0.5;
+            mix([20.0, 60.0, 90.0], [160.0, 220.0, 230.0], ring * (1.0 - d))
+        }
+        Pattern::Checker => {
+            if (x / 80 + y / 80).is_multiple_of(2) {
+                mix([230.0, 225.0, 210.0], [180.0, 170.0, 150.0], v)
+            } else {
+                mix([60.0, 50.0, 45.0], [30.0, 25.0, 20.0], v)
+            }
+        }
+        Pattern::Forest => {
+            // tree trunks in front of a foggy gradient
+            let trunk = (u * 9.0 + (v * 3.0).sin() * 0.1).fract() < 0.12;
+            let fog = mix([210.0, 220.0, 215.0], [60.0, 100.0, 70.0], v * 1.2);
+            match trunk {
+                true => mix([70.0, 60.0, 50.0], [30.0, 25.0, 20.0], v),
+                false => fog,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_photos() {
+        let photos = photos();
+        assert_eq!(photos.len(), SAMPLES.len());
+        let today = photos
+            .iter()
+            .filter(|p| p.meta.datetime.unwrap().date == Date::today())
+            .count();
+        assert_eq!(today, 3);
+
+        assert!(photos.iter().all(|p| is_demo_photo(&p.path)));
+
+        let (cx, cy, yuv24) = generate(&photos[3].path).unwrap();
+        assert_eq!((cx, cy), (720, 960));
+        assert_eq!(yuv24.len(), cx * cy * 3);
+        assert!(generate(Path::new("<demo>/missing.webp")).is_err());
+        assert!(!is_demo_photo(Path::new("assets/photos/sunset.webp")));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error::Error;
+use crate::scene::{
+    Context, Element, Handle, Layout, LayoutId, LayoutItem, Layouter, Scene, SceneEvent, Text,
+};
+use crate::util::utf8::graphemes;
+use crate::v2d::{rect::Rect, v2::V2, v4::V4};
+
+// ----------------------------------------------------------------------------
+const TITLE_HEIGHT: f32 = 0.06;
+const TEXT_HEIGHT: f32 = 0.035;
+const LINE_SPACING: f32 = 1.5 * TEXT_HEIGHT;
+const MARGIN: f32 = 0.05;
+const MAX_LINE_CHARS: usize = 60;
+
+// Seconds between attempts to start the failed scene again
+pub const RETRY_INTERVAL: f32 = 30.0;
+
+// ----------------------------------------------------------------------------
+// Shown instead of a black panel when no scene can run, e.g. without photos
+// or after a GL error. The scene manager retries the failed scene meanwhile.
+pub struct ErrorScene {
+    lines: Vec<String>, // title first
+    handles: Vec<Handle>,
+}
+
+impl ErrorScene {
+    pub fn new(error: &Error, admin_url: Option<&str>) -> Self {
+        let mut lines = vec![String::from("Something went wrong")];
+        lines.extend(wrap(&describe(error), MAX_LINE_CHARS));
+        lines.push(format!("Retrying every {RETRY_INTERVAL} seconds."));
+        if let Some(url) = admin_url {
+            lines.push(format!("Settings: {url}"));
+        }
+        Self {
+            lines,
+            handles: Vec::new(),
+        }
+    }
+
+    fn layout(&self, layouter: &Layouter) -> Layout {
+        let mut y = 0.5 - (self.handles.len() as f32 * LINE_SPACING) / 2.0;
+        let items = self.handles.iter().enumerate().map(|(i, &handle)| {
+            let (height, color) = match i {
+                0 => (TITLE_HEIGHT, V4::new([1.0, 0.6, 0.4, 1.0])),
+                _ => (TEXT_HEIGHT, V4::new([1.0, 1.0, 1.0, 1.0])),
+            };
+            let size = V2::new([height / layouter.aspect_ratio(), height]);
+            let dst = Rect::new(V2::new([MARGIN, y]), size);
+            y += LINE_SPACING.max(height * 1.25);
+            LayoutItem {
+                id: LayoutId(i as u32 + 1),
+                element: Element::Text(Text {
+                    dst,
+                    opacity: 1.0,
+                    color,
+                    handle,
+                }),
+                animation_time: Some(0.5),
+            }
+        });
+        Layout {
+            items: items.collect(),
+        }
+    }
+}
+
+impl Scene for ErrorScene {
+    fn update(
+        &mut self,
+        event: &SceneEvent,
+        _ctx: &Context,
+        layouter: &mut Layouter,
+    ) -> Option<Layout> {
+        match event {
+            SceneEvent::Enter => {
+                let lines = self.lines.iter();
+                self.handles = lines.filter_map(|l| layouter.create_text(l).ok()).collect();
+                Some(self.layout(layouter))
+            }
+            SceneEvent::Exit => {
+                for handle in self.handles.drain(..) {
+                    layouter.free_handle(handle);
+                }
+                Some(Layout::empty())
+            }
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Error"
+    }
+}
+
+// ----------------------------------------------------------------------------
+// A sentence for whoever looks at the frame, the details are in the log
+pub fn describe(error: &Error) -> String {
+    match error.root_cause() {
+        Error::EmptyPhotos => String::from(
+            "No photos found. Check that the photo folders exist and the network drive is mounted.",
+        ),
+        Error::EmptyScenes | Error::InvalidScene => {
+            String::from("The selected scene is not available.")
+        }
+        Error::FileNotFound { path } | Error::FileRead { path } => {
+            format!("Can't read {}.", path.display())
+        }
+        Error::OpenGl { .. }
+        | Error::Framebuffer { .. }
+        | Error::ShaderLoad { .. }
+        | Error::OpenGlLoad { .. } => String::from("The graphics driver reported an error."),
+        Error::GpuOutOfMemory => String::from("The graphics memory is full."),
+        e => format!("{e}"),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Breaks `text` into lines of at most `width` characters at spaces, accents
+// and emoji sequences count as one
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if graphemes(line).count() + 1 + graphemes(word).count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        let lines = wrap("No photos found. Check the  folders.", 16);
+        assert_eq!(lines, ["No photos found.", "Check the", "folders."]);
+        assert!(wrap("", 16).is_empty());
+    }
+
+    #[test]
+    fn test_error_lines() {
+        let scene = ErrorScene::new(&Error::EmptyPhotos, Some("http://10.0.0.5:8080/"));
+        assert_eq!(scene.lines[0], "Something went wrong");
+        assert_eq!(
+            scene.lines.last().unwrap(),
+            "Settings: http://10.0.0.5:8080/"
+        );
+        assert!(scene.lines.iter().all(|l| l.len() <= MAX_LINE_CHARS));
+    }
+}
@@ -1,6 +1,36 @@
-use crate::error::{Error, Result};
+use crate::core::gl_pipeline::msdf_tex::DEFAULT_PX_RANGE;
+use crate::error::{Error, Result, ResultExt};
+use crate::gfx::sdf;
+use crate::gfx::ttf::{self, TrueType};
+use crate::v2d::v2::V2;
 use miniz::png_read;
 use serde::Deserialize;
+use std::path::Path;
+
+// Atlas shipped with the frame
+pub const DEFAULT_FONT: &str = "assets/fonts/roboto.png";
+
+// Atlases generated from TrueType fonts
+const ATLAS_WIDTH: usize = 512;
+const ATLAS_EM_SIZE: f32 = 32.0; // pixels per em
+const ATLAS_PX_RANGE: f32 = 4.0;
+const ATLAS_CHARS: &[(u32, u32)] = &[
+    (0x20, 0x7e),     // ASCII
+    (0xa0, 0x17f),    // Latin-1 and Latin Extended-A
+    (0x5b0, 0x5c7),   // Hebrew points
+    (0x5d0, 0x5ea),   // Hebrew
+    (0x60c, 0x60c),   // Arabic comma
+    (0x61b, 0x61f),   // Arabic semicolon and question mark
+    (0x64b, 0x652),   // Arabic vowel marks
+    (0x660, 0x669),   // Arabic-Indic digits
+    (0xfb56, 0xfbff), // Persian presentation forms
+    (0xfe70, 0xfefc), // Arabic presentation forms
+    (0x2013, 0x2014), // dashes
+    (0x2018, 0x201e), // quotes
+    (0x2022, 0x2022), // bullet
+    (0x2026, 0x2026), // ellipsis
+    (0x20ac, 0x20ac), // euro
+];
 
 #[derive(Clone)]
 pub struct Font {
@@ -9,11 +39,13 @@ pub struct Font {
     pub data: Vec<u8>,
     pub meta: FontMeta,
     pub glyphs: FontGlyphs,
+    pub kerning: FontKerning,
 }
 
 #[derive(Debug, Clone)]
 pub struct FontMeta {
     pub line_height: f32,
+    pub px_range: f32, // distance range of the atlas in texels
 }
 
 #[derive(Debug, Clone)]
@@ -25,6 +57,9 @@ pub struct FontGlyph {
 
 type FontGlyphs = std::collections::HashMap<u32, FontGlyph>;
 
+// Advance adjustment in em between two characters, e.g. negative for "AV"
+type FontKerning = std::collections::HashMap<(u32, u32), f32>;
+
 impl FontGlyph {
     fn new(glyph: &JsonGlyph, size: (f32, f32)) -> Self {
         let uv = if let Some(b) = &glyph.atlas_bounds {
@@ -48,30 +83,216 @@ impl FontGlyph {
             advance: glyph.advance,
         }
     }
+
+    // Advance only, e.g. the space
+    fn blank(advance: f32) -> Self {
+        Self {
+            uv: [0.0; 4],
+            xy: [0.0; 4],
+            advance,
+        }
+    }
 }
 
 impl Font {
-    pub fn load(path: &std::path::Path) -> Result<Self> {
+    // ------------------------------------------------------------------------
+    // A pre-baked MSDF atlas (PNG + JSON, given either file) or a TrueType font
+    // that the atlas is generated from
+    pub fn load(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|e| e.to_str());
+        if extension.is_some_and(|e| e.eq_ignore_ascii_case("ttf")) {
+            let data = std::fs::read(path).with_path("reading", path)?;
+            return Self::from_ttf(&data).with_path("generating the atlas of", path);
+        }
         let png_path = path.with_extension("png");
         let (width, height, data) = load_png(&png_path)?;
 
         let size = (1.0 / width as f32, 1.0 / height as f32);
         let json_path = path.with_extension("json");
-        let (meta, glyphs) = load_json(&json_path, size)?;
+        let (meta, glyphs, kerning) = load_json(&json_path, size)?;
+
+        Ok(Self {
+            width,
+            height,
+            data,
+            meta,
+            glyphs,
+            kerning,
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    // Advance from `left` to `right` in em, including their kerning
+    pub fn advance(&self, left: u32, right: Option<u32>) -> f32 {
+        let advance = self.glyphs.get(&left).map_or(0.0, |g| g.advance);
+        let kerning = right.and_then(|right| self.kerning.get(&(left, right)));
+        advance + kerning.copied().unwrap_or_default()
+    }
+
+    // ------------------------------------------------------------------------
+    // Renders a single channel distance field of every glyph into all four
+    // channels, which the MSDF shader draws like a true multi-channel one,
+    // only with slightly rounded corners
+    pub fn from_ttf(data: &[u8]) -> Result<Self> {
+        let ttf = TrueType::parse(data)?;
+        let metrics = ttf.metrics();
+        let scale = ATLAS_EM_SIZE / metrics.units_per_em;
+        let pad = ATLAS_PX_RANGE / 2.0;
+
+        let mut cells = Vec::new();
+        let chars = ATLAS_CHARS.iter().flat_map(|&(first, last)| first..=last);
+        for c in chars {
+            let Some(id) = ttf.glyph_index(c) else {
+                continue;
+            };
+            let advance = ttf.advance(id) / metrics.units_per_em;
+            let outline = ttf.outline(id)?;
+            let points = outline.iter().flatten().flat_map(|segment| match *segment {
+                ttf::Segment::Line(a, b) => vec![a, b],
+                ttf::Segment::Quad(a, c, b) => vec![a, c, b],
+            });
+            let Some((min, max)) = points.fold(None, |bounds: Option<(V2, V2)>, p| {
+                Some(bounds.map_or((p, p), |(min, max)| (V2::min(&min, &p), V2::max(&max, &p))))
+            }) else {
+                cells.push(GlyphCell::empty(c, advance));
+                continue;
+            };
+            // whole pixels around the outline plus the distance range
+            let (x0, y0) = (
+                (min.x0() * scale - pad).floor(),
+                (min.x1() * scale - pad).floor(),
+            );
+            let (x1, y1) = (
+                (max.x0() * scale + pad).ceil(),
+                (max.x1() * scale + pad).ceil(),
+            );
+            let (width, height) = ((x1 - x0) as usize, (y1 - y0) as usize);
+            let origin = V2::new([x0 + 0.5, y1 - 0.5]) / scale;
+            let field = sdf::distance_field(&outline, width, height, origin, scale, ATLAS_PX_RANGE);
+            cells.push(GlyphCell {
+                unicode: c,
+                advance,
+                plane: [x0 + 0.5, y0 + 0.5, x1 - 0.5, y1 - 0.5].map(|v| v / ATLAS_EM_SIZE),
+                width,
+                height,
+                field,
+            });
+        }
+        if cells.is_empty() {
+            return Err(Error::Font {
+                msg: String::from("no glyphs"),
+            });
+        }
+
+        // shelves from top to bottom with a pixel of space between glyphs
+        let mut positions = Vec::with_capacity(cells.len());
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+        for cell in &cells {
+            if cell.width == 0 {
+                positions.push((0, 0));
+                continue;
+            }
+            if x + cell.width > ATLAS_WIDTH {
+                (x, y, shelf_height) = (0, y + shelf_height + 1, 0);
+            }
+            positions.push((x, y));
+            x += cell.width + 1;
+            shelf_height = shelf_height.max(cell.height);
+        }
+        let (width, height) = (ATLAS_WIDTH, y + shelf_height);
+
+        let mut data = vec![0u8; width * height * 4];
+        let mut glyphs = FontGlyphs::new();
+        let size = (1.0 / width as f32, 1.0 / height as f32);
+        for (cell, &(x, y)) in cells.iter().zip(&positions) {
+            if cell.width == 0 {
+                glyphs.insert(cell.unicode, FontGlyph::blank(cell.advance));
+                continue;
+            }
+            for (row, line) in cell.field.chunks_exact(cell.width).enumerate() {
+                let start = ((y + row) * width + x) * 4;
+                for (texel, &value) in data[start..].chunks_exact_mut(4).zip(line) {
+                    texel.fill(value);
+                }
+            }
+            // texel centers, bottom up like in the atlas JSON
+            let uv = [
+                size.0 * (x as f32 + 0.5),
+                size.1 * ((height - y - cell.height) as f32 + 0.5),
+                size.0 * ((x + cell.width) as f32 - 0.5),
+                size.1 * ((height - y) as f32 - 0.5),
+            ];
+            let glyph = FontGlyph {
+                uv,
+                xy: cell.plane,
+                advance: cell.advance,
+            };
+            glyphs.insert(cell.unicode, glyph);
+        }
 
+        let units = metrics.units_per_em;
+        let mut kerning = FontKerning::new();
+        if ttf.has_kerning() {
+            let ids: Vec<_> = cells
+                .iter()
+                .filter_map(|c| Some((c.unicode, ttf.glyph_index(c.unicode)?)))
+                .collect();
+            for &(left, left_id) in &ids {
+                for &(right, right_id) in &ids {
+                    let value = ttf.kerning(left_id, right_id);
+                    if value != 0.0 {
+                        kerning.insert((left, right), value / units);
+                    }
+                }
+            }
+        }
+        let meta = FontMeta {
+            line_height: (metrics.ascender - metrics.descender + metrics.line_gap) / units,
+            px_range: ATLAS_PX_RANGE,
+        };
+        log::info!(
+            "Generated a {width}x{height} atlas of {} glyphs, {} kerning pairs",
+            glyphs.len(),
+            kerning.len()
+        );
         Ok(Self {
             width,
             height,
             data,
             meta,
             glyphs,
+            kerning,
         })
     }
 }
 
-fn load_png(path: &std::path::Path) -> Result<(usize, usize, Vec<u8>)> {
-    let contents = std::fs::read(path)?;
-    let (png, _plte, data) = png_read::png_read(&contents)?;
+// ----------------------------------------------------------------------------
+// Distance field of one glyph before it is packed into the atlas
+struct GlyphCell {
+    unicode: u32,
+    advance: f32,
+    plane: [f32; 4], // em
+    width: usize,
+    height: usize,
+    field: Vec<u8>,
+}
+
+impl GlyphCell {
+    fn empty(unicode: u32, advance: f32) -> Self {
+        Self {
+            unicode,
+            advance,
+            plane: [0.0; 4],
+            width: 0,
+            height: 0,
+            field: Vec::new(),
+        }
+    }
+}
+
+fn load_png(path: &Path) -> Result<(usize, usize, Vec<u8>)> {
+    let contents = std::fs::read(path).with_path("reading", path)?;
+    let (png, _plte, data) = png_read::png_read(&contents).with_path("decoding", path)?;
 
     if png.color_type != png_read::PNGColorType::TrueColorAlpha {
         return Err(Error::InvalidColorFormat);
@@ -94,8 +315,18 @@ fn load_png(path: &std::path::Path) -> Result<(usize, usize, Vec<u8>)> {
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct JsonGlyphAtlas {
+    #[serde(default)]
+    pub atlas: Option<JsonAtlas>,
     pub metrics: JsonMetrics,
     pub glyphs: Vec<JsonGlyph>,
+    #[serde(default)]
+    pub kerning: Vec<JsonKerning>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonAtlas {
+    pub distance_range: f32,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -108,6 +339,14 @@ struct JsonMetrics {
     pub underline_thickness: f32,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonKerning {
+    pub unicode1: u32,
+    pub unicode2: u32,
+    pub advance: f32,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct JsonBounds {
@@ -126,9 +365,13 @@ struct JsonGlyph {
     pub atlas_bounds: Option<JsonBounds>,
 }
 
-fn load_json(path: &std::path::Path, size: (f32, f32)) -> Result<(FontMeta, FontGlyphs)> {
-    let contents = std::fs::read_to_string(path)?;
-    let atlas = serde_json::from_str::<JsonGlyphAtlas>(&contents)?;
+fn load_json(path: &Path, size: (f32, f32)) -> Result<(FontMeta, FontGlyphs, FontKerning)> {
+    let contents = std::fs::read_to_string(path).with_path("reading", path)?;
+    parse_json(&contents, size).with_path("parsing", path)
+}
+
+fn parse_json(contents: &str, size: (f32, f32)) -> Result<(FontMeta, FontGlyphs, FontKerning)> {
+    let atlas = serde_json::from_str::<JsonGlyphAtlas>(contents)?;
 
     let mut glyphs = FontGlyphs::new();
     for glyph in atlas.glyphs.iter() {
@@ -138,7 +381,43 @@ fn load_json(path: &std::path::Path, size: (f32, f32)) -> Result<(FontMeta, Font
 
     let meta = FontMeta {
         line_height: atlas.metrics.line_height,
+        px_range: atlas.atlas.map_or(DEFAULT_PX_RANGE, |a| a.distance_range),
     };
 
-    Ok((meta, glyphs))
+    let kerning = atlas.kerning.iter();
+    let kerning = kerning
+        .map(|k| ((k.unicode1, k.unicode2), k.advance))
+        .collect();
+
+    Ok((meta, glyphs, kerning))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kerning() {
+        let json = r#"{
+            "atlas": {"distanceRange": 6},
+            "metrics": {"lineHeight": 1.2, "ascender": 0.9, "descender": -0.2,
+                        "underlineY": -0.1, "underlineThickness": 0.05},
+            "glyphs": [{"unicode": 65, "advance": 0.6}, {"unicode": 86, "advance": 0.58}],
+            "kerning": [{"unicode1": 65, "unicode2": 86, "advance": -0.05}]
+        }"#;
+        let (meta, glyphs, kerning) = parse_json(json, (1.0, 1.0)).unwrap();
+        assert_eq!(meta.px_range, 6.0);
+        let font = Font {
+            width: 0,
+            height: 0,
+            data: Vec::new(),
+            meta,
+            glyphs,
+            kerning,
+        };
+        assert_eq!(font.advance(65, Some(86)), 0.6 - 0.05);
+        assert_eq!(font.advance(86, Some(65)), 0.58);
+        assert_eq!(font.advance(65, None), 0.6);
+        assert_eq!(font.advance(32, Some(65)), 0.0);
+    }
 }
@@ -1,116 +1,355 @@
 use crate::core::gl_canvas::{Canvas, GlMaterial, GlMesh, GlObject, GlTransition, Vertex};
 use crate::core::gl_pipeline::GlPipelineType;
-use crate::error::Result;
-use crate::gfx::color_conversion::{ImageGeometry, ycbcr420_to_ycbcr24};
-use crate::gfx::color_format::ColorFormat;
-use crate::scene::photo;
+use crate::error::{Error, Result, ResultExt};
+use crate::gfx::sharpen::UnsharpMask;
+use crate::scene::decoder::PhotoDecoder;
+use crate::scene::photo::{self, DecodedPhoto, Letterbox, PixelFormat};
+use crate::scene::slots::{SlotId, Slots};
+use crate::scene::text_cache::{TextCache, TextKey};
 use crate::scene::{
-    Element, Handle, Layout, Photo,
+    Element, Handle, Layout, LayoutId, Photo, SystemEvent,
     font::{Font, FontGlyph},
 };
-use crate::util::utf8::next_code_point;
-use crate::v2d::v2::V2;
+use crate::util::datetime::{DateTime, Time};
+use crate::util::utf8::{graphemes, next_code_point};
+use crate::util::{arabic, bidi};
+use crate::v2d::{rect::Rect, v2::V2};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Decoded photos kept for scenes that haven't loaded them yet, e.g. after
+// the scene changed
+const MAX_DECODED: usize = 4;
+
+// Photos the decoder works on at most. Further photos are requested once
+// some of them are decoded, `load_photo` retries when the decoded event
+// arrives.
+const MAX_DECODING: usize = 8;
+
+// Canvas ids of the font and the quad photos are drawn on, the slots of
+// materials and meshes follow
+const FONT_MATERIAL_ID: usize = 0;
+const QUAD_MESH_ID: usize = 0;
+
+// ----------------------------------------------------------------------------
+// An item of the last layout and how it is drawn, reused while it stays
+#[derive(Debug)]
+enum Draw {
+    Object(GlObject),
+    Transition(GlTransition),
+    Clock {
+        text: String,
+        handle: Option<Handle>,
+    }, // placed like the item
+    Nothing, // unsupported or freed
+}
+
+struct Drawn {
+    id: LayoutId,
+    element: Element,
+    draw: Draw,
+}
+
+// ----------------------------------------------------------------------------
+// A photo handed to the decoder and what came of it
+enum Decoding {
+    Pending,
+    Done(Arc<DecodedPhoto>),
+    Failed(String),
+}
 
 // ----------------------------------------------------------------------------
 pub struct Layouter {
     canvas: Canvas,
     font: Font,
-    materials: Vec<Option<GlMaterial>>,
-    meshes: Vec<Option<GlMesh>>,
-    free_material_ids: Vec<usize>,
-    free_mesh_ids: Vec<usize>,
+    materials: Slots<GlMaterial>,
+    meshes: Slots<GlMesh>,
     font_texture: GlMaterial,
-    quad_mesh: GlMesh,
+    drawn: Vec<Drawn>,
+    time: Time, // shown by clocks
+    sharpen: Option<UnsharpMask>,
+    letterbox: Letterbox,
+    textures_changed: bool, // contents updated in place, e.g. video frames
+    texts: TextCache,
+    decoder: Option<PhotoDecoder>,
+    decoding: Vec<(PathBuf, Decoding)>, // oldest first
+    rgba: HashSet<SlotId>,              // materials of RGBA photos, the others are YCbCr
+    backdrops: HashMap<SlotId, Handle>, // by the material of their photo
 }
 
 impl Layouter {
     // ------------------------------------------------------------------------
-    pub fn new(canvas: Canvas) -> Result<Self> {
+    pub fn new(canvas: Canvas, font: &Path) -> Result<Self> {
         let mut canvas = canvas;
-        let font = Font::load(std::path::Path::new("assets/fonts/roboto.png"))?;
-        let font_texture = canvas.create_texture(font.width, font.height, 0, &font.data)?;
+        let font = Font::load(font).context("loading the font")?;
+        let font_texture = match canvas.create_texture(font.width, font.height, 0, &font.data)? {
+            GlMaterial::Texture(id) => GlMaterial::MSDFTexture(id, font.meta.px_range),
+            material => material,
+        };
 
         let verts = create_plane_mesh();
         let quad_mesh = canvas.create_mesh(&verts)?;
+        canvas.set_material(FONT_MATERIAL_ID, Some(font_texture.clone()));
+        canvas.set_mesh(QUAD_MESH_ID, Some(quad_mesh));
 
         Ok(Self {
             canvas,
             font,
-            materials: Vec::new(),
-            meshes: Vec::new(),
-            free_material_ids: Vec::new(),
-            free_mesh_ids: Vec::new(),
+            materials: Slots::new(),
+            meshes: Slots::new(),
             font_texture,
-            quad_mesh,
+            drawn: Vec::new(),
+            time: DateTime::local_now().time,
+            sharpen: None,
+            letterbox: Letterbox::default(),
+            textures_changed: false,
+            texts: TextCache::new(),
+            decoder: None,
+            decoding: Vec::new(),
+            rgba: HashSet::new(),
+            backdrops: HashMap::new(),
         })
     }
 
     // ------------------------------------------------------------------------
+    // Uploads the photo once it's decoded, until then the handle is pending
+    // and the photo, or the first frame of a clip, is decoded in the
+    // background. Without a decoder the photo is decoded right away.
     pub fn load_photo(&mut self, photo: &Photo) -> Result<Handle> {
-        let contents = std::fs::read(&photo.path)?;
-        let frame = miniwebp::read_image(&contents)?;
-
-        let tx_width = frame.mb_width * 16;
-        let tx_height = frame.mb_height * 16;
-        let geo = ImageGeometry {
-            cx: tx_width,
-            cy: tx_height,
-            cf: ColorFormat::YCbCr420,
+        if self.decoder.is_none() {
+            let decoded = DecodedPhoto::read(&photo.path, self.sharpen.as_ref(), self.letterbox)?;
+            return self.upload_decoded(&photo.path, &decoded);
+        }
+        let Some(index) = self
+            .decoding
+            .iter()
+            .position(|(path, _)| *path == photo.path)
+        else {
+            self.prefetch(photo);
+            return Ok(Handle::PENDING);
         };
-        let yuv24 = ycbcr420_to_ycbcr24(&frame.ybuf, &frame.ubuf, &frame.vbuf, &geo);
+        match &self.decoding[index].1 {
+            Decoding::Pending => Ok(Handle::PENDING),
+            Decoding::Done(decoded) => {
+                let decoded = Arc::clone(decoded);
+                self.decoding.remove(index);
+                self.upload_decoded(&photo.path, &decoded)
+            }
+            Decoding::Failed(msg) => {
+                let msg = msg.clone();
+                self.decoding.remove(index);
+                Err(Error::Decode { msg })
+            }
+        }
+    }
 
-        let material = self.canvas.create_texture(tx_width, tx_height, 1, &yuv24)?;
-        let material_id = self.insert_material(material);
+    fn upload_decoded(&mut self, path: &Path, decoded: &DecodedPhoto) -> Result<Handle> {
+        let (cx, cy) = (decoded.cx, decoded.cy);
+        let handle = match decoded.format {
+            PixelFormat::YCbCr24 => self.upload_photo(cx, cy, &decoded.pixels)?,
+            PixelFormat::Rgba32 => self.upload_rgba_photo(cx, cy, &decoded.pixels)?,
+        };
+        let material_id = handle.material_id.unwrap_or_default();
+        log::info!("Loaded photo {path:?} as texture {material_id} ({cx}x{cy})");
+        if let Some(backdrop) = &decoded.backdrop {
+            match self.upload_photo(backdrop.cx, backdrop.cy, &backdrop.pixels) {
+                Ok(backdrop) => {
+                    self.backdrops.insert(material_id, backdrop);
+                }
+                Err(e) => log::warn!("Can't upload the backdrop of {path:?}: {e:?}"),
+            }
+        }
+        Ok(handle)
+    }
 
-        log::info!(
-            "Loaded photo {:?} as texture {material_id} ({}x{})",
-            photo.path,
-            tx_width,
-            tx_height
-        );
+    // ------------------------------------------------------------------------
+    // Drawn below the photo to fill the letterbox, freed with the photo
+    pub fn backdrop(&self, photo: &Handle) -> Option<Handle> {
+        let id = photo.material_id?;
+        self.backdrops.get(&id).copied()
+    }
+
+    // ------------------------------------------------------------------------
+    // Starts decoding a photo that is shown soon, see `load_photo`
+    pub fn prefetch(&mut self, photo: &Photo) {
+        let Some(decoder) = &self.decoder else {
+            return;
+        };
+        if self.decoding.iter().any(|(path, _)| *path == photo.path) {
+            return;
+        }
+        let pending = self
+            .decoding
+            .iter()
+            .filter(|(_, d)| matches!(d, Decoding::Pending));
+        if pending.count() >= MAX_DECODING {
+            log::debug!("Decoder busy, not decoding {:?} yet", photo.path);
+            return;
+        }
+        match decoder.request(&photo.path) {
+            Ok(()) => self.decoding.push((photo.path.clone(), Decoding::Pending)),
+            Err(e) => log::warn!("Can't decode {:?}: {e:?}", photo.path),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Keeps the results of the decoder, false for other events
+    pub fn handle_decoded(&mut self, event: &SystemEvent) -> bool {
+        let (path, result) = match event {
+            SystemEvent::PhotoDecoded { path, photo } => (path, Decoding::Done(Arc::clone(photo))),
+            SystemEvent::PhotoFailed { path, error } => (path, Decoding::Failed(error.clone())),
+            _ => return false,
+        };
+        match self.decoding.iter_mut().find(|(p, _)| p == path) {
+            Some((_, decoding)) => *decoding = result,
+            None => log::debug!("Dropping decoded photo {path:?}"),
+        }
+        let is_done = |(_, decoding): &(PathBuf, Decoding)| !matches!(decoding, Decoding::Pending);
+        while self.decoding.iter().filter(|entry| is_done(entry)).count() > MAX_DECODED {
+            if let Some(oldest) = self.decoding.iter().position(is_done) {
+                self.decoding.remove(oldest);
+            }
+        }
+        true
+    }
+
+    // ------------------------------------------------------------------------
+    // Photos are decoded on the thread of `decoder` from now on
+    pub fn set_decoder(&mut self, decoder: PhotoDecoder) {
+        self.decoder = Some(decoder);
+    }
+
+    // ------------------------------------------------------------------------
+    // Downscales (and sharpens) decoded photos that exceed the maximum size
+    pub fn prepare_photo(&self, cx: usize, cy: usize, yuv24: Vec<u8>) -> (usize, usize, Vec<u8>) {
+        photo::prepare(cx, cy, yuv24, self.sharpen.as_ref())
+    }
 
+    // ------------------------------------------------------------------------
+    pub fn upload_photo(&mut self, cx: usize, cy: usize, yuv24: &[u8]) -> Result<Handle> {
+        let material = self.canvas.create_texture(cx, cy, 1, yuv24)?;
+        let material_id = self.insert_material(material);
         Ok(Handle {
             material_id: Some(material_id),
             mesh_id: None,
-            aspect_ratio: tx_width as f32 / tx_height as f32,
+            aspect_ratio: cx as f32 / cy as f32,
+            pending: false,
         })
     }
 
     // ------------------------------------------------------------------------
+    // Drawn with the RGBA pipeline, transparent parts show what is below
+    pub fn upload_rgba_photo(&mut self, cx: usize, cy: usize, rgba32: &[u8]) -> Result<Handle> {
+        let material = self.canvas.create_texture(cx, cy, 0, rgba32)?;
+        let material_id = self.insert_material(material);
+        self.rgba.insert(material_id);
+        Ok(Handle {
+            material_id: Some(material_id),
+            mesh_id: None,
+            aspect_ratio: cx as f32 / cy as f32,
+            pending: false,
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    // Replaces the image of a photo with one of the same size, e.g. the next
+    // frame of a video clip
+    pub fn update_photo(
+        &mut self,
+        handle: &Handle,
+        cx: usize,
+        cy: usize,
+        yuv24: &[u8],
+    ) -> Result<()> {
+        let material = self
+            .get_material(handle)
+            .ok_or(Error::InvalidPhotoId)?
+            .clone();
+        self.textures_changed = true;
+        self.canvas.update_texture(&material, cx, cy, 1, yuv24)
+    }
+
+    // ------------------------------------------------------------------------
+    // True if a texture was updated in place since the last call
+    pub fn take_textures_changed(&mut self) -> bool {
+        std::mem::take(&mut self.textures_changed)
+    }
+
+    // ------------------------------------------------------------------------
+    // Handles freed before are ignored, their ids may belong to others by now
     pub fn free_handle(&mut self, handle: Handle) {
-        if let Some(id) = handle.material_id
-            && let Some(material) = self.materials.get(id).and_then(|m| m.as_ref())
-        {
-            self.canvas.delete_material(material);
-            self.materials[id] = None;
-            self.free_material_ids.push(id);
+        if let Some(id) = handle.material_id {
+            self.rgba.remove(&id);
+            if let Some(backdrop) = self.backdrops.remove(&id) {
+                self.free_handle(backdrop);
+            }
+            match self.materials.remove(id) {
+                Some(material) => {
+                    self.canvas.delete_material(&material);
+                    self.canvas.set_material(material_canvas_id(id), None);
+                }
+                None => log::warn!("Freeing stale texture {id}"),
+            }
         }
 
-        if let Some(id) = handle.mesh_id
-            && let Some(mesh) = self.meshes.get(id).and_then(|m| m.as_ref())
-        {
-            self.canvas.delete_mesh(mesh);
-            self.meshes[id] = None;
-            self.free_mesh_ids.push(id);
+        // text meshes are shared and stay around for a while after their last use
+        let mesh_ids = match handle.mesh_id {
+            Some(id) if self.texts.contains_mesh(id) => self.texts.release(id),
+            Some(id) => vec![id],
+            None => Vec::new(),
+        };
+        for id in mesh_ids {
+            self.delete_mesh(id);
+        }
+    }
+
+    fn delete_mesh(&mut self, id: SlotId) {
+        match self.meshes.remove(id) {
+            Some(mesh) => {
+                self.canvas.delete_mesh(&mesh);
+                self.canvas.set_mesh(mesh_canvas_id(id), None);
+            }
+            None => log::warn!("Freeing stale mesh {id}"),
+        }
+    }
+
+    fn text_handle(mesh_id: SlotId) -> Handle {
+        Handle {
+            material_id: None,
+            mesh_id: Some(mesh_id),
+            aspect_ratio: 0.0,
+            pending: false,
         }
     }
 
     // ------------------------------------------------------------------------
+    // Hebrew and Arabic are reordered for display, Arabic letters shaped
     pub fn create_text(&mut self, text: &str) -> Result<Handle> {
-        let mut iter = text.as_bytes().iter();
+        self.create_fitted_text(text, f32::INFINITY)
+    }
+
+    // ------------------------------------------------------------------------
+    // Like `create_text`, cut at a grapheme and ended with an ellipsis where
+    // wider than `max_width` em
+    pub fn create_fitted_text(&mut self, text: &str, max_width: f32) -> Result<Handle> {
+        let key = TextKey::line(text, max_width);
+        if let Some(mesh_id) = self.texts.acquire(&key) {
+            return Ok(Self::text_handle(mesh_id));
+        }
+
+        let measure = |s: &str| self.word_width(&arabic::shape(s));
+        let fitted = truncate(text, max_width, measure, self.ellipsis());
+        let line = bidi::reorder(&arabic::shape(&fitted), bidi::direction(text));
         let mut pos = V2::new([0.0, 0.0]);
         let mut verts = Vec::new();
-        while let Some(ch) = next_code_point(&mut iter) {
-            if let Some(glyph) = self.font.glyphs.get(&ch) {
-                Self::add_glyph(glyph, &pos, &mut verts);
-                pos += V2::new([glyph.advance, 0.0]);
-            }
-        }
+        self.add_word(&line, &mut pos, &mut verts);
 
         let mesh = self.canvas.create_mesh(&verts)?;
         let mesh_id = self.insert_mesh(mesh.clone());
 
-        log::info!(
+        log::debug!(
             "Created text mesh '{}' as id {mesh_id}, vao/vbo {}/{} ({} vertices)",
             text,
             mesh.vao,
@@ -118,15 +357,30 @@ impl Layouter {
             verts.len()
         );
 
-        Ok(Handle {
-            material_id: None,
-            mesh_id: Some(mesh_id),
-            aspect_ratio: 0.0,
-        })
+        self.texts.insert(key, mesh_id);
+        Ok(Self::text_handle(mesh_id))
+    }
+
+    // ------------------------------------------------------------------------
+    // Width of a line of text in em
+    pub fn text_width(&self, text: &str) -> f32 {
+        self.word_width(&arabic::shape(text))
     }
 
     // ------------------------------------------------------------------------
-    pub fn create_multiline_text(&mut self, text: &str, max_width: f32) -> Result<Handle> {
+    // Text broken into lines of `max_width` em at spaces. Beyond `max_lines`
+    // the last line ends with an ellipsis, as do words wider than a line.
+    pub fn create_multiline_text(
+        &mut self,
+        text: &str,
+        max_width: f32,
+        max_lines: Option<usize>,
+    ) -> Result<Handle> {
+        let key = TextKey::lines(text, max_width, max_lines);
+        if let Some(mesh_id) = self.texts.acquire(&key) {
+            return Ok(Self::text_handle(mesh_id));
+        }
+
         let mut lines = Vec::new();
         let mut line = Vec::new();
         let mut line_width = 0.0;
@@ -134,18 +388,14 @@ impl Layouter {
         let space_width = self.font.glyphs.get(&32).map_or(0.0, |g| g.advance);
         let line_height = self.font.meta.line_height;
 
-        let words = text.split_whitespace();
-        for word in words {
-            let mut iter = word.as_bytes().iter();
-            let mut word_width = 0.0;
-            while let Some(ch) = next_code_point(&mut iter) {
-                if let Some(glyph) = self.font.glyphs.get(&ch) {
-                    word_width += glyph.advance;
-                }
-            }
+        // lines are broken in logical order and reordered one by one
+        let direction = bidi::direction(text);
+        let shaped = arabic::shape(text);
+        for word in shaped.split_whitespace() {
+            let word_width = self.word_width(word);
 
             line_width += word_width;
-            if line_width > max_width {
+            if line_width > max_width && !line.is_empty() {
                 lines.push(line);
                 line = Vec::new();
                 line_width = word_width;
@@ -153,35 +403,34 @@ impl Layouter {
                 line_width += space_width;
             }
 
-            line.push(word.to_string());
+            line.push(word);
         }
 
         if !line.is_empty() {
             lines.push(line);
         }
+        let mut lines: Vec<String> = lines.iter().map(|line| line.join(" ")).collect();
+        if let Some(max_lines) = max_lines.filter(|&n| n > 0 && lines.len() > n) {
+            let rest = lines.split_off(max_lines).join(" ");
+            lines[max_lines - 1] = format!("{} {rest}", lines[max_lines - 1]);
+        }
 
         let line_count = lines.len() as f32;
 
         let mut verts = Vec::new();
         let mut pos = V2::new([0.0, (line_count - 1.0) * line_height]);
+        let ellipsis = self.ellipsis();
         for line in lines {
-            for word in line {
-                let mut iter = word.as_bytes().iter();
-                while let Some(ch) = next_code_point(&mut iter) {
-                    if let Some(glyph) = self.font.glyphs.get(&ch) {
-                        Self::add_glyph(glyph, &pos, &mut verts);
-                        pos += V2::new([glyph.advance, 0.0]);
-                    }
-                }
-                pos += V2::new([space_width, 0.0]);
-            }
+            let line = truncate(&line, max_width, |s| self.word_width(s), ellipsis);
+            let line = bidi::reorder(&line, direction);
+            self.add_word(&line, &mut pos, &mut verts);
             pos = V2::new([0.0, pos.x1() - line_height]);
         }
 
         let mesh = self.canvas.create_mesh(&verts)?;
         let mesh_id = self.insert_mesh(mesh.clone());
 
-        log::info!(
+        log::debug!(
             "Created text mesh '{}' as id {mesh_id}, vao/vbo {}/{} ({} vertices)",
             text,
             mesh.vao,
@@ -189,79 +438,218 @@ impl Layouter {
             verts.len()
         );
 
-        Ok(Handle {
-            material_id: None,
-            mesh_id: Some(mesh_id),
-            aspect_ratio: 0.0,
-        })
+        self.texts.insert(key, mesh_id);
+        Ok(Self::text_handle(mesh_id))
     }
 
     // ------------------------------------------------------------------------
+    // Items are compared with the last layout by id, only new and changed
+    // ones are prepared again and the canvas is left alone without changes
     pub fn update_layout(&mut self, layout: &Layout) {
-        let mut objects = Vec::new();
-        let mut transitions = Vec::new();
-
-        let mut materials = vec![self.font_texture.clone()];
-        let font_material_id = 0;
-
-        let mut meshes = vec![self.quad_mesh.clone()];
-        let quad_mesh_id = 0;
+        let previous_ids: Vec<LayoutId> = self.drawn.iter().map(|drawn| drawn.id).collect();
+        let mut previous: HashMap<LayoutId, Drawn> = HashMap::new();
+        for drawn in std::mem::take(&mut self.drawn) {
+            if let Some(duplicate) = previous.insert(drawn.id, drawn) {
+                self.release(duplicate.draw);
+            }
+        }
+        // items in another order are drawn in another order
+        let mut changed = !layout.items.iter().map(|item| item.id).eq(previous_ids);
 
         for item in &layout.items {
-            match &item.element {
-                Element::Picture(picture) => {
-                    if let Some(material) = self.get_material(&picture.handle) {
-                        let material_id = materials.len();
-                        materials.push(material.clone());
-
-                        let object = GlObject {
-                            mesh_id: quad_mesh_id,
-                            pipeline_id: GlPipelineType::YUVTex.into(),
-                            material_id,
-                            transform: photo::transform(&picture.dst),
-                        };
-                        objects.push(object);
-                    }
+            let kept = match previous.remove(&item.id) {
+                Some(drawn) if drawn.element == item.element && self.is_live(&item.element) => {
+                    Some(drawn)
                 }
-                Element::Text(text) => {
-                    if let Some(mesh) = self.get_mesh(&text.handle) {
-                        let mesh_id = meshes.len();
-                        meshes.push(mesh.clone());
-                        let object = GlObject {
-                            mesh_id,
-                            pipeline_id: GlPipelineType::MSDFTex.into(),
-                            material_id: font_material_id,
-                            transform: photo::transform(&text.dst),
-                        };
-                        objects.push(object);
-                    }
+                Some(drawn) => {
+                    self.release(drawn.draw);
+                    None
                 }
-                Element::Transition(transition) => {
-                    let from = self.get_material(&transition.from);
-                    let to = self.get_material(&transition.to);
-                    if let Some((from, to)) = Option::zip(from, to) {
-                        materials.push(from.clone());
-                        materials.push(to.clone());
-
-                        let transition = GlTransition {
-                            mesh_id: quad_mesh_id,
-                            pipeline_id: 0,
-                            from_id: materials.len() - 2,
-                            to_id: materials.len() - 1,
-                            progress: transition.progress,
-                            from_pos: transition.from_dst.pos,
-                            from_size: transition.from_dst.size,
-                            to_pos: transition.to_dst.pos,
-                            to_size: transition.to_dst.size,
-                        };
-                        transitions.push(transition);
-                    }
+                None => None,
+            };
+            changed |= kept.is_none();
+            let drawn = match kept {
+                Some(drawn) => drawn,
+                None => Drawn {
+                    id: item.id,
+                    element: item.element.clone(),
+                    draw: self.draw(&item.element),
+                },
+            };
+            self.drawn.push(drawn);
+        }
+        for drawn in previous.into_values() {
+            self.release(drawn.draw);
+        }
+        if changed {
+            self.update_canvas();
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Regenerates the text of clocks that show another time by now, true if
+    // any did
+    pub fn update_clocks(&mut self, time: &Time) -> bool {
+        self.time = *time;
+        let mut changed = false;
+        for i in 0..self.drawn.len() {
+            let Drawn {
+                element: Element::Clock(clock),
+                draw: Draw::Clock { text, .. },
+                ..
+            } = &self.drawn[i]
+            else {
+                continue;
+            };
+            let now = clock.format.text(time);
+            if now == *text {
+                continue;
+            }
+            let draw = self.draw_clock(now);
+            let shown = std::mem::replace(&mut self.drawn[i].draw, draw);
+            self.release(shown);
+            changed = true;
+        }
+        if changed {
+            self.update_canvas();
+        }
+        changed
+    }
+
+    fn update_canvas(&mut self) {
+        let mut objects = Vec::new();
+        let mut transitions = Vec::new();
+        for drawn in &self.drawn {
+            match (&drawn.draw, &drawn.element) {
+                (Draw::Object(object), _) => objects.push(object.clone()),
+                (Draw::Transition(transition), _) => transitions.push(transition.clone()),
+                (
+                    Draw::Clock {
+                        handle: Some(handle),
+                        ..
+                    },
+                    Element::Clock(clock),
+                ) => objects.extend(self.text_object(handle, &clock.dst, clock.opacity)),
+                _ => {}
+            }
+        }
+        self.canvas.update(objects, transitions);
+    }
+
+    fn draw(&mut self, element: &Element) -> Draw {
+        match element {
+            Element::Picture(picture) => match picture.handle.material_id {
+                Some(id) if self.materials.get(id).is_some() => Draw::Object(GlObject {
+                    mesh_id: QUAD_MESH_ID,
+                    pipeline_id: match self.rgba.contains(&id) {
+                        true => GlPipelineType::RGBATex.into(),
+                        false => GlPipelineType::YUVTex.into(),
+                    },
+                    material_id: material_canvas_id(id),
+                    transform: photo::transform(&picture.dst),
+                    opacity: picture.opacity,
+                    src: picture.src,
+                }),
+                _ => Draw::Nothing,
+            },
+            Element::Text(text) => match self.text_object(&text.handle, &text.dst, text.opacity) {
+                Some(object) => Draw::Object(object),
+                None => Draw::Nothing,
+            },
+            Element::Clock(clock) => self.draw_clock(clock.format.text(&self.time)),
+            Element::Transition(transition) => {
+                let live = |handle: &Handle| {
+                    handle
+                        .material_id
+                        .filter(|&id| self.materials.get(id).is_some())
+                };
+                match Option::zip(live(&transition.from), live(&transition.to)) {
+                    Some((from, to)) => Draw::Transition(GlTransition {
+                        mesh_id: QUAD_MESH_ID,
+                        pipeline_id: transition.kind.into(),
+                        from_id: material_canvas_id(from),
+                        to_id: material_canvas_id(to),
+                        progress: transition.progress,
+                        easing: transition.easing,
+                        from_pos: transition.from_dst.pos,
+                        from_size: transition.from_dst.size,
+                        to_pos: transition.to_dst.pos,
+                        to_size: transition.to_dst.size,
+                        from_src: transition.from_src,
+                        to_src: transition.to_src,
+                        from_rgba: self.rgba.contains(&from),
+                        to_rgba: self.rgba.contains(&to),
+                    }),
+                    None => Draw::Nothing,
                 }
-                _ => {} // Unsupported element types
             }
+            _ => Draw::Nothing, // Unsupported element types
         }
+    }
 
-        self.canvas.update(objects, transitions, materials, meshes);
+    fn draw_clock(&mut self, text: String) -> Draw {
+        let handle = self
+            .create_text(&text)
+            .inspect_err(|e| log::warn!("Failed to create clock text '{text}': {e:?}"))
+            .ok();
+        Draw::Clock { text, handle }
+    }
+
+    fn text_object(&self, handle: &Handle, dst: &Rect, opacity: f32) -> Option<GlObject> {
+        let id = handle.mesh_id.filter(|&id| self.meshes.get(id).is_some())?;
+        Some(GlObject {
+            mesh_id: mesh_canvas_id(id),
+            pipeline_id: GlPipelineType::MSDFTex.into(),
+            material_id: FONT_MATERIAL_ID,
+            transform: photo::transform(dst),
+            opacity,
+            src: Rect::unit(),
+        })
+    }
+
+    // Frees what the layouter created to draw an item
+    fn release(&mut self, draw: Draw) {
+        if let Draw::Clock {
+            handle: Some(handle),
+            ..
+        } = draw
+        {
+            self.free_handle(handle);
+        }
+    }
+
+    // False if the element shows a freed handle, its slot may be reused
+    fn is_live(&self, element: &Element) -> bool {
+        match element {
+            Element::Picture(picture) => self.get_material(&picture.handle).is_some(),
+            Element::Text(text) => self.get_mesh(&text.handle).is_some(),
+            Element::Transition(transition) => {
+                self.get_material(&transition.from).is_some()
+                    && self.get_material(&transition.to).is_some()
+            }
+            _ => true,
+        }
+    }
+
+    // Sharpening applied to downscaled photos, None to keep them as they are
+    pub fn set_sharpen(&mut self, sharpen: Option<UnsharpMask>) {
+        self.sharpen = sharpen;
+    }
+
+    // Backdrops of photos read without a decoder, see `PhotoDecoder::start`
+    pub fn set_letterbox(&mut self, letterbox: Letterbox) {
+        self.letterbox = letterbox;
+    }
+
+    // Distance range of the font atlas in texels, None for the one of the atlas
+    pub fn set_px_range(&mut self, px_range: Option<f32>) {
+        let px_range = px_range.unwrap_or(self.font.meta.px_range);
+        if let GlMaterial::MSDFTexture(_, range) = &mut self.font_texture {
+            *range = px_range;
+        }
+        let font_texture = self.font_texture.clone();
+        self.canvas
+            .set_material(FONT_MATERIAL_ID, Some(font_texture));
     }
 
     pub fn canvas(&self) -> &Canvas {
@@ -276,44 +664,57 @@ impl Layouter {
         self.canvas.resize(aspect_ratio);
     }
 
-    fn insert_material(&mut self, material: GlMaterial) -> usize {
-        if let Some(id) = self.free_material_ids.pop() {
-            assert!(id < self.materials.len());
-            assert!(self.materials[id].is_none());
-            self.materials[id] = Some(material);
-            id
-        } else {
-            self.materials.push(Some(material));
-            self.materials.len() - 1
-        }
+    fn insert_material(&mut self, material: GlMaterial) -> SlotId {
+        let id = self.materials.insert(material.clone());
+        self.canvas
+            .set_material(material_canvas_id(id), Some(material));
+        id
+    }
+
+    fn insert_mesh(&mut self, mesh: GlMesh) -> SlotId {
+        let id = self.meshes.insert(mesh.clone());
+        self.canvas.set_mesh(mesh_canvas_id(id), Some(mesh));
+        id
     }
 
     fn get_material(&self, handle: &Handle) -> Option<&GlMaterial> {
-        if let Some(material_id) = handle.material_id {
-            self.materials.get(material_id).and_then(|m| m.as_ref())
-        } else {
-            None
+        handle.material_id.and_then(|id| self.materials.get(id))
+    }
+
+    fn get_mesh(&self, handle: &Handle) -> Option<&GlMesh> {
+        handle.mesh_id.and_then(|id| self.meshes.get(id))
+    }
+
+    // Glyphs of `text` from `pos` on, kerned pairwise
+    fn add_word(&self, text: &str, pos: &mut V2, verts: &mut Vec<Vertex>) {
+        let mut chars = text.as_bytes().iter();
+        let mut next = next_code_point(&mut chars);
+        while let Some(ch) = next {
+            next = next_code_point(&mut chars);
+            if let Some(glyph) = self.font.glyphs.get(&ch) {
+                Self::add_glyph(glyph, pos, verts);
+                *pos += V2::new([self.font.advance(ch, next), 0.0]);
+            }
         }
     }
 
-    fn insert_mesh(&mut self, mesh: GlMesh) -> usize {
-        if let Some(id) = self.free_mesh_ids.pop() {
-            assert!(id < self.meshes.len());
-            assert!(self.meshes[id].is_none());
-            self.meshes[id] = Some(mesh);
-            id
-        } else {
-            self.meshes.push(Some(mesh));
-            self.meshes.len() - 1
+    // Three dots for fonts without the ellipsis
+    fn ellipsis(&self) -> &'static str {
+        match self.font.glyphs.contains_key(&0x2026) {
+            true => "\u{2026}",
+            false => "...",
         }
     }
 
-    fn get_mesh(&self, handle: &Handle) -> Option<&GlMesh> {
-        if let Some(mesh_id) = handle.mesh_id {
-            self.meshes.get(mesh_id).and_then(|m| m.as_ref())
-        } else {
-            None
+    fn word_width(&self, word: &str) -> f32 {
+        let mut chars = word.as_bytes().iter();
+        let mut width = 0.0;
+        let mut next = next_code_point(&mut chars);
+        while let Some(ch) = next {
+            next = next_code_point(&mut chars);
+            width += self.font.advance(ch, next);
         }
+        width
     }
 
     fn add_glyph(glyph: &FontGlyph, pos: &V2, verts: &mut Vec<Vertex>) {
@@ -343,6 +744,42 @@ impl Layouter {
     }
 }
 
+// ----------------------------------------------------------------------------
+fn material_canvas_id(id: SlotId) -> usize {
+    FONT_MATERIAL_ID + 1 + id.index
+}
+
+fn mesh_canvas_id(id: SlotId) -> usize {
+    QUAD_MESH_ID + 1 + id.index
+}
+
+// --------------------------------------------------------------------------------
+// `text` cut at a grapheme so that it fits into `max_width` together with
+// `ellipsis`, measured by `width`
+fn truncate<'a>(
+    text: &'a str,
+    max_width: f32,
+    width: impl Fn(&str) -> f32,
+    ellipsis: &str,
+) -> Cow<'a, str> {
+    if width(text) <= max_width {
+        return Cow::Borrowed(text);
+    }
+    let ends = graphemes(text).scan(0, |end, grapheme| {
+        *end += grapheme.len();
+        Some(*end)
+    });
+    let mut fitted = String::from(ellipsis);
+    for end in ends {
+        let candidate = format!("{}{ellipsis}", text[..end].trim_end());
+        if width(&candidate) > max_width {
+            break;
+        }
+        fitted = candidate;
+    }
+    Cow::Owned(fitted)
+}
+
 // --------------------------------------------------------------------------------
 fn add_plane_quad(verts: &mut Vec<Vertex>, uv: V2, u: f32, v: f32, xy: V2, x: f32, y: f32) {
     #[rustfmt::skip]
@@ -366,3 +803,23 @@ fn create_plane_mesh() -> Vec<Vertex> {
         Vertex { pos: V2::new([1.0, 1.0]), tex: V2::new([1.0, 0.0]) },
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        let width = |s: &str| graphemes(s).count() as f32;
+        assert_eq!(truncate("Beach", 5.0, width, "…"), "Beach");
+        assert_eq!(truncate("Beach day", 6.0, width, "…"), "Beach…");
+        assert_eq!(truncate("Beach day", 8.0, width, "…"), "Beach d…");
+        // accents and emoji stay whole
+        assert_eq!(
+            truncate("Cafe\u{301} au lait", 5.0, width, "…"),
+            "Cafe\u{301}…"
+        );
+        assert_eq!(truncate("👍🏽👍🏽👍🏽", 2.0, width, "…"), "👍🏽…");
+        assert_eq!(truncate("Beach", 0.5, width, "…"), "…");
+    }
+}
@@ -1,34 +1,98 @@
+use crate::app::AppConfig;
+use crate::core::frame_stats::FrameStats;
 use crate::core::gl_canvas::Canvas;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::library;
 use crate::scene::{
-    Context, Layout, Layouter, Scene, SceneEvent, photo, slideshow::create_slideshow_all,
+    Context, FrameEvent, Layout, Layouter, Scene, SceneEvent, SceneKind, UserEvent,
+    animator::LayoutAnimator,
+    clock::ClockScene,
+    demo,
+    error::{self, ErrorScene},
+    overlay::{ClockOverlay, DebugOverlay},
+    photo::{self, MetaUpdate},
+    registry::SceneRegistry,
+    rotation::Rotation,
+    schedule::Schedule,
+    slideshow::{PhotoFilter, SlideTiming, create_daily_slideshow, create_slideshow_all},
 };
 use crate::util::datetime::DateTime;
-use std::path::Path;
+use crate::util::locale::LocaleOverride;
+use crate::util::random;
+use std::path::PathBuf;
 
 pub struct SceneManager {
     scene: Option<Box<dyn Scene>>,
     context: Context,
     layouter: Layouter,
     layout: Layout,
+    animator: LayoutAnimator,
+    overlay: Option<DebugOverlay>,
+    clock_overlay: Option<ClockOverlay>, // on top of all scenes but the clock
+    events: Vec<FrameEvent>,
+    photo: Option<usize>,
+    paused: bool,
+    kind: SceneKind,
+    schedule: Schedule,
+    default_scene: SceneKind, // shown when no schedule rule matches
+    scheduled: SceneKind,     // chosen by the schedule at the last check
+    scheduled_rule: Option<usize>,
+    rotation: Rotation,         // of the scene keys and the dwell times
+    dwell_left: Option<f32>,    // seconds until the rotation moves on
+    announcements: Vec<String>, // to be spoken, from schedule rules
+    scene_fade: Option<f32>,    // seconds the next scene layout fades in
+    layout_dirty: bool,         // the layouter needs the layout again
+    moving: bool,               // the layout changed with the last time step
+    timing: SlideTiming,        // of new slideshows
+    filter: PhotoFilter,        // of the photos of the built-in slideshows
+    photo_dirs: Vec<PathBuf>,
+    retry_in: Option<f32>,     // seconds until the failed scene is retried
+    admin_url: Option<String>, // of the settings page, shown with errors
+    registry: SceneRegistry,   // last, plugins are shut down after their scenes
 }
 
 impl SceneManager {
-    pub fn new(layouter: Layouter, photo_dir: &Path) -> Result<Self> {
-        let photos = photo::read_webp_photos(photo_dir);
-
+    pub fn new(
+        layouter: Layouter,
+        config: &AppConfig,
+        registry: SceneRegistry,
+        admin_url: Option<String>,
+    ) -> Result<Self> {
         let mut layouter = layouter;
+        let mut registry = registry;
+        registry.init(&config.scene_config);
 
         let context = Context {
-            photos,
+            photos: read_photos(&config.photo_dirs),
             time: DateTime::now(),
             weather: None,
-            locale: Box::new(crate::util::locale::LocaleUs {}),
+            locale: Box::new(LocaleOverride {
+                base: config.locale.locale(),
+                time_format: config.time_format,
+            }),
+            units: config.units,
         };
 
-        let mut scene = create_slideshow_all(&context)
-            .ok()
-            .map(|s| Box::new(s) as Box<dyn Scene>);
+        let timing = slide_timing(config, random::time_seed());
+        let now = DateTime::local_now();
+        let start_scene = config.schedule.scene_at(now, &config.start_scene).clone();
+        let filter = &config.photo_filter;
+        let scene = create_scene(&start_scene, &context, &registry, timing, filter).or_else(|e| {
+            log::error!("Failed to create scene {start_scene:?}: {e:?}");
+            create_scene(&SceneKind::AllPhotos, &context, &registry, timing, filter)
+        });
+        let (scene, retry_in) = match scene {
+            Ok(scene) => (scene, None),
+            Err(e) => {
+                log::error!("No scene to show: {e:?}");
+                let scene = ErrorScene::new(&e, admin_url.as_deref());
+                (
+                    Box::new(scene) as Box<dyn Scene>,
+                    Some(error::RETRY_INTERVAL),
+                )
+            }
+        };
+        let mut scene = Some(scene);
 
         let mut layout = Layout::empty();
         update_scene(
@@ -39,23 +103,395 @@ impl SceneManager {
             &mut layout,
         );
 
-        Ok(Self {
+        let mut animator = LayoutAnimator::new();
+        animator.set_layout(&layout);
+
+        let rotation = rotation(config, &registry);
+        let dwell_left = dwell_time(&rotation, &start_scene);
+        let mut manager = Self {
             scene,
             context,
             layouter,
             layout,
-        })
+            animator,
+            overlay: None,
+            clock_overlay: None,
+            events: Vec::new(),
+            photo: None,
+            paused: false,
+            kind: start_scene.clone(),
+            schedule: config.schedule.clone(),
+            default_scene: config.start_scene.clone(),
+            scheduled: start_scene,
+            scheduled_rule: config.schedule.rule_at(now),
+            rotation,
+            dwell_left,
+            announcements: Vec::new(),
+            scene_fade: None,
+            layout_dirty: true,
+            moving: false,
+            timing,
+            filter: config.photo_filter.clone(),
+            photo_dirs: config.photo_dirs.clone(),
+            retry_in,
+            admin_url,
+            registry,
+        };
+        if let Some(scene) = &manager.scene {
+            manager.events.push(FrameEvent::SceneChanged {
+                name: scene.name().to_string(),
+            });
+        }
+        manager.check_photo();
+        if config.debug_overlay {
+            manager.toggle_overlay();
+        }
+        manager.set_clock_overlay(config);
+        manager.apply_layout();
+        Ok(manager)
     }
 
     pub fn update(&mut self, event: &SceneEvent) {
         self.context.time = DateTime::now();
-        update_scene(
+        let mut changed = false;
+        if let SceneEvent::System(event) = event {
+            self.layouter.handle_decoded(event);
+        }
+        if let SceneEvent::TimeTick(dt) = event {
+            changed = self.animator.advance(*dt);
+            // video frames are uploaded in place and show without a new layout
+            self.moving = changed | self.layouter.take_textures_changed();
+            let now = DateTime::local_now();
+            self.layout_dirty |= self.layouter.update_clocks(&now.time);
+            if let Some(overlay) = self.clock_overlay.as_mut() {
+                self.layout_dirty |= overlay.refresh(&now, &mut self.layouter);
+            }
+            self.check_announcements();
+            self.check_retry(*dt);
+            // animations still finish, the scene itself stands still
+            if self.paused {
+                if changed {
+                    self.apply_layout();
+                }
+                return;
+            }
+            self.check_schedule();
+            self.check_rotation(*dt);
+        }
+        if update_scene(
             &mut self.scene,
             event,
             &self.context,
             &mut self.layouter,
             &mut self.layout,
+        ) {
+            self.set_animator_layout();
+            self.check_photo();
+            self.moving |= matches!(event, SceneEvent::TimeTick(_));
+            changed = true;
+        }
+        if changed {
+            self.apply_layout();
+        }
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // The layout changed with the last time step
+    pub fn is_moving(&self) -> bool {
+        self.moving
+    }
+
+    // The next frame differs from the last one
+    pub fn needs_redraw(&self) -> bool {
+        self.layout_dirty || self.moving
+    }
+
+    // Replaces the current scene, keeps the current one if `kind` can't be
+    // created. Without one the error scene is shown and `kind` is retried.
+    pub fn switch_scene(&mut self, kind: SceneKind) {
+        let scene = create_scene(
+            &kind,
+            &self.context,
+            &self.registry,
+            self.timing,
+            &self.filter,
         );
+        let scene = match scene {
+            Ok(scene) => scene,
+            Err(e) => {
+                log::error!("Failed to switch to scene {kind:?}: {e:?}");
+                if self.scene.is_none() {
+                    self.kind = kind;
+                    self.show_error(&e);
+                }
+                return;
+            }
+        };
+        self.retry_in = None;
+        self.dwell_left = dwell_time(&self.rotation, &kind);
+        self.kind = kind;
+        self.set_scene(scene);
+    }
+
+    // ------------------------------------------------------------------------
+    // Keys switch scenes: `Next` and `Previous` step through the rotation,
+    // `Home` returns to the start scene. Other events reach the scene.
+    pub fn handle_user_event(&mut self, event: UserEvent) {
+        match event {
+            UserEvent::Next => self.step_scene(1),
+            UserEvent::Previous => self.step_scene(-1),
+            UserEvent::Home if self.kind != self.default_scene => {
+                self.switch_scene(self.default_scene.clone())
+            }
+            _ => self.update(&SceneEvent::User(event)),
+        }
+    }
+
+    // Switches to the scene `steps` entries after the current one in the
+    // rotation
+    pub fn step_scene(&mut self, steps: isize) {
+        let current = self.rotation.position(&self.kind);
+        if let Some(next) = self.rotation.step(current, steps) {
+            self.switch_scene(self.rotation.entries[next].scene.clone());
+        }
+    }
+
+    // Moves on when the dwell time of the scene is over, not while a schedule
+    // rule holds its scene
+    fn check_rotation(&mut self, dt: f32) {
+        if self.scheduled_rule.is_some() || self.retry_in.is_some() {
+            return;
+        }
+        let Some(dwell_left) = self.dwell_left.as_mut() else {
+            return;
+        };
+        *dwell_left -= dt;
+        if *dwell_left <= 0.0 {
+            log::info!("Rotating from scene {:?}", self.kind);
+            self.step_scene(1);
+        }
+    }
+
+    // Shows `error` in place of the current scene until a retry of the scene
+    // succeeds, e.g. after a GL error
+    pub fn show_error(&mut self, error: &Error) {
+        self.retry_in = Some(error::RETRY_INTERVAL);
+        let scene = ErrorScene::new(error, self.admin_url.as_deref());
+        self.set_scene(Box::new(scene));
+    }
+
+    pub fn is_showing_error(&self) -> bool {
+        self.retry_in.is_some()
+    }
+
+    fn set_scene(&mut self, scene: Box<dyn Scene>) {
+        self.update(&SceneEvent::Exit);
+        self.scene = Some(scene);
+        self.scene_fade = Some(self.timing.transition);
+        self.events.push(FrameEvent::SceneChanged {
+            name: self.scene.as_ref().map_or("", |s| s.name()).to_string(),
+        });
+        self.update(&SceneEvent::Enter);
+    }
+
+    // Recoverable failures pass, e.g. a network drive is mounted again
+    fn check_retry(&mut self, dt: f32) {
+        let Some(retry_in) = self.retry_in.as_mut() else {
+            return;
+        };
+        *retry_in -= dt;
+        if *retry_in > 0.0 {
+            return;
+        }
+        self.retry_in = Some(error::RETRY_INTERVAL);
+        if self.context.photos.is_empty() {
+            self.context.photos = read_photos(&self.photo_dirs);
+        }
+        self.switch_scene(self.kind.clone());
+    }
+
+    // Re-reads the photos and restarts the current scene with the photo
+    // directories and timing of `config`
+    pub fn reload(&mut self, config: &AppConfig) {
+        self.update(&SceneEvent::Exit);
+        self.scene = None;
+        self.photo = None;
+        self.context.photos = read_photos(&config.photo_dirs);
+        self.photo_dirs = config.photo_dirs.clone();
+        self.timing = slide_timing(config, self.timing.seed);
+        self.filter = config.photo_filter.clone();
+        self.schedule = config.schedule.clone();
+        self.scheduled_rule = self.schedule.rule_at(DateTime::local_now());
+        self.default_scene = config.start_scene.clone();
+        self.rotation = rotation(config, &self.registry);
+        self.set_clock_overlay(config);
+        self.switch_scene(self.kind.clone());
+    }
+
+    // Switches scenes when the schedule changes to another one. A scene
+    // selected in between, e.g. remotely, stays until the next change.
+    fn check_schedule(&mut self) {
+        if self.schedule.is_empty() {
+            return;
+        }
+        let now = DateTime::local_now();
+        let scene = self.schedule.scene_at(now, &self.default_scene);
+        if *scene != self.scheduled {
+            self.scheduled = scene.clone();
+            log::info!("Scheduled scene {:?}", self.scheduled);
+            if self.scheduled != self.kind {
+                self.switch_scene(self.scheduled.clone());
+            }
+        }
+    }
+
+    // Announcements are made even while paused, e.g. for alarms
+    fn check_announcements(&mut self) {
+        if self.schedule.is_empty() {
+            return;
+        }
+        let rule = self.schedule.rule_at(DateTime::local_now());
+        if rule != self.scheduled_rule {
+            self.scheduled_rule = rule;
+            let say = rule.and_then(|i| self.schedule.rules[i].say.clone());
+            self.announcements.extend(say);
+        }
+    }
+
+    // Items of a new scene fade in unless they bring their own animation
+    fn set_animator_layout(&mut self) {
+        match self.scene_fade.take() {
+            Some(fade) => {
+                let mut layout = Layout {
+                    items: self.layout.items.clone(),
+                };
+                for item in &mut layout.items {
+                    item.animation_time.get_or_insert(fade);
+                }
+                self.animator.set_layout(&layout);
+            }
+            None => self.animator.set_layout(&self.layout),
+        }
+    }
+
+    // Writes `update` to the sidecar of the photo on screen and keeps the
+    // metadata in memory in step
+    pub fn update_photo_meta(&mut self, update: &MetaUpdate) -> Result<()> {
+        let photo = self.photo.and_then(|id| self.context.photos.get_mut(id));
+        let photo = photo.ok_or(Error::InvalidPhotoId)?;
+        library::write_meta(&photo.path, update)?;
+        update.apply(&mut photo.meta);
+        Ok(())
+    }
+
+    // For metadata resolved after loading, e.g. geocoded places
+    pub fn photos_mut(&mut self) -> &mut [photo::Photo] {
+        &mut self.context.photos
+    }
+
+    // Messages of schedule rules that started since the last call
+    pub fn take_announcements(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.announcements)
+    }
+
+    // Events collected since the last call
+    pub fn take_events(&mut self) -> Vec<FrameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn check_photo(&mut self) {
+        let current = self.scene.as_ref().and_then(|s| s.current_photo());
+        if current == self.photo {
+            return;
+        }
+        self.photo = current;
+        let now = self.context.time.to_unix_seconds();
+        if let Some(photo) = current.and_then(|id| self.context.photos.get_mut(id)) {
+            self.events.push(FrameEvent::PhotoChanged {
+                path: photo.path.clone(),
+                title: photo.meta.title.as_ref().and_then(|t| t.first()).cloned(),
+            });
+            // slideshows pick photos shown lately less often, also after a restart
+            photo.last_shown = Some(now);
+            if let Err(e) = library::record_shown(&photo.path, now) {
+                log::warn!("Failed to note when {:?} was shown: {e:?}", photo.path);
+            }
+        }
+    }
+
+    pub fn toggle_overlay(&mut self) {
+        if let Some(mut overlay) = self.overlay.take() {
+            overlay.free(&mut self.layouter);
+        } else {
+            let mut overlay = DebugOverlay::new();
+            overlay.refresh(&mut self.layouter);
+            self.overlay = Some(overlay);
+        }
+        self.apply_layout();
+    }
+
+    fn set_clock_overlay(&mut self, config: &AppConfig) {
+        if let Some(mut overlay) = self.clock_overlay.take() {
+            overlay.free(&mut self.layouter);
+        }
+        self.clock_overlay = config.clock_overlay.map(|style| {
+            let mut overlay = ClockOverlay::new(style, config.locale, config.time_format);
+            overlay.refresh(&DateTime::local_now(), &mut self.layouter);
+            overlay
+        });
+        self.apply_layout();
+    }
+
+    pub fn update_overlay(&mut self, fps: f32, frame_stats: Option<FrameStats>) {
+        if let Some(overlay) = self.overlay.as_mut() {
+            overlay.set_fps(fps, frame_stats);
+            overlay.refresh(&mut self.layouter);
+            self.apply_layout();
+        }
+    }
+
+    fn apply_layout(&mut self) {
+        self.layout_dirty = true;
+    }
+
+    // Hands the animated scene layout plus the overlay layout on top of it to
+    // the layouter before rendering, while moving sampled `alpha` of the way
+    // from the previous time step to the last one
+    pub fn prepare_render(&mut self, alpha: f32) {
+        if !self.layout_dirty && !self.moving {
+            return;
+        }
+        let mut layout = match self.moving {
+            true => self.animator.layout_at(alpha),
+            false => self.animator.layout(),
+        };
+        layout.items.extend(self.overlay_layout().items);
+        self.layouter.update_layout(&layout);
+        // once more after moving to show the final state
+        self.layout_dirty = self.moving;
+    }
+
+    // The secondary layout composited after the scene: the clock, then the
+    // debug overlay. Both are placed for the current aspect ratio.
+    fn overlay_layout(&self) -> Layout {
+        let aspect_ratio = self.layouter.aspect_ratio();
+        let mut items = Vec::new();
+        if let Some(clock) = &self.clock_overlay
+            && self.kind != SceneKind::Clock
+        {
+            items.extend(clock.items(aspect_ratio));
+        }
+        if let Some(overlay) = &self.overlay {
+            items.extend(overlay.items(aspect_ratio));
+        }
+        Layout { items }
     }
 
     pub fn canvas(&self) -> &Canvas {
@@ -64,20 +500,84 @@ impl SceneManager {
 
     pub fn resize(&mut self, aspect_ratio: f32) {
         self.layouter.resize(aspect_ratio);
+        // overlays are placed for the aspect ratio
+        self.apply_layout();
     }
 }
 
+// ----------------------------------------------------------------------------
+fn slide_timing(config: &AppConfig, seed: u64) -> SlideTiming {
+    SlideTiming {
+        show: config.slide_duration,
+        transition: config.transition_duration,
+        easing: config.transition_easing,
+        effect: config.transition_effect,
+        jitter: config.slide_jitter,
+        ken_burns: config.ken_burns,
+        seed,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The configured rotation, else all scenes without dwell times
+fn rotation(config: &AppConfig, registry: &SceneRegistry) -> Rotation {
+    match config.rotation.is_empty() {
+        true => Rotation::builtin(registry.names()),
+        false => config.rotation.clone(),
+    }
+}
+
+fn dwell_time(rotation: &Rotation, kind: &SceneKind) -> Option<f32> {
+    let index = rotation.position(kind)?;
+    rotation.entries[index].dwell
+}
+
+// ----------------------------------------------------------------------------
+// Today falls back to all photos if there is nothing to show, `timing` and
+// `filter` are the ones of the built-in slideshows
+fn create_scene(
+    kind: &SceneKind,
+    ctx: &Context,
+    registry: &SceneRegistry,
+    timing: SlideTiming,
+    filter: &PhotoFilter,
+) -> Result<Box<dyn Scene>> {
+    let slideshow = match kind {
+        SceneKind::AllPhotos => create_slideshow_all(ctx, filter),
+        SceneKind::Today => create_daily_slideshow(ctx, filter).or_else(|e| {
+            log::warn!("No photos for today ({e:?}), showing all photos");
+            create_slideshow_all(ctx, filter)
+        }),
+        SceneKind::Clock => return Ok(Box::new(ClockScene::new())),
+        SceneKind::Plugin(name) => return registry.create(name, ctx),
+    };
+    Ok(Box::new(slideshow?.with_timing(timing)))
+}
+
+// ----------------------------------------------------------------------------
+fn read_photos(dirs: &[std::path::PathBuf]) -> Vec<photo::Photo> {
+    dirs.iter()
+        .flat_map(|dir| match dir.as_os_str() == demo::DEMO_DIR {
+            true => demo::photos(),
+            false => photo::read_dir_photos(dir),
+        })
+        .collect()
+}
+
 fn update_scene(
     scene: &mut Option<Box<dyn Scene>>,
     event: &SceneEvent,
     ctx: &Context,
     layouter: &mut Layouter,
     layout: &mut Layout,
-) {
+) -> bool {
+    // scenes lay out on every event, a still photo gives the same layout
     if let Some(scene) = scene.as_mut()
         && let Some(new_layout) = scene.update(event, ctx, layouter)
+        && new_layout.items != layout.items
     {
         layout.replace(new_layout);
-        layouter.update_layout(layout);
+        return true;
     }
+    false
 }
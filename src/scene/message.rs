@@ -0,0 +1,143 @@
+use crate::error::{Error, Result};
+use crate::scene::registry::ScenePlugin;
+use crate::scene::{
+    Context, Element, Handle, Layout, LayoutId, LayoutItem, Layouter, Scene, SceneEvent, Text,
+};
+use crate::v2d::{rect::Rect, v2::V2, v4::V4};
+
+// ----------------------------------------------------------------------------
+const TEXT_HEIGHT: f32 = 0.08;
+const MARGIN: f32 = 0.05;
+const SCROLL_SPEED: f32 = 0.1; // screen widths per second
+
+// ----------------------------------------------------------------------------
+// Example plugin (feature `scene-message`): shows the text of its config
+// section, e.g.
+//
+//   [scenes.message]
+//   text = "Happy Birthday!"
+//   scroll = true  # texts wider than the screen scroll through, else they
+//                  # end with an ellipsis
+#[derive(Default)]
+pub struct MessagePlugin {
+    text: String,
+    scroll: bool,
+}
+
+impl ScenePlugin for MessagePlugin {
+    fn name(&self) -> &str {
+        "message"
+    }
+
+    fn init(&mut self, config: Option<&toml::Table>) -> Result<()> {
+        let text = config.and_then(|c| c.get("text"));
+        self.text = match text {
+            Some(toml::Value::String(text)) => text.clone(),
+            Some(_) => {
+                return Err(Error::InvalidArgument {
+                    arg: String::from("scenes.message.text"),
+                });
+            }
+            None => String::from("Hello"),
+        };
+        self.scroll = match config.and_then(|c| c.get("scroll")) {
+            Some(toml::Value::Boolean(scroll)) => *scroll,
+            Some(_) => {
+                return Err(Error::InvalidArgument {
+                    arg: String::from("scenes.message.scroll"),
+                });
+            }
+            None => false,
+        };
+        Ok(())
+    }
+
+    fn create(&self, _ctx: &Context) -> Result<Box<dyn Scene>> {
+        Ok(Box::new(MessageScene {
+            text: self.text.clone(),
+            scroll: self.scroll,
+            handle: None,
+            marquee: None,
+        }))
+    }
+}
+
+// ----------------------------------------------------------------------------
+struct MessageScene {
+    text: String,
+    scroll: bool,
+    handle: Option<Handle>,
+    marquee: Option<Marquee>,
+}
+
+// Text scrolling from the right edge of the screen to the left
+#[derive(Clone, Copy, Debug)]
+struct Marquee {
+    x: f32,
+    width: f32,
+}
+
+impl MessageScene {
+    fn layout(&self, aspect_ratio: f32) -> Option<Layout> {
+        let handle = self.handle?;
+        let x = self.marquee.map_or(MARGIN, |m| m.x);
+        let size = V2::new([TEXT_HEIGHT / aspect_ratio, TEXT_HEIGHT]);
+        let item = LayoutItem {
+            id: LayoutId(1),
+            element: Element::Text(Text {
+                dst: Rect::new(V2::new([x, 0.5 - TEXT_HEIGHT / 2.0]), size),
+                opacity: 1.0,
+                color: V4::new([1.0, 1.0, 1.0, 1.0]),
+                handle,
+            }),
+            animation_time: self.marquee.is_none().then_some(1.0),
+        };
+        Some(Layout { items: vec![item] })
+    }
+}
+
+impl Scene for MessageScene {
+    fn update(
+        &mut self,
+        event: &SceneEvent,
+        _ctx: &Context,
+        layouter: &mut Layouter,
+    ) -> Option<Layout> {
+        match event {
+            SceneEvent::Enter => {
+                let em = TEXT_HEIGHT / layouter.aspect_ratio();
+                let max_width = (1.0 - 2.0 * MARGIN) / em;
+                let width = layouter.text_width(&self.text);
+                self.handle = match self.scroll && width > max_width {
+                    true => {
+                        let width = width * em;
+                        self.marquee = Some(Marquee { x: 1.0, width });
+                        layouter.create_text(&self.text).ok()
+                    }
+                    false => layouter.create_fitted_text(&self.text, max_width).ok(),
+                };
+                self.layout(layouter.aspect_ratio())
+            }
+            SceneEvent::TimeTick(dt) => {
+                let marquee = self.marquee.as_mut()?;
+                marquee.x -= SCROLL_SPEED * dt;
+                if marquee.x < -marquee.width {
+                    marquee.x = 1.0;
+                }
+                self.layout(layouter.aspect_ratio())
+            }
+            SceneEvent::Exit => {
+                if let Some(handle) = self.handle.take() {
+                    layouter.free_handle(handle);
+                }
+                self.marquee = None;
+                Some(Layout::empty())
+            }
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Message"
+    }
+}
@@ -1,14 +1,39 @@
+use crate::gfx::animation::Easing;
+use crate::gfx::transition::TransitionKind;
 use crate::util::datetime::DateTime;
-use crate::util::locale::DateLocale;
-use crate::v2d::{v2::V2, v4::V4};
+use crate::util::datetime::Time;
+use crate::util::locale::{self, DateLocale, LocaleId, LocaleOverride, TimePattern, Units};
+use crate::v2d::{rect::Rect, v4::V4};
 use layouter::Layouter;
-use photo::Photo;
+use photo::{DecodedPhoto, Photo};
+use serde::Serialize;
+use slots::SlotId;
+use std::path::PathBuf;
+use std::sync::Arc;
 
+pub mod animator;
+pub mod clock;
+pub mod decoder;
+pub mod demo;
+pub mod error;
 pub mod font;
 pub mod layouter;
 pub mod manager;
+pub mod overlay;
 pub mod photo;
+pub mod picker;
+pub mod registry;
+pub mod rotation;
+pub mod schedule;
 pub mod slideshow;
+pub mod slots;
+pub mod text_cache;
+pub mod video;
+
+#[cfg(feature = "scene-message")]
+pub mod message;
+#[cfg(feature = "scripting")]
+pub mod script;
 
 pub trait Scene {
     fn update(
@@ -17,13 +42,59 @@ pub trait Scene {
         ctx: &Context,
         layouter: &mut Layouter,
     ) -> Option<Layout>;
+
+    // Shown to users, e.g. in events sent to companion apps
+    fn name(&self) -> &str;
+
+    // Id of the photo currently on screen, if any
+    fn current_photo(&self) -> Option<usize> {
+        None
+    }
+}
+
+// State changes of the frame reported to the outside, e.g. over WebSocket
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrameEvent {
+    SceneChanged {
+        name: String,
+    },
+    PhotoChanged {
+        path: PathBuf,
+        title: Option<String>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+// Scenes that can be selected by name, e.g. with `--start-scene`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SceneKind {
+    #[default]
+    AllPhotos,
+    Today,
+    Clock,
+    Plugin(String), // registered with `SceneRegistry`, checked when created
+}
+
+impl SceneKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "all" => Some(SceneKind::AllPhotos),
+            "today" => Some(SceneKind::Today),
+            "clock" => Some(SceneKind::Clock),
+            _ if registry::is_valid_name(name) => Some(SceneKind::Plugin(name.to_string())),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum SceneEvent {
     Enter,
     Exit,
-    TimeTick,
+    TimeTick(f32), // seconds since the previous tick
     User(UserEvent),
     System(SystemEvent),
 }
@@ -40,6 +111,30 @@ pub enum UserEvent {
 pub enum SystemEvent {
     WeatherUpdate,
     Alarm,
+    // see `net::fetch::Fetcher`
+    Fetched {
+        key: String,
+        body: Arc<[u8]>,
+    },
+    FetchFailed {
+        key: String,
+        error: String,
+    },
+    Offline,
+    Online,
+    // see `net::presence::PresenceDetector`
+    Presence {
+        present: bool,
+    },
+    // see `decoder::PhotoDecoder`
+    PhotoDecoded {
+        path: PathBuf,
+        photo: Arc<DecodedPhoto>,
+    },
+    PhotoFailed {
+        path: PathBuf,
+        error: String,
+    },
 }
 
 pub struct Layout {
@@ -61,6 +156,7 @@ pub struct Context {
     pub time: DateTime,
     pub weather: Option<Weather>,
     pub locale: Box<dyn DateLocale>,
+    pub units: Units,
 }
 
 impl Context {
@@ -78,36 +174,43 @@ pub struct Weather {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct LayoutId(pub u32);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct LayoutItem {
     pub id: LayoutId,
     pub element: Element,
     pub animation_time: Option<f32>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Element {
     Picture(Picture),
     Thumbnail(Picture),
     Icon(Icon),
     Text(Text),
+    Clock(Clock),
     Transition(Transition),
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct Rect {
-    pub pos: V2,
-    pub size: V2,
-}
-
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Handle {
-    pub material_id: Option<usize>,
-    pub mesh_id: Option<usize>,
+    pub material_id: Option<SlotId>,
+    pub mesh_id: Option<SlotId>,
     pub aspect_ratio: f32,
+    pub pending: bool, // the photo is still being decoded
 }
 
-#[derive(Clone, Debug)]
+impl Handle {
+    // Of a photo that isn't decoded yet, scenes show something else until
+    // `Layouter::load_photo` returns a handle that isn't pending
+    pub const PENDING: Handle = Handle {
+        material_id: None,
+        mesh_id: None,
+        aspect_ratio: 1.0,
+        pending: true,
+    };
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Picture {
     pub dst: Rect,
     pub src: Rect,
@@ -115,7 +218,7 @@ pub struct Picture {
     pub handle: Handle,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Transition {
     pub from_dst: Rect,
     pub from_src: Rect,
@@ -124,9 +227,11 @@ pub struct Transition {
     pub from: Handle,
     pub to: Handle,
     pub progress: f32,
+    pub easing: Easing, // of `progress`
+    pub kind: TransitionKind,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Icon {
     pub dst: Rect,
     pub opacity: f32,
@@ -134,10 +239,37 @@ pub struct Icon {
     pub handle: Handle,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Text {
     pub dst: Rect,
     pub opacity: f32,
     pub color: V4,
     pub handle: Handle,
 }
+
+// The current time as text, kept up to date by the layouter so scenes don't
+// need a new layout every minute
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clock {
+    pub dst: Rect,
+    pub opacity: f32,
+    pub color: V4,
+    pub format: ClockFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockFormat {
+    pub locale: LocaleId,
+    pub time_format: Option<TimePattern>, // the one of the locale if None
+    pub seconds: bool,
+}
+
+impl ClockFormat {
+    pub fn text(&self, time: &Time) -> String {
+        let locale = LocaleOverride {
+            base: self.locale.locale(),
+            time_format: self.time_format,
+        };
+        locale::fmt_time(time, self.seconds, &locale)
+    }
+}
@@ -0,0 +1,323 @@
+use crate::core::frame_stats::FrameStats;
+use crate::scene::{Element, Handle, LayoutId, LayoutItem, Layouter, Text};
+use crate::util::datetime::{Date, DateTime};
+use crate::util::locale::{LocaleId, LocaleOverride, TimePattern, fmt_long, fmt_time};
+use crate::util::logger;
+use crate::v2d::{rect::Rect, v2::V2, v4::V4};
+
+// ----------------------------------------------------------------------------
+const LOG_LINES: usize = 12;
+const MAX_LINE_CHARS: usize = 120;
+const TEXT_HEIGHT: f32 = 0.025;
+const LINE_SPACING: f32 = 1.25 * TEXT_HEIGHT;
+const MARGIN: f32 = 0.01;
+
+const CLOCK_TIME_HEIGHT: f32 = 0.08;
+const CLOCK_DATE_HEIGHT: f32 = 0.035;
+const CLOCK_GAP: f32 = 0.01; // between the time and the date
+const CLOCK_MARGIN: f32 = 0.03;
+
+// Keep clear of the ids used by scenes
+const FIRST_LAYOUT_ID: u32 = 0x1000;
+const CLOCK_LAYOUT_ID: u32 = 0x2000;
+
+// ----------------------------------------------------------------------------
+// Debug layer drawn on top of the active scene: frame rate, frame times,
+// texture memory and the most recent log lines.
+pub struct DebugOverlay {
+    fps: f32,
+    frame_stats: Option<FrameStats>,
+    lines: Vec<Handle>,
+}
+
+// ----------------------------------------------------------------------------
+impl DebugOverlay {
+    // ------------------------------------------------------------------------
+    pub fn new() -> Self {
+        Self {
+            fps: 0.0,
+            frame_stats: None,
+            lines: Vec::new(),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn set_fps(&mut self, fps: f32, frame_stats: Option<FrameStats>) {
+        self.fps = fps;
+        self.frame_stats = frame_stats;
+    }
+
+    // ------------------------------------------------------------------------
+    // Rebuilds all text meshes from the current statistics and log contents.
+    pub fn refresh(&mut self, layouter: &mut Layouter) {
+        self.free(layouter);
+
+        let texture_mb = layouter.canvas().texture_bytes() as f32 / (1024.0 * 1024.0);
+        let stats = format!("FPS: {:.1}  Textures: {:.1} MB", self.fps, texture_mb);
+
+        // average and 99th percentile of the main loop stages
+        let times = self.frame_stats.map(|s| {
+            format!(
+                "Update: {:.1}/{:.1}  Render: {:.1}/{:.1}  Swap: {:.1}/{:.1} ms",
+                s.update.avg_ms,
+                s.update.p99_ms,
+                s.render.avg_ms,
+                s.render.p99_ms,
+                s.swap.avg_ms,
+                s.swap.p99_ms
+            )
+        });
+
+        let log_lines = logger::recent_lines(LOG_LINES);
+        let lines = std::iter::once(stats).chain(times).chain(log_lines);
+        for line in lines {
+            let line: String = line.chars().take(MAX_LINE_CHARS).collect();
+            if let Ok(handle) = layouter.create_text(&line) {
+                self.lines.push(handle);
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn free(&mut self, layouter: &mut Layouter) {
+        for handle in self.lines.drain(..) {
+            layouter.free_handle(handle);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Lines are stacked downwards from the top left corner.
+    pub fn items(&self, aspect_ratio: f32) -> Vec<LayoutItem> {
+        let size = V2::new([TEXT_HEIGHT / aspect_ratio, TEXT_HEIGHT]);
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(i, handle)| {
+                let y = 1.0 - MARGIN - (i + 1) as f32 * LINE_SPACING;
+                LayoutItem {
+                    id: LayoutId(FIRST_LAYOUT_ID + i as u32),
+                    element: Element::Text(Text {
+                        dst: Rect::new(V2::new([MARGIN, y]), size),
+                        opacity: 1.0,
+                        color: V4::new([1.0, 1.0, 1.0, 1.0]),
+                        handle: *handle,
+                    }),
+                    animation_time: None,
+                }
+            })
+            .collect()
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Corner of the screen an overlay sits in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl Corner {
+    // Name in config files, e.g. "top-left"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "top-left" => Some(Corner::TopLeft),
+            "top-right" => Some(Corner::TopRight),
+            "bottom-left" => Some(Corner::BottomLeft),
+            "bottom-right" => Some(Corner::BottomRight),
+            _ => None,
+        }
+    }
+
+    fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+
+    fn is_left(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::BottomLeft)
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockOverlayStyle {
+    pub position: Corner,
+    pub opacity: f32,
+    pub time_format: Option<TimePattern>, // the one of the frame if None
+    pub date: bool,                       // the long date below the time
+}
+
+impl Default for ClockOverlayStyle {
+    fn default() -> Self {
+        Self {
+            position: Corner::default(),
+            opacity: 0.8,
+            time_format: None,
+            date: true,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The time and date in a corner on top of every scene. The texts are only
+// created again when the shown minute changes.
+pub struct ClockOverlay {
+    style: ClockOverlayStyle,
+    locale: LocaleOverride,
+    shown: Option<(Date, u32, u32)>, // date, hour and minute of the texts
+    time: Option<(Handle, f32)>,     // and the width of the text in em
+    date: Option<(Handle, f32)>,
+}
+
+impl ClockOverlay {
+    // ------------------------------------------------------------------------
+    // `time_format` of the frame, the style may override it
+    pub fn new(
+        style: ClockOverlayStyle,
+        locale: LocaleId,
+        time_format: Option<TimePattern>,
+    ) -> Self {
+        Self {
+            style,
+            locale: LocaleOverride {
+                base: locale.locale(),
+                time_format: style.time_format.or(time_format),
+            },
+            shown: None,
+            time: None,
+            date: None,
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Creates the texts of the local time `now`, false while the shown minute
+    // stays the same
+    pub fn refresh(&mut self, now: &DateTime, layouter: &mut Layouter) -> bool {
+        let (hour, minute, _) = now.time.to_hms();
+        if self.shown == Some((now.date, hour, minute)) {
+            return false;
+        }
+        self.free(layouter);
+        self.shown = Some((now.date, hour, minute));
+
+        self.time = create_clock_text(layouter, &fmt_time(&now.time, false, &self.locale));
+        if self.style.date {
+            self.date = create_clock_text(layouter, &fmt_long(&now.date, &self.locale));
+        }
+        true
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn free(&mut self, layouter: &mut Layouter) {
+        for (handle, _) in [self.time.take(), self.date.take()].into_iter().flatten() {
+            layouter.free_handle(handle);
+        }
+        self.shown = None;
+    }
+
+    // ------------------------------------------------------------------------
+    // The date below the time, both aligned to the side of the corner
+    pub fn items(&self, aspect_ratio: f32) -> Vec<LayoutItem> {
+        let lines: Vec<_> = [
+            self.time
+                .map(|(handle, width)| (handle, width, CLOCK_TIME_HEIGHT)),
+            self.date
+                .map(|(handle, width)| (handle, width, CLOCK_DATE_HEIGHT)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let height: f32 = lines.iter().map(|(_, _, height)| height + CLOCK_GAP).sum();
+        let mut top = match self.style.position.is_top() {
+            true => 1.0 - CLOCK_MARGIN,
+            false => CLOCK_MARGIN + height - CLOCK_GAP,
+        };
+        let mut items = Vec::new();
+        for (i, (handle, width, height)) in lines.into_iter().enumerate() {
+            let em = height / aspect_ratio;
+            let x = match self.style.position.is_left() {
+                true => CLOCK_MARGIN,
+                false => 1.0 - CLOCK_MARGIN - width * em,
+            };
+            top -= height;
+            items.push(LayoutItem {
+                id: LayoutId(CLOCK_LAYOUT_ID + i as u32),
+                element: Element::Text(Text {
+                    dst: Rect::new(V2::new([x, top]), V2::new([em, height])),
+                    opacity: self.style.opacity,
+                    color: V4::new([1.0, 1.0, 1.0, 1.0]),
+                    handle,
+                }),
+                animation_time: None,
+            });
+            top -= CLOCK_GAP;
+        }
+        items
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn create_clock_text(layouter: &mut Layouter, text: &str) -> Option<(Handle, f32)> {
+    let handle = layouter
+        .create_text(text)
+        .inspect_err(|e| log::warn!("Failed to create clock overlay text '{text}': {e:?}"))
+        .ok()?;
+    Some((handle, layouter.text_width(text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_float_eq;
+    use crate::v2d::float_eq::float_eq_rel;
+
+    fn clock_overlay(position: Corner) -> ClockOverlay {
+        let style = ClockOverlayStyle {
+            position,
+            ..ClockOverlayStyle::default()
+        };
+        let mut overlay = ClockOverlay::new(style, LocaleId::German, None);
+        overlay.time = Some((Handle::PENDING, 5.0));
+        overlay.date = Some((Handle::PENDING, 20.0));
+        overlay
+    }
+
+    fn dst(item: &LayoutItem) -> Rect {
+        let Element::Text(text) = &item.element else {
+            panic!("{item:?}");
+        };
+        text.dst
+    }
+
+    #[test]
+    fn test_clock_overlay_items() {
+        assert_eq!(Corner::from_name("top-left"), Some(Corner::TopLeft));
+        assert_eq!(Corner::from_name("center"), None);
+
+        // the date ends at the bottom margin, right aligned like the time
+        let items = clock_overlay(Corner::BottomRight).items(2.0);
+        assert_eq!(items.len(), 2);
+        let (time, date) = (dst(&items[0]), dst(&items[1]));
+        assert_float_eq!(date.pos.x1(), CLOCK_MARGIN);
+        assert_float_eq!(time.pos.x1(), date.pos.x1() + CLOCK_DATE_HEIGHT + CLOCK_GAP);
+        assert_float_eq!(time.pos.x0() + 5.0 * time.size.x0(), 1.0 - CLOCK_MARGIN);
+        assert_float_eq!(date.pos.x0() + 20.0 * date.size.x0(), 1.0 - CLOCK_MARGIN);
+
+        // the time starts at the top margin
+        let items = clock_overlay(Corner::TopLeft).items(2.0);
+        let time = dst(&items[0]);
+        assert_float_eq!(time.pos.x1() + CLOCK_TIME_HEIGHT, 1.0 - CLOCK_MARGIN);
+        assert_float_eq!(time.pos.x0(), CLOCK_MARGIN);
+    }
+}
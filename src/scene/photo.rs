@@ -1,52 +1,614 @@
-use crate::scene::Rect;
-use crate::util::datetime::DateTime;
-use crate::{error::Result, v2d};
-use serde::Deserialize;
+use crate::error::{Error, Result, ResultExt};
+use crate::geo::Coord;
+use crate::gfx::blur;
+use crate::gfx::color_conversion::{
+    ImageBuffer, ImageGeometry, YCbCrMatrix, premultiply_rgba, rgba32_to_ycbcr24,
+    unpremultiply_rgba, ycbcr420_to_ycbcr24,
+};
+use crate::gfx::color_format::ColorFormat;
+use crate::gfx::dominant_color;
+use crate::gfx::exif::{self, Exif, Orientation};
+use crate::gfx::jpeg;
+use crate::gfx::resize::{self, Filter};
+use crate::gfx::sharpen::UnsharpMask;
+use crate::gfx::transform;
+use crate::library;
+use crate::scene::{demo, video};
+use crate::util::datetime::{Date, DateTime};
+use crate::v2d;
+use crate::v2d::rect::Rect;
+use crate::v2d::v2::V2;
+use miniz::png_read::{self, PNGColorType};
+use serde::{Deserialize, Deserializer};
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use v2d::m4x4;
+use v2d::{m2x3::M2x3, m4x4};
 
 #[derive(Clone, Debug)]
 pub struct Photo {
     pub path: PathBuf,
     pub meta: PhotoMeta,
+    pub last_shown: Option<i64>, // seconds since 1970, kept in the index
 }
 
 impl Photo {
     pub fn from_path(path: PathBuf) -> Result<Self> {
         let json_path = path.with_extension("json");
-        let data = std::fs::read_to_string(json_path)?;
-        let meta = serde_json::from_str(&data)?;
-        Ok(Self { path, meta })
+        let data = std::fs::read_to_string(&json_path).with_path("reading", &json_path)?;
+        let mut meta: PhotoMeta = serde_json::from_str(&data).with_path("parsing", &json_path)?;
+        if meta.version > META_VERSION {
+            log::warn!("{json_path:?} is of a newer version, some fields may be ignored");
+        }
+        // the camera knows when and where, if the sidecar doesn't tell
+        if (meta.datetime.is_none() || meta.coord.is_none())
+            && is_jpeg_file(&path)
+            && let Some(exif) = read_exif(&path)
+        {
+            meta.datetime = meta.datetime.or(exif.datetime);
+            meta.coord = meta.coord.or(exif.coord);
+        }
+        Ok(Self {
+            path,
+            meta,
+            last_shown: None,
+        })
     }
 }
 
+// ----------------------------------------------------------------------------
+// Version of the sidecar format, sidecars without one are version 1.
+// Version 2 always writes lists for the place, title, tag and weather and
+// calls the tags `tag`. Older sidecars are still read and are upgraded by
+// `home-rs index`, unknown fields are kept.
+pub const META_VERSION: u32 = 2;
+
+// Former names of fields, (old, new)
+const RENAMED_FIELDS: [(&str, &str); 1] = [("tags", "tag")];
+const LIST_FIELDS: [&str; 4] = ["place", "title", "tag", "weather"];
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct PhotoMeta {
+    #[serde(default = "first_version")]
+    pub version: u32,
     pub datetime: Option<DateTime>,
+    pub coord: Option<Coord>, // from the file or resolved from `place`
+    #[serde(default, deserialize_with = "one_or_many")]
     pub place: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "one_or_many")]
     pub title: Option<Vec<String>>,
+    #[serde(default, alias = "tags", deserialize_with = "one_or_many")]
     pub tag: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "one_or_many")]
     pub weather: Option<Vec<String>>,
     pub rating: Option<u8>,
+    #[serde(default)]
+    pub display: DisplayHints,
+}
+
+impl PhotoMeta {
+    // ------------------------------------------------------------------------
+    // Taken on the same day of the year as `date`, in any year
+    pub fn is_anniversary(&self, date: Date) -> bool {
+        let same_day = |dt: DateTime| {
+            let (_, month, day) = dt.date.to_ymd();
+            let (_, on_month, on_day) = date.to_ymd();
+            month == on_month && day == on_day
+        };
+        self.datetime.is_some_and(same_day)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// How slideshows show a photo, e.g.
+//
+//   "display": { "crop": [0.1, 0.2, 0.5, 0.5], "pan": "right", "duration": 20 }
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayHints {
+    pub crop: Option<[f32; 4]>, // x, y, width and height, fractions from the top left
+    pub pan: Option<PanDirection>,
+    pub duration: Option<f32>, // seconds, instead of the slide duration
+    pub show_on_date: bool,    // shown first on every anniversary of `datetime`
+}
+
+impl DisplayHints {
+    // ------------------------------------------------------------------------
+    // The crop in texture coordinates, None if it's not inside the photo
+    pub fn crop_rect(&self) -> Option<Rect> {
+        let [x, y, cx, cy] = self.crop?;
+        let inside = x >= 0.0 && y >= 0.0 && cx > 0.0 && cy > 0.0;
+        if !inside || x + cx > 1.0 || y + cy > 1.0 {
+            return None;
+        }
+        Some(Rect::new(V2::new([x, 1.0 - y - cy]), V2::new([cx, cy])))
+    }
+
+    pub fn duration(&self) -> Option<f32> {
+        self.duration.filter(|&seconds| seconds > 0.0)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Where the view moves to while the photo is shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PanDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl PanDirection {
+    pub fn vector(self) -> V2 {
+        match self {
+            PanDirection::Left => V2::new([-1.0, 0.0]),
+            PanDirection::Right => V2::new([1.0, 0.0]),
+            PanDirection::Up => V2::new([0.0, 1.0]),
+            PanDirection::Down => V2::new([0.0, -1.0]),
+        }
+    }
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+// Version 1 allowed a single string instead of a list
+fn one_or_many<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        Some(OneOrMany::One(value)) => Some(vec![value]),
+        Some(OneOrMany::Many(values)) => Some(values),
+        None => None,
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Upgrades the fields of a sidecar to `META_VERSION`, returns false if they
+// are up to date. Newer versions are left alone.
+pub fn migrate_meta(fields: &mut serde_json::Map<String, serde_json::Value>) -> bool {
+    let version = fields.get("version").and_then(|v| v.as_u64()).unwrap_or(1);
+    if version >= u64::from(META_VERSION) {
+        return false;
+    }
+    for (old, new) in RENAMED_FIELDS {
+        if !fields.contains_key(new)
+            && let Some(value) = fields.remove(old)
+        {
+            fields.insert(new.to_string(), value);
+        }
+    }
+    for name in LIST_FIELDS {
+        if let Some(value) = fields.get_mut(name)
+            && value.is_string()
+        {
+            *value = serde_json::Value::Array(vec![value.take()]);
+        }
+    }
+    fields.insert(String::from("version"), META_VERSION.into());
+    true
+}
+
+// Photos larger than this are downscaled before the texture upload
+const MAX_PHOTO_SIZE: (usize, usize) = (3840, 2160);
+
+// Longest side of letterbox backdrops, they are stretched over the screen
+const BACKDROP_SIZE: usize = 64;
+
+// Highest rating of a photo, 0 removes the rating
+pub const MAX_RATING: u8 = 5;
+
+// ----------------------------------------------------------------------------
+// Changes of the metadata of a photo made at the frame, e.g. by a like or by
+// hiding it. Values that are None or empty are kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetaUpdate {
+    pub rating: Option<u8>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+impl MetaUpdate {
+    pub fn is_valid(&self) -> bool {
+        self.rating.is_none_or(|rating| rating <= MAX_RATING)
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn apply(&self, meta: &mut PhotoMeta) {
+        if let Some(rating) = self.rating {
+            meta.rating = (rating > 0).then_some(rating);
+        }
+        let mut tags = meta.tag.take().unwrap_or_default();
+        self.apply_tags(&mut tags);
+        meta.tag = (!tags.is_empty()).then_some(tags);
+    }
+
+    // ------------------------------------------------------------------------
+    // Tags are added once, in the given order
+    pub fn apply_tags(&self, tags: &mut Vec<String>) {
+        tags.retain(|tag| !self.remove_tags.contains(tag));
+        for tag in &self.add_tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// How the pixels of a decoded photo are stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    YCbCr24, // interleaved, drawn by the YUV pipeline
+    Rgba32,  // PNG photos, which may be transparent
+}
+
+impl PixelFormat {
+    pub fn channels(self) -> usize {
+        match self {
+            PixelFormat::YCbCr24 => 3,
+            PixelFormat::Rgba32 => 4,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// How the screen around a photo of another aspect ratio is filled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Letterbox {
+    #[default]
+    Plain, // the background color
+    Blur,  // a blurred and darkened copy of the photo, covering the screen
+    Color, // the most frequent color of the photo, darkened
+}
+
+impl Letterbox {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Letterbox::Plain),
+            "blur" => Some(Letterbox::Blur),
+            "color" => Some(Letterbox::Color),
+            _ => None,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The image of a photo, ready for the texture upload
+pub struct DecodedPhoto {
+    pub cx: usize,
+    pub cy: usize,
+    pub format: PixelFormat,
+    pub pixels: Vec<u8>,
+    pub backdrop: Option<Box<DecodedPhoto>>, // drawn below the photo, see `Letterbox`
+}
+
+impl DecodedPhoto {
+    // ------------------------------------------------------------------------
+    // Reads and prepares a photo, PNGs keep their colors and transparency and
+    // get no backdrop
+    pub fn read(path: &Path, sharpen: Option<&UnsharpMask>, letterbox: Letterbox) -> Result<Self> {
+        if is_png_file(path) {
+            let (cx, cy, mut rgba32) = read_png(path)?;
+            // premultiplied while resized, so transparent pixels don't bleed
+            premultiply_rgba(&mut rgba32);
+            let (cx, cy, mut rgba32) = downscale(cx, cy, rgba32, 4);
+            unpremultiply_rgba(&mut rgba32);
+            return Ok(Self {
+                cx,
+                cy,
+                format: PixelFormat::Rgba32,
+                pixels: rgba32,
+                backdrop: None,
+            });
+        }
+        let (cx, cy, yuv24) = read_photo(path)?;
+        let (cx, cy, yuv24) = prepare(cx, cy, yuv24, sharpen);
+        let backdrop = match letterbox {
+            Letterbox::Plain => None,
+            Letterbox::Blur => Some(Box::new(blurred_backdrop(cx, cy, &yuv24))),
+            Letterbox::Color => Some(Box::new(tinted_backdrop(&yuv24))),
+        };
+        Ok(Self {
+            cx,
+            cy,
+            format: PixelFormat::YCbCr24,
+            pixels: yuv24,
+            backdrop,
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Small copy of a YCbCr photo, blurred and darkened so that the photo stands
+// out in front of it
+fn blurred_backdrop(cx: usize, cy: usize, yuv24: &[u8]) -> DecodedPhoto {
+    let (fit_cx, fit_cy) = resize::fit_size(cx, cy, BACKDROP_SIZE, BACKDROP_SIZE);
+    // rows of 3 byte pixels are uploaded 4 byte aligned
+    let fit_cx = fit_cx.next_multiple_of(4);
+    let mut pixels = resize::resize(yuv24, cx, cy, 3, fit_cx, fit_cy, Filter::Bilinear);
+    // three box blurs come close to a Gaussian one
+    for _ in 0..3 {
+        blur::box_blur(&mut pixels, fit_cx, fit_cy, 3, 3);
+    }
+    for luma in pixels.iter_mut().step_by(3) {
+        *luma = darken(*luma);
+    }
+    DecodedPhoto {
+        cx: fit_cx,
+        cy: fit_cy,
+        format: PixelFormat::YCbCr24,
+        pixels,
+        backdrop: None,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Backdrop in the most frequent color of a YCbCr photo, darkened like
+// the blurred one
+fn tinted_backdrop(yuv24: &[u8]) -> DecodedPhoto {
+    let stats = dominant_color::color_stats(yuv24, 3, 1);
+    let [luma, cb, cr] = stats
+        .dominant
+        .first()
+        .map_or(stats.average, |dominant| dominant.color);
+    DecodedPhoto {
+        cx: 4,
+        cy: 4,
+        format: PixelFormat::YCbCr24,
+        pixels: [darken(luma), cb, cr].repeat(4 * 4),
+        backdrop: None,
+    }
 }
 
-fn is_webp_file(path: &Path) -> bool {
+fn darken(luma: u8) -> u8 {
+    (u16::from(luma) * 3 / 5) as u8
+}
+
+impl std::fmt::Debug for DecodedPhoto {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "DecodedPhoto({}x{})", self.cx, self.cy)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Downscales (and sharpens) decoded photos that exceed MAX_PHOTO_SIZE
+pub fn prepare(
+    cx: usize,
+    cy: usize,
+    yuv24: Vec<u8>,
+    sharpen: Option<&UnsharpMask>,
+) -> (usize, usize, Vec<u8>) {
+    let (fit_cx, fit_cy, mut yuv24) = downscale(cx, cy, yuv24, 3);
+    if let Some(usm) = sharpen.filter(|_| (fit_cx, fit_cy) != (cx, cy)) {
+        usm.apply(&mut yuv24, fit_cx, fit_cy, 3, 0);
+    }
+    (fit_cx, fit_cy, yuv24)
+}
+
+fn downscale(cx: usize, cy: usize, pixels: Vec<u8>, channels: usize) -> (usize, usize, Vec<u8>) {
+    let (max_cx, max_cy) = MAX_PHOTO_SIZE;
+    let (fit_cx, fit_cy) = resize::fit_size(cx, cy, max_cx, max_cy);
+    if (fit_cx, fit_cy) == (cx, cy) {
+        return (cx, cy, pixels);
+    }
+    let pixels = resize::resize(&pixels, cx, cy, channels, fit_cx, fit_cy, Filter::Lanczos3);
+    (fit_cx, fit_cy, pixels)
+}
+
+// ----------------------------------------------------------------------------
+// Decodes a WebP file to interleaved YCbCr, the size is a multiple of 16
+pub fn read_webp(path: &Path) -> Result<(usize, usize, Vec<u8>)> {
+    let frame = decode_webp(path)?;
+    Ok(frame_to_ycbcr24(&frame))
+}
+
+// ----------------------------------------------------------------------------
+// Reads the image of any photo returned by the photo sources, the first
+// frame of video clips
+pub fn read_photo(path: &Path) -> Result<(usize, usize, Vec<u8>)> {
+    if demo::is_demo_photo(path) {
+        demo::generate(path)
+    } else if video::is_video_file(path) {
+        let mut clip = video::VideoClip::open(path)?;
+        Ok((clip.cx, clip.cy, clip.first_frame()?))
+    } else if is_jpeg_file(path) {
+        read_jpeg(path)
+    } else if is_png_file(path) {
+        let (cx, cy, rgba32) = read_png(path)?;
+        let geo = ImageGeometry {
+            cx,
+            cy,
+            cf: ColorFormat::RGB0888,
+        };
+        Ok((cx, cy, rgba32_to_ycbcr24(&rgba32, &geo, YCbCrMatrix::Bt601)))
+    } else {
+        read_webp(path)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Decodes a baseline JPEG file to interleaved YCbCr, the width is a multiple
+// of 4. Photos are turned upright as told by their EXIF orientation.
+pub fn read_jpeg(path: &Path) -> Result<(usize, usize, Vec<u8>)> {
+    let contents = std::fs::read(path).with_path("reading", path)?;
+    let (cx, cy, yuv24) = jpeg::decode(&contents).with_path("decoding", path)?;
+    let orientation = exif::from_jpeg(&contents).map(|exif| exif.orientation);
+    orient(cx, cy, yuv24, orientation.unwrap_or_default()).with_path("turning", path)
+}
+
+// ----------------------------------------------------------------------------
+// The EXIF data of a JPEG file, it's in the first segments of the file
+pub fn read_exif(path: &Path) -> Option<Exif> {
+    let mut head = Vec::new();
+    let file = std::fs::File::open(path).ok()?;
+    file.take(EXIF_READ_LIMIT).read_to_end(&mut head).ok()?;
+    exif::from_jpeg(&head)
+}
+
+// APP1 segments are up to 64 KiB, after a few small ones
+const EXIF_READ_LIMIT: u64 = 128 * 1024;
+
+// ----------------------------------------------------------------------------
+// Mirrors and rotates interleaved YCbCr pixels, a new width is padded to a
+// multiple of 4 by repeating the right edge
+fn orient(
+    cx: usize,
+    cy: usize,
+    yuv24: Vec<u8>,
+    orientation: Orientation,
+) -> Result<(usize, usize, Vec<u8>)> {
+    if orientation.is_upright() {
+        return Ok((cx, cy, yuv24));
+    }
+    let mut geo = ImageGeometry {
+        cx,
+        cy,
+        cf: ColorFormat::RGB0888,
+    };
+    let mut image = ImageBuffer {
+        data: yuv24,
+        stride: cx * 3,
+    };
+    if orientation.mirror {
+        (image, geo) = transform::mirror(&image, &geo, 1)?;
+    }
+    let (image, geo) = transform::rotate(&image, &geo, orientation.rotation, 1)?;
+    Ok(pad_width(geo.cx, geo.cy, image.data, 3))
+}
+
+fn pad_width(cx: usize, cy: usize, pixels: Vec<u8>, channels: usize) -> (usize, usize, Vec<u8>) {
+    let stride = cx.next_multiple_of(4);
+    if stride == cx {
+        return (cx, cy, pixels);
+    }
+    let mut padded = Vec::with_capacity(stride * cy * channels);
+    for row in pixels.chunks_exact(cx * channels) {
+        padded.extend_from_slice(row);
+        let edge = &row[row.len() - channels..];
+        for _ in cx..stride {
+            padded.extend_from_slice(edge);
+        }
+    }
+    (stride, cy, padded)
+}
+
+// ----------------------------------------------------------------------------
+// Decodes an 8 bit PNG file to RGBA, the width is a multiple of 4 with
+// transparent pixels on the right
+pub fn read_png(path: &Path) -> Result<(usize, usize, Vec<u8>)> {
+    let contents = std::fs::read(path).with_path("reading", path)?;
+    let (png, _plte, data) = png_read::png_read(&contents).with_path("decoding", path)?;
+    let channels = match png.color_type {
+        PNGColorType::Grayscale => 1,
+        PNGColorType::GrayscaleAlpha => 2,
+        PNGColorType::TrueColor => 3,
+        PNGColorType::TrueColorAlpha => 4,
+        PNGColorType::Indexed => return Err(Error::InvalidColorFormat),
+    };
+    Ok(expand_to_rgba32(png.width, png.height, channels, &data))
+}
+
+// Rows of `data` start with their filter byte
+fn expand_to_rgba32(cx: usize, cy: usize, channels: usize, data: &[u8]) -> (usize, usize, Vec<u8>) {
+    let stride = cx.next_multiple_of(4);
+    let mut rgba32 = vec![0u8; stride * cy * 4];
+    for (src, dst) in data
+        .chunks_exact(cx * channels + 1)
+        .zip(rgba32.chunks_exact_mut(stride * 4))
+    {
+        for (p, q) in src[1..].chunks_exact(channels).zip(dst.chunks_exact_mut(4)) {
+            q.copy_from_slice(&match *p {
+                [l] => [l, l, l, 255],
+                [l, a] => [l, l, l, a],
+                [r, g, b] => [r, g, b, 255],
+                [r, g, b, a] => [r, g, b, a],
+                _ => unreachable!(),
+            });
+        }
+    }
+    (stride, cy, rgba32)
+}
+
+// ----------------------------------------------------------------------------
+// The two steps of `read_webp`, separate for timing them
+pub fn decode_webp(path: &Path) -> Result<miniwebp::Frame> {
+    let contents = std::fs::read(path).with_path("reading", path)?;
+    miniwebp::read_image(&contents).with_path("decoding", path)
+}
+
+pub fn frame_to_ycbcr24(frame: &miniwebp::Frame) -> (usize, usize, Vec<u8>) {
+    let geo = ImageGeometry {
+        cx: frame.mb_width * 16,
+        cy: frame.mb_height * 16,
+        cf: ColorFormat::YCbCr420,
+    };
+    let yuv24 = ycbcr420_to_ycbcr24(&frame.ybuf, &frame.ubuf, &frame.vbuf, &geo);
+    (geo.cx, geo.cy, yuv24)
+}
+
+pub fn is_webp_file(path: &Path) -> bool {
     path.is_file()
         && path
             .extension()
             .is_some_and(|ext| ext.eq_ignore_ascii_case("webp"))
 }
 
-pub fn read_webp_photos(dir: &Path) -> Vec<Photo> {
+pub fn is_png_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+}
+
+pub fn is_jpeg_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"))
+}
+
+// Photos the frame decodes itself, clips are decoded by ffmpeg
+pub fn is_image_file(path: &Path) -> bool {
+    is_webp_file(path) || is_jpeg_file(path) || is_png_file(path)
+}
+
+// Names of the photo a sidecar may belong to, with all image extensions
+pub fn image_paths(json_path: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    IMAGE_EXTENSIONS
+        .iter()
+        .map(|ext| json_path.with_extension(ext))
+}
+
+const IMAGE_EXTENSIONS: [&str; 7] = ["webp", "jpg", "jpeg", "JPG", "JPEG", "png", "PNG"];
+
+// WebP, JPEG and PNG photos and video clips of a directory, all with a JSON sidecar
+pub fn read_dir_photos(dir: &Path) -> Vec<Photo> {
     log::info!("Reading photos: {dir:?}");
+    let index = library::load_index(dir).unwrap_or_default();
     let mut photos = Vec::new();
     if let Ok(entries) = std::fs::read_dir(dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if is_webp_file(&path) {
+            if is_image_file(&path) || (path.is_file() && video::is_video_file(&path)) {
                 let photo = Photo::from_path(path);
                 log::info!("Found photo: {:?} => {photo:?}", entry.path());
-                if let Ok(photo) = photo {
+                if let Ok(mut photo) = photo {
+                    let file = entry.file_name();
+                    photo.last_shown = index
+                        .photos
+                        .iter()
+                        .find(|e| file.to_str() == Some(e.file.as_str()))
+                        .and_then(|e| e.last_shown);
                     photos.push(photo);
                 }
             }
@@ -55,12 +617,120 @@ pub fn read_webp_photos(dir: &Path) -> Vec<Photo> {
     photos
 }
 
-#[rustfmt::skip]
 pub fn transform(dst: &Rect) -> m4x4::M4x4 {
-    m4x4::M4x4::new([
-        dst.size.x0(), 0.0,           0.0, 0.0,
-        0.0,           dst.size.x1(), 0.0, 0.0,
-        0.0,           0.0,           1.0, 0.0,
-        dst.pos.x0(),  dst.pos.x1(),  0.0, 1.0,
-    ])
+    M2x3::from_rect(dst).as_m4x4()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_versions() {
+        let old = r#"{ "title": "Beach", "tags": ["sea"], "camera": "X100" }"#;
+        let meta: PhotoMeta = serde_json::from_str(old).unwrap();
+        assert_eq!(meta.version, 1);
+        assert_eq!(meta.title.as_deref(), Some(&[String::from("Beach")][..]));
+        assert_eq!(meta.tag.as_deref(), Some(&[String::from("sea")][..]));
+
+        let mut fields = serde_json::from_str(old).unwrap();
+        assert!(migrate_meta(&mut fields));
+        assert!(!migrate_meta(&mut fields));
+        let upgraded = serde_json::Value::Object(fields);
+        assert_eq!(upgraded["version"], META_VERSION);
+        assert_eq!(upgraded["title"], serde_json::json!(["Beach"]));
+        assert_eq!(upgraded["tag"], serde_json::json!(["sea"]));
+        assert_eq!(upgraded["camera"], "X100");
+        assert!(upgraded.get("tags").is_none());
+
+        let meta: PhotoMeta = serde_json::from_value(upgraded).unwrap();
+        assert_eq!(meta.version, META_VERSION);
+        assert!(serde_json::from_str::<PhotoMeta>(r#"{ "title": 7 }"#).is_err());
+    }
+
+    #[test]
+    fn test_display_hints() {
+        let meta = r#"{
+            "datetime": "2019-07-14T10:00:00",
+            "display": { "crop": [0.25, 0.0, 0.5, 0.25], "pan": "up", "show_on_date": true }
+        }"#;
+        let meta: PhotoMeta = serde_json::from_str(meta).unwrap();
+        let hints = &meta.display;
+        let crop = Rect::new(V2::new([0.25, 0.75]), V2::new([0.5, 0.25]));
+        assert_eq!(hints.crop_rect(), Some(crop));
+        assert_eq!(hints.pan, Some(PanDirection::Up));
+        assert_eq!(hints.duration(), None);
+        assert!(meta.is_anniversary(Date::from_ymd(2025, 7, 14).unwrap()));
+        assert!(!meta.is_anniversary(Date::from_ymd(2019, 7, 15).unwrap()));
+
+        let outside = DisplayHints {
+            crop: Some([0.5, 0.5, 0.6, 0.1]),
+            ..DisplayHints::default()
+        };
+        assert_eq!(outside.crop_rect(), None);
+        let meta: PhotoMeta = serde_json::from_str("{}").unwrap();
+        assert_eq!(meta.display, DisplayHints::default());
+        assert!(!meta.is_anniversary(Date::from_ymd(2019, 7, 14).unwrap()));
+    }
+
+    #[test]
+    fn test_expand_to_rgba32() {
+        // two rows of gray+alpha pixels, each row after its filter byte
+        let data = [0, 10, 255, 20, 128, 0, 30, 0, 40, 64];
+        let (cx, cy, rgba32) = expand_to_rgba32(2, 2, 2, &data);
+        assert_eq!((cx, cy), (4, 2));
+        assert_eq!(&rgba32[..8], &[10, 10, 10, 255, 20, 20, 20, 128]);
+        assert_eq!(&rgba32[8..16], &[0; 8]); // padding is transparent
+        assert_eq!(&rgba32[16..24], &[30, 30, 30, 0, 40, 40, 40, 64]);
+
+        let (_, _, rgba32) = expand_to_rgba32(1, 1, 3, &[0, 1, 2, 3]);
+        assert_eq!(&rgba32[..4], &[1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn test_orient() {
+        // 4x2 pixels numbered row by row, turned clockwise to 2x4
+        let yuv24: Vec<u8> = (0..8).flat_map(|i| [i, i, i]).collect();
+        let (cx, cy, turned) = orient(4, 2, yuv24.clone(), Orientation::from_tag(6)).unwrap();
+        assert_eq!((cx, cy), (4, 4)); // padded to a multiple of 4
+        let lumas: Vec<u8> = turned.iter().step_by(3).copied().collect();
+        assert_eq!(lumas, [4, 0, 0, 0, 5, 1, 1, 1, 6, 2, 2, 2, 7, 3, 3, 3]);
+
+        let (_, _, mirrored) = orient(4, 2, yuv24.clone(), Orientation::from_tag(2)).unwrap();
+        assert_eq!(&mirrored[..3], &[3, 3, 3]);
+        let upright = orient(4, 2, yuv24.clone(), Orientation::from_tag(1)).unwrap();
+        assert_eq!(upright, (4, 2, yuv24));
+    }
+
+    #[test]
+    fn test_blurred_backdrop() {
+        // 300x100 photo, white on the left half and black on the right
+        let luma = |i: usize| if i % 300 < 150 { 255 } else { 0 };
+        let yuv24: Vec<u8> = (0..100 * 300).flat_map(|i| [luma(i), 128, 128]).collect();
+        let backdrop = blurred_backdrop(300, 100, &yuv24);
+        assert_eq!((backdrop.cx, backdrop.cy), (64, 21));
+        assert_eq!(backdrop.format, PixelFormat::YCbCr24);
+        assert_eq!(backdrop.pixels.len(), 64 * 21 * 3);
+
+        // darkened, with a soft edge in the middle and the colors kept
+        let row: Vec<u8> = backdrop
+            .pixels
+            .iter()
+            .step_by(3)
+            .take(64)
+            .copied()
+            .collect();
+        assert_eq!((row[0], row[63]), (153, 0));
+        assert!(row[30] > row[32] && row[34] > 0, "{row:?}");
+        assert!(backdrop.pixels.chunks(3).all(|p| p[1..] == [128, 128]));
+    }
+
+    #[test]
+    fn test_tinted_backdrop() {
+        // mostly blue with some white
+        let yuv24 = [[40, 240, 110].repeat(70), [255, 128, 128].repeat(30)].concat();
+        let backdrop = tinted_backdrop(&yuv24);
+        assert_eq!((backdrop.cx, backdrop.cy), (4, 4));
+        assert_eq!(backdrop.pixels, [24, 240, 110].repeat(16));
+    }
 }
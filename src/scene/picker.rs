@@ -0,0 +1,93 @@
+use crate::scene::photo::{MAX_RATING, Photo};
+use crate::util::random::XorShift;
+
+// A shown photo counts half as much again this many seconds later
+const RECENCY_HALF_LIFE: f32 = 7.0 * 86_400.0;
+
+// Photos with one of these tags come up twice as often
+const FAVORITE_TAGS: [&str; 2] = ["favorite", "favourite"];
+const FAVORITE_WEIGHT: f32 = 2.0;
+
+// ----------------------------------------------------------------------------
+// How likely `photo` is shown next at `now`, in seconds since 1970. Every
+// star of the rating adds as much as an unrated photo weighs, photos shown
+// lately come back slowly.
+pub fn weight(photo: &Photo, now: i64) -> f32 {
+    let rating = f32::from(photo.meta.rating.unwrap_or(0).min(MAX_RATING));
+    let favorite = photo
+        .meta
+        .tag
+        .iter()
+        .flatten()
+        .any(|tag| FAVORITE_TAGS.contains(&tag.as_str()));
+    let age = photo
+        .last_shown
+        .map_or(f32::INFINITY, |shown| (now - shown).max(0) as f32);
+    let recency = 1.0 - 0.5f32.powf(age / RECENCY_HALF_LIFE);
+    let favorite = if favorite { FAVORITE_WEIGHT } else { 1.0 };
+    (1.0 + rating) * favorite * recency
+}
+
+// ----------------------------------------------------------------------------
+// Index of a weight picked randomly in proportion to it, any one if they are
+// all 0
+pub fn pick(weights: &[f32], rng: &mut XorShift) -> Option<usize> {
+    let total: f32 = weights.iter().sum();
+    if weights.is_empty() || total <= 0.0 {
+        return (!weights.is_empty()).then(|| (rng.next_u64() % weights.len() as u64) as usize);
+    }
+    let mut x = rng.next_f32() * total;
+    for (i, &weight) in weights.iter().enumerate() {
+        if x < weight {
+            return Some(i);
+        }
+        x -= weight;
+    }
+    // rounding
+    weights.iter().rposition(|&weight| weight > 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::photo::PhotoMeta;
+    use std::path::PathBuf;
+
+    fn photo(meta: &str, last_shown: Option<i64>) -> Photo {
+        Photo {
+            path: PathBuf::from("photo.webp"),
+            meta: serde_json::from_str::<PhotoMeta>(meta).unwrap(),
+            last_shown,
+        }
+    }
+
+    #[test]
+    fn test_weight() {
+        let now = 1_000_000_000;
+        let day = 86_400;
+        assert_eq!(weight(&photo("{}", None), now), 1.0);
+        assert_eq!(weight(&photo(r#"{"rating": 4}"#, None), now), 5.0);
+        let favorite = photo(r#"{"rating": 1, "tag": ["favorite"]}"#, None);
+        assert_eq!(weight(&favorite, now), 4.0);
+
+        // just shown photos don't come back, after a week they count half
+        assert_eq!(weight(&photo("{}", Some(now)), now), 0.0);
+        assert!(weight(&photo("{}", Some(now - 60)), now) < 0.001);
+        let week_ago = photo(r#"{"rating": 5}"#, Some(now - 7 * day));
+        assert!((weight(&week_ago, now) - 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_pick() {
+        let mut rng = XorShift::new(3);
+        let mut counts = [0; 3];
+        for _ in 0..3000 {
+            counts[pick(&[1.0, 0.0, 2.0], &mut rng).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((800..1200).contains(&counts[0]), "{counts:?}");
+
+        assert_eq!(pick(&[], &mut rng), None);
+        assert!(pick(&[0.0, 0.0], &mut rng).is_some());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::error::{Error, Result};
+use crate::scene::{Context, Scene};
+use std::collections::BTreeMap;
+
+// ----------------------------------------------------------------------------
+// Scenes added by downstream crates without touching `SceneManager`. A plugin
+// is selected by its name like the built-in scenes, e.g. with
+// `--start-scene <name>` or `home-rs ctl scene <name>`, and gets the
+// `[scenes.<name>]` section of the config file.
+pub trait ScenePlugin {
+    // Lower case letters, digits, '-' and '_', see `is_valid_name`
+    fn name(&self) -> &str;
+
+    // Called once before the first scene is created
+    fn init(&mut self, _config: Option<&toml::Table>) -> Result<()> {
+        Ok(())
+    }
+
+    // Called whenever the scene is selected, the scene gets `SceneEvent::Enter`
+    // right after and `SceneEvent::Exit` before it's replaced
+    fn create(&self, ctx: &Context) -> Result<Box<dyn Scene>>;
+
+    // Called once when the app shuts down
+    fn shutdown(&mut self) {}
+}
+
+// ----------------------------------------------------------------------------
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+// Names of the built-in scenes, plugins can't replace them
+const RESERVED: [&str; 3] = ["all", "today", "clock"];
+
+// ----------------------------------------------------------------------------
+#[derive(Default)]
+pub struct SceneRegistry {
+    plugins: Vec<Box<dyn ScenePlugin>>,
+    initialized: bool,
+}
+
+impl SceneRegistry {
+    // ------------------------------------------------------------------------
+    // Registry with the plugins enabled by cargo features and, with
+    // `scripting`, the scripts in assets/scenes/
+    pub fn new() -> Self {
+        #[allow(unused_mut)] // without any plugin features
+        let mut registry = Self::default();
+        #[cfg(feature = "scene-message")]
+        let _ = registry.register(crate::scene::message::MessagePlugin::default());
+        #[cfg(feature = "scripting")]
+        crate::scene::script::register_scripts(
+            &mut registry,
+            std::path::Path::new(crate::scene::script::SCRIPT_DIR),
+        );
+        registry
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn register(&mut self, plugin: impl ScenePlugin + 'static) -> Result<()> {
+        let name = plugin.name();
+        if !is_valid_name(name) || RESERVED.contains(&name) || self.find(name).is_some() {
+            return Err(Error::InvalidArgument {
+                arg: format!("scene plugin {name:?}"),
+            });
+        }
+        log::info!("Registered scene plugin {name:?}");
+        self.plugins.push(Box::new(plugin));
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.plugins.iter().map(|p| p.name())
+    }
+
+    // ------------------------------------------------------------------------
+    // A failing plugin is dropped so the others still work
+    pub fn init(&mut self, sections: &BTreeMap<String, toml::Table>) {
+        if std::mem::replace(&mut self.initialized, true) {
+            return;
+        }
+        for name in sections.keys().filter(|name| self.find(name).is_none()) {
+            log::warn!("Config section for unknown scene {name:?}");
+        }
+        self.plugins.retain_mut(|plugin| {
+            let section = sections.get(plugin.name());
+            match plugin.init(section) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::error!("Scene plugin {:?} failed to start: {e:?}", plugin.name());
+                    false
+                }
+            }
+        });
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn create(&self, name: &str, ctx: &Context) -> Result<Box<dyn Scene>> {
+        self.find(name).ok_or(Error::InvalidScene)?.create(ctx)
+    }
+
+    // ------------------------------------------------------------------------
+    fn find(&self, name: &str) -> Option<&dyn ScenePlugin> {
+        self.plugins
+            .iter()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_ref())
+    }
+}
+
+impl Drop for SceneRegistry {
+    fn drop(&mut self) {
+        if self.initialized {
+            for plugin in &mut self.plugins {
+                plugin.shutdown();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Layout, SceneEvent, layouter::Layouter};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct Blank;
+
+    impl Scene for Blank {
+        fn update(&mut self, _: &SceneEvent, _: &Context, _: &mut Layouter) -> Option<Layout> {
+            None
+        }
+
+        fn name(&self) -> &str {
+            "blank"
+        }
+    }
+
+    struct BlankPlugin {
+        name: &'static str,
+        calls: Arc<AtomicU32>, // init adds 1, shutdown 10
+    }
+
+    impl ScenePlugin for BlankPlugin {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn init(&mut self, config: Option<&toml::Table>) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            match config.and_then(|c| c.get("fail")) {
+                Some(_) => Err(Error::InvalidScene),
+                None => Ok(()),
+            }
+        }
+
+        fn create(&self, _ctx: &Context) -> Result<Box<dyn Scene>> {
+            Ok(Box::new(Blank))
+        }
+
+        fn shutdown(&mut self) {
+            self.calls.fetch_add(10, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let mut registry = SceneRegistry::default();
+        let plugin = |name| BlankPlugin {
+            name,
+            calls: Arc::clone(&calls),
+        };
+        assert!(registry.register(plugin("blank")).is_ok());
+        assert!(registry.register(plugin("broken")).is_ok());
+        assert!(registry.register(plugin("blank")).is_err());
+        assert!(registry.register(plugin("all")).is_err());
+        assert!(registry.register(plugin("Not Valid")).is_err());
+
+        // the plugin failing to start is removed
+        let broken: toml::Table = toml::from_str("fail = true").unwrap();
+        let sections = BTreeMap::from([(String::from("broken"), broken)]);
+        registry.init(&sections);
+        registry.init(&sections);
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["blank"]);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        drop(registry);
+        assert_eq!(calls.load(Ordering::Relaxed), 12);
+    }
+}
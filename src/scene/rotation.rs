@@ -0,0 +1,97 @@
+use crate::error::{Error, Result};
+use crate::scene::SceneKind;
+
+// ----------------------------------------------------------------------------
+// A scene of the rotation and how long it's shown before the next one
+#[derive(Clone, Debug, PartialEq)]
+pub struct RotationEntry {
+    pub scene: SceneKind,
+    pub dwell: Option<f32>, // seconds, None stays until switched by hand
+}
+
+impl RotationEntry {
+    // ------------------------------------------------------------------------
+    pub fn parse(scene: &str, dwell: Option<f32>) -> Result<Self> {
+        Ok(Self {
+            scene: SceneKind::from_name(scene).ok_or(Error::InvalidArgument {
+                arg: scene.to_string(),
+            })?,
+            dwell,
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The scenes `next-scene` and `prev-scene` step through, in this order. A
+// scene with a dwell time hands over to the next one when it's over.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rotation {
+    pub entries: Vec<RotationEntry>,
+}
+
+impl Rotation {
+    // ------------------------------------------------------------------------
+    // The built-in scenes and then the plugins, all without a dwell time
+    pub fn builtin<'a>(plugins: impl Iterator<Item = &'a str>) -> Self {
+        let scenes = [SceneKind::AllPhotos, SceneKind::Today, SceneKind::Clock]
+            .into_iter()
+            .chain(plugins.map(|name| SceneKind::Plugin(name.to_string())));
+        Self {
+            entries: scenes
+                .map(|scene| RotationEntry { scene, dwell: None })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn position(&self, scene: &SceneKind) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.scene == *scene)
+    }
+
+    // ------------------------------------------------------------------------
+    // Index `steps` entries away from `from`, wrapping around. Scenes outside
+    // the rotation continue with the first one.
+    pub fn step(&self, from: Option<usize>, steps: isize) -> Option<usize> {
+        let len = self.entries.len() as isize;
+        match from {
+            _ if len == 0 => None,
+            Some(index) => Some((index as isize + steps).rem_euclid(len) as usize),
+            None => Some(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation() {
+        let rotation = Rotation {
+            entries: vec![
+                RotationEntry::parse("all", Some(600.0)).unwrap(),
+                RotationEntry::parse("clock", Some(60.0)).unwrap(),
+                RotationEntry::parse("weather", None).unwrap(),
+            ],
+        };
+        assert_eq!(rotation.position(&SceneKind::Clock), Some(1));
+        assert_eq!(rotation.position(&SceneKind::Today), None);
+        assert_eq!(rotation.step(Some(1), 1), Some(2));
+        assert_eq!(rotation.step(Some(2), 1), Some(0));
+        assert_eq!(rotation.step(Some(0), -1), Some(2));
+        assert_eq!(rotation.step(None, -1), Some(0));
+        assert_eq!(Rotation::default().step(Some(0), 1), None);
+        assert!(RotationEntry::parse("Nope!", None).is_err());
+
+        let builtin = Rotation::builtin(["message"].into_iter());
+        assert_eq!(builtin.entries.len(), 4);
+        assert_eq!(
+            builtin.entries[3].scene,
+            SceneKind::Plugin(String::from("message"))
+        );
+        assert!(builtin.entries.iter().all(|entry| entry.dwell.is_none()));
+    }
+}
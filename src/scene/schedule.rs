@@ -0,0 +1,156 @@
+use crate::error::{Error, Result};
+use crate::scene::SceneKind;
+use crate::util::datetime::{Date, DateTime, Time};
+
+// In the order of `Weekday`
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+// ----------------------------------------------------------------------------
+// Shows `scene` on the given weekdays from `start` until `end`, the range may
+// wrap past midnight and then ends on the following day
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScheduleRule {
+    pub days: [bool; 7], // Monday first
+    pub start: Time,
+    pub end: Time,
+    pub scene: SceneKind,
+    pub say: Option<String>, // spoken when the rule starts, e.g. an alarm
+}
+
+impl ScheduleRule {
+    // ------------------------------------------------------------------------
+    // `days` is e.g. "mon-fri", "sat,sun" or "daily", times are "HH:MM"
+    pub fn parse(days: &str, start: &str, end: &str, scene: &str) -> Result<Self> {
+        Ok(Self {
+            days: parse_days(days)?,
+            start: Time::from_hms_str(start)?,
+            end: Time::from_hms_str(end)?,
+            scene: SceneKind::from_name(scene).ok_or(Error::InvalidArgument {
+                arg: scene.to_string(),
+            })?,
+            say: None,
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn contains(&self, now: DateTime) -> bool {
+        let on = |date: Date| self.days[date.weekday() as usize];
+        if self.start <= self.end {
+            on(now.date) && self.start <= now.time && now.time < self.end
+        } else if now.time >= self.start {
+            on(now.date)
+        } else {
+            now.time < self.end && on(Date::new(now.date.days() - 1))
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// The first matching rule decides, `default` is shown at all other times
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schedule {
+    pub rules: Vec<ScheduleRule>,
+}
+
+impl Schedule {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn scene_at<'a>(&'a self, now: DateTime, default: &'a SceneKind) -> &'a SceneKind {
+        let rule = self.rule_at(now).map(|i| &self.rules[i]);
+        rule.map_or(default, |rule| &rule.scene)
+    }
+
+    // Index of the deciding rule
+    pub fn rule_at(&self, now: DateTime) -> Option<usize> {
+        self.rules.iter().position(|rule| rule.contains(now))
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn parse_days(spec: &str) -> Result<[bool; 7]> {
+    let invalid = || Error::InvalidArgument {
+        arg: spec.to_string(),
+    };
+    let day = |name: &str| {
+        let name = name.trim().to_ascii_lowercase();
+        DAY_NAMES
+            .iter()
+            .position(|&d| d == name)
+            .ok_or_else(invalid)
+    };
+
+    let mut days = [false; 7];
+    match spec.trim() {
+        "daily" | "" => days = [true; 7],
+        "weekdays" => days[..5].fill(true),
+        "weekends" => days[5..].fill(true),
+        spec => {
+            for part in spec.split(',') {
+                match part.split_once('-') {
+                    // ranges may wrap, e.g. "sat-mon"
+                    Some((first, last)) => {
+                        let (first, last) = (day(first)?, day(last)?);
+                        let len = (last + 7 - first) % 7 + 1;
+                        (0..len).for_each(|i| days[(first + i) % 7] = true);
+                    }
+                    None => days[day(part)?] = true,
+                }
+            }
+        }
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ymd: (i32, i32, i32), hm: (u32, u32)) -> DateTime {
+        DateTime {
+            date: Date::from_ymd(ymd.0, ymd.1, ymd.2).unwrap(),
+            time: Time::from_hms(hm.0, hm.1, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_schedule() {
+        assert_eq!(
+            parse_days("mon-fri").unwrap(),
+            parse_days("weekdays").unwrap()
+        );
+        assert_eq!(
+            parse_days("Sat, sun").unwrap(),
+            [false, false, false, false, false, true, true]
+        );
+        assert_eq!(
+            parse_days("sat-mon").unwrap(),
+            [true, false, false, false, false, true, true]
+        );
+        assert!(parse_days("mon-someday").is_err());
+
+        let schedule = Schedule {
+            rules: vec![
+                ScheduleRule::parse("mon-fri", "06:30", "08:00", "today").unwrap(),
+                ScheduleRule::parse("fri", "22:00", "02:00", "night").unwrap(),
+            ],
+        };
+        let all = SceneKind::AllPhotos;
+        let night = SceneKind::Plugin(String::from("night"));
+
+        // 2025-03-14 is a Friday
+        assert_eq!(
+            schedule.scene_at(at((2025, 3, 14), (7, 0)), &all),
+            &SceneKind::Today
+        );
+        assert_eq!(schedule.scene_at(at((2025, 3, 14), (8, 0)), &all), &all);
+        assert_eq!(schedule.scene_at(at((2025, 3, 15), (7, 0)), &all), &all);
+        assert_eq!(schedule.scene_at(at((2025, 3, 14), (23, 0)), &all), &night);
+        assert_eq!(schedule.scene_at(at((2025, 3, 15), (1, 59)), &all), &night);
+        assert_eq!(schedule.scene_at(at((2025, 3, 16), (1, 0)), &all), &all);
+        assert_eq!(schedule.scene_at(at((2025, 3, 13), (23, 0)), &all), &all);
+        assert_eq!(schedule.rule_at(at((2025, 3, 15), (1, 0))), Some(1));
+        assert_eq!(schedule.rule_at(at((2025, 3, 15), (3, 0))), None);
+    }
+}
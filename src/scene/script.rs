@@ -0,0 +1,583 @@
+use crate::error::{Error, Result, ResultExt};
+use crate::scene::registry::{ScenePlugin, SceneRegistry};
+use crate::scene::{
+    Context, Element, Handle, Layout, LayoutId, LayoutItem, Layouter, Picture, Scene, SceneEvent,
+    SystemEvent, Text, UserEvent,
+};
+use crate::v2d::{rect::Rect, v2::V2, v4::V4};
+use rhai::{AST, Array, CallFnOptions, Dynamic, Engine, Map, Scope};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// ----------------------------------------------------------------------------
+// Scenes written in Rhai (feature `scripting`). Every `<name>.rhai` file in
+// `assets/scenes/` becomes a scene plugin called `<name>`:
+//
+//   // returns the elements to show, called on enter, on user events and
+//   // once per second
+//   fn render(ctx) {
+//       this.count += 1;    // `this` is a map kept between calls
+//       [
+//           #{ text: `${ctx.hour}:${ctx.minute}`, x: 0.05, y: 0.4, h: 0.2 },
+//           #{ photo: 0, x: 0.6, y: 0.1, h: 0.3 },
+//       ]
+//   }
+//
+//   // optional, `name` is "next", "previous" or "home"
+//   fn on_event(ctx, name) { }
+//
+// Elements are maps with either `text` or `photo` (an index below
+// `ctx.photo_count`), the position `x`, `y` and height `h` in screen units,
+// and optionally `w`, `color` ([r, g, b, a]), `opacity` and `id`. A photo
+// without `h` fills the screen. `ctx` holds the date and time, the screen's
+// `aspect_ratio`, `photo_count`, `temperature` if known and the
+// `[scenes.<name>]` config section as `config`.
+pub const SCRIPT_DIR: &str = "assets/scenes/";
+
+// Keeps a script stuck in a loop from freezing the frame
+const MAX_OPERATIONS: u64 = 1_000_000;
+const TEXT_HEIGHT: f32 = 0.05;
+
+// ----------------------------------------------------------------------------
+// Registers a plugin per script, broken scripts are logged and skipped
+pub fn register_scripts(registry: &mut SceneRegistry, dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let plugin = ScriptPlugin::load(&path).and_then(|plugin| registry.register(plugin));
+        if let Err(e) = plugin {
+            log::error!("Failed to load scene script {path:?}: {e:?}");
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn create_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    // the same nesting limits in debug and release builds
+    engine.set_max_expr_depths(64, 32);
+    engine.on_print(|text| log::info!("script: {text}"));
+    engine.on_debug(|text, _, pos| log::debug!("script {pos}: {text}"));
+    engine
+}
+
+// ----------------------------------------------------------------------------
+fn script_error(err: impl std::fmt::Display) -> Error {
+    Error::Script {
+        msg: err.to_string(),
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub struct ScriptPlugin {
+    name: String,
+    engine: Rc<Engine>,
+    ast: Rc<AST>,
+    config: Dynamic,
+}
+
+impl ScriptPlugin {
+    // ------------------------------------------------------------------------
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let source = std::fs::read_to_string(path).with_path("reading", path)?;
+        Self::from_source(name, &source)
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn from_source(name: &str, source: &str) -> Result<Self> {
+        let engine = create_engine();
+        let ast = engine.compile(source).map_err(script_error)?;
+        if !has_function(&ast, "render", 1) {
+            return Err(script_error("missing fn render(ctx)"));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            engine: Rc::new(engine),
+            ast: Rc::new(ast),
+            config: Dynamic::from_map(Map::new()),
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    fn scene(&self) -> ScriptScene {
+        ScriptScene {
+            name: self.name.clone(),
+            engine: Rc::clone(&self.engine),
+            ast: Rc::clone(&self.ast),
+            config: self.config.clone(),
+            state: Dynamic::from_map(Map::new()),
+            elements: Vec::new(),
+            rendered_at: None,
+            failed: false,
+            texts: HashMap::new(),
+            photos: HashMap::new(),
+            pending: false,
+        }
+    }
+}
+
+impl ScenePlugin for ScriptPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn init(&mut self, config: Option<&toml::Table>) -> Result<()> {
+        if let Some(config) = config {
+            self.config = toml_to_dynamic(&toml::Value::Table(config.clone()));
+        }
+        Ok(())
+    }
+
+    fn create(&self, _ctx: &Context) -> Result<Box<dyn Scene>> {
+        Ok(Box::new(self.scene()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn has_function(ast: &AST, name: &str, params: usize) -> bool {
+    ast.iter_functions()
+        .any(|f| f.name == name && f.params.len() == params)
+}
+
+// ----------------------------------------------------------------------------
+fn toml_to_dynamic(value: &toml::Value) -> Dynamic {
+    match value {
+        toml::Value::String(s) => s.clone().into(),
+        toml::Value::Integer(i) => (*i).into(),
+        toml::Value::Float(f) => (*f).into(),
+        toml::Value::Boolean(b) => (*b).into(),
+        toml::Value::Datetime(d) => d.to_string().into(),
+        toml::Value::Array(values) => {
+            Dynamic::from_array(values.iter().map(toml_to_dynamic).collect())
+        }
+        toml::Value::Table(table) => Dynamic::from_map(
+            table
+                .iter()
+                .map(|(k, v)| (k.as_str().into(), toml_to_dynamic(v)))
+                .collect(),
+        ),
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+enum Content {
+    Text(String),
+    Photo(usize),
+}
+
+// ----------------------------------------------------------------------------
+// Element as returned by the script, in screen units
+#[derive(Clone, Debug, PartialEq)]
+struct ElementSpec {
+    id: u32,
+    content: Content,
+    x: f32,
+    y: f32,
+    w: Option<f32>,
+    h: Option<f32>,
+    color: [f32; 4],
+    opacity: f32,
+}
+
+impl ElementSpec {
+    // ------------------------------------------------------------------------
+    fn from_map(map: &Map, index: usize) -> Result<Self> {
+        let number = |key: &str| -> Result<Option<f32>> {
+            match map.get(key) {
+                None => Ok(None),
+                Some(value) => as_number(value)
+                    .map(Some)
+                    .ok_or_else(|| script_error(format!("`{key}` is not a number"))),
+            }
+        };
+
+        let content = match (map.get("text"), map.get("photo")) {
+            (Some(text), None) => Content::Text(text.to_string()),
+            (None, Some(photo)) => {
+                let id = photo.as_int().ok().and_then(|id| usize::try_from(id).ok());
+                Content::Photo(id.ok_or_else(|| script_error("`photo` is not an index"))?)
+            }
+            _ => return Err(script_error("element needs either `text` or `photo`")),
+        };
+
+        let color = match map.get("color") {
+            None => [1.0; 4],
+            Some(color) => {
+                let values: Vec<f32> = color
+                    .as_array_ref()
+                    .map(|a| a.iter().filter_map(as_number).collect())
+                    .unwrap_or_default();
+                match values[..] {
+                    [r, g, b] => [r, g, b, 1.0],
+                    [r, g, b, a] => [r, g, b, a],
+                    _ => return Err(script_error("`color` is not [r, g, b, a]")),
+                }
+            }
+        };
+
+        let id = match map.get("id") {
+            None => index as u32 + 1,
+            Some(id) => id
+                .as_int()
+                .ok()
+                .and_then(|id| u32::try_from(id).ok())
+                .ok_or_else(|| script_error("`id` is not a number"))?,
+        };
+
+        Ok(Self {
+            id,
+            content,
+            x: number("x")?.unwrap_or(0.0),
+            y: number("y")?.unwrap_or(0.0),
+            w: number("w")?,
+            h: number("h")?,
+            color,
+            opacity: number("opacity")?.unwrap_or(1.0),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn as_number(value: &Dynamic) -> Option<f32> {
+    value
+        .as_float()
+        .ok()
+        .map(|f| f as f32)
+        .or_else(|| value.as_int().ok().map(|i| i as f32))
+}
+
+// ----------------------------------------------------------------------------
+fn context_map(ctx: &Context, aspect_ratio: f32, config: &Dynamic) -> Map {
+    let (year, month, day) = ctx.time.date.to_ymd();
+    let (hour, minute, second) = ctx.time.time.to_hms();
+    let mut map = Map::new();
+    let mut set = |key: &str, value: Dynamic| {
+        map.insert(key.into(), value);
+    };
+    set("year", (year as i64).into());
+    set("month", (i32::from(month) as i64).into());
+    set("day", (day as i64).into());
+    set("weekday", (ctx.time.date.weekday() as i64).into()); // 0 = Monday
+    set("hour", (hour as i64).into());
+    set("minute", (minute as i64).into());
+    set("second", (second as i64).into());
+    set("aspect_ratio", (aspect_ratio as f64).into());
+    set("photo_count", (ctx.photos.len() as i64).into());
+    if let Some(weather) = &ctx.weather {
+        set("temperature", (weather.temperature as f64).into());
+    }
+    set("config", config.clone());
+    map
+}
+
+// ----------------------------------------------------------------------------
+struct ScriptScene {
+    name: String,
+    engine: Rc<Engine>,
+    ast: Rc<AST>,
+    config: Dynamic,
+    state: Dynamic, // `this` of the script functions
+    elements: Vec<ElementSpec>,
+    rendered_at: Option<(u32, u32, u32)>,
+    failed: bool, // errors are logged once until the script works again
+    texts: HashMap<String, Handle>,
+    photos: HashMap<usize, Handle>,
+    pending: bool, // photos left out until they are decoded
+}
+
+impl ScriptScene {
+    // ------------------------------------------------------------------------
+    fn call(&mut self, name: &str, args: impl rhai::FuncArgs) -> Result<Dynamic> {
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.state);
+        self.engine
+            .call_fn_with_options(options, &mut Scope::new(), &self.ast, name, args)
+            .map_err(script_error)
+    }
+
+    // ------------------------------------------------------------------------
+    fn run_render(&mut self, ctx: Map) -> Result<Vec<ElementSpec>> {
+        let result = self.call("render", (ctx,))?;
+        let elements: Array = result
+            .try_cast()
+            .ok_or_else(|| script_error("render must return an array"))?;
+        elements
+            .iter()
+            .enumerate()
+            .map(|(i, element)| match element.read_lock::<Map>() {
+                Some(map) => ElementSpec::from_map(&map, i),
+                None => Err(script_error("elements must be maps")),
+            })
+            .collect()
+    }
+
+    // ------------------------------------------------------------------------
+    fn report(&mut self, result: Result<()>) {
+        match result {
+            Ok(()) => self.failed = false,
+            Err(e) => {
+                if !std::mem::replace(&mut self.failed, true) {
+                    log::error!("Scene script {:?} failed: {e:?}", self.name);
+                }
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Returns a layout if the elements changed or `force` is set
+    fn render(&mut self, ctx: &Context, layouter: &mut Layouter, force: bool) -> Option<Layout> {
+        self.rendered_at = Some(ctx.time.time.to_hms());
+        let ctx_map = context_map(ctx, layouter.aspect_ratio(), &self.config);
+        let elements = match self.run_render(ctx_map) {
+            Ok(elements) => elements,
+            Err(e) => {
+                self.report(Err(e));
+                return None;
+            }
+        };
+        self.report(Ok(()));
+        if !force && elements == self.elements {
+            return None;
+        }
+        self.elements = elements;
+        Some(self.build_layout(ctx, layouter))
+    }
+
+    // ------------------------------------------------------------------------
+    // Meshes and textures are kept while they are shown, unused ones freed
+    fn build_layout(&mut self, ctx: &Context, layouter: &mut Layouter) -> Layout {
+        let aspect = layouter.aspect_ratio();
+        let mut texts = HashMap::new();
+        let mut photos = HashMap::new();
+        let mut items = Vec::new();
+        self.pending = false;
+
+        for spec in &self.elements {
+            let color = V4::new(spec.color);
+            let element = match &spec.content {
+                Content::Text(text) => {
+                    let handle = match self.texts.remove(text).or_else(|| texts.get(text).copied())
+                    {
+                        Some(handle) => handle,
+                        None => match layouter.create_text(text) {
+                            Ok(handle) => handle,
+                            Err(e) => {
+                                log::warn!("Script {:?}: text {text:?}: {e:?}", self.name);
+                                continue;
+                            }
+                        },
+                    };
+                    texts.insert(text.clone(), handle);
+                    let h = spec.h.unwrap_or(TEXT_HEIGHT);
+                    Element::Text(Text {
+                        dst: Rect::new(V2::new([spec.x, spec.y]), V2::new([h / aspect, h])),
+                        opacity: spec.opacity,
+                        color,
+                        handle,
+                    })
+                }
+                Content::Photo(id) => {
+                    let cached = self.photos.remove(id).or_else(|| photos.get(id).copied());
+                    let handle = match cached {
+                        Some(handle) => handle,
+                        None => {
+                            let handle = ctx
+                                .find_photo(*id)
+                                .ok_or(Error::InvalidPhotoId)
+                                .and_then(|photo| layouter.load_photo(photo));
+                            match handle {
+                                Ok(handle) => handle,
+                                Err(e) => {
+                                    log::warn!("Script {:?}: photo {id}: {e:?}", self.name);
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+                    if handle.pending {
+                        self.pending = true;
+                        continue;
+                    }
+                    photos.insert(*id, handle);
+                    let dst = match spec.h {
+                        None => Rect::unit().fit(handle.aspect_ratio / aspect),
+                        Some(h) => {
+                            let w = spec.w.unwrap_or(h * handle.aspect_ratio / aspect);
+                            Rect::new(V2::new([spec.x, spec.y]), V2::new([w, h]))
+                        }
+                    };
+                    Element::Picture(Picture {
+                        dst,
+                        src: Rect::unit(),
+                        opacity: spec.opacity,
+                        handle,
+                    })
+                }
+            };
+            items.push(LayoutItem {
+                id: LayoutId(spec.id),
+                element,
+                animation_time: None,
+            });
+        }
+
+        self.free(layouter);
+        self.texts = texts;
+        self.photos = photos;
+        Layout { items }
+    }
+
+    // ------------------------------------------------------------------------
+    fn free(&mut self, layouter: &mut Layouter) {
+        let handles = self.texts.drain().map(|(_, h)| h);
+        for handle in handles.chain(self.photos.drain().map(|(_, h)| h)) {
+            layouter.free_handle(handle);
+        }
+    }
+}
+
+impl Scene for ScriptScene {
+    fn update(
+        &mut self,
+        event: &SceneEvent,
+        ctx: &Context,
+        layouter: &mut Layouter,
+    ) -> Option<Layout> {
+        match event {
+            SceneEvent::Enter => self.render(ctx, layouter, true),
+            SceneEvent::Exit => {
+                self.free(layouter);
+                self.elements.clear();
+                Some(Layout::empty())
+            }
+            SceneEvent::TimeTick(_) => {
+                if self.rendered_at == Some(ctx.time.time.to_hms()) {
+                    return None;
+                }
+                self.render(ctx, layouter, false)
+            }
+            SceneEvent::User(event) => {
+                let name = match event {
+                    UserEvent::Next => "next",
+                    UserEvent::Previous => "previous",
+                    UserEvent::Home => "home",
+                    UserEvent::Exit => return None,
+                };
+                if has_function(&self.ast, "on_event", 2) {
+                    let ctx_map = context_map(ctx, layouter.aspect_ratio(), &self.config);
+                    let result = self.call("on_event", (ctx_map, name.to_string()));
+                    self.report(result.map(|_| ()));
+                }
+                self.render(ctx, layouter, false)
+            }
+            SceneEvent::System(
+                SystemEvent::PhotoDecoded { .. } | SystemEvent::PhotoFailed { .. },
+            ) if self.pending => Some(self.build_layout(ctx, layouter)),
+            SceneEvent::System(_) => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::datetime::DateTime;
+    use crate::util::locale::{LocaleId, Units};
+
+    fn scene(source: &str) -> ScriptScene {
+        ScriptPlugin::from_source("test", source).unwrap().scene()
+    }
+
+    fn context() -> Context {
+        Context {
+            photos: Vec::new(),
+            time: DateTime::now(),
+            weather: None,
+            locale: LocaleId::default().locale(),
+            units: Units::metric(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let mut scene = scene(
+            r#"
+            fn render(ctx) {
+                this.calls = (this.calls ?? 0) + 1;
+                [
+                    #{ text: `calls ${this.calls}`, x: 0.1, y: 0.5, h: 0.2 },
+                    #{ photo: 3, color: [1, 0, 0], id: 7 },
+                ]
+            }
+            "#,
+        );
+        let ctx = context_map(&context(), 1.5, &Dynamic::UNIT);
+        let elements = scene.run_render(ctx.clone()).unwrap();
+        assert_eq!(elements[0].content, Content::Text(String::from("calls 1")));
+        assert_eq!((elements[0].id, elements[0].h), (1, Some(0.2)));
+        assert_eq!(elements[1].content, Content::Photo(3));
+        assert_eq!(elements[1].color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(elements[1].id, 7);
+
+        // `this` is kept between calls
+        let elements = scene.run_render(ctx).unwrap();
+        assert_eq!(elements[0].content, Content::Text(String::from("calls 2")));
+    }
+
+    #[test]
+    fn test_invalid_scripts() {
+        assert!(ScriptPlugin::from_source("x", "fn draw(ctx) { [] }").is_err());
+        assert!(ScriptPlugin::from_source("x", "fn render(ctx) { [").is_err());
+
+        let ctx = context_map(&context(), 1.0, &Dynamic::UNIT);
+        let mut endless = scene("fn render(ctx) { loop {} }");
+        assert!(endless.run_render(ctx.clone()).is_err());
+        let mut no_content = scene("fn render(ctx) { [#{ x: 1 }] }");
+        assert!(no_content.run_render(ctx.clone()).is_err());
+        let mut not_array = scene("fn render(ctx) { 42 }");
+        assert!(not_array.run_render(ctx).is_err());
+    }
+
+    #[test]
+    fn test_example_script() {
+        let path = Path::new(SCRIPT_DIR).join("greeting.rhai");
+        let mut plugin = ScriptPlugin::load(&path).unwrap();
+        let config: toml::Table = toml::from_str("name = \"Anna\"").unwrap();
+        plugin.init(Some(&config)).unwrap();
+
+        let mut scene = plugin.scene();
+        let ctx = context_map(&context(), 1.5, &scene.config.clone());
+        let elements = scene.run_render(ctx).unwrap();
+        let Content::Text(greeting) = &elements[1].content else {
+            panic!("no greeting");
+        };
+        assert!(greeting.ends_with(", Anna"));
+    }
+
+    #[test]
+    fn test_config() {
+        let mut plugin = ScriptPlugin::from_source("x", "fn render(ctx) { [] }").unwrap();
+        let config: toml::Table = toml::from_str("city = \"Bern\"\nsize = [1, 2.5]").unwrap();
+        plugin.init(Some(&config)).unwrap();
+        let map = plugin.config.read_lock::<Map>().unwrap();
+        assert_eq!(map["city"].to_string(), "Bern");
+        assert_eq!(map["size"].to_string(), "[1, 2.5]");
+    }
+}
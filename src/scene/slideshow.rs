@@ -1,20 +1,81 @@
 use crate::error::{Error, Result};
+use crate::gfx::animation::{Animation, Easing, Track};
+use crate::gfx::timeline::Timeline;
+use crate::gfx::transition::TransitionKind;
 use crate::scene::{
-    Context, Element, Handle, Layout, LayoutId, LayoutItem, Layouter, Picture, Rect, Scene,
-    SceneEvent, Text, Transition, UserEvent,
+    Context, Element, Handle, Layout, LayoutId, LayoutItem, Layouter, Picture, Scene, SceneEvent,
+    SystemEvent, Text, Transition, UserEvent,
+    photo::{DisplayHints, Photo},
+    picker,
+    video::{self, PendingClip, VideoClip},
 };
 use crate::util::datetime::Date;
 use crate::util::locale::fmt_long;
-use crate::v2d::{v2::V2, v4::V4};
+use crate::util::random::XorShift;
+use crate::v2d::{rect::Rect, v2::V2, v4::V4};
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+// Default seconds a photo is shown and the cross fade to the next one takes
+const SHOW_DURATION: f32 = 1.5;
+const TRANSITION_DURATION: f32 = 0.4;
+
+// Longer titles end with an ellipsis
+const MAX_TITLE_LINES: usize = 2;
+
+// Panned photos are shown this much larger to have room to move
+const PAN_ZOOM: f32 = 1.15;
+
+// Ken Burns views show at least this part of the photo's width and height
+const KEN_BURNS_MIN_VIEW: f32 = 0.8;
+
+// Photos picked ahead and decoded in the background
+const PREFETCH_COUNT: usize = 2;
+
+// Photos that `Previous` goes back to
+const MAX_HISTORY: usize = 100;
 
 // ----------------------------------------------------------------------------
-#[derive(Clone, Debug)]
+// How slideshows show their photos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlideTiming {
+    pub show: f32,                      // seconds a photo is shown
+    pub transition: f32,                // seconds of the transition
+    pub easing: Easing,                 // of the transition
+    pub effect: Option<TransitionKind>, // a random one for each photo if None
+    pub jitter: f32,                    // fraction `show` randomly varies by, e.g. 0.2 for ±20%
+    pub ken_burns: bool,                // photos slowly pan and zoom while shown
+    pub seed: u64,                      // of the variation and the order, one per session
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SlideTrack {
+    Show,
+    Transition,
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Debug)]
 pub struct SlideShowScene {
     photos: Vec<usize>,
     title: String,
-    tick_count: usize,
+    timeline: Timeline<SlideTrack>,
+    show_duration: f32,
+    transition_duration: f32,
+    easing: Easing,
+    effect: Option<TransitionKind>,
+    jitter: f32,
+    ken_burns: bool,
+    rng: XorShift,
     index: usize,
+    history: Vec<usize>,         // indices shown before, the last one last
+    upcoming: VecDeque<usize>,   // indices shown next, the first one first
+    waiting: Option<usize>,      // index of a photo shown once it's decoded
+    placeholder: Option<Handle>, // title shown while the first photo is decoded
     state: SlideshowState,
+    clip: Option<VideoClip>, // of the photo shown last, if it's a video
+    starting: Option<(usize, PendingClip)>, // clip shown once its first frame is decoded
 }
 
 // ----------------------------------------------------------------------------
@@ -23,6 +84,24 @@ struct PhotoState {
     index: usize,
     photo: Handle,
     text: Handle,
+    hints: DisplayHints,
+    view: Option<Animation<V4>>, // Ken Burns crop of the photo over the show progress
+}
+
+impl PhotoState {
+    // ------------------------------------------------------------------------
+    // Part of the photo shown as the show progress `t` runs from 0 to 1
+    fn src(&self, t: f32) -> Rect {
+        let Some(view) = &self.view else {
+            return Rect::unit();
+        };
+        let [x, y, cx, cy] = view.blend(t).as_array();
+        Rect::new(V2::new([x, y]), V2::new([cx, cy]))
+    }
+
+    fn is_still(&self) -> bool {
+        self.hints.pan.is_none() && self.view.is_none()
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -33,9 +112,9 @@ enum SlideshowState {
         photo: PhotoState,
     },
     Transitioning {
-        photo_from: PhotoState,
-        photo_to: PhotoState,
-        duration: usize,
+        photo_from: Box<PhotoState>,
+        photo_to: Box<PhotoState>,
+        kind: TransitionKind,
     },
 }
 
@@ -50,12 +129,37 @@ impl SlideShowScene {
         Ok(Self {
             photos,
             title,
-            tick_count: 0,
+            timeline: Timeline::new(),
+            show_duration: SHOW_DURATION,
+            transition_duration: TRANSITION_DURATION,
+            easing: Easing::SineInOut,
+            effect: Some(TransitionKind::Crossfade),
+            jitter: 0.0,
+            ken_burns: false,
+            rng: XorShift::new(0),
             index: 0,
+            history: Vec::new(),
+            upcoming: VecDeque::new(),
+            waiting: None,
+            placeholder: None,
             state: SlideshowState::Idle,
+            clip: None,
+            starting: None,
         })
     }
 
+    // ------------------------------------------------------------------------
+    pub fn with_timing(mut self, timing: SlideTiming) -> Self {
+        self.show_duration = timing.show;
+        self.transition_duration = timing.transition;
+        self.easing = timing.easing;
+        self.effect = timing.effect;
+        self.jitter = timing.jitter;
+        self.ken_burns = timing.ken_burns;
+        self.rng = XorShift::new(timing.seed);
+        self
+    }
+
     // ------------------------------------------------------------------------
     fn start_transition(
         &mut self,
@@ -64,11 +168,27 @@ impl SlideShowScene {
         layouter: &mut Layouter,
     ) -> Option<bool> {
         self.finish_transition(layouter);
-        log::info!("Slideshow: transitioning to photo index {}", next_index);
+        self.waiting = None;
 
         let id = self.photos[next_index];
         let photo = ctx.find_photo(id)?;
-        let photo_handle = layouter.load_photo(photo).ok()?;
+        let (photo_handle, clip) = match video::is_video_file(&photo.path) {
+            true => self.load_clip(next_index, &photo.path, layouter)?,
+            false => {
+                self.starting = None;
+                (layouter.load_photo(photo).ok()?, None)
+            }
+        };
+        // the photo on screen stays until the next one is decoded
+        if photo_handle.pending {
+            self.waiting = Some(next_index);
+            return Some(false);
+        }
+        if let Some(placeholder) = self.placeholder.take() {
+            layouter.free_handle(placeholder);
+        }
+        log::info!("Slideshow: transitioning to photo index {}", next_index);
+        self.clip = clip;
 
         // let text = photo
         //     .meta
@@ -86,22 +206,40 @@ impl SlideShowScene {
         .to_string();
 
         //let res = layouter.create_text(&text);
-        let text_handle = layouter.create_multiline_text(&text, 0.6 / 0.05).ok()?;
+        let text_handle = layouter
+            .create_multiline_text(&text, 0.6 / 0.05, Some(MAX_TITLE_LINES))
+            .ok()?;
+        // clips and photos with a pan of their own keep still
+        let view = (self.ken_burns && self.clip.is_none() && photo.meta.display.pan.is_none())
+            .then(|| ken_burns_view(&mut self.rng));
         let photo_to = PhotoState {
             index: next_index,
             photo: photo_handle,
             text: text_handle,
+            hints: photo.meta.display.clone(),
+            view,
         };
 
-        self.tick_count = 0;
+        self.timeline.clear();
         self.index = next_index;
         self.state = if let SlideshowState::Static { photo } = &self.state {
+            self.timeline.add(
+                SlideTrack::Transition,
+                progress_track(self.transition_duration),
+            );
+            let kind = match self.effect {
+                Some(kind) => kind,
+                None => TransitionKind::random(&mut self.rng),
+            };
             SlideshowState::Transitioning {
-                photo_from: photo.clone(),
-                photo_to: photo_to.clone(),
-                duration: 40,
+                photo_from: Box::new(photo.clone()),
+                photo_to: Box::new(photo_to.clone()),
+                kind,
             }
         } else {
+            let duration = self.slide_duration(&photo_to.hints);
+            self.timeline
+                .add(SlideTrack::Show, progress_track(duration));
             SlideshowState::Static {
                 photo: photo_to.clone(),
             }
@@ -110,77 +248,205 @@ impl SlideShowScene {
         Some(true)
     }
 
+    // ------------------------------------------------------------------------
+    fn free(&mut self, layouter: &mut Layouter) {
+        let photos = match &self.state {
+            SlideshowState::Idle => vec![],
+            SlideshowState::Static { photo } => vec![photo],
+            SlideshowState::Transitioning {
+                photo_from,
+                photo_to,
+                ..
+            } => vec![&**photo_from, &**photo_to],
+        };
+        for photo in photos {
+            layouter.free_handle(photo.photo);
+            layouter.free_handle(photo.text);
+        }
+        if let Some(placeholder) = self.placeholder.take() {
+            layouter.free_handle(placeholder);
+        }
+        self.timeline.clear();
+        self.upcoming.clear();
+        self.waiting = None;
+        self.state = SlideshowState::Idle;
+        self.clip = None;
+        self.starting = None;
+    }
+
+    // ------------------------------------------------------------------------
+    // Starts a clip in the background and shows its first frame until the
+    // playback catches up, the handle is pending until the frame is decoded
+    fn load_clip(
+        &mut self,
+        index: usize,
+        path: &std::path::Path,
+        layouter: &mut Layouter,
+    ) -> Option<(Handle, Option<VideoClip>)> {
+        let mut pending = match self.starting.take() {
+            Some((starting, pending)) if starting == index => pending,
+            _ => PendingClip::start(path),
+        };
+        let Some(started) = pending.take() else {
+            self.starting = Some((index, pending));
+            return Some((Handle::PENDING, None));
+        };
+        let (clip, frame) = started
+            .inspect_err(|e| log::warn!("Slideshow: can't play {path:?}: {e:?}"))
+            .ok()?;
+        let handle = layouter.upload_photo(clip.cx, clip.cy, &frame).ok()?;
+        Some((handle, Some(clip)))
+    }
+
+    // ------------------------------------------------------------------------
+    // Shows the photo waited for once it's decoded, or the next one if it
+    // can't be shown
+    fn show_waiting(&mut self, ctx: &Context, layouter: &mut Layouter) {
+        if let Some(index) = self.waiting
+            && self.start_transition(index, ctx, layouter).is_none()
+        {
+            self.show_next(ctx, layouter);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Clips are shown until they end, photos for their own duration or the
+    // configured one give or take the jitter
+    fn slide_duration(&mut self, hints: &DisplayHints) -> f32 {
+        match (&self.clip, hints.duration()) {
+            (Some(clip), _) => clip.remaining().max(self.transition_duration),
+            (None, Some(seconds)) => seconds,
+            (None, None) => self.rng.jitter(self.show_duration, self.jitter),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Uploads the next frame of a playing clip into the texture of its photo
+    fn update_clip(&mut self, dt: f32, layouter: &mut Layouter) {
+        let handle = match &self.state {
+            SlideshowState::Static { photo } => photo.photo,
+            SlideshowState::Transitioning { photo_to, .. } => photo_to.photo,
+            SlideshowState::Idle => return,
+        };
+        let Some(clip) = &mut self.clip else {
+            return;
+        };
+        if let Some(frame) = clip.update(dt)
+            && let Err(e) = layouter.update_photo(&handle, clip.cx, clip.cy, &frame)
+        {
+            log::warn!("Slideshow: video frame upload failed: {e:?}");
+            self.clip = None;
+        }
+    }
+
     // ------------------------------------------------------------------------
     fn finish_transition(&mut self, layouter: &mut Layouter) {
         log::info!("Slideshow: finishing transition");
-        self.tick_count = 0;
-        self.state = if let SlideshowState::Transitioning {
+        let SlideshowState::Transitioning {
             photo_from,
             photo_to,
             ..
         } = &self.state
-        {
-            layouter.free_handle(photo_from.photo);
-            layouter.free_handle(photo_from.text);
-            SlideshowState::Static {
-                photo: photo_to.clone(),
-            }
-        } else {
-            self.state.clone()
+        else {
+            return;
         };
+        layouter.free_handle(photo_from.photo);
+        layouter.free_handle(photo_from.text);
+        let photo = PhotoState::clone(photo_to);
+        let duration = self.slide_duration(&photo.hints);
+        self.timeline
+            .add(SlideTrack::Show, progress_track(duration));
+        self.state = SlideshowState::Static { photo };
     }
 
     // ------------------------------------------------------------------------
     fn layout(&mut self, layouter: &mut Layouter) -> Option<Layout> {
         match &self.state {
-            SlideshowState::Idle => None,
+            SlideshowState::Idle => self.placeholder_layout(layouter),
             SlideshowState::Static { photo } => self.static_layout(photo, layouter),
             SlideshowState::Transitioning {
                 photo_from,
                 photo_to,
-                duration,
-            } => self.transition_layout(photo_from, photo_to, duration, layouter),
+                kind,
+            } => self.transition_layout(photo_from, photo_to, *kind, layouter),
         }
     }
 
+    // ------------------------------------------------------------------------
+    // The title until the first photo is decoded
+    fn placeholder_layout(&mut self, layouter: &mut Layouter) -> Option<Layout> {
+        self.waiting?;
+        let handle = match self.placeholder {
+            Some(handle) => handle,
+            None => {
+                let handle = layouter
+                    .create_multiline_text(&self.title, 0.6 / 0.05, Some(MAX_TITLE_LINES))
+                    .ok()?;
+                *self.placeholder.insert(handle)
+            }
+        };
+        let text = Text {
+            dst: Rect::new(V2::new([0.025, 0.025]), V2::new([0.05, 0.05])),
+            color: V4::new([1.0, 1.0, 1.0, 1.0]),
+            opacity: 1.0,
+            handle,
+        };
+        let items = vec![LayoutItem {
+            id: LayoutId(1),
+            element: Element::Text(text),
+            animation_time: Some(0.5),
+        }];
+        Some(Layout { items })
+    }
+
     // ------------------------------------------------------------------------
     fn static_layout(&self, current: &PhotoState, layouter: &mut Layouter) -> Option<Layout> {
         let src_aspect = current.photo.aspect_ratio;
         let dst_aspect = layouter.aspect_ratio();
-        let dst = place_photo(src_aspect, dst_aspect);
+        let shown = self.timeline.value(SlideTrack::Show).unwrap_or(0.0);
+        let dst = place_photo(src_aspect, dst_aspect, &current.hints, shown);
 
         let picture = Picture {
             dst,
-            src: Rect {
-                pos: V2::new([0.0, 0.0]),
-                size: V2::new([1.0, 1.0]),
-            },
+            src: current.src(shown),
             opacity: 1.0,
             handle: current.photo,
         };
 
         let text = Text {
-            dst: Rect {
-                pos: V2::new([0.025, 0.025]),
-                size: V2::new([0.05, 0.05]),
-            },
+            dst: Rect::new(V2::new([0.025, 0.025]), V2::new([0.05, 0.05])),
             color: V4::new([1.0, 1.0, 1.0, 1.0]),
             opacity: 1.0,
             handle: current.text,
         };
 
-        let items = vec![
+        let backdrop = layouter
+            .backdrop(&current.photo)
+            .map(|backdrop| LayoutItem {
+                id: LayoutId(2),
+                element: Element::Picture(Picture {
+                    dst: Rect::unit(),
+                    src: backdrop_src(&backdrop, dst_aspect),
+                    opacity: 1.0,
+                    handle: backdrop,
+                }),
+                animation_time: None,
+            });
+
+        let mut items = Vec::from_iter(backdrop);
+        items.extend([
             LayoutItem {
                 id: LayoutId(0),
                 element: Element::Picture(picture),
-                animation_time: Some(0.5),
+                // panning moves the photo on every tick, easing would lag behind
+                animation_time: current.is_still().then_some(0.5),
             },
             LayoutItem {
                 id: LayoutId(1),
                 element: Element::Text(text),
                 animation_time: Some(0.5),
             },
-        ];
+        ]);
 
         log::info!("Slideshow: static layout for index {}", current.index);
 
@@ -192,35 +458,48 @@ impl SlideShowScene {
         &self,
         from: &PhotoState,
         to: &PhotoState,
-        duration: &usize,
+        kind: TransitionKind,
         layouter: &mut Layouter,
     ) -> Option<Layout> {
         let dst_aspect = layouter.aspect_ratio();
-        let from_dst = place_photo(from.photo.aspect_ratio, dst_aspect);
-        let to_dst = place_photo(to.photo.aspect_ratio, dst_aspect);
-        let progress = (self.tick_count as f32 / *duration as f32).min(1.0);
+        let from_dst = place_photo(from.photo.aspect_ratio, dst_aspect, &from.hints, 1.0);
+        let to_dst = place_photo(to.photo.aspect_ratio, dst_aspect, &to.hints, 0.0);
+        let progress = self.timeline.value(SlideTrack::Transition).unwrap_or(1.0);
 
         let transition = Transition {
             from_dst,
-            from_src: Rect {
-                pos: V2::new([0.0, 0.0]),
-                size: V2::new([1.0, 1.0]),
-            },
+            from_src: from.src(1.0),
             to_dst,
-            to_src: Rect {
-                pos: V2::new([0.0, 0.0]),
-                size: V2::new([1.0, 1.0]),
-            },
+            to_src: to.src(0.0),
             from: from.photo,
             to: to.photo,
             progress,
+            easing: self.easing,
+            kind,
         };
 
-        let items = vec![LayoutItem {
+        // the backdrops change along with the photos, without both there is none
+        let backdrops = Option::zip(layouter.backdrop(&from.photo), layouter.backdrop(&to.photo));
+        let backdrop = backdrops.map(|(from, to)| LayoutItem {
+            id: LayoutId(2),
+            element: Element::Transition(Transition {
+                from_dst: Rect::unit(),
+                from_src: backdrop_src(&from, dst_aspect),
+                to_dst: Rect::unit(),
+                to_src: backdrop_src(&to, dst_aspect),
+                from,
+                to,
+                ..transition
+            }),
+            animation_time: None,
+        });
+
+        let mut items = Vec::from_iter(backdrop);
+        items.push(LayoutItem {
             id: LayoutId(0),
             element: Element::Transition(transition),
             animation_time: Some(0.5),
-        }];
+        });
 
         log::info!(
             "Slideshow: transition progress {:.2} from index {} to index {}",
@@ -231,12 +510,82 @@ impl SlideShowScene {
         Some(Layout { items })
     }
 
-    fn next_index(&self) -> usize {
-        (self.index + 1) % self.photos.len()
+    // ------------------------------------------------------------------------
+    // Photos to be shown on their anniversary come first, the others are
+    // picked by rating, tags and when they were shown last, never the one on
+    // screen or one picked already
+    fn next_index(&mut self, ctx: &Context) -> usize {
+        let now = ctx.time.to_unix_seconds();
+        let on_screen = !matches!(self.state, SlideshowState::Idle) && self.photos.len() > 1;
+        let taken =
+            |index: usize| (on_screen && index == self.index) || self.upcoming.contains(&index);
+        let midnight = now - now.rem_euclid(86_400);
+        let due = self.photos.iter().enumerate().position(|(index, &id)| {
+            ctx.find_photo(id).is_some_and(|photo| {
+                photo.meta.display.show_on_date
+                    && photo.meta.is_anniversary(ctx.time.date)
+                    && photo.last_shown.is_none_or(|shown| shown < midnight)
+                    && !taken(index)
+            })
+        });
+        if let Some(index) = due {
+            return index;
+        }
+        let weights: Vec<f32> = self
+            .photos
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| match ctx.find_photo(id) {
+                Some(_) if taken(index) => 0.0,
+                Some(photo) => picker::weight(photo, now),
+                None => 0.0,
+            })
+            .collect();
+        picker::pick(&weights, &mut self.rng).unwrap_or(0)
+    }
+
+    // ------------------------------------------------------------------------
+    // Picks the photos after the one on screen ahead, so that they are
+    // decoded by the time they are shown
+    fn prefetch(&mut self, ctx: &Context, layouter: &mut Layouter) {
+        let others = self.photos.len().saturating_sub(1);
+        while self.upcoming.len() < PREFETCH_COUNT.min(others) {
+            let index = self.next_index(ctx);
+            if self.upcoming.contains(&index) {
+                break;
+            }
+            self.upcoming.push_back(index);
+            // clips are started once they are shown
+            if let Some(photo) = ctx.find_photo(self.photos[index])
+                && !video::is_video_file(&photo.path)
+            {
+                layouter.prefetch(photo);
+            }
+        }
+    }
+
+    fn show_next(&mut self, ctx: &Context, layouter: &mut Layouter) {
+        let next = match self.upcoming.pop_front() {
+            Some(index) => index,
+            None => self.next_index(ctx),
+        };
+        if !matches!(self.state, SlideshowState::Idle) && self.waiting.is_none() {
+            if self.history.len() == MAX_HISTORY {
+                self.history.remove(0);
+            }
+            self.history.push(self.index);
+        }
+        self.start_transition(next, ctx, layouter);
+        self.prefetch(ctx, layouter);
     }
 
-    fn prev_index(&self) -> usize {
-        (self.index + self.photos.len() - 1) % self.photos.len()
+    // The photo shown before, or the one before in the list
+    fn show_previous(&mut self, ctx: &Context, layouter: &mut Layouter) {
+        let previous = self
+            .history
+            .pop()
+            .unwrap_or((self.index + self.photos.len() - 1) % self.photos.len());
+        self.start_transition(previous, ctx, layouter);
     }
 }
 
@@ -250,30 +599,36 @@ impl Scene for SlideShowScene {
     ) -> Option<Layout> {
         match event {
             SceneEvent::Enter | SceneEvent::User(UserEvent::Home) => {
-                self.start_transition(0, ctx, layouter)?;
+                self.history.clear();
+                self.upcoming.clear();
+                let first = self.next_index(ctx);
+                self.start_transition(first, ctx, layouter)?;
+                self.prefetch(ctx, layouter);
             }
-            SceneEvent::TimeTick => {
-                self.tick_count += 1;
-                match &mut self.state {
-                    SlideshowState::Transitioning { duration, .. } => {
-                        if self.tick_count >= *duration {
-                            self.finish_transition(layouter);
-                        }
-                    }
-                    SlideshowState::Static { .. } => {
-                        if self.tick_count >= 150 {
-                            self.start_transition(self.next_index(), ctx, layouter);
-                        }
+            SceneEvent::TimeTick(dt) => {
+                self.update_clip(*dt, layouter);
+                if let Some((index, pending)) = &mut self.starting
+                    && self.waiting == Some(*index)
+                    && pending.is_ready()
+                {
+                    self.show_waiting(ctx, layouter);
+                }
+                for track in self.timeline.advance(*dt) {
+                    match track {
+                        SlideTrack::Transition => self.finish_transition(layouter),
+                        SlideTrack::Show => self.show_next(ctx, layouter),
                     }
-                    _ => {}
                 }
             }
-            SceneEvent::User(UserEvent::Next) => {
-                self.start_transition(self.next_index(), ctx, layouter);
-            }
-
-            SceneEvent::User(UserEvent::Previous) => {
-                self.start_transition(self.prev_index(), ctx, layouter);
+            SceneEvent::User(UserEvent::Next) => self.show_next(ctx, layouter),
+            SceneEvent::User(UserEvent::Previous) => self.show_previous(ctx, layouter),
+            SceneEvent::System(
+                SystemEvent::PhotoDecoded { .. } | SystemEvent::PhotoFailed { .. },
+            ) => self.show_waiting(ctx, layouter),
+
+            SceneEvent::Exit => {
+                self.free(layouter);
+                return Some(Layout::empty());
             }
 
             _ => {}
@@ -281,46 +636,157 @@ impl Scene for SlideShowScene {
 
         self.layout(layouter)
     }
+
+    fn name(&self) -> &str {
+        &self.title
+    }
+
+    fn current_photo(&self) -> Option<usize> {
+        match &self.state {
+            SlideshowState::Idle => None,
+            SlideshowState::Static { photo } => Some(self.photos[photo.index]),
+            SlideshowState::Transitioning { photo_to, .. } => Some(self.photos[photo_to.index]),
+        }
+    }
 }
 
 // ----------------------------------------------------------------------------
-fn place_photo(src_aspect: f32, dst_aspect: f32) -> Rect {
-    if src_aspect > dst_aspect {
-        // source is wider than destination
-        let scaled_height = dst_aspect / src_aspect;
-        let ofs_y = (1.0 - scaled_height) / 2.0;
-        let pos = V2::new([0.0, ofs_y]);
-        let size = V2::new([1.0, 1.0 * scaled_height]);
-        Rect { pos, size }
-    } else {
-        // source is taller than destination
-        let scaled_width = src_aspect / dst_aspect;
-        let ofs_x = (1.0 - scaled_width) / 2.0;
-        let pos = V2::new([ofs_x, 0.0]);
-        let size = V2::new([scaled_width, 1.0]);
-        Rect { pos, size }
+// Runs from 0 to 1 over the given duration
+fn progress_track(duration: f32) -> Track<f32> {
+    Track::new(0.0, 0.0).key(duration, 1.0, Easing::Linear)
+}
+
+// ----------------------------------------------------------------------------
+// Letterboxes the photo, or its crop with the rest of the photo around it,
+// inside the screen, both in normalized coordinates. Panned photos are shown
+// larger and move across the screen as `t` runs from 0 to 1.
+fn place_photo(src_aspect: f32, dst_aspect: f32, hints: &DisplayHints, t: f32) -> Rect {
+    let crop = hints.crop_rect().unwrap_or(Rect::unit());
+    let mut view = Rect::unit().fit(src_aspect * crop.aspect() / dst_aspect);
+    if let Some(pan) = hints.pan {
+        let size = view.size * PAN_ZOOM;
+        let room = (size - view.size) * (0.5 - t);
+        let dir = pan.vector();
+        let shift = V2::new([dir.x0() * room.x0(), dir.x1() * room.x1()]);
+        view = Rect::new(view.center() - size * 0.5 + shift, size);
     }
+    let size = view.size / crop.size;
+    let offset = V2::new([crop.pos.x0() * size.x0(), crop.pos.x1() * size.x1()]);
+    Rect::new(view.pos - offset, size)
 }
 
 // ----------------------------------------------------------------------------
-fn select_same_day(date: Date, ctx: &Context) -> Vec<usize> {
+// Part of a backdrop covering the screen, see `Layouter::backdrop`
+fn backdrop_src(backdrop: &Handle, dst_aspect: f32) -> Rect {
+    Rect::unit().fit(dst_aspect / backdrop.aspect_ratio)
+}
+
+// ----------------------------------------------------------------------------
+// Random start and end crops of a Ken Burns pan and zoom as (x, y, cx, cy).
+// Both are scaled evenly to keep the aspect ratio of the photo, one of them
+// shows all of it so that the view zooms in or out.
+fn ken_burns_view(rng: &mut XorShift) -> Animation<V4> {
+    let full = V4::new([0.0, 0.0, 1.0, 1.0]);
+    let size = KEN_BURNS_MIN_VIEW + (1.0 - KEN_BURNS_MIN_VIEW) * rng.next_f32();
+    let x = (1.0 - size) * rng.next_f32();
+    let y = (1.0 - size) * rng.next_f32();
+    let detail = V4::new([x, y, size, size]);
+    let view = Animation::new(0.0, 1.0, full, detail).with_easing(Easing::SineInOut);
+    match rng.next_f32() < 0.5 {
+        true => view,
+        false => view.reversed(),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Which photos the built-in slideshows show, a photo must pass every check,
+// e.g. `min_rating = 3` and `tags = ["family"]`
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PhotoFilter {
+    pub min_rating: Option<u8>,
+    pub tags: Vec<String>,         // has every one of them
+    pub any_tags: Vec<String>,     // has one of them unless empty
+    pub exclude_tags: Vec<String>, // has none of them
+}
+
+impl PhotoFilter {
+    // ------------------------------------------------------------------------
+    // The checks combined into one selection
+    pub fn selection(&self) -> Selection<'_> {
+        let mut selection: Selection = Box::new(|_| true);
+        if let Some(rating) = self.min_rating {
+            selection = select_both(selection, select_min_rating(rating));
+        }
+        for tag in &self.tags {
+            selection = select_both(selection, select_by_tag(tag));
+        }
+        let any = self.any_tags.iter().map(|tag| select_by_tag(tag));
+        if let Some(any) = any.reduce(select_either) {
+            selection = select_both(selection, any);
+        }
+        for tag in &self.exclude_tags {
+            selection = select_both(selection, select_not(select_by_tag(tag)));
+        }
+        selection
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Picks photos by their metadata, selections combine with `select_both`,
+// `select_either` and `select_not`
+pub type Selection<'a> = Box<dyn Fn(&Photo) -> bool + 'a>;
+
+pub fn select_by_tag(tag: &str) -> Selection<'_> {
+    Box::new(move |photo| {
+        let tags = photo.meta.tag.as_deref().unwrap_or_default();
+        tags.iter().any(|t| t == tag)
+    })
+}
+
+// Unrated photos count as 0
+pub fn select_min_rating<'a>(rating: u8) -> Selection<'a> {
+    Box::new(move |photo| photo.meta.rating.unwrap_or(0) >= rating)
+}
+
+// Photos of the day, and of its anniversaries if they ask to be shown then
+pub fn select_same_day<'a>(date: Date) -> Selection<'a> {
+    Box::new(move |photo| {
+        photo.meta.datetime.is_some_and(|dt| dt.date == date)
+            || (photo.meta.display.show_on_date && photo.meta.is_anniversary(date))
+    })
+}
+
+pub fn select_both<'a>(a: Selection<'a>, b: Selection<'a>) -> Selection<'a> {
+    Box::new(move |photo| a(photo) && b(photo))
+}
+
+pub fn select_either<'a>(a: Selection<'a>, b: Selection<'a>) -> Selection<'a> {
+    Box::new(move |photo| a(photo) || b(photo))
+}
+
+pub fn select_not<'a>(a: Selection<'a>) -> Selection<'a> {
+    Box::new(move |photo| !a(photo))
+}
+
+// ----------------------------------------------------------------------------
+// Indices of the selected photos of `ctx`
+fn select(ctx: &Context, selection: &Selection) -> Vec<usize> {
     ctx.photos
         .iter()
         .enumerate()
-        .filter(|(_, p)| p.meta.datetime.map(|dt| dt.date == date).unwrap_or(false))
+        .filter(|(_, photo)| selection(photo))
         .map(|(idx, _)| idx)
         .collect()
 }
 
 // ----------------------------------------------------------------------------
-fn select_all(ctx: &Context) -> Vec<usize> {
-    Vec::from_iter(0..ctx.photos.len())
-}
-
-// ----------------------------------------------------------------------------
-pub fn create_daily_slideshow(ctx: &Context) -> Result<SlideShowScene> {
+pub fn create_daily_slideshow(ctx: &Context, filter: &PhotoFilter) -> Result<SlideShowScene> {
     let today = ctx.time.date;
-    let photos = select_same_day(today, ctx);
+    let photos = select(
+        ctx,
+        &select_both(select_same_day(today), filter.selection()),
+    );
     SlideShowScene::new(
         photos,
         format!("Photos from {}", fmt_long(&today, ctx.locale.as_ref())),
@@ -328,6 +794,102 @@ pub fn create_daily_slideshow(ctx: &Context) -> Result<SlideShowScene> {
 }
 
 // ----------------------------------------------------------------------------
-pub fn create_slideshow_all(ctx: &Context) -> Result<SlideShowScene> {
-    SlideShowScene::new(select_all(ctx), String::from("All Photos"))
+pub fn create_slideshow_all(ctx: &Context, filter: &PhotoFilter) -> Result<SlideShowScene> {
+    SlideShowScene::new(select(ctx, &filter.selection()), String::from("All Photos"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::photo::{PanDirection, PhotoMeta};
+    use std::path::PathBuf;
+
+    fn photo(meta: &str) -> Photo {
+        Photo {
+            path: PathBuf::from("photo.webp"),
+            meta: serde_json::from_str::<PhotoMeta>(meta).unwrap(),
+            last_shown: None,
+        }
+    }
+
+    #[test]
+    fn test_place_photo() {
+        let none = DisplayHints::default();
+        let half = Rect::new(V2::new([0.25, 0.0]), V2::new([0.5, 1.0]));
+        assert_eq!(place_photo(1.0, 2.0, &none, 0.0), half);
+
+        // the right half of a 2:1 photo fills a square screen
+        let crop = DisplayHints {
+            crop: Some([0.5, 0.0, 0.5, 1.0]),
+            ..DisplayHints::default()
+        };
+        let dst = place_photo(2.0, 1.0, &crop, 0.0);
+        assert_eq!(dst, Rect::new(V2::new([-1.0, 0.0]), V2::new([2.0, 1.0])));
+
+        // panning right moves the photo to the left
+        let pan = DisplayHints {
+            pan: Some(PanDirection::Right),
+            ..DisplayHints::default()
+        };
+        let start = place_photo(1.0, 1.0, &pan, 0.0);
+        let end = place_photo(1.0, 1.0, &pan, 1.0);
+        assert_eq!(start.size, V2::new([PAN_ZOOM, PAN_ZOOM]));
+        assert!((start.pos.x0() - 0.0).abs() < 1e-6);
+        assert!((end.max().x0() - 1.0).abs() < 1e-6);
+        assert_eq!(start.pos.x1(), end.pos.x1());
+    }
+
+    #[test]
+    fn test_ken_burns_view() {
+        let mut rng = XorShift::new(7);
+        for _ in 0..100 {
+            let view = ken_burns_view(&mut rng);
+            for t in [0.0, 0.5, 1.0] {
+                let [x, y, cx, cy] = view.blend(t).as_array();
+                assert_eq!(cx, cy);
+                assert!((KEN_BURNS_MIN_VIEW..=1.0).contains(&cx));
+                assert!(x >= 0.0 && x + cx <= 1.0 + 1e-6);
+                assert!(y >= 0.0 && y + cy <= 1.0 + 1e-6);
+            }
+            let full = |t: f32| view.blend(t).as_array() == [0.0, 0.0, 1.0, 1.0];
+            assert!(full(0.0) || full(1.0));
+        }
+    }
+
+    #[test]
+    fn test_photo_filter() {
+        let photos = [
+            photo(r#"{"rating": 4, "tag": ["family", "beach"]}"#),
+            photo(r#"{"rating": 2, "tag": "family"}"#),
+            photo(r#"{"rating": 5, "tag": ["family", "private"]}"#),
+            photo(r#"{"tag": ["garden"]}"#),
+        ];
+        let selected = |selection: Selection| -> Vec<usize> {
+            let photos = photos.iter().enumerate();
+            photos
+                .filter(|(_, p)| selection(p))
+                .map(|(i, _)| i)
+                .collect()
+        };
+
+        assert_eq!(selected(PhotoFilter::default().selection()), [0, 1, 2, 3]);
+        assert_eq!(selected(select_by_tag("family")), [0, 1, 2]);
+        assert_eq!(selected(select_min_rating(3)), [0, 2]);
+        assert_eq!(selected(select_not(select_min_rating(1))), [3]);
+
+        // rating >= 3 and tagged family, but not private
+        let filter = PhotoFilter {
+            min_rating: Some(3),
+            tags: vec![String::from("family")],
+            exclude_tags: vec![String::from("private")],
+            ..PhotoFilter::default()
+        };
+        assert_eq!(selected(filter.selection()), [0]);
+
+        let filter = PhotoFilter {
+            any_tags: vec![String::from("beach"), String::from("garden")],
+            ..PhotoFilter::default()
+        };
+        assert_eq!(selected(filter.selection()), [0, 3]);
+    }
 }
@@ -0,0 +1,110 @@
+use std::fmt;
+
+// ----------------------------------------------------------------------------
+// Index of a slot and the generation it was filled in. A slot's generation
+// changes whenever it is emptied, so ids kept after that don't find the
+// value that reuses the slot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SlotId {
+    pub index: usize,
+    pub generation: u32,
+}
+
+impl fmt::Display for SlotId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    value: Option<T>,
+    generation: u32,
+}
+
+// ----------------------------------------------------------------------------
+// Values addressed by `SlotId`, freed slots are reused
+#[derive(Debug)]
+pub struct Slots<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+}
+
+impl<T> Default for Slots<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<T> Slots<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, value: T) -> SlotId {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index];
+            assert!(slot.value.is_none());
+            slot.value = Some(value);
+            SlotId {
+                index,
+                generation: slot.generation,
+            }
+        } else {
+            self.slots.push(Slot {
+                value: Some(value),
+                generation: 0,
+            });
+            SlotId {
+                index: self.slots.len() - 1,
+                generation: 0,
+            }
+        }
+    }
+
+    // None for ids of emptied slots, also when the slot was filled again
+    pub fn get(&self, id: SlotId) -> Option<&T> {
+        let slot = self.slots.get(id.index)?;
+        match slot.generation == id.generation {
+            true => slot.value.as_ref(),
+            false => None,
+        }
+    }
+
+    pub fn remove(&mut self, id: SlotId) -> Option<T> {
+        let slot = self.slots.get_mut(id.index)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots() {
+        let mut slots = Slots::new();
+        let a = slots.insert("a");
+        let b = slots.insert("b");
+        assert_eq!(slots.get(a), Some(&"a"));
+        assert_eq!(slots.remove(a), Some("a"));
+        assert_eq!(slots.remove(a), None);
+
+        // the slot of `a` is reused, `a` stays invalid
+        let c = slots.insert("c");
+        assert_eq!(c.index, a.index);
+        assert_eq!(slots.get(a), None);
+        assert_eq!(slots.remove(a), None);
+        assert_eq!(slots.get(c), Some(&"c"));
+        assert_eq!(slots.get(b), Some(&"b"));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::scene::slots::SlotId;
+use std::collections::HashMap;
+
+// Meshes of text nobody shows are kept this long for the next time it is
+// shown, e.g. a caption that comes back with the slideshow
+const MAX_UNUSED: usize = 64;
+
+// ----------------------------------------------------------------------------
+// What a text mesh is built from
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextKey {
+    pub text: String,
+    pub max_width: u32, // bits of the f32
+    pub max_lines: Option<usize>,
+    pub multiline: bool,
+}
+
+impl TextKey {
+    pub fn line(text: &str, max_width: f32) -> Self {
+        Self {
+            text: text.to_string(),
+            max_width: max_width.to_bits(),
+            max_lines: None,
+            multiline: false,
+        }
+    }
+
+    pub fn lines(text: &str, max_width: f32, max_lines: Option<usize>) -> Self {
+        Self {
+            text: text.to_string(),
+            max_width: max_width.to_bits(),
+            max_lines,
+            multiline: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    mesh_id: SlotId,
+    refs: usize,
+    last_used: u64,
+}
+
+// ----------------------------------------------------------------------------
+// Text meshes shared by all handles to the same text. A mesh is released
+// when its last handle is freed and deleted once too many are unused.
+#[derive(Debug, Default)]
+pub struct TextCache {
+    entries: HashMap<TextKey, Entry>,
+    keys: HashMap<SlotId, TextKey>, // by mesh
+    clock: u64,
+}
+
+impl TextCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // ------------------------------------------------------------------------
+    // Mesh of the text, counted as one more handle
+    pub fn acquire(&mut self, key: &TextKey) -> Option<SlotId> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.refs += 1;
+        entry.last_used = self.clock;
+        Some(entry.mesh_id)
+    }
+
+    pub fn insert(&mut self, key: TextKey, mesh_id: SlotId) {
+        self.clock += 1;
+        let entry = Entry {
+            mesh_id,
+            refs: 1,
+            last_used: self.clock,
+        };
+        self.keys.insert(mesh_id, key.clone());
+        self.entries.insert(key, entry);
+    }
+
+    pub fn contains_mesh(&self, mesh_id: SlotId) -> bool {
+        self.keys.contains_key(&mesh_id)
+    }
+
+    // ------------------------------------------------------------------------
+    // Counts a freed handle, returns the meshes to delete
+    pub fn release(&mut self, mesh_id: SlotId) -> Vec<SlotId> {
+        let Some(entry) = self
+            .keys
+            .get(&mesh_id)
+            .and_then(|k| self.entries.get_mut(k))
+        else {
+            return Vec::new();
+        };
+        entry.refs = entry.refs.saturating_sub(1);
+
+        let mut unused: Vec<(u64, SlotId)> = self
+            .entries
+            .values()
+            .filter(|e| e.refs == 0)
+            .map(|e| (e.last_used, e.mesh_id))
+            .collect();
+        if unused.len() <= MAX_UNUSED {
+            return Vec::new();
+        }
+        unused.sort_unstable();
+        let evicted: Vec<SlotId> = unused[..unused.len() - MAX_UNUSED]
+            .iter()
+            .map(|&(_, mesh_id)| mesh_id)
+            .collect();
+        for mesh_id in &evicted {
+            if let Some(key) = self.keys.remove(mesh_id) {
+                self.entries.remove(&key);
+            }
+        }
+        evicted
+    }
+
+    // ------------------------------------------------------------------------
+    // Meshes of all texts, e.g. when the font changes
+    pub fn clear(&mut self) -> Vec<SlotId> {
+        self.entries.clear();
+        self.keys.drain().map(|(mesh_id, _)| mesh_id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh(index: usize) -> SlotId {
+        SlotId {
+            index,
+            generation: 0,
+        }
+    }
+
+    #[test]
+    fn test_text_cache() {
+        let mut cache = TextCache::new();
+        let hello = TextKey::line("Hello", f32::INFINITY);
+        assert_eq!(cache.acquire(&hello), None);
+        cache.insert(hello.clone(), mesh(7));
+        assert_eq!(cache.acquire(&hello), Some(mesh(7)));
+        assert_eq!(cache.acquire(&TextKey::line("Hello", 3.0)), None);
+
+        // unused meshes stay until there are too many
+        assert!(cache.release(mesh(7)).is_empty());
+        assert!(cache.release(mesh(7)).is_empty());
+        assert!(cache.contains_mesh(mesh(7)));
+        assert!(cache.release(mesh(99)).is_empty());
+
+        for i in 0..MAX_UNUSED {
+            let key = TextKey::line(&format!("{i}"), f32::INFINITY);
+            cache.insert(key, mesh(100 + i));
+            let evicted = cache.release(mesh(100 + i));
+            if i + 1 < MAX_UNUSED {
+                assert!(evicted.is_empty());
+            } else {
+                assert_eq!(evicted, [mesh(7)]); // the least recently used
+            }
+        }
+        assert!(!cache.contains_mesh(mesh(7)));
+        assert_eq!(cache.acquire(&hello), None);
+        assert_eq!(cache.clear().len(), MAX_UNUSED);
+    }
+}
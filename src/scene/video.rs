@@ -0,0 +1,335 @@
+use crate::error::{Error, Result};
+use crate::gfx::color_conversion::{ImageGeometry, ycbcr420_to_ycbcr24};
+use crate::gfx::color_format::ColorFormat;
+use crate::gfx::resize;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::time::Duration;
+
+// Clips are decoded by ffmpeg, which uses the hardware decoder of the GPU if
+// there is one (VA-API, VDPAU, ...) and falls back to software otherwise
+const FFMPEG: &str = "ffmpeg";
+const FFPROBE: &str = "ffprobe";
+
+const VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "m4v", "mov", "mkv", "webm"];
+
+// Clips are scaled to fit, frames are uploaded every 1/fps seconds
+const MAX_VIDEO_SIZE: (usize, usize) = (1920, 1080);
+
+// Decoded frames queued ahead of the playback
+const FRAME_QUEUE: usize = 4;
+
+// Waiting time for the first frame, ffmpeg needs a moment to start
+const FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ----------------------------------------------------------------------------
+pub fn is_video_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| VIDEO_EXTENSIONS.iter().any(|v| ext.eq_ignore_ascii_case(v)))
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VideoInfo {
+    pub cx: usize,
+    pub cy: usize,
+    pub fps: f32,
+    pub duration: f32, // seconds
+}
+
+// ----------------------------------------------------------------------------
+pub fn probe(path: &Path) -> Result<VideoInfo> {
+    let output = Command::new(FFPROBE)
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args([
+            "-show_entries",
+            "stream=width,height,avg_frame_rate:format=duration",
+        ])
+        .args(["-of", "default=noprint_wrappers=1"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| Error::Video {
+            msg: format!("{FFPROBE} failed: {e}"),
+        })?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    parse_probe(&text).ok_or_else(|| Error::Video {
+        msg: format!("No video stream in {}", path.display()),
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Parses the key=value lines of ffprobe, the frame rate is a fraction like
+// "30000/1001"
+fn parse_probe(text: &str) -> Option<VideoInfo> {
+    let (mut cx, mut cy, mut fps, mut duration) = (None, None, None, None);
+    for line in text.lines() {
+        let Some(entry) = line.trim().split_once('=') else {
+            continue;
+        };
+        match entry {
+            ("width", value) => cx = value.parse().ok(),
+            ("height", value) => cy = value.parse().ok(),
+            ("avg_frame_rate", value) => {
+                let (num, den) = value.split_once('/').unwrap_or((value, "1"));
+                let (num, den): (f32, f32) = (num.parse().ok()?, den.parse().ok()?);
+                fps = (num > 0.0 && den > 0.0).then_some(num / den);
+            }
+            ("duration", value) => duration = value.parse().ok(),
+            _ => {}
+        }
+    }
+    Some(VideoInfo {
+        cx: cx.filter(|&cx| cx > 0)?,
+        cy: cy.filter(|&cy| cy > 0)?,
+        fps: fps.unwrap_or(25.0),
+        duration: duration.filter(|&d: &f32| d > 0.0)?,
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Size of the decoded frames, a multiple of 16 like the WebP photos so that
+// texture rows need no padding
+pub fn frame_size(cx: usize, cy: usize) -> (usize, usize) {
+    let (max_cx, max_cy) = MAX_VIDEO_SIZE;
+    let (cx, cy) = resize::fit_size(cx, cy, max_cx, max_cy);
+    ((cx / 16).max(1) * 16, (cy / 16).max(1) * 16)
+}
+
+// ----------------------------------------------------------------------------
+// A playing clip, frames are decoded on a background thread and taken by
+// `update` as the playback time advances
+#[derive(Debug)]
+pub struct VideoClip {
+    child: Child,
+    frames: Receiver<Vec<u8>>,
+    pub cx: usize,
+    pub cy: usize,
+    fps: f32,
+    duration: f32,
+    t: f32,
+    shown: usize, // frames taken from the queue
+}
+
+impl VideoClip {
+    // ------------------------------------------------------------------------
+    pub fn open(path: &Path) -> Result<Self> {
+        let info = probe(path)?;
+        let (cx, cy) = frame_size(info.cx, info.cy);
+        let mut child = Command::new(FFMPEG)
+            .args(["-nostdin", "-hide_banner", "-loglevel", "error"])
+            .args(["-hwaccel", "auto"])
+            .arg("-i")
+            .arg(path)
+            .args(["-an", "-sn", "-vf", &format!("scale={cx}:{cy}")])
+            .args(["-pix_fmt", "yuv420p", "-f", "rawvideo", "pipe:1"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::Video {
+                msg: format!("{FFMPEG} failed: {e}"),
+            })?;
+        let stdout = child.stdout.take().ok_or(Error::Video {
+            msg: String::from("No output of ffmpeg"),
+        })?;
+
+        let (sender, frames) = mpsc::sync_channel(FRAME_QUEUE);
+        let spawned = std::thread::Builder::new()
+            .name(String::from("video"))
+            .spawn(move || decode_frames(stdout, cx, cy, sender));
+        if let Err(e) = spawned {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e.into());
+        }
+        log::info!(
+            "Playing {} ({}x{} as {cx}x{cy}, {:.1} fps, {:.1} s)",
+            path.display(),
+            info.cx,
+            info.cy,
+            info.fps,
+            info.duration
+        );
+        Ok(Self {
+            child,
+            frames,
+            cx,
+            cy,
+            fps: info.fps,
+            duration: info.duration,
+            t: 0.0,
+            shown: 0,
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    // Blocks until the first frame is decoded, shown before the playback, see
+    // `PendingClip` to wait in the background
+    pub fn first_frame(&mut self) -> Result<Vec<u8>> {
+        let frame = self.frames.recv_timeout(FIRST_FRAME_TIMEOUT);
+        self.shown = 1;
+        frame.map_err(|_| Error::Video {
+            msg: String::from("No frame decoded"),
+        })
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn remaining(&self) -> f32 {
+        (self.duration - self.t).max(0.0)
+    }
+
+    // ------------------------------------------------------------------------
+    // Returns the frame due after `dt` seconds if there is a new one, late
+    // frames are skipped
+    pub fn update(&mut self, dt: f32) -> Option<Vec<u8>> {
+        self.t += dt;
+        let due = (self.t * self.fps) as usize + 1;
+        let mut frame = None;
+        while self.shown < due {
+            match self.frames.try_recv() {
+                Ok(next) => frame = Some(next),
+                Err(_) => break, // the decoder is behind or done
+            }
+            self.shown += 1;
+        }
+        frame
+    }
+}
+
+impl Drop for VideoClip {
+    fn drop(&mut self) {
+        // the decoder thread ends with the closed pipe
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// ----------------------------------------------------------------------------
+// A clip started on a background thread, ready once its first frame is
+// decoded. Dropping it stops the clip.
+#[derive(Debug)]
+pub struct PendingClip {
+    started: Receiver<Result<(VideoClip, Vec<u8>)>>,
+    result: Option<Result<(VideoClip, Vec<u8>)>>,
+}
+
+impl PendingClip {
+    // ------------------------------------------------------------------------
+    pub fn start(path: &Path) -> Self {
+        let (sender, started) = mpsc::sync_channel(1);
+        let path = path.to_path_buf();
+        let spawned = std::thread::Builder::new()
+            .name(String::from("video-start"))
+            .spawn(move || {
+                let clip = VideoClip::open(&path).and_then(|mut clip| {
+                    let frame = clip.first_frame()?;
+                    Ok((clip, frame))
+                });
+                let _ = sender.send(clip);
+            });
+        if let Err(e) = spawned {
+            log::warn!("Can't start a clip: {e:?}");
+        }
+        Self {
+            started,
+            result: None,
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // True once the clip started or failed to, see `take`
+    pub fn is_ready(&mut self) -> bool {
+        if self.result.is_none() {
+            self.result = match self.started.try_recv() {
+                Ok(result) => Some(result),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Err(Error::Video {
+                    msg: String::from("Clip not started"),
+                })),
+            };
+        }
+        self.result.is_some()
+    }
+
+    // ------------------------------------------------------------------------
+    // The clip and its first frame, None while it's starting
+    pub fn take(&mut self) -> Option<Result<(VideoClip, Vec<u8>)>> {
+        self.is_ready();
+        self.result.take()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Reads YCbCr 4:2:0 frames from ffmpeg and converts them to interleaved
+// YCbCr for the texture upload
+fn decode_frames(mut stdout: impl Read, cx: usize, cy: usize, frames: SyncSender<Vec<u8>>) {
+    let luma = cx * cy;
+    let chroma = luma / 4;
+    let geo = ImageGeometry {
+        cx,
+        cy,
+        cf: ColorFormat::YCbCr420,
+    };
+    let mut buf = vec![0; luma + 2 * chroma];
+    while stdout.read_exact(&mut buf).is_ok() {
+        let (y, cbcr) = buf.split_at(luma);
+        let (cb, cr) = cbcr.split_at(chroma);
+        if frames.send(ycbcr420_to_ycbcr24(y, cb, cr, &geo)).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe() {
+        let text = "width=1920\nheight=1080\navg_frame_rate=30000/1001\nduration=12.480000\n";
+        let info = parse_probe(text).unwrap();
+        assert_eq!((info.cx, info.cy), (1920, 1080));
+        assert!((info.fps - 29.97).abs() < 0.01);
+        assert_eq!(info.duration, 12.48);
+
+        let text = "width=640\nheight=480\navg_frame_rate=0/0\nduration=3.0\n";
+        assert_eq!(parse_probe(text).unwrap().fps, 25.0);
+        assert_eq!(parse_probe("width=640\nheight=480\nduration=N/A\n"), None);
+
+        assert_eq!(frame_size(3840, 2160), (1920, 1072));
+        assert_eq!(frame_size(1080, 1920), (608, 1072));
+        assert_eq!(frame_size(640, 480), (640, 480));
+        assert!(is_video_file(Path::new("clips/beach.MP4")));
+        assert!(!is_video_file(Path::new("photos/beach.webp")));
+    }
+
+    #[test]
+    fn test_decode_frames() {
+        let (cx, cy) = (16, 16);
+        let mut data = vec![16u8; cx * cy];
+        data.extend(vec![128u8; cx * cy / 2]);
+        let two_frames = [data.clone(), data].concat();
+
+        let (sender, receiver) = mpsc::sync_channel(FRAME_QUEUE);
+        decode_frames(two_frames.as_slice(), cx, cy, sender);
+        let frames: Vec<_> = receiver.iter().collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].len(), cx * cy * 3);
+        assert_eq!(&frames[0][..3], &[16, 128, 128]);
+    }
+
+    #[test]
+    fn test_pending_clip() {
+        let mut pending = PendingClip::start(Path::new("missing/clip.mp4"));
+        while !pending.is_ready() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(pending.take(), Some(Err(_))));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::error::{Error, Result, ResultExt};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Sensors are read this often, brightness follows with SMOOTHING seconds
+const READ_INTERVAL: Duration = Duration::from_secs(1);
+const SMOOTHING: f32 = 3.0;
+
+// ----------------------------------------------------------------------------
+// `sensor` is the illuminance file of a Linux IIO light sensor, e.g. an I²C
+// BH1750 or TSL2561: /sys/bus/iio/devices/iio:device0/in_illuminance_input.
+// Brightness goes from `min_brightness` at `dark_lux` to 1.0 at `bright_lux`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AmbientLightConfig {
+    pub sensor: Option<PathBuf>,
+    pub min_brightness: f32,
+    pub dark_lux: f32,
+    pub bright_lux: f32,
+}
+
+impl Default for AmbientLightConfig {
+    fn default() -> Self {
+        Self {
+            sensor: None,
+            min_brightness: 0.25,
+            dark_lux: 5.0,
+            bright_lux: 300.0,
+        }
+    }
+}
+
+impl AmbientLightConfig {
+    // ------------------------------------------------------------------------
+    // Interpolates on a log scale, the eye perceives light logarithmically
+    pub fn brightness(&self, lux: f32) -> f32 {
+        let dark = self.dark_lux.max(0.1).ln();
+        let bright = self.bright_lux.max(0.1).ln();
+        let t = if bright > dark {
+            ((lux.max(0.1).ln() - dark) / (bright - dark)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let min = self.min_brightness.clamp(0.0, 1.0);
+        min + (1.0 - min) * t
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Illuminance in lux read from sysfs, `*_raw` files are multiplied with the
+// `*_scale` file next to them
+pub struct LightSensor {
+    path: PathBuf,
+    scale: f32,
+}
+
+impl LightSensor {
+    pub fn open(path: &Path) -> Result<Self> {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let scale = match name.strip_suffix("_raw") {
+            Some(base) => read_value(&path.with_file_name(format!("{base}_scale"))).unwrap_or(1.0),
+            None => 1.0,
+        };
+        let sensor = Self {
+            path: path.to_path_buf(),
+            scale,
+        };
+        sensor.read_lux()?;
+        Ok(sensor)
+    }
+
+    pub fn read_lux(&self) -> Result<f32> {
+        Ok(read_value(&self.path)? * self.scale)
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn read_value(path: &Path) -> Result<f32> {
+    let text = std::fs::read_to_string(path).with_path("reading", path)?;
+    text.trim().parse().map_err(|_| Error::FileRead {
+        path: path.to_path_buf(),
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Brightness of the final render pass that smoothly follows the sensor
+pub struct AutoBrightness {
+    config: AmbientLightConfig,
+    sensor: LightSensor,
+    brightness: f32,
+    target: f32,
+    next_read: Duration, // time left until the sensor is read again
+    failed: bool,        // the last read failed, logged once
+}
+
+impl AutoBrightness {
+    // ------------------------------------------------------------------------
+    // None without a sensor in the config or if it can't be read
+    pub fn new(config: &AmbientLightConfig) -> Option<Self> {
+        let path = config.sensor.as_ref()?;
+        let sensor = LightSensor::open(path)
+            .inspect_err(|e| log::warn!("Light sensor {path:?} not available: {e:?}"))
+            .ok()?;
+        let target = sensor.read_lux().map_or(1.0, |lux| config.brightness(lux));
+        Some(Self {
+            config: config.clone(),
+            sensor,
+            brightness: target,
+            target,
+            next_read: READ_INTERVAL,
+            failed: false,
+        })
+    }
+
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    // ------------------------------------------------------------------------
+    // Returns the new brightness if it changed noticeably
+    pub fn update(&mut self, dt: Duration) -> Option<f32> {
+        self.next_read = self.next_read.saturating_sub(dt);
+        if self.next_read.is_zero() {
+            self.next_read = READ_INTERVAL;
+            match self.sensor.read_lux() {
+                Ok(lux) => {
+                    self.target = self.config.brightness(lux);
+                    self.failed = false;
+                }
+                Err(e) if !self.failed => {
+                    log::warn!("Failed to read the light sensor: {e:?}");
+                    self.failed = true;
+                }
+                Err(_) => {}
+            }
+        }
+
+        let step = (self.target - self.brightness) * (1.0 - (-dt.as_secs_f32() / SMOOTHING).exp());
+        if step.abs() < 1e-4 {
+            return None;
+        }
+        self.brightness += step;
+        Some(self.brightness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_brightness() {
+        let config = AmbientLightConfig::default();
+        assert_eq!(config.brightness(0.0), 0.25);
+        assert_eq!(config.brightness(5.0), 0.25);
+        assert_eq!(config.brightness(1000.0), 1.0);
+        let mid = config.brightness((5.0f32 * 300.0).sqrt());
+        assert!((mid - 0.625).abs() < 1e-3);
+
+        let dir = std::env::temp_dir().join(format!("home-light-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let raw = dir.join("in_illuminance_raw");
+        std::fs::write(&raw, "1000\n").unwrap();
+        std::fs::write(dir.join("in_illuminance_scale"), "0.3\n").unwrap();
+
+        let config = AmbientLightConfig {
+            sensor: Some(raw.clone()),
+            ..AmbientLightConfig::default()
+        };
+        let mut auto = AutoBrightness::new(&config).unwrap();
+        assert_eq!(auto.brightness(), 1.0);
+        assert_eq!(auto.update(Duration::from_millis(100)), None);
+
+        // the room gets dark, brightness follows gradually
+        std::fs::write(&raw, "0\n").unwrap();
+        auto.update(Duration::from_secs(1)).unwrap();
+        let b = auto.brightness();
+        assert!(b < 1.0 && b > 0.25);
+        for _ in 0..100 {
+            auto.update(Duration::from_millis(500));
+        }
+        assert!((auto.brightness() - 0.25).abs() < 1e-2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
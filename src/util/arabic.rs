@@ -0,0 +1,166 @@
+// ----------------------------------------------------------------------------
+// Arabic letters change their shape depending on whether they join the
+// letters next to them. Fonts carry the shapes as presentation forms, the
+// isolated form is followed by the final, initial and medial one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Joining {
+    Dual,        // joins on both sides
+    Right,       // joins the previous letter only, e.g. alef
+    Causing,     // tatweel
+    Transparent, // marks between letters
+    None,
+}
+
+const LAM: char = '\u{644}';
+const TATWEEL: char = '\u{640}';
+
+// Isolated forms of U+0621 to U+064A, 0 where the letter has none
+#[rustfmt::skip]
+const FORMS: [(u16, Joining); 42] = {
+    use Joining::{Dual as D, None as U, Right as R};
+    [
+        (0xfe80, U), (0xfe81, R), (0xfe83, R), (0xfe85, R), (0xfe87, R), (0xfe89, D),
+        (0xfe8d, R), (0xfe8f, D), (0xfe93, R), (0xfe95, D), (0xfe99, D), (0xfe9d, D),
+        (0xfea1, D), (0xfea5, D), (0xfea9, R), (0xfeab, R), (0xfead, R), (0xfeaf, R),
+        (0xfeb1, D), (0xfeb5, D), (0xfeb9, D), (0xfebd, D), (0xfec1, D), (0xfec5, D),
+        (0xfec9, D), (0xfecd, D), (0, U), (0, U), (0, U), (0, U),
+        (0, U), (0, Joining::Causing), (0xfed1, D), (0xfed5, D), (0xfed9, D), (0xfedd, D),
+        (0xfee1, D), (0xfee5, D), (0xfee9, D), (0xfeed, R), (0xfeef, R), (0xfef1, D),
+    ]
+};
+
+// Persian letters, from the presentation forms A
+const EXTRA_FORMS: [(char, u16, Joining); 6] = [
+    ('\u{67e}', 0xfb56, Joining::Dual),  // peh
+    ('\u{686}', 0xfb7a, Joining::Dual),  // tcheh
+    ('\u{698}', 0xfb8a, Joining::Right), // jeh
+    ('\u{6a9}', 0xfb8e, Joining::Dual),  // keheh
+    ('\u{6af}', 0xfb92, Joining::Dual),  // gaf
+    ('\u{6cc}', 0xfbfc, Joining::Dual),  // farsi yeh
+];
+
+// Lam followed by one of the alefs is a ligature, isolated and final form
+const LAM_ALEF: [(char, u16); 4] = [
+    ('\u{622}', 0xfef5),
+    ('\u{623}', 0xfef7),
+    ('\u{625}', 0xfef9),
+    ('\u{627}', 0xfefb),
+];
+
+// ----------------------------------------------------------------------------
+// Replaces Arabic letters by the presentation forms that fit their
+// neighbours, in logical order before the bidi reordering
+pub fn shape(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if !chars.iter().any(|&c| form(c).is_some()) {
+        return text.to_string();
+    }
+
+    let mut shaped = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let Some((isolated, joining)) = form(c) else {
+            shaped.push(c);
+            i += 1;
+            continue;
+        };
+        let joins_previous = joining != Joining::None
+            && matches!(neighbour(&chars, i, -1), Joining::Dual | Joining::Causing);
+
+        let alef = chars.get(i + 1).and_then(|&next| {
+            let lam_alef = LAM_ALEF.iter().find(|&&(alef, _)| alef == next);
+            lam_alef.filter(|_| c == LAM).map(|&(_, ligature)| ligature)
+        });
+        if let Some(ligature) = alef {
+            shaped.extend(char::from_u32(u32::from(
+                ligature + u16::from(joins_previous),
+            )));
+            i += 2;
+            continue;
+        }
+
+        let joins_next = matches!(joining, Joining::Dual | Joining::Causing)
+            && matches!(
+                neighbour(&chars, i, 1),
+                Joining::Dual | Joining::Right | Joining::Causing
+            );
+        let offset = match (joins_previous, joins_next && joining == Joining::Dual) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        match isolated {
+            0 => shaped.push(c),
+            _ => shaped.extend(char::from_u32(u32::from(isolated + offset))),
+        }
+        i += 1;
+    }
+    shaped
+}
+
+// ----------------------------------------------------------------------------
+// Isolated form and joining type of a letter
+fn form(c: char) -> Option<(u16, Joining)> {
+    match c {
+        '\u{621}'..='\u{64a}' => Some(FORMS[c as usize - 0x621]),
+        _ => EXTRA_FORMS
+            .iter()
+            .find(|&&(letter, _, _)| letter == c)
+            .map(|&(_, isolated, joining)| (isolated, joining)),
+    }
+}
+
+fn joining(c: char) -> Joining {
+    if c == TATWEEL || c == '\u{200d}' {
+        return Joining::Causing;
+    }
+    if is_mark(c) {
+        return Joining::Transparent;
+    }
+    form(c).map_or(Joining::None, |(_, joining)| joining)
+}
+
+// Joining type of the next letter in `step` direction, skipping marks
+fn neighbour(chars: &[char], i: usize, step: isize) -> Joining {
+    let mut i = i as isize + step;
+    while let Some(&c) = usize::try_from(i).ok().and_then(|i| chars.get(i)) {
+        match joining(c) {
+            Joining::Transparent => i += step,
+            joining => return joining,
+        }
+    }
+    Joining::None
+}
+
+pub fn is_mark(c: char) -> bool {
+    matches!(c,
+        '\u{610}'..='\u{61a}'
+        | '\u{64b}'..='\u{65f}'
+        | '\u{670}'
+        | '\u{6d6}'..='\u{6dc}'
+        | '\u{6df}'..='\u{6e4}'
+        | '\u{6e7}'..='\u{6e8}'
+        | '\u{6ea}'..='\u{6ed}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape() {
+        // beh yeh teh: initial, medial, final
+        assert_eq!(shape("\u{628}\u{64a}\u{62a}"), "\u{fe91}\u{fef4}\u{fe96}");
+        // seen lam alef meem: lam-alef ligature, alef doesn't join meem
+        assert_eq!(
+            shape("\u{633}\u{644}\u{627}\u{645}"),
+            "\u{feb3}\u{fefc}\u{fee1}"
+        );
+        // marks don't break the joining
+        assert_eq!(shape("\u{628}\u{64e}\u{628}"), "\u{fe91}\u{64e}\u{fe90}");
+        assert_eq!(shape("\u{628} \u{628}"), "\u{fe8f} \u{fe8f}");
+        assert_eq!(shape("Hello"), "Hello");
+    }
+}
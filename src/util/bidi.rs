@@ -0,0 +1,277 @@
+use crate::util::arabic;
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+// ----------------------------------------------------------------------------
+// Bidi classes of the Unicode bidirectional algorithm (UAX #9), without the
+// explicit embeddings and isolates
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    L,   // left-to-right letters
+    R,   // Hebrew
+    AL,  // Arabic
+    EN,  // European digits
+    AN,  // Arabic-Indic digits
+    ES,  // plus and minus
+    ET,  // currency and percent signs
+    CS,  // number separators
+    NSM, // combining marks
+    N,   // spaces and other neutrals
+}
+
+fn class(c: char) -> Class {
+    match c {
+        '0'..='9' | '\u{6f0}'..='\u{6f9}' => Class::EN,
+        '\u{660}'..='\u{669}' | '\u{66b}'..='\u{66c}' => Class::AN,
+        '+' | '-' => Class::ES,
+        '#' | '$' | '%' | '\u{a2}'..='\u{a5}' | '\u{b0}' | '\u{20a0}'..='\u{20cf}' => Class::ET,
+        ',' | '.' | '/' | ':' | '\u{a0}' | '\u{60c}' => Class::CS,
+        '\u{300}'..='\u{36f}'
+        | '\u{591}'..='\u{5bd}'
+        | '\u{5bf}'
+        | '\u{5c1}'..='\u{5c2}'
+        | '\u{5c4}'..='\u{5c5}'
+        | '\u{5c7}' => Class::NSM,
+        c if arabic::is_mark(c) => Class::NSM,
+        '\u{590}'..='\u{5ff}' | '\u{7c0}'..='\u{85f}' | '\u{fb1d}'..='\u{fb4f}' => Class::R,
+        '\u{600}'..='\u{7bf}'
+        | '\u{860}'..='\u{8ff}'
+        | '\u{fb50}'..='\u{fdff}'
+        | '\u{fe70}'..='\u{fefe}' => Class::AL,
+        c if c.is_alphanumeric() => Class::L,
+        _ => Class::N,
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Direction of the first strong character, left-to-right without any
+pub fn direction(text: &str) -> Direction {
+    for c in text.chars() {
+        match class(c) {
+            Class::L => return Direction::Ltr,
+            Class::R | Class::AL => return Direction::Rtl,
+            _ => {}
+        }
+    }
+    Direction::Ltr
+}
+
+// ----------------------------------------------------------------------------
+// Characters of one line in the order they are drawn from left to right,
+// with mirrored brackets in right-to-left runs. Combining marks stay behind
+// their base character.
+pub fn reorder(text: &str, direction: Direction) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let classes: Vec<Class> = chars.iter().map(|&c| class(c)).collect();
+    if !classes
+        .iter()
+        .any(|&c| matches!(c, Class::R | Class::AL | Class::AN))
+        && direction == Direction::Ltr
+    {
+        return text.to_string();
+    }
+
+    let base = match direction {
+        Direction::Ltr => 0,
+        Direction::Rtl => 1,
+    };
+    let levels = resolve_levels(&classes, base);
+
+    // clusters of a base character and its marks are reordered as one
+    let mut clusters: Vec<(usize, usize)> = Vec::new(); // start, end
+    for (i, &class) in classes.iter().enumerate() {
+        match clusters.last_mut() {
+            Some((_, end)) if class == Class::NSM => *end = i + 1,
+            _ => clusters.push((i, i + 1)),
+        }
+    }
+    let mut cluster_levels: Vec<u8> = clusters.iter().map(|&(start, _)| levels[start]).collect();
+
+    // L2: reverse every run at or above each level, from the highest down to
+    // the lowest odd one
+    let highest = cluster_levels.iter().copied().max().unwrap_or(0);
+    let lowest_odd = cluster_levels.iter().copied().min().unwrap_or(0) | 1;
+    for level in (lowest_odd..=highest).rev() {
+        let mut i = 0;
+        while i < clusters.len() {
+            if cluster_levels[i] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < clusters.len() && cluster_levels[i] >= level {
+                i += 1;
+            }
+            clusters[start..i].reverse();
+            cluster_levels[start..i].reverse();
+        }
+    }
+
+    let mut visual = String::with_capacity(text.len());
+    for (&(start, end), &level) in clusters.iter().zip(&cluster_levels) {
+        for &c in &chars[start..end] {
+            visual.push(if level % 2 == 1 { mirror(c) } else { c });
+        }
+    }
+    visual
+}
+
+// ----------------------------------------------------------------------------
+// Embedding levels per character, even is left-to-right
+fn resolve_levels(classes: &[Class], base: u8) -> Vec<u8> {
+    let e = if base.is_multiple_of(2) {
+        Class::L
+    } else {
+        Class::R
+    };
+    let mut types = classes.to_vec();
+
+    // W1: marks take the type of the character before them
+    let mut previous = e;
+    for t in types.iter_mut() {
+        if *t == Class::NSM {
+            *t = previous;
+        }
+        previous = *t;
+    }
+
+    // W2: European digits after Arabic letters are Arabic digits, W3: AL is R
+    let mut strong = e;
+    for t in types.iter_mut() {
+        match *t {
+            Class::L | Class::R | Class::AL => strong = *t,
+            Class::EN if strong == Class::AL => *t = Class::AN,
+            _ => {}
+        }
+        if *t == Class::AL {
+            *t = Class::R;
+        }
+    }
+
+    // W4: a single separator between two numbers of the same kind joins them
+    for i in 1..types.len().saturating_sub(1) {
+        let (before, after) = (types[i - 1], types[i + 1]);
+        let joins = match types[i] {
+            Class::ES => before == Class::EN && after == Class::EN,
+            Class::CS => before == after && matches!(before, Class::EN | Class::AN),
+            _ => false,
+        };
+        if joins {
+            types[i] = before;
+        }
+    }
+
+    // W5: currency and percent signs next to European digits belong to them
+    for i in 0..types.len() {
+        if types[i] != Class::ET {
+            continue;
+        }
+        let end = (i..types.len())
+            .find(|&j| types[j] != Class::ET)
+            .unwrap_or(types.len());
+        let touches_en = (i > 0 && types[i - 1] == Class::EN) || types.get(end) == Some(&Class::EN);
+        if touches_en {
+            types[i..end].fill(Class::EN);
+        }
+    }
+
+    // W6: remaining separators and terminators are neutral, W7: European
+    // digits in left-to-right context are L
+    let mut strong = e;
+    for t in types.iter_mut() {
+        match *t {
+            Class::ES | Class::ET | Class::CS => *t = Class::N,
+            Class::L | Class::R => strong = *t,
+            Class::EN if strong == Class::L => *t = Class::L,
+            _ => {}
+        }
+    }
+
+    // N1/N2: neutrals between characters of the same direction take it,
+    // digits count as right-to-left there, others get the base direction
+    let direction = |t: Class| match t {
+        Class::L => Some(Class::L),
+        Class::R | Class::EN | Class::AN => Some(Class::R),
+        _ => None,
+    };
+    let mut i = 0;
+    while i < types.len() {
+        if types[i] != Class::N {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < types.len() && types[i] == Class::N {
+            i += 1;
+        }
+        let before = start
+            .checked_sub(1)
+            .map_or(Some(e), |j| direction(types[j]));
+        let after = types.get(i).map_or(Some(e), |&t| direction(t));
+        let resolved = if before == after { before } else { None };
+        types[start..i].fill(resolved.unwrap_or(e));
+    }
+
+    // I1/I2
+    types
+        .iter()
+        .map(|&t| match (base % 2, t) {
+            (0, Class::R) => base + 1,
+            (0, Class::AN | Class::EN) => base + 2,
+            (1, Class::L | Class::EN | Class::AN) => base + 1,
+            _ => base,
+        })
+        .collect()
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direction() {
+        assert_eq!(direction("Hello"), Direction::Ltr);
+        assert_eq!(direction("123 שלום"), Direction::Rtl);
+        assert_eq!(
+            direction("\u{633}\u{644}\u{627}\u{645} world"),
+            Direction::Rtl
+        );
+        assert_eq!(direction("2024"), Direction::Ltr);
+    }
+
+    #[test]
+    fn test_reorder() {
+        assert_eq!(reorder("Hello (world)", Direction::Ltr), "Hello (world)");
+        assert_eq!(reorder("abc אבג", Direction::Ltr), "abc גבא");
+        assert_eq!(reorder("אבג abc", Direction::Rtl), "abc גבא");
+        assert_eq!(reorder("(שלום)", Direction::Rtl), "(םולש)");
+        // numbers keep their order, also with separators and units
+        assert_eq!(reorder("מחיר 1,000 ש", Direction::Rtl), "ש 1,000 ריחמ");
+        assert_eq!(reorder("הנחה 20%", Direction::Rtl), "20% החנה");
+        // marks stay after their base
+        assert_eq!(reorder("אָב", Direction::Rtl), "באָ");
+    }
+}
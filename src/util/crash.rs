@@ -0,0 +1,144 @@
+use crate::error::{Result, ResultExt};
+use crate::scene::FrameEvent;
+use crate::util::datetime::DateTime;
+use crate::util::logger;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Log lines included in a report
+const LOG_LINES: usize = 32;
+
+// ----------------------------------------------------------------------------
+// What the frame was doing, collected while running so that a fatal error or
+// panic can write it to `crash/` for bug reports from remote devices.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct CrashContext {
+    dir: Option<PathBuf>, // reports are only written once set
+    gl_vendor: Option<String>,
+    gl_renderer: Option<String>,
+    gl_version: Option<String>,
+    scene: Option<String>,
+    photo: Option<PathBuf>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    dir: None,
+    gl_vendor: None,
+    gl_renderer: None,
+    gl_version: None,
+    scene: None,
+    photo: None,
+});
+
+// ----------------------------------------------------------------------------
+fn update(f: impl FnOnce(&mut CrashContext)) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        f(&mut context);
+    }
+}
+
+// ----------------------------------------------------------------------------
+pub fn set_dir(dir: &Path) {
+    update(|c| c.dir = Some(dir.to_path_buf()));
+}
+
+// ----------------------------------------------------------------------------
+pub fn set_gl_info(vendor: &str, renderer: &str, version: &str) {
+    update(|c| {
+        c.gl_vendor = Some(vendor.to_string());
+        c.gl_renderer = Some(renderer.to_string());
+        c.gl_version = Some(version.to_string());
+    });
+}
+
+// ----------------------------------------------------------------------------
+// Tracks the current scene and photo
+pub fn record_event(event: &FrameEvent) {
+    match event {
+        FrameEvent::SceneChanged { name } => update(|c| c.scene = Some(name.clone())),
+        FrameEvent::PhotoChanged { path, .. } => update(|c| c.photo = Some(path.clone())),
+        FrameEvent::Error { .. } => {}
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Writes a report to the crash directory, returns its path or None if no
+// directory is set. `try_lock` keeps a panic while holding the context from
+// deadlocking the panic hook.
+pub fn write_report(reason: &str) -> Option<PathBuf> {
+    let context = CONTEXT.try_lock().ok()?.clone();
+    let dir = context.dir.clone()?;
+    let text = format_report(&context, reason, &logger::recent_lines(LOG_LINES));
+    match save(&dir, &text) {
+        Ok(path) => Some(path),
+        Err(e) => {
+            log::warn!("Failed to write crash report to {dir:?}: {e:?}");
+            None
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn save(dir: &Path, text: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir).with_path("creating", dir)?;
+    let timestamp = DateTime::now().as_timestamp();
+    let mut path = dir.join(format!("crash-{timestamp}.txt"));
+    let mut seq = 1;
+    while path.exists() {
+        path = dir.join(format!("crash-{timestamp}_{seq}.txt"));
+        seq += 1;
+    }
+    std::fs::write(&path, text).with_path("writing", &path)?;
+    Ok(path)
+}
+
+// ----------------------------------------------------------------------------
+fn format_report(context: &CrashContext, reason: &str, log_lines: &[String]) -> String {
+    let unknown = |value: &Option<String>| value.clone().unwrap_or_else(|| String::from("-"));
+    let mut text = String::new();
+    let _ = writeln!(text, "home-rs {} crash report", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(text, "Time:        {} UTC", DateTime::now());
+    let _ = writeln!(
+        text,
+        "Platform:    {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(text, "GL vendor:   {}", unknown(&context.gl_vendor));
+    let _ = writeln!(text, "GL renderer: {}", unknown(&context.gl_renderer));
+    let _ = writeln!(text, "GL version:  {}", unknown(&context.gl_version));
+    let _ = writeln!(text, "Scene:       {}", unknown(&context.scene));
+    let photo = context.photo.as_ref().map(|p| p.display().to_string());
+    let _ = writeln!(text, "Photo:       {}", unknown(&photo));
+    let _ = writeln!(text, "\nReason:\n{reason}");
+    let _ = writeln!(text, "\nLast {} log lines:", log_lines.len());
+    for line in log_lines {
+        let _ = writeln!(text, "{line}");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_report() {
+        let context = CrashContext {
+            gl_vendor: Some(String::from("Mesa")),
+            scene: Some(String::from("All Photos")),
+            photo: Some(PathBuf::from("photos/beach.webp")),
+            ..CrashContext::default()
+        };
+        let lines = [String::from("INFO one"), String::from("ERROR two")];
+        let text = format_report(&context, "GpuOutOfMemory", &lines);
+
+        assert!(text.contains("GL vendor:   Mesa\n"));
+        assert!(text.contains("GL renderer: -\n"));
+        assert!(text.contains("Scene:       All Photos\n"));
+        assert!(text.contains("Photo:       photos/beach.webp\n"));
+        assert!(text.contains("Reason:\nGpuOutOfMemory\n"));
+        assert!(text.ends_with("Last 2 log lines:\nINFO one\nERROR two\n"));
+    }
+}
@@ -40,7 +40,7 @@ impl TryFrom<i32> for Weekday {
 }
 
 // ----------------------------------------------------------------------------
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Month {
     Jan = 1,
     Feb = 2,
@@ -67,7 +67,7 @@ impl From<Month> for i32 {
 impl fmt::Display for Month {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let num: i32 = (*self).into();
-        write!(f, "{num}")
+        fmt::Display::fmt(&num, f)
     }
 }
 
@@ -124,6 +124,12 @@ impl Date {
         Date(today as i32)
     }
 
+    // ------------------------------------------------------------------------
+    // Days since 1970-01-01
+    pub const fn days(self) -> i32 {
+        self.0
+    }
+
     // ------------------------------------------------------------------------
     pub const fn to_ymd(self) -> (i32, Month, i32) {
         gregorian_from_days(self.0)
@@ -185,7 +191,7 @@ const fn gregorian_from_days(days: i32) -> (i32, Month, i32) {
 }
 
 // ----------------------------------------------------------------------------
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Time(u32);
 
 impl Time {
@@ -205,6 +211,19 @@ impl Time {
         Ok(Time(hour * 3600 + minute * 60 + second))
     }
 
+    // ------------------------------------------------------------------------
+    // HH:MM with optional :SS
+    pub fn from_hms_str(s: &str) -> Result<Self> {
+        let mut parts = s.split(':');
+        let hour = parts.next().ok_or(Error::InvalidTime)?.parse()?;
+        let minute = parts.next().ok_or(Error::InvalidTime)?.parse()?;
+        let second = parts.next().map_or(Ok(0), str::parse)?;
+        if parts.next().is_some() {
+            return Err(Error::InvalidTime);
+        }
+        Time::from_hms(hour, minute, second)
+    }
+
     // ------------------------------------------------------------------------
     pub fn now() -> Self {
         let now = SystemTime::now()
@@ -249,6 +268,37 @@ impl DateTime {
         }
     }
 
+    // ------------------------------------------------------------------------
+    // Local time including daylight saving time
+    pub fn local_now() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+        Self::from_unix_seconds(now.as_secs() as i64).to_local()
+    }
+
+    // ------------------------------------------------------------------------
+    // This UTC time in the local time zone
+    pub fn to_local(&self) -> Self {
+        let seconds = self.to_unix_seconds();
+        Self::from_unix_seconds(seconds + utc_offset(seconds) as i64)
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn from_unix_seconds(seconds: i64) -> Self {
+        let days = seconds.div_euclid(SECONDS_PER_DAY as i64);
+        let seconds = seconds.rem_euclid(SECONDS_PER_DAY as i64);
+
+        DateTime {
+            date: Date(days as i32),
+            time: Time(seconds as u32),
+        }
+    }
+
+    pub fn to_unix_seconds(&self) -> i64 {
+        i64::from(self.date.0) * SECONDS_PER_DAY as i64 + i64::from(self.time.0)
+    }
+
     // ------------------------------------------------------------------------
     pub fn from_iso8601(s: &str) -> Result<Self> {
         // Strict minimal form: YYYY-MM-DDTHH:MM:SS(Z optional)
@@ -295,6 +345,60 @@ impl fmt::Display for DateTime {
     }
 }
 
+// ----------------------------------------------------------------------------
+// Offset of local time to UTC in seconds (east of UTC is positive) at the given
+// unix time, including daylight saving time.
+#[cfg(unix)]
+pub fn utc_offset(unix_seconds: i64) -> i32 {
+    use std::ffi::{c_char, c_int, c_long};
+
+    // struct tm as defined by glibc / musl, time_t is a C long on Linux
+    #[repr(C)]
+    struct Tm {
+        tm_sec: c_int,
+        tm_min: c_int,
+        tm_hour: c_int,
+        tm_mday: c_int,
+        tm_mon: c_int,
+        tm_year: c_int,
+        tm_wday: c_int,
+        tm_yday: c_int,
+        tm_isdst: c_int,
+        tm_gmtoff: c_long,
+        tm_zone: *const c_char,
+    }
+
+    unsafe extern "C" {
+        fn localtime_r(time: *const c_long, tm: *mut Tm) -> *mut Tm;
+    }
+
+    let time = unix_seconds as c_long;
+    let mut tm: Tm = unsafe { std::mem::zeroed() };
+    let res = unsafe { localtime_r(&time, &mut tm) };
+    if res.is_null() {
+        0
+    } else {
+        tm.tm_gmtoff as i32
+    }
+}
+
+// ----------------------------------------------------------------------------
+#[cfg(windows)]
+pub fn utc_offset(_unix_seconds: i64) -> i32 {
+    use windows::Win32::System::Time::{GetTimeZoneInformation, TIME_ZONE_INFORMATION};
+
+    // UTC = local time + bias (in minutes)
+    const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+    let mut tzi = TIME_ZONE_INFORMATION::default();
+    let id = unsafe { GetTimeZoneInformation(&mut tzi) };
+    let bias = if id == TIME_ZONE_ID_DAYLIGHT {
+        tzi.Bias + tzi.DaylightBias
+    } else {
+        tzi.Bias + tzi.StandardBias
+    };
+    -bias * 60
+}
+
 // ----------------------------------------------------------------------------
 impl<'a> Deserialize<'a> for DateTime {
     fn deserialize<D: serde::Deserializer<'a>>(
@@ -353,6 +457,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_time_from_str() {
+        assert_eq!(Time::from_hms_str("22:30").unwrap().to_hms(), (22, 30, 0));
+        assert_eq!(Time::from_hms_str("06:05:09").unwrap().to_hms(), (6, 5, 9));
+        assert!(Time::from_hms_str("24:00").is_err());
+        assert!(Time::from_hms_str("7").is_err());
+        assert!(Time::from_hms_str("1:2:3:4").is_err());
+    }
+
+    #[test]
+    fn test_from_unix_seconds() {
+        let dt = DateTime::from_unix_seconds(86_400 + 3_661);
+        assert_eq!(dt.as_iso8601(), "1970-01-02T01:01:01Z");
+
+        let dt = DateTime::from_unix_seconds(-1);
+        assert_eq!(dt.as_iso8601(), "1969-12-31T23:59:59Z");
+        assert_eq!(dt.to_unix_seconds(), -1);
+    }
+
     #[test]
     fn test_current_time() {
         let now = DateTime::now();
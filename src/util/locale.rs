@@ -6,6 +6,7 @@ pub trait DateLocale {
     fn time_format(&self) -> TimePattern;
     fn weekday_name(&self, weekday: &Weekday) -> (&'static str, &'static str);
     fn month_name(&self, month: &Month) -> (&'static str, &'static str);
+    fn number_format(&self) -> NumberPattern;
 }
 
 #[derive(Clone, Copy)]
@@ -15,12 +16,44 @@ pub enum DatePattern {
     MdySlash,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TimePattern {
     HmsColon12,
     HmsColon24,
 }
 
+impl TimePattern {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "12h" => Some(TimePattern::HmsColon12),
+            "24h" => Some(TimePattern::HmsColon24),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberPattern {
+    CommaPoint, // 1,234.5
+    PointComma, // 1.234,5
+}
+
+impl NumberPattern {
+    pub const fn decimal_separator(&self) -> char {
+        match self {
+            NumberPattern::CommaPoint => '.',
+            NumberPattern::PointComma => ',',
+        }
+    }
+
+    pub const fn group_separator(&self) -> char {
+        match self {
+            NumberPattern::CommaPoint => ',',
+            NumberPattern::PointComma => '.',
+        }
+    }
+}
+
 pub fn fmt_short(date: &Date, locale: &dyn DateLocale) -> String {
     let (year, month, day) = date.to_ymd();
 
@@ -73,6 +106,186 @@ impl TimeFormat for Time {
     }
 }
 
+// Example: "9:41 PM" (US), "21:41" (German), with `seconds` "21:41:07"
+pub fn fmt_time(time: &Time, seconds: bool, locale: &dyn DateLocale) -> String {
+    let (hour, minute, second) = time.to_hms();
+    let (hour, suffix) = match locale.time_format() {
+        TimePattern::HmsColon12 => {
+            let suffix = if hour < 12 { " AM" } else { " PM" };
+            (format!("{}", (hour + 11) % 12 + 1), suffix)
+        }
+        TimePattern::HmsColon24 => (format!("{hour:02}"), ""),
+    };
+    match seconds {
+        true => format!("{hour}:{minute:02}:{second:02}{suffix}"),
+        false => format!("{hour}:{minute:02}{suffix}"),
+    }
+}
+
+// Example: "1,234,567" (US), "1.234.567" (German)
+pub fn fmt_integer(value: i64, locale: &dyn DateLocale) -> String {
+    let digits = group_digits(&value.unsigned_abs().to_string(), locale.number_format());
+    if value < 0 {
+        format!("-{digits}")
+    } else {
+        digits
+    }
+}
+
+// Example: "1,234.57" (US), "1.234,57" (German) for 1234.567 with 2 decimals
+pub fn fmt_decimal(value: f64, decimals: usize, locale: &dyn DateLocale) -> String {
+    let pattern = locale.number_format();
+    let abs = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = abs.split_once('.').unwrap_or((&abs, ""));
+
+    let mut s = String::new();
+    // avoid "-0.0" when the value rounds to zero
+    if value < 0.0 && abs.bytes().any(|b| (b'1'..=b'9').contains(&b)) {
+        s.push('-');
+    }
+    s.push_str(&group_digits(int_part, pattern));
+    if !frac_part.is_empty() {
+        s.push(pattern.decimal_separator());
+        s.push_str(frac_part);
+    }
+    s
+}
+
+fn group_digits(digits: &str, pattern: NumberPattern) -> String {
+    let mut s = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            s.push(pattern.group_separator());
+        }
+        s.push(ch);
+    }
+    s
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindUnit {
+    KilometersPerHour,
+    MilesPerHour,
+    MetersPerSecond,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrecipitationUnit {
+    Millimeters,
+    Inches,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub wind: WindUnit,
+    pub precipitation: PrecipitationUnit,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self::metric()
+    }
+}
+
+impl Units {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "metric" => Some(Self::metric()),
+            "imperial" => Some(Self::imperial()),
+            _ => None,
+        }
+    }
+
+    pub const fn metric() -> Self {
+        Self {
+            temperature: TemperatureUnit::Celsius,
+            wind: WindUnit::KilometersPerHour,
+            precipitation: PrecipitationUnit::Millimeters,
+        }
+    }
+
+    pub const fn imperial() -> Self {
+        Self {
+            temperature: TemperatureUnit::Fahrenheit,
+            wind: WindUnit::MilesPerHour,
+            precipitation: PrecipitationUnit::Inches,
+        }
+    }
+}
+
+// Weather values are stored in SI-ish units (°C, m/s, mm) and converted on output.
+// Example: "21°C", "70°F"
+pub fn fmt_temperature(celsius: f32, unit: TemperatureUnit, locale: &dyn DateLocale) -> String {
+    match unit {
+        TemperatureUnit::Celsius => {
+            format!("{}°C", fmt_decimal(celsius as f64, 0, locale))
+        }
+        TemperatureUnit::Fahrenheit => {
+            let fahrenheit = celsius as f64 * 9.0 / 5.0 + 32.0;
+            format!("{}°F", fmt_decimal(fahrenheit, 0, locale))
+        }
+    }
+}
+
+// Example: "18 km/h", "11 mph", "5 m/s"
+pub fn fmt_wind(meters_per_second: f32, unit: WindUnit, locale: &dyn DateLocale) -> String {
+    let mps = meters_per_second as f64;
+    match unit {
+        WindUnit::KilometersPerHour => format!("{} km/h", fmt_decimal(mps * 3.6, 0, locale)),
+        WindUnit::MilesPerHour => format!("{} mph", fmt_decimal(mps * 2.236_936, 0, locale)),
+        WindUnit::MetersPerSecond => format!("{} m/s", fmt_decimal(mps, 0, locale)),
+    }
+}
+
+// Example: "2.5 mm", "0.10 in" (US), "2,5 mm" (German)
+pub fn fmt_precipitation(
+    millimeters: f32,
+    unit: PrecipitationUnit,
+    locale: &dyn DateLocale,
+) -> String {
+    let mm = millimeters as f64;
+    match unit {
+        PrecipitationUnit::Millimeters => format!("{} mm", fmt_decimal(mm, 1, locale)),
+        PrecipitationUnit::Inches => format!("{} in", fmt_decimal(mm / 25.4, 2, locale)),
+    }
+}
+
+// Wraps a locale and optionally replaces its time pattern, e.g. to show a
+// 24-hour clock with US date formatting.
+pub struct LocaleOverride {
+    pub base: Box<dyn DateLocale>,
+    pub time_format: Option<TimePattern>,
+}
+
+impl DateLocale for LocaleOverride {
+    fn date_format(&self) -> DatePattern {
+        self.base.date_format()
+    }
+
+    fn time_format(&self) -> TimePattern {
+        self.time_format.unwrap_or_else(|| self.base.time_format())
+    }
+
+    fn weekday_name(&self, weekday: &Weekday) -> (&'static str, &'static str) {
+        self.base.weekday_name(weekday)
+    }
+
+    fn month_name(&self, month: &Month) -> (&'static str, &'static str) {
+        self.base.month_name(month)
+    }
+
+    fn number_format(&self) -> NumberPattern {
+        self.base.number_format()
+    }
+}
+
 pub struct LocaleUs;
 
 impl DateLocale for LocaleUs {
@@ -112,6 +325,10 @@ impl DateLocale for LocaleUs {
             Month::Dec => ("Dec", "December"),
         }
     }
+
+    fn number_format(&self) -> NumberPattern {
+        NumberPattern::CommaPoint
+    }
 }
 
 pub struct LocaleGerman;
@@ -153,4 +370,123 @@ impl DateLocale for LocaleGerman {
             Month::Dec => ("Dez", "Dezember"),
         }
     }
+
+    fn number_format(&self) -> NumberPattern {
+        NumberPattern::PointComma
+    }
+}
+
+// Built-in locales selectable by name, e.g. from the config file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LocaleId {
+    #[default]
+    Us,
+    German,
+}
+
+impl LocaleId {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "en-US" | "en" | "us" => Some(LocaleId::Us),
+            "de-DE" | "de" => Some(LocaleId::German),
+            _ => None,
+        }
+    }
+
+    // ISO 639-1 code, e.g. for names of places
+    pub fn language(self) -> &'static str {
+        match self {
+            LocaleId::Us => "en",
+            LocaleId::German => "de",
+        }
+    }
+
+    pub fn locale(self) -> Box<dyn DateLocale> {
+        match self {
+            LocaleId::Us => Box::new(LocaleUs),
+            LocaleId::German => Box::new(LocaleGerman),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_format_override() {
+        let locale = LocaleOverride {
+            base: Box::new(LocaleUs),
+            time_format: Some(TimePattern::HmsColon24),
+        };
+        assert_eq!(locale.time_format(), TimePattern::HmsColon24);
+
+        let locale = LocaleOverride {
+            base: Box::new(LocaleGerman),
+            time_format: None,
+        };
+        assert_eq!(locale.time_format(), TimePattern::HmsColon24);
+
+        let locale = LocaleOverride {
+            base: Box::new(LocaleGerman),
+            time_format: Some(TimePattern::HmsColon12),
+        };
+        assert_eq!(locale.time_format(), TimePattern::HmsColon12);
+    }
+
+    #[test]
+    fn test_fmt_time() {
+        let time = |h, m, s| Time::from_hms(h, m, s).unwrap();
+        assert_eq!(fmt_time(&time(21, 41, 7), false, &LocaleUs), "9:41 PM");
+        assert_eq!(fmt_time(&time(0, 5, 0), false, &LocaleUs), "12:05 AM");
+        assert_eq!(fmt_time(&time(12, 0, 0), true, &LocaleUs), "12:00:00 PM");
+        assert_eq!(fmt_time(&time(9, 41, 7), false, &LocaleGerman), "09:41");
+        assert_eq!(fmt_time(&time(9, 41, 7), true, &LocaleGerman), "09:41:07");
+    }
+
+    #[test]
+    fn test_weather_units() {
+        let us = LocaleUs;
+        let de = LocaleGerman;
+        assert_eq!(fmt_temperature(21.0, TemperatureUnit::Celsius, &us), "21°C");
+        assert_eq!(
+            fmt_temperature(21.0, TemperatureUnit::Fahrenheit, &us),
+            "70°F"
+        );
+        assert_eq!(
+            fmt_temperature(-40.0, TemperatureUnit::Fahrenheit, &us),
+            "-40°F"
+        );
+        assert_eq!(fmt_wind(5.0, WindUnit::KilometersPerHour, &us), "18 km/h");
+        assert_eq!(fmt_wind(5.0, WindUnit::MilesPerHour, &us), "11 mph");
+        assert_eq!(fmt_wind(5.0, WindUnit::MetersPerSecond, &us), "5 m/s");
+        assert_eq!(
+            fmt_precipitation(2.54, PrecipitationUnit::Millimeters, &us),
+            "2.5 mm"
+        );
+        assert_eq!(
+            fmt_precipitation(2.54, PrecipitationUnit::Millimeters, &de),
+            "2,5 mm"
+        );
+        assert_eq!(
+            fmt_precipitation(2.54, PrecipitationUnit::Inches, &us),
+            "0.10 in"
+        );
+    }
+
+    #[test]
+    fn test_number_format() {
+        let us = LocaleUs;
+        let de = LocaleGerman;
+        assert_eq!(fmt_integer(0, &us), "0");
+        assert_eq!(fmt_integer(999, &us), "999");
+        assert_eq!(fmt_integer(1000, &us), "1,000");
+        assert_eq!(fmt_integer(-1234567, &us), "-1,234,567");
+        assert_eq!(fmt_integer(1234567, &de), "1.234.567");
+        assert_eq!(fmt_decimal(1234.5, 1, &us), "1,234.5");
+        assert_eq!(fmt_decimal(1234.5, 1, &de), "1.234,5");
+        assert_eq!(fmt_decimal(-0.04, 1, &us), "0.0");
+        assert_eq!(fmt_decimal(-12.345, 2, &de), "-12,35");
+        assert_eq!(fmt_decimal(42.0, 0, &us), "42");
+    }
 }
@@ -1,64 +1,436 @@
 use crate::error::{Error, Result};
-use crate::util::datetime::DateTime;
+use crate::util::crash;
+use crate::util::datetime::{self, DateTime};
 use log::Log;
+use std::collections::VecDeque;
 use std::io::Write;
-use std::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // ----------------------------------------------------------------------------
-struct FileLogger {
-    file: RwLock<std::fs::File>,
+// Most recent log lines, kept in memory for the on-screen debug overlay
+const RECENT_LINES: usize = 32;
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+// Receives every logged error, see set_error_hook()
+type ErrorHook = Box<dyn Fn(&str) + Send + Sync>;
+static ERROR_HOOK: OnceLock<ErrorHook> = OnceLock::new();
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct LogConfig {
+    pub dir: PathBuf,
+    pub file_level: log::LevelFilter,
+    pub console_level: log::LevelFilter,
+    pub max_file_size: u64,
+    pub max_file_age: Duration,
+    pub max_files: usize,
+    pub filter: LogFilter,
+}
+
+// ----------------------------------------------------------------------------
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("log"),
+            file_level: log::LevelFilter::Info,
+            console_level: log::LevelFilter::Warn,
+            max_file_size: 4 * 1024 * 1024,
+            max_file_age: Duration::from_secs(24 * 60 * 60),
+            max_files: 10,
+            filter: LogFilter::default(),
+        }
+    }
 }
 
 // ----------------------------------------------------------------------------
-impl FileLogger {
-    fn init(path: &std::path::Path, level: log::LevelFilter) -> Result<()> {
+// Per-target level directives in RUST_LOG style, e.g. "warn,home_rs::scene=debug".
+// A bare level applies to all targets, the longest matching target prefix wins.
+#[derive(Clone, Debug, Default)]
+pub struct LogFilter {
+    directives: Vec<(String, log::LevelFilter)>,
+}
+
+// ----------------------------------------------------------------------------
+impl LogFilter {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || Error::InvalidArgument {
+            arg: spec.to_string(),
+        };
+
+        let mut directives = Vec::new();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let (target, level) = directive.split_once('=').unwrap_or(("", directive));
+            let level = level.trim().parse().map_err(|_| invalid())?;
+            directives.push((target.trim().to_string(), level));
+        }
+
+        // longest (most specific) prefix first
+        directives.sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        Ok(Self { directives })
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn level(&self, target: &str) -> Option<log::LevelFilter> {
+        self.directives
+            .iter()
+            .find(|(prefix, _)| {
+                prefix.is_empty()
+                    || target == prefix
+                    || target
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+    }
+
+    // ------------------------------------------------------------------------
+    fn max_level(&self) -> log::LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .max()
+            .unwrap_or(log::LevelFilter::Off)
+    }
+}
+
+// ----------------------------------------------------------------------------
+struct LogFile {
+    file: std::fs::File,
+    size: u64,
+    opened: Instant,
+}
+
+// ----------------------------------------------------------------------------
+impl LogFile {
+    fn create(dir: &Path) -> Result<Self> {
         let date_time = DateTime::now().as_timestamp();
-        let file_name = path.join(format!("{date_time}.log"));
-        let logger = FileLogger {
-            file: RwLock::new(
-                std::fs::OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(file_name)
-                    .map_err(|_| Error::Logging)?,
-            ),
+        let mut file_name = dir.join(format!("{date_time}.log"));
+        let mut seq = 1;
+        while file_name.exists() {
+            file_name = dir.join(format!("{date_time}_{seq}.log"));
+            seq += 1;
+        }
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_name)
+            .map_err(|_| Error::Logging)?;
+
+        Ok(Self {
+            file,
+            size: 0,
+            opened: Instant::now(),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Writes each record to stderr and/or the log file, depending on the level
+// configured for each sink. A matching filter directive replaces the level of
+// all enabled sinks. If the log file can't be created, console logging still
+// works.
+struct Logger {
+    config: LogConfig,
+    file: Option<RwLock<LogFile>>,
+}
+
+// ----------------------------------------------------------------------------
+impl Logger {
+    fn init(config: &LogConfig) -> Result<()> {
+        let file = if config.file_level > log::LevelFilter::Off {
+            let file = std::fs::create_dir_all(&config.dir)
+                .map_err(Error::from)
+                .and_then(|_| LogFile::create(&config.dir));
+            remove_old_logs(&config.dir, config.max_files);
+            file.ok().map(RwLock::new)
+        } else {
+            None
         };
-        log::set_max_level(level);
+
+        let logger = Logger {
+            config: config.clone(),
+            file,
+        };
+        let max_level = config.file_level.max(config.console_level);
+        log::set_max_level(max_level.max(config.filter.max_level()));
         log::set_boxed_logger(Box::new(logger)).map_err(|_| Error::Logging)?;
         Ok(())
     }
+
+    // ------------------------------------------------------------------------
+    fn needs_rotation(&self, file: &LogFile) -> bool {
+        file.size >= self.config.max_file_size || file.opened.elapsed() >= self.config.max_file_age
+    }
+
+    // ------------------------------------------------------------------------
+    fn rotate(&self, file: &mut LogFile) {
+        let _ = file.file.flush();
+        if let Ok(new_file) = LogFile::create(&self.config.dir) {
+            *file = new_file;
+            remove_old_logs(&self.config.dir, self.config.max_files);
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    fn sink_level(&self, sink_level: log::LevelFilter, target: &str) -> log::LevelFilter {
+        if sink_level == log::LevelFilter::Off {
+            return sink_level;
+        }
+        self.config.filter.level(target).unwrap_or(sink_level)
+    }
+
+    // ------------------------------------------------------------------------
+    fn console_level(&self, target: &str) -> log::LevelFilter {
+        self.sink_level(self.config.console_level, target)
+    }
+
+    // ------------------------------------------------------------------------
+    fn file_level(&self, target: &str) -> log::LevelFilter {
+        if self.file.is_none() {
+            return log::LevelFilter::Off;
+        }
+        self.sink_level(self.config.file_level, target)
+    }
+
+    // ------------------------------------------------------------------------
+    fn write_file(&self, line: &str) {
+        if let Some(file) = &self.file
+            && let Ok(mut file) = file.write()
+        {
+            if self.needs_rotation(&file) {
+                self.rotate(&mut file);
+            }
+
+            if file.file.write_all(line.as_bytes()).is_ok() {
+                file.size += line.len() as u64;
+            }
+        }
+    }
 }
 
 // ----------------------------------------------------------------------------
-impl Log for FileLogger {
+impl Log for Logger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        metadata.level() <= log::max_level()
+        let target = metadata.target();
+        metadata.level() <= self.console_level(target).max(self.file_level(target))
     }
 
     fn log(&self, record: &log::Record) {
         if self.enabled(record.metadata()) {
-            if let Ok(mut file) = self.file.write() {
-                let timestamp = DateTime::now();
-                let _ = writeln!(
-                    &mut file,
-                    "{timestamp} [{:5}] {}",
-                    record.level(),
-                    record.args()
-                );
+            let level = record.level();
+            let to_console = level <= self.console_level(record.target());
+            let to_file = level <= self.file_level(record.target());
+
+            let timestamp = local_timestamp();
+            let line = format!("{timestamp} [{level:5}] {}\n", record.args());
+
+            if to_console {
+                let _ = std::io::stderr().write_all(line.as_bytes());
+            }
+            if to_file {
+                self.write_file(&line);
+            }
+            push_recent(line.trim_end());
+            if level == log::Level::Error
+                && let Some(hook) = ERROR_HOOK.get()
+            {
+                hook(&record.args().to_string());
             }
         }
     }
 
     fn flush(&self) {
-        if let Ok(mut file) = self.file.write() {
-            let _ = file.flush();
+        let _ = std::io::stderr().flush();
+        if let Some(file) = &self.file
+            && let Ok(mut file) = file.write()
+        {
+            let _ = file.file.flush();
         }
     }
 }
 
 // ----------------------------------------------------------------------------
-pub fn init_logger(level: log::LevelFilter) -> Result<()> {
-    let log_dir = std::path::PathBuf::from("log");
-    std::fs::create_dir_all(&log_dir)?;
-    FileLogger::init(&log_dir, level)
+// Local time with milliseconds and UTC offset, e.g. "2025/03/10-14:03:22.123+01:00"
+fn local_timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO);
+
+    let seconds = now.as_secs() as i64;
+    let offset = datetime::utc_offset(seconds);
+    let local = DateTime::from_unix_seconds(seconds + offset as i64);
+    let millis = now.subsec_millis();
+    format!("{local}.{millis:03}{}", fmt_utc_offset(offset))
+}
+
+// ----------------------------------------------------------------------------
+fn fmt_utc_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.unsigned_abs() / 60;
+    format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+// ----------------------------------------------------------------------------
+// Log files are sorted by age, see `log_order`.
+fn remove_old_logs(dir: &Path, max_files: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut logs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+
+    if logs.len() <= max_files {
+        return;
+    }
+
+    logs.sort_by_cached_key(|path| log_order(path));
+    let excess = logs.len() - max_files.max(1);
+    for path in &logs[..excess] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Log file names are the timestamp they were started at, e.g.
+// 20250101_120000, and the number of files started in the same second
+// after the first, e.g. 20250101_120000_10
+fn log_order(path: &Path) -> (String, u32) {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    match stem.rsplit_once('_') {
+        Some((timestamp, seq)) if timestamp.contains('_') => {
+            (timestamp.to_string(), seq.parse().unwrap_or(0))
+        }
+        _ => (stem.to_string(), 0),
+    }
+}
+
+// ----------------------------------------------------------------------------
+fn push_recent(line: &str) {
+    if let Ok(mut recent) = RECENT.lock() {
+        if recent.len() >= RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line.to_string());
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Returns up to `count` of the most recently logged lines, oldest first.
+pub fn recent_lines(count: usize) -> Vec<String> {
+    match RECENT.lock() {
+        Ok(recent) => {
+            let skip = recent.len().saturating_sub(count);
+            recent.iter().skip(skip).cloned().collect()
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Called with the message of every logged error, e.g. to forward errors to
+// companion apps. Only the first hook set is used.
+pub fn set_error_hook(hook: impl Fn(&str) + Send + Sync + 'static) {
+    let _ = ERROR_HOOK.set(Box::new(hook));
+}
+
+// ----------------------------------------------------------------------------
+pub fn init_logger(config: &LogConfig) -> Result<()> {
+    Logger::init(config)
+}
+
+// ----------------------------------------------------------------------------
+// Panics are written to the log sinks together with a backtrace instead of
+// only stderr, which nobody sees on a kiosk device, and to a crash report.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let thread = std::thread::current();
+        let name = thread.name().unwrap_or("<unnamed>");
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let message = format!("Thread '{name}' panicked: {info}\n{backtrace}");
+        log::error!("{message}");
+        if let Some(path) = crash::write_report(&message) {
+            log::error!("Crash report written to {path:?}");
+        }
+        log::logger().flush();
+    }));
+}
+
+// ----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_old_logs() {
+        let dir = std::env::temp_dir().join(format!("home-rs-logs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let names = [
+            "20250101_000000.log",
+            "20250102_000000.log",
+            "20250103_000000.log",
+            "20250103_000000_2.log",
+            "20250103_000000_10.log",
+        ];
+        for name in names {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        remove_old_logs(&dir, 2);
+
+        // the 10th file of a second is newer than the 2nd
+        let kept: Vec<bool> = names.iter().map(|name| dir.join(name).exists()).collect();
+        assert_eq!(kept, [false, false, false, true, true]);
+        assert!(dir.join("notes.txt").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_utc_offset_format() {
+        assert_eq!(fmt_utc_offset(0), "+00:00");
+        assert_eq!(fmt_utc_offset(3600), "+01:00");
+        assert_eq!(fmt_utc_offset(-9 * 3600 - 30 * 60), "-09:30");
+        assert_eq!(fmt_utc_offset(5 * 3600 + 45 * 60), "+05:45");
+    }
+
+    #[test]
+    fn test_log_filter() {
+        use log::LevelFilter;
+
+        let filter = LogFilter::parse("warn, home_rs::scene=debug,home_rs::gl=error").unwrap();
+        assert_eq!(filter.level("home_rs::scene"), Some(LevelFilter::Debug));
+        assert_eq!(
+            filter.level("home_rs::scene::slideshow"),
+            Some(LevelFilter::Debug)
+        );
+        assert_eq!(filter.level("home_rs::scenery"), Some(LevelFilter::Warn));
+        assert_eq!(
+            filter.level("home_rs::gl::opengl"),
+            Some(LevelFilter::Error)
+        );
+        assert_eq!(filter.level("home_rs::core"), Some(LevelFilter::Warn));
+        assert_eq!(filter.max_level(), LevelFilter::Debug);
+
+        let filter = LogFilter::parse("home_rs::scene=trace").unwrap();
+        assert_eq!(filter.level("home_rs::core"), None);
+        assert_eq!(
+            filter.level("home_rs::scene::font"),
+            Some(LevelFilter::Trace)
+        );
+
+        assert!(LogFilter::parse("home_rs::scene=loud").is_err());
+        assert!(LogFilter::parse("").unwrap().level("home_rs").is_none());
+    }
 }
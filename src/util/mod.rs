@@ -1,4 +1,12 @@
+pub mod ambient_light;
+pub mod arabic;
+pub mod base64;
+pub mod bidi;
+pub mod crash;
 pub mod datetime;
 pub mod locale;
 pub mod logger;
+pub mod random;
+pub mod sha1;
+pub mod tasks;
 pub mod utf8;
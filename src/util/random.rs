@@ -0,0 +1,57 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// ----------------------------------------------------------------------------
+// Xorshift generator, good enough for playlists and slide timings
+#[derive(Clone, Debug)]
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        Self(seed | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut state = self.0;
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        self.0 = state;
+        state
+    }
+
+    // Uniform in 0..1
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // `value` randomly made up to `fraction` of it larger or smaller
+    pub fn jitter(&mut self, value: f32, fraction: f32) -> f32 {
+        value * (1.0 + fraction * (2.0 * self.next_f32() - 1.0))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Differs from run to run
+pub fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(1, |t| t.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jitter() {
+        let mut rng = XorShift::new(7);
+        let values: Vec<f32> = (0..1000).map(|_| rng.jitter(10.0, 0.2)).collect();
+        assert!(values.iter().all(|v| (8.0..=12.0).contains(v)));
+        assert!(values.iter().any(|&v| v < 9.0) && values.iter().any(|&v| v > 11.0));
+
+        // the same seed gives the same values
+        let mut again = XorShift::new(7);
+        assert_eq!(again.jitter(10.0, 0.2), values[0]);
+        assert_eq!(XorShift::new(7).jitter(10.0, 0.0), 10.0);
+    }
+}
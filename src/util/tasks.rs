@@ -0,0 +1,62 @@
+use crate::error::Result;
+use crate::scene::SystemEvent;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+// ----------------------------------------------------------------------------
+// Fetchers, watchers and decoders run on their own threads and hand their
+// results to the single-threaded GL world as `SystemEvent`s. The app drains
+// the receiver at the start of every update and passes the events on to the
+// scenes.
+pub fn channel() -> (TaskSender, Receiver<SystemEvent>) {
+    let (sender, receiver) = mpsc::channel();
+    (TaskSender { sender }, receiver)
+}
+
+// ----------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct TaskSender {
+    sender: Sender<SystemEvent>,
+}
+
+impl TaskSender {
+    // ------------------------------------------------------------------------
+    // False once the app is gone, workers may stop then
+    pub fn send(&self, event: SystemEvent) -> bool {
+        self.sender.send(event).is_ok()
+    }
+
+    // ------------------------------------------------------------------------
+    // Runs `task` on a thread of its own and sends its result, e.g. for a
+    // photo decoded in the background
+    pub fn spawn<F>(&self, name: &str, task: F) -> Result<()>
+    where
+        F: FnOnce() -> SystemEvent + Send + 'static,
+    {
+        let sender = self.clone();
+        std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                sender.send(task());
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_tasks() {
+        let (tasks, events) = channel();
+        assert!(tasks.send(SystemEvent::Offline));
+        tasks.spawn("test", || SystemEvent::Online).unwrap();
+        assert!(matches!(events.recv(), Ok(SystemEvent::Offline)));
+        let event = events.recv_timeout(Duration::from_secs(5));
+        assert!(matches!(event, Ok(SystemEvent::Online)));
+
+        drop(events);
+        assert!(!tasks.send(SystemEvent::Offline));
+    }
+}
@@ -1,22 +1,189 @@
+// Stands in for invalid or truncated sequences
+pub const REPLACEMENT: u32 = 0xfffd;
+
 // ----------------------------------------------------------------------------
-pub fn next_code_point<'a, I: Iterator<Item = &'a u8>>(bytes: &mut I) -> Option<u32> {
-    // Note: This function assumes valid UTF-8 input.
-    let x = *bytes.next()? as u32;
-    if x < 0x80 {
-        Some(x)
-    } else if x < 0xE0 {
-        let y = (*bytes.next()? & 0x3F) as u32;
-        Some((x & 0x1F) << 6 | y)
-    } else if x < 0xF0 {
-        let y = (*bytes.next()? & 0x3F) as u32;
-        let z = (*bytes.next()? & 0x3F) as u32;
-        Some((x & 0x0F) << 12 | y << 6 | z)
-    } else if x < 0xF8 {
-        let y = (*bytes.next()? & 0x3F) as u32;
-        let z = (*bytes.next()? & 0x3F) as u32;
-        let w = (*bytes.next()? & 0x3F) as u32;
-        Some((x & 0x07) << 18 | y << 12 | z << 6 | w)
-    } else {
-        None
+// Decodes the next code point. Invalid bytes become U+FFFD, one for every
+// maximal invalid subpart as `String::from_utf8_lossy` does, so the byte after
+// a truncated sequence is decoded on its own.
+pub fn next_code_point<'a, I>(bytes: &mut I) -> Option<u32>
+where
+    I: Iterator<Item = &'a u8> + Clone,
+{
+    let x = *bytes.next()?;
+    let (len, min, mut c) = match x {
+        0x00..=0x7f => return Some(u32::from(x)),
+        0xc2..=0xdf => (2, 0x80, u32::from(x & 0x1f)),
+        0xe0..=0xef => (3, 0x800, u32::from(x & 0x0f)),
+        0xf0..=0xf4 => (4, 0x10000, u32::from(x & 0x07)),
+        _ => return Some(REPLACEMENT),
+    };
+    for i in 1..len {
+        let mut ahead = bytes.clone();
+        let Some(&y) = ahead.next().filter(|&&y| y & 0xc0 == 0x80) else {
+            return Some(REPLACEMENT);
+        };
+        c = (c << 6) | u32::from(y & 0x3f);
+        // overlong forms, surrogates and beyond U+10FFFF fail at the second byte
+        if i == 1 {
+            let top = c << (6 * (len - 2));
+            if top < min || (0xd800..0xe000).contains(&top) || top > 0x10ffff {
+                return Some(REPLACEMENT);
+            }
+        }
+        *bytes = ahead;
+    }
+    Some(c)
+}
+
+// ----------------------------------------------------------------------------
+// Code points of possibly broken UTF-8, e.g. from a sidecar file
+pub fn code_points(bytes: &[u8]) -> impl Iterator<Item = u32> + '_ {
+    let mut iter = bytes.iter();
+    std::iter::from_fn(move || next_code_point(&mut iter))
+}
+
+// ----------------------------------------------------------------------------
+// User-perceived characters (extended grapheme clusters, UAX #29), e.g. a
+// letter with its accents, an emoji with skin tone or a flag. Hangul syllables
+// are split into their jamo.
+pub fn graphemes(text: &str) -> Graphemes<'_> {
+    Graphemes { text }
+}
+
+pub struct Graphemes<'a> {
+    text: &'a str,
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.text.char_indices();
+        let (_, first) = chars.next()?;
+        let mut previous = first;
+        let mut regional = u32::from(is_regional_indicator(first));
+        let mut end = self.text.len();
+        for (i, c) in chars {
+            let joins = match (previous, c) {
+                ('\r', '\n') => true,
+                ('\r' | '\n', _) | (_, '\r' | '\n') => false,
+                (_, c) if is_extend(c) => true,
+                ('\u{200d}', c) => is_pictographic(c),
+                (p, c) if is_regional_indicator(p) && is_regional_indicator(c) => regional % 2 == 1,
+                _ => false,
+            };
+            if !joins {
+                end = i;
+                break;
+            }
+            regional += u32::from(is_regional_indicator(c));
+            previous = c;
+        }
+        let (grapheme, rest) = self.text.split_at(end);
+        self.text = rest;
+        Some(grapheme)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Combining marks, variation selectors, skin tones and zero width joiners
+fn is_extend(c: char) -> bool {
+    matches!(c,
+        '\u{300}'..='\u{36f}'
+        | '\u{483}'..='\u{489}'
+        | '\u{591}'..='\u{5bd}'
+        | '\u{5bf}'
+        | '\u{5c1}'..='\u{5c2}'
+        | '\u{5c4}'..='\u{5c5}'
+        | '\u{5c7}'
+        | '\u{610}'..='\u{61a}'
+        | '\u{64b}'..='\u{65f}'
+        | '\u{670}'
+        | '\u{6d6}'..='\u{6dc}'
+        | '\u{6df}'..='\u{6e4}'
+        | '\u{6e7}'..='\u{6e8}'
+        | '\u{6ea}'..='\u{6ed}'
+        | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}'
+        | '\u{200c}'..='\u{200d}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe00}'..='\u{fe0f}'
+        | '\u{fe20}'..='\u{fe2f}'
+        | '\u{1f3fb}'..='\u{1f3ff}'
+        | '\u{e0020}'..='\u{e007f}'
+        | '\u{e0100}'..='\u{e01ef}')
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1f1e6}'..='\u{1f1ff}')
+}
+
+// Emoji and symbols that zero width joiners combine
+fn is_pictographic(c: char) -> bool {
+    matches!(c,
+        '\u{a9}'
+        | '\u{ae}'
+        | '\u{203c}'
+        | '\u{2049}'
+        | '\u{2122}'
+        | '\u{2139}'
+        | '\u{2194}'..='\u{21aa}'
+        | '\u{2300}'..='\u{23ff}'
+        | '\u{2600}'..='\u{27bf}'
+        | '\u{2b00}'..='\u{2bff}'
+        | '\u{3030}'
+        | '\u{303d}'
+        | '\u{3297}'
+        | '\u{3299}'
+        | '\u{1f000}'..='\u{1faff}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> Vec<u32> {
+        code_points(bytes).collect()
+    }
+
+    #[test]
+    fn test_code_points() {
+        assert_eq!(decode("aé€😀".as_bytes()), [0x61, 0xe9, 0x20ac, 0x1f600]);
+        // truncated sequences don't swallow the next character
+        assert_eq!(decode(b"\xe2\x82a"), [REPLACEMENT, 0x61]);
+        assert_eq!(decode(b"\xf0\x9f\x98"), [REPLACEMENT]);
+        // stray continuation bytes, overlong forms and surrogates
+        assert_eq!(decode(b"\x80b"), [REPLACEMENT, 0x62]);
+        assert_eq!(decode(b"\xc0\xaf"), [REPLACEMENT, REPLACEMENT]);
+        assert_eq!(
+            decode(b"\xe0\x80\xaf"),
+            [REPLACEMENT, REPLACEMENT, REPLACEMENT]
+        );
+        assert_eq!(
+            decode(b"\xed\xa0\x80"),
+            [REPLACEMENT, REPLACEMENT, REPLACEMENT]
+        );
+        assert_eq!(decode(b"\xf4\x90\x80\x80").len(), 4);
+
+        // same as the standard library
+        let broken = b"ok\xe2\x82 \xf0\x9f\x98\x80\xff\xc3";
+        let lossy: Vec<u32> = String::from_utf8_lossy(broken)
+            .chars()
+            .map(u32::from)
+            .collect();
+        assert_eq!(decode(broken), lossy);
+    }
+
+    #[test]
+    fn test_graphemes() {
+        let split = |text| graphemes(text).collect::<Vec<_>>();
+        assert_eq!(split("abc"), ["a", "b", "c"]);
+        assert_eq!(split("e\u{301}x"), ["e\u{301}", "x"]);
+        assert_eq!(split("a\r\nb"), ["a", "\r\n", "b"]);
+        // family, thumbs up with skin tone, two flags
+        assert_eq!(split("👨\u{200d}👩\u{200d}👧!").len(), 2);
+        assert_eq!(split("👍🏽👍"), ["👍🏽", "👍"]);
+        assert_eq!(split("🇩🇪🇫🇷🇮"), ["🇩🇪", "🇫🇷", "🇮"]);
+        assert_eq!(split(""), Vec::<&str>::new());
     }
 }
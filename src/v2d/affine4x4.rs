@@ -154,3 +154,79 @@ pub fn perspective(fov: f32, aspect: f32, zn: f32, zf: f32) -> M4x4 {
         .with((2, 3), -1.0)
         .with((3, 2), -2.0 * zn * zf * dz)
 }
+
+// ----------------------------------------------------------------------------
+// Standard perspective without far plane, depth range [-1, 1]
+pub fn perspective_infinite(fov: f32, aspect: f32, zn: f32) -> M4x4 {
+    let fov = fov.to_radians();
+    let y = 1.0 / (0.5 * fov).tan();
+
+    M4x4::zero()
+        .with((0, 0), y / aspect)
+        .with((1, 1), y)
+        .with((2, 2), -1.0)
+        .with((2, 3), -1.0)
+        .with((3, 2), -2.0 * zn)
+}
+
+// ----------------------------------------------------------------------------
+// Reverse-Z perspective: near plane maps to depth 1, far plane to 0. Needs a
+// [0, 1] clip depth range, see DepthMode::ReverseZ in the renderer.
+pub fn perspective_reverse_z(fov: f32, aspect: f32, zn: f32, zf: f32) -> M4x4 {
+    let fov = fov.to_radians();
+    let y = 1.0 / (0.5 * fov).tan();
+    let dz = 1.0 / (zf - zn);
+
+    M4x4::zero()
+        .with((0, 0), y / aspect)
+        .with((1, 1), y)
+        .with((2, 2), zn * dz)
+        .with((2, 3), -1.0)
+        .with((3, 2), zn * zf * dz)
+}
+
+// ----------------------------------------------------------------------------
+// Reverse-Z perspective with the far plane at infinity
+pub fn perspective_infinite_reverse_z(fov: f32, aspect: f32, zn: f32) -> M4x4 {
+    let fov = fov.to_radians();
+    let y = 1.0 / (0.5 * fov).tan();
+
+    M4x4::zero()
+        .with((0, 0), y / aspect)
+        .with((1, 1), y)
+        .with((2, 3), -1.0)
+        .with((3, 2), zn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_float_eq;
+    use crate::v2d::float_eq::float_eq_rel;
+
+    // depth after perspective division of a point at view space distance d
+    fn depth(m: &M4x4, d: f32) -> f32 {
+        let v = *m * V4::new([0.0, 0.0, -d, 1.0]);
+        v.x2() / v.x3()
+    }
+
+    #[test]
+    fn test_perspective_depth() {
+        let m = perspective(60.0, 1.5, 0.1, 100.0);
+        assert_float_eq!(depth(&m, 0.1), -1.0);
+        assert_float_eq!(depth(&m, 100.0), 1.0);
+
+        let m = perspective_infinite(60.0, 1.5, 0.1);
+        assert_float_eq!(depth(&m, 0.1), -1.0);
+        assert!(depth(&m, 1.0e6) < 1.0);
+
+        let m = perspective_reverse_z(60.0, 1.5, 0.1, 100.0);
+        assert_float_eq!(depth(&m, 0.1), 1.0);
+        assert_float_eq!(depth(&m, 100.0), 0.0);
+
+        let m = perspective_infinite_reverse_z(60.0, 1.5, 0.1);
+        assert_float_eq!(depth(&m, 0.1), 1.0);
+        assert!(depth(&m, 1.0e6) > 0.0);
+        assert!(depth(&m, 10.0) > depth(&m, 20.0));
+    }
+}
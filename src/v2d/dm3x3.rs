@@ -0,0 +1,213 @@
+// Double precision 3x3 matrix, same memory layout as M3x3
+use std::ops::{Add, Mul, Neg, Sub};
+
+use super::dv3::DV3;
+use super::float_eq::float_eq_rel_f64;
+use super::m3x3::M3x3;
+
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone)]
+pub struct DM3x3 {
+    m: [f64; 9],
+}
+
+// ----------------------------------------------------------------------------
+impl Default for DM3x3 {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl PartialEq for DM3x3 {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.m
+            .iter()
+            .zip(rhs.m.iter())
+            .all(|(a, b)| float_eq_rel_f64(*a, *b))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Add for DM3x3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        DM3x3::new(std::array::from_fn(|i| self.m[i] + rhs.m[i]))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Sub for DM3x3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        DM3x3::new(std::array::from_fn(|i| self.m[i] - rhs.m[i]))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DM3x3 * f64 -> DM3x3
+impl Mul<f64> for DM3x3 {
+    type Output = Self;
+
+    fn mul(self, s: f64) -> Self::Output {
+        DM3x3::new(self.m.map(|x| x * s))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DM3x3 * DV3 -> DV3
+impl Mul<DV3> for DM3x3 {
+    type Output = DV3;
+
+    fn mul(self, v: DV3) -> Self::Output {
+        DV3::new(std::array::from_fn(|row| {
+            (0..3).map(|col| self.get(row, col) * v[col]).sum()
+        }))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DM3x3 * DM3x3 -> DM3x3
+impl Mul<DM3x3> for DM3x3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = DM3x3::zero();
+        for row in 0..3 {
+            for col in 0..3 {
+                let x = (0..3).map(|k| self.get(row, k) * rhs.get(k, col)).sum();
+                m.set(row, col, x);
+            }
+        }
+        m
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Neg for DM3x3 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        DM3x3::new(self.m.map(|x| -x))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl From<M3x3> for DM3x3 {
+    fn from(m: M3x3) -> Self {
+        let m = m.as_slice();
+        DM3x3::new(std::array::from_fn(|i| m[i] as f64))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DM3x3 {
+    // ------------------------------------------------------------------------
+    pub const fn new(m: [f64; 9]) -> Self {
+        DM3x3 { m }
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn zero() -> Self {
+        DM3x3::new([0.0; 9])
+    }
+
+    // ------------------------------------------------------------------------
+    #[rustfmt::skip]
+    pub const fn identity() -> Self {
+        DM3x3::new([
+            1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 1.0
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    // Element in row `row` and column `col`, matches M3x3::x::<row, col>()
+    pub const fn get(&self, row: usize, col: usize) -> f64 {
+        self.m[row + col * 3]
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn set(&mut self, row: usize, col: usize, value: f64) {
+        self.m[row + col * 3] = value;
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn as_slice(&self) -> &[f64] {
+        &self.m
+    }
+
+    // ------------------------------------------------------------------------
+    // Lossy conversion to single precision, e.g. for rendering
+    pub fn as_m3x3(&self) -> M3x3 {
+        M3x3::new(self.m.map(|x| x as f32))
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn transpose(&self) -> Self {
+        DM3x3::new(std::array::from_fn(|i| self.get(i / 3, i % 3)))
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn det(&self) -> f64 {
+        let x = |row, col| self.get(row, col);
+        x(0, 0) * (x(1, 1) * x(2, 2) - x(1, 2) * x(2, 1))
+            - x(0, 1) * (x(1, 0) * x(2, 2) - x(1, 2) * x(2, 0))
+            + x(0, 2) * (x(1, 0) * x(2, 1) - x(1, 1) * x(2, 0))
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn inverse(&self) -> Self {
+        let d = self.det();
+        if d.abs() < f64::EPSILON {
+            return Self::zero();
+        }
+
+        // adjugate: transposed matrix of cofactors
+        let x = |row: usize, col: usize| self.get(row % 3, col % 3);
+        let mut inv = DM3x3::zero();
+        for row in 0..3 {
+            for col in 0..3 {
+                let c = x(col + 1, row + 1) * x(col + 2, row + 2)
+                    - x(col + 1, row + 2) * x(col + 2, row + 1);
+                inv.set(row, col, c / d);
+            }
+        }
+        inv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dm3x3_inverse() {
+        #[rustfmt::skip]
+        let m = DM3x3::new([
+            2.0, 0.0, 1.0,
+            1.0, 3.0, 0.0,
+            0.0, 1.0, 4.0
+        ]);
+        assert_eq!(m.det(), 25.0);
+        assert_eq!(m * m.inverse(), DM3x3::identity());
+        assert_eq!(m.inverse() * m, DM3x3::identity());
+        assert_eq!(m.transpose().transpose(), m);
+
+        let v = DV3::new([1.0, 2.0, 3.0]);
+        assert_eq!(m.inverse() * (m * v), v);
+        assert_eq!(DM3x3::zero().inverse(), DM3x3::zero());
+    }
+
+    #[test]
+    fn test_dm3x3_matches_m3x3() {
+        let m = M3x3::new([2.0, 0.0, 1.0, 1.0, 3.0, 0.0, 0.0, 1.0, 4.0]);
+        let dm = DM3x3::from(m);
+        assert_eq!(dm.get(0, 1), m.x01() as f64);
+        assert_eq!(dm.det(), m.det() as f64);
+        assert_eq!(dm.as_m3x3(), m);
+    }
+}
@@ -0,0 +1,225 @@
+// Double precision 4x4 matrix, same memory layout as M4x4
+use std::ops::{Add, Mul, Neg, Sub};
+
+use super::float_eq::float_eq_rel_f64;
+use super::m4x4::M4x4;
+
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone)]
+pub struct DM4x4 {
+    m: [f64; 16],
+}
+
+// ----------------------------------------------------------------------------
+impl Default for DM4x4 {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl PartialEq for DM4x4 {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.m
+            .iter()
+            .zip(rhs.m.iter())
+            .all(|(a, b)| float_eq_rel_f64(*a, *b))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Add for DM4x4 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        DM4x4::new(std::array::from_fn(|i| self.m[i] + rhs.m[i]))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Sub for DM4x4 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        DM4x4::new(std::array::from_fn(|i| self.m[i] - rhs.m[i]))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DM4x4 * f64 -> DM4x4
+impl Mul<f64> for DM4x4 {
+    type Output = Self;
+
+    fn mul(self, s: f64) -> Self::Output {
+        DM4x4::new(self.m.map(|x| x * s))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DM4x4 * [f64; 4] -> [f64; 4]
+impl Mul<[f64; 4]> for DM4x4 {
+    type Output = [f64; 4];
+
+    fn mul(self, v: [f64; 4]) -> Self::Output {
+        std::array::from_fn(|row| (0..4).map(|col| self.get(row, col) * v[col]).sum())
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DM4x4 * DM4x4 -> DM4x4
+impl Mul<DM4x4> for DM4x4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = DM4x4::zero();
+        for row in 0..4 {
+            for col in 0..4 {
+                let x = (0..4).map(|k| self.get(row, k) * rhs.get(k, col)).sum();
+                m.set(row, col, x);
+            }
+        }
+        m
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Neg for DM4x4 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        DM4x4::new(self.m.map(|x| -x))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl From<M4x4> for DM4x4 {
+    fn from(m: M4x4) -> Self {
+        DM4x4::new(m.as_array().map(|x| x as f64))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DM4x4 {
+    // ------------------------------------------------------------------------
+    pub const fn new(m: [f64; 16]) -> Self {
+        DM4x4 { m }
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn zero() -> Self {
+        DM4x4::new([0.0; 16])
+    }
+
+    // ------------------------------------------------------------------------
+    #[rustfmt::skip]
+    pub const fn identity() -> Self {
+        DM4x4::new([
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    // Element in row `row` and column `col`, matches M4x4::x::<row, col>()
+    pub const fn get(&self, row: usize, col: usize) -> f64 {
+        self.m[row + col * 4]
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn set(&mut self, row: usize, col: usize, value: f64) {
+        self.m[row + col * 4] = value;
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn as_slice(&self) -> &[f64] {
+        &self.m
+    }
+
+    // ------------------------------------------------------------------------
+    // Lossy conversion to single precision, e.g. for uploading to the GPU
+    pub fn as_m4x4(&self) -> M4x4 {
+        M4x4::new(self.m.map(|x| x as f32))
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn transpose(&self) -> Self {
+        DM4x4::new(std::array::from_fn(|i| self.get(i / 4, i % 4)))
+    }
+
+    // ------------------------------------------------------------------------
+    // Gauss-Jordan elimination with partial pivoting, zero if singular
+    pub fn inverse(&self) -> Self {
+        let mut a = *self;
+        let mut inv = DM4x4::identity();
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&r0, &r1| a.get(r0, col).abs().total_cmp(&a.get(r1, col).abs()))
+                .unwrap_or(col);
+            if a.get(pivot, col).abs() < f64::EPSILON {
+                return Self::zero();
+            }
+
+            a.swap_rows(col, pivot);
+            inv.swap_rows(col, pivot);
+
+            let p = 1.0 / a.get(col, col);
+            for k in 0..4 {
+                a.set(col, k, a.get(col, k) * p);
+                inv.set(col, k, inv.get(col, k) * p);
+            }
+
+            for row in (0..4).filter(|&row| row != col) {
+                let f = a.get(row, col);
+                for k in 0..4 {
+                    a.set(row, k, a.get(row, k) - f * a.get(col, k));
+                    inv.set(row, k, inv.get(row, k) - f * inv.get(col, k));
+                }
+            }
+        }
+
+        inv
+    }
+
+    // ------------------------------------------------------------------------
+    fn swap_rows(&mut self, r0: usize, r1: usize) {
+        for col in 0..4 {
+            self.m.swap(r0 + col * 4, r1 + col * 4);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dm4x4_inverse() {
+        #[rustfmt::skip]
+        let m = DM4x4::new([
+            0.0, 2.0, 0.0, 1.0,
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 3.0, 0.0,
+            5.0, 0.0, 1.0, 1.0
+        ]);
+        assert_eq!(m * m.inverse(), DM4x4::identity());
+        assert_eq!(m.inverse() * m, DM4x4::identity());
+        assert_eq!(m.transpose().transpose(), m);
+        assert_eq!(DM4x4::zero().inverse(), DM4x4::zero());
+
+        let v = [1.0, 2.0, 3.0, 1.0];
+        let r = m.inverse() * (m * v);
+        assert!(r.iter().zip(v).all(|(a, b)| float_eq_rel_f64(*a, b)));
+    }
+
+    #[test]
+    fn test_dm4x4_matches_m4x4() {
+        let m = M4x4::identity().with((3, 0), 2.0);
+        let dm = DM4x4::from(m);
+        assert_eq!(dm.get(0, 3), m.x03() as f64);
+        assert_eq!(dm * DM4x4::identity(), dm);
+        assert_eq!(dm.as_m4x4(), m);
+    }
+}
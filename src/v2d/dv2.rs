@@ -0,0 +1,216 @@
+// Double precision 2D vector for geodesic and astronomical calculations
+use super::float_eq::float_eq_rel_f64;
+use super::v2::V2;
+use std::ops::{Add, Div, Index, IndexMut, Mul, Neg, Sub};
+
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone)]
+pub struct DV2 {
+    m: [f64; 2],
+}
+
+// ----------------------------------------------------------------------------
+impl Default for DV2 {
+    fn default() -> Self {
+        DV2::zero()
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl PartialEq for DV2 {
+    #[rustfmt::skip]
+    fn eq(&self, rhs: &Self) -> bool {
+        float_eq_rel_f64(self.x0(), rhs.x0()) &&
+        float_eq_rel_f64(self.x1(), rhs.x1())
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Add for DV2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        DV2::new([self.x0() + rhs.x0(), self.x1() + rhs.x1()])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Sub for DV2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        DV2::new([self.x0() - rhs.x0(), self.x1() - rhs.x1()])
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DV2 * f64 -> DV2
+impl Mul<f64> for DV2 {
+    type Output = Self;
+
+    fn mul(self, s: f64) -> Self {
+        DV2::new([self.x0() * s, self.x1() * s])
+    }
+}
+
+// ----------------------------------------------------------------------------
+// f64 * DV2 -> DV2
+impl Mul<DV2> for f64 {
+    type Output = DV2;
+
+    fn mul(self, v: DV2) -> DV2 {
+        v * self
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DV2 * DV2 -> f64
+impl Mul for DV2 {
+    type Output = f64;
+
+    fn mul(self, rhs: Self) -> f64 {
+        DV2::dot(&self, &rhs)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DV2 / f64 -> DV2
+impl Div<f64> for DV2 {
+    type Output = Self;
+
+    fn div(self, s: f64) -> Self {
+        DV2::new([self.x0() / s, self.x1() / s])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Neg for DV2 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        DV2::new([-self.x0(), -self.x1()])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Index<usize> for DV2 {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IndexMut<usize> for DV2 {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl From<[f64; 2]> for DV2 {
+    fn from(m: [f64; 2]) -> Self {
+        DV2 { m }
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl From<V2> for DV2 {
+    fn from(v: V2) -> Self {
+        DV2::new([v.x0() as f64, v.x1() as f64])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DV2 {
+    pub const fn new(m: [f64; 2]) -> Self {
+        DV2 { m }
+    }
+
+    pub const fn zero() -> Self {
+        DV2::new([0.0, 0.0])
+    }
+
+    pub const fn x0(&self) -> f64 {
+        self.m[0]
+    }
+
+    pub const fn x1(&self) -> f64 {
+        self.m[1]
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        &self.m
+    }
+
+    // ------------------------------------------------------------------------
+    // Lossy conversion to single precision, e.g. for rendering
+    pub fn as_v2(&self) -> V2 {
+        V2::new([self.x0() as f32, self.x1() as f32])
+    }
+
+    pub const fn length2(&self) -> f64 {
+        self.x0() * self.x0() + self.x1() * self.x1()
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length2().sqrt()
+    }
+
+    pub fn norm(&self) -> Self {
+        let l2 = self.length2();
+        if l2 < f64::EPSILON {
+            DV2::default()
+        } else {
+            *self * (1.0 / l2.sqrt())
+        }
+    }
+
+    pub fn distance(x0: &Self, x1: &Self) -> f64 {
+        (*x1 - *x0).length()
+    }
+
+    pub const fn dot(v0: &Self, v1: &Self) -> f64 {
+        v0.x0() * v1.x0() + v0.x1() * v1.x1()
+    }
+
+    pub const fn cross(v0: &Self, v1: &Self) -> f64 {
+        v0.x0() * v1.x1() - v0.x1() * v1.x0()
+    }
+
+    pub fn lerp(v0: &Self, v1: &Self, t: f64) -> Self {
+        *v0 + (*v1 - *v0) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dv2() {
+        let v0 = DV2::new([1.0, 2.0]);
+        let v1 = DV2::new([4.0, 6.0]);
+
+        assert_eq!(v0 + v1, DV2::new([5.0, 8.0]));
+        assert_eq!(v1 - v0, DV2::new([3.0, 4.0]));
+        assert_eq!(2.0 * v0, DV2::new([2.0, 4.0]));
+        assert_eq!(v1 / 2.0, DV2::new([2.0, 3.0]));
+        assert_eq!(v0 * v1, 16.0);
+        assert_eq!(DV2::distance(&v0, &v1), 5.0);
+        assert_eq!(DV2::cross(&v0, &v1), -2.0);
+        assert_eq!((v1 - v0).norm(), DV2::new([0.6, 0.8]));
+        assert_eq!(DV2::lerp(&v0, &v1, 0.5), DV2::new([2.5, 4.0]));
+        assert_eq!(DV2::from(V2::new([1.0, 2.0])), v0);
+        assert_eq!(v0.as_v2(), V2::new([1.0, 2.0]));
+    }
+
+    #[test]
+    fn test_dv2_precision() {
+        // 1e-9 degrees is lost in f32 but must survive in f64
+        let v0 = DV2::new([48.137154, 11.576124]);
+        let v1 = v0 + DV2::new([1.0e-9, 0.0]);
+        assert!(v1.x0() > v0.x0());
+    }
+}
@@ -0,0 +1,230 @@
+// Double precision 3D vector for geodesic and astronomical calculations
+use super::float_eq::float_eq_rel_f64;
+use super::v3::V3;
+use std::ops::{Add, Div, Index, IndexMut, Mul, Neg, Sub};
+
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone)]
+pub struct DV3 {
+    m: [f64; 3],
+}
+
+// ----------------------------------------------------------------------------
+impl Default for DV3 {
+    fn default() -> Self {
+        DV3::zero()
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl PartialEq for DV3 {
+    #[rustfmt::skip]
+    fn eq(&self, rhs: &Self) -> bool {
+        float_eq_rel_f64(self.x0(), rhs.x0()) &&
+        float_eq_rel_f64(self.x1(), rhs.x1()) &&
+        float_eq_rel_f64(self.x2(), rhs.x2())
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Add for DV3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let x0 = self.x0() + rhs.x0();
+        let x1 = self.x1() + rhs.x1();
+        let x2 = self.x2() + rhs.x2();
+        DV3::new([x0, x1, x2])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Sub for DV3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        let x0 = self.x0() - rhs.x0();
+        let x1 = self.x1() - rhs.x1();
+        let x2 = self.x2() - rhs.x2();
+        DV3::new([x0, x1, x2])
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DV3 * f64 -> DV3
+impl Mul<f64> for DV3 {
+    type Output = Self;
+
+    fn mul(self, s: f64) -> Self {
+        DV3::new([self.x0() * s, self.x1() * s, self.x2() * s])
+    }
+}
+
+// ----------------------------------------------------------------------------
+// f64 * DV3 -> DV3
+impl Mul<DV3> for f64 {
+    type Output = DV3;
+
+    fn mul(self, v: DV3) -> DV3 {
+        v * self
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DV3 * DV3 -> f64
+impl Mul for DV3 {
+    type Output = f64;
+
+    fn mul(self, rhs: Self) -> f64 {
+        DV3::dot(&self, &rhs)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DV3 / f64 -> DV3
+impl Div<f64> for DV3 {
+    type Output = Self;
+
+    fn div(self, s: f64) -> Self {
+        DV3::new([self.x0() / s, self.x1() / s, self.x2() / s])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Neg for DV3 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        DV3::new([-self.x0(), -self.x1(), -self.x2()])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl Index<usize> for DV3 {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IndexMut<usize> for DV3 {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl From<[f64; 3]> for DV3 {
+    fn from(m: [f64; 3]) -> Self {
+        DV3 { m }
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl From<V3> for DV3 {
+    fn from(v: V3) -> Self {
+        DV3::new([v.x0() as f64, v.x1() as f64, v.x2() as f64])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DV3 {
+    // ------------------------------------------------------------------------
+    pub const fn new(m: [f64; 3]) -> Self {
+        DV3 { m }
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn zero() -> Self {
+        DV3::new([0.0, 0.0, 0.0])
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn x0(&self) -> f64 {
+        self.m[0]
+    }
+
+    pub const fn x1(&self) -> f64 {
+        self.m[1]
+    }
+
+    pub const fn x2(&self) -> f64 {
+        self.m[2]
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn as_slice(&self) -> &[f64] {
+        &self.m
+    }
+
+    // ------------------------------------------------------------------------
+    // Lossy conversion to single precision, e.g. for rendering
+    pub fn as_v3(&self) -> V3 {
+        V3::new([self.x0() as f32, self.x1() as f32, self.x2() as f32])
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn length2(&self) -> f64 {
+        Self::dot(self, self)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.length2().sqrt()
+    }
+
+    pub fn distance(x0: &Self, x1: &Self) -> f64 {
+        (*x1 - *x0).length()
+    }
+
+    pub fn norm(&self) -> Self {
+        let l2 = self.length2();
+        if l2 < f64::EPSILON {
+            DV3::default()
+        } else {
+            *self * (1.0 / l2.sqrt())
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn dot(v0: &Self, v1: &Self) -> f64 {
+        v0.x0() * v1.x0() + v0.x1() * v1.x1() + v0.x2() * v1.x2()
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn cross(v0: &Self, v1: &Self) -> Self {
+        let x0 = v0.x1() * v1.x2() - v0.x2() * v1.x1();
+        let x1 = v0.x2() * v1.x0() - v0.x0() * v1.x2();
+        let x2 = v0.x0() * v1.x1() - v0.x1() * v1.x0();
+        DV3::new([x0, x1, x2])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn lerp(v0: &Self, v1: &Self, t: f64) -> Self {
+        *v0 + (*v1 - *v0) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dv3() {
+        let v0 = DV3::new([3.0, 4.0, 0.0]);
+        let v1 = DV3::new([1.0, 2.0, 1.0]);
+
+        assert_eq!(v0 + v1, DV3::new([4.0, 6.0, 1.0]));
+        assert_eq!(v0 - v1, DV3::new([2.0, 2.0, -1.0]));
+        assert_eq!(v0 * 2.0, DV3::new([6.0, 8.0, 0.0]));
+        assert_eq!(v0 / 2.0, DV3::new([1.5, 2.0, 0.0]));
+        assert_eq!(v0 * v1, 11.0);
+        assert_eq!(v0.length(), 5.0);
+        assert_eq!(v0.norm(), DV3::new([0.6, 0.8, 0.0]));
+        assert_eq!(DV3::distance(&v0, &v1), 3.0);
+        assert_eq!(DV3::cross(&v0, &v1), DV3::new([4.0, -3.0, 2.0]));
+        assert_eq!(DV3::from(V3::new([1.0, 2.0, 1.0])), v1);
+        assert_eq!(v1.as_v3(), V3::new([1.0, 2.0, 1.0]));
+    }
+}
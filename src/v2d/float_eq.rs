@@ -22,3 +22,21 @@ macro_rules! assert_float_eq {
         assert!(float_eq_rel($lhs, $rhs), "{:?} != {:?}", $lhs, $rhs);
     };
 }
+
+#[inline]
+pub fn float_eq_ulps_f64(lhs: f64, rhs: f64) -> bool {
+    if lhs.is_sign_positive() != rhs.is_sign_positive() {
+        lhs == rhs // +0.0 == -0.0
+    } else {
+        let bits0 = lhs.to_bits();
+        let bits1 = rhs.to_bits();
+        let ulps = bits0.abs_diff(bits1);
+        ulps <= 4
+    }
+}
+
+#[inline]
+pub fn float_eq_rel_f64(lhs: f64, rhs: f64) -> bool {
+    let diff = (lhs - rhs).abs();
+    diff <= 1.0e-12 || float_eq_ulps_f64(lhs, rhs)
+}
@@ -1,4 +1,4 @@
-use std::ops::{Add, Mul, Neg, Sub};
+use std::ops::{Add, Div, DivAssign, Index, IndexMut, Mul, Neg, Sub};
 
 use super::float_eq::float_eq_rel;
 use super::v2::V2;
@@ -119,6 +119,23 @@ impl Mul<M2x2> for M2x2 {
     }
 }
 
+// ----------------------------------------------------------------------------
+// M2x2 / f32 -> M2x2
+impl Div<f32> for M2x2 {
+    type Output = Self;
+
+    fn div(self, s: f32) -> Self::Output {
+        M2x2::new(self.m.map(|x| x / s))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DivAssign<f32> for M2x2 {
+    fn div_assign(&mut self, s: f32) {
+        self.m.iter_mut().for_each(|x| *x /= s);
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl Neg for M2x2 {
     type Output = Self;
@@ -128,12 +145,43 @@ impl Neg for M2x2 {
     }
 }
 
+// ----------------------------------------------------------------------------
+impl Index<(usize, usize)> for M2x2 {
+    type Output = f32;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        &self.m[row * 2 + col]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IndexMut<(usize, usize)> for M2x2 {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        &mut self.m[row * 2 + col]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IntoIterator for M2x2 {
+    type Item = f32;
+    type IntoIter = std::array::IntoIter<f32, 4>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.m.into_iter()
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl M2x2 {
     pub const fn new(m: [f32; 4]) -> Self {
         M2x2 { m }
     }
 
+    // ------------------------------------------------------------------------
+    pub fn as_slice(&self) -> &[f32] {
+        &self.m
+    }
+
     // ------------------------------------------------------------------------
     pub const fn zero() -> Self {
         M2x2::new([0.0, 0.0, 0.0, 0.0])
@@ -297,6 +345,11 @@ mod tests {
 
         assert_eq!(m.row0(), V2::new([1.0, 2.0]));
         assert_eq!(m.row1(), V2::new([3.0, 4.0]));
+
+        assert_eq!(m[(0, 1)], 2.0);
+        assert_eq!(m[(1, 0)], 3.0);
+        assert_eq!(m.as_slice(), &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(m.into_iter().sum::<f32>(), 10.0);
     }
 
     #[test]
@@ -338,5 +391,10 @@ mod tests {
         assert_eq!(-m, M2x2::new([1.0, -3.0, -2.0, 5.0]));
         assert_eq!(m * 2.0, M2x2::new([-2.0, 6.0, 4.0, -10.0]));
         assert_eq!(2.0 * m, M2x2::new([-2.0, 6.0, 4.0, -10.0]));
+        assert_eq!(m / 2.0, M2x2::new([-0.5, 1.5, 1.0, -2.5]));
+
+        let mut m2 = m;
+        m2 /= -1.0;
+        assert_eq!(m2, -m);
     }
 }
@@ -0,0 +1,200 @@
+// 2D affine transform: 2x2 linear part plus translation
+//
+//   [ x00 x01 x02 ]   [ x ]
+//   [ x10 x11 x12 ] * [ y ]
+//                     [ 1 ]
+use std::ops::Mul;
+
+use super::float_eq::float_eq_rel;
+use super::m2x2::M2x2;
+use super::m4x4::M4x4;
+use super::rect::Rect;
+use super::v2::V2;
+
+// ----------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone)]
+pub struct M2x3 {
+    m: [f32; 6],
+}
+
+// ----------------------------------------------------------------------------
+impl Default for M2x3 {
+    fn default() -> Self {
+        M2x3::identity()
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl PartialEq for M2x3 {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.m
+            .iter()
+            .zip(rhs.m.iter())
+            .all(|(a, b)| float_eq_rel(*a, *b))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// M2x3 * M2x3 -> M2x3, applies rhs first
+impl Mul for M2x3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let linear = self.linear() * rhs.linear();
+        let t = self.transform_point(&rhs.translation());
+        M2x3::from_parts(&linear, &t)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// M2x3 * V2 -> V2 (point)
+impl Mul<V2> for M2x3 {
+    type Output = V2;
+
+    fn mul(self, v: V2) -> Self::Output {
+        self.transform_point(&v)
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl M2x3 {
+    // ------------------------------------------------------------------------
+    pub const fn new(m: [f32; 6]) -> Self {
+        M2x3 { m }
+    }
+
+    // ------------------------------------------------------------------------
+    #[rustfmt::skip]
+    pub const fn identity() -> Self {
+        M2x3::new([
+            1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    #[rustfmt::skip]
+    pub const fn from_parts(linear: &M2x2, t: &V2) -> Self {
+        M2x3::new([
+            linear.x00(), linear.x01(), t.x0(),
+            linear.x10(), linear.x11(), t.x1()
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn translate(t: &V2) -> Self {
+        M2x3::from_parts(&M2x2::identity(), t)
+    }
+
+    // ------------------------------------------------------------------------
+    // Counter clockwise rotation by `rad` around the origin
+    pub fn rotate(rad: f32) -> Self {
+        M2x3::from_parts(&M2x2::rotation(rad), &V2::zero())
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn scale(s: &V2) -> Self {
+        M2x3::from_parts(&M2x2::diag(s.x0(), s.x1()), &V2::zero())
+    }
+
+    // ------------------------------------------------------------------------
+    // Maps the unit square onto `dst`
+    pub const fn from_rect(dst: &Rect) -> Self {
+        M2x3::from_parts(&M2x2::diag(dst.size.x0(), dst.size.x1()), &dst.pos)
+    }
+
+    // ------------------------------------------------------------------------
+    // Composition helpers, each applies the new step after the existing ones
+    pub fn then_translate(self, t: &V2) -> Self {
+        M2x3::translate(t) * self
+    }
+
+    pub fn then_rotate(self, rad: f32) -> Self {
+        M2x3::rotate(rad) * self
+    }
+
+    pub fn then_scale(self, s: &V2) -> Self {
+        M2x3::scale(s) * self
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn x<const I0: usize, const I1: usize>(&self) -> f32 {
+        self.m[I0 * 3 + I1]
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn linear(&self) -> M2x2 {
+        M2x2::new([
+            self.x::<0, 0>(),
+            self.x::<0, 1>(),
+            self.x::<1, 0>(),
+            self.x::<1, 1>(),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn translation(&self) -> V2 {
+        V2::new([self.x::<0, 2>(), self.x::<1, 2>()])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn transform_point(&self, p: &V2) -> V2 {
+        self.transform_vector(p) + self.translation()
+    }
+
+    // ------------------------------------------------------------------------
+    // Ignores the translation
+    pub fn transform_vector(&self, v: &V2) -> V2 {
+        self.linear() * *v
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn inverse(&self) -> Self {
+        let inv = self.linear().inverse();
+        let t = -(inv * self.translation());
+        M2x3::from_parts(&inv, &t)
+    }
+
+    // ------------------------------------------------------------------------
+    // Embeds the transform into the x0/x1 plane of a 4x4 model matrix
+    #[rustfmt::skip]
+    pub fn as_m4x4(&self) -> M4x4 {
+        M4x4::new([
+            self.x::<0, 0>(), self.x::<1, 0>(), 0.0, 0.0,
+            self.x::<0, 1>(), self.x::<1, 1>(), 0.0, 0.0,
+            0.0,              0.0,              1.0, 0.0,
+            self.x::<0, 2>(), self.x::<1, 2>(), 0.0, 1.0,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2d::v4::V4;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_m2x3_composition() {
+        let p = V2::new([1.0, 0.0]);
+        let m = M2x3::scale(&V2::new([2.0, 3.0]))
+            .then_rotate(0.5 * PI)
+            .then_translate(&V2::new([1.0, 1.0]));
+
+        assert_eq!(m * p, V2::new([1.0, 3.0]));
+        assert_eq!(m.transform_vector(&p), V2::new([0.0, 2.0]));
+        assert_eq!(m.inverse() * (m * p), p);
+        assert_eq!(m * m.inverse(), M2x3::identity());
+    }
+
+    #[test]
+    fn test_m2x3_rect() {
+        let dst = Rect::new(V2::new([0.25, 0.5]), V2::new([0.5, 0.25]));
+        let m = M2x3::from_rect(&dst);
+        assert_eq!(m * V2::new([0.0, 0.0]), dst.min());
+        assert_eq!(m * V2::new([1.0, 1.0]), dst.max());
+
+        let v = m.as_m4x4() * V4::new([1.0, 1.0, 0.0, 1.0]);
+        assert_eq!(v, V4::new([0.75, 0.75, 0.0, 1.0]));
+    }
+}
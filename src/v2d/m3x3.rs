@@ -1,7 +1,8 @@
-use std::ops::{Add, Mul, Neg, Sub};
+use std::ops::{Add, Div, DivAssign, Index, IndexMut, Mul, Neg, Sub};
 
 use super::float_eq::float_eq_rel;
 use super::m2x2::M2x2;
+use super::q::Q;
 use super::v3::V3;
 
 // ----------------------------------------------------------------------------
@@ -139,6 +140,23 @@ impl Mul<M3x3> for M3x3 {
     }
 }
 
+// ----------------------------------------------------------------------------
+// M3x3 / f32 -> M3x3
+impl Div<f32> for M3x3 {
+    type Output = Self;
+
+    fn div(self, s: f32) -> Self::Output {
+        M3x3::new(self.m.map(|x| x / s))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DivAssign<f32> for M3x3 {
+    fn div_assign(&mut self, s: f32) {
+        self.m.iter_mut().for_each(|x| *x /= s);
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl Neg for M3x3 {
     type Output = Self;
@@ -153,6 +171,32 @@ impl Neg for M3x3 {
     }
 }
 
+// ----------------------------------------------------------------------------
+impl Index<(usize, usize)> for M3x3 {
+    type Output = f32;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        &self.m[row * 3 + col]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IndexMut<(usize, usize)> for M3x3 {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        &mut self.m[row * 3 + col]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IntoIterator for M3x3 {
+    type Item = f32;
+    type IntoIter = std::array::IntoIter<f32, 9>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.m.into_iter()
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl M3x3 {
     // ------------------------------------------------------------------------
@@ -160,6 +204,11 @@ impl M3x3 {
         M3x3 { m }
     }
 
+    // ------------------------------------------------------------------------
+    pub fn as_slice(&self) -> &[f32] {
+        &self.m
+    }
+
     // ------------------------------------------------------------------------
     #[rustfmt::skip]
     pub const fn from_cols(c0: V3, c1: V3, c2: V3) -> Self {
@@ -210,6 +259,18 @@ impl M3x3 {
         M3x3::diag(s, s, s)
     }
 
+    // ------------------------------------------------------------------------
+    // Rotation matrix of a unit quaternion, M * v == q.rotate(v)
+    #[rustfmt::skip]
+    pub fn from_quat(q: &Q) -> Self {
+        let [c0, c1, c2] = q.as_mat3();
+        M3x3::new([
+            c0[0], c0[1], c0[2],
+            c1[0], c1[1], c1[2],
+            c2[0], c2[1], c2[2]
+        ])
+    }
+
     // ------------------------------------------------------------------------
     pub const fn x<const I0: usize, const I1: usize>(&self) -> f32 {
         self.m[I0 + I1 * 3]
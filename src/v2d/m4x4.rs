@@ -1,11 +1,18 @@
-use std::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+use std::ops::{Add, Div, DivAssign, Index, IndexMut, Mul, Neg, Sub};
 
 use super::float_eq::float_eq_rel;
 use super::m3x3::M3x3;
+use super::q::Q;
+use super::v3::V3;
 use super::v4::V4;
 
 // ----------------------------------------------------------------------------
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "v2d-serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct M4x4 {
     m: [f32; 16],
 }
@@ -164,6 +171,23 @@ impl Mul<M4x4> for M4x4 {
     }
 }
 
+// ----------------------------------------------------------------------------
+// M4x4 / f32 -> M4x4
+impl Div<f32> for M4x4 {
+    type Output = Self;
+
+    fn div(self, s: f32) -> Self::Output {
+        M4x4::new(self.m.map(|x| x / s))
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DivAssign<f32> for M4x4 {
+    fn div_assign(&mut self, s: f32) {
+        self.m.iter_mut().for_each(|x| *x /= s);
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl Neg for M4x4 {
     type Output = Self;
@@ -195,6 +219,16 @@ impl IndexMut<(usize, usize)> for M4x4 {
     }
 }
 
+// ----------------------------------------------------------------------------
+impl IntoIterator for M4x4 {
+    type Item = f32;
+    type IntoIter = std::array::IntoIter<f32, 16>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.m.into_iter()
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl M4x4 {
     // ------------------------------------------------------------------------
@@ -245,6 +279,11 @@ impl M4x4 {
         self.m
     }
 
+    // ------------------------------------------------------------------------
+    pub fn as_slice(&self) -> &[f32] {
+        &self.m
+    }
+
     // ------------------------------------------------------------------------
     pub fn as_ptr(&self) -> *const f32 {
         self.m.as_ptr()
@@ -270,6 +309,19 @@ impl M4x4 {
             r3.x0(), r3.x1(), r3.x2(), r3.x3()])
     }
 
+    // ------------------------------------------------------------------------
+    // Rotation matrix of a unit quaternion without translation
+    #[rustfmt::skip]
+    pub fn from_quat(q: &Q) -> Self {
+        let [c0, c1, c2] = q.as_mat3();
+        M4x4::new([
+            c0[0], c0[1], c0[2], 0.0,
+            c1[0], c1[1], c1[2], 0.0,
+            c2[0], c2[1], c2[2], 0.0,
+              0.0,   0.0,   0.0, 1.0
+        ])
+    }
+
     // ------------------------------------------------------------------------
     pub const fn x<const I0: usize, const I1: usize>(&self) -> f32 {
         self.m[I0 + I1 * 4]
@@ -494,4 +546,143 @@ impl M4x4 {
                 ])
         }
     }
+
+    // ------------------------------------------------------------------------
+    // Composes translation * rotation * scale
+    #[rustfmt::skip]
+    pub fn from_trs(t: &V3, r: &Q, s: &V3) -> Self {
+        let [c0, c1, c2] = r.as_mat3();
+        M4x4::new([
+            c0[0] * s.x0(), c0[1] * s.x0(), c0[2] * s.x0(), 0.0,
+            c1[0] * s.x1(), c1[1] * s.x1(), c1[2] * s.x1(), 0.0,
+            c2[0] * s.x2(), c2[1] * s.x2(), c2[2] * s.x2(), 0.0,
+            t.x0(),         t.x1(),         t.x2(),         1.0,
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    // Basis vector I of the upper 3x3 part, i.e. the image of axis I
+    const fn axis<const I: usize>(&self) -> V3 {
+        V3::new([self.m[I * 4], self.m[I * 4 + 1], self.m[I * 4 + 2]])
+    }
+
+    // ------------------------------------------------------------------------
+    // Splits an affine transform into translation, rotation and scale so that
+    // M4x4::from_trs(t, r, s) reproduces it. Shear is not represented, a
+    // mirroring transform is returned as a negative x0 scale.
+    pub fn decompose(&self) -> (V3, Q, V3) {
+        let t = V3::new([self.x03(), self.x13(), self.x23()]);
+
+        let a0 = self.axis::<0>();
+        let a1 = self.axis::<1>();
+        let a2 = self.axis::<2>();
+
+        let mut s = V3::new([a0.length(), a1.length(), a2.length()]);
+        if self.minor::<3, 3>().det() < 0.0 {
+            s = V3::new([-s.x0(), s.x1(), s.x2()]);
+        }
+
+        let safe_div = |v: V3, l: f32| {
+            if l.abs() < f32::EPSILON {
+                v
+            } else {
+                v * (1.0 / l)
+            }
+        };
+        let r0 = safe_div(a0, s.x0());
+        let r1 = safe_div(a1, s.x1());
+        let r2 = safe_div(a2, s.x2());
+
+        #[rustfmt::skip]
+        let rot = M3x3::new([
+            r0.x0(), r0.x1(), r0.x2(),
+            r1.x0(), r1.x1(), r1.x2(),
+            r2.x0(), r2.x1(), r2.x2(),
+        ]);
+
+        (t, Q::from_mat3(&rot), s)
+    }
+
+    // ------------------------------------------------------------------------
+    // Gram-Schmidt orthonormalization of the upper 3x3 part, removes scale
+    // and shear accumulated by repeated multiplication. Translation is kept.
+    #[rustfmt::skip]
+    pub fn orthonormalize(&self) -> Self {
+        let a0 = self.axis::<0>();
+        let a1 = self.axis::<1>();
+        let a2 = self.axis::<2>();
+
+        let b0 = a0.norm();
+        let b1 = (a1 - V3::dot(&a1, &b0) * b0).norm();
+        let b2 = (a2 - V3::dot(&a2, &b0) * b0 - V3::dot(&a2, &b1) * b1).norm();
+
+        M4x4::new([
+            b0.x0(),    b0.x1(),    b0.x2(),    self.m[3],
+            b1.x0(),    b1.x1(),    b1.x2(),    self.m[7],
+            b2.x0(),    b2.x1(),    b2.x2(),    self.m[11],
+            self.m[12], self.m[13], self.m[14], self.m[15],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompose() {
+        let t = V3::new([1.0, -2.0, 3.0]);
+        let r = Q::from_axis_angle(&V3::new([0.0, 0.6, 0.8]), 1.2);
+        let s = V3::new([2.0, 0.5, 1.5]);
+
+        let m = M4x4::from_trs(&t, &r, &s);
+        let (t1, r1, s1) = m.decompose();
+        assert_eq!(t1, t);
+        assert_eq!(s1, s);
+        assert_eq!(M4x4::from_trs(&t1, &r1, &s1), m);
+
+        let v = V4::new([1.0, 2.0, 3.0, 1.0]);
+        let rv = r.rotate(&V3::new([2.0, 1.0, 4.5]));
+        assert_eq!(m * v, V4::from_v3(&(rv + t), 1.0));
+    }
+
+    #[test]
+    fn test_decompose_mirror() {
+        let s = V3::new([-1.0, 1.0, 1.0]);
+        let m = M4x4::from_trs(&V3::zero(), &Q::identity(), &s);
+        let (_, r, s1) = m.decompose();
+        assert_eq!(s1, s);
+        assert_eq!(r, Q::identity());
+    }
+
+    #[cfg(feature = "v2d-serde")]
+    #[test]
+    fn test_m4x4_serde() {
+        let m = M4x4::identity().with((3, 0), 2.0);
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(
+            json,
+            "[1.0,0.0,0.0,0.0,0.0,1.0,0.0,0.0,0.0,0.0,1.0,0.0,2.0,0.0,0.0,1.0]"
+        );
+        assert_eq!(serde_json::from_str::<M4x4>(&json).unwrap(), m);
+    }
+
+    #[test]
+    fn test_orthonormalize() {
+        let r = Q::from_axis_angle(&V3::new([1.0, 0.0, 0.0]), 0.3);
+        let t = V3::new([4.0, 5.0, 6.0]);
+        let m = M4x4::from_trs(&t, &r, &V3::new([1.0, 1.0, 1.0]));
+
+        // drift: slight scale and shear on the basis
+        let mut drift = m;
+        drift[(0, 0)] *= 1.01;
+        drift[(1, 0)] += 0.02;
+        drift[(2, 1)] -= 0.01;
+
+        let n = drift.orthonormalize();
+        let (t1, _, s1) = n.decompose();
+        assert_eq!(t1, t);
+        assert_eq!(s1, V3::new([1.0, 1.0, 1.0]));
+        assert_eq!(m.orthonormalize(), m);
+    }
 }
@@ -1,11 +1,18 @@
 pub mod affine2x2;
 pub mod affine4x4;
+pub mod dm3x3;
+pub mod dm4x4;
+pub mod dv2;
+pub mod dv3;
 pub mod float_eq;
 pub mod m2x2;
+pub mod m2x3;
 pub mod m3x3;
 pub mod m4x4;
 pub mod q;
 pub mod r2;
+pub mod rect;
+pub mod scalar;
 pub mod v2;
 pub mod v3;
 pub mod v4;
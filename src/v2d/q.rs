@@ -1,5 +1,6 @@
 // Quaternion
 use super::float_eq::float_eq_rel;
+use super::m3x3::M3x3;
 use super::v3::V3;
 use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
@@ -275,6 +276,102 @@ impl Q {
         ]
     }
 
+    // ----------------------------------------------------------------------------
+    // Convert a pure rotation matrix to a unit quaternion. Branches on the largest
+    // diagonal term to stay numerically stable for angles near 180°.
+    pub fn from_mat3(m: &M3x3) -> Self {
+        let trace = m.x00() + m.x11() + m.x22();
+
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Q::new([
+                (m.x21() - m.x12()) / s,
+                (m.x02() - m.x20()) / s,
+                (m.x10() - m.x01()) / s,
+                0.25 * s,
+            ])
+        } else if m.x00() > m.x11() && m.x00() > m.x22() {
+            let s = 2.0 * (1.0 + m.x00() - m.x11() - m.x22()).sqrt();
+            Q::new([
+                0.25 * s,
+                (m.x01() + m.x10()) / s,
+                (m.x02() + m.x20()) / s,
+                (m.x21() - m.x12()) / s,
+            ])
+        } else if m.x11() > m.x22() {
+            let s = 2.0 * (1.0 + m.x11() - m.x00() - m.x22()).sqrt();
+            Q::new([
+                (m.x01() + m.x10()) / s,
+                0.25 * s,
+                (m.x12() + m.x21()) / s,
+                (m.x02() - m.x20()) / s,
+            ])
+        } else {
+            let s = 2.0 * (1.0 + m.x22() - m.x00() - m.x11()).sqrt();
+            Q::new([
+                (m.x02() + m.x20()) / s,
+                (m.x12() + m.x21()) / s,
+                0.25 * s,
+                (m.x10() - m.x01()) / s,
+            ])
+        };
+
+        q.norm()
+    }
+
+    // ----------------------------------------------------------------------------
+    // Euler angles in radians: yaw around x1 (up), pitch around x0, roll around x2.
+    // Rotations are applied roll first, then pitch, then yaw (R = Ryaw * Rpitch * Rroll).
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Self {
+        let qy = Q::from_axis_angle(&V3::new([0.0, 1.0, 0.0]), yaw);
+        let qp = Q::from_axis_angle(&V3::new([1.0, 0.0, 0.0]), pitch);
+        let qr = Q::from_axis_angle(&V3::new([0.0, 0.0, 1.0]), roll);
+        qy * qp * qr
+    }
+
+    // ----------------------------------------------------------------------------
+    // Inverse of from_euler, returns (yaw, pitch, roll) with pitch in [-π/2, π/2].
+    // At ±90° pitch yaw and roll are ambiguous; roll is reported as 0 then.
+    pub fn to_euler(self) -> (f32, f32, f32) {
+        let (x, y, z, w) = (self.x(), self.y(), self.z(), self.w());
+
+        let m12 = 2.0 * (y * z - w * x);
+        let pitch = (-m12).clamp(-1.0, 1.0).asin();
+
+        if m12.abs() > 0.9999 {
+            let m00 = 1.0 - 2.0 * (y * y + z * z);
+            let m20 = 2.0 * (x * z - w * y);
+            let yaw = (-m20).atan2(m00);
+            return (yaw, pitch, 0.0);
+        }
+
+        let m02 = 2.0 * (x * z + w * y);
+        let m22 = 1.0 - 2.0 * (x * x + y * y);
+        let m10 = 2.0 * (x * y + w * z);
+        let m11 = 1.0 - 2.0 * (x * x + z * z);
+        (m02.atan2(m22), pitch, m10.atan2(m11))
+    }
+
+    // ----------------------------------------------------------------------------
+    // Shortest arc rotation that turns the direction of `from` into that of `to`.
+    // Antiparallel vectors rotate by 180° around any axis perpendicular to `from`.
+    pub fn rotation_from_to(from: &V3, to: &V3) -> Self {
+        let a = from.norm();
+        let b = to.norm();
+        let d = V3::dot(&a, &b);
+
+        if d < -0.999_999 {
+            let mut axis = V3::cross(&V3::new([1.0, 0.0, 0.0]), &a);
+            if axis.length2() < 1e-6 {
+                axis = V3::cross(&V3::new([0.0, 1.0, 0.0]), &a);
+            }
+            return Q::from_axis_angle(&axis.norm(), std::f32::consts::PI);
+        }
+
+        let c = V3::cross(&a, &b);
+        Q::new([c.x0(), c.x1(), c.x2(), 1.0 + d]).norm()
+    }
+
     // ------------------------------------------------------------------------
     // Rotate a vector
     pub fn rotate(&self, v: &V3) -> V3 {
@@ -324,12 +421,68 @@ mod test {
         assert_eq!(r, v);
     }
 
-    // #[test]
-    // fn test_rotation_from_to() {
-    //     let a = V3::new([1.0, 1.0, 1.0]);
-    //     let b = V3::new([-1.0, -1.0, -1.0]);
-    //     let q = super::rotation_from_to(a, b);
-    //     let a_prime = q.rotate(a);
-    //     assert_eq!(a_prime, a);
-    // }
+    #[test]
+    fn test_mat3_rotates_like_quat() {
+        let axis = V3::new([1.0, 2.0, 3.0]).norm();
+        let q = Q::from_axis_angle(&axis, 0.7);
+        let v = V3::new([0.5, -1.0, 2.0]);
+        assert_eq!(M3x3::from_quat(&q) * v, q.rotate(&v));
+    }
+
+    #[test]
+    fn test_mat3_round_trip() {
+        let axes = [
+            V3::new([1.0, 0.0, 0.0]),
+            V3::new([0.0, 1.0, 0.0]),
+            V3::new([0.0, 0.0, 1.0]),
+            V3::new([1.0, -2.0, 0.5]).norm(),
+        ];
+        for axis in axes {
+            for angle in [0.0, 0.3, 1.5, 3.0, PI] {
+                let q = Q::from_axis_angle(&axis, angle);
+                let r = Q::from_mat3(&M3x3::from_quat(&q));
+                // q and -q describe the same rotation
+                let r = if Q::dot(&q, &r) < 0.0 { -r } else { r };
+                assert_eq!(r, q, "axis {axis:?}, angle {angle}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_euler_single_axis() {
+        let q = Q::from_euler(0.5, 0.0, 0.0);
+        assert_eq!(q, Q::from_axis_angle(&V3::new([0.0, 1.0, 0.0]), 0.5));
+
+        let q = Q::from_euler(0.0, 0.0, 0.5);
+        assert_eq!(q, Q::from_axis_angle(&V3::new([0.0, 0.0, 1.0]), 0.5));
+    }
+
+    #[test]
+    fn test_euler_round_trip() {
+        let angles = [(0.3, -0.2, 1.1), (-2.5, 1.2, -0.4), (3.0, 0.0, 0.0)];
+        for (yaw, pitch, roll) in angles {
+            let (y, p, r) = Q::from_euler(yaw, pitch, roll).to_euler();
+            assert_float_eq!(y, yaw);
+            assert_float_eq!(p, pitch);
+            assert_float_eq!(r, roll);
+        }
+    }
+
+    #[test]
+    fn test_rotation_from_to() {
+        let a = V3::new([1.0, 1.0, 1.0]);
+        let b = V3::new([-1.0, -1.0, -1.0]);
+        let q = Q::rotation_from_to(&a, &b);
+        let a_prime = q.rotate(&a);
+        assert_eq!(a_prime, b);
+    }
+
+    #[test]
+    fn test_rotation_from_to_axes() {
+        let x = V3::new([1.0, 0.0, 0.0]);
+        let y = V3::new([0.0, 1.0, 0.0]);
+        assert_eq!(Q::rotation_from_to(&x, &y).rotate(&x), y);
+        assert_eq!(Q::rotation_from_to(&x, &-x).rotate(&x), -x);
+        assert_eq!(Q::rotation_from_to(&x, &x), Q::identity());
+    }
 }
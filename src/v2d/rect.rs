@@ -0,0 +1,181 @@
+use super::v2::V2;
+
+// ----------------------------------------------------------------------------
+// Axis aligned rectangle given by its lower left corner and size.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "v2d-serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub pos: V2,
+    pub size: V2,
+}
+
+// ----------------------------------------------------------------------------
+impl Rect {
+    // ------------------------------------------------------------------------
+    pub const fn new(pos: V2, size: V2) -> Self {
+        Rect { pos, size }
+    }
+
+    // ------------------------------------------------------------------------
+    // (0, 0) - (1, 1), the full screen or texture in normalized coordinates
+    pub const fn unit() -> Self {
+        Rect::new(V2::new([0.0, 0.0]), V2::new([1.0, 1.0]))
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn from_min_max(min: V2, max: V2) -> Self {
+        Rect::new(min, max - min)
+    }
+
+    // ------------------------------------------------------------------------
+    pub const fn min(&self) -> V2 {
+        self.pos
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn max(&self) -> V2 {
+        self.pos + self.size
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn center(&self) -> V2 {
+        self.pos + self.size * 0.5
+    }
+
+    // ------------------------------------------------------------------------
+    // Width over height, 0 for a degenerate rect
+    pub fn aspect(&self) -> f32 {
+        if self.size.x1() == 0.0 {
+            0.0
+        } else {
+            self.size.x0() / self.size.x1()
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.size.x0() <= 0.0 || self.size.x1() <= 0.0
+    }
+
+    // ------------------------------------------------------------------------
+    // Lower and left edges are inclusive, upper and right edges exclusive
+    pub fn contains(&self, p: &V2) -> bool {
+        let max = self.max();
+        p.x0() >= self.pos.x0() && p.x1() >= self.pos.x1() && p.x0() < max.x0() && p.x1() < max.x1()
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let min = V2::max(&self.min(), &other.min());
+        let max = V2::min(&self.max(), &other.max());
+        let r = Rect::from_min_max(min, max);
+        if r.is_empty() { None } else { Some(r) }
+    }
+
+    // ------------------------------------------------------------------------
+    // Smallest rect enclosing both
+    pub fn union(&self, other: &Rect) -> Rect {
+        let min = V2::min(&self.min(), &other.min());
+        let max = V2::max(&self.max(), &other.max());
+        Rect::from_min_max(min, max)
+    }
+
+    // ------------------------------------------------------------------------
+    // Shrinks all edges by d, negative values grow the rect
+    pub fn inset(&self, d: f32) -> Rect {
+        let d = V2::new([d, d]);
+        let size = V2::max(&(self.size - 2.0 * d), &V2::zero());
+        Rect::new(self.pos + d, size)
+    }
+
+    // ------------------------------------------------------------------------
+    // Largest centered rect with the given aspect ratio inside this one
+    // (letterboxing)
+    pub fn fit(&self, aspect: f32) -> Rect {
+        if aspect > self.aspect() {
+            self.with_size(V2::new([self.size.x0(), self.size.x0() / aspect]))
+        } else {
+            self.with_size(V2::new([self.size.x1() * aspect, self.size.x1()]))
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Smallest centered rect with the given aspect ratio covering this one
+    // (cropping)
+    pub fn fill(&self, aspect: f32) -> Rect {
+        if aspect > self.aspect() {
+            self.with_size(V2::new([self.size.x1() * aspect, self.size.x1()]))
+        } else {
+            self.with_size(V2::new([self.size.x0(), self.size.x0() / aspect]))
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn lerp(r0: &Rect, r1: &Rect, t: f32) -> Rect {
+        Rect::new(
+            V2::lerp(&r0.pos, &r1.pos, t),
+            V2::lerp(&r0.size, &r1.size, t),
+        )
+    }
+
+    // ------------------------------------------------------------------------
+    fn with_size(&self, size: V2) -> Rect {
+        Rect::new(self.center() - size * 0.5, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect() {
+        let r0 = Rect::new(V2::new([0.0, 0.0]), V2::new([2.0, 1.0]));
+        let r1 = Rect::new(V2::new([1.0, 0.5]), V2::new([2.0, 2.0]));
+
+        assert_eq!(r0.aspect(), 2.0);
+        assert_eq!(r0.center(), V2::new([1.0, 0.5]));
+        assert!(r0.contains(&V2::new([0.0, 0.5])));
+        assert!(!r0.contains(&V2::new([2.0, 0.5])));
+
+        let i = Rect::new(V2::new([1.0, 0.5]), V2::new([1.0, 0.5]));
+        assert_eq!(r0.intersect(&r1), Some(i));
+        assert_eq!(r0.intersect(&r1.inset(-1.0).inset(1.0)), Some(i));
+        assert_eq!(r0.intersect(&Rect::new(V2::new([3.0, 0.0]), r0.size)), None);
+
+        let u = Rect::new(V2::new([0.0, 0.0]), V2::new([3.0, 2.5]));
+        assert_eq!(r0.union(&r1), u);
+        assert_eq!(
+            r1.inset(0.5),
+            Rect::new(V2::new([1.5, 1.0]), V2::new([1.0, 1.0]))
+        );
+        assert_eq!(r1.inset(2.0).size, V2::zero());
+
+        let m = Rect::new(V2::new([0.5, 0.25]), V2::new([2.0, 1.5]));
+        assert_eq!(Rect::lerp(&r0, &r1, 0.5), m);
+    }
+
+    #[test]
+    fn test_rect_fit_fill() {
+        let unit = Rect::unit();
+
+        let wide = unit.fit(2.0);
+        assert_eq!(wide, Rect::new(V2::new([0.0, 0.25]), V2::new([1.0, 0.5])));
+        let tall = unit.fit(0.5);
+        assert_eq!(tall, Rect::new(V2::new([0.25, 0.0]), V2::new([0.5, 1.0])));
+
+        let wide = unit.fill(2.0);
+        assert_eq!(wide, Rect::new(V2::new([-0.5, 0.0]), V2::new([2.0, 1.0])));
+        let tall = unit.fill(0.5);
+        assert_eq!(tall, Rect::new(V2::new([0.0, -0.5]), V2::new([1.0, 2.0])));
+    }
+
+    #[cfg(feature = "v2d-serde")]
+    #[test]
+    fn test_rect_serde() {
+        let json = r#"{"pos":[0.25,0.5],"size":[0.5,0.25]}"#;
+        let r: Rect = serde_json::from_str(json).unwrap();
+        assert_eq!(r, Rect::new(V2::new([0.25, 0.5]), V2::new([0.5, 0.25])));
+        assert_eq!(serde_json::to_string(&r).unwrap(), json);
+    }
+}
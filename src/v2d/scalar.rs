@@ -0,0 +1,44 @@
+// Scalar interpolation helpers shared by the vector types and animations
+
+// ----------------------------------------------------------------------------
+// Linear interpolation, t = 0 -> x0, t = 1 -> x1 (not clamped)
+pub fn lerp(x0: f32, x1: f32, t: f32) -> f32 {
+    x0 + (x1 - x0) * t
+}
+
+// ----------------------------------------------------------------------------
+// Inverse of lerp: position of x relative to x0 and x1 (not clamped)
+pub fn inverse_lerp(x0: f32, x1: f32, x: f32) -> f32 {
+    let d = x1 - x0;
+    if d.abs() < f32::EPSILON {
+        0.0
+    } else {
+        (x - x0) / d
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Hermite interpolation like GLSL: 0 below e0, 1 above e1, smooth in between
+pub fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
+    let t = inverse_lerp(e0, e1, x).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_float_eq;
+    use crate::v2d::float_eq::float_eq_rel;
+
+    #[test]
+    fn test_interpolation() {
+        assert_float_eq!(lerp(2.0, 4.0, 0.25), 2.5);
+        assert_float_eq!(lerp(2.0, 4.0, 1.5), 5.0);
+        assert_float_eq!(inverse_lerp(2.0, 4.0, 2.5), 0.25);
+        assert_float_eq!(inverse_lerp(2.0, 2.0, 3.0), 0.0);
+        assert_float_eq!(smoothstep(0.0, 1.0, -1.0), 0.0);
+        assert_float_eq!(smoothstep(0.0, 1.0, 0.5), 0.5);
+        assert_float_eq!(smoothstep(0.0, 1.0, 0.25), 0.15625);
+        assert_float_eq!(smoothstep(0.0, 1.0, 2.0), 1.0);
+    }
+}
@@ -1,9 +1,17 @@
-use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
 
 use super::float_eq::float_eq_rel;
+use super::scalar;
 
 // ----------------------------------------------------------------------------
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "v2d-serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct V2 {
     m: [f32; 2],
 }
@@ -125,6 +133,38 @@ impl MulAssign<f32> for V2 {
     }
 }
 
+// ----------------------------------------------------------------------------
+// V2 / f32 -> V2
+impl Div<f32> for V2 {
+    type Output = Self;
+
+    fn div(self, s: f32) -> Self {
+        let x0 = self.x0() / s;
+        let x1 = self.x1() / s;
+        V2::new([x0, x1])
+    }
+}
+
+// ----------------------------------------------------------------------------
+// V2 / V2 -> V2 (component-wise)
+impl Div for V2 {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let x0 = self.x0() / rhs.x0();
+        let x1 = self.x1() / rhs.x1();
+        V2::new([x0, x1])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DivAssign<f32> for V2 {
+    fn div_assign(&mut self, s: f32) {
+        self.m[0] /= s;
+        self.m[1] /= s;
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl From<[f32; 2]> for V2 {
     fn from(m: [f32; 2]) -> Self {
@@ -132,6 +172,32 @@ impl From<[f32; 2]> for V2 {
     }
 }
 
+// ----------------------------------------------------------------------------
+impl Index<usize> for V2 {
+    type Output = f32;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IndexMut<usize> for V2 {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IntoIterator for V2 {
+    type Item = f32;
+    type IntoIter = std::array::IntoIter<f32, 2>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.m.into_iter()
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl V2 {
     pub const fn new(m: [f32; 2]) -> Self {
@@ -142,6 +208,10 @@ impl V2 {
         V2::new([0.0, 0.0])
     }
 
+    pub fn as_slice(&self) -> &[f32] {
+        &self.m
+    }
+
     pub fn as_ptr(&self) -> *const f32 {
         self.m.as_ptr()
     }
@@ -182,6 +252,39 @@ impl V2 {
         V2::new([self.x0().abs(), self.x1().abs()])
     }
 
+    // ------------------------------------------------------------------------
+    pub fn min(v0: &Self, v1: &Self) -> Self {
+        V2::new([v0.x0().min(v1.x0()), v0.x1().min(v1.x1())])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn max(v0: &Self, v1: &Self) -> Self {
+        V2::new([v0.x0().max(v1.x0()), v0.x1().max(v1.x1())])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn clamp(&self, lo: &Self, hi: &Self) -> Self {
+        V2::new([
+            self.x0().clamp(lo.x0(), hi.x0()),
+            self.x1().clamp(lo.x1(), hi.x1()),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn floor(&self) -> Self {
+        V2::new([self.x0().floor(), self.x1().floor()])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn ceil(&self) -> Self {
+        V2::new([self.x0().ceil(), self.x1().ceil()])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn round(&self) -> Self {
+        V2::new([self.x0().round(), self.x1().round()])
+    }
+
     pub fn distance(x0: &Self, x1: &Self) -> f32 {
         let d = *x1 - *x0;
         d.length()
@@ -191,6 +294,29 @@ impl V2 {
         v0.x0() * v1.x0() + v0.x1() * v1.x1()
     }
 
+    // ------------------------------------------------------------------------
+    pub fn lerp(v0: &Self, v1: &Self, t: f32) -> Self {
+        *v0 + (*v1 - *v0) * t
+    }
+
+    // ------------------------------------------------------------------------
+    // Eases in and out between v0 and v1, t is clamped to [0, 1]
+    pub fn smoothstep(v0: &Self, v1: &Self, t: f32) -> Self {
+        Self::lerp(v0, v1, scalar::smoothstep(0.0, 1.0, t))
+    }
+
+    // ------------------------------------------------------------------------
+    // Parameter t of v projected onto the line through v0 and v1
+    pub fn inverse_lerp(v0: &Self, v1: &Self, v: &Self) -> f32 {
+        let d = *v1 - *v0;
+        let l2 = d.length2();
+        if l2 < f32::EPSILON {
+            0.0
+        } else {
+            Self::dot(&(*v - *v0), &d) / l2
+        }
+    }
+
     // ----------------------------------------------------------------------------
     // Two "crossed" vectors return a scalar, which is:
     // * area of the parallelogram of the 2 vectors
@@ -255,5 +381,24 @@ mod tests {
         assert_eq!(V2::winding(&v0, &v1, &v0), 0.0);
         assert_eq!(V2::winding(&v0, &v1, &v2), -2.0);
         assert_eq!(V2::winding(&v2, &v1, &v0), 2.0);
+        assert_eq!(V2::lerp(&v0, &v1, 0.5), V2::new([2.0, 3.0]));
+        assert_eq!(V2::smoothstep(&v0, &v1, 2.0), v1);
+        assert_eq!(V2::inverse_lerp(&v0, &v1, &V2::new([2.0, 3.0])), 0.5);
+        assert_eq!(V2::min(&v0, &v2), V2::new([-2.0, -2.0]));
+        assert_eq!(V2::max(&v0, &v2), v0);
+        assert_eq!(v1.clamp(&v2, &v0), V2::new([1.0, 2.0]));
+        let v3 = V2::new([1.5, -0.4]);
+        assert_eq!(v3.floor(), V2::new([1.0, -1.0]));
+        assert_eq!(v3.ceil(), V2::new([2.0, 0.0]));
+        assert_eq!(v3.round(), V2::new([2.0, 0.0]));
+        assert_eq!(v1 / 2.0, V2::new([1.5, 2.0]));
+        assert_eq!(v1 / v0, V2::new([3.0, 2.0]));
+        let mut v4 = v1;
+        v4 /= 4.0;
+        assert_eq!(v4, V2::new([0.75, 1.0]));
+        v4[1] = 5.0;
+        assert_eq!(v4[0], 0.75);
+        assert_eq!(v4.as_slice(), &[0.75, 5.0]);
+        assert_eq!(v1.into_iter().sum::<f32>(), 7.0);
     }
 }
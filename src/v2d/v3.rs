@@ -1,9 +1,15 @@
 use super::float_eq::float_eq_rel;
+use super::scalar;
 use super::v2::V2;
-use std::ops::{Add, Mul, Neg, Sub};
+use std::ops::{Add, Div, DivAssign, Index, IndexMut, Mul, Neg, Sub};
 
 // ----------------------------------------------------------------------------
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "v2d-serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct V3 {
     m: [f32; 3],
 }
@@ -94,6 +100,41 @@ impl Neg for V3 {
     }
 }
 
+// ----------------------------------------------------------------------------
+// V3 / f32 -> V3
+impl Div<f32> for V3 {
+    type Output = Self;
+
+    fn div(self, s: f32) -> Self {
+        let x0 = self.x0() / s;
+        let x1 = self.x1() / s;
+        let x2 = self.x2() / s;
+        V3::new([x0, x1, x2])
+    }
+}
+
+// ----------------------------------------------------------------------------
+// V3 / V3 -> V3 (component-wise)
+impl Div for V3 {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let x0 = self.x0() / rhs.x0();
+        let x1 = self.x1() / rhs.x1();
+        let x2 = self.x2() / rhs.x2();
+        V3::new([x0, x1, x2])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DivAssign<f32> for V3 {
+    fn div_assign(&mut self, s: f32) {
+        self.m[0] /= s;
+        self.m[1] /= s;
+        self.m[2] /= s;
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl From<[f32; 3]> for V3 {
     fn from(m: [f32; 3]) -> Self {
@@ -101,6 +142,32 @@ impl From<[f32; 3]> for V3 {
     }
 }
 
+// ----------------------------------------------------------------------------
+impl Index<usize> for V3 {
+    type Output = f32;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IndexMut<usize> for V3 {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IntoIterator for V3 {
+    type Item = f32;
+    type IntoIter = std::array::IntoIter<f32, 3>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.m.into_iter()
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl V3 {
     // ------------------------------------------------------------------------
@@ -123,6 +190,11 @@ impl V3 {
         V3 { m: *m }
     }
 
+    // ------------------------------------------------------------------------
+    pub fn as_slice(&self) -> &[f32] {
+        &self.m
+    }
+
     // ------------------------------------------------------------------------
     pub fn as_ptr(&self) -> *const f32 {
         self.m.as_ptr()
@@ -173,11 +245,76 @@ impl V3 {
         V3::new([self.x0().abs(), self.x1().abs(), self.x2().abs()])
     }
 
+    // ------------------------------------------------------------------------
+    pub fn min(v0: &Self, v1: &Self) -> Self {
+        V3::new([
+            v0.x0().min(v1.x0()),
+            v0.x1().min(v1.x1()),
+            v0.x2().min(v1.x2()),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn max(v0: &Self, v1: &Self) -> Self {
+        V3::new([
+            v0.x0().max(v1.x0()),
+            v0.x1().max(v1.x1()),
+            v0.x2().max(v1.x2()),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn clamp(&self, lo: &Self, hi: &Self) -> Self {
+        V3::new([
+            self.x0().clamp(lo.x0(), hi.x0()),
+            self.x1().clamp(lo.x1(), hi.x1()),
+            self.x2().clamp(lo.x2(), hi.x2()),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn floor(&self) -> Self {
+        V3::new([self.x0().floor(), self.x1().floor(), self.x2().floor()])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn ceil(&self) -> Self {
+        V3::new([self.x0().ceil(), self.x1().ceil(), self.x2().ceil()])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn round(&self) -> Self {
+        V3::new([self.x0().round(), self.x1().round(), self.x2().round()])
+    }
+
     // ------------------------------------------------------------------------
     pub const fn dot(v0: &Self, v1: &Self) -> f32 {
         v0.x0() * v1.x0() + v0.x1() * v1.x1() + v0.x2() * v1.x2()
     }
 
+    // ------------------------------------------------------------------------
+    pub fn lerp(v0: &Self, v1: &Self, t: f32) -> Self {
+        *v0 + (*v1 - *v0) * t
+    }
+
+    // ------------------------------------------------------------------------
+    // Eases in and out between v0 and v1, t is clamped to [0, 1]
+    pub fn smoothstep(v0: &Self, v1: &Self, t: f32) -> Self {
+        Self::lerp(v0, v1, scalar::smoothstep(0.0, 1.0, t))
+    }
+
+    // ------------------------------------------------------------------------
+    // Parameter t of v projected onto the line through v0 and v1
+    pub fn inverse_lerp(v0: &Self, v1: &Self, v: &Self) -> f32 {
+        let d = *v1 - *v0;
+        let l2 = d.length2();
+        if l2 < f32::EPSILON {
+            0.0
+        } else {
+            Self::dot(&(*v - *v0), &d) / l2
+        }
+    }
+
     // ------------------------------------------------------------------------
     pub const fn cross(v0: &Self, v1: &Self) -> Self {
         let x0 = v0.x1() * v1.x2() - v0.x2() * v1.x1();
@@ -212,5 +349,24 @@ mod tests {
         assert_eq!(V3::distance(&v0, &v1), 3.0);
         assert_eq!(V3::dot(&v0, &v1), 11.0);
         assert_eq!(V3::cross(&v0, &v1), V3::new([4.0, -3.0, 2.0]));
+        assert_eq!(V3::lerp(&v0, &v1, 0.5), V3::new([2.0, 3.0, 0.5]));
+        assert_eq!(V3::smoothstep(&v0, &v1, -1.0), v0);
+        assert_eq!(V3::inverse_lerp(&v0, &v1, &v1), 1.0);
+        assert_eq!(V3::min(&v0, &v1), V3::new([1.0, 2.0, 0.0]));
+        assert_eq!(V3::max(&v0, &v1), V3::new([3.0, 4.0, 1.0]));
+        assert_eq!((2.0 * v0).clamp(&v1, &(v0 + v1)), V3::new([4.0, 6.0, 1.0]));
+        let v2 = V3::new([1.5, -0.4, 2.2]);
+        assert_eq!(v2.floor(), V3::new([1.0, -1.0, 2.0]));
+        assert_eq!(v2.ceil(), V3::new([2.0, 0.0, 3.0]));
+        assert_eq!(v2.round(), V3::new([2.0, 0.0, 2.0]));
+        assert_eq!(v0 / 2.0, V3::new([1.5, 2.0, 0.0]));
+        assert_eq!(v0 / v1, V3::new([3.0, 2.0, 0.0]));
+        let mut v3 = v1;
+        v3 /= 2.0;
+        assert_eq!(v3, V3::new([0.5, 1.0, 0.5]));
+        v3[2] = 4.0;
+        assert_eq!(v3[1], 1.0);
+        assert_eq!(v3.as_slice(), &[0.5, 1.0, 4.0]);
+        assert_eq!(v0.into_iter().collect::<Vec<_>>(), vec![3.0, 4.0, 0.0]);
     }
 }
@@ -1,9 +1,17 @@
 use super::float_eq::float_eq_rel;
+use super::scalar;
 use super::v3::V3;
-use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
 
 // ----------------------------------------------------------------------------
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "v2d-serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct V4 {
     m: [f32; 4],
 }
@@ -129,6 +137,44 @@ impl MulAssign<f32> for V4 {
     }
 }
 
+// ----------------------------------------------------------------------------
+// V4 / f32 -> V4
+impl Div<f32> for V4 {
+    type Output = Self;
+
+    fn div(self, s: f32) -> Self {
+        let x0 = self.x0() / s;
+        let x1 = self.x1() / s;
+        let x2 = self.x2() / s;
+        let x3 = self.x3() / s;
+        V4::new([x0, x1, x2, x3])
+    }
+}
+
+// ----------------------------------------------------------------------------
+// V4 / V4 -> V4 (component-wise)
+impl Div for V4 {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let x0 = self.x0() / rhs.x0();
+        let x1 = self.x1() / rhs.x1();
+        let x2 = self.x2() / rhs.x2();
+        let x3 = self.x3() / rhs.x3();
+        V4::new([x0, x1, x2, x3])
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl DivAssign<f32> for V4 {
+    fn div_assign(&mut self, s: f32) {
+        self.m[0] /= s;
+        self.m[1] /= s;
+        self.m[2] /= s;
+        self.m[3] /= s;
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl From<[f32; 4]> for V4 {
     fn from(m: [f32; 4]) -> Self {
@@ -136,6 +182,32 @@ impl From<[f32; 4]> for V4 {
     }
 }
 
+// ----------------------------------------------------------------------------
+impl Index<usize> for V4 {
+    type Output = f32;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IndexMut<usize> for V4 {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.m[i]
+    }
+}
+
+// ----------------------------------------------------------------------------
+impl IntoIterator for V4 {
+    type Item = f32;
+    type IntoIter = std::array::IntoIter<f32, 4>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.m.into_iter()
+    }
+}
+
 // ----------------------------------------------------------------------------
 impl V4 {
     // ------------------------------------------------------------------------
@@ -207,6 +279,10 @@ impl V4 {
         self.m
     }
 
+    pub fn as_slice(&self) -> &[f32] {
+        &self.m
+    }
+
     pub fn as_ptr(&self) -> *const f32 {
         self.m.as_ptr()
     }
@@ -251,10 +327,93 @@ impl V4 {
         ])
     }
 
+    // ------------------------------------------------------------------------
+    pub fn min(v0: &Self, v1: &Self) -> Self {
+        V4::new([
+            v0.x0().min(v1.x0()),
+            v0.x1().min(v1.x1()),
+            v0.x2().min(v1.x2()),
+            v0.x3().min(v1.x3()),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn max(v0: &Self, v1: &Self) -> Self {
+        V4::new([
+            v0.x0().max(v1.x0()),
+            v0.x1().max(v1.x1()),
+            v0.x2().max(v1.x2()),
+            v0.x3().max(v1.x3()),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn clamp(&self, lo: &Self, hi: &Self) -> Self {
+        V4::new([
+            self.x0().clamp(lo.x0(), hi.x0()),
+            self.x1().clamp(lo.x1(), hi.x1()),
+            self.x2().clamp(lo.x2(), hi.x2()),
+            self.x3().clamp(lo.x3(), hi.x3()),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn floor(&self) -> Self {
+        V4::new([
+            self.x0().floor(),
+            self.x1().floor(),
+            self.x2().floor(),
+            self.x3().floor(),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn ceil(&self) -> Self {
+        V4::new([
+            self.x0().ceil(),
+            self.x1().ceil(),
+            self.x2().ceil(),
+            self.x3().ceil(),
+        ])
+    }
+
+    // ------------------------------------------------------------------------
+    pub fn round(&self) -> Self {
+        V4::new([
+            self.x0().round(),
+            self.x1().round(),
+            self.x2().round(),
+            self.x3().round(),
+        ])
+    }
+
     pub const fn dot(v0: &Self, v1: &Self) -> f32 {
         v0.x0() * v1.x0() + v0.x1() * v1.x1() + v0.x2() * v1.x2() + v0.x3() * v1.x3()
     }
 
+    // ------------------------------------------------------------------------
+    pub fn lerp(v0: &Self, v1: &Self, t: f32) -> Self {
+        *v0 + (*v1 - *v0) * t
+    }
+
+    // ------------------------------------------------------------------------
+    // Eases in and out between v0 and v1, t is clamped to [0, 1]
+    pub fn smoothstep(v0: &Self, v1: &Self, t: f32) -> Self {
+        Self::lerp(v0, v1, scalar::smoothstep(0.0, 1.0, t))
+    }
+
+    // ------------------------------------------------------------------------
+    // Parameter t of v projected onto the line through v0 and v1
+    pub fn inverse_lerp(v0: &Self, v1: &Self, v: &Self) -> f32 {
+        let d = *v1 - *v0;
+        let l2 = d.length2();
+        if l2 < f32::EPSILON {
+            0.0
+        } else {
+            Self::dot(&(*v - *v0), &d) / l2
+        }
+    }
+
     pub const fn cross(v0: &Self, v1: &Self) -> Self {
         V4::new([
             v0.x1() * v1.x2() - v0.x2() * v1.x1(),
@@ -283,6 +442,26 @@ mod tests {
         assert_eq!(v1.length2(), 16.0);
         assert_eq!(v1.length(), 4.0);
         assert_eq!(v1.norm(), V4::new([-0.5, 0.5, 0.5, -0.5]));
+        assert_eq!(V4::lerp(&v0, &v1, 0.5), V4::new([-1.5, 1.5, 3.5, 0.5]));
+        assert_eq!(V4::smoothstep(&v0, &v1, 0.5), V4::lerp(&v0, &v1, 0.5));
+        assert_eq!(V4::inverse_lerp(&v0, &v1, &v0), 0.0);
+        assert_eq!(V4::min(&v0, &v1), V4::new([-2.0, 1.0, 2.0, -2.0]));
+        assert_eq!(V4::max(&v0, &v1), V4::new([-1.0, 2.0, 5.0, 3.0]));
+        let (lo, hi) = (V4::min(&v0, &v1), V4::max(&v0, &v1));
+        assert_eq!(V4::zero().clamp(&lo, &hi), V4::new([-1.0, 1.0, 2.0, 0.0]));
+        let v2 = V4::new([1.5, -0.4, 2.2, -2.5]);
+        assert_eq!(v2.floor(), V4::new([1.0, -1.0, 2.0, -3.0]));
+        assert_eq!(v2.ceil(), V4::new([2.0, 0.0, 3.0, -2.0]));
+        assert_eq!(v2.round(), V4::new([2.0, 0.0, 2.0, -3.0]));
+        assert_eq!(v1 / 2.0, V4::new([-1.0, 1.0, 1.0, -1.0]));
+        assert_eq!(v0 / v1, V4::new([0.5, 0.5, 2.5, -1.5]));
+        let mut v3 = v1;
+        v3 /= -2.0;
+        assert_eq!(v3, V4::new([1.0, -1.0, -1.0, 1.0]));
+        v3[3] = 2.0;
+        assert_eq!(v3[0], 1.0);
+        assert_eq!(v3.as_slice(), &[1.0, -1.0, -1.0, 2.0]);
+        assert_eq!(v0.into_iter().fold(0.0, f32::max), 5.0);
         assert_eq!(v0.abs(), V4::new([1.0, 1.0, 5.0, 3.0]));
         assert_eq!(V4::distance(&v0, &v1), 6.0);
     }